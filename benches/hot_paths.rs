@@ -0,0 +1,166 @@
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use globset::GlobSet;
+
+use tfl::app::App;
+use tfl::config::Config;
+use tfl::fs::{ArchiveSource, FileTree};
+use tfl::preview::{archive, markdown, structured};
+use tfl::preview::text::SyntaxHighlighter;
+use tfl::theme::Theme;
+
+/// Files per synthetic directory; tree sizes are multiples of this
+const FILES_PER_DIR: usize = 100;
+
+/// Build `root/dir_NNNN/file_NNN.txt` with roughly `entries` entries in total.
+fn synthetic_tree(entries: usize) -> PathBuf {
+  let root = std::env::temp_dir().join(format!("tfl_bench_tree_{entries}"));
+  let dirs = entries / (FILES_PER_DIR + 1);
+  let marker = root.join(".complete");
+  if marker.exists() {
+    return root;
+  }
+  let _ = std::fs::remove_dir_all(&root);
+  for d in 0..dirs {
+    let dir = root.join(format!("dir_{d:04}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    for f in 0..FILES_PER_DIR {
+      std::fs::write(dir.join(format!("file_{f:03}.txt")), "").unwrap();
+    }
+  }
+  std::fs::write(&marker, "").unwrap();
+  root
+}
+
+fn expand_all(tree: &mut FileTree) {
+  let mut i = 0;
+  while i < tree.entries.len() {
+    if tree.entries[i].is_dir && !tree.entries[i].expanded {
+      tree.toggle_expand(i).unwrap();
+    }
+    i += 1;
+  }
+}
+
+fn bench_tree(c: &mut Criterion) {
+  let mut group = c.benchmark_group("tree");
+  group.sample_size(10);
+  for entries in [10_000, 100_000] {
+    let root = synthetic_tree(entries);
+    group.bench_with_input(BenchmarkId::new("load_expanded", entries), &root, |b, root| {
+      b.iter(|| {
+        let mut tree = FileTree::with_ignore_patterns(root.clone(), GlobSet::empty()).unwrap();
+        expand_all(&mut tree);
+        black_box(tree.entries.len())
+      });
+    });
+
+    let mut tree = FileTree::with_ignore_patterns(root.clone(), GlobSet::empty()).unwrap();
+    expand_all(&mut tree);
+    group.bench_function(BenchmarkId::new("reload_expanded", entries), |b| {
+      b.iter(|| {
+        tree.reload().unwrap();
+        black_box(tree.entries.len())
+      });
+    });
+  }
+  group.finish();
+}
+
+fn bench_visible_entries(c: &mut Criterion) {
+  let mut group = c.benchmark_group("visible_entries");
+  for entries in [10_000, 100_000] {
+    let root = synthetic_tree(entries);
+    let mut app = App::new_deferred_git(root, None, &Config::default(), None).unwrap();
+    expand_all(&mut app.tree);
+    for query in ["", "file_05", "no-match"] {
+      app.search.set(query);
+      let label = if query.is_empty() { "all" } else { query };
+      group.bench_function(BenchmarkId::new(label, entries), |b| {
+        b.iter(|| {
+          app.rebuild_visible_cache();
+          black_box(app.cached_visible.len())
+        });
+      });
+    }
+  }
+  group.finish();
+}
+
+fn bench_highlight(c: &mut Criterion) {
+  let highlighter = SyntaxHighlighter::new("base16-ocean.dark");
+  let theme = Theme::default();
+  let rust = include_str!("../src/preview/mod.rs");
+  let toml = include_str!("../Cargo.toml");
+  let readme = include_str!("../README.md");
+  let json = serde_json::to_string(&serde_json::json!({
+    "items": (0..500).map(|i| serde_json::json!({ "id": i, "name": format!("item {i}"), "tags": ["a", "b"] })).collect::<Vec<_>>(),
+  }))
+  .unwrap();
+
+  let mut group = c.benchmark_group("highlight");
+  group.bench_function("rust", |b| b.iter(|| black_box(highlighter.highlight(rust, "rs"))));
+  group.bench_function("toml", |b| b.iter(|| black_box(highlighter.highlight(toml, "toml"))));
+  group.bench_function("markdown_rendered", |b| {
+    b.iter(|| black_box(markdown::render_markdown(readme, &highlighter, &theme)))
+  });
+  group.bench_function("json_formatted", |b| {
+    b.iter(|| {
+      let structured::FormatResult::Formatted { content, extension } = structured::format_json(&json) else {
+        unreachable!("bench input is valid JSON");
+      };
+      black_box(highlighter.highlight(&content, &extension))
+    })
+  });
+  group.finish();
+}
+
+fn bench_archive(c: &mut Criterion) {
+  // The summary shows while the listing loads; the listing then reads every
+  // header (decompressing tarballs to the end), and browsing lists it all.
+  let dir = std::env::temp_dir().join("tfl_bench_archive");
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir).unwrap();
+  let files: Vec<PathBuf> = (0..200)
+    .map(|i| {
+      let path = dir.join(format!("member_{i:03}.txt"));
+      std::fs::write(&path, format!("member {i}\n").repeat(20)).unwrap();
+      path
+    })
+    .collect();
+  let theme = Theme::default();
+
+  let mut group = c.benchmark_group("archive");
+  for format in ["zip", "tar.gz"] {
+    let archive_path = dir.join(format!("bench.{format}"));
+    archive::compress_to_archive(&files, &archive_path, format).unwrap();
+    group.bench_function(BenchmarkId::new("preview", format), |b| {
+      b.iter(|| black_box(archive_preview(&archive_path, &theme)))
+    });
+    group.bench_function(BenchmarkId::new("listing", format), |b| {
+      b.iter(|| black_box(archive_listing(&archive_path, &theme)))
+    });
+    group.bench_function(BenchmarkId::new("browse", format), |b| {
+      b.iter(|| black_box(ArchiveSource::open(&archive_path).unwrap()))
+    });
+  }
+  group.finish();
+}
+
+fn archive_preview(path: &Path, theme: &Theme) -> usize {
+  let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+  let kind = archive::archive_type(path).unwrap_or("archive");
+  archive::render_archive_summary(kind, size, theme).len()
+}
+
+fn archive_listing(path: &Path, theme: &Theme) -> usize {
+  let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+  let kind = archive::archive_type(path).unwrap_or("archive");
+  let listing = archive::list_archive(path, archive::MAX_LISTED_ENTRIES).unwrap();
+  archive::render_archive_contents(kind, size, &listing, theme).len()
+}
+
+criterion_group!(benches, bench_tree, bench_visible_entries, bench_highlight, bench_archive);
+criterion_main!(benches);
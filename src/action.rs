@@ -1,15 +1,30 @@
+use crate::fs::ops::Collision;
+use crate::line_editor::LineEdit;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Action {
   Quit,
+  /// Esc in normal mode: quits unless `esc_quits` is off
+  Escape,
   MoveUp,
   MoveDown,
   MoveLeft,
   MoveRight,
+  /// Move by several entries at once; sent for accelerated held keys
+  MoveBy(i32),
+  /// Move a screenful through the visible (filtered) entries
+  PageDown,
+  PageUp,
   ToggleExpand,
   EnterDir,
   ScrollPreviewUp,
   ScrollPreviewDown,
   ToggleHidden,
+  /// Switch between directories first and interleaved sorting
+  ToggleDirsFirst,
+  ToggleSizeColumn,
+  /// Capture the mouse or leave it to the terminal for selecting text
+  ToggleMouse,
   ToggleFormatted,
   GoToTop,
   GoToBottom,
@@ -23,18 +38,36 @@ pub enum Action {
   OpenClaude,
   OpenClaudeAlt,
   OpenShell,
+  TmuxWindow,
+  TmuxPane,
+  ZellijTab,
   ShrinkTree,
   GrowTree,
   GPress,
   ToggleHelp,
   ToggleBlame,
+  /// Follow the previewed text file like `tail -f`, showing lines as they are appended
+  ToggleFollow,
+  /// Show or hide the line numbers of text previews
+  ToggleLineNumbers,
+  /// Prompt for a line to scroll the text preview to
+  PreviewGotoLine,
+  /// Wrap long preview lines instead of cutting them off
+  ToggleWrap,
+  ScrollPreviewLeft,
+  ScrollPreviewRight,
   CutFile,
   CopyFile,
   Paste,
+  /// Copy or move the targets into a directory picked from a prompt
+  CopyToStart,
+  MoveToStart,
   DeleteFile,
   RenameStart,
   NewFileStart,
   NewDirStart,
+  /// Prompt for where to put a symlink to the selected entry
+  CreateSymlink,
   PromptInput(char),
   PromptBackspace,
   PromptDelete,
@@ -42,6 +75,16 @@ pub enum Action {
   PromptRight,
   PromptHome,
   PromptEnd,
+  /// Readline-style edit in the text prompt or search query
+  LineEdit(LineEdit),
+  /// Text the terminal pasted in one piece (bracketed paste), inserted
+  /// into the prompt or search query without going through key bindings
+  InsertText(String),
+  /// Recall the previous/next search query or prompt name
+  InputHistoryPrev,
+  InputHistoryNext,
+  /// Tab in a prompt: +x for a new file, path completion for copy/move to
+  PromptTab,
   PromptConfirm,
   PromptCancel,
   GoHome,
@@ -59,6 +102,15 @@ pub enum Action {
   OpenWithUp,
   OpenWithSelect,
   OpenWithClose,
+  UrlsOpen,
+  UrlsDown,
+  UrlsUp,
+  UrlsSelect,
+  /// Open the URL at this index of the picker (digit keys)
+  UrlsPick(usize),
+  UrlsClose,
+  DoctorOpen,
+  DoctorClose,
   ErrorClose,
   ExtractArchive,
   ExtractAndDelete,
@@ -68,92 +120,451 @@ pub enum Action {
   ChmodOctalBackspace,
   ChmodToggleOctal,
   ChmodToggleRecursive,
+  ChmodToggleFollowSymlinks,
+  ChmodPreset(usize),
   ChmodApply,
   ChmodClose,
   ToggleCustomIgnore,
   HistoryBack,
   HistoryForward,
   BreadcrumbSelect(usize),
+  /// Dropdown of the directories beside the current one
+  BreadcrumbMenuOpen,
+  /// Dropdown of the directories inside the nth breadcrumb segment
+  BreadcrumbMenuAt(usize),
+  BreadcrumbMenuDown,
+  BreadcrumbMenuUp,
+  BreadcrumbMenuParent,
+  BreadcrumbMenuChild,
+  BreadcrumbMenuSelect,
+  /// Mouse click at (column, row) while the dropdown is open
+  BreadcrumbMenuClick(u16, u16),
+  BreadcrumbMenuClose,
   ToggleMarkdownMode,
+  ToggleSizeFormat,
   SwitchPane,
+  /// Move focus to the next region in `focus_order`
+  CycleFocus,
   ToggleDualPane,
   ShowDiff,
   NextHunk,
   PrevHunk,
+  OpenDiffTool,
+  ToggleDiffSideBySide,
+  CycleDiffMode,
+  ExportPatchStart,
+  ExportPatchSelect(usize),
+  ExportPatchClose,
+  StageHunk,
+  RevertHunk,
+  /// Stage the marked entries or the selection (`git add`)
+  GitStage,
+  /// Unstage them, keeping the working tree (`git restore --staged`)
+  GitUnstage,
+  /// Ask, then discard their unstaged changes (`git restore`)
+  GitDiscard,
+  ImageRevisionOlder,
+  ImageRevisionNewer,
+  ToggleDebugOverlay,
+  ReloadPreview,
   ShowProperties,
   PropertiesClose,
+  PropertiesToggleExecutable,
   ToggleMark,
   MarkAll,
   ClearMarks,
   CompressStart,
   CompressSelect(usize),
   CompressClose,
+  NewFromTemplate,
+  TemplatesDown,
+  TemplatesUp,
+  TemplatesSelect,
+  TemplatesClose,
+  WorktreesStart,
+  WorktreesDown,
+  WorktreesUp,
+  WorktreesSelect,
+  WorktreesClose,
+  /// Local branches of the repository, to check one out
+  BranchesStart,
+  BranchesDown,
+  BranchesUp,
+  BranchesSelect,
+  /// Prompt for a branch to create at HEAD and switch to
+  BranchesNew,
+  BranchesClose,
+  OpLogOpen,
+  OpLogDown,
+  OpLogUp,
+  OpLogClose,
+  /// Paste with the copy options changed for this once
+  PasteWithOptions,
+  /// Flip one paste option: symlinks, mode, timestamps, xattrs, verify
+  PasteOptionsToggle(usize),
+  PasteOptionsConfirm,
+  PasteOptionsClose,
+  /// Answer for the taken name being asked about (and, with "apply to
+  /// all", the rest)
+  PasteConflictChoose(Collision),
+  /// Flip "apply to all remaining"
+  PasteConflictAll,
+  PasteConflictCancel,
+  /// Reset SELinux labels on the targets to the policy default
+  Restorecon,
+  JobsOpen,
+  JobsDown,
+  JobsUp,
+  JobsCancel,
+  JobsClose,
+  LayoutSaveStart,
+  LayoutsOpen,
+  LayoutsDown,
+  LayoutsUp,
+  LayoutsSelect,
+  LayoutsRemove,
+  LayoutsClose,
+  SettingsOpen,
+  SettingsDown,
+  SettingsUp,
+  /// Next value of the selected option (false: previous)
+  SettingsChange(bool),
+  SettingsSave,
+  SettingsClose,
+  RepeatLast,
+  Undo,
+  Redo,
+  /// Quit and print the selection to stdout
+  QuitPrintSelection,
+  /// Stop in the background like any job on Ctrl+Z, until `fg`
+  SuspendProcess,
+  MacroRecord,
+  MacroPlay,
+  /// Register key typed after `MacroRecord` or `MacroPlay`
+  MacroRegister(char),
+  /// Wait for the slot to bookmark the selection in
+  BookmarkSet,
+  /// Wait for the slot of the bookmark to jump to
+  BookmarkJump,
+  /// Bookmark the selection in slot 1-9 of the current project
+  SetMark(u8),
+  /// Go to the bookmark in slot 1-9 of the current project
+  JumpMark(u8),
   Resize(u16, u16),
   Tick,
   None,
 }
 
+/// Names of the actions that can be bound to keys in the config.
+const NAMES: &[(&str, Action)] = &[
+  ("quit", Action::Quit),
+  ("escape", Action::Escape),
+  ("move_up", Action::MoveUp),
+  ("move_down", Action::MoveDown),
+  ("move_left", Action::MoveLeft),
+  ("move_right", Action::MoveRight),
+  ("page_down", Action::PageDown),
+  ("page_up", Action::PageUp),
+  ("toggle_expand", Action::ToggleExpand),
+  ("enter_dir", Action::EnterDir),
+  ("scroll_preview_up", Action::ScrollPreviewUp),
+  ("scroll_preview_down", Action::ScrollPreviewDown),
+  ("toggle_hidden", Action::ToggleHidden),
+  ("toggle_dirs_first", Action::ToggleDirsFirst),
+  ("toggle_size_column", Action::ToggleSizeColumn),
+  ("toggle_mouse", Action::ToggleMouse),
+  ("suspend", Action::SuspendProcess),
+  ("toggle_formatted", Action::ToggleFormatted),
+  ("go_to_top", Action::GoToTop),
+  ("go_to_bottom", Action::GoToBottom),
+  ("search_start", Action::SearchStart),
+  ("search_confirm", Action::SearchConfirm),
+  ("search_cancel", Action::SearchCancel),
+  ("yank_path", Action::YankPath),
+  ("open_editor", Action::OpenEditor),
+  ("open_claude", Action::OpenClaude),
+  ("open_claude_alt", Action::OpenClaudeAlt),
+  ("open_shell", Action::OpenShell),
+  ("tmux_window", Action::TmuxWindow),
+  ("tmux_pane", Action::TmuxPane),
+  ("zellij_tab", Action::ZellijTab),
+  ("shrink_tree", Action::ShrinkTree),
+  ("grow_tree", Action::GrowTree),
+  ("g_press", Action::GPress),
+  ("toggle_help", Action::ToggleHelp),
+  ("cut_file", Action::CutFile),
+  ("copy_file", Action::CopyFile),
+  ("paste", Action::Paste),
+  ("paste_with_options", Action::PasteWithOptions),
+  ("restorecon", Action::Restorecon),
+  ("copy_to", Action::CopyToStart),
+  ("move_to", Action::MoveToStart),
+  ("delete_file", Action::DeleteFile),
+  ("rename_start", Action::RenameStart),
+  ("new_file_start", Action::NewFileStart),
+  ("new_dir_start", Action::NewDirStart),
+  ("create_symlink", Action::CreateSymlink),
+  ("go_home", Action::GoHome),
+  ("breadcrumb_menu", Action::BreadcrumbMenuOpen),
+  ("favorite_add", Action::FavoriteAdd),
+  ("favorites_open", Action::FavoritesOpen),
+  ("open_default", Action::OpenDefault),
+  ("open_with", Action::OpenWithStart),
+  ("open_url", Action::UrlsOpen),
+  ("doctor", Action::DoctorOpen),
+  ("extract_archive", Action::ExtractArchive),
+  ("extract_and_delete", Action::ExtractAndDelete),
+  ("chmod", Action::ChmodStart),
+  ("toggle_custom_ignore", Action::ToggleCustomIgnore),
+  ("history_back", Action::HistoryBack),
+  ("history_forward", Action::HistoryForward),
+  ("toggle_blame", Action::ToggleBlame),
+  ("toggle_follow", Action::ToggleFollow),
+  ("toggle_line_numbers", Action::ToggleLineNumbers),
+  ("preview_goto_line", Action::PreviewGotoLine),
+  ("toggle_wrap", Action::ToggleWrap),
+  ("scroll_preview_left", Action::ScrollPreviewLeft),
+  ("scroll_preview_right", Action::ScrollPreviewRight),
+  ("toggle_markdown_mode", Action::ToggleMarkdownMode),
+  ("toggle_size_format", Action::ToggleSizeFormat),
+  ("switch_pane", Action::SwitchPane),
+  ("cycle_focus", Action::CycleFocus),
+  ("toggle_dual_pane", Action::ToggleDualPane),
+  ("show_diff", Action::ShowDiff),
+  ("next_hunk", Action::NextHunk),
+  ("prev_hunk", Action::PrevHunk),
+  ("open_difftool", Action::OpenDiffTool),
+  ("toggle_diff_side_by_side", Action::ToggleDiffSideBySide),
+  ("cycle_diff_mode", Action::CycleDiffMode),
+  ("export_patch", Action::ExportPatchStart),
+  ("stage_hunk", Action::StageHunk),
+  ("revert_hunk", Action::RevertHunk),
+  ("git_stage", Action::GitStage),
+  ("git_unstage", Action::GitUnstage),
+  ("git_discard", Action::GitDiscard),
+  ("image_revision_older", Action::ImageRevisionOlder),
+  ("image_revision_newer", Action::ImageRevisionNewer),
+  ("toggle_debug_overlay", Action::ToggleDebugOverlay),
+  ("reload_preview", Action::ReloadPreview),
+  ("show_properties", Action::ShowProperties),
+  ("toggle_mark", Action::ToggleMark),
+  ("mark_all", Action::MarkAll),
+  ("clear_marks", Action::ClearMarks),
+  ("compress", Action::CompressStart),
+  ("new_from_template", Action::NewFromTemplate),
+  ("worktrees", Action::WorktreesStart),
+  ("branches", Action::BranchesStart),
+  ("operation_log", Action::OpLogOpen),
+  ("jobs", Action::JobsOpen),
+  ("layout_save", Action::LayoutSaveStart),
+  ("layouts", Action::LayoutsOpen),
+  ("settings", Action::SettingsOpen),
+  ("repeat_last", Action::RepeatLast),
+  ("undo", Action::Undo),
+  ("redo", Action::Redo),
+  ("quit_print_selection", Action::QuitPrintSelection),
+  ("macro_record", Action::MacroRecord),
+  ("macro_play", Action::MacroPlay),
+  ("bookmark_set", Action::BookmarkSet),
+  ("bookmark_jump", Action::BookmarkJump),
+  ("none", Action::None),
+];
+
+/// The actions the help overlay lists, by section, with what each does.
+pub const HELP_SECTIONS: &[(&str, &[(Action, &str)])] = &[
+  (
+    "Navigation",
+    &[
+      (Action::MoveDown, "Move down"),
+      (Action::MoveUp, "Move up"),
+      (Action::MoveLeft, "Collapse / parent"),
+      (Action::MoveRight, "Expand / select"),
+      (Action::PageDown, "Page down"),
+      (Action::PageUp, "Page up"),
+      (Action::ToggleExpand, "Toggle expand"),
+      (Action::GoToTop, "Go to top"),
+      (Action::GoToBottom, "Go to bottom"),
+      (Action::GoHome, "Go to home"),
+      (Action::FavoritesOpen, "Open favorites"),
+      (Action::FavoriteAdd, "Add to favorites"),
+      (Action::LayoutsOpen, "Saved layouts"),
+      (Action::LayoutSaveStart, "Save layout"),
+      (Action::BreadcrumbMenuOpen, "Sibling directories menu"),
+    ],
+  ),
+  (
+    "Search",
+    &[
+      (Action::SearchStart, "Start search"),
+      (Action::SearchConfirm, "Confirm"),
+      (Action::SearchCancel, "Cancel"),
+    ],
+  ),
+  (
+    "Preview",
+    &[
+      (Action::ScrollPreviewDown, "Scroll down"),
+      (Action::ScrollPreviewUp, "Scroll up"),
+      (Action::ScrollPreviewLeft, "Scroll left"),
+      (Action::ScrollPreviewRight, "Scroll right"),
+      (Action::ToggleWrap, "Wrap long lines"),
+      (Action::CycleFocus, "Focus next pane / preview"),
+      (Action::ReloadPreview, "Reload preview"),
+      (Action::ToggleFollow, "Follow file (tail -f)"),
+      (Action::PreviewGotoLine, "Go to line"),
+      (Action::ToggleLineNumbers, "Line numbers"),
+      (Action::ToggleSizeFormat, "Human/exact sizes"),
+      (Action::ShrinkTree, "Shrink tree pane"),
+      (Action::GrowTree, "Grow tree pane"),
+    ],
+  ),
+  (
+    "Actions",
+    &[
+      (Action::OpenDefault, "Open file / enter dir"),
+      (Action::OpenWithStart, "Open with..."),
+      (Action::UrlsOpen, "Open a URL in the preview"),
+      (Action::ShowProperties, "Show properties"),
+      (Action::OpenEditor, "Open in $EDITOR"),
+      (Action::OpenClaude, "Open Claude Code with file context"),
+      (Action::OpenShell, "Open $SHELL"),
+      (Action::TmuxWindow, "New tmux window here"),
+      (Action::TmuxPane, "New tmux pane here"),
+      (Action::ZellijTab, "New zellij tab here"),
+      (Action::YankPath, "Yank path"),
+      (Action::ToggleHidden, "Toggle hidden files"),
+      (Action::ToggleDirsFirst, "Dirs first / interleaved"),
+      (Action::ToggleSizeColumn, "Size column (directory totals)"),
+      (Action::ToggleMouse, "Mouse capture / terminal select"),
+      (Action::SettingsOpen, "Settings (theme, icons, width)"),
+      (Action::ToggleDebugOverlay, "Debug overlay"),
+      (Action::DoctorOpen, "Diagnostics (detected features)"),
+    ],
+  ),
+  (
+    "Git",
+    &[
+      (Action::ShowDiff, "Show diff"),
+      (Action::NextHunk, "Next hunk"),
+      (Action::PrevHunk, "Previous hunk"),
+      (Action::OpenDiffTool, "Open in difftool"),
+      (Action::ToggleDiffSideBySide, "Side-by-side diff"),
+      (Action::CycleDiffMode, "Unstaged/staged/all diff"),
+      (Action::ExportPatchStart, "Export diff as patch"),
+      (Action::StageHunk, "Stage/unstage hunk"),
+      (Action::RevertHunk, "Revert hunk"),
+      (Action::GitStage, "Stage file/marked"),
+      (Action::GitUnstage, "Unstage file/marked"),
+      (Action::GitDiscard, "Discard unstaged changes"),
+      (Action::ImageRevisionOlder, "Older image revision"),
+      (Action::ImageRevisionNewer, "Newer image revision"),
+      (Action::WorktreesStart, "Switch / add worktree"),
+      (Action::BranchesStart, "Switch / create branch"),
+    ],
+  ),
+  (
+    "Marking",
+    &[
+      (Action::ToggleMark, "Toggle mark"),
+      (Action::MarkAll, "Mark all"),
+      (Action::ClearMarks, "Clear marks"),
+    ],
+  ),
+  (
+    "Macros",
+    &[
+      (Action::RepeatLast, "Repeat last operation"),
+      (Action::MacroRecord, "Record macro / stop"),
+      (Action::MacroPlay, "Replay macro (@@ repeats)"),
+    ],
+  ),
+  (
+    "Bookmarks",
+    &[
+      (Action::BookmarkSet, "Bookmark selection in slot 1-9"),
+      (Action::BookmarkJump, "Jump to bookmark 1-9"),
+    ],
+  ),
+  (
+    "File Operations",
+    &[
+      (Action::RenameStart, "Rename"),
+      (Action::DeleteFile, "Delete"),
+      (Action::CopyFile, "Copy"),
+      (Action::CutFile, "Cut"),
+      (Action::Paste, "Paste"),
+      (Action::PasteWithOptions, "Paste with symlink/metadata options"),
+      (Action::CopyToStart, "Copy to..."),
+      (Action::MoveToStart, "Move to..."),
+      (Action::NewFileStart, "New file"),
+      (Action::NewDirStart, "New directory"),
+      (Action::CreateSymlink, "Symlink to the selection"),
+      (Action::NewFromTemplate, "New from template"),
+      (Action::CompressStart, "Compress to archive"),
+      (Action::ExtractArchive, "Extract archive"),
+      (Action::ExtractAndDelete, "Extract and delete archive"),
+      (Action::JobsOpen, "Running and queued jobs"),
+      (Action::Restorecon, "Restore default SELinux labels"),
+      (Action::Undo, "Undo rename, create, symlink or paste"),
+      (Action::Redo, "Redo"),
+      (Action::OpLogOpen, "Today's operation log"),
+    ],
+  ),
+  (
+    "Quit",
+    &[
+      (Action::Quit, "Quit"),
+      (Action::QuitPrintSelection, "Quit, print selection"),
+      (Action::SuspendProcess, "Suspend (fg to return)"),
+      (Action::Escape, "Clear filter/marks/overlay, stop a copy, then quit"),
+    ],
+  ),
+];
+
 impl Action {
+  /// What the action changes on disk, for the read-only mode message, or
+  /// None when it doesn't modify anything.
+  pub fn mutation(&self) -> Option<&'static str> {
+    Some(match self {
+      Action::Paste | Action::PasteWithOptions | Action::PasteOptionsConfirm => "paste",
+      Action::CopyToStart => "copy",
+      Action::MoveToStart => "move",
+      Action::DeleteFile => "delete",
+      Action::RenameStart => "rename",
+      Action::NewFileStart | Action::NewFromTemplate => "new file",
+      Action::NewDirStart => "new directory",
+      Action::CreateSymlink => "symlink",
+      Action::ExtractArchive | Action::ExtractAndDelete => "extract",
+      Action::ChmodStart | Action::PropertiesToggleExecutable => "chmod",
+      Action::Restorecon => "restorecon",
+      Action::CompressStart => "compress",
+      Action::ExportPatchStart => "export patch",
+      Action::StageHunk => "stage hunk",
+      Action::RevertHunk => "revert hunk",
+      Action::GitStage => "stage",
+      Action::GitUnstage => "unstage",
+      Action::GitDiscard => "discard",
+      Action::BranchesSelect => "checkout",
+      Action::BranchesNew => "new branch",
+      Action::RepeatLast => "repeat",
+      Action::Undo => "undo",
+      Action::Redo => "redo",
+      _ => return None,
+    })
+  }
+
   pub fn from_name(name: &str) -> Option<Action> {
-    match name {
-      "quit" => Some(Action::Quit),
-      "move_up" => Some(Action::MoveUp),
-      "move_down" => Some(Action::MoveDown),
-      "move_left" => Some(Action::MoveLeft),
-      "move_right" => Some(Action::MoveRight),
-      "toggle_expand" => Some(Action::ToggleExpand),
-      "enter_dir" => Some(Action::EnterDir),
-      "scroll_preview_up" => Some(Action::ScrollPreviewUp),
-      "scroll_preview_down" => Some(Action::ScrollPreviewDown),
-      "toggle_hidden" => Some(Action::ToggleHidden),
-      "toggle_formatted" => Some(Action::ToggleFormatted),
-      "go_to_top" => Some(Action::GoToTop),
-      "go_to_bottom" => Some(Action::GoToBottom),
-      "search_start" => Some(Action::SearchStart),
-      "search_confirm" => Some(Action::SearchConfirm),
-      "search_cancel" => Some(Action::SearchCancel),
-      "yank_path" => Some(Action::YankPath),
-      "open_editor" => Some(Action::OpenEditor),
-      "open_claude" => Some(Action::OpenClaude),
-      "open_claude_alt" => Some(Action::OpenClaudeAlt),
-      "open_shell" => Some(Action::OpenShell),
-      "shrink_tree" => Some(Action::ShrinkTree),
-      "grow_tree" => Some(Action::GrowTree),
-      "g_press" => Some(Action::GPress),
-      "toggle_help" => Some(Action::ToggleHelp),
-      "cut_file" => Some(Action::CutFile),
-      "copy_file" => Some(Action::CopyFile),
-      "paste" => Some(Action::Paste),
-      "delete_file" => Some(Action::DeleteFile),
-      "rename_start" => Some(Action::RenameStart),
-      "new_file_start" => Some(Action::NewFileStart),
-      "new_dir_start" => Some(Action::NewDirStart),
-      "go_home" => Some(Action::GoHome),
-      "favorite_add" => Some(Action::FavoriteAdd),
-      "favorites_open" => Some(Action::FavoritesOpen),
-      "open_default" => Some(Action::OpenDefault),
-      "open_with" => Some(Action::OpenWithStart),
-      "extract_archive" => Some(Action::ExtractArchive),
-      "extract_and_delete" => Some(Action::ExtractAndDelete),
-      "chmod" => Some(Action::ChmodStart),
-      "toggle_custom_ignore" => Some(Action::ToggleCustomIgnore),
-      "history_back" => Some(Action::HistoryBack),
-      "history_forward" => Some(Action::HistoryForward),
-      "toggle_blame" => Some(Action::ToggleBlame),
-      "toggle_markdown_mode" => Some(Action::ToggleMarkdownMode),
-      "switch_pane" => Some(Action::SwitchPane),
-      "toggle_dual_pane" => Some(Action::ToggleDualPane),
-      "show_diff" => Some(Action::ShowDiff),
-      "next_hunk" => Some(Action::NextHunk),
-      "prev_hunk" => Some(Action::PrevHunk),
-      "show_properties" => Some(Action::ShowProperties),
-      "toggle_mark" => Some(Action::ToggleMark),
-      "mark_all" => Some(Action::MarkAll),
-      "clear_marks" => Some(Action::ClearMarks),
-      "compress" => Some(Action::CompressStart),
-      "none" => Some(Action::None),
-      _ => None,
-    }
+    NAMES.iter().find(|(n, _)| *n == name).map(|(_, action)| action.clone())
+  }
+
+  /// The name bindings use for the action, or None for ones that can't be bound.
+  pub fn name(&self) -> Option<&'static str> {
+    NAMES.iter().find(|(_, action)| action == self).map(|(n, _)| *n)
+  }
+
+  /// What the action does, as the help overlay lists it.
+  pub fn description(&self) -> Option<&'static str> {
+    HELP_SECTIONS.iter().flat_map(|(_, entries)| entries.iter()).find(|(action, _)| action == self).map(|(_, d)| *d)
   }
 }
 
@@ -161,9 +572,18 @@ impl Action {
 mod tests {
   use super::*;
 
+  #[test]
+  fn test_mutation() {
+    assert_eq!(Action::DeleteFile.mutation(), Some("delete"));
+    assert_eq!(Action::PropertiesToggleExecutable.mutation(), Some("chmod"));
+    assert_eq!(Action::CopyFile.mutation(), None);
+    assert_eq!(Action::MoveDown.mutation(), None);
+  }
+
   #[test]
   fn test_from_name_bindable_actions() {
     assert_eq!(Action::from_name("quit"), Some(Action::Quit));
+    assert_eq!(Action::from_name("escape"), Some(Action::Escape));
     assert_eq!(Action::from_name("move_up"), Some(Action::MoveUp));
     assert_eq!(Action::from_name("move_down"), Some(Action::MoveDown));
     assert_eq!(Action::from_name("move_left"), Some(Action::MoveLeft));
@@ -172,7 +592,12 @@ mod tests {
     assert_eq!(Action::from_name("enter_dir"), Some(Action::EnterDir));
     assert_eq!(Action::from_name("scroll_preview_up"), Some(Action::ScrollPreviewUp));
     assert_eq!(Action::from_name("scroll_preview_down"), Some(Action::ScrollPreviewDown));
+    assert_eq!(Action::from_name("page_down"), Some(Action::PageDown));
     assert_eq!(Action::from_name("toggle_hidden"), Some(Action::ToggleHidden));
+    assert_eq!(Action::from_name("toggle_dirs_first"), Some(Action::ToggleDirsFirst));
+    assert_eq!(Action::from_name("toggle_size_column"), Some(Action::ToggleSizeColumn));
+    assert_eq!(Action::from_name("toggle_mouse"), Some(Action::ToggleMouse));
+    assert_eq!(Action::from_name("suspend"), Some(Action::SuspendProcess));
     assert_eq!(Action::from_name("toggle_formatted"), Some(Action::ToggleFormatted));
     assert_eq!(Action::from_name("go_to_top"), Some(Action::GoToTop));
     assert_eq!(Action::from_name("go_to_bottom"), Some(Action::GoToBottom));
@@ -184,6 +609,9 @@ mod tests {
     assert_eq!(Action::from_name("open_claude"), Some(Action::OpenClaude));
     assert_eq!(Action::from_name("open_claude_alt"), Some(Action::OpenClaudeAlt));
     assert_eq!(Action::from_name("open_shell"), Some(Action::OpenShell));
+    assert_eq!(Action::from_name("tmux_window"), Some(Action::TmuxWindow));
+    assert_eq!(Action::from_name("tmux_pane"), Some(Action::TmuxPane));
+    assert_eq!(Action::from_name("zellij_tab"), Some(Action::ZellijTab));
     assert_eq!(Action::from_name("shrink_tree"), Some(Action::ShrinkTree));
     assert_eq!(Action::from_name("grow_tree"), Some(Action::GrowTree));
     assert_eq!(Action::from_name("g_press"), Some(Action::GPress));
@@ -191,30 +619,75 @@ mod tests {
     assert_eq!(Action::from_name("cut_file"), Some(Action::CutFile));
     assert_eq!(Action::from_name("copy_file"), Some(Action::CopyFile));
     assert_eq!(Action::from_name("paste"), Some(Action::Paste));
+    assert_eq!(Action::from_name("copy_to"), Some(Action::CopyToStart));
+    assert_eq!(Action::from_name("move_to"), Some(Action::MoveToStart));
     assert_eq!(Action::from_name("delete_file"), Some(Action::DeleteFile));
     assert_eq!(Action::from_name("rename_start"), Some(Action::RenameStart));
     assert_eq!(Action::from_name("new_file_start"), Some(Action::NewFileStart));
     assert_eq!(Action::from_name("new_dir_start"), Some(Action::NewDirStart));
+    assert_eq!(Action::from_name("create_symlink"), Some(Action::CreateSymlink));
     assert_eq!(Action::from_name("go_home"), Some(Action::GoHome));
+    assert_eq!(Action::from_name("breadcrumb_menu"), Some(Action::BreadcrumbMenuOpen));
     assert_eq!(Action::from_name("favorite_add"), Some(Action::FavoriteAdd));
     assert_eq!(Action::from_name("favorites_open"), Some(Action::FavoritesOpen));
     assert_eq!(Action::from_name("open_default"), Some(Action::OpenDefault));
     assert_eq!(Action::from_name("open_with"), Some(Action::OpenWithStart));
+    assert_eq!(Action::from_name("open_url"), Some(Action::UrlsOpen));
+    assert_eq!(Action::from_name("doctor"), Some(Action::DoctorOpen));
     assert_eq!(Action::from_name("chmod"), Some(Action::ChmodStart));
     assert_eq!(Action::from_name("toggle_custom_ignore"), Some(Action::ToggleCustomIgnore));
     assert_eq!(Action::from_name("history_back"), Some(Action::HistoryBack));
     assert_eq!(Action::from_name("history_forward"), Some(Action::HistoryForward));
     assert_eq!(Action::from_name("toggle_blame"), Some(Action::ToggleBlame));
+    assert_eq!(Action::from_name("toggle_follow"), Some(Action::ToggleFollow));
+    assert_eq!(Action::from_name("toggle_line_numbers"), Some(Action::ToggleLineNumbers));
+    assert_eq!(Action::from_name("preview_goto_line"), Some(Action::PreviewGotoLine));
+    assert_eq!(Action::from_name("toggle_wrap"), Some(Action::ToggleWrap));
+    assert_eq!(Action::from_name("scroll_preview_left"), Some(Action::ScrollPreviewLeft));
+    assert_eq!(Action::from_name("scroll_preview_right"), Some(Action::ScrollPreviewRight));
     assert_eq!(Action::from_name("switch_pane"), Some(Action::SwitchPane));
+    assert_eq!(Action::from_name("cycle_focus"), Some(Action::CycleFocus));
     assert_eq!(Action::from_name("toggle_dual_pane"), Some(Action::ToggleDualPane));
     assert_eq!(Action::from_name("show_diff"), Some(Action::ShowDiff));
     assert_eq!(Action::from_name("next_hunk"), Some(Action::NextHunk));
     assert_eq!(Action::from_name("prev_hunk"), Some(Action::PrevHunk));
+    assert_eq!(Action::from_name("open_difftool"), Some(Action::OpenDiffTool));
+    assert_eq!(Action::from_name("toggle_diff_side_by_side"), Some(Action::ToggleDiffSideBySide));
+    assert_eq!(Action::from_name("cycle_diff_mode"), Some(Action::CycleDiffMode));
+    assert_eq!(Action::from_name("export_patch"), Some(Action::ExportPatchStart));
+    assert_eq!(Action::from_name("stage_hunk"), Some(Action::StageHunk));
+    assert_eq!(Action::from_name("revert_hunk"), Some(Action::RevertHunk));
+    assert_eq!(Action::from_name("git_stage"), Some(Action::GitStage));
+    assert_eq!(Action::from_name("git_unstage"), Some(Action::GitUnstage));
+    assert_eq!(Action::from_name("git_discard"), Some(Action::GitDiscard));
+    assert_eq!(Action::from_name("image_revision_older"), Some(Action::ImageRevisionOlder));
+    assert_eq!(Action::from_name("image_revision_newer"), Some(Action::ImageRevisionNewer));
+    assert_eq!(Action::from_name("toggle_debug_overlay"), Some(Action::ToggleDebugOverlay));
+    assert_eq!(Action::from_name("reload_preview"), Some(Action::ReloadPreview));
     assert_eq!(Action::from_name("show_properties"), Some(Action::ShowProperties));
     assert_eq!(Action::from_name("toggle_mark"), Some(Action::ToggleMark));
+    assert_eq!(Action::from_name("toggle_size_format"), Some(Action::ToggleSizeFormat));
     assert_eq!(Action::from_name("mark_all"), Some(Action::MarkAll));
     assert_eq!(Action::from_name("clear_marks"), Some(Action::ClearMarks));
     assert_eq!(Action::from_name("compress"), Some(Action::CompressStart));
+    assert_eq!(Action::from_name("new_from_template"), Some(Action::NewFromTemplate));
+    assert_eq!(Action::from_name("worktrees"), Some(Action::WorktreesStart));
+    assert_eq!(Action::from_name("branches"), Some(Action::BranchesStart));
+    assert_eq!(Action::from_name("operation_log"), Some(Action::OpLogOpen));
+    assert_eq!(Action::from_name("paste_with_options"), Some(Action::PasteWithOptions));
+    assert_eq!(Action::from_name("jobs"), Some(Action::JobsOpen));
+    assert_eq!(Action::from_name("restorecon"), Some(Action::Restorecon));
+    assert_eq!(Action::from_name("layout_save"), Some(Action::LayoutSaveStart));
+    assert_eq!(Action::from_name("layouts"), Some(Action::LayoutsOpen));
+    assert_eq!(Action::from_name("settings"), Some(Action::SettingsOpen));
+    assert_eq!(Action::from_name("repeat_last"), Some(Action::RepeatLast));
+    assert_eq!(Action::from_name("undo"), Some(Action::Undo));
+    assert_eq!(Action::from_name("redo"), Some(Action::Redo));
+    assert_eq!(Action::from_name("quit_print_selection"), Some(Action::QuitPrintSelection));
+    assert_eq!(Action::from_name("macro_record"), Some(Action::MacroRecord));
+    assert_eq!(Action::from_name("macro_play"), Some(Action::MacroPlay));
+    assert_eq!(Action::from_name("bookmark_set"), Some(Action::BookmarkSet));
+    assert_eq!(Action::from_name("bookmark_jump"), Some(Action::BookmarkJump));
   }
 
   #[test]
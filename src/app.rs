@@ -1,22 +1,53 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
-use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
-use std::process::Command;
-use std::sync::mpsc;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use ratatui::layout::Rect;
 use ratatui_image::picker::Picker;
 
 use crate::action::Action;
-use crate::config::Config;
+use crate::bookmarks::{self, Bookmarks};
+use crate::breadcrumb_menu::{self, BreadcrumbMenu};
+use crate::command::{self, Command, History};
+use crate::config::{ChmodPreset, ChmodPresetMode, Config, FocusRegion, QuitConfirm, SafeNames};
+use crate::destination::{self, DestinationPicker};
+use crate::dir_counts::{self, DirCounts};
+use crate::dir_sizes::{self, DirSizes};
+use crate::doctor::{self, Check};
 use crate::event::{InputMode, PromptKind};
 use crate::favorites::Favorites;
-use crate::fs::{FileProperties, FileTree};
-use crate::fs::ops;
+use crate::fs::{ArchiveSource, FileEntry, FileProperties, FileTree, MultiProperties};
+use crate::fs::names::{self, NameChange};
+use crate::fs::{locks, ops, selinux, walk};
+use crate::fs::ops::{Collision, CopyOptions, RemovalSummary};
+use crate::fs::progress::{self, Counts, Progress};
+use crate::git::{Branch, Worktree};
+use crate::input_history::{HistoryKind, InputHistory};
+use crate::jobs::{self, Job, JobKind, Jobs, Transferred};
+use crate::journal::{Journal, SessionState};
+use crate::labels::{self, Labels};
+use crate::layouts::{Layout, Layouts};
+use crate::line_editor::{LineEdit, LineEditor};
+use crate::logging::LoopStats;
+use crate::macros::{MacroOp, Macros};
+use crate::op_log::{self, OpLog, OpRecord};
 use crate::opener::{self, OpenApp};
-use crate::preview::{PreviewState, PreviewType, archive};
+use crate::persist::{StateFile, StateWriter};
+use crate::preview::diff::{self, DiffMode, HunkAction};
+use crate::preview::directory::SizeFormat;
+use crate::preview::hover::Hover;
+use crate::preview::{PreviewState, PreviewType, archive, links};
+use crate::process::{ProcessRunner, ProcessSpec, SystemRunner};
+use crate::repeat::{self, RenamePattern, Repeatable};
+use crate::settings::{self, Settings, SettingsEditor};
+use crate::templates::{self, ContextRule, FileTemplate};
 use crate::ui::breadcrumb::{BreadcrumbSegment, parse_breadcrumb_segments};
+use crate::ui::favorites::contract_home;
+use crate::ui::hyperlinks::Hyperlink;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClipboardOp {
@@ -24,32 +55,61 @@ pub enum ClipboardOp {
   Copy,
 }
 
-/// Result of an async archive extraction
-pub struct ExtractResult {
-  pub name: String,
-  pub path: PathBuf,
-  pub delete_after: bool,
-  pub result: Result<(), String>,
+/// What the entries awaiting delete confirmation hold, counted in the
+/// background. Dropping it stops the count.
+pub struct DeleteSummary {
+  pub targets: Vec<PathBuf>,
+  /// None while still counting
+  pub summary: Option<RemovalSummary>,
+  /// Cancelled when the prompt closes
+  progress: Arc<Progress>,
+  rx: mpsc::Receiver<RemovalSummary>,
 }
 
-/// State for an in-progress extraction
-pub struct ExtractingState {
-  pub rx: mpsc::Receiver<ExtractResult>,
+impl DeleteSummary {
+  fn start(targets: Vec<PathBuf>) -> Self {
+    let (tx, rx) = mpsc::channel();
+    let progress = Arc::new(Progress::default());
+    let (paths, counted) = (targets.clone(), Arc::clone(&progress));
+    std::thread::spawn(move || {
+      if let Some(summary) = ops::summarize_removal(&paths, &counted) {
+        let _ = tx.send(summary);
+      }
+    });
+    Self { targets, summary: None, progress, rx }
+  }
 }
 
-/// Result of an async archive compression
-pub struct CompressResult {
-  pub name: String,
-  pub path: PathBuf,
-  pub result: Result<(), String>,
+impl Drop for DeleteSummary {
+  fn drop(&mut self) {
+    self.progress.cancel();
+  }
 }
 
-/// State for an in-progress compression
-pub struct CompressingState {
-  pub rx: mpsc::Receiver<CompressResult>,
+/// A diff exported to a file, awaiting the prompt for where to save it.
+pub struct PendingPatch {
+  pub patch: String,
+  /// "patch" or "hunk N", for the prompt and status
+  pub what: String,
+  /// The file the diff is of
+  source: PathBuf,
+  /// Where relative paths start: the directory of the selected entry
+  dir: PathBuf,
 }
 
-#[derive(Debug, Clone)]
+/// A row of the worktree switcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorktreeChoice {
+  /// Go to a checkout that already exists
+  Existing(Worktree),
+  /// Add a worktree for a branch that has none yet
+  Branch(Branch),
+}
+
+/// Branches without a worktree offered in the switcher
+const WORKTREE_BRANCH_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Clipboard {
   pub paths: Vec<PathBuf>,
   pub op: Option<ClipboardOp>,
@@ -58,37 +118,124 @@ pub struct Clipboard {
 #[derive(Debug, Clone)]
 pub struct ChmodState {
   pub path: PathBuf,
+  /// The other marked entries, given the same mode
+  pub others: Vec<PathBuf>,
   pub original_mode: u32,
   pub new_mode: u32,
   pub is_dir: bool,
   pub recursive: bool,
+  pub is_symlink: bool,
+  /// Apply to symlink targets; off by default since a link's target may live
+  /// outside the tree
+  pub follow_symlinks: bool,
+  pub owner_uid: u32,
   pub octal_mode: bool,
   pub octal_input: String,
 }
 
+impl ChmodState {
+  /// Octal permissions, widened to four digits when setuid/setgid/sticky is set
+  pub fn octal(mode: u32) -> String {
+    if mode & 0o7000 != 0 {
+      format!("{:04o}", mode & 0o7777)
+    } else {
+      format!("{:03o}", mode & 0o777)
+    }
+  }
+
+  /// Warning for special bits that change who a program runs as.
+  pub fn warning(&self) -> Option<&'static str> {
+    let executable = !self.is_dir && self.new_mode & 0o111 != 0;
+    if !executable {
+      return None;
+    }
+    if self.new_mode & 0o4000 != 0 {
+      Some(if self.owner_uid == 0 {
+        "setuid root: anyone who runs this gets root"
+      } else {
+        "setuid: runs as the file's owner, whoever starts it"
+      })
+    } else if self.new_mode & 0o2000 != 0 {
+      Some("setgid: runs with the file's group, whoever starts it")
+    } else {
+      None
+    }
+  }
+}
+
 impl Default for ChmodState {
   fn default() -> Self {
     Self {
       path: PathBuf::new(),
+      others: Vec::new(),
       original_mode: 0o644,
       new_mode: 0o644,
       is_dir: false,
       recursive: false,
+      is_symlink: false,
+      follow_symlinks: false,
+      owner_uid: 0,
       octal_mode: false,
       octal_input: String::new(),
     }
   }
 }
 
+/// Bookkeeping for one recursive chmod
+struct ChmodWalk {
+  follow_symlinks: bool,
+  visited: walk::VisitedDirs,
+  skipped_links: usize,
+}
+
 /// Maximum number of entries in the directory history
 const HISTORY_LIMIT: usize = 50;
 
+/// Copies bigger than this run in the background, with progress shown
+const BACKGROUND_COPY: Counts = Counts { entries: 2000, bytes: 64 * 1024 * 1024 };
+
+/// How often git status is rescanned in the background, for changes to
+/// the index or in directories that aren't watched
+const GIT_REFRESH: Duration = Duration::from_secs(3);
+
+/// Columns the preview moves per sideways scroll
+const PREVIEW_H_SCROLL: usize = 8;
+
+/// How a copy or move started by `transfer` went
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Transfer {
+  /// Done, with notes for the status bar on what was left out
+  Done(String),
+  /// Handed to a job
+  Background,
+  /// Stopped by a failure, shown in the status bar
+  Failed,
+}
+
+/// The main tree while it shows the inside of an archive.
+pub struct ArchiveView {
+  pub archive: PathBuf,
+  /// The tree the archive was entered from, restored on leaving
+  outer: FileTree,
+}
+
+/// An archive being listed on a worker before its view opens.
+pub struct ArchiveOpening {
+  /// Where in the archive to go once it is listed
+  dir: PathBuf,
+  /// The tree root while waiting; moving elsewhere abandons the archive
+  root: PathBuf,
+  /// Root to push onto the history once the view opens
+  from: Option<PathBuf>,
+  rx: mpsc::Receiver<(PathBuf, Result<archive::ArchiveListing, String>)>,
+}
+
 /// State for a single pane in dual-pane mode
 pub struct Pane {
   pub tree: FileTree,
   pub cursor: usize,
   pub scroll_offset: usize,
-  pub search_query: String,
+  pub search: LineEditor,
   pub marked: HashSet<PathBuf>,
   pub cached_visible: Vec<usize>,
 }
@@ -101,7 +248,7 @@ impl Pane {
       tree,
       cursor: 0,
       scroll_offset: 0,
-      search_query: String::new(),
+      search: LineEditor::default(),
       marked: HashSet::new(),
       cached_visible,
     })
@@ -112,10 +259,10 @@ impl Pane {
   }
 
   pub fn rebuild_visible_cache(&mut self) {
-    if self.search_query.is_empty() {
+    if self.search.is_empty() {
       self.cached_visible = (0..self.tree.entries.len()).collect();
     } else {
-      let query = self.search_query.to_lowercase();
+      let query = self.search.text.to_lowercase();
       self.cached_visible = self
         .tree
         .entries
@@ -147,35 +294,103 @@ pub struct App {
   pub preview: PreviewState,
   pub picker: Option<Picker>,
   pub input_mode: InputMode,
-  pub search_query: String,
+  pub search: LineEditor,
   pub tree_ratio: u16,
   pub min_tree_ratio: u16,
   pub max_tree_ratio: u16,
   pub ratio_step: u16,
   pub show_help: bool,
+  pub show_debug: bool,
+  pub loop_stats: LoopStats,
   pub help_scroll: usize,
   pub should_quit: bool,
   pub should_suspend: Option<SuspendAction>,
+  /// Stop the process until it is continued (Ctrl+Z)
+  pub should_stop: bool,
   pub status_message: Option<String>,
   pub status_ticks: u8,
   pub viewport_height: usize,
+  /// Where the tree panes were last drawn, for mapping mouse positions
+  pub tree_area: Rect,
+  pub right_tree_area: Option<Rect>,
+  pub hover: Option<Hover>,
+  /// Whether the terminal should report mouse events to tfl; off, it
+  /// selects text natively
+  pub mouse_capture: bool,
+  /// File type icons in the tree
+  pub icons: bool,
+  /// Git status markers in the tree and counts in the header
+  pub git_markers: bool,
+  /// When the last background git status rescan was started
+  git_refreshed: Instant,
+  /// Name of the theme on screen
+  pub theme_name: String,
+  /// Theme picked in the settings overlay, for the main loop to switch to
+  pub requested_theme: Option<String>,
+  /// The settings overlay's options while it is open
+  pub settings: Option<SettingsEditor>,
+  /// Delay before the hover popup appears; 0 disables it
+  pub hover_preview_ms: u64,
+  /// Preview the selection as the cursor moves; off, only `R` loads one
+  pub auto_preview: bool,
   pub tree_scroll_offset: usize,
   pub clipboard: Clipboard,
+  /// How pastes and copy to treat symlinks and metadata (`[copy]`)
+  pub copy_options: CopyOptions,
+  /// This paste's options, while the paste options dialog is open or its
+  /// paste waits on the safe names prompt
+  pub paste_options: Option<CopyOptions>,
   pub marked: HashSet<PathBuf>,
   pub prompt_kind: Option<PromptKind>,
-  pub prompt_input: String,
-  pub prompt_cursor: usize,
+  pub prompt: LineEditor,
+  /// Earlier search queries and prompt names for Up/Down
+  pub input_history: InputHistory,
   pub favorites: Favorites,
   pub favorites_cursor: usize,
+  pub layouts: Layouts,
+  pub layouts_cursor: usize,
+  /// Numbered quick-jump bookmarks of each project
+  pub bookmarks: Bookmarks,
+  pub breadcrumb_menu: Option<BreadcrumbMenu>,
+  /// Links in the last drawn frame, printed as OSC 8 hyperlinks
+  pub hyperlinks: Vec<Hyperlink>,
+  /// Where the dropdown was last drawn, for mouse clicks
+  pub breadcrumb_menu_area: Option<Rect>,
   pub open_with_apps: Vec<OpenApp>,
   pub open_with_cursor: usize,
+  /// URLs in view in the preview, while the URL picker is open
+  pub urls: Vec<String>,
+  pub urls_cursor: usize,
+  /// Detected capabilities, while the diagnostics overlay is open
+  pub doctor: Vec<Check>,
+  /// Text rows of the preview pane when last drawn
+  pub preview_rows: usize,
   pub custom_apps: Vec<OpenApp>,
+  /// Launches external programs; replaced by a mock in tests
+  pub runner: Arc<dyn ProcessRunner>,
   pub error_messages: Vec<String>,
-  pub wrote_config: bool,
+  /// Unsaved favorites, layouts, input history and session journal
+  pub state_writer: StateWriter,
+  /// This process's session journal; None when not journaling (tests, batch)
+  pub journal: Option<Journal>,
   pub claude_yolo: bool,
-  pub extracting: Option<ExtractingState>,
-  pub compressing: Option<CompressingState>,
+  pub ai_command: String,
+  pub ai_command_alt: String,
+  pub tmux_window_command: String,
+  pub tmux_pane_command: String,
+  pub zellij_tab_command: String,
+  pub terminal_command: String,
+  /// A display is available for `terminal_command` windows
+  pub graphical_session: bool,
+  pub difftool: String,
+  /// Extractions and compressions, running and queued
+  pub jobs: Jobs,
+  pub jobs_cursor: usize,
+  pub delete_summary: Option<DeleteSummary>,
   pub chmod_state: ChmodState,
+  /// Set while the main tree is browsing inside an archive
+  pub archive_view: Option<ArchiveView>,
+  pub archive_opening: Option<ArchiveOpening>,
   /// Stack of previously visited directories (for back navigation)
   history_back: Vec<PathBuf>,
   /// Stack of directories to return to (for forward navigation)
@@ -186,24 +401,130 @@ pub struct App {
   pub dual_pane_mode: bool,
   pub active_pane: usize,
   pub right_pane: Option<Pane>,
+  /// Movement keys scroll the preview instead of moving the cursor
+  pub preview_focused: bool,
+  pub focus_order: Vec<FocusRegion>,
   pub dual_left_ratio: u16,
   pub dual_right_ratio: u16,
   pub file_properties: Option<FileProperties>,
+  /// Aggregate shown instead of `file_properties` when several entries are marked
+  pub multi_properties: Option<MultiProperties>,
   pub has_apps_file: bool,
   pub picker_mode: Option<PickerOutput>,
+  /// `--restrict`: navigation can't leave this directory
+  pub restrict_root: Option<PathBuf>,
   pub picked_paths: Vec<PathBuf>,
   pub use_trash: bool,
   pub tree_reloaded: bool,
   pub cached_visible: Vec<usize>,
   pub needs_redraw: bool,
+  pub template_rules: Vec<ContextRule>,
+  pub chmod_presets: Vec<ChmodPreset>,
+  pub template_choices: Vec<FileTemplate>,
+  pub template_cursor: usize,
+  pub worktree_choices: Vec<WorktreeChoice>,
+  pub worktree_cursor: usize,
+  /// Local branches in the branch switcher
+  pub branch_choices: Vec<Branch>,
+  pub branch_cursor: usize,
+  /// Branch checked out when the switcher opened; None when detached
+  pub branch_current: Option<String>,
+  /// Columns of the branch name in the header, for clicks
+  pub header_branch: Option<(u16, u16)>,
+  /// Where new worktrees go; next to the main checkout when unset
+  pub worktree_dir: Option<PathBuf>,
+  pub op_log: OpLog,
+  /// Today's operations shown by the operation log overlay, newest first
+  pub op_log_entries: Vec<OpRecord>,
+  pub op_log_cursor: usize,
+  pub dir_counts: DirCounts,
+  /// Sizes for the tree's size column, toggled with `toggle_size_column`
+  pub dir_sizes: DirSizes,
+  /// SELinux labels that differ from the policy default
+  pub labels: Labels,
+  /// Content to write when the pending new-file prompt was started from a template
+  pub pending_template: Option<String>,
+  /// Entry the pending symlink prompt links to
+  pub link_target: Option<PathBuf>,
+  /// Patch awaiting the path prompt to be saved
+  pub pending_patch: Option<PendingPatch>,
+  /// Journaled state from an unclean exit, awaiting the restore prompt
+  pub pending_session: Option<SessionState>,
+  /// Renames a paste needs for the destination filesystem, awaiting the
+  /// safe-names prompt
+  pub pending_safe_names: Option<SafeNamesPlan>,
+  /// A paste asking what to do with names already taken
+  pub paste_plan: Option<PastePlan>,
+  /// Targets and choices of the pending copy/move to prompt
+  pub destination: Option<DestinationPicker>,
+  /// Processes holding the targets of the pending delete/rename prompt open
+  pub prompt_holders: Vec<String>,
+  holders_rx: Option<mpsc::Receiver<Vec<String>>>,
+  /// Process umask, read once at startup
+  pub umask: u32,
+  /// Create the pending new file with execute bits
+  pub new_file_executable: bool,
+  pub quit_confirm: QuitConfirm,
+  pub safe_names: SafeNames,
+  pub esc_quits: bool,
+  pub print_last_dir: bool,
+  pub read_only: bool,
+  /// Paths to print on exit, set by `quit_print_selection`
+  pub exported_selection: Option<Vec<PathBuf>>,
+  /// When quit was first pressed under `QuitConfirm::Double`
+  quit_armed_at: Option<Instant>,
+  pub macros: Macros,
+  /// Last mutating operation, replayed by `repeat_last`
+  pub last_repeatable: Option<Repeatable>,
+  /// Completed file operations for `undo` / `redo`
+  pub history: History,
 }
 
 #[derive(Debug, Clone)]
 pub enum SuspendAction {
   Editor(PathBuf),
-  Claude(PathBuf, bool),
+  /// Expanded AI CLI command line (run through `sh`), working dir
+  Ai(String, PathBuf),
   Shell(PathBuf),
   OpenWith(String, PathBuf),
+  /// External diff command, repo root (working dir), file path relative to the repo root
+  DiffTool(String, PathBuf, PathBuf),
+}
+
+/// A paste, or a copy/move to a typed destination, about to run. Sources
+/// whose names are taken in `target_dir` are asked about one at a time
+/// before anything is copied.
+#[derive(Debug, Clone)]
+pub struct PastePlan {
+  pub sources: Vec<PathBuf>,
+  pub op: ClipboardOp,
+  pub target_dir: PathBuf,
+  pub options: CopyOptions,
+  /// Sources still to ask about, the one shown first
+  pub pending: Vec<PathBuf>,
+  /// Answers given so far
+  pub choices: HashMap<PathBuf, Collision>,
+  /// The next answer goes for every pending source
+  pub apply_to_all: bool,
+  /// Started from the copy/move to prompt rather than the clipboard
+  to_destination: bool,
+}
+
+impl PastePlan {
+  fn new(sources: Vec<PathBuf>, op: ClipboardOp, target_dir: PathBuf, options: CopyOptions, to_destination: bool) -> Self {
+    // Marks come in no particular order; ask in name order
+    let mut pending = jobs::collisions(&sources, &target_dir, options.sanitize);
+    pending.sort();
+    Self { sources, op, target_dir, options, pending, choices: HashMap::new(), apply_to_all: false, to_destination }
+  }
+}
+
+/// Names in a paste the destination filesystem would reject.
+#[derive(Debug, Clone)]
+pub struct SafeNamesPlan {
+  /// Filesystem type of the destination, e.g. "vfat"
+  pub fs_type: String,
+  pub changes: Vec<NameChange>,
 }
 
 #[derive(Debug, Clone)]
@@ -222,67 +543,284 @@ impl PickerOutput {
 }
 
 impl App {
+  #[cfg(test)]
   pub fn new(root: PathBuf, picker: Option<Picker>, config: &Config, picker_mode: Option<PickerOutput>) -> Result<Self> {
-    let mut tree = FileTree::with_ignore_patterns(root, config.ignore_glob_set.clone())?;
-    // Initialize custom ignore state from config
+    let tree = FileTree::with_ignore_patterns(root, config.ignore_glob_set.clone())?;
+    Self::with_tree(tree, picker, config, picker_mode)
+  }
+
+  /// Scans git status in the background so the first frame doesn't wait on
+  /// large repositories. Tests use the synchronous `new`.
+  pub fn new_deferred_git(root: PathBuf, picker: Option<Picker>, config: &Config, picker_mode: Option<PickerOutput>) -> Result<Self> {
+    let tree = FileTree::with_deferred_git(root, config.ignore_glob_set.clone())?;
+    Self::with_tree(tree, picker, config, picker_mode)
+  }
+
+  fn with_tree(mut tree: FileTree, picker: Option<Picker>, config: &Config, picker_mode: Option<PickerOutput>) -> Result<Self> {
+    // Initialize custom ignore state and sort order from config
     tree.show_custom_ignored = !config.use_custom_ignore;
+    tree.show_hidden = config.show_hidden;
+    if !config.dirs_first || !config.pinned_patterns.is_empty() {
+      tree.set_sort(config.dirs_first, config.pinned_glob_set.clone());
+    }
+    if config.show_hidden || !config.dirs_first || !config.pinned_patterns.is_empty() {
+      tree.reload()?;
+    }
     let breadcrumb_segments = parse_breadcrumb_segments(&tree.root);
     let cached_visible = (0..tree.entries.len()).collect();
+    let mut preview = PreviewState::new(&config.syntax_theme, config.theme.clone());
+    preview.thumbnails = config.image_thumbnails;
+    preview.timeout = Duration::from_millis(config.preview_timeout_ms);
     Ok(Self {
       tree,
       cursor: 0,
-      preview: PreviewState::new(&config.syntax_theme, config.theme.clone()),
+      preview,
       picker,
       input_mode: InputMode::Normal,
-      search_query: String::new(),
+      search: LineEditor::default(),
       tree_ratio: config.tree_ratio,
       min_tree_ratio: config.min_tree_ratio,
       max_tree_ratio: config.max_tree_ratio,
       ratio_step: config.ratio_step,
       show_help: false,
+      show_debug: false,
+      loop_stats: LoopStats::default(),
       help_scroll: 0,
       should_quit: false,
       should_suspend: None,
+      should_stop: false,
       status_message: None,
       status_ticks: 0,
       viewport_height: 20,
+      tree_area: Rect::default(),
+      right_tree_area: None,
+      hover: None,
+      mouse_capture: config.mouse,
+      icons: config.icons,
+      git_markers: config.git_markers,
+      git_refreshed: Instant::now(),
+      theme_name: config.theme_name.clone(),
+      requested_theme: None,
+      settings: None,
+      hover_preview_ms: config.hover_preview_ms,
+      auto_preview: config.auto_preview,
       tree_scroll_offset: 0,
       clipboard: Clipboard { paths: Vec::new(), op: None },
+      copy_options: config.copy_options,
+      paste_options: None,
       marked: HashSet::new(),
       prompt_kind: None,
-      prompt_input: String::new(),
-      prompt_cursor: 0,
+      prompt: LineEditor::default(),
+      input_history: InputHistory::new(config.history_size),
       favorites: Favorites::load(),
       favorites_cursor: 0,
+      layouts: Layouts::load(),
+      bookmarks: Bookmarks::load(),
+      breadcrumb_menu: None,
+      hyperlinks: Vec::new(),
+      breadcrumb_menu_area: None,
+      layouts_cursor: 0,
       open_with_apps: Vec::new(),
       open_with_cursor: 0,
+      urls: Vec::new(),
+      urls_cursor: 0,
+      doctor: Vec::new(),
+      preview_rows: 0,
       custom_apps: config.custom_apps.clone(),
+      runner: Arc::new(SystemRunner),
       error_messages: Vec::new(),
-      wrote_config: false,
+      state_writer: StateWriter::default(),
+      journal: None,
       claude_yolo: config.claude_yolo,
-      extracting: None,
-      compressing: None,
+      ai_command: config.ai_command.clone(),
+      ai_command_alt: config.ai_command_alt.clone(),
+      tmux_window_command: config.tmux_window_command.clone(),
+      tmux_pane_command: config.tmux_pane_command.clone(),
+      zellij_tab_command: config.zellij_tab_command.clone(),
+      terminal_command: config.terminal_command.clone(),
+      graphical_session: opener::graphical_session(),
+      difftool: config.difftool.clone(),
+      jobs: Jobs::new(config.max_jobs),
+      jobs_cursor: 0,
+      delete_summary: None,
       chmod_state: ChmodState::default(),
+      archive_view: None,
+      archive_opening: None,
       history_back: Vec::new(),
       history_forward: Vec::new(),
       breadcrumb_segments,
       breadcrumb_truncated: false,
       dual_pane_mode: false,
       active_pane: 0,
+      preview_focused: false,
+      focus_order: config.focus_order.clone(),
       right_pane: None,
       dual_left_ratio: config.tree_ratio,
       dual_right_ratio: config.tree_ratio,
       file_properties: None,
+      multi_properties: None,
       has_apps_file: config.has_apps_file,
       picker_mode,
+      restrict_root: None,
       picked_paths: Vec::new(),
       use_trash: config.use_trash,
       tree_reloaded: false,
       cached_visible,
       needs_redraw: true,
+      template_rules: config.template_rules.clone(),
+      chmod_presets: config.chmod_presets.clone(),
+      quit_confirm: config.quit_confirm,
+      safe_names: config.safe_names,
+      esc_quits: config.esc_quits,
+      print_last_dir: config.print_last_dir,
+      read_only: config.read_only,
+      exported_selection: None,
+      quit_armed_at: None,
+      macros: Macros::default(),
+      last_repeatable: None,
+      history: History::default(),
+      template_choices: Vec::new(),
+      template_cursor: 0,
+      worktree_choices: Vec::new(),
+      worktree_cursor: 0,
+      branch_choices: Vec::new(),
+      branch_cursor: 0,
+      branch_current: None,
+      header_branch: None,
+      worktree_dir: config.worktree_dir.clone(),
+      op_log: OpLog::new(OpLog::default_path(), config.op_log),
+      op_log_entries: Vec::new(),
+      op_log_cursor: 0,
+      dir_counts: DirCounts::new(config.dir_counts, 1),
+      dir_sizes: DirSizes::new(false, dir_sizes::WORKERS),
+      labels: Labels::new(selinux::enabled(), 1),
+      pending_template: None,
+      link_target: None,
+      pending_patch: None,
+      pending_session: None,
+      pending_safe_names: None,
+      paste_plan: None,
+      destination: None,
+      prompt_holders: Vec::new(),
+      holders_rx: None,
+      umask: ops::current_umask(),
+      new_file_executable: false,
     })
   }
 
+  /// Permissions the entry being named in a new-file/new-dir prompt will get.
+  pub fn new_entry_mode(&self) -> Option<u32> {
+    match self.prompt_kind {
+      Some(PromptKind::NewFile) => {
+        let mode = ops::default_mode(false, self.umask);
+        Some(if self.new_file_executable { ops::with_execute(mode) } else { mode })
+      }
+      Some(PromptKind::NewDir) => Some(ops::default_mode(true, self.umask)),
+      _ => None,
+    }
+  }
+
+  pub fn session_state(&self) -> SessionState {
+    let mut marked: Vec<PathBuf> = self.marked.iter().cloned().collect();
+    marked.sort();
+    SessionState {
+      root: self.tree.root.clone(),
+      expanded: self.tree.entries.iter().filter(|e| e.expanded).map(|e| e.path.clone()).collect(),
+      selected: self.selected_entry().map(|e| e.path.clone()),
+      marked,
+      clipboard: self.clipboard.paths.clone(),
+      clipboard_cut: self.clipboard.op == Some(ClipboardOp::Cut),
+      show_hidden: self.tree.show_hidden,
+      right_root: self.right_pane.as_ref().filter(|_| self.dual_pane_mode).map(|p| p.tree.root.clone()),
+    }
+  }
+
+  /// Ask whether to restore `state`, unless it matches what is already shown.
+  pub fn offer_session_restore(&mut self, state: SessionState) {
+    if state.root == self.tree.root && state.marked.is_empty() && state.clipboard.is_empty() {
+      return;
+    }
+    self.pending_session = Some(state);
+    self.ask(PromptKind::ConfirmRestoreSession);
+  }
+
+  fn restore_session(&mut self) -> Result<()> {
+    let Some(state) = self.pending_session.take() else {
+      return Ok(());
+    };
+    self.restore_view(&state)?;
+    self.marked = state.marked.into_iter().filter(|p| p.exists()).collect();
+    let clipboard: Vec<PathBuf> = state.clipboard.into_iter().filter(|p| p.exists()).collect();
+    self.clipboard = if clipboard.is_empty() {
+      Clipboard { paths: Vec::new(), op: None }
+    } else {
+      let op = if state.clipboard_cut { ClipboardOp::Cut } else { ClipboardOp::Copy };
+      Clipboard { paths: clipboard, op: Some(op) }
+    };
+    tracing::info!(root = %self.tree.root.display(), "session restored");
+    self.set_status("Session restored".to_string());
+    Ok(())
+  }
+
+  /// Bring back the session tfl was last quit in, for `start_in =
+  /// "session"`. Marks and the clipboard start fresh.
+  pub fn resume_session(&mut self, state: &SessionState) -> Result<()> {
+    self.restore_view(state)?;
+    self.status_message = None;
+    Ok(())
+  }
+
+  /// Show `state`'s tree, hidden files, second pane and selection.
+  fn restore_view(&mut self, state: &SessionState) -> Result<()> {
+    if state.show_hidden != self.tree.show_hidden {
+      self.tree.toggle_hidden()?;
+    }
+    self.restore_tree(&state.root, &state.expanded)?;
+    if let Some(ref right) = state.right_root
+      && right.is_dir()
+      && !self.restricted(right)
+    {
+      if !self.dual_pane_mode {
+        self.toggle_dual_pane()?;
+      }
+      if let Some(ref mut pane) = self.right_pane
+        && pane.tree.root != *right
+      {
+        pane.tree.navigate_to(right)?;
+        pane.rebuild_visible_cache();
+      }
+    }
+    if let Some(ref selected) = state.selected {
+      self.reposition_cursor_to(selected);
+    }
+    self.preview.invalidate();
+    self.update_preview();
+    Ok(())
+  }
+
+  /// Go to `root` unless it is already shown, then expand the directories
+  /// in `expanded` that still exist.
+  fn restore_tree(&mut self, root: &Path, expanded: &[PathBuf]) -> Result<()> {
+    if root != self.tree.root && root.is_dir() && !self.restricted(root) {
+      self.push_history(self.tree.root.clone());
+      self.navigate_tree_to(root)?;
+      self.search.clear();
+      self.cursor = 0;
+      self.tree_scroll_offset = 0;
+    }
+    let mut i = 0;
+    while i < self.tree.entries.len() {
+      let entry = &self.tree.entries[i];
+      if entry.is_dir && !entry.expanded && expanded.contains(&entry.path) {
+        self.tree.toggle_expand(i)?;
+      }
+      i += 1;
+    }
+    self.rebuild_visible_cache();
+    self.update_breadcrumbs();
+    Ok(())
+  }
+
   pub fn active_marks(&self) -> &HashSet<PathBuf> {
     if self.dual_pane_mode && self.active_pane == 1
       && let Some(ref pane) = self.right_pane
@@ -292,6 +830,16 @@ impl App {
     &self.marked
   }
 
+  /// Marked paths of both panes, sorted.
+  pub fn all_marks(&self) -> Vec<PathBuf> {
+    let mut marks: Vec<PathBuf> = self.marked.iter().cloned().collect();
+    if let Some(ref pane) = self.right_pane {
+      marks.extend(pane.marked.iter().filter(|p| !self.marked.contains(*p)).cloned());
+    }
+    marks.sort();
+    marks
+  }
+
   fn active_marks_mut(&mut self) -> &mut HashSet<PathBuf> {
     if self.dual_pane_mode && self.active_pane == 1
       && let Some(ref mut pane) = self.right_pane
@@ -301,6 +849,15 @@ impl App {
     &mut self.marked
   }
 
+  fn active_tree(&self) -> &FileTree {
+    if self.dual_pane_mode && self.active_pane == 1
+      && let Some(ref pane) = self.right_pane
+    {
+      return &pane.tree;
+    }
+    &self.tree
+  }
+
   pub fn operation_targets(&self) -> Vec<PathBuf> {
     let marks = self.active_marks();
     if !marks.is_empty() {
@@ -318,10 +875,140 @@ impl App {
   }
 
   pub fn update(&mut self, action: Action) -> Result<()> {
+    if !matches!(action, Action::Tick | Action::Resize(..)) {
+      self.hover = None;
+    }
+    self.macros.record(&action);
+    self.run(action)
+  }
+
+  /// Run a command, refusing ones that modify files in read-only mode.
+  pub fn run(&mut self, mut command: impl Command) -> Result<()> {
+    if self.read_only
+      && let Some(what) = command.mutation()
+    {
+      self.quit_armed_at = None;
+      self.set_status(format!("Read-only mode: {what} is disabled"));
+      return Ok(());
+    }
+    // An archive's entries can only be extracted
+    if self.archive_view.is_some()
+      && let Some(what) = command.mutation()
+      && what != "extract"
+    {
+      self.quit_armed_at = None;
+      self.set_status(format!("Inside an archive: {what} is not available"));
+      return Ok(());
+    }
+    match command.confirmation(self) {
+      Ok(Some(kind)) => self.ask(kind),
+      Ok(None) => command.execute(self)?,
+      Err(message) => self.set_status(message),
+    }
+    Ok(())
+  }
+
+  /// Open the y/N prompt a command asked for. Answering `y` carries it out.
+  pub fn ask(&mut self, kind: PromptKind) {
+    self.prompt_kind = Some(kind);
+    self.prompt.clear();
+    self.input_mode = InputMode::Prompt;
+    match kind {
+      PromptKind::ConfirmDeleteMulti(count) => {
+        let targets = self.operation_targets();
+        self.delete_summary = Some(DeleteSummary::start(targets.clone()));
+        self.find_holders(targets);
+        self.set_status(format!("Delete {count} items? (y/N)"));
+      }
+      PromptKind::ConfirmDelete => {
+        if let Some(entry) = self.selected_entry() {
+          let name = entry.name.clone();
+          let path = entry.path.clone();
+          let is_dir = entry.is_dir && !entry.is_symlink;
+          self.delete_summary = is_dir.then(|| DeleteSummary::start(vec![path.clone()]));
+          self.find_holders(vec![path]);
+          self.set_status(format!("Delete {name}? (y/N)"));
+        }
+      }
+      PromptKind::ConfirmExtractAndDelete => {
+        if let Some(entry) = self.selected_entry() {
+          self.set_status(format!("Extract and delete {}? (y/N)", entry.name));
+        }
+      }
+      _ => {}
+    }
+  }
+
+  /// Look up the processes holding `paths` open on a worker, for the
+  /// prompt about to change them.
+  fn find_holders(&mut self, paths: Vec<PathBuf>) {
+    self.prompt_holders.clear();
+    self.holders_rx = Some(locks::holders_async(paths));
+  }
+
+  /// Show the processes found holding the prompt's targets open.
+  fn check_holders_found(&mut self) -> bool {
+    let Some(holders) = self.holders_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+      return false;
+    };
+    self.holders_rx = None;
+    self.prompt_holders = holders;
+    true
+  }
+
+  /// The prompt deleting the targets asks, or None with nothing selected.
+  pub fn delete_confirmation(&self) -> Option<PromptKind> {
+    match self.active_marks().len() {
+      count if count > 1 => Some(PromptKind::ConfirmDeleteMulti(count)),
+      _ => self.selected_entry().map(|_| PromptKind::ConfirmDelete),
+    }
+  }
+
+  /// The prompt discarding the targets' changes asks, or why it can't.
+  pub fn git_discard_confirmation(&self) -> Result<Option<PromptKind>, String> {
+    match self.operation_targets().len() {
+      0 => Ok(None),
+      _ if self.active_tree().git_repo().is_none() => Err("Not in a git repository".to_string()),
+      count => Ok(Some(PromptKind::ConfirmGitDiscard(count))),
+    }
+  }
+
+  /// The prompt extracting and deleting the selected archive asks, or why
+  /// it can't.
+  pub fn extract_and_delete_confirmation(&self) -> Result<Option<PromptKind>, String> {
+    match self.selected_entry() {
+      None => Ok(None),
+      Some(entry) if !archive::is_archive(&entry.path) => Err("Not an archive file".to_string()),
+      Some(_) => Ok(Some(PromptKind::ConfirmExtractAndDelete)),
+    }
+  }
+
+  /// Carry out an action. Goes through `run` from `update`; called
+  /// directly only for follow-up actions of one that already passed.
+  pub fn dispatch(&mut self, action: Action) -> Result<()> {
+    if !matches!(action, Action::Quit | Action::Escape | Action::Tick | Action::Resize(..)) {
+      self.quit_armed_at = None;
+    }
     match action {
-      Action::Quit => self.should_quit = true,
+      Action::Quit => self.request_quit(),
+      Action::Escape => self.escape()?,
+      Action::QuitPrintSelection => {
+        let marks = self.all_marks();
+        self.exported_selection = Some(if marks.is_empty() { self.operation_targets() } else { marks });
+        self.should_quit = true;
+      }
+      Action::MoveDown | Action::MoveUp | Action::MoveBy(_) | Action::PageDown | Action::PageUp
+        if self.preview_focused =>
+      {
+        self.scroll_focused_preview(&action);
+      }
+      Action::MoveLeft if self.preview_focused => self.preview.scroll_left(PREVIEW_H_SCROLL),
+      Action::MoveRight if self.preview_focused => self.preview.scroll_right(PREVIEW_H_SCROLL),
       Action::MoveDown => self.move_cursor(1),
       Action::MoveUp => self.move_cursor(-1),
+      Action::MoveBy(delta) => self.move_cursor(delta),
+      Action::PageDown => self.move_cursor(self.page_size()),
+      Action::PageUp => self.move_cursor(-self.page_size()),
       Action::ToggleExpand => self.enter_or_expand()?,
       Action::MoveRight => {
         if self.picker_mode.as_ref().is_some_and(PickerOutput::picks_directories) {
@@ -352,7 +1039,27 @@ impl App {
           self.preview.scroll_up(3);
         }
       }
+      Action::ScrollPreviewLeft => self.preview.scroll_left(PREVIEW_H_SCROLL),
+      Action::ScrollPreviewRight => self.preview.scroll_right(PREVIEW_H_SCROLL),
+      Action::ToggleWrap => {
+        self.preview.toggle_wrap();
+        self.set_status(format!("Wrap long lines: {}", if self.preview.wrap { "on" } else { "off" }));
+      }
       Action::ToggleHidden => self.toggle_hidden()?,
+      Action::ToggleDirsFirst => self.toggle_dirs_first()?,
+      Action::ToggleSizeColumn => {
+        self.dir_sizes.enabled = !self.dir_sizes.enabled;
+        self.set_status(if self.dir_sizes.enabled { "Size column on" } else { "Size column off" }.to_string());
+      }
+      Action::SuspendProcess => self.should_stop = true,
+      Action::ToggleMouse => {
+        self.mouse_capture = !self.mouse_capture;
+        self.set_status(if self.mouse_capture {
+          "Mouse on".to_string()
+        } else {
+          "Mouse off: select text with the terminal (M turns it back on)".to_string()
+        });
+      }
       Action::ToggleFormatted => {
         if self.preview.toggle_formatted() {
           let mode = if self.preview.show_formatted { "formatted" } else { "raw" };
@@ -391,36 +1098,40 @@ impl App {
       }
       Action::SearchStart => {
         self.input_mode = InputMode::Search;
+        self.input_history.reset();
         if self.dual_pane_mode && self.active_pane == 1 {
           if let Some(ref mut pane) = self.right_pane {
-            pane.search_query.clear();
+            pane.search.clear();
           }
         } else {
-          self.search_query.clear();
+          self.search.clear();
         }
       }
       Action::SearchInput(c) => {
         if self.dual_pane_mode && self.active_pane == 1 {
           if let Some(ref mut pane) = self.right_pane {
-            pane.search_query.push(c);
+            pane.search.insert(c);
           }
         } else {
-          self.search_query.push(c);
+          self.search.insert(c);
         }
         self.apply_search_filter();
       }
       Action::SearchBackspace => {
         if self.dual_pane_mode && self.active_pane == 1 {
           if let Some(ref mut pane) = self.right_pane {
-            pane.search_query.pop();
+            pane.search.backspace();
           }
         } else {
-          self.search_query.pop();
+          self.search.backspace();
         }
         self.apply_search_filter();
       }
       Action::SearchConfirm => {
         self.input_mode = InputMode::Normal;
+        let query = self.active_search_mut().text.clone();
+        self.input_history.record(HistoryKind::Search, &query);
+        self.state_writer.mark(StateFile::History);
         // Enter directory while filter is still active so cursor resolves correctly
         if self.picker_mode.is_some() {
           self.pick_file()?;
@@ -430,11 +1141,11 @@ impl App {
         // Clear query for non-dir entries (enter_directory already clears for dirs)
         if self.dual_pane_mode && self.active_pane == 1 {
           if let Some(ref mut pane) = self.right_pane {
-            pane.search_query.clear();
+            pane.search.clear();
             pane.rebuild_visible_cache();
           }
         } else {
-          self.search_query.clear();
+          self.search.clear();
           self.rebuild_visible_cache();
         }
       }
@@ -442,11 +1153,11 @@ impl App {
         self.input_mode = InputMode::Normal;
         if self.dual_pane_mode && self.active_pane == 1 {
           if let Some(ref mut pane) = self.right_pane {
-            pane.search_query.clear();
+            pane.search.clear();
             pane.rebuild_visible_cache();
           }
         } else {
-          self.search_query.clear();
+          self.search.clear();
           self.rebuild_visible_cache();
         }
       }
@@ -457,18 +1168,10 @@ impl App {
             self.should_suspend = Some(SuspendAction::Editor(entry.path.clone()));
           }
       }
-      Action::OpenClaude => {
-        let dir = self.current_dir();
-        self.should_suspend = Some(SuspendAction::Claude(dir, self.claude_yolo));
-      }
-      Action::OpenClaudeAlt => {
-        let dir = self.current_dir();
-        self.should_suspend = Some(SuspendAction::Claude(dir, !self.claude_yolo));
-      }
-      Action::OpenShell => {
-        let dir = self.current_dir();
-        self.should_suspend = Some(SuspendAction::Shell(dir));
-      }
+      Action::OpenClaude => self.open_ai(false),
+      Action::OpenClaudeAlt => self.open_ai(true),
+      Action::OpenShell => self.open_shell(),
+      Action::TmuxWindow | Action::TmuxPane | Action::ZellijTab => self.open_in_multiplexer(&action),
       Action::ShrinkTree => {
         if self.dual_pane_mode {
           if self.active_pane == 0 {
@@ -509,50 +1212,100 @@ impl App {
         }
         self.input_mode = if self.show_help { InputMode::Help } else { InputMode::Normal };
       }
+      Action::ReloadPreview if !self.auto_preview => {
+        self.preview.invalidate();
+        self.load_preview();
+      }
+      Action::ReloadPreview => {
+        self.refresh_preview();
+        self.set_status("Preview reloaded".to_string());
+      }
+      Action::ToggleDebugOverlay => {
+        self.show_debug = !self.show_debug;
+      }
       Action::ToggleBlame => {
         self.preview.toggle_blame(self.tree.git_repo());
       }
+      Action::ToggleFollow => self.toggle_follow(),
+      Action::ToggleLineNumbers => {
+        let scroll = self.preview.scroll_offset;
+        self.preview.toggle_line_numbers();
+        self.refresh_preview();
+        self.preview.scroll_offset = scroll;
+        self.preview.scroll_down(0);
+        let state = if self.preview.line_numbers() { "on" } else { "off" };
+        self.set_status(format!("Line numbers: {state}"));
+      }
+      Action::PreviewGotoLine => {
+        if self.preview.get_content().is_some() {
+          self.prompt.clear();
+          self.prompt_kind = Some(PromptKind::GotoLine);
+          self.input_mode = InputMode::Prompt;
+        }
+      }
       Action::CutFile => self.cut_file(),
       Action::CopyFile => self.copy_file(),
       Action::Paste => self.paste_clipboard()?,
-      Action::DeleteFile => {
-        let marks = self.active_marks();
-        if marks.len() > 1 {
-          let count = marks.len();
-          self.prompt_kind = Some(PromptKind::ConfirmDeleteMulti(count));
-          self.prompt_input.clear();
-          self.prompt_cursor = 0;
-          self.input_mode = InputMode::Prompt;
-          self.set_status(format!("Delete {count} items? (y/N)"));
-        } else if let Some(entry) = self.selected_entry() {
-          let name = entry.name.clone();
-          self.prompt_kind = Some(PromptKind::ConfirmDelete);
-          self.prompt_input.clear();
-          self.prompt_cursor = 0;
-          self.input_mode = InputMode::Prompt;
-          self.set_status(format!("Delete {name}? (y/N)"));
+      Action::PasteWithOptions => self.paste_options_open(),
+      Action::Restorecon => self.restorecon(),
+      Action::PasteOptionsToggle(option) => self.paste_options_toggle(option),
+      Action::PasteOptionsConfirm => {
+        self.input_mode = InputMode::Normal;
+        self.paste_clipboard()?;
+      }
+      Action::PasteOptionsClose => {
+        self.paste_options = None;
+        self.input_mode = InputMode::Normal;
+      }
+      Action::PasteConflictChoose(choice) => self.paste_conflict_choose(choice)?,
+      Action::PasteConflictAll => {
+        if let Some(ref mut plan) = self.paste_plan {
+          plan.apply_to_all = !plan.apply_to_all;
         }
       }
+      Action::PasteConflictCancel => {
+        self.paste_plan = None;
+        self.input_mode = InputMode::Normal;
+        self.set_status("Paste cancelled".to_string());
+      }
+      Action::GitStage => self.git_stage(false)?,
+      Action::GitUnstage => self.git_stage(true)?,
+      Action::CopyToStart => self.transfer_start(false),
+      Action::MoveToStart => self.transfer_start(true),
+      // Asked for in `run` through `Command::confirmation`, which leaves
+      // only the cases with nothing to act on
+      Action::DeleteFile | Action::GitDiscard | Action::ExtractAndDelete | Action::StageHunk | Action::RevertHunk => {}
       Action::RenameStart => {
         if self.active_marks().len() > 1 {
           self.set_status("Cannot rename multiple files".to_string());
         } else if let Some(entry) = self.selected_entry() {
-          self.prompt_input = entry.name.clone();
-          self.prompt_cursor = self.prompt_input.chars().count();
+          let path = entry.path.clone();
+          self.prompt.set(entry.name.clone());
+          self.find_holders(vec![path]);
           self.prompt_kind = Some(PromptKind::Rename);
+          self.input_history.reset();
           self.input_mode = InputMode::Prompt;
         }
       }
       Action::NewFileStart => {
-        self.prompt_input.clear();
-        self.prompt_cursor = 0;
+        self.prompt.clear();
         self.prompt_kind = Some(PromptKind::NewFile);
+        self.input_history.reset();
         self.input_mode = InputMode::Prompt;
       }
       Action::NewDirStart => {
-        self.prompt_input.clear();
-        self.prompt_cursor = 0;
+        self.prompt.clear();
         self.prompt_kind = Some(PromptKind::NewDir);
+        self.input_history.reset();
+        self.input_mode = InputMode::Prompt;
+      }
+      Action::CreateSymlink => {
+        let Some(target) = self.selected_entry().map(|e| e.path.clone()) else {
+          return Ok(());
+        };
+        self.link_target = Some(target);
+        self.prompt.clear();
+        self.prompt_kind = Some(PromptKind::Symlink);
         self.input_mode = InputMode::Prompt;
       }
       Action::PromptInput(c) => {
@@ -581,65 +1334,93 @@ impl App {
               self.set_status("Extract cancelled".to_string());
             }
           }
-          Some(_) => {
-            let byte_pos = self.prompt_input.char_indices()
-              .nth(self.prompt_cursor)
-              .map(|(i, _)| i)
-              .unwrap_or(self.prompt_input.len());
-            self.prompt_input.insert(byte_pos, c);
-            self.prompt_cursor += 1;
+          Some(PromptKind::ConfirmHunk(action, hunk)) => {
+            self.cancel_prompt();
+            if c == 'y' {
+              self.execute_hunk_action(action, hunk)?;
+            } else {
+              self.set_status(format!("{} cancelled", action.verb()));
+            }
+          }
+          Some(PromptKind::ConfirmGitDiscard(_)) => {
+            self.cancel_prompt();
+            if c == 'y' {
+              self.execute_git_discard()?;
+            } else {
+              self.set_status("Discard cancelled".to_string());
+            }
+          }
+          Some(PromptKind::ConfirmQuit) => {
+            self.cancel_prompt();
+            if c == 'y' || c == 'q' {
+              self.should_quit = true;
+            }
+          }
+          Some(PromptKind::ConfirmRestoreSession) => {
+            self.cancel_prompt();
+            if c == 'y' {
+              self.restore_session()?;
+            } else {
+              self.pending_session = None;
+            }
+          }
+          Some(PromptKind::ConfirmSafeNames) => {
+            // The paste options dialog's choices outlive the prompt
+            let options = self.paste_options.take();
+            self.cancel_prompt();
+            if c == 'y' {
+              self.paste_options = options;
+              self.execute_paste(true)?;
+            } else {
+              self.set_status("Paste cancelled".to_string());
+            }
           }
+          Some(_) => self.prompt.insert(c),
           None => {}
         }
       }
       Action::PromptBackspace => {
-        let is_confirm = matches!(
-          self.prompt_kind,
-          Some(PromptKind::ConfirmDelete) | Some(PromptKind::ConfirmDeleteMulti(_)) | Some(PromptKind::ConfirmExtractAndDelete)
-        );
-        if !is_confirm && self.prompt_cursor > 0 {
-          let byte_pos = self.prompt_input.char_indices()
-            .nth(self.prompt_cursor - 1)
-            .map(|(i, _)| i)
-            .unwrap_or(0);
-          self.prompt_input.remove(byte_pos);
-          self.prompt_cursor -= 1;
+        if self.prompt_kind.is_some_and(PromptKind::takes_text) {
+          self.prompt.backspace();
         }
       }
       Action::PromptDelete => {
-        let is_confirm = matches!(
-          self.prompt_kind,
-          Some(PromptKind::ConfirmDelete) | Some(PromptKind::ConfirmDeleteMulti(_)) | Some(PromptKind::ConfirmExtractAndDelete)
-        );
-        if !is_confirm && self.prompt_cursor < self.prompt_input.chars().count()
-        {
-          let byte_pos = self.prompt_input.char_indices()
-            .nth(self.prompt_cursor)
-            .map(|(i, _)| i)
-            .unwrap_or(self.prompt_input.len());
-          self.prompt_input.remove(byte_pos);
+        if self.prompt_kind.is_some_and(PromptKind::takes_text) {
+          self.prompt.delete();
         }
       }
-      Action::PromptLeft => {
-        self.prompt_cursor = self.prompt_cursor.saturating_sub(1);
-      }
-      Action::PromptRight => {
-        let len = self.prompt_input.chars().count();
-        if self.prompt_cursor < len {
-          self.prompt_cursor += 1;
+      Action::PromptLeft => self.prompt.left(),
+      Action::PromptRight => self.prompt.right(),
+      Action::PromptHome => self.prompt.home(),
+      Action::PromptEnd => self.prompt.end(),
+      Action::LineEdit(edit) => self.line_edit(edit),
+      Action::InsertText(text) => self.insert_text(&text),
+      Action::InputHistoryPrev => self.recall_history(true),
+      Action::InputHistoryNext => self.recall_history(false),
+      Action::PromptTab => match self.prompt_kind {
+        Some(PromptKind::NewFile) => self.new_file_executable = !self.new_file_executable,
+        Some(PromptKind::CopyTo | PromptKind::MoveTo) => {
+          if let Some(text) = destination::complete(&self.prompt.text, &self.active_tree().root) {
+            self.prompt.set(text);
+          }
         }
-      }
-      Action::PromptHome => {
-        self.prompt_cursor = 0;
-      }
-      Action::PromptEnd => {
-        self.prompt_cursor = self.prompt_input.chars().count();
-      }
+        _ => {}
+      },
       Action::PromptConfirm => {
+        if let Some(kind) = self.prompt_kind.and_then(HistoryKind::of_prompt) {
+          self.input_history.record(kind, &self.prompt.text);
+          self.state_writer.mark(StateFile::History);
+        }
         match self.prompt_kind {
           Some(PromptKind::Rename) => self.execute_rename()?,
           Some(PromptKind::NewFile) => self.execute_new_file()?,
           Some(PromptKind::NewDir) => self.execute_new_dir()?,
+          Some(PromptKind::Symlink) => self.execute_symlink()?,
+          Some(PromptKind::PatchPath) => self.save_patch()?,
+          Some(PromptKind::NewBranch) => self.execute_new_branch()?,
+          Some(PromptKind::SaveLayout) => self.save_layout(),
+          Some(PromptKind::GotoLine) => self.goto_line(),
+          Some(PromptKind::CopyTo | PromptKind::MoveTo) => self.execute_transfer()?,
           Some(PromptKind::ConfirmDelete) => {
             self.cancel_prompt();
             self.set_status("Delete cancelled".to_string());
@@ -652,11 +1433,29 @@ impl App {
             self.cancel_prompt();
             self.set_status("Extract cancelled".to_string());
           }
+          Some(PromptKind::ConfirmHunk(action, _)) => {
+            self.cancel_prompt();
+            self.set_status(format!("{} cancelled", action.verb()));
+          }
+          Some(PromptKind::ConfirmGitDiscard(_)) => {
+            self.cancel_prompt();
+            self.set_status("Discard cancelled".to_string());
+          }
+          Some(PromptKind::ConfirmRestoreSession) => {
+            self.cancel_prompt();
+            self.pending_session = None;
+          }
+          Some(PromptKind::ConfirmSafeNames) => {
+            self.cancel_prompt();
+            self.set_status("Paste cancelled".to_string());
+          }
+          Some(PromptKind::ConfirmQuit) => self.cancel_prompt(),
           None => {}
         }
       }
       Action::PromptCancel => {
         self.cancel_prompt();
+        self.pending_session = None;
       }
       Action::Resize(_, h) => {
         self.viewport_height = h.saturating_sub(4) as usize;
@@ -670,6 +1469,53 @@ impl App {
       Action::FavoritesClose => self.favorites_close(),
       Action::FavoritesRemove => self.favorites_remove(),
       Action::FavoritesAddCurrent => self.favorites_add_current(),
+      Action::LayoutSaveStart => {
+        let name = self.tree.root.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        self.prompt.set(name);
+        self.prompt_kind = Some(PromptKind::SaveLayout);
+        self.input_mode = InputMode::Prompt;
+      }
+      Action::LayoutsOpen => {
+        self.input_mode = InputMode::Layouts;
+        self.layouts_cursor = 0;
+      }
+      Action::LayoutsDown => {
+        self.layouts_cursor = (self.layouts_cursor + 1).min(self.layouts.len().saturating_sub(1));
+      }
+      Action::LayoutsUp => self.layouts_cursor = self.layouts_cursor.saturating_sub(1),
+      Action::LayoutsSelect => self.layouts_select()?,
+      Action::LayoutsRemove => {
+        if let Some(layout) = self.layouts.get(self.layouts_cursor) {
+          let name = layout.name.clone();
+          self.layouts.remove(self.layouts_cursor);
+          self.state_writer.mark(StateFile::Layouts);
+          self.layouts_cursor = self.layouts_cursor.min(self.layouts.len().saturating_sub(1));
+          self.set_status(format!("Removed layout {name}"));
+        }
+      }
+      Action::LayoutsClose => self.input_mode = InputMode::Normal,
+      Action::SettingsOpen => {
+        self.settings = Some(SettingsEditor::new(self.current_settings()));
+        self.input_mode = InputMode::Settings;
+      }
+      Action::SettingsDown => {
+        if let Some(ref mut editor) = self.settings {
+          editor.move_down();
+        }
+      }
+      Action::SettingsUp => {
+        if let Some(ref mut editor) = self.settings {
+          editor.move_up();
+        }
+      }
+      Action::SettingsChange(forward) => self.settings_change(forward)?,
+      Action::SettingsSave => self.settings_save(),
+      Action::SettingsClose => {
+        self.input_mode = InputMode::Normal;
+        if let Some(editor) = self.settings.take() {
+          self.apply_settings(&editor.original)?;
+        }
+      }
       Action::OpenDefault => self.open_default_action()?,
       Action::OpenWithStart => self.open_with_start(),
       Action::OpenWithDown => self.open_with_move(1),
@@ -678,25 +1524,67 @@ impl App {
       Action::OpenWithClose => {
         self.input_mode = InputMode::Normal;
       }
+      Action::UrlsOpen => self.urls_open(),
+      Action::UrlsDown => self.urls_cursor = (self.urls_cursor + 1).min(self.urls.len().saturating_sub(1)),
+      Action::UrlsUp => self.urls_cursor = self.urls_cursor.saturating_sub(1),
+      Action::UrlsSelect => self.urls_select(self.urls_cursor),
+      Action::UrlsPick(index) => self.urls_select(index),
+      Action::UrlsClose => {
+        self.input_mode = InputMode::Normal;
+        self.urls.clear();
+      }
+      Action::DoctorOpen => self.doctor_open(),
+      Action::DoctorClose => {
+        self.input_mode = InputMode::Normal;
+        self.doctor.clear();
+      }
       Action::ErrorClose => {
         self.error_messages.clear();
         self.input_mode = InputMode::Normal;
       }
       Action::ExtractArchive => self.extract_archive_start(false)?,
-      Action::ExtractAndDelete => self.extract_archive_start_confirm()?,
       Action::ChmodStart => self.chmod_start(),
       Action::ChmodToggleBit(bit) => self.chmod_toggle_bit(bit),
       Action::ChmodDigit(c) => self.chmod_digit(c),
       Action::ChmodOctalBackspace => self.chmod_octal_backspace(),
       Action::ChmodToggleOctal => self.chmod_toggle_octal(),
       Action::ChmodToggleRecursive => self.chmod_toggle_recursive(),
+      Action::ChmodToggleFollowSymlinks => self.chmod_toggle_follow_symlinks(),
+      Action::ChmodPreset(index) => self.chmod_apply_preset(index)?,
       Action::ChmodApply => self.chmod_apply()?,
       Action::ChmodClose => self.chmod_close(),
       Action::ToggleCustomIgnore => self.toggle_custom_ignore()?,
       Action::HistoryBack => self.history_go_back()?,
       Action::HistoryForward => self.history_go_forward()?,
       Action::BreadcrumbSelect(index) => self.breadcrumb_select(index)?,
+      Action::BreadcrumbMenuOpen => {
+        let root = self.tree.root.clone();
+        self.breadcrumb_menu_open(root.parent().unwrap_or(&root));
+      }
+      Action::BreadcrumbMenuAt(index) => {
+        if let Some(segment) = self.breadcrumb_segments.get(index) {
+          let dir = segment.path.clone();
+          self.breadcrumb_menu_open(&dir);
+        }
+      }
+      Action::BreadcrumbMenuDown | Action::BreadcrumbMenuUp | Action::BreadcrumbMenuParent | Action::BreadcrumbMenuChild => {
+        if let Some(ref mut menu) = self.breadcrumb_menu {
+          match action {
+            Action::BreadcrumbMenuDown => menu.move_down(),
+            Action::BreadcrumbMenuUp => menu.move_up(),
+            Action::BreadcrumbMenuParent => menu.parent(),
+            _ => menu.child(),
+          }
+        }
+      }
+      Action::BreadcrumbMenuSelect => self.breadcrumb_menu_select()?,
+      Action::BreadcrumbMenuClick(column, row) => self.breadcrumb_menu_click(column, row)?,
+      Action::BreadcrumbMenuClose => {
+        self.breadcrumb_menu = None;
+        self.input_mode = InputMode::Normal;
+      }
       Action::SwitchPane => self.switch_pane(),
+      Action::CycleFocus => self.cycle_focus(),
       Action::ToggleDualPane => self.toggle_dual_pane()?,
       Action::ShowDiff => {
         if let Some(entry) = self.selected_entry()
@@ -728,18 +1616,40 @@ impl App {
           self.set_status("Previous hunk".to_string());
         }
       }
+      Action::OpenDiffTool => self.open_difftool(),
+      Action::CycleDiffMode => self.cycle_diff_mode(),
+      Action::ExportPatchStart => self.export_patch_start(),
+      Action::ExportPatchSelect(choice) => self.export_patch_select(choice)?,
+      Action::ExportPatchClose => {
+        self.input_mode = InputMode::Normal;
+      }
+      Action::ImageRevisionOlder | Action::ImageRevisionNewer => {
+        let older = action == Action::ImageRevisionOlder;
+        if let Some(msg) = self.preview.step_image_revision(older, self.picker.as_ref(), self.tree.git_repo()) {
+          self.set_status(msg);
+        }
+      }
+      Action::ToggleDiffSideBySide => {
+        if self.preview.toggle_diff_side_by_side() {
+          let layout = if self.preview.diff_side_by_side { "side-by-side" } else { "unified" };
+          if self.preview.diff_side_by_side && self.preview.active_side_by_side().is_none() {
+            self.set_status(format!("Diff: {layout} (pane too narrow, showing unified)"));
+          } else {
+            self.set_status(format!("Diff: {layout}"));
+          }
+        }
+      }
       Action::ShowProperties => {
-        if let Some(entry) = self.selected_entry()
-          && let Some(props) = FileProperties::from_path(&entry.path)
-        {
-          self.file_properties = Some(props);
+        if self.load_properties() {
           self.input_mode = InputMode::Properties;
         }
       }
       Action::PropertiesClose => {
         self.file_properties = None;
+        self.multi_properties = None;
         self.input_mode = InputMode::Normal;
       }
+      Action::PropertiesToggleExecutable => self.properties_toggle_executable(),
       Action::ToggleMark => self.toggle_mark(),
       Action::MarkAll => self.mark_all(),
       Action::ClearMarks => self.clear_marks(),
@@ -748,11 +1658,61 @@ impl App {
       Action::CompressClose => {
         self.input_mode = InputMode::Normal;
       }
-      Action::Tick => {
-        let mut async_completed = self.preview.check_image_loaded();
-        async_completed |= self.preview.check_git_commits_loaded();
-        async_completed |= self.check_extraction_complete()?;
-        async_completed |= self.check_compression_complete()?;
+      Action::NewFromTemplate => self.templates_start(),
+      Action::TemplatesDown => self.templates_move(1),
+      Action::TemplatesUp => self.templates_move(-1),
+      Action::TemplatesSelect => self.templates_select(),
+      Action::TemplatesClose => {
+        self.input_mode = InputMode::Normal;
+      }
+      Action::WorktreesStart => self.worktrees_start(),
+      Action::WorktreesDown => self.worktrees_move(1),
+      Action::WorktreesUp => self.worktrees_move(-1),
+      Action::WorktreesSelect => self.worktrees_select()?,
+      Action::WorktreesClose => {
+        self.input_mode = InputMode::Normal;
+      }
+      Action::BranchesStart => self.branches_start(),
+      Action::BranchesDown => self.branches_move(1),
+      Action::BranchesUp => self.branches_move(-1),
+      Action::BranchesSelect => self.branches_select()?,
+      Action::BranchesNew => {
+        self.prompt.clear();
+        self.prompt_kind = Some(PromptKind::NewBranch);
+        self.input_mode = InputMode::Prompt;
+      }
+      Action::BranchesClose => {
+        self.input_mode = InputMode::Normal;
+      }
+      Action::OpLogOpen => self.op_log_open(),
+      Action::OpLogDown => self.op_log_move(1),
+      Action::OpLogUp => self.op_log_move(-1),
+      Action::OpLogClose => {
+        self.input_mode = InputMode::Normal;
+      }
+      Action::JobsOpen => {
+        self.jobs_cursor = 0;
+        self.input_mode = InputMode::Jobs;
+      }
+      Action::JobsDown => self.jobs_move(1),
+      Action::JobsUp => self.jobs_move(-1),
+      Action::JobsCancel => self.jobs_cancel(),
+      Action::JobsClose => {
+        self.input_mode = InputMode::Normal;
+      }
+      Action::Tick => {
+        let mut async_completed = self.preview.check_image_loaded();
+        async_completed |= self.preview.check_git_commits_loaded();
+        async_completed |= self.preview.check_archive_listed();
+        async_completed |= self.check_archive_opened()?;
+        async_completed |= self.check_holders_found();
+        async_completed |= self.preview.check_text_loaded();
+        async_completed |= self.preview.check_changes_loaded();
+        async_completed |= self.preview.check_follow(self.preview_rows);
+        async_completed |= self.check_jobs_complete()?;
+        async_completed |= self.jobs.progress_changed();
+        async_completed |= self.check_git_loaded();
+        async_completed |= self.check_lost_root()?;
         if async_completed {
           self.needs_redraw = true;
         }
@@ -763,18 +1723,431 @@ impl App {
           self.set_status(format!("Markdown: {mode}"));
         }
       }
+      Action::ToggleSizeFormat => {
+        self.preview.toggle_size_format();
+        let mode = match self.preview.size_format {
+          SizeFormat::Human => "human-readable",
+          SizeFormat::Exact => "exact bytes",
+        };
+        self.set_status(format!("Sizes: {mode}"));
+      }
+      Action::RepeatLast => self.repeat_last()?,
+      Action::Undo => self.undo()?,
+      Action::Redo => self.redo()?,
+      Action::MacroRecord => {
+        if let Some((reg, len)) = self.macros.stop() {
+          self.set_status(format!("Recorded {len} action(s) into @{reg}"));
+        } else {
+          self.macros.pending = Some(MacroOp::Record);
+          self.input_mode = InputMode::MacroRegister;
+        }
+      }
+      Action::MacroPlay => {
+        self.macros.pending = Some(MacroOp::Play);
+        self.input_mode = InputMode::MacroRegister;
+      }
+      Action::MacroRegister(c) => self.macro_register(c)?,
+      Action::BookmarkSet => self.input_mode = InputMode::BookmarkSet,
+      Action::BookmarkJump => self.input_mode = InputMode::BookmarkJump,
+      Action::SetMark(slot) => self.set_bookmark(slot),
+      Action::JumpMark(slot) => self.jump_to_bookmark(slot)?,
       Action::None => {}
     }
     Ok(())
   }
 
+  fn macro_register(&mut self, c: char) -> Result<()> {
+    self.input_mode = InputMode::Normal;
+    match self.macros.pending.take() {
+      Some(MacroOp::Record) if Macros::is_register(c) => {
+        self.macros.start(c);
+        self.set_status(format!("Recording @{c}"));
+      }
+      Some(MacroOp::Play) => {
+        let reg = if c == '@' { self.macros.last_played } else { Some(c) };
+        match reg {
+          Some(reg) if Macros::is_register(reg) => self.play_macro(reg)?,
+          _ => self.set_status("No macro to repeat".to_string()),
+        }
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+
+  /// Where bookmarks of the main tree are kept: its repository, or its
+  /// root outside one.
+  pub fn bookmark_project(&self) -> PathBuf {
+    self.tree.git_repo().map_or_else(|| self.tree.root.clone(), |repo| repo.root().to_path_buf())
+  }
+
+  fn set_bookmark(&mut self, slot: u8) {
+    self.input_mode = InputMode::Normal;
+    if !bookmarks::SLOTS.contains(&slot) {
+      return;
+    }
+    let Some(path) = self.selected_entry().map(|e| e.path.clone()) else {
+      return;
+    };
+    self.bookmarks.set(&self.bookmark_project(), slot, &path);
+    self.state_writer.mark(StateFile::Bookmarks);
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    self.set_status(format!("Bookmark {slot}: {name}"));
+  }
+
+  /// Select the bookmarked path in the main tree, expanding the directories
+  /// above it, or open its directory when it is outside the tree.
+  fn jump_to_bookmark(&mut self, slot: u8) -> Result<()> {
+    self.input_mode = InputMode::Normal;
+    let Some(path) = self.bookmarks.get(&self.bookmark_project(), slot).map(Path::to_path_buf) else {
+      self.set_status(format!("Bookmark {slot} is not set"));
+      return Ok(());
+    };
+    if !path.exists() {
+      self.set_status(format!("Bookmark {slot} no longer exists"));
+      return Ok(());
+    }
+    // Bookmarks belong to the main tree's project
+    self.active_pane = 0;
+    let root = self.tree.root.clone();
+    if path != root && path.starts_with(&root) {
+      let expanded: Vec<PathBuf> = path.ancestors().skip(1).take_while(|p| *p != root).map(Path::to_path_buf).collect();
+      self.restore_tree(&root, &expanded)?;
+    } else if path.is_dir() {
+      if self.restricted(&path) {
+        return Ok(());
+      }
+      self.go_to_dir(&path)?;
+    } else if let Some(parent) = path.parent() {
+      if self.restricted(parent) {
+        return Ok(());
+      }
+      self.restore_tree(parent, &[])?;
+    }
+    self.reposition_cursor_to(&path);
+    self.preview.invalidate();
+    self.update_preview();
+    self.set_status(format!("Bookmark {slot}"));
+    Ok(())
+  }
+
+  /// Replay a register through `update`, so a recording in progress
+  /// captures the replayed actions. Stops early when an action hands
+  /// control elsewhere (quit, a suspended program, an error dialog).
+  fn play_macro(&mut self, reg: char) -> Result<()> {
+    let Some(actions) = self.macros.get(reg).map(<[Action]>::to_vec) else {
+      self.set_status(format!("Register @{reg} is empty"));
+      return Ok(());
+    };
+    self.macros.last_played = Some(reg);
+    for action in actions {
+      self.update(action)?;
+      if self.should_quit || self.should_suspend.is_some() || self.input_mode == InputMode::Error {
+        break;
+      }
+    }
+    Ok(())
+  }
+
+  /// Switch the diff view between unstaged, staged and all changes
+  fn cycle_diff_mode(&mut self) {
+    let showing_diff = self.preview.get_content().is_some_and(|c| c.preview_type == PreviewType::Diff);
+    let Some(entry) = self.selected_entry().filter(|e| showing_diff && !e.is_dir) else {
+      return;
+    };
+    let path = entry.path.clone();
+    self.preview.diff_mode = self.preview.diff_mode.next();
+    let label = self.preview.diff_mode.label();
+    if self.preview.show_diff(&path, self.tree.git_repo()) {
+      self.set_status(format!("Diff: {label}"));
+    } else {
+      self.set_status(format!("Diff: {label} (no changes)"));
+    }
+  }
+
+  fn export_patch_start(&mut self) {
+    if self.preview.current_diff().is_none() {
+      self.set_status("No diff to export".to_string());
+      return;
+    }
+    self.input_mode = InputMode::PatchExport;
+  }
+
+  /// Export the shown diff: 0 = save file, 1 = save current hunk (both ask
+  /// where), 2 = copy, 3 = copy current hunk
+  fn export_patch_select(&mut self, choice: usize) -> Result<()> {
+    self.input_mode = InputMode::Normal;
+    let Some(diff) = self.preview.current_diff() else {
+      return Ok(());
+    };
+    let hunk = self.preview.current_hunk();
+    let hunk_only = choice % 2 == 1;
+    let patch = if hunk_only {
+      hunk.and_then(|h| diff.hunk_patch(h))
+    } else {
+      Some(diff.patch())
+    };
+    let Some(patch) = patch else {
+      self.set_status("No hunk to export".to_string());
+      return Ok(());
+    };
+    let what = match hunk {
+      Some(h) if hunk_only => format!("hunk {}", h + 1),
+      _ => "patch".to_string(),
+    };
+
+    match choice {
+      0 | 1 => {
+        let Some(file_path) = self.selected_entry().map(|e| e.path.clone()) else {
+          return Ok(());
+        };
+        let name = file_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let file_name = match hunk {
+          Some(h) if hunk_only => format!("{name}.hunk{}.patch", h + 1),
+          _ => format!("{name}.patch"),
+        };
+        let dir = self.current_dir();
+        let dest = ops::unique_dest_path(&dir.join(file_name));
+        let shown = dest.strip_prefix(&dir).unwrap_or(&dest);
+        self.prompt.set(shown.to_string_lossy().into_owned());
+        self.pending_patch = Some(PendingPatch { patch, what, source: file_path, dir });
+        self.prompt_kind = Some(PromptKind::PatchPath);
+        self.input_mode = InputMode::Prompt;
+      }
+      2 | 3 => match clipboard_anywhere::set_clipboard(&patch) {
+        Ok(_) => self.set_status(format!("Copied {what} to clipboard")),
+        Err(e) => self.set_status(format!("Copy failed: {e}")),
+      },
+      _ => {}
+    }
+    Ok(())
+  }
+
+  /// Write the pending patch where the prompt says: relative paths start
+  /// in the directory it defaulted to, and a directory gets the default
+  /// name. An existing file is left alone.
+  fn save_patch(&mut self) -> Result<()> {
+    let Some(pending) = self.pending_patch.take() else {
+      self.cancel_prompt();
+      return Ok(());
+    };
+    let text = self.prompt.text.trim().to_string();
+    self.cancel_prompt();
+    if text.is_empty() {
+      self.set_status("Path cannot be empty".to_string());
+      return Ok(());
+    }
+    let mut dest = destination::expand(&text, &pending.dir);
+    if dest.is_dir() {
+      let name = pending.source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+      dest = ops::unique_dest_path(&dest.join(format!("{name}.patch")));
+    }
+    let (Some(dir), Some(file_name)) = (dest.parent(), dest.file_name()) else {
+      return Ok(());
+    };
+    // Resolve `..` first so the restriction sees where the file really goes
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    if self.restricted(&dir) {
+      return Ok(());
+    }
+    let dest = dir.join(file_name);
+    if dest.symlink_metadata().is_ok() {
+      self.set_status(format!("{} already exists", dest.display()));
+      return Ok(());
+    }
+    match std::fs::write(&dest, &pending.patch) {
+      Ok(()) => {
+        self.log_op(OpRecord::now("export patch").from(&pending.source).to(&dest));
+        let shown = dest.strip_prefix(&pending.dir).unwrap_or(&dest).display().to_string();
+        self.set_status(format!("Saved {} to {shown}", pending.what));
+        self.tree.reload()?;
+        self.tree_reloaded = true;
+        self.rebuild_visible_cache();
+        self.reposition_cursor_to(&pending.source);
+      }
+      Err(e) => self.set_status(format!("Patch export failed: {e}")),
+    }
+    Ok(())
+  }
+
+  /// The prompt staging/unstaging (or reverting) the hunk at the top of
+  /// the diff view asks, or why it can't.
+  pub fn hunk_confirmation(&self, revert: bool) -> Result<Option<PromptKind>, String> {
+    let Some(diff) = self.preview.current_diff() else {
+      return Err("No diff shown".to_string());
+    };
+    if diff.rename.is_some() {
+      return Err("Hunks of renamed files cannot be staged separately".to_string());
+    }
+    let mode = self.preview.diff_mode;
+    let action = if revert {
+      (mode == DiffMode::WorktreeIndex).then_some(HunkAction::Revert)
+    } else {
+      mode.stage_action()
+    };
+    let Some(action) = action else {
+      let view = if revert { "unstaged" } else { "unstaged or staged" };
+      return Err(format!("{} works in the {view} diff view (S to switch)", if revert { "Revert" } else { "Staging" }));
+    };
+    Ok(self.preview.current_hunk().map(|hunk| PromptKind::ConfirmHunk(action, hunk)))
+  }
+
+  fn execute_hunk_action(&mut self, action: HunkAction, hunk: usize) -> Result<()> {
+    let Some(path) = self.selected_entry().map(|e| e.path.clone()) else {
+      return Ok(());
+    };
+    let Some(root) = self.tree.git_repo().map(|r| r.root().to_path_buf()) else {
+      return Ok(());
+    };
+    match diff::apply_hunk(&root, &path, self.preview.diff_mode, hunk, action) {
+      Ok(()) => {
+        let done = match action {
+          HunkAction::Stage => "Staged",
+          HunkAction::Unstage => "Unstaged",
+          HunkAction::Revert => "Reverted",
+        };
+        self.log_op(OpRecord::now(&format!("{} hunk", action.verb().to_lowercase())).from(&path).detail(format!("hunk {}", hunk + 1)));
+        self.set_status(format!("{done} hunk {}", hunk + 1));
+        self.tree.invalidate_git_statuses();
+        self.tree.reload()?;
+        self.rebuild_visible_cache();
+        self.reposition_cursor_to(&path);
+        self.refresh_diff();
+      }
+      Err(e) => self.set_status(format!("{} failed: {e}", action.verb())),
+    }
+    Ok(())
+  }
+
+  /// Stage, or with `unstage` unstage, the marked entries or the selection.
+  fn git_stage(&mut self, unstage: bool) -> Result<()> {
+    let targets = self.operation_targets();
+    if targets.is_empty() {
+      return Ok(());
+    }
+    let Some(repo) = self.active_tree().git_repo() else {
+      self.set_status("Not in a git repository".to_string());
+      return Ok(());
+    };
+    let (verb, done) = if unstage { ("unstage", "Unstaged") } else { ("stage", "Staged") };
+    let result = if unstage { repo.unstage(&targets) } else { repo.stage(&targets) };
+    self.finish_git_change(&targets, verb, done, result)
+  }
+
+  fn execute_git_discard(&mut self) -> Result<()> {
+    let targets = self.operation_targets();
+    let Some(repo) = self.active_tree().git_repo() else {
+      return Ok(());
+    };
+    let result = repo.discard(&targets);
+    self.finish_git_change(&targets, "discard", "Discarded changes to", result)
+  }
+
+  /// Report a stage, unstage or discard and show the statuses it changed.
+  fn finish_git_change(&mut self, targets: &[PathBuf], verb: &str, done: &str, result: anyhow::Result<()>) -> Result<()> {
+    if let Err(e) = result {
+      self.set_status(format!("Could not {verb}: {e}"));
+      return Ok(());
+    }
+    for path in targets {
+      self.log_op(OpRecord::now(verb).from(path));
+    }
+    let what = match targets {
+      [one] => one.file_name().unwrap_or_default().to_string_lossy().to_string(),
+      many => format!("{} items", many.len()),
+    };
+    self.set_status(format!("{done} {what}"));
+    self.reload_git_trees()?;
+    self.refresh_preview();
+    Ok(())
+  }
+
+  /// Reload both panes with fresh git status after a git command changed
+  /// the work tree or the index, keeping the active pane's cursor on its
+  /// entry.
+  fn reload_git_trees(&mut self) -> Result<()> {
+    let selected = self.selected_entry().map(|e| e.path.clone());
+    self.tree.invalidate_git_statuses();
+    self.tree.reload()?;
+    self.rebuild_visible_cache();
+    if let Some(ref mut pane) = self.right_pane {
+      pane.tree.invalidate_git_statuses();
+      pane.tree.reload()?;
+      pane.rebuild_visible_cache();
+    }
+    self.tree_reloaded = true;
+    let Some(path) = selected else {
+      return Ok(());
+    };
+    if self.dual_pane_mode && self.active_pane == 1 {
+      if let Some(ref mut pane) = self.right_pane
+        && let Some(pos) = pane.cached_visible.iter().position(|&idx| pane.tree.entries[idx].path == path)
+      {
+        pane.cursor = pos;
+        pane.adjust_scroll(self.viewport_height);
+      }
+    } else {
+      self.reposition_cursor_to(&path);
+    }
+    Ok(())
+  }
+
+  /// Regenerate the shown diff for the selected file, keeping the scroll position
+  fn refresh_diff(&mut self) {
+    let Some(path) = self.selected_entry().map(|e| e.path.clone()) else {
+      return;
+    };
+    let scroll = self.preview.scroll_offset;
+    self.preview.show_diff(&path, self.tree.git_repo());
+    self.preview.scroll_offset = scroll;
+    self.preview.scroll_down(0);
+  }
+
+  /// Follow the previewed file like `tail -f`, or stop and show it as usual again
+  fn toggle_follow(&mut self) {
+    match self.preview.toggle_follow(self.preview_rows) {
+      Some(true) => self.set_status("Following: new lines show up at the bottom".to_string()),
+      Some(false) => {
+        self.refresh_preview();
+        self.set_status("Stopped following".to_string());
+      }
+      None => self.set_status("Only text files can be followed".to_string()),
+    }
+  }
+
+  /// Scroll the preview to the line typed in the prompt
+  fn goto_line(&mut self) {
+    let text = self.prompt.text.trim().to_string();
+    self.cancel_prompt();
+    let Ok(line) = text.parse::<usize>() else {
+      self.set_status(format!("Not a line number: {text}"));
+      return;
+    };
+    if let Err(message) = self.preview.goto_line(line) {
+      self.set_status(message);
+    }
+  }
+
+  /// Reload the preview after files changed on disk; a shown diff stays in diff view
+  pub fn refresh_preview(&mut self) {
+    let showing_diff = self.preview.get_content().is_some_and(|c| c.preview_type == PreviewType::Diff);
+    let shown = self.preview.current_path.is_some();
+    self.preview.invalidate();
+    if showing_diff {
+      self.refresh_diff();
+    } else if self.auto_preview || shown {
+      self.load_preview();
+    }
+  }
+
   fn breadcrumb_select(&mut self, index: usize) -> Result<()> {
-    if let Some(segment) = self.breadcrumb_segments.get(index)
+    if let Some(segment) = self.breadcrumb_segments.get(index).cloned()
       && segment.path != self.tree.root
       && segment.path.is_dir()
+      && !self.restricted(&segment.path)
     {
-      self.tree.navigate_to(&segment.path)?;
-      self.search_query.clear();
+      self.navigate_tree_to(&segment.path)?;
+      self.search.clear();
       self.cursor = 0;
       self.tree_scroll_offset = 0;
       self.marked.clear();
@@ -786,20 +2159,212 @@ impl App {
     Ok(())
   }
 
+  fn breadcrumb_menu_open(&mut self, dir: &Path) {
+    self.breadcrumb_menu = Some(BreadcrumbMenu::open(dir, &self.tree.root, self.tree.show_hidden));
+    self.breadcrumb_menu_area = None;
+    self.input_mode = InputMode::BreadcrumbMenu;
+  }
+
+  /// Go to the highlighted directory, or the listed one when it has none.
+  fn breadcrumb_menu_select(&mut self) -> Result<()> {
+    self.input_mode = InputMode::Normal;
+    let Some(menu) = self.breadcrumb_menu.take() else {
+      return Ok(());
+    };
+    let dir = menu.selected().cloned().unwrap_or(menu.dir);
+    if dir != self.tree.root {
+      self.change_dir(&dir)?;
+    }
+    Ok(())
+  }
+
+  /// A click on a dropdown row goes there; one outside closes it.
+  fn breadcrumb_menu_click(&mut self, column: u16, row: u16) -> Result<()> {
+    let (Some(menu), Some(area)) = (&mut self.breadcrumb_menu, self.breadcrumb_menu_area) else {
+      return Ok(());
+    };
+    if !area.contains(ratatui::layout::Position::new(column, row)) {
+      self.breadcrumb_menu = None;
+      self.input_mode = InputMode::Normal;
+      return Ok(());
+    }
+    let rows = area.height.saturating_sub(2) as usize;
+    let line = row.saturating_sub(area.y + 1) as usize;
+    let index = breadcrumb_menu::scroll_offset(menu.cursor, rows) + line;
+    if row > area.y && line < rows && index < menu.entries.len() {
+      menu.cursor = index;
+      self.breadcrumb_menu_select()?;
+    }
+    Ok(())
+  }
+
   fn go_home(&mut self) -> Result<()> {
     if let Some(home) = dirs::home_dir() {
-      self.push_history(self.tree.root.clone());
-      self.tree.navigate_to(&home)?;
-      self.search_query.clear();
-      self.cursor = 0;
-      self.tree_scroll_offset = 0;
-      self.marked.clear();
-      self.rebuild_visible_cache();
-      self.input_mode = InputMode::Normal;
-      self.preview.invalidate();
-      self.update_preview();
-      self.update_breadcrumbs();
+      self.change_dir(&home)?;
+    }
+    Ok(())
+  }
+
+  /// Whether `--restrict` keeps navigation out of `dir`, telling the user
+  /// when it does.
+  pub fn restricted(&mut self, dir: &Path) -> bool {
+    let Some(ref root) = self.restrict_root else {
+      return false;
+    };
+    if dir.starts_with(root) {
+      return false;
+    }
+    self.restricted_notice();
+    true
+  }
+
+  fn restricted_notice(&mut self) {
+    if let Some(root) = self.restrict_root.clone() {
+      self.set_status(format!("Restricted to {}", root.display()));
+    }
+  }
+
+  /// Make `dir` the root of the main pane, recording history.
+  pub fn change_dir(&mut self, dir: &Path) -> Result<()> {
+    if self.restricted(dir) {
+      return Ok(());
+    }
+    self.push_history(self.tree.root.clone());
+    self.navigate_tree_to(dir)?;
+    self.search.clear();
+    self.cursor = 0;
+    self.tree_scroll_offset = 0;
+    self.marked.clear();
+    self.rebuild_visible_cache();
+    self.input_mode = InputMode::Normal;
+    self.preview.invalidate();
+    self.update_preview();
+    self.update_breadcrumbs();
+    Ok(())
+  }
+
+  /// Make `dir` the root of the main tree, stepping out of the archive
+  /// being browsed or into one as `dir` requires.
+  fn navigate_tree_to(&mut self, dir: &Path) -> Result<()> {
+    if let Some(ref view) = self.archive_view
+      && !dir.starts_with(&view.archive)
+    {
+      self.close_archive_view();
+    }
+    self.archive_opening = None;
+    if self.archive_view.is_none()
+      && let Some(archive) = dir.ancestors().find(|p| archive::is_archive(p) && p.is_file())
+    {
+      // Wait next to the archive while it is listed
+      let parent = archive.parent().unwrap_or(archive).to_path_buf();
+      self.start_archive_listing(archive, dir, None, parent.clone());
+      return self.tree.navigate_to(&parent);
+    }
+    self.tree.navigate_to(dir)
+  }
+
+  /// List `archive` on a worker, the way its preview is, then open its view
+  /// at `dir` from `check_archive_opened`.
+  fn start_archive_listing(&mut self, archive: &Path, dir: &Path, from: Option<PathBuf>, root: PathBuf) {
+    let name = archive.file_name().map_or_else(|| archive.display().to_string(), |n| n.to_string_lossy().to_string());
+    self.set_status(format!("Listing {name}..."));
+    self.archive_opening = Some(ArchiveOpening {
+      dir: dir.to_path_buf(),
+      root,
+      from,
+      rx: crate::preview::list_archive_async(archive, usize::MAX),
+    });
+  }
+
+  /// Open the view of an archive whose listing finished, unless the tree
+  /// moved on meanwhile.
+  fn check_archive_opened(&mut self) -> Result<bool> {
+    let Some(ref opening) = self.archive_opening else {
+      return Ok(false);
+    };
+    let Ok((archive, listing)) = opening.rx.try_recv() else {
+      return Ok(false);
+    };
+    let Some(opening) = self.archive_opening.take() else {
+      return Ok(false);
+    };
+    if self.tree.root != opening.root || self.archive_view.is_some() {
+      return Ok(false);
+    }
+    let opened = listing.map_err(anyhow::Error::msg).and_then(|listing| {
+      self.open_archive_view(&archive, ArchiveSource::from_listing(&archive, listing))?;
+      self.tree.navigate_to(&opening.dir)
+    });
+    if let Err(e) = opened {
+      self.close_archive_view();
+      self.set_status(format!("Cannot open archive: {e}"));
+      return Ok(true);
+    }
+    if let Some(from) = opening.from {
+      self.push_history(from);
+    }
+    if self.status_message.as_deref().is_some_and(|m| m.starts_with("Listing ")) {
+      self.status_message = None;
+    }
+    self.search.clear();
+    self.cursor = 0;
+    self.tree_scroll_offset = 0;
+    self.marked.clear();
+    self.rebuild_visible_cache();
+    self.preview.invalidate();
+    self.update_preview();
+    self.update_breadcrumbs();
+    Ok(true)
+  }
+
+  /// Swap the main tree for one listing the inside of `archive`.
+  fn open_archive_view(&mut self, archive: &Path, source: ArchiveSource) -> Result<()> {
+    let source = Arc::new(source);
+    let mut tree = FileTree::with_source(archive.to_path_buf(), source.clone(), self.tree.ignore_patterns().clone())?;
+    tree.show_hidden = self.tree.show_hidden;
+    tree.set_sort(self.tree.dirs_first, self.tree.pinned().clone());
+    tree.reload()?;
+    let outer = std::mem::replace(&mut self.tree, tree);
+    self.archive_view = Some(ArchiveView { archive: archive.to_path_buf(), outer });
+    self.preview.source = Some(source);
+    Ok(())
+  }
+
+  /// Put back the tree the archive was entered from, reloaded.
+  fn close_archive_view(&mut self) {
+    if let Some(view) = self.archive_view.take() {
+      self.tree = view.outer;
+      self.preview.source = None;
+      if let Err(e) = self.tree.reload() {
+        tracing::warn!(error = %e, "reload after leaving archive failed");
+      }
+    }
+  }
+
+  /// Browse the archive at `path` like a directory, once it is listed.
+  fn enter_archive(&mut self, path: &Path) -> Result<()> {
+    let root = self.tree.root.clone();
+    self.start_archive_listing(path, path, Some(root.clone()), root);
+    Ok(())
+  }
+
+  /// Go back to the directory holding the browsed archive, with the cursor
+  /// on it.
+  fn leave_archive(&mut self) -> Result<()> {
+    let Some(archive) = self.archive_view.as_ref().map(|v| v.archive.clone()) else {
+      return Ok(());
+    };
+    if self.history_forward.last() != Some(&archive) {
+      self.history_forward.push(archive.clone());
     }
+    self.close_archive_view();
+    self.search.clear();
+    self.tree_scroll_offset = 0;
+    self.rebuild_visible_cache();
+    self.reposition_cursor_to(&archive);
+    self.preview.invalidate();
+    self.update_preview();
+    self.update_breadcrumbs();
     Ok(())
   }
 
@@ -820,13 +2385,16 @@ impl App {
 
   /// Go back in history
   fn history_go_back(&mut self) -> Result<()> {
-    if let Some(prev) = self.history_back.pop() {
+    if let Some(prev) = self.history_back.last().cloned()
+      && !self.restricted(&prev)
+    {
+      self.history_back.pop();
       let current = self.tree.root.clone();
       if self.history_forward.last() != Some(&current) {
         self.history_forward.push(current);
       }
-      self.tree.navigate_to(&prev)?;
-      self.search_query.clear();
+      self.navigate_tree_to(&prev)?;
+      self.search.clear();
       self.cursor = 0;
       self.tree_scroll_offset = 0;
       self.marked.clear();
@@ -840,13 +2408,16 @@ impl App {
 
   /// Go forward in history
   fn history_go_forward(&mut self) -> Result<()> {
-    if let Some(next) = self.history_forward.pop() {
+    if let Some(next) = self.history_forward.last().cloned()
+      && !self.restricted(&next)
+    {
+      self.history_forward.pop();
       let current = self.tree.root.clone();
       if self.history_back.last() != Some(&current) {
         self.history_back.push(current);
       }
-      self.tree.navigate_to(&next)?;
-      self.search_query.clear();
+      self.navigate_tree_to(&next)?;
+      self.search.clear();
       self.cursor = 0;
       self.tree_scroll_offset = 0;
       self.marked.clear();
@@ -876,6 +2447,70 @@ impl App {
     self.set_status(format!("Pane: {}", if self.active_pane == 0 { "left" } else { "right" }));
   }
 
+  fn focus(&self) -> FocusRegion {
+    if self.preview_focused {
+      FocusRegion::Preview
+    } else if self.active_pane == 1 {
+      FocusRegion::Right
+    } else {
+      FocusRegion::Left
+    }
+  }
+
+  /// Focus the next region in `focus_order`, skipping the right tree
+  /// outside dual-pane mode.
+  fn cycle_focus(&mut self) {
+    let dual = self.dual_pane_mode && self.right_pane.is_some();
+    let order: Vec<FocusRegion> =
+      self.focus_order.iter().copied().filter(|r| *r != FocusRegion::Right || dual).collect();
+    let current = self.focus();
+    let next = match order.iter().position(|r| *r == current) {
+      Some(i) => order[(i + 1) % order.len()],
+      None => match order.first() {
+        Some(&first) => first,
+        None => return,
+      },
+    };
+    if next == current {
+      return;
+    }
+    self.preview_focused = next == FocusRegion::Preview;
+    let pane = match next {
+      FocusRegion::Left => 0,
+      FocusRegion::Right => 1,
+      FocusRegion::Preview => self.active_pane,
+    };
+    if pane != self.active_pane {
+      self.active_pane = pane;
+      self.preview.invalidate();
+      self.update_preview();
+    }
+    self.set_status(format!("Focus: {}", match next {
+      FocusRegion::Left if dual => "left pane",
+      FocusRegion::Left => "tree",
+      FocusRegion::Right => "right pane",
+      FocusRegion::Preview => "preview",
+    }));
+  }
+
+  /// Movement keys while the preview has focus: lines for j/k, a page for
+  /// PageDown/PageUp.
+  fn scroll_focused_preview(&mut self, action: &Action) {
+    let delta = match *action {
+      Action::MoveDown => 1,
+      Action::MoveUp => -1,
+      Action::MoveBy(delta) => delta,
+      Action::PageDown => self.page_size(),
+      Action::PageUp => -self.page_size(),
+      _ => return,
+    };
+    if delta > 0 {
+      self.preview.scroll_down(delta as usize);
+    } else {
+      self.preview.scroll_up(delta.unsigned_abs() as usize);
+    }
+  }
+
   fn toggle_dual_pane(&mut self) -> Result<()> {
     if self.dual_pane_mode {
       // Disable dual-pane mode
@@ -886,7 +2521,13 @@ impl App {
     } else {
       // Enable dual-pane mode
       let root = self.tree.root.clone();
-      self.right_pane = Some(Pane::new(root)?);
+      let mut pane = Pane::new(root)?;
+      if !self.tree.dirs_first || !self.tree.pinned().is_empty() {
+        pane.tree.set_sort(self.tree.dirs_first, self.tree.pinned().clone());
+        pane.tree.reload()?;
+        pane.rebuild_visible_cache();
+      }
+      self.right_pane = Some(pane);
       self.dual_pane_mode = true;
       self.active_pane = 0;
       self.set_status("Dual-pane mode: on".to_string());
@@ -916,11 +2557,7 @@ impl App {
       return;
     }
     self.favorites.add(root);
-    if let Err(e) = self.favorites.save() {
-      self.set_status(format!("Save favorites failed: {e}"));
-      return;
-    }
-    self.wrote_config = true;
+    self.state_writer.mark(StateFile::Favorites);
     self.set_status("Added to favorites".to_string());
   }
 
@@ -947,27 +2584,10 @@ impl App {
 
   fn favorites_select(&mut self) -> Result<()> {
     if let Some(path) = self.favorites.get(self.favorites_cursor).map(|p| p.to_path_buf()) {
-      if path.is_dir() {
-        if self.dual_pane_mode && self.active_pane == 1 {
-          if let Some(ref mut pane) = self.right_pane {
-            pane.tree.navigate_to(&path)?;
-            pane.search_query.clear();
-            pane.cursor = 0;
-            pane.scroll_offset = 0;
-            pane.rebuild_visible_cache();
-          }
-        } else {
-          self.push_history(self.tree.root.clone());
-          self.tree.navigate_to(&path)?;
-          self.search_query.clear();
-          self.cursor = 0;
-          self.tree_scroll_offset = 0;
-          self.marked.clear();
-          self.rebuild_visible_cache();
-          self.update_breadcrumbs();
-        }
-        self.preview.invalidate();
-        self.update_preview();
+      if self.restricted(&path) {
+        self.input_mode = InputMode::Normal;
+      } else if path.is_dir() {
+        self.go_to_dir(&path)?;
         self.input_mode = InputMode::Normal;
       } else {
         self.set_status("Directory no longer exists".to_string());
@@ -976,15 +2596,81 @@ impl App {
     Ok(())
   }
 
+  /// Save the main tree's root, expanded directories and selection under
+  /// the name typed into the prompt.
+  fn save_layout(&mut self) {
+    let name = self.prompt.text.trim().to_string();
+    self.cancel_prompt();
+    if name.is_empty() {
+      self.set_status("Layout not saved: empty name".to_string());
+      return;
+    }
+    let state = self.session_state();
+    self.layouts.put(Layout { name: name.clone(), root: state.root, expanded: state.expanded, selected: state.selected });
+    self.state_writer.mark(StateFile::Layouts);
+    self.set_status(format!("Saved layout {name}"));
+  }
+
+  fn layouts_select(&mut self) -> Result<()> {
+    let Some(layout) = self.layouts.get(self.layouts_cursor).cloned() else {
+      return Ok(());
+    };
+    if !layout.root.is_dir() {
+      self.set_status("Directory no longer exists".to_string());
+      return Ok(());
+    }
+    self.input_mode = InputMode::Normal;
+    if self.restricted(&layout.root) {
+      return Ok(());
+    }
+    // Start from a collapsed tree so the layout's expansion is all that shows
+    if layout.root == self.tree.root {
+      self.navigate_tree_to(&layout.root)?;
+      self.search.clear();
+    }
+    self.marked.clear();
+    self.restore_tree(&layout.root, &layout.expanded)?;
+    self.cursor = 0;
+    self.tree_scroll_offset = 0;
+    if let Some(ref selected) = layout.selected {
+      self.reposition_cursor_to(selected);
+    }
+    self.preview.invalidate();
+    self.update_preview();
+    self.set_status(format!("Layout {}", layout.name));
+    Ok(())
+  }
+
+  /// Make `path` the root of the focused pane.
+  fn go_to_dir(&mut self, path: &Path) -> Result<()> {
+    if self.dual_pane_mode && self.active_pane == 1 {
+      if let Some(ref mut pane) = self.right_pane {
+        pane.tree.navigate_to(path)?;
+        pane.search.clear();
+        pane.cursor = 0;
+        pane.scroll_offset = 0;
+        pane.rebuild_visible_cache();
+      }
+    } else {
+      self.push_history(self.tree.root.clone());
+      self.navigate_tree_to(path)?;
+      self.search.clear();
+      self.cursor = 0;
+      self.tree_scroll_offset = 0;
+      self.marked.clear();
+      self.rebuild_visible_cache();
+      self.update_breadcrumbs();
+    }
+    self.preview.invalidate();
+    self.update_preview();
+    Ok(())
+  }
+
   fn favorites_remove(&mut self) {
     if self.favorites_cursor < self.favorites.len() {
       self.favorites.remove(self.favorites_cursor);
-      if let Err(e) = self.favorites.save() {
-        self.set_status(format!("Save favorites failed: {e}"));
-        return;
-      }
-      self.wrote_config = true;
-      if self.favorites.len() > 0 {
+      self.state_writer.mark(StateFile::Favorites);
+      if !self.favorites.is_empty() {
         self.favorites_cursor = self.favorites_cursor.min(self.favorites.len() - 1);
       } else {
         self.favorites_cursor = 0;
@@ -999,11 +2685,7 @@ impl App {
       return;
     }
     self.favorites.add(root);
-    if let Err(e) = self.favorites.save() {
-      self.set_status(format!("Save favorites failed: {e}"));
-      return;
-    }
-    self.wrote_config = true;
+    self.state_writer.mark(StateFile::Favorites);
     self.set_status("Added to favorites".to_string());
   }
 
@@ -1017,6 +2699,14 @@ impl App {
         return self.enter_directory();
       }
       let path = self.tree.entries[idx].path.clone();
+      if self.archive_view.is_some() {
+        let name = &self.tree.entries[idx].name;
+        self.set_status(format!("Inside an archive: x extracts {name} next to it"));
+        return Ok(());
+      }
+      if self.browsable_archive(&path) {
+        return self.enter_archive(&path);
+      }
       match opener::open_default(&path) {
         Ok(()) => {
           let name = &self.tree.entries[idx].name;
@@ -1033,7 +2723,15 @@ impl App {
   fn open_with_start(&mut self) {
     if let Some(entry) = self.selected_entry() {
       let is_dir = entry.is_dir;
-      let mut apps = opener::detect_apps(&self.custom_apps, !self.has_apps_file);
+      // Project apps come first and win over global ones with the same command
+      let top = self.tree.git_repo().map(|r| r.root().to_path_buf()).unwrap_or_else(|| self.tree.root.clone());
+      let mut errors = Vec::new();
+      let mut custom = Config::project_apps(&self.tree.root, &top, &mut errors);
+      if let Some(error) = errors.into_iter().next() {
+        self.set_status(error);
+      }
+      custom.extend(self.custom_apps.iter().cloned());
+      let mut apps = opener::detect_apps(self.runner.as_ref(), &custom, !self.has_apps_file);
       if !is_dir {
         let folder_apps: Vec<opener::OpenApp> = apps
           .iter()
@@ -1091,7 +2789,7 @@ impl App {
         if app.is_tui {
           self.should_suspend = Some(SuspendAction::OpenWith(app.command.clone(), target));
         } else {
-          match opener::open_with_app(&target, &app) {
+          match opener::open_with_app(self.runner.as_ref(), &target, &app) {
             Ok(()) => self.set_status(format!("Opened with {}", app.name)),
             Err(e) => self.status_message = Some(e),
           }
@@ -1101,6 +2799,56 @@ impl App {
     Ok(())
   }
 
+  /// Open the picker over the URLs in the visible rows of the preview.
+  fn urls_open(&mut self) {
+    let Some(lines) = self.preview.get_display_lines() else {
+      self.set_status("No URLs in the preview".to_string());
+      return;
+    };
+    let rows = if self.preview_rows > 0 { self.preview_rows } else { self.viewport_height };
+    let mut urls: Vec<String> = Vec::new();
+    for line in lines.iter().skip(self.preview.scroll_offset).take(rows) {
+      let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+      for (_, url) in links::find_urls(&text) {
+        if !urls.iter().any(|u| u == url) {
+          urls.push(url.to_string());
+        }
+      }
+    }
+    if urls.is_empty() {
+      self.set_status("No URLs in the preview".to_string());
+      return;
+    }
+    self.urls = urls;
+    self.urls_cursor = 0;
+    self.input_mode = InputMode::Urls;
+  }
+
+  fn urls_select(&mut self, index: usize) {
+    let Some(url) = self.urls.get(index).cloned() else {
+      return;
+    };
+    self.input_mode = InputMode::Normal;
+    self.urls.clear();
+    match opener::open_url(self.runner.as_ref(), &url) {
+      Ok(()) => self.set_status(format!("Opened {url}")),
+      Err(e) => self.status_message = Some(e),
+    }
+  }
+
+  /// Run the capability checks and show them in the diagnostics overlay.
+  fn doctor_open(&mut self) {
+    let config_home = dirs::config_dir();
+    let probe = doctor::Probe {
+      runner: self.runner.as_ref(),
+      var: &|name| std::env::var(name).ok(),
+      config_home: config_home.as_deref(),
+      image_protocol: self.picker.as_ref().map(|p| p.protocol_type()),
+    };
+    self.doctor = doctor::run(&probe);
+    self.input_mode = InputMode::Doctor;
+  }
+
   fn move_cursor(&mut self, delta: i32) {
     if self.dual_pane_mode && self.active_pane == 1 {
       if let Some(ref mut pane) = self.right_pane {
@@ -1131,6 +2879,12 @@ impl App {
     }
   }
 
+  /// Entries a page move skips: the rows the tree pane shows, less one so
+  /// the previous page's edge stays in view.
+  fn page_size(&self) -> i32 {
+    (self.viewport_height.saturating_sub(3) as i32).max(1)
+  }
+
   fn adjust_scroll(&mut self) {
     let visible = self.viewport_height.saturating_sub(2); // borders
     if visible == 0 {
@@ -1143,7 +2897,21 @@ impl App {
     }
   }
 
+  /// Tell why a symlink back to one of its ancestors doesn't expand.
+  /// Returns true when the selection is one.
+  fn refuse_cycle(&mut self) -> bool {
+    let Some(entry) = self.selected_entry().filter(|e| e.is_cycle && !e.expanded) else {
+      return false;
+    };
+    let target = entry.symlink_target.clone().unwrap_or_default();
+    self.set_status(format!("{} links back to {target}, not expanded", entry.name));
+    true
+  }
+
   fn enter_or_expand(&mut self) -> Result<()> {
+    if self.refuse_cycle() {
+      return Ok(());
+    }
     if self.dual_pane_mode && self.active_pane == 1 {
       if let Some(ref mut pane) = self.right_pane {
         let idx = pane.cached_visible.get(pane.cursor).copied();
@@ -1167,6 +2935,9 @@ impl App {
   }
 
   fn expand_only(&mut self) -> Result<()> {
+    if self.refuse_cycle() {
+      return Ok(());
+    }
     if self.dual_pane_mode && self.active_pane == 1 {
       if let Some(ref mut pane) = self.right_pane {
         let idx = pane.cached_visible.get(pane.cursor).copied();
@@ -1196,7 +2967,7 @@ impl App {
         if let Some(idx) = idx {
           if pane.tree.entries[idx].is_dir {
             pane.tree.enter_dir(idx)?;
-            pane.search_query.clear();
+            pane.search.clear();
             pane.cursor = 0;
             pane.scroll_offset = 0;
             pane.marked.clear();
@@ -1214,7 +2985,7 @@ impl App {
         if self.tree.entries[idx].is_dir {
           self.push_history(self.tree.root.clone());
           self.tree.enter_dir(idx)?;
-          self.search_query.clear();
+          self.search.clear();
           self.cursor = 0;
           self.tree_scroll_offset = 0;
           self.marked.clear();
@@ -1222,6 +2993,9 @@ impl App {
           self.preview.invalidate();
           self.update_preview();
           self.update_breadcrumbs();
+        } else if self.browsable_archive(&self.tree.entries[idx].path.clone()) {
+          let path = self.tree.entries[idx].path.clone();
+          self.enter_archive(&path)?;
         } else {
           self.update_preview();
         }
@@ -1230,6 +3004,16 @@ impl App {
     Ok(())
   }
 
+  /// Whether `path` is a ZIP or tar archive of the local main tree, which
+  /// Enter browses into. Single compressed files have no entries to list,
+  /// and a picker has to return real paths.
+  fn browsable_archive(&self, path: &Path) -> bool {
+    self.archive_view.is_none()
+      && self.picker_mode.is_none()
+      && self.tree.source().is_local()
+      && matches!(archive::archive_type(path), Some("zip" | "tar" | "tar.gz" | "tar.bz2" | "tar.xz"))
+  }
+
   fn pick_file(&mut self) -> Result<()> {
     let pick_directories = self.picker_mode.as_ref().is_some_and(PickerOutput::picks_directories);
 
@@ -1288,6 +3072,7 @@ impl App {
   }
 
   fn go_parent_or_collapse(&mut self) -> Result<()> {
+    let mut restricted = false;
     if self.dual_pane_mode && self.active_pane == 1 {
       if let Some(ref mut pane) = self.right_pane {
         let idx = pane.cached_visible.get(pane.cursor).copied();
@@ -1318,8 +3103,10 @@ impl App {
         }
 
         // Case 3: At root level or parent not visible -> change tree root
-        if let Some(old_root) = pane.tree.go_parent()? {
-          pane.search_query.clear();
+        if self.restrict_root.as_ref() == Some(&pane.tree.root) {
+          restricted = true;
+        } else if let Some(old_root) = pane.tree.go_parent()? {
+          pane.search.clear();
           pane.cursor = pane
             .tree
             .entries
@@ -1362,12 +3149,16 @@ impl App {
       }
 
       // Case 3: At root level or parent not visible -> change tree root
-      if let Some(old_root) = self.tree.go_parent()? {
+      if self.archive_view.as_ref().is_some_and(|v| v.archive == self.tree.root) {
+        self.leave_archive()?;
+      } else if self.restrict_root.as_ref() == Some(&self.tree.root) {
+        restricted = true;
+      } else if let Some(old_root) = self.tree.go_parent()? {
         // Push current location to forward history so we can return with HistoryForward
         if self.history_forward.last() != Some(&old_root) {
           self.history_forward.push(old_root.clone());
         }
-        self.search_query.clear();
+        self.search.clear();
         self.cursor = self
           .tree
           .entries
@@ -1382,6 +3173,9 @@ impl App {
         self.update_breadcrumbs();
       }
     }
+    if restricted {
+      self.restricted_notice();
+    }
     Ok(())
   }
 
@@ -1404,6 +3198,108 @@ impl App {
     Ok(())
   }
 
+  /// Switch the focused pane between directories first and interleaved
+  /// sorting, keeping the cursor on the same entry.
+  fn toggle_dirs_first(&mut self) -> Result<()> {
+    let selected = self.selected_entry().map(|e| e.path.clone());
+    let dirs_first = if self.dual_pane_mode && self.active_pane == 1
+      && let Some(ref mut pane) = self.right_pane
+    {
+      pane.tree.toggle_dirs_first()?;
+      pane.rebuild_visible_cache();
+      if let Some(pos) = pane.cached_visible.iter().position(|&i| Some(&pane.tree.entries[i].path) == selected.as_ref()) {
+        pane.cursor = pos;
+      }
+      pane.tree.dirs_first
+    } else {
+      self.tree.toggle_dirs_first()?;
+      self.rebuild_visible_cache();
+      if let Some(ref selected) = selected {
+        self.reposition_cursor_to(selected);
+      }
+      self.tree.dirs_first
+    };
+    self.set_status(format!("Sort: {}", if dirs_first { "directories first" } else { "interleaved" }));
+    Ok(())
+  }
+
+  fn current_settings(&self) -> Settings {
+    Settings {
+      theme: self.theme_name.clone(),
+      icons: self.icons,
+      tree_ratio: self.tree_ratio,
+      dirs_first: self.tree.dirs_first,
+      show_hidden: self.tree.show_hidden,
+    }
+  }
+
+  fn settings_change(&mut self, forward: bool) -> Result<()> {
+    let Some(ref mut editor) = self.settings else {
+      return Ok(());
+    };
+    editor.change(forward, self.min_tree_ratio, self.max_tree_ratio, self.ratio_step);
+    let draft = editor.draft.clone();
+    self.apply_settings(&draft)
+  }
+
+  /// Show `settings` right away, in both panes. The theme is left to the
+  /// main loop through `requested_theme`.
+  fn apply_settings(&mut self, settings: &Settings) -> Result<()> {
+    if settings.theme != self.theme_name {
+      self.theme_name = settings.theme.clone();
+      self.requested_theme = Some(settings.theme.clone());
+    }
+    self.icons = settings.icons;
+    self.tree_ratio = settings.tree_ratio;
+
+    if self.tree.dirs_first != settings.dirs_first || self.tree.show_hidden != settings.show_hidden {
+      let selected = self.cached_visible.get(self.cursor).map(|&i| self.tree.entries[i].path.clone());
+      if self.tree.dirs_first != settings.dirs_first {
+        self.tree.toggle_dirs_first()?;
+      }
+      if self.tree.show_hidden != settings.show_hidden {
+        self.tree.toggle_hidden()?;
+      }
+      self.rebuild_visible_cache();
+      if let Some(ref selected) = selected {
+        self.reposition_cursor_to(selected);
+      }
+      self.cursor = self.cursor.min(self.cached_visible.len().saturating_sub(1));
+      self.preview.invalidate();
+      self.update_preview();
+    }
+    if let Some(ref mut pane) = self.right_pane {
+      if pane.tree.dirs_first != settings.dirs_first {
+        pane.tree.toggle_dirs_first()?;
+      }
+      if pane.tree.show_hidden != settings.show_hidden {
+        pane.tree.toggle_hidden()?;
+      }
+      pane.rebuild_visible_cache();
+      pane.cursor = pane.cursor.min(pane.cached_visible.len().saturating_sub(1));
+    }
+    Ok(())
+  }
+
+  /// Close the settings overlay, keeping its options and writing the
+  /// changed ones to config.toml.
+  fn settings_save(&mut self) {
+    self.input_mode = InputMode::Normal;
+    let Some(editor) = self.settings.take() else {
+      return;
+    };
+    if editor.draft == editor.original {
+      self.set_status("Settings unchanged".to_string());
+      return;
+    }
+    let result = Config::config_path()
+      .and_then(|path| settings::save(&path, &editor.original, &editor.draft).map(|()| path).map_err(|e| e.to_string()));
+    match result {
+      Ok(path) => self.set_status(format!("Settings saved to {}", contract_home(&path))),
+      Err(e) => self.set_status(format!("Could not save settings: {e}")),
+    }
+  }
+
   fn toggle_custom_ignore(&mut self) -> Result<()> {
     self.tree.toggle_custom_ignored()?;
     self.rebuild_visible_cache();
@@ -1420,7 +3316,7 @@ impl App {
     if self.dual_pane_mode && self.active_pane == 1 {
       if let Some(ref mut pane) = self.right_pane {
         pane.rebuild_visible_cache();
-        if !pane.search_query.is_empty() && !pane.cached_visible.is_empty() {
+        if !pane.search.is_empty() && !pane.cached_visible.is_empty() {
           pane.cursor = 0;
           pane.adjust_scroll(self.viewport_height);
         }
@@ -1428,7 +3324,7 @@ impl App {
       self.update_preview();
     } else {
       self.rebuild_visible_cache();
-      if !self.search_query.is_empty() && !self.cached_visible.is_empty() {
+      if !self.search.is_empty() && !self.cached_visible.is_empty() {
         self.cursor = 0;
         self.adjust_scroll();
       }
@@ -1479,91 +3375,354 @@ impl App {
   }
 
   fn paste_clipboard(&mut self) -> Result<()> {
-    let Some(op) = self.clipboard.op else {
+    if self.clipboard.op.is_none() || self.clipboard.paths.is_empty() {
       self.set_status("Nothing to paste".to_string());
       return Ok(());
+    }
+
+    // Ask before renaming anything the destination filesystem would reject
+    let fs_type = match self.safe_names {
+      SafeNames::Auto => names::restricted_fs(&self.current_dir()),
+      SafeNames::Always => Some("FAT/NTFS".to_string()),
+      SafeNames::Never => None,
     };
+    if let Some(fs_type) = fs_type {
+      let changes = names::unsafe_names(&self.clipboard.paths);
+      if !changes.is_empty() {
+        let count = changes.len();
+        self.pending_safe_names = Some(SafeNamesPlan { fs_type, changes });
+        self.ask(PromptKind::ConfirmSafeNames);
+        self.set_status(format!("{count} names need renaming. Paste with safe names? (y/N)"));
+        return Ok(());
+      }
+    }
+    self.execute_paste(false)
+  }
 
-    let paths = self.clipboard.paths.clone();
-    if paths.is_empty() {
+  fn paste_options_open(&mut self) {
+    if self.clipboard.op.is_none() || self.clipboard.paths.is_empty() {
       self.set_status("Nothing to paste".to_string());
+      return;
+    }
+    self.paste_options = Some(self.copy_options);
+    self.input_mode = InputMode::PasteOptions;
+  }
+
+  fn paste_options_toggle(&mut self, option: usize) {
+    let Some(ref mut options) = self.paste_options else {
+      return;
+    };
+    match option {
+      0 => options.preserve_links = !options.preserve_links,
+      1 => options.preserve_mode = !options.preserve_mode,
+      2 => options.preserve_timestamps = !options.preserve_timestamps,
+      3 => options.preserve_xattrs = !options.preserve_xattrs,
+      4 => options.verify = !options.verify,
+      _ => {}
+    }
+  }
+
+  /// Paste the clipboard into the current directory. With `safe_names`,
+  /// every pasted name is made acceptable to FAT and NTFS.
+  fn execute_paste(&mut self, safe_names: bool) -> Result<()> {
+    let Some(op) = self.clipboard.op else {
+      return Ok(());
+    };
+    let options = CopyOptions { sanitize: safe_names, ..self.paste_options.take().unwrap_or(self.copy_options) };
+    self.start_paste(PastePlan::new(self.clipboard.paths.clone(), op, self.current_dir(), options, false))
+  }
+
+  /// Run `plan`, first asking what to do with each name already taken
+  /// where it goes.
+  fn start_paste(&mut self, plan: PastePlan) -> Result<()> {
+    if plan.pending.is_empty() {
+      return self.run_paste(plan);
+    }
+    self.paste_plan = Some(plan);
+    self.input_mode = InputMode::PasteConflict;
+    Ok(())
+  }
+
+  /// Answer for the taken name shown, or for all of them with "apply to
+  /// all". Pastes once nothing is left to ask.
+  fn paste_conflict_choose(&mut self, choice: Collision) -> Result<()> {
+    let Some(ref mut plan) = self.paste_plan else {
+      return Ok(());
+    };
+    let answered = if plan.apply_to_all { plan.pending.len() } else { 1 };
+    for source in plan.pending.drain(..answered.min(plan.pending.len())) {
+      plan.choices.insert(source, choice);
+    }
+    if !plan.pending.is_empty() {
+      return Ok(());
+    }
+    self.input_mode = InputMode::Normal;
+    match self.paste_plan.take() {
+      Some(plan) => self.run_paste(plan),
+      None => Ok(()),
+    }
+  }
+
+  /// Copy or move what `plan` holds, then clear the clipboard after a cut
+  /// paste or the marks after a copy/move to.
+  fn run_paste(&mut self, plan: PastePlan) -> Result<()> {
+    let done = self.transfer(&plan)?;
+    if done == Transfer::Failed {
+      return Ok(());
+    }
+    if plan.to_destination {
+      self.active_marks_mut().clear();
+      if let Transfer::Done(notes) = done {
+        let verb = if plan.op == ClipboardOp::Cut { "Moved" } else { "Copied" };
+        let what = match plan.sources.as_slice() {
+          [one] => one.file_name().unwrap_or_default().to_string_lossy().to_string(),
+          many => format!("{} items", many.len()),
+        };
+        let verified = verified_note(plan.options.verify);
+        self.set_status(format!("{verb} {what} to {}{verified}{notes}", contract_home(&plan.target_dir)));
+      }
+      return Ok(());
+    }
+    if plan.op == ClipboardOp::Cut {
+      self.clipboard = Clipboard { paths: Vec::new(), op: None };
+    }
+    if let Transfer::Done(notes) = done {
+      self.set_status(format!("Pasted{}{notes}", verified_note(plan.options.verify)));
+    }
+    self.last_repeatable = Some(Repeatable::Paste);
+    Ok(())
+  }
+
+  /// Copy or move the sources of `plan` into its target directory,
+  /// recording the operation for undo. Copies follow its options; moves
+  /// that can't rename keep everything, like `mv`. Copies of more than
+  /// `BACKGROUND_COPY` run as a job instead, with their progress in the
+  /// status bar.
+  fn transfer(&mut self, plan: &PastePlan) -> Result<Transfer> {
+    let (paths, target_dir, options) = (&plan.sources, &plan.target_dir, plan.options);
+    let cut = plan.op == ClipboardOp::Cut;
+    let copy_options = match plan.op {
+      ClipboardOp::Cut => CopyOptions { sanitize: options.sanitize, verify: options.verify, ..CopyOptions::archive() },
+      ClipboardOp::Copy => options,
+    };
+
+    // Moves within a filesystem are renames, whatever their size
+    let device = |path: &Path| std::fs::symlink_metadata(path).map(|m| m.dev()).ok();
+    let copied: Vec<&PathBuf> = paths.iter().filter(|p| !cut || device(p) != device(target_dir)).collect();
+    if progress::exceeds(&copied, BACKGROUND_COPY) {
+      let kind = JobKind::Transfer {
+        sources: paths.to_vec(),
+        target_dir: target_dir.to_path_buf(),
+        cut,
+        options: copy_options,
+        collisions: plan.choices.clone(),
+        use_trash: self.use_trash,
+      };
+      let job = Job { id: 0, kind: kind.clone() };
+      let (verb, name) = (if cut { "Moving" } else { "Copying" }, job.name());
+      match self.jobs.push(kind) {
+        None => self.set_status(format!("{verb} {name} in the background (Esc cancels)...")),
+        Some(position) => self.set_status(format!("Queued {} of {name} (#{position})", job.verb())),
+      }
+      return Ok(Transfer::Background);
+    }
+
+    let transferred =
+      jobs::transfer(paths, target_dir, cut, &copy_options, &plan.choices, self.use_trash, &Progress::default());
+    Ok(match self.finish_transfer(transferred, cut, copy_options)? {
+      Some(notes) => Transfer::Done(notes),
+      None => Transfer::Failed,
+    })
+  }
+
+  /// Log, record for undo and show what a copy or move did. Returns notes
+  /// on symlink cycles and taken names left out, or None when a failure
+  /// stopped it (with the error in the status bar). Pairs that overwrote
+  /// something aren't recorded: undo couldn't bring the old entry back.
+  fn finish_transfer(&mut self, transferred: Transferred, cut: bool, options: CopyOptions) -> Result<Option<String>> {
+    for source in &transferred.missing {
+      self.set_status(format!("Source no longer exists: {}", source.display()));
+    }
+    let verb = if cut { "move" } else { "copy" };
+    for (source, dest) in &transferred.pairs {
+      self.log_op(OpRecord::now(verb).from(source).to(dest));
+    }
+    let op = if cut { ClipboardOp::Cut } else { ClipboardOp::Copy };
+    let undoable = transferred.pairs.into_iter().filter(|(_, dest)| !transferred.replaced.contains(dest)).collect();
+    self.record_paste(undoable, op, options);
+    self.tree.reload()?;
+    self.tree_reloaded = true;
+    self.rebuild_visible_cache();
+
+    if let Some((source, e)) = transferred.error {
+      let message = format!("Paste failed: {e}");
+      self.set_status(if cut { with_holders(message, std::slice::from_ref(&source)) } else { message });
+      return Ok(None);
+    }
+    if let Some(dest) = transferred.last_dest {
+      self.reposition_cursor_to(&dest);
+    }
+    self.preview.invalidate();
+    self.update_preview();
+    let mut notes = walk::cycle_note(transferred.cycles);
+    if !transferred.replaced.is_empty() {
+      notes.push_str(&format!(" ({} overwritten)", transferred.replaced.len()));
+    }
+    if transferred.skipped > 0 {
+      notes.push_str(&format!(" ({} skipped)", transferred.skipped));
+    }
+    Ok(Some(notes))
+  }
+
+  /// Open the copy/move to prompt for the marked entries or the selection.
+  fn transfer_start(&mut self, cut: bool) {
+    let sources = self.operation_targets();
+    let Some(here) = sources.first().and_then(|p| p.parent()).map(Path::to_path_buf) else {
+      return;
+    };
+    let other_pane = match (self.dual_pane_mode, self.active_pane, &self.right_pane) {
+      (true, 0, Some(pane)) => Some(pane.tree.root.clone()),
+      (true, _, Some(_)) => Some(self.tree.root.clone()),
+      _ => None,
+    };
+    let recent = self.input_history.entries(HistoryKind::Destination).iter().rev().map(|d| destination::expand(d, &here));
+    let mut choices: Vec<PathBuf> = Vec::new();
+    for dir in other_pane.into_iter().chain(recent).chain(self.favorites.list().iter().cloned()) {
+      if dir != here && dir.is_dir() && !choices.contains(&dir) {
+        choices.push(dir);
+      }
+    }
+    self.destination = Some(DestinationPicker::new(cut, sources, choices));
+    self.prompt.clear();
+    self.prompt_kind = Some(if cut { PromptKind::MoveTo } else { PromptKind::CopyTo });
+    self.input_mode = InputMode::Prompt;
+  }
+
+  /// Copy or move the picker's targets into the directory typed into the
+  /// prompt. An unusable destination leaves the prompt open.
+  fn execute_transfer(&mut self) -> Result<()> {
+    let Some(picker) = self.destination.as_ref() else {
+      self.cancel_prompt();
+      return Ok(());
+    };
+    let text = self.prompt.text.trim().to_string();
+    if text.is_empty() {
+      self.set_status("Type a destination directory".to_string());
+      return Ok(());
+    }
+    let dest = destination::expand(&text, &self.active_tree().root);
+    if !dest.is_dir() {
+      self.set_status(format!("Not a directory: {text}"));
+      return Ok(());
+    }
+    // Resolve `..` so the restriction sees where the entries really go
+    let dest = dest.canonicalize().unwrap_or(dest);
+    if let Some(inside) = picker.sources.iter().find(|s| s.is_dir() && dest.starts_with(s)) {
+      let name = inside.file_name().unwrap_or_default().to_string_lossy().to_string();
+      self.set_status(format!("Can't put {name} inside itself"));
       return Ok(());
     }
+    if self.restricted(&dest) {
+      return Ok(());
+    }
+    let Some(picker) = self.destination.take() else {
+      return Ok(());
+    };
+    self.cancel_prompt();
+    self.input_history.record(HistoryKind::Destination, &contract_home(&dest));
+    self.state_writer.mark(StateFile::History);
 
-    let target_dir = self.current_dir();
-    let mut last_dest = None;
+    let op = if picker.cut { ClipboardOp::Cut } else { ClipboardOp::Copy };
+    self.start_paste(PastePlan::new(picker.sources, op, dest, self.copy_options, true))
+  }
 
-    for source in &paths {
-      if !source.exists() {
-        self.set_status(format!("Source no longer exists: {}", source.display()));
-        continue;
+  /// Apply a readline edit to the text prompt or search query being typed.
+  fn line_edit(&mut self, edit: LineEdit) {
+    let clipboard = if edit == LineEdit::Paste {
+      match clipboard_anywhere::get_clipboard() {
+        Ok(text) => Some(text),
+        Err(e) => {
+          self.set_status(format!("Paste failed: {e}"));
+          return;
+        }
       }
-
-      let file_name = source.file_name().unwrap_or_default();
-      let raw_dest = target_dir.join(file_name);
-
-      // Cut to same location is a no-op
-      if op == ClipboardOp::Cut && raw_dest == *source {
-        last_dest = Some(raw_dest);
-        continue;
+    } else {
+      None
+    };
+    match self.input_mode {
+      InputMode::Prompt if self.prompt_kind.is_some_and(PromptKind::takes_text) => {
+        self.prompt.apply(edit, clipboard.as_deref());
       }
-
-      let dest = ops::unique_dest_path(&raw_dest);
-
-      match op {
-        ClipboardOp::Cut => {
-          // Try rename first (same filesystem), fallback to copy+delete
-          if std::fs::rename(source, &dest).is_err() {
-            match ops::copy_path(source, &dest) {
-              Ok(()) => {
-                if source.is_dir() {
-                  let _ = std::fs::remove_dir_all(source);
-                } else {
-                  let _ = std::fs::remove_file(source);
-                }
-              }
-              Err(e) => {
-                self.set_status(format!("Paste failed: {e}"));
-                self.tree.reload()?;
-                self.tree_reloaded = true;
-                self.rebuild_visible_cache();
-                return Ok(());
-              }
-            }
-          }
-        }
-        ClipboardOp::Copy => {
-          if let Err(e) = ops::copy_path(source, &dest) {
-            self.set_status(format!("Paste failed: {e}"));
-            self.tree.reload()?;
-            self.tree_reloaded = true;
-            self.rebuild_visible_cache();
-            return Ok(());
-          }
-        }
+      InputMode::Search => {
+        self.active_search_mut().apply(edit, clipboard.as_deref());
+        self.apply_search_filter();
       }
-      last_dest = Some(dest);
+      _ => {}
     }
+  }
 
-    if op == ClipboardOp::Cut {
-      self.clipboard = Clipboard { paths: Vec::new(), op: None };
+  /// Insert pasted text at the cursor of the text prompt or search query.
+  /// Pastes anywhere else, including y/n confirmations, are dropped.
+  fn insert_text(&mut self, text: &str) {
+    match self.input_mode {
+      InputMode::Prompt if self.prompt_kind.is_some_and(PromptKind::takes_text) => self.prompt.insert_str(text),
+      InputMode::Search => {
+        self.active_search_mut().insert_str(text);
+        self.apply_search_filter();
+      }
+      _ => {}
     }
+  }
 
-    self.tree.reload()?;
-    self.tree_reloaded = true;
-    self.rebuild_visible_cache();
+  /// The search query of the focused pane.
+  fn active_search_mut(&mut self) -> &mut LineEditor {
+    if self.dual_pane_mode
+      && self.active_pane == 1
+      && let Some(ref mut pane) = self.right_pane
+    {
+      return &mut pane.search;
+    }
+    &mut self.search
+  }
 
-    if let Some(dest) = last_dest {
-      self.reposition_cursor_to(&dest);
+  /// Replace the search query or prompt name with an older (Up) or newer
+  /// (Down) entry from its history.
+  fn recall_history(&mut self, older: bool) {
+    if let Some(ref mut picker) = self.destination {
+      if let Some(text) = picker.step(&self.prompt.text, !older) {
+        self.prompt.set(text);
+      }
+      return;
+    }
+    let kind = match self.input_mode {
+      InputMode::Search => HistoryKind::Search,
+      InputMode::Prompt => match self.prompt_kind.and_then(HistoryKind::of_prompt) {
+        Some(kind) => kind,
+        None => return,
+      },
+      _ => return,
+    };
+    let editor = if kind == HistoryKind::Search { self.active_search_mut() } else { &mut self.prompt };
+    let current = editor.text.clone();
+    let recalled = if older { self.input_history.older(kind, &current) } else { self.input_history.newer(kind) };
+    let Some(text) = recalled else {
+      return;
+    };
+    if kind == HistoryKind::Search {
+      self.active_search_mut().set(text);
+      self.apply_search_filter();
+    } else {
+      self.prompt.set(text);
     }
+  }
 
-    self.set_status("Pasted".to_string());
-    self.preview.invalidate();
-    self.update_preview();
-    Ok(())
+  fn record_paste(&mut self, pairs: Vec<(PathBuf, PathBuf)>, op: ClipboardOp, options: CopyOptions) {
+    if !pairs.is_empty() {
+      self.history.record(command::Paste { pairs, cut: op == ClipboardOp::Cut, options });
+    }
   }
 
-  fn remove_path(path: &std::path::Path, use_trash: bool) -> Result<(), String> {
+  pub fn remove_path(path: &std::path::Path, use_trash: bool) -> Result<(), String> {
     if use_trash {
       trash::delete(path).map_err(|e| e.to_string())
     } else if path.is_dir() {
@@ -1585,6 +3744,7 @@ impl App {
 
     match result {
       Ok(()) => {
+        self.log_op(OpRecord::now(if use_trash { "trash" } else { "delete" }).from(&entry.path));
         // Clean clipboard if deleted path was in it
         self.clipboard.paths.retain(|p| !p.starts_with(&entry.path));
         if self.clipboard.paths.is_empty() {
@@ -1607,7 +3767,7 @@ impl App {
       }
       Err(e) => {
         self.cancel_prompt();
-        self.set_status(format!("Delete failed: {e}"));
+        self.set_status(with_holders(format!("Delete failed: {e}"), &[entry.path]));
       }
     }
     Ok(())
@@ -1627,6 +3787,7 @@ impl App {
     let use_trash = self.use_trash;
     for path in &targets {
       if Self::remove_path(path, use_trash).is_ok() {
+        self.log_op(OpRecord::now(if use_trash { "trash" } else { "delete" }).from(path));
         deleted += 1;
         self.clipboard.paths.retain(|p| !p.starts_with(path));
       }
@@ -1654,7 +3815,7 @@ impl App {
   }
 
   fn execute_rename(&mut self) -> Result<()> {
-    let new_name = self.prompt_input.trim().to_string();
+    let new_name = self.prompt.text.trim().to_string();
     if new_name.is_empty() {
       self.cancel_prompt();
       self.set_status("Name cannot be empty".to_string());
@@ -1678,40 +3839,55 @@ impl App {
 
     match std::fs::rename(&entry.path, &new_path) {
       Ok(()) => {
-        // Update clipboard if renamed path was in it
-        for p in &mut self.clipboard.paths {
-          if *p == entry.path {
-            *p = new_path.clone();
-          }
-        }
-        // Remap expanded paths so reload() preserves expansion under the new name
-        let old_prefix = &entry.path;
-        for e in &mut self.tree.entries {
-          if e.expanded && (e.path == *old_prefix || e.path.starts_with(old_prefix))
-            && let Ok(suffix) = e.path.strip_prefix(old_prefix)
-          {
-            e.path = new_path.join(suffix);
-          }
-        }
+        self.log_op(OpRecord::now("rename").from(&entry.path).to(&new_path));
+        self.follow_rename(&entry.path, &new_path);
         self.cancel_prompt();
         self.tree.reload()?;
         self.tree_reloaded = true;
         self.rebuild_visible_cache();
         self.reposition_cursor_to(&new_path);
         self.set_status(format!("Renamed to {new_name}"));
+        if new_path != entry.path {
+          self.history.record(command::Rename { from: entry.path.clone(), to: new_path.clone() });
+        }
+        if new_name != entry.name {
+          self.last_repeatable = Some(Repeatable::Rename(RenamePattern::from_names(&entry.name, &new_name)));
+        }
         self.preview.invalidate();
         self.update_preview();
       }
       Err(e) => {
         self.cancel_prompt();
-        self.set_status(format!("Rename failed: {e}"));
+        self.set_status(with_holders(format!("Rename failed: {e}"), &[entry.path]));
       }
     }
     Ok(())
   }
 
+  /// Point the clipboard, marks and expanded directories at `to` after
+  /// `from` was renamed, so the reload keeps them.
+  fn follow_rename(&mut self, from: &Path, to: &Path) {
+    for p in &mut self.clipboard.paths {
+      if *p == from {
+        *p = to.to_path_buf();
+      }
+    }
+    let marks = self.active_marks_mut();
+    if marks.remove(from) {
+      marks.insert(to.to_path_buf());
+    }
+    // Remap expanded paths so reload() preserves expansion under the new name
+    for e in &mut self.tree.entries {
+      if e.expanded
+        && let Ok(suffix) = e.path.strip_prefix(from)
+      {
+        e.path = to.join(suffix);
+      }
+    }
+  }
+
   fn execute_new_file(&mut self) -> Result<()> {
-    let name = self.prompt_input.trim().to_string();
+    let name = self.prompt.text.trim().to_string();
     if name.is_empty() {
       self.cancel_prompt();
       self.set_status("Name cannot be empty".to_string());
@@ -1727,14 +3903,30 @@ impl App {
       return Ok(());
     }
 
-    match std::fs::File::create(&new_path) {
-      Ok(_) => {
+    let template = self.pending_template.take();
+    let executable = self.new_file_executable;
+    let result = match template {
+      Some(ref content) => std::fs::write(&new_path, templates::render_content(content, &name)),
+      None => std::fs::File::create(&new_path).map(|_| ()),
+    }
+    .and_then(|()| match self.new_entry_mode() {
+      Some(mode) if self.new_file_executable => {
+        std::fs::set_permissions(&new_path, std::fs::Permissions::from_mode(mode))
+      }
+      _ => Ok(()),
+    });
+
+    match result {
+      Ok(()) => {
         self.cancel_prompt();
         self.tree.reload()?;
         self.tree_reloaded = true;
         self.rebuild_visible_cache();
         self.reposition_cursor_to(&new_path);
         self.set_status(format!("Created: {name}"));
+        self.log_op(OpRecord::now("create file").to(&new_path));
+        self.history.record(command::Create::file(new_path.clone()));
+        self.last_repeatable = Some(Repeatable::NewEntry { name, is_dir: false, executable, template });
         self.preview.invalidate();
         self.update_preview();
       }
@@ -1747,7 +3939,7 @@ impl App {
   }
 
   fn execute_new_dir(&mut self) -> Result<()> {
-    let name = self.prompt_input.trim().to_string();
+    let name = self.prompt.text.trim().to_string();
     if name.is_empty() {
       self.cancel_prompt();
       self.set_status("Name cannot be empty".to_string());
@@ -1763,6 +3955,7 @@ impl App {
       return Ok(());
     }
 
+    let create = command::Create::dir(new_path.clone());
     match std::fs::create_dir_all(&new_path) {
       Ok(()) => {
         self.cancel_prompt();
@@ -1771,6 +3964,9 @@ impl App {
         self.rebuild_visible_cache();
         self.reposition_cursor_to(&new_path);
         self.set_status(format!("Created dir: {name}"));
+        self.log_op(OpRecord::now("create dir").to(&new_path));
+        self.history.record(create);
+        self.last_repeatable = Some(Repeatable::NewEntry { name, is_dir: true, executable: false, template: None });
         self.preview.invalidate();
         self.update_preview();
       }
@@ -1779,135 +3975,484 @@ impl App {
         self.set_status(format!("Create dir failed: {e}"));
       }
     }
-    Ok(())
+    Ok(())
+  }
+
+  /// Create the symlink typed into the prompt, beside the entry it was
+  /// started on unless the path says otherwise. Naming a directory puts it
+  /// inside under the entry's name, like `ln -s`. The target is written
+  /// relative to the link, like `ln -sr`, so both can move together.
+  fn execute_symlink(&mut self) -> Result<()> {
+    let Some(target) = self.link_target.clone() else {
+      self.cancel_prompt();
+      return Ok(());
+    };
+    let text = self.prompt.text.trim().to_string();
+    self.cancel_prompt();
+    if text.is_empty() {
+      self.set_status("Name cannot be empty".to_string());
+      return Ok(());
+    }
+    let (Some(here), Some(target_name)) = (target.parent(), target.file_name()) else {
+      return Ok(());
+    };
+    let mut link = destination::expand(&text, here);
+    if link.is_dir() {
+      link.push(target_name);
+    }
+    let (Some(dir), Some(link_name)) = (link.parent(), link.file_name()) else {
+      return Ok(());
+    };
+    let name = link_name.to_string_lossy().to_string();
+    if link.symlink_metadata().is_ok() {
+      self.set_status(format!("{name} already exists"));
+      return Ok(());
+    }
+    // Resolve both directories first so `..` in either counts right
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    if self.restricted(&dir) {
+      return Ok(());
+    }
+    let here = here.canonicalize().unwrap_or_else(|_| here.to_path_buf());
+    let link = dir.join(link_name);
+    let relative = ops::relative_path(&dir, &here.join(target_name));
+
+    match std::os::unix::fs::symlink(&relative, &link) {
+      Ok(()) => {
+        self.tree.reload()?;
+        self.tree_reloaded = true;
+        self.rebuild_visible_cache();
+        self.reposition_cursor_to(&link);
+        self.set_status(format!("Linked {name} -> {}", relative.display()));
+        self.log_op(OpRecord::now("symlink").from(&target).to(&link));
+        self.history.record(command::Symlink { link, target: relative });
+        self.preview.invalidate();
+        self.update_preview();
+      }
+      Err(e) => self.set_status(format!("Symlink failed: {e}")),
+    }
+    Ok(())
+  }
+
+  fn extract_archive_start(&mut self, delete_after: bool) -> Result<()> {
+    let Some(entry) = self.selected_entry() else {
+      return Ok(());
+    };
+
+    let path = entry.path.clone();
+    let name = entry.name.clone();
+
+    if let Some(ref view) = self.archive_view {
+      return self.extract_entry_start(view.archive.clone(), &path, name, delete_after);
+    }
+
+    // Check if it's an archive
+    if !archive::is_archive(&path) {
+      self.set_status("Not an archive file".to_string());
+      return Ok(());
+    }
+
+    if self.jobs.involves(&path) {
+      self.set_status(format!("Already queued: {name}"));
+      return Ok(());
+    }
+
+    // Extracts next to the archive
+    match self.jobs.push(JobKind::Extract { path, delete_after }) {
+      None => self.set_status(format!("Extracting {name}...")),
+      Some(position) => self.set_status(format!("Queued extract of {name} (#{position})")),
+    }
+    Ok(())
+  }
+
+  /// Queue extracting the entry at `path` of the browsed `archive` next to
+  /// the archive.
+  fn extract_entry_start(&mut self, archive: PathBuf, path: &Path, name: String, delete_after: bool) -> Result<()> {
+    if delete_after {
+      self.set_status("Inside an archive: delete is not available".to_string());
+      return Ok(());
+    }
+    let Some(entry) = path.strip_prefix(&archive).ok().map(|p| p.to_string_lossy().to_string()) else {
+      return Ok(());
+    };
+    if self.jobs.involves(path) {
+      self.set_status(format!("Already queued: {name}"));
+      return Ok(());
+    }
+    match self.jobs.push(JobKind::ExtractEntry { archive, entry }) {
+      None => self.set_status(format!("Extracting {name}...")),
+      Some(position) => self.set_status(format!("Queued extract of {name} (#{position})")),
+    }
+    Ok(())
+  }
+
+  /// Handle jobs that finished since the last tick.
+  fn check_jobs_complete(&mut self) -> Result<bool> {
+    let finished = self.jobs.poll();
+    if finished.is_empty() {
+      return Ok(false);
+    }
+    let mut created = None;
+    for done in finished {
+      let (name, verb) = (done.job.name(), done.job.verb());
+      match (done.job.kind, done.result) {
+        (JobKind::Transfer { target_dir, cut, options, .. }, result) => {
+          let notes = self.finish_transfer(done.transferred, cut, options)?;
+          if done.cancelled {
+            self.set_status(format!("Cancelled {verb} of {name}"));
+          } else if let (Some(notes), Ok(())) = (notes, result) {
+            let verb = if cut { "Moved" } else { "Copied" };
+            let verified = verified_note(options.verify);
+            self.set_status(format!("{verb} {name} to {}{verified}{notes}", contract_home(&target_dir)));
+          }
+        }
+        (_, Err(_)) if done.cancelled => self.set_status(format!("Cancelled {verb} of {name}")),
+        (JobKind::Extract { path, delete_after }, Ok(())) => {
+          let dest_dir = path.parent().unwrap_or(&self.tree.root);
+          self.log_op(OpRecord::now("extract").from(&path).to(dest_dir));
+          if delete_after {
+            if let Err(e) = Self::remove_path(&path, self.use_trash) {
+              self.set_status(format!("Extracted but failed to delete: {e}"));
+            } else {
+              self.log_op(OpRecord::now(if self.use_trash { "trash" } else { "delete" }).from(&path));
+              let verb = if self.use_trash { "trashed" } else { "deleted" };
+              self.set_status(format!("Extracted and {verb}: {name}"));
+            }
+          } else {
+            self.set_status(format!("Extracted: {name}"));
+          }
+        }
+        (JobKind::ExtractEntry { archive, entry }, Ok(())) => {
+          let dest_dir = archive.parent().unwrap_or(&self.tree.root);
+          self.log_op(OpRecord::now("extract").from(&archive.join(entry)).to(dest_dir));
+          self.set_status(format!("Extracted: {name}"));
+        }
+        (JobKind::Compress { dest, .. }, Ok(())) => {
+          self.log_op(OpRecord::now("compress").to(&dest));
+          self.set_status(format!("Created: {name}"));
+          created = Some(dest);
+        }
+        (JobKind::Extract { .. } | JobKind::ExtractEntry { .. }, Err(e)) => self.set_status(format!("Extract failed: {e}")),
+        (JobKind::Compress { .. }, Err(e)) => self.set_status(format!("Compress failed: {e}")),
+      }
+    }
+    self.tree.reload()?;
+    self.tree_reloaded = true;
+    self.rebuild_visible_cache();
+    if let Some(dest) = created {
+      self.reposition_cursor_to(&dest);
+    }
+    self.jobs_cursor = self.jobs_cursor.min(self.jobs_rows().saturating_sub(1));
+    self.preview.invalidate();
+    self.update_preview();
+    Ok(true)
+  }
+
+  fn jobs_rows(&self) -> usize {
+    self.jobs.running().count() + self.jobs.queued_len()
+  }
+
+  fn jobs_move(&mut self, delta: i32) {
+    let len = self.jobs_rows();
+    if len == 0 {
+      return;
+    }
+    if delta > 0 {
+      self.jobs_cursor = (self.jobs_cursor + delta as usize).min(len - 1);
+    } else {
+      self.jobs_cursor = self.jobs_cursor.saturating_sub((-delta) as usize);
+    }
+  }
+
+  /// Drop the queued job under the cursor, or stop the running one.
+  fn jobs_cancel(&mut self) {
+    let running = self.jobs.running().count();
+    let Some(index) = self.jobs_cursor.checked_sub(running) else {
+      self.cancel_running_job(self.jobs_cursor);
+      return;
+    };
+    if let Some(job) = self.jobs.cancel(index) {
+      self.set_status(format!("Cancelled {} of {}", job.verb(), job.name()));
+      self.jobs_cursor = self.jobs_cursor.min(self.jobs_rows().saturating_sub(1));
+    }
+  }
+
+  /// Ask the running job at `index` to stop; it reports when it has.
+  fn cancel_running_job(&mut self, index: usize) {
+    let status = match self.jobs.cancel_running(index) {
+      Some(job) => format!("Cancelling {} of {}...", job.verb(), job.name()),
+      None => "Compressions can't be stopped once running".to_string(),
+    };
+    self.set_status(status);
+  }
+
+  fn execute_extract_and_delete(&mut self) -> Result<()> {
+    self.cancel_prompt();
+    self.extract_archive_start(true)
+  }
+
+  pub fn reposition_cursor_to(&mut self, path: &PathBuf) {
+    let pos = self.cached_visible.iter().position(|&idx| self.tree.entries[idx].path == *path);
+    if let Some(pos) = pos {
+      self.cursor = pos;
+      self.adjust_scroll();
+    }
+  }
+
+  fn cancel_prompt(&mut self) {
+    self.input_mode = InputMode::Normal;
+    self.prompt_kind = None;
+    self.prompt.clear();
+    self.pending_template = None;
+    self.link_target = None;
+    self.pending_patch = None;
+    self.pending_safe_names = None;
+    self.prompt_holders.clear();
+    self.holders_rx = None;
+    self.new_file_executable = false;
+    self.delete_summary = None;
+    self.destination = None;
+    self.paste_options = None;
+  }
+
+  fn templates_start(&mut self) {
+    let dir = self.current_dir();
+    let choices = templates::templates_for_dir(&dir, &self.template_rules);
+    if choices.is_empty() {
+      self.set_status("No templates for this directory".to_string());
+      return;
+    }
+    self.template_choices = choices;
+    self.template_cursor = 0;
+    self.input_mode = InputMode::Templates;
+  }
+
+  fn templates_move(&mut self, delta: i32) {
+    let len = self.template_choices.len();
+    if len == 0 {
+      return;
+    }
+    if delta > 0 {
+      self.template_cursor = (self.template_cursor + delta as usize).min(len - 1);
+    } else {
+      self.template_cursor = self.template_cursor.saturating_sub((-delta) as usize);
+    }
+  }
+
+  fn templates_select(&mut self) {
+    let Some(template) = self.template_choices.get(self.template_cursor).cloned() else {
+      self.input_mode = InputMode::Normal;
+      return;
+    };
+    // Pre-fill the new-file prompt with the template name so it can be edited
+    self.prompt.cursor = template.name.split('.').next().map(|s| s.chars().count()).unwrap_or(0);
+    self.prompt.text = template.name;
+    self.prompt_kind = Some(PromptKind::NewFile);
+    self.input_history.reset();
+    self.new_file_executable = template.content.starts_with("#!");
+    self.pending_template = Some(template.content);
+    self.input_mode = InputMode::Prompt;
+  }
+
+  fn worktrees_start(&mut self) {
+    let Some(repo) = self.active_tree().git_repo() else {
+      self.set_status("Not in a git repository".to_string());
+      return;
+    };
+    let worktrees = repo.worktrees();
+    let branches = repo.recent_branches(WORKTREE_BRANCH_LIMIT + worktrees.len());
+    let checked_out: HashSet<&str> = worktrees.iter().filter_map(|w| w.branch.as_deref()).collect();
+    let free: Vec<WorktreeChoice> = branches
+      .into_iter()
+      .filter(|b| !checked_out.contains(b.name.as_str()))
+      .take(WORKTREE_BRANCH_LIMIT)
+      .map(WorktreeChoice::Branch)
+      .collect();
+    self.worktree_choices = worktrees.into_iter().map(WorktreeChoice::Existing).chain(free).collect();
+    self.worktree_cursor = 0;
+    self.input_mode = InputMode::Worktrees;
+  }
+
+  fn worktrees_move(&mut self, delta: i32) {
+    let len = self.worktree_choices.len();
+    if len == 0 {
+      return;
+    }
+    if delta > 0 {
+      self.worktree_cursor = (self.worktree_cursor + delta as usize).min(len - 1);
+    } else {
+      self.worktree_cursor = self.worktree_cursor.saturating_sub((-delta) as usize);
+    }
+  }
+
+  /// Directory a new worktree for `branch` goes into: `worktree_dir`, or
+  /// `<repo>.worktrees` next to the main checkout.
+  fn worktree_path(&self, main: &Path, branch: &str) -> PathBuf {
+    let base = self.worktree_dir.clone().unwrap_or_else(|| {
+      let name = main.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+      main.with_file_name(format!("{name}.worktrees"))
+    });
+    base.join(branch.replace('/', "-"))
+  }
+
+  fn worktrees_select(&mut self) -> Result<()> {
+    self.input_mode = InputMode::Normal;
+    let Some(choice) = self.worktree_choices.get(self.worktree_cursor).cloned() else {
+      return Ok(());
+    };
+    let path = match choice {
+      WorktreeChoice::Existing(worktree) => {
+        if self.restricted(&worktree.path) {
+          return Ok(());
+        }
+        worktree.path
+      }
+      WorktreeChoice::Branch(branch) => {
+        if self.read_only {
+          self.set_status("Read-only mode: add worktree is disabled".to_string());
+          return Ok(());
+        }
+        let Some(main) = self.active_tree().git_repo().and_then(|repo| repo.worktrees().into_iter().find(|w| w.is_main))
+        else {
+          self.set_status("Not in a git repository".to_string());
+          return Ok(());
+        };
+        let path = self.worktree_path(&main.path, &branch.name);
+        // Before anything is created where the user couldn't go
+        if self.restricted(&path) {
+          return Ok(());
+        }
+        let Some(repo) = self.active_tree().git_repo() else {
+          return Ok(());
+        };
+        if let Err(e) = repo.add_worktree(&branch.name, &path) {
+          self.set_status(format!("Add worktree failed: {e}"));
+          return Ok(());
+        }
+        self.log_op(OpRecord::now("add worktree").to(&path).detail(branch.name.clone()));
+        self.set_status(format!("Added worktree for {} at {}", branch.name, path.display()));
+        path
+      }
+    };
+    self.go_to_dir(&path)
+  }
+
+  /// Whether header column `column` is on the branch name.
+  pub fn on_header_branch(&self, column: u16) -> bool {
+    self.header_branch.is_some_and(|(start, width)| (start..start + width).contains(&column))
+  }
+
+  fn branches_start(&mut self) {
+    let Some(repo) = self.active_tree().git_repo() else {
+      self.set_status("Not in a git repository".to_string());
+      return;
+    };
+    let current = repo.get_branch();
+    let branches = repo.recent_branches(usize::MAX);
+    // Start on the newest branch to hop to
+    self.branch_cursor = branches.iter().position(|b| Some(&b.name) != current.as_ref()).unwrap_or(0);
+    self.branch_choices = branches;
+    self.branch_current = current;
+    self.input_mode = InputMode::Branches;
+  }
+
+  fn branches_move(&mut self, delta: i32) {
+    let len = self.branch_choices.len();
+    if len == 0 {
+      return;
+    }
+    if delta > 0 {
+      self.branch_cursor = (self.branch_cursor + delta as usize).min(len - 1);
+    } else {
+      self.branch_cursor = self.branch_cursor.saturating_sub((-delta) as usize);
+    }
   }
 
-  fn extract_archive_start(&mut self, delete_after: bool) -> Result<()> {
-    // Don't start another extraction while one is in progress
-    if self.extracting.is_some() {
-      self.set_status("Extraction already in progress".to_string());
+  fn branches_select(&mut self) -> Result<()> {
+    let Some(branch) = self.branch_choices.get(self.branch_cursor).map(|b| b.name.clone()) else {
+      self.input_mode = InputMode::Normal;
+      return Ok(());
+    };
+    self.input_mode = InputMode::Normal;
+    if self.branch_current.as_ref() == Some(&branch) {
+      self.set_status(format!("Already on {branch}"));
       return Ok(());
     }
-
-    let Some(entry) = self.selected_entry() else {
+    let Some(repo) = self.active_tree().git_repo() else {
       return Ok(());
     };
+    let result = repo.checkout_branch(&branch);
+    self.finish_branch_switch(&branch, "checkout", "Switched to", result)
+  }
 
-    let path = entry.path.clone();
-    let name = entry.name.clone();
-
-    // Check if it's an archive
-    if !archive::is_archive(&path) {
-      self.set_status("Not an archive file".to_string());
+  fn execute_new_branch(&mut self) -> Result<()> {
+    let branch = self.prompt.text.trim().to_string();
+    self.cancel_prompt();
+    if branch.is_empty() {
+      self.set_status("Branch name cannot be empty".to_string());
       return Ok(());
     }
-
-    // Extract to parent directory of the archive
-    let dest_dir = path.parent().unwrap_or(&self.tree.root).to_path_buf();
-
-    // Show extracting status
-    self.set_status(format!("Extracting {name}..."));
-
-    // Spawn background thread for extraction
-    let (tx, rx) = mpsc::channel();
-    let extract_name = name.clone();
-    let extract_path = path.clone();
-
-    std::thread::spawn(move || {
-      let result = archive::extract_archive(&extract_path, &dest_dir);
-      let _ = tx.send(ExtractResult {
-        name: extract_name,
-        path: extract_path,
-        delete_after,
-        result,
-      });
-    });
-
-    self.extracting = Some(ExtractingState { rx });
-    Ok(())
-  }
-
-  fn check_extraction_complete(&mut self) -> Result<bool> {
-    let Some(ref state) = self.extracting else {
-      return Ok(false);
-    };
-
-    // Non-blocking check
-    let result = match state.rx.try_recv() {
-      Ok(r) => r,
-      Err(mpsc::TryRecvError::Empty) => return Ok(false),
-      Err(mpsc::TryRecvError::Disconnected) => {
-        self.extracting = None;
-        self.set_status("Extraction failed: thread died".to_string());
-        return Ok(true);
-      }
+    let Some(repo) = self.active_tree().git_repo() else {
+      return Ok(());
     };
-
-    // Clear extracting state
-    self.extracting = None;
-
-    match result.result {
-      Ok(()) => {
-        if result.delete_after {
-          if let Err(e) = Self::remove_path(&result.path, self.use_trash) {
-            self.set_status(format!("Extracted but failed to delete: {e}"));
-          } else {
-            let verb = if self.use_trash { "trashed" } else { "deleted" };
-            self.set_status(format!("Extracted and {verb}: {}", result.name));
-          }
-        } else {
-          self.set_status(format!("Extracted: {}", result.name));
-        }
-        self.tree.reload()?;
-        self.tree_reloaded = true;
-        self.rebuild_visible_cache();
-        self.preview.invalidate();
-        self.update_preview();
-      }
-      Err(e) => {
-        self.set_status(format!("Extract failed: {e}"));
-      }
-    }
-    Ok(true)
+    let result = repo.create_branch(&branch);
+    self.finish_branch_switch(&branch, "new branch", "Switched to new branch", result)
   }
 
-  fn extract_archive_start_confirm(&mut self) -> Result<()> {
-    let Some(entry) = self.selected_entry() else {
+  /// Report a checkout and reload what it changed: files, git status and
+  /// the preview. The root may not exist on the other branch.
+  fn finish_branch_switch(&mut self, branch: &str, verb: &str, done: &str, result: anyhow::Result<()>) -> Result<()> {
+    if let Err(e) = result {
+      self.set_status(format!("Could not {verb} {branch}: {e}"));
       return Ok(());
+    }
+    if let Some(root) = self.active_tree().git_repo().map(|r| r.root().to_path_buf()) {
+      self.log_op(OpRecord::now(verb).to(&root).detail(branch.to_string()));
+    }
+    self.reload_git_trees()?;
+    if !self.check_lost_root()? {
+      self.set_status(format!("{done} {branch}"));
+    }
+    let tree = match self.right_pane {
+      Some(ref pane) if self.dual_pane_mode && self.active_pane == 1 => &pane.tree,
+      _ => &self.tree,
     };
-
-    if !archive::is_archive(&entry.path) {
-      self.set_status("Not an archive file".to_string());
-      return Ok(());
+    if let Some(repo) = tree.git_repo() {
+      self.preview.request_git_commits(repo);
     }
-
-    let name = entry.name.clone();
-    self.prompt_kind = Some(PromptKind::ConfirmExtractAndDelete);
-    self.prompt_input.clear();
-    self.prompt_cursor = 0;
-    self.input_mode = InputMode::Prompt;
-    self.set_status(format!("Extract and delete {name}? (y/N)"));
+    self.refresh_preview();
     Ok(())
   }
 
-  fn execute_extract_and_delete(&mut self) -> Result<()> {
-    self.cancel_prompt();
-    self.extract_archive_start(true)
+  fn op_log_open(&mut self) {
+    if !self.op_log.enabled {
+      self.set_status("Operation log is off (set op_log = true)".to_string());
+      return;
+    }
+    let mut entries = self.op_log.read_since(op_log::today_start());
+    entries.reverse();
+    self.op_log_entries = entries;
+    self.op_log_cursor = 0;
+    self.input_mode = InputMode::OpLog;
   }
 
-  pub fn reposition_cursor_to(&mut self, path: &PathBuf) {
-    let pos = self.cached_visible.iter().position(|&idx| self.tree.entries[idx].path == *path);
-    if let Some(pos) = pos {
-      self.cursor = pos;
-      self.adjust_scroll();
+  fn op_log_move(&mut self, delta: i32) {
+    let len = self.op_log_entries.len();
+    if len == 0 {
+      return;
+    }
+    if delta > 0 {
+      self.op_log_cursor = (self.op_log_cursor + delta as usize).min(len - 1);
+    } else {
+      self.op_log_cursor = self.op_log_cursor.saturating_sub((-delta) as usize);
     }
   }
 
-  fn cancel_prompt(&mut self) {
-    self.input_mode = InputMode::Normal;
-    self.prompt_kind = None;
-    self.prompt_input.clear();
-    self.prompt_cursor = 0;
+  /// Append `record` to the operation log; a failed write only gets logged.
+  pub fn log_op(&self, record: OpRecord) {
+    if let Err(e) = self.op_log.append(&record) {
+      tracing::warn!("operation log write failed: {e}");
+    }
   }
 
   fn yank_path(&mut self) {
@@ -1930,19 +4475,33 @@ impl App {
     }
   }
 
+  /// Preview the selection, or with `auto_preview` off drop the preview of
+  /// whatever was selected before.
   pub fn update_preview(&mut self) {
+    if self.auto_preview {
+      self.load_preview();
+    } else {
+      self.preview.clear_shown();
+    }
+  }
+
+  fn load_preview(&mut self) {
     if self.dual_pane_mode && self.active_pane == 1 {
       if let Some(ref pane) = self.right_pane {
         let idx = pane.cached_visible.get(pane.cursor).copied();
         if let Some(idx) = idx {
-          let path = pane.tree.entries[idx].path.clone();
+          let entry = &pane.tree.entries[idx];
+          let path = entry.path.clone();
+          self.preview.git_status = entry.git_status;
           self.preview.request_preview(&path, self.picker.as_ref(), pane.tree.git_repo());
         }
       }
     } else {
       let idx = self.cached_visible.get(self.cursor).copied();
       if let Some(idx) = idx {
-        let path = self.tree.entries[idx].path.clone();
+        let entry = &self.tree.entries[idx];
+        let path = entry.path.clone();
+        self.preview.git_status = entry.git_status;
         self.preview.request_preview(&path, self.picker.as_ref(), self.tree.git_repo());
       }
     }
@@ -1962,6 +4521,340 @@ impl App {
       .and_then(|&idx| self.tree.entries.get(idx))
   }
 
+  /// Esc outside the modal dialogs peels back one layer at a time: a
+  /// pending `g` prefix, the active filter, marks, then overlays on the
+  /// preview, a running extraction or copy, and only then quits (when
+  /// `esc_quits` is on). Modal dialogs close through
+  /// `InputMode::escape_action` instead.
+  fn escape(&mut self) -> Result<()> {
+    if self.input_mode != InputMode::Normal {
+      let action = self.input_mode.escape_action();
+      if action == Action::Escape {
+        self.input_mode = InputMode::Normal;
+        self.macros.pending = None;
+        return Ok(());
+      }
+      return self.dispatch(action);
+    }
+    if self.preview_focused {
+      self.preview_focused = false;
+      return Ok(());
+    }
+    let filtered = if self.dual_pane_mode && self.active_pane == 1 {
+      self.right_pane.as_ref().is_some_and(|p| !p.search.is_empty())
+    } else {
+      !self.search.is_empty()
+    };
+    if filtered {
+      return self.dispatch(Action::SearchCancel);
+    }
+    if !self.active_marks().is_empty() {
+      self.clear_marks();
+      return Ok(());
+    }
+    if self.show_debug {
+      self.show_debug = false;
+      return Ok(());
+    }
+    if self.preview.blame_enabled {
+      self.preview.toggle_blame(self.tree.git_repo());
+      return Ok(());
+    }
+    if self.preview.following() {
+      self.toggle_follow();
+      return Ok(());
+    }
+    if self.preview.content.as_ref().is_some_and(|c| c.preview_type == PreviewType::Diff) {
+      self.preview.invalidate();
+      self.update_preview();
+      return Ok(());
+    }
+    let stoppable = self.jobs.running_progress().position(|(job, progress)| job.cancellable() && !progress.is_cancelled());
+    if let Some(index) = stoppable {
+      self.cancel_running_job(index);
+      return Ok(());
+    }
+    if self.esc_quits {
+      self.request_quit();
+    }
+    Ok(())
+  }
+
+  fn undo(&mut self) -> Result<()> {
+    let Some(mut command) = self.history.take_undo() else {
+      self.set_status("Nothing to undo".to_string());
+      return Ok(());
+    };
+    let description = command.describe();
+    match command.undo(self) {
+      Ok(()) => {
+        self.history.undone(command);
+        self.log_op(OpRecord::now("undo").detail(&description));
+        self.set_status(format!("Undid {description}"));
+      }
+      // Dropped: whatever is on disk now no longer matches the record
+      Err(e) => self.set_status(format!("Can't undo {description}: {e}")),
+    }
+    self.reload_after_history()
+  }
+
+  fn redo(&mut self) -> Result<()> {
+    let Some(mut command) = self.history.take_redo() else {
+      self.set_status("Nothing to redo".to_string());
+      return Ok(());
+    };
+    let description = command.describe();
+    match command.execute(self) {
+      Ok(()) => {
+        self.history.redone(command);
+        self.log_op(OpRecord::now("redo").detail(&description));
+        self.set_status(format!("Redid {description}"));
+      }
+      Err(e) => self.set_status(format!("Can't redo {description}: {e}")),
+    }
+    self.reload_after_history()
+  }
+
+  fn reload_after_history(&mut self) -> Result<()> {
+    self.tree.reload()?;
+    self.tree_reloaded = true;
+    self.rebuild_visible_cache();
+    let len = self.cached_visible.len();
+    self.cursor = self.cursor.min(len.saturating_sub(1));
+    self.preview.invalidate();
+    self.update_preview();
+    Ok(())
+  }
+
+  /// Run the last rename pattern, chmod, new file/dir or paste again on
+  /// the current selection.
+  fn repeat_last(&mut self) -> Result<()> {
+    let Some(last) = self.last_repeatable.clone() else {
+      self.set_status("Nothing to repeat".to_string());
+      return Ok(());
+    };
+    match last {
+      Repeatable::Rename(ref pattern) => {
+        let Some(entry) = self.selected_entry() else {
+          return Ok(());
+        };
+        let Some(new_name) = pattern.apply(&entry.name) else {
+          self.set_status(format!("Can't repeat {} on {}", last.describe(), entry.name));
+          return Ok(());
+        };
+        self.prompt_kind = Some(PromptKind::Rename);
+        self.prompt.set(new_name);
+        self.execute_rename()?;
+      }
+      Repeatable::Chmod { mode, recursive, follow_symlinks } => {
+        self.chmod_start();
+        if self.input_mode != InputMode::Chmod {
+          return Ok(());
+        }
+        let state = &mut self.chmod_state;
+        state.new_mode = (state.original_mode & !0o7777) | mode;
+        state.recursive = recursive && state.is_dir;
+        state.follow_symlinks = follow_symlinks;
+        self.chmod_apply()?;
+      }
+      Repeatable::NewEntry { ref name, is_dir, executable, ref template } => {
+        let dir = self.current_dir();
+        let next = repeat::next_numbered_name(name, is_dir, |n| dir.join(n).exists());
+        self.prompt.set(next);
+        if is_dir {
+          self.prompt_kind = Some(PromptKind::NewDir);
+          self.execute_new_dir()?;
+        } else {
+          self.prompt_kind = Some(PromptKind::NewFile);
+          self.new_file_executable = executable;
+          self.pending_template = template.clone();
+          self.execute_new_file()?;
+        }
+        // The new name is what the next repeat counts up from
+        return Ok(());
+      }
+      Repeatable::Paste => self.paste_clipboard()?,
+    }
+    // Re-deriving from the repeated operation could drift; keep the original
+    self.last_repeatable = Some(last);
+    Ok(())
+  }
+
+  /// Quit now, or arm/ask first depending on `quit_confirm`.
+  fn request_quit(&mut self) {
+    const DOUBLE_QUIT_WINDOW: Duration = Duration::from_secs(1);
+    match self.quit_confirm {
+      QuitConfirm::Instant => self.should_quit = true,
+      QuitConfirm::Double => {
+        if self.quit_armed_at.take().is_some_and(|at| at.elapsed() <= DOUBLE_QUIT_WINDOW) {
+          self.should_quit = true;
+        } else {
+          self.quit_armed_at = Some(Instant::now());
+          self.set_status("Press again to quit".to_string());
+        }
+      }
+      QuitConfirm::Prompt => self.ask(PromptKind::ConfirmQuit),
+    }
+  }
+
+  /// Path of the tree entry drawn at a screen cell, if any.
+  pub fn entry_at(&self, column: u16, row: u16) -> Option<PathBuf> {
+    // Inside the pane borders
+    let hit = |area: Rect| column > area.x && column + 1 < area.right() && row > area.y && row + 1 < area.bottom();
+    let (visible, scroll, tree, area) = if hit(self.tree_area) {
+      (&self.cached_visible, self.tree_scroll_offset, &self.tree, self.tree_area)
+    } else if let Some(area) = self.right_tree_area.filter(|a| hit(*a))
+      && let Some(ref pane) = self.right_pane
+    {
+      (&pane.cached_visible, pane.scroll_offset, &pane.tree, area)
+    } else {
+      return None;
+    };
+    let idx = *visible.get(scroll + (row - area.y - 1) as usize)?;
+    tree.entries.get(idx).map(|e| e.path.clone())
+  }
+
+  /// Track the entry under the mouse pointer for the hover popup. Returns
+  /// true when a visible popup went away.
+  pub fn mouse_moved(&mut self, column: u16, row: u16) -> bool {
+    if self.hover_preview_ms == 0 || self.input_mode != InputMode::Normal {
+      return self.hover.take().is_some_and(|h| h.preview.is_some());
+    }
+    let path = self.entry_at(column, row);
+    if let Some(ref hover) = self.hover
+      && Some(&hover.path) == path.as_ref()
+    {
+      return false;
+    }
+    let had_popup = self.hover.as_ref().is_some_and(|h| h.preview.is_some());
+    self.hover = path.map(|p| Hover::new(p, column, row));
+    had_popup
+  }
+
+  /// Load the hover popup once the pointer has rested long enough. Returns
+  /// true when it became ready to draw.
+  pub fn poll_hover(&mut self) -> bool {
+    let delay = Duration::from_millis(self.hover_preview_ms);
+    self.hover.as_mut().is_some_and(|h| h.poll(delay))
+  }
+
+  /// Directories on screen in either pane.
+  fn dirs_on_screen(&self) -> Vec<&FileEntry> {
+    let mut dirs = Vec::new();
+    let mut panes = vec![(&self.tree, &self.cached_visible, self.tree_scroll_offset)];
+    if let Some(ref pane) = self.right_pane {
+      panes.push((&pane.tree, &pane.cached_visible, pane.scroll_offset));
+    }
+    for (tree, visible, offset) in panes {
+      // Only real directories; archive and remote sources list differently
+      if !tree.source().is_local() {
+        continue;
+      }
+      for &i in visible.iter().skip(offset).take(self.viewport_height) {
+        let entry = &tree.entries[i];
+        if entry.is_dir && !entry.is_cycle {
+          dirs.push(entry);
+        }
+      }
+    }
+    dirs
+  }
+
+  /// Count the collapsed directories on screen in either pane and take
+  /// finished counts. Returns true when one changed.
+  pub fn poll_dir_counts(&mut self) -> bool {
+    if !self.dir_counts.enabled {
+      return false;
+    }
+    let dirs: Vec<PathBuf> = self.dirs_on_screen().into_iter().filter(|e| !e.expanded).map(|e| e.path.clone()).collect();
+    self.dir_counts.request(dirs.iter().map(PathBuf::as_path), dir_counts::work);
+    self.dir_counts.poll()
+  }
+
+  /// Size the directories on screen for the size column and take finished
+  /// sizes. Returns true when one changed.
+  pub fn poll_dir_sizes(&mut self) -> bool {
+    if !self.dir_sizes.enabled {
+      return false;
+    }
+    // A linked directory may be anywhere; its size is not this tree's
+    let dirs: Vec<PathBuf> = self.dirs_on_screen().into_iter().filter(|e| !e.is_symlink).map(|e| e.path.clone()).collect();
+    self.dir_sizes.request(dirs.iter().map(PathBuf::as_path), dir_sizes::work);
+    self.dir_sizes.poll()
+  }
+
+  /// Check the labels of the entries on screen in either pane and take
+  /// finished checks. Returns true when a flag changed.
+  pub fn poll_labels(&mut self) -> bool {
+    if !self.labels.enabled {
+      return false;
+    }
+    let mut paths = Vec::new();
+    let mut panes = vec![(&self.tree, &self.cached_visible, self.tree_scroll_offset)];
+    if let Some(ref pane) = self.right_pane {
+      panes.push((&pane.tree, &pane.cached_visible, pane.scroll_offset));
+    }
+    for (tree, visible, offset) in panes {
+      if !tree.source().is_local() {
+        continue;
+      }
+      for &i in visible.iter().skip(offset).take(self.viewport_height) {
+        paths.push(tree.entries[i].path.clone());
+      }
+    }
+    let runner = self.runner.clone();
+    self.labels.request(paths.iter().map(PathBuf::as_path), || labels::work(runner));
+    self.labels.poll()
+  }
+
+  /// Take the delete popup's counts once ready. Returns true when they
+  /// arrived.
+  pub fn poll_delete_summary(&mut self) -> bool {
+    let Some(ref mut pending) = self.delete_summary else {
+      return false;
+    };
+    if pending.summary.is_some() {
+      return false;
+    }
+    match pending.rx.try_recv() {
+      Ok(summary) => {
+        pending.summary = Some(summary);
+        true
+      }
+      Err(_) => false,
+    }
+  }
+
+  /// Directory of the active pane, printed on exit with `print_last_dir`.
+  pub fn last_dir(&self) -> &Path {
+    if self.dual_pane_mode && self.active_pane == 1
+      && let Some(ref pane) = self.right_pane
+    {
+      return &pane.tree.root;
+    }
+    &self.tree.root
+  }
+
+  /// `window_title` with `{dir}` (the active pane's directory, `~` for
+  /// home), `{name}` (its last component) and `{project}` (repository name
+  /// plus the path inside it, or `{dir}` outside a repository) filled in.
+  pub fn window_title(&self, template: &str) -> String {
+    let tree = self.active_tree();
+    let dir = contract_home(&tree.root);
+    let name = tree.root.file_name().map_or_else(|| dir.clone(), |n| n.to_string_lossy().to_string());
+    let project = tree
+      .git_repo()
+      .and_then(|repo| {
+        let repo_name = repo.root().file_name()?.to_string_lossy().to_string();
+        let rel = tree.root.strip_prefix(repo.root()).ok()?;
+        Some(if rel.as_os_str().is_empty() { repo_name } else { format!("{repo_name}/{}", rel.display()) })
+      })
+      .unwrap_or_else(|| dir.clone());
+    let title = template.replace("{dir}", &dir).replace("{name}", &name).replace("{project}", &project);
+    // A BEL or ESC in a directory name would end the title sequence early
+    title.chars().filter(|c| !c.is_control()).collect()
+  }
+
   fn current_dir(&self) -> PathBuf {
     if let Some(entry) = self.selected_entry() {
       if entry.is_dir {
@@ -1980,10 +4873,10 @@ impl App {
   }
 
   pub fn rebuild_visible_cache(&mut self) {
-    if self.search_query.is_empty() {
+    if self.search.is_empty() {
       self.cached_visible = (0..self.tree.entries.len()).collect();
     } else {
-      let query = self.search_query.to_lowercase();
+      let query = self.search.text.to_lowercase();
       self.cached_visible = self
         .tree
         .entries
@@ -2001,6 +4894,9 @@ impl App {
   }
 
   pub fn show_error(&mut self, errors: Vec<String>) {
+    for error in &errors {
+      tracing::warn!("{error}");
+    }
     self.error_messages = errors;
     self.input_mode = InputMode::Error;
   }
@@ -2008,16 +4904,90 @@ impl App {
   pub fn apply_config(&mut self, config: &Config) {
     self.custom_apps = config.custom_apps.clone();
     self.claude_yolo = config.claude_yolo;
+    self.ai_command = config.ai_command.clone();
+    self.ai_command_alt = config.ai_command_alt.clone();
+    self.tmux_window_command = config.tmux_window_command.clone();
+    self.tmux_pane_command = config.tmux_pane_command.clone();
+    self.zellij_tab_command = config.zellij_tab_command.clone();
+    self.terminal_command = config.terminal_command.clone();
     self.use_trash = config.use_trash;
+    self.difftool = config.difftool.clone();
     self.has_apps_file = config.has_apps_file;
+    self.template_rules = config.template_rules.clone();
+    self.chmod_presets = config.chmod_presets.clone();
+    self.quit_confirm = config.quit_confirm;
+    self.safe_names = config.safe_names;
+    self.esc_quits = config.esc_quits;
+    self.print_last_dir = config.print_last_dir;
+    self.read_only = config.read_only;
+    self.hover_preview_ms = config.hover_preview_ms;
+    self.auto_preview = config.auto_preview;
+    self.input_history.set_limit(config.history_size);
+    self.jobs.set_limit(config.max_jobs);
+    self.copy_options = config.copy_options;
+    self.preview.thumbnails = config.image_thumbnails;
+    self.preview.timeout = Duration::from_millis(config.preview_timeout_ms);
+    self.worktree_dir = config.worktree_dir.clone();
+    self.op_log.enabled = config.op_log;
+    self.dir_counts.enabled = config.dir_counts;
+    self.focus_order = config.focus_order.clone();
     self.tree.set_ignore_patterns(config.ignore_glob_set.clone());
+    self.tree.set_sort(self.tree.dirs_first, config.pinned_glob_set.clone());
+    if let Some(ref mut pane) = self.right_pane {
+      pane.tree.set_sort(pane.tree.dirs_first, config.pinned_glob_set.clone());
+    }
     self.preview.set_syntax_theme(&config.syntax_theme);
     self.preview.set_theme(config.theme.clone());
+    self.theme_name = config.theme_name.clone();
+    self.icons = config.icons;
+    self.git_markers = config.git_markers;
+  }
+
+  /// Write the state files whose changes have settled, or every unsaved
+  /// one when `all` (on exit). The session journal is noted here each call;
+  /// on exit it is removed rather than written.
+  pub fn flush_state(&mut self, all: bool) {
+    let state = self.session_state();
+    if let Some(journal) = self.journal.as_mut()
+      && journal.note(state)
+    {
+      self.state_writer.mark(StateFile::Session);
+    }
+    let now = Instant::now();
+    let files = if all { self.state_writer.take_all() } else { self.state_writer.take_due(now) };
+    for file in files {
+      if !(all && file == StateFile::Session) {
+        self.save_state(file, now);
+      }
+    }
+  }
+
+  fn save_state(&mut self, file: StateFile, now: Instant) {
+    let result = match file {
+      StateFile::Favorites => self.favorites.save(),
+      StateFile::Layouts => self.layouts.save(),
+      StateFile::Bookmarks => self.bookmarks.save(),
+      StateFile::History => self.input_history.save(),
+      StateFile::Session => self.journal.as_mut().map_or(Ok(()), Journal::flush),
+    };
+    match result {
+      // The favorites file is watched for other instances' changes
+      Ok(()) if file == StateFile::Favorites => self.state_writer.wrote_watched(self.favorites.path(), now),
+      Ok(()) => {}
+      Err(e) if file == StateFile::Favorites => self.set_status(format!("Save favorites failed: {e}")),
+      Err(e) if file == StateFile::Layouts => self.set_status(format!("Save layouts failed: {e}")),
+      Err(e) if file == StateFile::Bookmarks => self.set_status(format!("Save bookmarks failed: {e}")),
+      Err(e) => tracing::warn!("saving {file:?} failed: {e}"),
+    }
   }
 
   pub fn reload_favorites(&mut self) {
+    // Loading over unsaved changes would drop them
+    if self.state_writer.take(StateFile::Favorites) {
+      self.save_state(StateFile::Favorites, Instant::now());
+    }
     self.favorites = Favorites::load();
-    if self.favorites.len() == 0 {
+    if self.favorites.is_empty() {
       self.favorites_cursor = 0;
     } else {
       self.favorites_cursor = self.favorites_cursor.min(self.favorites.len() - 1);
@@ -2033,44 +5003,244 @@ impl App {
           println!("{}", path.display());
         }
       }
-      PickerOutput::ChooserFile(file) | PickerOutput::DirectoryChooserFile(file) => {
-        let mut f = std::fs::File::create(file)
-          .map_err(|e| format!("Failed to write chooser file: {e}"))?;
-        for path in &self.picked_paths {
-          writeln!(f, "{}", path.display())
-            .map_err(|e| format!("Failed to write chooser file: {e}"))?;
-        }
+      PickerOutput::ChooserFile(file) | PickerOutput::DirectoryChooserFile(file) => {
+        let mut f = std::fs::File::create(file)
+          .map_err(|e| format!("Failed to write chooser file: {e}"))?;
+        for path in &self.picked_paths {
+          writeln!(f, "{}", path.display())
+            .map_err(|e| format!("Failed to write chooser file: {e}"))?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  pub fn handle_suspend(&mut self) -> Option<SuspendAction> {
+    self.should_suspend.take()
+  }
+
+  /// The program a suspend action runs in the foreground.
+  pub fn suspend_command(action: &SuspendAction) -> ProcessSpec {
+    match action {
+      SuspendAction::Editor(path) => {
+        let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vim".to_string());
+        ProcessSpec::new(editor).arg(path)
+      }
+      SuspendAction::Ai(cmd, dir) => ProcessSpec::new("sh").arg("-c").arg(cmd).current_dir(dir),
+      SuspendAction::Shell(dir) => {
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        ProcessSpec::new(shell).current_dir(dir)
+      }
+      SuspendAction::OpenWith(cmd, path) => ProcessSpec::new(cmd).arg(path),
+      // Run through the shell so the configured command may contain quoting and flags
+      SuspendAction::DiffTool(cmd, repo_root, rel_path) => ProcessSpec::new("sh")
+        .arg("-c")
+        .arg(format!("{cmd} \"$1\""))
+        .arg("tfl")
+        .arg(rel_path)
+        .current_dir(repo_root),
+    }
+  }
+
+  pub fn execute_suspend(action: &SuspendAction, runner: &dyn ProcessRunner) -> Result<()> {
+    runner.run(&Self::suspend_command(action))?;
+    Ok(())
+  }
+
+  /// Launch the AI CLI in the repository root (or the current directory)
+  /// with the marked entries, or the file under the cursor, as context.
+  fn open_ai(&mut self, alt: bool) {
+    let template = if self.claude_yolo != alt { &self.ai_command_alt } else { &self.ai_command };
+    if template.trim().is_empty() {
+      self.set_status("No AI command configured".to_string());
+      return;
+    }
+    let current = self.current_dir();
+    let dir = self
+      .tree
+      .git_repo()
+      .map(|r| r.root().to_path_buf())
+      .filter(|root| !root.as_os_str().is_empty() && current.starts_with(root))
+      .unwrap_or(current);
+    let marks = self.active_marks();
+    let mut paths: Vec<PathBuf> = if marks.is_empty() {
+      self.selected_entry().filter(|e| !e.is_dir).map(|e| e.path.clone()).into_iter().collect()
+    } else {
+      marks.iter().cloned().collect()
+    };
+    paths.sort();
+    let files: Vec<String> = paths
+      .iter()
+      .map(|p| p.strip_prefix(&dir).unwrap_or(p).to_string_lossy().into_owned())
+      .collect();
+    let cmd = opener::expand_ai_command(template, &files);
+    self.should_suspend = Some(SuspendAction::Ai(cmd, dir));
+  }
+
+  /// Reset the SELinux labels of the targets with `restorecon`, descending
+  /// into directories.
+  fn restorecon(&mut self) {
+    if !self.labels.enabled {
+      self.set_status("SELinux is not enabled".to_string());
+      return;
+    }
+    let targets = self.operation_targets();
+    if targets.is_empty() {
+      return;
+    }
+    let recursive = targets.iter().any(|p| p.is_dir());
+    let spec = selinux::restorecon(&targets, recursive);
+    match self.runner.output(&spec) {
+      Ok((true, output)) => {
+        let n = selinux::relabeled_count(&output);
+        self.set_status(match n {
+          0 => "Labels already match the policy".to_string(),
+          1 => "Relabeled 1 entry".to_string(),
+          n => format!("Relabeled {n} entries"),
+        });
+        for path in &targets {
+          self.log_op(OpRecord::now("restorecon").from(path).detail(format!("{n} relabeled")));
+        }
+      }
+      Ok((false, _)) => self.set_status("restorecon failed (permission denied?)".to_string()),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+        self.set_status("restorecon not found (install policycoreutils)".to_string())
       }
+      Err(e) => self.set_status(format!("restorecon failed: {e}")),
     }
-    Ok(())
+    self.labels.invalidate(&targets);
+    self.preview.invalidate();
+    self.update_preview();
   }
 
-  pub fn handle_suspend(&mut self) -> Option<SuspendAction> {
-    self.should_suspend.take()
+  /// Open `terminal_command` in the current directory and keep tfl usable,
+  /// or suspend for `$SHELL` without one or on a plain TTY.
+  fn open_shell(&mut self) {
+    let dir = self.current_dir();
+    if self.terminal_command.trim().is_empty() || !self.graphical_session {
+      self.should_suspend = Some(SuspendAction::Shell(dir));
+      return;
+    }
+    // nohup and no output, so the terminal survives tfl and stays off the UI
+    let cmd = format!("nohup {} >/dev/null 2>&1", opener::expand_dir_command(&self.terminal_command, &dir));
+    match self.runner.spawn(&ProcessSpec::new("sh").arg("-c").arg(cmd).current_dir(&dir)) {
+      Ok(()) => self.set_status(format!("Opened terminal in {}", contract_home(&dir))),
+      Err(e) => {
+        tracing::warn!("terminal_command failed: {e}");
+        self.should_suspend = Some(SuspendAction::Shell(dir));
+      }
+    }
   }
 
-  pub fn execute_suspend(action: &SuspendAction) -> Result<()> {
-    match action {
-      SuspendAction::Editor(path) => {
-        let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vim".to_string());
-        Command::new(&editor).arg(path).status()?;
-      }
-      SuspendAction::Claude(dir, yolo) => {
-        let mut cmd = Command::new("claude");
-        if *yolo {
-          cmd.arg("--dangerously-skip-permissions");
-        }
-        cmd.current_dir(dir).status()?;
-      }
-      SuspendAction::Shell(dir) => {
-        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
-        Command::new(&shell).current_dir(dir).status()?;
-      }
-      SuspendAction::OpenWith(cmd, path) => {
-        Command::new(cmd).arg(path).status()?;
+  /// Open the current directory in a new tmux window or pane or zellij tab,
+  /// leaving tfl running.
+  fn open_in_multiplexer(&mut self, action: &Action) {
+    let (env, template, what) = match action {
+      Action::TmuxWindow => ("TMUX", &self.tmux_window_command, "tmux window"),
+      Action::TmuxPane => ("TMUX", &self.tmux_pane_command, "tmux pane"),
+      Action::ZellijTab => ("ZELLIJ", &self.zellij_tab_command, "zellij tab"),
+      _ => return,
+    };
+    let multiplexer = what.split(' ').next().unwrap_or(what);
+    if std::env::var_os(env).is_none() {
+      self.set_status(format!("Not running inside {multiplexer}"));
+      return;
+    }
+    if template.trim().is_empty() {
+      self.set_status(format!("No {what} command configured"));
+      return;
+    }
+    let dir = self.current_dir();
+    let spec = ProcessSpec::new("sh").arg("-c").arg(opener::expand_dir_command(template, &dir)).current_dir(&dir);
+    // Captured so the multiplexer's messages don't land on the UI
+    match self.runner.output(&spec) {
+      Ok((true, _)) => self.set_status(format!("Opened {what} in {}", contract_home(&dir))),
+      Ok((false, _)) => self.set_status(format!("Failed to open {what}")),
+      Err(e) => self.set_status(format!("Failed to open {what}: {e}")),
+    }
+  }
+
+  fn open_difftool(&mut self) {
+    let Some(entry) = self.selected_entry() else {
+      return;
+    };
+    if entry.is_dir {
+      return;
+    }
+    let path = entry.path.clone();
+    let Some(repo_root) = self.tree.git_repo().map(|r| r.root().to_path_buf()) else {
+      self.set_status("Not in a git repository".to_string());
+      return;
+    };
+    let Ok(rel_path) = path.strip_prefix(&repo_root).map(|p| p.to_path_buf()) else {
+      self.set_status("File is outside the git repository".to_string());
+      return;
+    };
+    if self.difftool.trim().is_empty() {
+      self.set_status("No difftool configured".to_string());
+      return;
+    }
+    self.should_suspend = Some(SuspendAction::DiffTool(self.difftool.clone(), repo_root, rel_path));
+  }
+
+  /// Fill the properties overlay for the marked entries or the selection.
+  fn load_properties(&mut self) -> bool {
+    let marks = self.active_marks();
+    if marks.len() > 1 {
+      let mut paths: Vec<PathBuf> = marks.iter().cloned().collect();
+      paths.sort();
+      self.file_properties = None;
+      self.multi_properties = MultiProperties::from_paths(&paths);
+      return self.multi_properties.is_some();
+    }
+    self.multi_properties = None;
+    self.file_properties = self.selected_entry().and_then(|e| FileProperties::from_path(&e.path));
+    if self.labels.enabled
+      && let Some(ref mut props) = self.file_properties
+      && props.security_context.is_some()
+    {
+      let path = PathBuf::from(&props.path);
+      props.default_context = selinux::default_contexts(self.runner.as_ref(), std::slice::from_ref(&path)).remove(&path);
+    }
+    self.file_properties.is_some()
+  }
+
+  /// Add execute bits to every targeted file, or remove them if all of the
+  /// files are already executable. Directories are left alone.
+  fn properties_toggle_executable(&mut self) {
+    let files: Vec<(PathBuf, u32)> = self
+      .operation_targets()
+      .into_iter()
+      .filter_map(|p| {
+        let meta = std::fs::metadata(&p).ok().filter(|m| !m.is_dir())?;
+        Some((p, meta.permissions().mode()))
+      })
+      .collect();
+    if files.is_empty() {
+      self.set_status("No files to change".to_string());
+      return;
+    }
+    let make_executable = files.iter().any(|(_, mode)| mode & 0o111 == 0);
+    let mut failed = 0;
+    for (path, mode) in &files {
+      let new_mode = if make_executable { ops::with_execute(*mode) } else { mode & !0o111 };
+      if std::fs::set_permissions(path, std::fs::Permissions::from_mode(new_mode)).is_err() {
+        failed += 1;
       }
     }
-    Ok(())
+    let changed = files.len() - failed;
+    let mut status = if make_executable {
+      format!("Made {changed} file(s) executable")
+    } else {
+      format!("Removed execute bit from {changed} file(s)")
+    };
+    if failed > 0 {
+      status.push_str(&format!(", {failed} failed"));
+    }
+    self.set_status(status);
+    self.load_properties();
+    self.preview.invalidate();
+    self.update_preview();
   }
 
   fn chmod_start(&mut self) {
@@ -2079,7 +5249,12 @@ impl App {
     };
 
     let path = entry.path.clone();
-    let is_dir = entry.is_dir;
+    let is_symlink = entry.is_symlink;
+    let mut others = self.operation_targets();
+    others.retain(|p| *p != path);
+    others.sort();
+    // Recursion is offered when any of the targets is a directory
+    let is_dir = entry.is_dir || others.iter().any(|p| p.is_dir());
 
     let Ok(metadata) = std::fs::metadata(&path) else {
       self.set_status("Cannot read file metadata".to_string());
@@ -2087,13 +5262,18 @@ impl App {
     };
 
     let mode = metadata.permissions().mode();
+    let owner_uid = std::os::unix::fs::MetadataExt::uid(&metadata);
 
     self.chmod_state = ChmodState {
       path,
+      others,
       original_mode: mode,
       new_mode: mode,
       is_dir,
       recursive: false,
+      is_symlink,
+      follow_symlinks: false,
+      owner_uid,
       octal_mode: false,
       octal_input: String::new(),
     };
@@ -2105,6 +5285,7 @@ impl App {
     // 0-2: owner r/w/x (shifts 8,7,6)
     // 3-5: group r/w/x (shifts 5,4,3)
     // 6-8: others r/w/x (shifts 2,1,0)
+    // 9-11: setuid/setgid/sticky (shifts 11,10,9)
     let shift = match bit {
       0 => 8, // owner read
       1 => 7, // owner write
@@ -2115,6 +5296,9 @@ impl App {
       6 => 2, // others read
       7 => 1, // others write
       8 => 0, // others execute
+      9 => 11, // setuid
+      10 => 10, // setgid
+      11 => 9, // sticky
       _ => return,
     };
     self.chmod_state.new_mode ^= 1 << shift;
@@ -2160,7 +5344,7 @@ impl App {
     self.chmod_state.octal_mode = !self.chmod_state.octal_mode;
     if self.chmod_state.octal_mode {
       // Pre-fill octal input with current mode
-      self.chmod_state.octal_input = format!("{:03o}", self.chmod_state.new_mode & 0o777);
+      self.chmod_state.octal_input = ChmodState::octal(self.chmod_state.new_mode);
     }
   }
 
@@ -2170,48 +5354,106 @@ impl App {
     }
   }
 
+  fn chmod_toggle_follow_symlinks(&mut self) {
+    self.chmod_state.follow_symlinks = !self.chmod_state.follow_symlinks;
+  }
+
+  fn chmod_apply_preset(&mut self, index: usize) -> Result<()> {
+    let Some(preset) = self.chmod_presets.get(index) else {
+      return Ok(());
+    };
+    let state = &self.chmod_state;
+    let bits = match preset.mode {
+      ChmodPresetMode::Octal(mode) => mode,
+      ChmodPresetMode::MatchParent => {
+        let Some(parent_mode) = state.path.parent().and_then(|p| std::fs::metadata(p).ok()) else {
+          self.set_status("Cannot read parent directory permissions".to_string());
+          return Ok(());
+        };
+        let mode = parent_mode.permissions().mode() & 0o777;
+        if state.is_dir { mode } else { mode & !0o111 }
+      }
+    };
+    self.chmod_state.new_mode = (state.original_mode & !0o7777) | bits;
+    self.chmod_state.octal_mode = false;
+    self.chmod_state.octal_input.clear();
+    self.chmod_apply()
+  }
+
   fn chmod_apply(&mut self) -> Result<()> {
     let path = self.chmod_state.path.clone();
     let new_mode = self.chmod_state.new_mode;
     let recursive = self.chmod_state.recursive && self.chmod_state.is_dir;
 
-    if recursive {
-      self.chmod_recursive(&path, new_mode)?;
-    } else {
-      self.chmod_single(&path, new_mode)?;
+    let follow_symlinks = self.chmod_state.follow_symlinks;
+    let mode_str = ChmodState::octal(new_mode);
+
+    let mut targets = vec![path.clone()];
+    targets.extend(self.chmod_state.others.iter().cloned());
+    if targets.len() == 1 && self.chmod_state.is_symlink && !follow_symlinks {
+      self.set_status(format!(
+        "{} is a symlink; enable follow symlinks (l) to change its target",
+        path.display()
+      ));
+      self.input_mode = InputMode::Normal;
+      return Ok(());
     }
 
-    let mode_str = format!("{:03o}", new_mode & 0o777);
-    if recursive {
-      self.set_status(format!("Permissions set to {mode_str} (recursive)"));
-    } else {
-      self.set_status(format!("Permissions set to {mode_str}"));
+    let mut progress = ChmodWalk { follow_symlinks, visited: walk::VisitedDirs::default(), skipped_links: 0 };
+    for target in &targets {
+      if recursive {
+        self.chmod_recursive(target, new_mode, &mut progress)?;
+      } else if targets.len() > 1 && !follow_symlinks && target.symlink_metadata().is_ok_and(|m| m.is_symlink()) {
+        progress.skipped_links += 1;
+      } else {
+        self.chmod_single(target, new_mode)?;
+      }
+    }
+    let mut note = walk::cycle_note(progress.visited.cycles);
+    match progress.skipped_links {
+      0 => {}
+      1 => note.push_str(" (skipped 1 symlink)"),
+      n => note.push_str(&format!(" (skipped {n} symlinks)")),
+    }
+    let items = match targets.len() {
+      1 => String::new(),
+      n => format!(" of {n} items"),
+    };
+    let how = if recursive { " (recursive)" } else { "" };
+    self.set_status(format!("Permissions{items} set to {mode_str}{how}{note}"));
+    let detail = if recursive { format!("{mode_str} recursive") } else { mode_str };
+    for target in &targets {
+      self.log_op(OpRecord::now("chmod").from(target).detail(detail.clone()));
     }
 
     self.input_mode = InputMode::Normal;
+    self.last_repeatable = Some(Repeatable::Chmod { mode: new_mode & 0o7777, recursive, follow_symlinks });
     self.preview.invalidate();
     self.update_preview();
     Ok(())
   }
 
-  fn chmod_single(&self, path: &PathBuf, mode: u32) -> Result<()> {
+  fn chmod_single(&self, path: &Path, mode: u32) -> Result<()> {
     let permissions = std::fs::Permissions::from_mode(mode);
     std::fs::set_permissions(path, permissions)?;
     Ok(())
   }
 
-  fn chmod_recursive(&self, path: &PathBuf, mode: u32) -> Result<()> {
+  fn chmod_recursive(&self, path: &Path, mode: u32, progress: &mut ChmodWalk) -> Result<()> {
+    // There is no lchmod on Linux: changing a link always changes its target
+    if !progress.follow_symlinks && path.symlink_metadata().is_ok_and(|m| m.is_symlink()) {
+      progress.skipped_links += 1;
+      return Ok(());
+    }
+    let is_dir = path.is_dir();
+    if is_dir && !progress.visited.enter(path) {
+      return Ok(());
+    }
     self.chmod_single(path, mode)?;
 
-    if path.is_dir() {
+    if is_dir {
       for entry in std::fs::read_dir(path)? {
-        let entry = entry?;
-        let entry_path = entry.path();
-        if entry_path.is_dir() {
-          self.chmod_recursive(&entry_path, mode)?;
-        } else {
-          self.chmod_single(&entry_path, mode)?;
-        }
+        self.chmod_recursive(&entry?.path(), mode, progress)?;
       }
     }
 
@@ -2273,10 +5515,6 @@ impl App {
   }
 
   fn compress_start(&mut self) {
-    if self.compressing.is_some() {
-      self.set_status("Compression already in progress".to_string());
-      return;
-    }
     let targets = self.operation_targets();
     if targets.is_empty() {
       self.set_status("No files selected".to_string());
@@ -2308,70 +5546,108 @@ impl App {
     };
 
     let dest_dir = self.tree.root.clone();
-    let raw_dest = dest_dir.join(format!("{stem}.{format}"));
-    let dest = ops::unique_dest_path(&raw_dest);
+    let mut dest = ops::unique_dest_path(&dest_dir.join(format!("{stem}.{format}")));
+    // A queued compression hasn't created its archive yet
+    let mut n = 2;
+    while self.jobs.involves(&dest) {
+      dest = ops::unique_dest_path(&dest_dir.join(format!("{stem}_{n}.{format}")));
+      n += 1;
+    }
 
     let dest_name = dest.file_name()
       .and_then(|n| n.to_str())
       .unwrap_or("archive")
       .to_string();
 
-    self.set_status(format!("Compressing to {dest_name}..."));
     self.input_mode = InputMode::Normal;
 
     // Clear marks
     self.active_marks_mut().clear();
 
-    let (tx, rx) = mpsc::channel();
-    let format_owned = format.to_string();
-    let targets_owned = targets;
-    let dest_owned = dest.clone();
-
-    std::thread::spawn(move || {
-      let result = archive::compress_to_archive(&targets_owned, &dest_owned, &format_owned);
-      let _ = tx.send(CompressResult {
-        name: dest_name,
-        path: dest_owned,
-        result,
-      });
-    });
-
-    self.compressing = Some(CompressingState { rx });
+    let job = JobKind::Compress { targets, dest, format: format.to_string() };
+    match self.jobs.push(job) {
+      None => self.set_status(format!("Compressing to {dest_name}...")),
+      Some(position) => self.set_status(format!("Queued compress to {dest_name} (#{position})")),
+    }
     Ok(())
   }
 
-  fn check_compression_complete(&mut self) -> Result<bool> {
-    let Some(ref state) = self.compressing else {
+  /// Move any pane whose root was deleted or unmounted up to the nearest
+  /// existing ancestor and tell the user. Returns true if anything moved.
+  pub fn check_lost_root(&mut self) -> Result<bool> {
+    if !self.tree.root.is_dir() {
+      self.tree.reload()?;
+    }
+    let mut lost = Vec::new();
+    if let Some(old_root) = self.tree.take_lost_root() {
+      self.search.clear();
+      self.cursor = 0;
+      self.tree_scroll_offset = 0;
+      self.marked.retain(|p| p.exists());
+      self.rebuild_visible_cache();
+      self.update_breadcrumbs();
+      self.preview.invalidate();
+      self.update_preview();
+      lost.push((old_root, self.tree.root.clone()));
+    }
+    if let Some(ref mut pane) = self.right_pane {
+      if !pane.tree.root.is_dir() {
+        pane.tree.reload()?;
+      }
+      if let Some(old_root) = pane.tree.take_lost_root() {
+        pane.search.clear();
+        pane.cursor = 0;
+        pane.scroll_offset = 0;
+        pane.marked.retain(|p| p.exists());
+        pane.rebuild_visible_cache();
+        lost.push((old_root, pane.tree.root.clone()));
+      }
+    }
+    let Some((old_root, new_root)) = lost.first() else {
       return Ok(false);
     };
+    tracing::warn!(old = %old_root.display(), new = %new_root.display(), "directory disappeared");
+    self.set_status(format!("{} no longer exists, moved up to {}", old_root.display(), new_root.display()));
+    Ok(true)
+  }
 
-    let result = match state.rx.try_recv() {
-      Ok(r) => r,
-      Err(mpsc::TryRecvError::Empty) => return Ok(false),
-      Err(mpsc::TryRecvError::Disconnected) => {
-        self.compressing = None;
-        self.set_status("Compression failed: thread died".to_string());
-        return Ok(true);
+  /// Start a background git status rescan of both panes every
+  /// `GIT_REFRESH`, and apply the scans that finished.
+  fn check_git_loaded(&mut self) -> bool {
+    if self.git_refreshed.elapsed() >= GIT_REFRESH {
+      self.git_refreshed = Instant::now();
+      self.tree.refresh_git_in_background();
+      if let Some(ref mut pane) = self.right_pane {
+        pane.tree.refresh_git_in_background();
       }
-    };
+    }
+    let had_repo = self.tree.git_repo().map(|r| r.root().to_path_buf());
+    let mut changed = self.tree.poll_git();
+    // Commits only need loading once a repository turns up
+    if let Some(repo) = self.tree.git_repo()
+      && had_repo.as_deref() != Some(repo.root())
+    {
+      self.preview.request_git_commits(repo);
+      changed = true;
+    }
+    if let Some(ref mut pane) = self.right_pane {
+      changed |= pane.tree.poll_git();
+    }
+    changed
+  }
+}
 
-    self.compressing = None;
+/// ` (verified)` after a transfer whose copies were read back and compared.
+fn verified_note(verify: bool) -> &'static str {
+  if verify { " (verified)" } else { "" }
+}
 
-    match result.result {
-      Ok(()) => {
-        self.set_status(format!("Created: {}", result.name));
-        self.tree.reload()?;
-        self.tree_reloaded = true;
-        self.rebuild_visible_cache();
-        self.reposition_cursor_to(&result.path);
-        self.preview.invalidate();
-        self.update_preview();
-      }
-      Err(e) => {
-        self.set_status(format!("Compress failed: {e}"));
-      }
-    }
-    Ok(true)
+/// Append the processes holding `paths` open to a failure message, since a
+/// file in use is the usual reason a move or delete fails.
+fn with_holders(message: String, paths: &[PathBuf]) -> String {
+  match locks::describe(&locks::holders(paths)) {
+    Some(holders) => format!("{message} ({holders})"),
+    None => message,
   }
 }
 
@@ -2379,8 +5655,11 @@ impl App {
 mod tests {
   use super::*;
   use std::fs;
+  use std::process::Command;
 
   use std::sync::atomic::{AtomicU32, Ordering};
+  use crate::fs::{GitFileStatus, GitStatus};
+  use crate::process::MockRunner;
   static COUNTER: AtomicU32 = AtomicU32::new(0);
 
   fn setup_test_dir() -> PathBuf {
@@ -2491,25 +5770,216 @@ mod tests {
   }
 
   #[test]
-  fn test_search_filter() {
+  fn test_quit_confirm_double_and_prompt() {
+    let dir = setup_test_dir();
+    let mut config = cfg();
+    config.quit_confirm = QuitConfirm::Double;
+    let mut app = App::new(dir.clone(), None, &config, None).unwrap();
+    app.update(Action::Quit).unwrap();
+    assert!(!app.should_quit);
+    // Any other key disarms the first press
+    app.update(Action::MoveDown).unwrap();
+    app.update(Action::Quit).unwrap();
+    assert!(!app.should_quit);
+    app.update(Action::Quit).unwrap();
+    assert!(app.should_quit);
+
+    config.quit_confirm = QuitConfirm::Prompt;
+    let mut app = App::new(dir.clone(), None, &config, None).unwrap();
+    app.update(Action::Quit).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::ConfirmQuit));
+    app.update(Action::PromptConfirm).unwrap();
+    assert!(!app.should_quit);
+    assert_eq!(app.input_mode, InputMode::Normal);
+    app.update(Action::Quit).unwrap();
+    app.update(Action::PromptInput('y')).unwrap();
+    assert!(app.should_quit);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_read_only_blocks_mutations() {
+    let dir = setup_test_dir();
+    let mut config = cfg();
+    config.read_only = true;
+    let mut app = App::new(dir.clone(), None, &config, None).unwrap();
+    app.update(Action::GPress).unwrap();
+    app.update(Action::GoToTop).unwrap();
+    let cursor = app.cursor;
+    app.update(Action::DeleteFile).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.status_message.as_deref(), Some("Read-only mode: delete is disabled"));
+    app.update(Action::RenameStart).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(dir.join("aaa_dir").exists());
+    app.run(command::Rename { from: dir.join("bbb.txt"), to: dir.join("moved.txt") }).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Read-only mode: rename is disabled"));
+    assert!(dir.join("bbb.txt").exists());
+    // Navigation still works
+    app.update(Action::MoveDown).unwrap();
+    assert_eq!(app.cursor, cursor + 1);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_quit_print_selection() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let cursor = app.selected_entry().unwrap().path.clone();
+    app.update(Action::QuitPrintSelection).unwrap();
+    assert!(app.should_quit);
+    assert_eq!(app.exported_selection, Some(vec![cursor]));
+
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.marked.insert(dir.join("ccc.rs"));
+    app.marked.insert(dir.join("bbb.txt"));
+    app.update(Action::QuitPrintSelection).unwrap();
+    assert_eq!(app.exported_selection, Some(vec![dir.join("bbb.txt"), dir.join("ccc.rs")]));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_escape_peels_layers_before_quitting() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+
+    app.update(Action::SearchStart).unwrap();
+    app.update(Action::SearchInput('b')).unwrap();
+    app.input_mode = InputMode::Normal;
+    app.marked.insert(dir.join("ccc.rs"));
+    app.show_debug = true;
+
+    app.update(Action::Escape).unwrap();
+    assert!(app.search.is_empty());
+    assert!(!app.marked.is_empty());
+    app.update(Action::Escape).unwrap();
+    assert!(app.marked.is_empty());
+    assert!(app.show_debug);
+    app.update(Action::Escape).unwrap();
+    assert!(!app.show_debug);
+    assert!(!app.should_quit);
+    app.update(Action::Escape).unwrap();
+    assert!(app.should_quit);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_macro_record_and_replay() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::MacroRecord).unwrap();
+    assert_eq!(app.input_mode, InputMode::MacroRegister);
+    app.update(Action::MacroRegister('a')).unwrap();
+    assert_eq!(app.macros.recording(), Some('a'));
+    app.update(Action::MoveDown).unwrap();
+    app.update(Action::MacroRecord).unwrap();
+    assert_eq!(app.macros.recording(), None);
+    assert_eq!(app.cursor, 1);
+
+    app.update(Action::MacroPlay).unwrap();
+    app.update(Action::MacroRegister('a')).unwrap();
+    assert_eq!(app.cursor, 2);
+    app.update(Action::MacroPlay).unwrap();
+    app.update(Action::MacroRegister('@')).unwrap();
+    assert_eq!(app.cursor, 3);
+
+    app.update(Action::MacroPlay).unwrap();
+    app.update(Action::MacroRegister('z')).unwrap();
+    assert_eq!(app.cursor, 3);
+    assert_eq!(app.status_message.as_deref(), Some("Register @z is empty"));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_escape_leaves_g_prefix() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::GPress).unwrap();
+    app.update(Action::Escape).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(!app.should_quit);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_esc_quits_can_be_disabled() {
+    let dir = setup_test_dir();
+    let mut config = cfg();
+    config.esc_quits = false;
+    let mut app = App::new(dir.clone(), None, &config, None).unwrap();
+    app.update(Action::Escape).unwrap();
+    assert!(!app.should_quit);
+    app.update(Action::Quit).unwrap();
+    assert!(app.should_quit);
+    assert_eq!(app.last_dir(), dir.as_path());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_search_filter() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::SearchStart).unwrap();
+    assert_eq!(app.input_mode, InputMode::Search);
+
+    app.update(Action::SearchInput('b')).unwrap();
+    app.update(Action::SearchInput('b')).unwrap();
+    assert_eq!(app.search.text, "bb");
+
+    let visible = app.visible_entries();
+    // Should only show bbb.txt
+    assert_eq!(visible.len(), 1);
+    assert_eq!(app.tree.entries[visible[0]].name, "bbb.txt");
+
+    app.update(Action::SearchCancel).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(app.search.is_empty());
+
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_page_moves_through_filtered_entries() {
+    let dir = setup_test_dir();
+    for i in 0..40 {
+      fs::write(dir.join(format!("log_{i:02}.txt")), "").unwrap();
+    }
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.viewport_height = 12;
+    app.update(Action::SearchStart).unwrap();
+    for c in "log_".chars() {
+      app.update(Action::SearchInput(c)).unwrap();
+    }
+    assert_eq!(app.cached_visible.len(), 40);
+
+    app.update(Action::PageDown).unwrap();
+    assert_eq!(app.selected_entry().unwrap().name, "log_09.txt");
+    assert!(app.tree_scroll_offset <= app.cursor);
+    for _ in 0..5 {
+      app.update(Action::PageDown).unwrap();
+    }
+    assert_eq!(app.selected_entry().unwrap().name, "log_39.txt");
+    app.update(Action::PageUp).unwrap();
+    assert_eq!(app.selected_entry().unwrap().name, "log_30.txt");
+    app.update(Action::MoveBy(-10)).unwrap();
+    assert_eq!(app.selected_entry().unwrap().name, "log_20.txt");
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_preview_on_demand_without_auto_preview() {
     let dir = setup_test_dir();
-    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
-    app.update(Action::SearchStart).unwrap();
-    assert_eq!(app.input_mode, InputMode::Search);
-
-    app.update(Action::SearchInput('b')).unwrap();
-    app.update(Action::SearchInput('b')).unwrap();
-    assert_eq!(app.search_query, "bb");
-
-    let visible = app.visible_entries();
-    // Should only show bbb.txt
-    assert_eq!(visible.len(), 1);
-    assert_eq!(app.tree.entries[visible[0]].name, "bbb.txt");
-
-    app.update(Action::SearchCancel).unwrap();
-    assert_eq!(app.input_mode, InputMode::Normal);
-    assert!(app.search_query.is_empty());
+    let mut config = cfg();
+    config.auto_preview = false;
+    let mut app = App::new(dir.clone(), None, &config, None).unwrap();
+    app.update(Action::MoveDown).unwrap();
+    assert!(app.preview.current_path.is_none());
 
+    app.update(Action::ReloadPreview).unwrap();
+    let selected = app.selected_entry().unwrap().path.clone();
+    assert_eq!(app.preview.current_path.as_ref(), Some(&selected));
+    app.update(Action::MoveDown).unwrap();
+    assert!(app.preview.current_path.is_none());
     cleanup_test_dir(&dir);
   }
 
@@ -2553,6 +6023,114 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_restrict_root_blocks_leaving() {
+    let dir = setup_test_dir();
+    let child_dir = dir.join("aaa_dir");
+    let mut app = App::new(child_dir.clone(), None, &cfg(), None).unwrap();
+    app.restrict_root = Some(child_dir.clone());
+
+    app.update(Action::MoveLeft).unwrap();
+    assert_eq!(app.tree.root, child_dir);
+    assert_eq!(app.status_message, Some(format!("Restricted to {}", child_dir.display())));
+
+    let parent_idx = app.breadcrumb_segments.iter().position(|s| s.path == dir).unwrap();
+    app.update(Action::BreadcrumbSelect(parent_idx)).unwrap();
+    assert_eq!(app.tree.root, child_dir);
+    app.change_dir(&dir).unwrap();
+    assert_eq!(app.tree.root, child_dir);
+
+    // History entries outside the root stay put
+    app.restrict_root = None;
+    app.change_dir(&dir).unwrap();
+    app.change_dir(&child_dir).unwrap();
+    app.restrict_root = Some(child_dir.clone());
+    app.update(Action::HistoryBack).unwrap();
+    assert_eq!(app.tree.root, child_dir);
+    assert_eq!(app.history_back.last(), Some(&dir));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_mouse_hover_tracks_entry_under_pointer() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.tree_area = Rect::new(0, 1, 30, 10);
+    let first = app.tree.entries[app.cached_visible[0]].path.clone();
+    let second = app.tree.entries[app.cached_visible[1]].path.clone();
+    assert_eq!(app.entry_at(5, 2), Some(first.clone()));
+    assert_eq!(app.entry_at(5, 3), Some(second));
+    // Borders and rows past the last entry
+    assert_eq!(app.entry_at(0, 2), None);
+    assert_eq!(app.entry_at(5, 1), None);
+    assert_eq!(app.entry_at(5, 9), None);
+
+    let cursor = app.cursor;
+    assert!(!app.mouse_moved(5, 2));
+    assert_eq!(app.hover.as_ref().map(|h| h.path.clone()), Some(first));
+    // Staying on the entry keeps the timer; leaving drops it
+    assert!(!app.mouse_moved(6, 2));
+    assert_eq!(app.hover.as_ref().map(|h| h.column), Some(5));
+    assert!(!app.mouse_moved(5, 9));
+    assert!(app.hover.is_none());
+    assert_eq!(app.cursor, cursor);
+
+    app.hover_preview_ms = 0;
+    app.mouse_moved(5, 2);
+    assert!(app.hover.is_none());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_toggle_mouse_releases_capture() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &Config::load_from_str("[general]\nmouse = false\n"), None).unwrap();
+    assert!(!app.mouse_capture);
+    app.update(Action::ToggleMouse).unwrap();
+    assert!(app.mouse_capture);
+    app.tree_area = Rect::new(0, 1, 30, 10);
+    app.mouse_moved(5, 2);
+    assert!(app.hover.is_some());
+    // Handing the mouse to the terminal drops a pending hover
+    app.update(Action::ToggleMouse).unwrap();
+    assert!(!app.mouse_capture);
+    assert!(app.hover.is_none());
+    assert!(app.status_message.as_deref().is_some_and(|m| m.starts_with("Mouse off")));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_settings_apply_live_and_revert_on_cancel() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let ratio = app.tree_ratio;
+    app.update(Action::SettingsOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::Settings);
+
+    app.update(Action::SettingsChange(true)).unwrap();
+    assert_eq!(app.requested_theme.take().as_deref(), Some("light"));
+    app.update(Action::SettingsDown).unwrap();
+    app.update(Action::SettingsChange(true)).unwrap();
+    assert!(!app.icons);
+    app.update(Action::SettingsDown).unwrap();
+    app.update(Action::SettingsChange(true)).unwrap();
+    assert_eq!(app.tree_ratio, ratio + app.ratio_step);
+    app.update(Action::SettingsDown).unwrap();
+    app.update(Action::SettingsDown).unwrap();
+    app.update(Action::SettingsChange(true)).unwrap();
+    assert!(app.tree.show_hidden);
+    assert!(app.visible_entries().iter().any(|&i| app.tree.entries[i].name == ".hidden"));
+
+    app.update(Action::SettingsClose).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.requested_theme.as_deref(), Some("dark"));
+    assert!(app.icons);
+    assert_eq!(app.tree_ratio, ratio);
+    assert!(!app.tree.show_hidden);
+    assert!(app.settings.is_none());
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_visible_entries_with_search() {
     let dir = setup_test_dir();
@@ -2562,7 +6140,7 @@ mod tests {
     assert_eq!(app.visible_entries().len(), app.tree.entries.len());
 
     // With search
-    app.search_query = "rs".to_string();
+    app.search.set("rs");
     app.rebuild_visible_cache();
     let visible = app.visible_entries();
     assert!(visible.len() < app.tree.entries.len());
@@ -2612,7 +6190,15 @@ mod tests {
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
     app.update(Action::OpenClaude).unwrap();
     let suspend = app.handle_suspend();
-    assert!(matches!(suspend, Some(SuspendAction::Claude(_, false))));
+    assert!(matches!(suspend, Some(SuspendAction::Ai(ref cmd, _)) if cmd == "claude"));
+
+    // Marked files are passed as context, relative to the working dir
+    app.update(Action::GoToBottom).unwrap();
+    app.marked.insert(dir.join("ccc.rs"));
+    app.marked.insert(dir.join("bbb.txt"));
+    app.update(Action::OpenClaude).unwrap();
+    let suspend = app.handle_suspend();
+    assert!(matches!(suspend, Some(SuspendAction::Ai(ref cmd, _)) if cmd == "claude '@bbb.txt @ccc.rs'"));
     cleanup_test_dir(&dir);
   }
 
@@ -2622,7 +6208,7 @@ mod tests {
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
     app.update(Action::OpenClaudeAlt).unwrap();
     let suspend = app.handle_suspend();
-    assert!(matches!(suspend, Some(SuspendAction::Claude(_, true))));
+    assert!(matches!(suspend, Some(SuspendAction::Ai(ref cmd, _)) if cmd.contains("--dangerously-skip-permissions")));
     cleanup_test_dir(&dir);
   }
 
@@ -2633,15 +6219,15 @@ mod tests {
     c.claude_yolo = true;
     let mut app = App::new(dir.clone(), None, &c, None).unwrap();
 
-    // OpenClaude should pass yolo=true when config is true
+    // OpenClaude should use the alt command when yolo is configured
     app.update(Action::OpenClaude).unwrap();
     let suspend = app.handle_suspend();
-    assert!(matches!(suspend, Some(SuspendAction::Claude(_, true))));
+    assert!(matches!(suspend, Some(SuspendAction::Ai(ref cmd, _)) if cmd.contains("--dangerously-skip-permissions")));
 
-    // OpenClaudeAlt should pass yolo=false (inverse of config)
+    // OpenClaudeAlt should use the plain command (inverse of config)
     app.update(Action::OpenClaudeAlt).unwrap();
     let suspend = app.handle_suspend();
-    assert!(matches!(suspend, Some(SuspendAction::Claude(_, false))));
+    assert!(matches!(suspend, Some(SuspendAction::Ai(ref cmd, _)) if !cmd.contains("--dangerously-skip-permissions")));
     cleanup_test_dir(&dir);
   }
 
@@ -2658,6 +6244,105 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_execute_suspend_runs_command() {
+    let runner = MockRunner::default();
+    let ai = SuspendAction::Ai("claude '@a b.rs'".to_string(), PathBuf::from("/repo"));
+    App::execute_suspend(&ai, &runner).unwrap();
+    let open = SuspendAction::OpenWith("nvim".to_string(), PathBuf::from("/repo/notes.md"));
+    App::execute_suspend(&open, &runner).unwrap();
+    let diff = SuspendAction::DiffTool("git difftool -y".to_string(), PathBuf::from("/repo"), PathBuf::from("src/a.rs"));
+    App::execute_suspend(&diff, &runner).unwrap();
+
+    let calls = runner.calls.lock().unwrap();
+    assert_eq!(calls[0].to_string(), "sh -c claude '@a b.rs'");
+    assert_eq!(calls[0].cwd, Some(PathBuf::from("/repo")));
+    assert_eq!(calls[1].to_string(), "nvim /repo/notes.md");
+    assert_eq!(calls[1].cwd, None);
+    assert_eq!(calls[2].args, vec!["-c", "git difftool -y \"$1\"", "tfl", "src/a.rs"]);
+    assert_eq!(calls[2].cwd, Some(PathBuf::from("/repo")));
+  }
+
+  #[test]
+  fn test_open_with_non_tui_app_spawns() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let runner = Arc::new(MockRunner::with_installed(&["code"]));
+    app.runner = runner.clone();
+    app.has_apps_file = true;
+    app.custom_apps = vec![
+      OpenApp {
+        name: "Code".into(),
+        command: "code".into(),
+        is_tui: false,
+        macos_app: None,
+        opens_dir: true,
+        dir_mode: false,
+        project: false,
+      },
+      OpenApp {
+        name: "Missing".into(),
+        command: "missing".into(),
+        is_tui: false,
+        macos_app: None,
+        opens_dir: false,
+        dir_mode: false,
+        project: false,
+      },
+    ];
+    while app.selected_entry().is_none_or(|e| e.is_dir) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    let file = app.selected_entry().unwrap().path.clone();
+    app.update(Action::OpenWithStart).unwrap();
+    // Only installed apps, plus the open-folder variant
+    let names: Vec<(&str, bool)> = app.open_with_apps.iter().map(|a| (a.name.as_str(), a.dir_mode)).collect();
+    assert_eq!(names, vec![("Code", false), ("Code", true)]);
+
+    app.update(Action::OpenWithDown).unwrap();
+    app.update(Action::OpenWithSelect).unwrap();
+    app.update(Action::OpenWithStart).unwrap();
+    app.update(Action::OpenWithDown).unwrap();
+    app.update(Action::OpenWithDown).unwrap();
+    app.update(Action::OpenWithSelect).unwrap();
+    assert_eq!(runner.commands(), vec![format!("code {}", file.display()), format!("code {}", dir.display())]);
+    assert_eq!(app.status_message.as_deref(), Some("Opened with Code"));
+    assert!(app.handle_suspend().is_none());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_open_with_lists_project_apps_first() {
+    let dir = setup_test_dir();
+    fs::write(dir.join(".tfl.toml"), "[[apps]]\nname = \"Preview\"\ncommand = \"./preview.sh\"\n").unwrap();
+    let script = dir.join("./preview.sh").to_string_lossy().into_owned();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let runner = Arc::new(MockRunner::with_installed(&[&script, "code"]));
+    app.runner = runner.clone();
+    app.has_apps_file = true;
+    app.custom_apps = vec![OpenApp {
+      name: "Code".into(),
+      command: "code".into(),
+      is_tui: false,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    }];
+    while app.selected_entry().is_none_or(|e| e.is_dir) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    let file = app.selected_entry().unwrap().path.clone();
+    app.update(Action::OpenWithStart).unwrap();
+    let names: Vec<(&str, bool)> = app.open_with_apps.iter().map(|a| (a.name.as_str(), a.project)).collect();
+    assert_eq!(names, vec![("Preview", true), ("Code", false)]);
+
+    app.update(Action::OpenWithDown).unwrap();
+    app.update(Action::OpenWithSelect).unwrap();
+    assert_eq!(runner.commands(), vec![format!("{script} {}", file.display())]);
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_open_shell_suspend() {
     let dir = setup_test_dir();
@@ -2668,6 +6353,31 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_open_shell_spawns_terminal_command() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let runner = Arc::new(MockRunner::default());
+    app.runner = runner.clone();
+    app.terminal_command = "kitty --directory {dir}".to_string();
+
+    app.graphical_session = false;
+    app.update(Action::OpenShell).unwrap();
+    assert!(matches!(app.handle_suspend(), Some(SuspendAction::Shell(_))));
+    assert!(runner.commands().is_empty());
+
+    app.graphical_session = true;
+    app.update(Action::OpenShell).unwrap();
+    assert!(app.handle_suspend().is_none());
+    // The selected directory, aaa_dir
+    let target = dir.join("aaa_dir");
+    let calls = runner.calls.lock().unwrap();
+    assert_eq!(calls[0].args[1], *format!("nohup kitty --directory {} >/dev/null 2>&1", target.display()));
+    assert_eq!(calls[0].cwd.as_deref(), Some(target.as_path()));
+    drop(calls);
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_enter_dir_changes_root() {
     let dir = setup_test_dir();
@@ -2724,13 +6434,13 @@ mod tests {
     let mut app = App::new(child_dir.clone(), None, &cfg(), None).unwrap();
 
     // Set a search filter
-    app.search_query = "nonexistent".to_string();
+    app.search.set("nonexistent");
     app.rebuild_visible_cache();
     assert_eq!(app.visible_entries().len(), 0);
 
     // Go to parent — search should be cleared
     app.update(Action::MoveLeft).unwrap();
-    assert!(app.search_query.is_empty());
+    assert!(app.search.is_empty());
     assert_eq!(app.tree.root, dir);
     // cursor and scroll_offset must be valid for visible_entries
     assert!(app.cursor < app.visible_entries().len());
@@ -2745,14 +6455,14 @@ mod tests {
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
 
     // Search for "aaa" to filter to the dir
-    app.search_query = "aaa".to_string();
+    app.search.set("aaa");
     app.rebuild_visible_cache();
     assert_eq!(app.visible_entries().len(), 1);
     app.cursor = 0;
 
     // Enter the directory
     app.update(Action::EnterDir).unwrap();
-    assert!(app.search_query.is_empty());
+    assert!(app.search.is_empty());
     assert_eq!(app.tree.root, dir.join("aaa_dir"));
     cleanup_test_dir(&dir);
   }
@@ -2772,7 +6482,7 @@ mod tests {
 
     // Confirm search — should enter the directory
     app.update(Action::SearchConfirm).unwrap();
-    assert!(app.search_query.is_empty());
+    assert!(app.search.is_empty());
     assert_eq!(app.input_mode, InputMode::Normal);
     assert_eq!(app.tree.root, dir.join("aaa_dir"));
     assert_eq!(app.cursor, 0);
@@ -2793,7 +6503,7 @@ mod tests {
 
     let root_before = app.tree.root.clone();
     app.update(Action::SearchConfirm).unwrap();
-    assert!(app.search_query.is_empty());
+    assert!(app.search.is_empty());
     // Root should not change for a file
     assert_eq!(app.tree.root, root_before);
     cleanup_test_dir(&dir);
@@ -2888,82 +6598,345 @@ mod tests {
   }
 
   #[test]
-  fn test_copy_stores_path_in_clipboard() {
+  fn test_copy_stores_path_in_clipboard() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_none_or(|e| e.is_dir) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    let path = app.selected_entry().unwrap().path.clone();
+    app.update(Action::CopyFile).unwrap();
+    assert_eq!(app.clipboard.op, Some(ClipboardOp::Copy));
+    assert_eq!(app.clipboard.paths, vec![path]);
+    assert!(app.status_message.as_ref().unwrap().starts_with("Copied:"));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_paste_copy_creates_new_file() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    // Move to bbb.txt
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::CopyFile).unwrap();
+
+    // Move cursor to root (a dir) so paste goes into that dir
+    app.cursor = 0; // aaa_dir
+    app.update(Action::Paste).unwrap();
+
+    // bbb.txt should now exist in aaa_dir
+    assert!(dir.join("aaa_dir").join("bbb.txt").exists());
+    // Original should still exist
+    assert!(dir.join("bbb.txt").exists());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_paste_cut_moves_file() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    // Move to bbb.txt
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::CutFile).unwrap();
+
+    // Paste into aaa_dir
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+
+    assert!(dir.join("aaa_dir").join("bbb.txt").exists());
+    assert!(!dir.join("bbb.txt").exists());
+    // Clipboard should be cleared after cut+paste
+    assert!(app.clipboard.op.is_none());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_move_to_prompt_completes_and_moves() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::MoveToStart).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::MoveTo));
+
+    app.prompt.set("zz".to_string());
+    app.update(Action::PromptTab).unwrap();
+    assert_eq!(app.prompt.text, "zzz_dir/");
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(dir.join("zzz_dir/bbb.txt").exists());
+    assert!(!dir.join("bbb.txt").exists());
+
+    // A missing directory keeps the prompt open
+    app.update(Action::CopyToStart).unwrap();
+    app.prompt.set("nowhere".to_string());
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::CopyTo));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_copy_to_parent_refused_when_restricted() {
+    let dir = setup_test_dir();
+    let child_dir = dir.join("aaa_dir");
+    fs::write(child_dir.join("a.txt"), "a").unwrap();
+    let mut app = App::new(child_dir.clone(), None, &cfg(), None).unwrap();
+    app.restrict_root = Some(child_dir.clone());
+
+    app.update(Action::CopyToStart).unwrap();
+    app.prompt.set("../zzz_dir".to_string());
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.status_message, Some(format!("Restricted to {}", child_dir.display())));
+    assert!(!dir.join("zzz_dir/a.txt").exists());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_paste_conflict_appends_suffix() {
+    let dir = setup_test_dir();
+    // Create bbb.txt inside aaa_dir to cause conflict
+    fs::write(dir.join("aaa_dir").join("bbb.txt"), "existing").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    // Copy bbb.txt
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::CopyFile).unwrap();
+
+    // Paste into aaa_dir asks first
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    assert_eq!(app.input_mode, InputMode::PasteConflict);
+    assert_eq!(app.paste_plan.as_ref().unwrap().pending, vec![dir.join("bbb.txt")]);
+    app.update(Action::PasteConflictChoose(Collision::Rename)).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+
+    // Should have created bbb_copy.txt
+    assert!(dir.join("aaa_dir").join("bbb_copy.txt").exists());
+    // Original in aaa_dir should be untouched
+    assert_eq!(fs::read_to_string(dir.join("aaa_dir").join("bbb.txt")).unwrap(), "existing");
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_paste_conflicts_skip_overwrite_and_apply_to_all() {
+    let dir = setup_test_dir();
+    for name in ["bbb.txt", "ccc.rs"] {
+      fs::write(dir.join("aaa_dir").join(name), "existing").unwrap();
+    }
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    for name in ["bbb.txt", "ccc.rs"] {
+      while app.selected_entry().is_none_or(|e| e.name != name) {
+        app.update(Action::MoveDown).unwrap();
+      }
+      app.update(Action::ToggleMark).unwrap();
+    }
+    app.update(Action::CopyFile).unwrap();
+    let read = |name: &str| fs::read_to_string(dir.join("aaa_dir").join(name)).unwrap();
+
+    // Cancelling leaves everything as it was
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    app.update(Action::Escape).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Paste cancelled"));
+    assert_eq!(read("bbb.txt"), "existing");
+
+    // One answer at a time
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    app.update(Action::PasteConflictChoose(Collision::Skip)).unwrap();
+    assert_eq!(app.input_mode, InputMode::PasteConflict);
+    app.update(Action::PasteConflictChoose(Collision::Overwrite)).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Pasted (1 overwritten) (1 skipped)"));
+    assert_eq!(read("bbb.txt"), "existing");
+    assert_eq!(read("ccc.rs"), fs::read_to_string(dir.join("ccc.rs")).unwrap());
+    assert!(!dir.join("aaa_dir/bbb_copy.txt").exists());
+
+    // One answer for all
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    app.update(Action::PasteConflictAll).unwrap();
+    app.update(Action::PasteConflictChoose(Collision::Rename)).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(dir.join("aaa_dir/bbb_copy.txt").exists());
+    assert!(dir.join("aaa_dir/ccc_copy.rs").exists());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_paste_overwriting_some_entries_still_undoes_the_rest() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("aaa_dir/bbb.txt"), "existing").unwrap();
+    let mut c = cfg();
+    c.use_trash = false;
+    let mut app = App::new(dir.clone(), None, &c, None).unwrap();
+    for name in ["bbb.txt", "ccc.rs"] {
+      while app.selected_entry().is_none_or(|e| e.name != name) {
+        app.update(Action::MoveDown).unwrap();
+      }
+      app.update(Action::ToggleMark).unwrap();
+    }
+    app.update(Action::CopyFile).unwrap();
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    app.update(Action::PasteConflictChoose(Collision::Overwrite)).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Pasted (1 overwritten)"));
+    assert!(dir.join("aaa_dir/ccc.rs").exists());
+
+    app.update(Action::Undo).unwrap();
+    assert!(!dir.join("aaa_dir/ccc.rs").exists());
+    assert!(dir.join("aaa_dir/bbb.txt").exists());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_paste_offers_safe_names() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("what?.txt"), "q").unwrap();
+    let mut c = cfg();
+    c.safe_names = SafeNames::Always;
+    let mut app = App::new(dir.clone(), None, &c, None).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "what?.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::CopyFile).unwrap();
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::ConfirmSafeNames));
+    let plan = app.pending_safe_names.as_ref().unwrap();
+    assert_eq!(plan.changes, vec![NameChange { path: PathBuf::from("what?.txt"), new_name: "what_.txt".to_string() }]);
+
+    app.update(Action::PromptInput('n')).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(app.pending_safe_names.is_none());
+    assert_eq!(fs::read_dir(dir.join("aaa_dir")).unwrap().count(), 0);
+
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    app.update(Action::PromptInput('y')).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("aaa_dir").join("what_.txt")).unwrap(), "q");
+    assert!(!dir.join("aaa_dir").join("what?.txt").exists());
+
+    // Names that are already safe paste without asking
+    app.cursor = 0;
+    app.update(Action::MoveDown).unwrap();
+    let name = app.selected_entry().unwrap().name.clone();
+    app.update(Action::CopyFile).unwrap();
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(dir.join("aaa_dir").join(name).exists());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_paste_with_options_keeps_symlink() {
     let dir = setup_test_dir();
+    std::os::unix::fs::symlink("bbb.txt", dir.join("link.txt")).unwrap();
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
-    while app.selected_entry().is_none_or(|e| e.is_dir) {
+    while app.selected_entry().is_none_or(|e| e.name != "link.txt") {
       app.update(Action::MoveDown).unwrap();
     }
-    let path = app.selected_entry().unwrap().path.clone();
     app.update(Action::CopyFile).unwrap();
-    assert_eq!(app.clipboard.op, Some(ClipboardOp::Copy));
-    assert_eq!(app.clipboard.paths, vec![path]);
-    assert!(app.status_message.as_ref().unwrap().starts_with("Copied:"));
+    app.cursor = 0; // aaa_dir
+
+    app.update(Action::PasteWithOptions).unwrap();
+    assert_eq!(app.input_mode, InputMode::PasteOptions);
+    app.update(Action::PasteOptionsToggle(0)).unwrap();
+    app.update(Action::PasteOptionsConfirm).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(app.paste_options.is_none());
+    assert_eq!(fs::read_link(dir.join("aaa_dir").join("link.txt")).unwrap(), PathBuf::from("bbb.txt"));
+
+    // A plain paste goes back to following the link, beside the dangling one
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    app.update(Action::PasteConflictChoose(Collision::Rename)).unwrap();
+    let copy = dir.join("aaa_dir").join("link_copy.txt");
+    assert!(!copy.symlink_metadata().unwrap().is_symlink());
+    assert!(!dir.join("aaa_dir").join("bbb.txt").exists());
     cleanup_test_dir(&dir);
   }
 
   #[test]
-  fn test_paste_copy_creates_new_file() {
+  fn test_paste_verifies_copies() {
     let dir = setup_test_dir();
-    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
-    // Move to bbb.txt
+    let config = Config::load_from_str("[copy]\nverify = true\n");
+    let mut app = App::new(dir.clone(), None, &config, None).unwrap();
     while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
       app.update(Action::MoveDown).unwrap();
     }
     app.update(Action::CopyFile).unwrap();
-
-    // Move cursor to root (a dir) so paste goes into that dir
     app.cursor = 0; // aaa_dir
     app.update(Action::Paste).unwrap();
-
-    // bbb.txt should now exist in aaa_dir
+    assert_eq!(app.status_message.as_deref(), Some("Pasted (verified)"));
     assert!(dir.join("aaa_dir").join("bbb.txt").exists());
-    // Original should still exist
-    assert!(dir.join("bbb.txt").exists());
     cleanup_test_dir(&dir);
   }
 
   #[test]
-  fn test_paste_cut_moves_file() {
+  fn test_large_paste_copies_in_the_background_and_can_be_cancelled() {
     let dir = setup_test_dir();
+    // Sparse, so only its length is large
+    std::fs::File::create(dir.join("big.bin")).unwrap().set_len(65 << 20).unwrap();
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
-    // Move to bbb.txt
-    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+    let wait = |app: &mut App| {
+      while !app.jobs.is_idle() {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        app.update(Action::Tick).unwrap();
+      }
+    };
+    while app.selected_entry().is_none_or(|e| e.name != "big.bin") {
       app.update(Action::MoveDown).unwrap();
     }
-    app.update(Action::CutFile).unwrap();
+    app.update(Action::CopyFile).unwrap();
+    app.cursor = 0; // aaa_dir
+    app.update(Action::Paste).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Copying big.bin in the background (Esc cancels)..."));
+    wait(&mut app);
+    assert!(app.status_message.as_deref().is_some_and(|s| s.starts_with("Copied big.bin to ")));
+    assert_eq!(std::fs::metadata(dir.join("aaa_dir/big.bin")).unwrap().len(), 65 << 20);
+    // Undo is recorded once the copy finished
+    app.update(Action::Undo).unwrap();
+    assert!(!dir.join("aaa_dir/big.bin").exists());
 
-    // Paste into aaa_dir
     app.cursor = 0;
     app.update(Action::Paste).unwrap();
-
-    assert!(dir.join("aaa_dir").join("bbb.txt").exists());
-    assert!(!dir.join("bbb.txt").exists());
-    // Clipboard should be cleared after cut+paste
-    assert!(app.clipboard.op.is_none());
+    app.update(Action::Escape).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Cancelling copy of big.bin..."));
+    wait(&mut app);
+    // Stopped before it got anywhere, or finished just before the cancel
+    let copy = std::fs::metadata(dir.join("aaa_dir/big.bin"));
+    assert!(copy.is_err() || copy.unwrap().len() == 65 << 20);
+    assert!(dir.join("big.bin").exists());
     cleanup_test_dir(&dir);
   }
 
   #[test]
-  fn test_paste_conflict_appends_suffix() {
+  fn test_restorecon_relabels_targets() {
     let dir = setup_test_dir();
-    // Create bbb.txt inside aaa_dir to cause conflict
-    fs::write(dir.join("aaa_dir").join("bbb.txt"), "existing").unwrap();
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
-    // Copy bbb.txt
-    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
-      app.update(Action::MoveDown).unwrap();
-    }
-    app.update(Action::CopyFile).unwrap();
+    let mut runner = MockRunner::with_installed(&["restorecon"]);
+    runner.outputs.insert("restorecon".to_string(), "Relabeled x from a:b:c_t:s0 to a:b:d_t:s0\n".repeat(2));
+    let runner = Arc::new(runner);
+    app.runner = runner.clone();
 
-    // Paste into aaa_dir
-    app.cursor = 0;
-    app.update(Action::Paste).unwrap();
+    app.labels.enabled = false;
+    app.update(Action::Restorecon).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("SELinux is not enabled"));
+    assert!(runner.commands().is_empty());
 
-    // Should have created bbb_copy.txt
-    assert!(dir.join("aaa_dir").join("bbb_copy.txt").exists());
-    // Original in aaa_dir should be untouched
-    assert_eq!(fs::read_to_string(dir.join("aaa_dir").join("bbb.txt")).unwrap(), "existing");
+    // A directory is relabeled recursively
+    app.labels.enabled = true;
+    app.cursor = 0; // aaa_dir
+    app.update(Action::Restorecon).unwrap();
+    assert_eq!(runner.commands(), vec![format!("restorecon -Rv -- {}", dir.join("aaa_dir").display())]);
+    assert_eq!(app.status_message.as_deref(), Some("Relabeled 2 entries"));
     cleanup_test_dir(&dir);
   }
 
@@ -3014,6 +6987,78 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_delete_dir_prompt_counts_contents() {
+    let dir = setup_test_dir();
+    fs::create_dir(dir.join("aaa_dir").join("sub")).unwrap();
+    fs::write(dir.join("aaa_dir").join("sub").join("a.txt"), "abc").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    assert_eq!(app.selected_entry().unwrap().name, "aaa_dir");
+
+    app.update(Action::DeleteFile).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !app.poll_delete_summary() && Instant::now() < deadline {
+      std::thread::sleep(Duration::from_millis(10));
+    }
+    let summary = app.delete_summary.as_ref().and_then(|d| d.summary.as_ref()).unwrap();
+    assert_eq!((summary.files, summary.dirs, summary.bytes), (1, 1, 3));
+
+    app.update(Action::PromptInput('n')).unwrap();
+    assert!(app.delete_summary.is_none());
+    assert!(dir.join("aaa_dir").exists());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_delete_multi_prompt_counts_every_entry() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("aaa_dir").join("a.txt"), "abc").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    // aaa_dir and zzz_dir
+    app.update(Action::ToggleMark).unwrap();
+    app.update(Action::ToggleMark).unwrap();
+
+    app.update(Action::DeleteFile).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::ConfirmDeleteMulti(2)));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !app.poll_delete_summary() && Instant::now() < deadline {
+      std::thread::sleep(Duration::from_millis(10));
+    }
+    let summary = app.delete_summary.as_ref().and_then(|d| d.summary.as_ref()).unwrap();
+    assert_eq!((summary.files, summary.dirs, summary.bytes), (1, 2, 3));
+    assert_eq!(summary.children.len(), 2);
+
+    let progress = Arc::clone(&app.delete_summary.as_ref().unwrap().progress);
+    app.update(Action::PromptInput('n')).unwrap();
+    assert!(progress.is_cancelled());
+    cleanup_test_dir(&dir);
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn test_delete_prompt_lists_processes_using_target() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let mut child = Command::new("sleep").arg("30").current_dir(dir.join("aaa_dir")).spawn().unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "aaa_dir") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::DeleteFile).unwrap();
+    // Looked up off the UI thread
+    while app.holders_rx.is_some() {
+      std::thread::sleep(Duration::from_millis(5));
+      app.update(Action::Tick).unwrap();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    assert_eq!(app.prompt_holders, vec!["sleep".to_string()]);
+
+    app.update(Action::PromptCancel).unwrap();
+    assert!(app.prompt_holders.is_empty());
+    assert!(dir.join("aaa_dir").exists());
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_delete_cancel_does_not_remove() {
     let dir = setup_test_dir();
@@ -3052,11 +7097,11 @@ mod tests {
     }
     app.update(Action::RenameStart).unwrap();
     assert_eq!(app.input_mode, InputMode::Prompt);
-    assert_eq!(app.prompt_input, "bbb.txt");
+    assert_eq!(app.prompt.text, "bbb.txt");
 
     // Clear and type new name
-    app.prompt_input.clear();
-    app.prompt_input.push_str("renamed.txt");
+    app.prompt.text.clear();
+    app.prompt.text.push_str("renamed.txt");
     app.update(Action::PromptConfirm).unwrap();
 
     assert!(!dir.join("bbb.txt").exists());
@@ -3065,6 +7110,121 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_repeat_last_rename_chmod_and_new_file() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::RepeatLast).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Nothing to repeat"));
+
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::RenameStart).unwrap();
+    app.prompt.set("bbb_old.txt");
+    app.update(Action::PromptConfirm).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "ccc.rs") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::RepeatLast).unwrap();
+    assert!(dir.join("ccc_old.rs").exists());
+
+    app.update(Action::ChmodStart).unwrap();
+    app.chmod_state.new_mode = (app.chmod_state.original_mode & !0o777) | 0o600;
+    app.update(Action::ChmodApply).unwrap();
+    app.update(Action::GPress).unwrap();
+    app.update(Action::GoToTop).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "bbb_old.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::RepeatLast).unwrap();
+    let mode = std::fs::metadata(dir.join("bbb_old.txt")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(app.last_repeatable.as_ref().map(Repeatable::describe).as_deref(), Some("chmod 600"));
+
+    app.update(Action::NewFileStart).unwrap();
+    app.prompt.set("note.md");
+    app.update(Action::PromptConfirm).unwrap();
+    app.update(Action::RepeatLast).unwrap();
+    app.update(Action::RepeatLast).unwrap();
+    assert!(dir.join("note-2.md").exists());
+    assert!(dir.join("note-3.md").exists());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_undo_redo_rename_and_new_file() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::Undo).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Nothing to undo"));
+
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::RenameStart).unwrap();
+    app.prompt.set("renamed.txt");
+    app.update(Action::PromptConfirm).unwrap();
+    app.update(Action::NewFileStart).unwrap();
+    app.prompt.set("note.md");
+    app.update(Action::PromptConfirm).unwrap();
+    fs::write(dir.join("note.md"), "draft").unwrap();
+    assert_eq!(app.history.len(), 2);
+
+    app.update(Action::Undo).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Undid new file note.md"));
+    assert!(!dir.join("note.md").exists());
+    app.update(Action::Undo).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Undid rename bbb.txt → renamed.txt"));
+    assert!(dir.join("bbb.txt").exists());
+    assert!(!dir.join("renamed.txt").exists());
+
+    app.update(Action::Redo).unwrap();
+    app.update(Action::Redo).unwrap();
+    assert!(dir.join("renamed.txt").exists());
+    // Redo brings back what the file held when it was undone
+    assert_eq!(fs::read_to_string(dir.join("note.md")).unwrap(), "draft");
+    app.update(Action::Redo).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Nothing to redo"));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_undo_cut_paste_and_nested_dir() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::CutFile).unwrap();
+    app.cursor = 0;
+    app.update(Action::Paste).unwrap();
+    assert!(dir.join("aaa_dir/bbb.txt").exists());
+    app.update(Action::Undo).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Undid move bbb.txt"));
+    assert!(dir.join("bbb.txt").exists());
+    assert!(!dir.join("aaa_dir/bbb.txt").exists());
+
+    app.update(Action::GPress).unwrap();
+    app.update(Action::GoToTop).unwrap();
+    app.update(Action::NewDirStart).unwrap();
+    app.prompt.set("x/y/z");
+    app.update(Action::PromptConfirm).unwrap();
+    let created = dir.join("aaa_dir/x/y/z");
+    assert!(created.is_dir());
+    app.update(Action::Undo).unwrap();
+    assert!(!dir.join("aaa_dir/x").exists());
+    assert!(dir.join("aaa_dir").exists());
+
+    // A directory that gained contents since is left alone
+    app.update(Action::Redo).unwrap();
+    fs::write(created.join("keep.txt"), "").unwrap();
+    app.update(Action::Undo).unwrap();
+    assert!(created.join("keep.txt").exists());
+    assert!(app.status_message.as_deref().is_some_and(|m| m.starts_with("Can't undo new dir z")));
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_rename_to_existing_shows_error() {
     let dir = setup_test_dir();
@@ -3073,7 +7233,7 @@ mod tests {
       app.update(Action::MoveDown).unwrap();
     }
     app.update(Action::RenameStart).unwrap();
-    app.prompt_input = "ccc.rs".to_string();
+    app.prompt.set("ccc.rs");
     app.update(Action::PromptConfirm).unwrap();
 
     // Should still exist as bbb.txt
@@ -3094,7 +7254,7 @@ mod tests {
     assert_eq!(app.input_mode, InputMode::Prompt);
     assert_eq!(app.prompt_kind, Some(PromptKind::NewFile));
 
-    app.prompt_input = "new_file.txt".to_string();
+    app.prompt.set("new_file.txt");
     app.update(Action::PromptConfirm).unwrap();
 
     assert!(dir.join("new_file.txt").exists());
@@ -3102,6 +7262,33 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_new_file_executable_toggle() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.umask = 0o022;
+    while app.selected_entry().is_none_or(|e| e.is_dir) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::NewFileStart).unwrap();
+    assert_eq!(app.new_entry_mode(), Some(0o644));
+    app.update(Action::PromptTab).unwrap();
+    assert_eq!(app.new_entry_mode(), Some(0o755));
+
+    app.prompt.set("run.sh");
+    app.update(Action::PromptConfirm).unwrap();
+    let mode = fs::metadata(dir.join("run.sh")).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode, 0o755);
+    assert!(!app.new_file_executable);
+
+    app.update(Action::NewDirStart).unwrap();
+    assert_eq!(app.new_entry_mode(), Some(0o755));
+    // Tab only applies to files
+    app.update(Action::PromptTab).unwrap();
+    assert!(!app.new_file_executable);
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_new_dir_creation() {
     let dir = setup_test_dir();
@@ -3114,23 +7301,193 @@ mod tests {
     assert_eq!(app.input_mode, InputMode::Prompt);
     assert_eq!(app.prompt_kind, Some(PromptKind::NewDir));
 
-    app.prompt_input = "new_dir".to_string();
-    app.update(Action::PromptConfirm).unwrap();
+    app.prompt.set("new_dir");
+    app.update(Action::PromptConfirm).unwrap();
+
+    assert!(dir.join("new_dir").is_dir());
+    assert_eq!(app.input_mode, InputMode::Normal);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_create_symlink_with_relative_target_and_undo() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::CreateSymlink).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::Symlink));
+    // A directory takes the link under the entry's name
+    app.prompt.set("zzz_dir");
+    app.update(Action::PromptConfirm).unwrap();
+    let link = dir.join("zzz_dir/bbb.txt");
+    assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("../bbb.txt"));
+    assert_eq!(fs::read_to_string(&link).unwrap(), fs::read_to_string(dir.join("bbb.txt")).unwrap());
+    assert_eq!(app.status_message.as_deref(), Some("Linked bbb.txt -> ../bbb.txt"));
+
+    // Taken names are refused
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt" || e.depth != 0) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::CreateSymlink).unwrap();
+    app.prompt.set("ccc.rs");
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("ccc.rs already exists"));
+
+    app.update(Action::Undo).unwrap();
+    assert!(link.symlink_metadata().is_err());
+    assert!(dir.join("bbb.txt").exists());
+
+    // A link back to an ancestor doesn't expand
+    std::os::unix::fs::symlink("..", dir.join("aaa_dir/up")).unwrap();
+    app.tree.reload().unwrap();
+    app.rebuild_visible_cache();
+    app.cursor = 0;
+    app.update(Action::ToggleExpand).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "up") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::ToggleExpand).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("up links back to .., not expanded"));
+    assert!(!app.selected_entry().unwrap().expanded);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_empty_name_rejected() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::NewFileStart).unwrap();
+    app.prompt.set("  ");
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Name cannot be empty"));
+    assert_eq!(app.input_mode, InputMode::Normal);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_prompt_editing_keeps_combining_marks_with_their_letter() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::NewFileStart).unwrap();
+    for c in "cafe\u{301}.md".chars() {
+      app.update(Action::PromptInput(c)).unwrap();
+    }
+    // Cursor after "é": one step left lands before the "e", not between it and the accent
+    app.update(Action::PromptHome).unwrap();
+    for _ in 0..4 {
+      app.update(Action::PromptRight).unwrap();
+    }
+    assert_eq!(app.prompt.cursor, 5);
+    app.update(Action::PromptLeft).unwrap();
+    assert_eq!(app.prompt.cursor, 3);
+    app.update(Action::PromptDelete).unwrap();
+    assert_eq!(app.prompt.text, "caf.md");
+    app.update(Action::PromptInput('é')).unwrap();
+    app.update(Action::PromptBackspace).unwrap();
+    assert_eq!(app.prompt.text, "caf.md");
+    assert_eq!(app.prompt.cursor, 3);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_line_edits_apply_to_prompt_and_search() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::NewFileStart).unwrap();
+    for c in "old_name.txt".chars() {
+      app.update(Action::PromptInput(c)).unwrap();
+    }
+    app.update(Action::LineEdit(LineEdit::WordLeft)).unwrap();
+    app.update(Action::LineEdit(LineEdit::KillToEnd)).unwrap();
+    assert_eq!(app.prompt.text, "old_name.");
+    app.update(Action::PromptInput('m')).unwrap();
+    app.update(Action::PromptInput('d')).unwrap();
+    assert_eq!(app.prompt.text, "old_name.md");
+    app.update(Action::PromptCancel).unwrap();
+
+    // Confirm prompts ignore edits
+    app.update(Action::DeleteFile).unwrap();
+    app.update(Action::LineEdit(LineEdit::KillToStart)).unwrap();
+    assert!(app.prompt.is_empty());
+    app.update(Action::PromptCancel).unwrap();
+
+    app.update(Action::SearchStart).unwrap();
+    for c in "aaa bbb".chars() {
+      app.update(Action::SearchInput(c)).unwrap();
+    }
+    app.update(Action::LineEdit(LineEdit::DeleteWord)).unwrap();
+    assert_eq!(app.search.text, "aaa ");
+    app.update(Action::SearchBackspace).unwrap();
+    assert_eq!(app.search.text, "aaa");
+    assert!(app.visible_entries().iter().all(|&i| app.tree.entries[i].name.contains("aaa")));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_pasted_text_goes_into_prompt_whole() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let tree_len = app.visible_entries().len();
+
+    // Outside a text field a paste doesn't reach the key bindings
+    app.update(Action::InsertText("dq".to_string())).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(!app.should_quit);
+    assert_eq!(app.visible_entries().len(), tree_len);
+
+    app.update(Action::NewFileStart).unwrap();
+    app.update(Action::PromptInput('x')).unwrap();
+    app.prompt.home();
+    app.update(Action::InsertText("notes/q.md\nsecond line".to_string())).unwrap();
+    assert_eq!(app.prompt.text, "notes/q.mdx");
+    assert_eq!(app.prompt.cursor, 10);
+    app.update(Action::PromptCancel).unwrap();
+
+    // A y/n confirmation ignores pasted text instead of taking its `y`
+    app.update(Action::DeleteFile).unwrap();
+    app.update(Action::InsertText("yes".to_string())).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::ConfirmDelete));
+    app.update(Action::PromptCancel).unwrap();
 
-    assert!(dir.join("new_dir").is_dir());
-    assert_eq!(app.input_mode, InputMode::Normal);
+    app.update(Action::SearchStart).unwrap();
+    app.update(Action::InsertText("bbb".to_string())).unwrap();
+    assert_eq!(app.search.text, "bbb");
+    assert!(app.visible_entries().iter().all(|&i| app.tree.entries[i].name.contains("bbb")));
     cleanup_test_dir(&dir);
   }
 
   #[test]
-  fn test_empty_name_rejected() {
+  fn test_up_recalls_earlier_queries_and_names() {
     let dir = setup_test_dir();
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
-    app.update(Action::NewFileStart).unwrap();
-    app.prompt_input = "  ".to_string();
+    app.update(Action::SearchStart).unwrap();
+    app.update(Action::SearchInput('z')).unwrap();
+    app.update(Action::SearchCancel).unwrap();
+    app.search.set("bbb");
+    app.input_mode = InputMode::Search;
+    app.update(Action::SearchConfirm).unwrap();
+
+    app.update(Action::SearchStart).unwrap();
+    app.update(Action::SearchInput('a')).unwrap();
+    app.update(Action::InputHistoryPrev).unwrap();
+    assert_eq!(app.search.text, "bbb");
+    assert!(app.visible_entries().iter().all(|&i| app.tree.entries[i].name.contains("bbb")));
+    app.update(Action::InputHistoryNext).unwrap();
+    assert_eq!(app.search.text, "a");
+    app.update(Action::SearchCancel).unwrap();
+
+    // Prompts keep their own list per kind
+    app.update(Action::NewDirStart).unwrap();
+    app.update(Action::InputHistoryPrev).unwrap();
+    assert!(app.prompt.is_empty());
+    app.prompt.set("made_dir");
     app.update(Action::PromptConfirm).unwrap();
-    assert_eq!(app.status_message.as_deref(), Some("Name cannot be empty"));
-    assert_eq!(app.input_mode, InputMode::Normal);
+    app.update(Action::NewDirStart).unwrap();
+    app.update(Action::InputHistoryPrev).unwrap();
+    assert_eq!(app.prompt.text, "made_dir");
+    assert_eq!(app.prompt.cursor, 8);
     cleanup_test_dir(&dir);
   }
 
@@ -3143,7 +7500,7 @@ mod tests {
     app.update(Action::PromptCancel).unwrap();
     assert_eq!(app.input_mode, InputMode::Normal);
     assert!(app.prompt_kind.is_none());
-    assert!(app.prompt_input.is_empty());
+    assert!(app.prompt.text.is_empty());
     cleanup_test_dir(&dir);
   }
 
@@ -3155,7 +7512,7 @@ mod tests {
       app.update(Action::MoveDown).unwrap();
     }
     app.update(Action::RenameStart).unwrap();
-    app.prompt_input = "zzz_renamed.txt".to_string();
+    app.prompt.set("zzz_renamed.txt");
     app.update(Action::PromptConfirm).unwrap();
 
     // Cursor should be on the renamed file
@@ -3216,6 +7573,7 @@ mod tests {
       macos_app: None,
       opens_dir: true,
       dir_mode: false,
+      project: false,
     }];
     // Move to a file
     while app.selected_entry().is_none_or(|e| e.is_dir) {
@@ -3241,6 +7599,7 @@ mod tests {
       macos_app: None,
       opens_dir: true,
       dir_mode: false,
+      project: false,
     }];
     // First entry is a dir
     assert!(app.selected_entry().unwrap().is_dir);
@@ -3304,6 +7663,7 @@ mod tests {
       macos_app: None,
       opens_dir: false,
       dir_mode: false,
+      project: false,
     }];
     app.open_with_cursor = 1; // Select the TUI app (0 is Default)
     app.input_mode = InputMode::OpenWith;
@@ -3329,6 +7689,7 @@ mod tests {
       macos_app: None,
       opens_dir: false,
       dir_mode: false,
+      project: false,
     }];
     app.apply_config(&c);
     assert_eq!(app.custom_apps.len(), 1);
@@ -3336,6 +7697,70 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_layout_saves_and_restores_expansion() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("aaa_dir/inner.txt"), "").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.layouts = Layouts::load_from(dir.join("layouts.json"));
+
+    app.update(Action::ToggleExpand).unwrap();
+    app.update(Action::MoveDown).unwrap();
+    app.update(Action::LayoutSaveStart).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::SaveLayout));
+    app.prompt.set("review".to_string());
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.layouts.len(), 1);
+    assert!(app.state_writer.is_dirty(StateFile::Layouts));
+
+    // Collapse and move away, then bring the layout back
+    app.update(Action::GoToTop).unwrap();
+    app.update(Action::ToggleExpand).unwrap();
+    app.update(Action::GoToBottom).unwrap();
+    app.update(Action::LayoutsOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::Layouts);
+    app.update(Action::LayoutsSelect).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.selected_entry().unwrap().path, dir.join("aaa_dir/inner.txt"));
+
+    app.update(Action::LayoutsOpen).unwrap();
+    app.update(Action::LayoutsRemove).unwrap();
+    assert!(app.layouts.is_empty());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_bookmark_jumps_back_into_a_collapsed_tree() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("aaa_dir/inner.txt"), "").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.bookmarks = Bookmarks::load_from(dir.join("bookmarks.json"));
+
+    app.update(Action::ToggleExpand).unwrap();
+    app.update(Action::MoveDown).unwrap();
+    app.update(Action::BookmarkSet).unwrap();
+    assert_eq!(app.input_mode, InputMode::BookmarkSet);
+    app.update(Action::SetMark(1)).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(app.state_writer.is_dirty(StateFile::Bookmarks));
+    assert_eq!(app.bookmarks.get(&app.bookmark_project(), 1), Some(dir.join("aaa_dir/inner.txt").as_path()));
+
+    app.update(Action::GoToTop).unwrap();
+    app.update(Action::ToggleExpand).unwrap();
+    app.update(Action::GoToBottom).unwrap();
+    app.update(Action::BookmarkJump).unwrap();
+    app.update(Action::JumpMark(1)).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.selected_entry().unwrap().path, dir.join("aaa_dir/inner.txt"));
+
+    app.update(Action::JumpMark(2)).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Bookmark 2 is not set"));
+    fs::remove_file(dir.join("aaa_dir/inner.txt")).unwrap();
+    app.update(Action::JumpMark(1)).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Bookmark 1 no longer exists"));
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_reload_favorites_clamps_cursor() {
     let dir = setup_test_dir();
@@ -3343,7 +7768,7 @@ mod tests {
     // Manually set cursor past what reload will return
     app.favorites_cursor = 100;
     app.reload_favorites();
-    if app.favorites.len() == 0 {
+    if app.favorites.is_empty() {
       assert_eq!(app.favorites_cursor, 0);
     } else {
       assert!(app.favorites_cursor < app.favorites.len());
@@ -3359,7 +7784,7 @@ mod tests {
     app.favorites_cursor = 5;
     app.reload_favorites();
     // With default test env, favorites file likely doesn't exist
-    if app.favorites.len() == 0 {
+    if app.favorites.is_empty() {
       assert_eq!(app.favorites_cursor, 0);
     }
     cleanup_test_dir(&dir);
@@ -3432,7 +7857,7 @@ mod tests {
     app.update(Action::MoveRight).unwrap();
 
     // Set search filter that hides the parent dir but shows inner.txt
-    app.search_query = "inner".to_string();
+    app.search.set("inner");
     app.rebuild_visible_cache();
     let visible = app.visible_entries();
     // Only inner.txt should be visible
@@ -3443,7 +7868,7 @@ mod tests {
     // MoveLeft should fall through to go_parent since parent is not visible
     app.update(Action::MoveLeft).unwrap();
     // Search query should be cleared and root should change to parent
-    assert!(app.search_query.is_empty());
+    assert!(app.search.is_empty());
 
     cleanup_test_dir(&dir);
   }
@@ -3545,6 +7970,101 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_follow_tails_the_previewed_file_until_escape() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.preview_rows = 2;
+    app.update(Action::ToggleFollow).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Only text files can be followed"));
+
+    let log = dir.join("bbb.txt");
+    fs::write(&log, "one\ntwo\n").unwrap();
+    app.reposition_cursor_to(&log);
+    app.update_preview();
+    app.update(Action::ToggleFollow).unwrap();
+    assert!(app.preview.following());
+
+    let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+    std::io::Write::write_all(&mut file, b"three\nfour\n").unwrap();
+    app.update(Action::Tick).unwrap();
+    let lines = app.preview.get_display_lines().unwrap();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[3].to_string().ends_with("four"));
+    assert_eq!(app.preview.scroll_offset, 2);
+    // Expected appends don't raise the "changed on disk" banner
+    assert!(!app.preview.note_file_changed(&log));
+
+    app.update(Action::Escape).unwrap();
+    assert!(!app.preview.following());
+    assert!(!app.should_quit);
+    assert_eq!(app.preview.get_content().unwrap().line_count, 4);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_goto_line_and_line_number_toggle() {
+    let dir = setup_test_dir();
+    let file = dir.join("bbb.txt");
+    fs::write(&file, (1..=50).map(|i| format!("row {i}\n")).collect::<String>()).unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.reposition_cursor_to(&file);
+    app.update_preview();
+
+    app.update(Action::PreviewGotoLine).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::GotoLine));
+    app.update(Action::InsertText("30".to_string())).unwrap();
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.preview.scroll_offset, 29);
+
+    app.update(Action::PreviewGotoLine).unwrap();
+    app.update(Action::InsertText("80".to_string())).unwrap();
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("No line 80: the file has 50 lines"));
+    assert_eq!(app.preview.scroll_offset, 29);
+
+    let first = |app: &App| app.preview.get_display_lines().unwrap()[0].to_string();
+    assert_eq!(first(&app), "   1 row 1");
+    app.update(Action::ToggleLineNumbers).unwrap();
+    assert!(!app.preview.line_numbers());
+    assert_eq!(first(&app), "row 1");
+    assert_eq!(app.preview.scroll_offset, 29);
+    app.update(Action::ToggleLineNumbers).unwrap();
+    assert_eq!(first(&app), "   1 row 1");
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_preview_scrolls_sideways_unless_wrapped() {
+    let dir = setup_test_dir();
+    let file = dir.join("bbb.txt");
+    fs::write(&file, format!("short\n{}\n", "x".repeat(60))).unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.reposition_cursor_to(&file);
+    app.update_preview();
+    app.preview.content_width = 40;
+
+    app.update(Action::ScrollPreviewRight).unwrap();
+    assert_eq!(app.preview.h_scroll, PREVIEW_H_SCROLL);
+    // Stops once the end of the longest line (gutter of 5 + 60) is in view
+    for _ in 0..10 {
+      app.update(Action::ScrollPreviewRight).unwrap();
+    }
+    assert_eq!(app.preview.h_scroll, 25);
+    app.preview_focused = true;
+    app.update(Action::MoveLeft).unwrap();
+    assert_eq!(app.preview.h_scroll, 25 - PREVIEW_H_SCROLL);
+    assert_eq!(app.selected_entry().unwrap().path, file);
+
+    app.update(Action::ToggleWrap).unwrap();
+    assert!(app.preview.wrap);
+    assert_eq!(app.preview.h_scroll, 0);
+    app.update(Action::MoveRight).unwrap();
+    assert_eq!(app.preview.h_scroll, 0);
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_toggle_blame() {
     let dir = setup_test_dir();
@@ -3661,7 +8181,7 @@ mod tests {
     app.update(Action::ExtractArchive).unwrap();
 
     // Wait for async extraction to complete
-    while app.extracting.is_some() {
+    while !app.jobs.is_idle() {
       std::thread::sleep(std::time::Duration::from_millis(10));
       app.update(Action::Tick).unwrap();
     }
@@ -3675,6 +8195,77 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_enter_archive_browses_previews_and_extracts_entries() {
+    let dir = setup_test_dir();
+    fs::create_dir_all(dir.join("src/pkg/docs")).unwrap();
+    fs::write(dir.join("src/pkg/notes.txt"), "inside the archive").unwrap();
+    fs::write(dir.join("src/pkg/docs/guide.md"), "# Guide").unwrap();
+    let tgz = dir.join("pkg.tar.gz");
+    archive::compress_to_archive(&[dir.join("src/pkg")], &tgz, "tar.gz").unwrap();
+    fs::remove_dir_all(dir.join("src")).unwrap();
+
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.preview.timeout = Duration::ZERO;
+    while app.selected_entry().is_none_or(|e| e.name != "pkg.tar.gz") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::OpenDefault).unwrap();
+    // Listed on a worker first
+    assert!(app.archive_view.is_none());
+    assert_eq!(app.status_message.as_deref(), Some("Listing pkg.tar.gz..."));
+    let wait_listed = |app: &mut App| {
+      while app.archive_opening.is_some() {
+        std::thread::sleep(Duration::from_millis(5));
+        app.update(Action::Tick).unwrap();
+      }
+    };
+    wait_listed(&mut app);
+    assert_eq!(app.tree.root, tgz);
+    assert!(app.archive_view.is_some());
+    assert_eq!(app.selected_entry().unwrap().name, "pkg");
+
+    app.update(Action::EnterDir).unwrap();
+    assert_eq!(app.tree.root, tgz.join("pkg"));
+    let names: Vec<&str> = app.tree.entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["docs", "notes.txt"]);
+    app.update(Action::MoveDown).unwrap();
+    let lines = app.preview.get_content().unwrap().lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+    assert!(lines.iter().any(|l| l.contains("inside the archive")), "{lines:?}");
+
+    // Only extracting changes anything
+    app.update(Action::DeleteFile).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Inside an archive: delete is not available"));
+    app.update(Action::ExtractArchive).unwrap();
+    while !app.jobs.is_idle() {
+      std::thread::sleep(std::time::Duration::from_millis(10));
+      app.update(Action::Tick).unwrap();
+    }
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "inside the archive");
+    assert_eq!(app.status_message.as_deref(), Some("Extracted: notes.txt"));
+
+    app.update(Action::MoveLeft).unwrap();
+    assert_eq!(app.tree.root, tgz);
+    // Collapses pkg, then leaves
+    app.update(Action::MoveLeft).unwrap();
+    app.update(Action::MoveLeft).unwrap();
+    assert_eq!(app.tree.root, dir);
+    assert!(app.archive_view.is_none());
+    assert!(app.preview.source.is_none());
+    assert_eq!(app.selected_entry().unwrap().name, "pkg.tar.gz");
+    assert!(app.tree.entries.iter().any(|e| e.name == "notes.txt"));
+
+    // Back in history steps into the archive again
+    app.update(Action::HistoryForward).unwrap();
+    wait_listed(&mut app);
+    assert_eq!(app.tree.root, tgz);
+    app.update(Action::HistoryBack).unwrap();
+    assert_eq!(app.tree.root, dir);
+    assert!(app.archive_view.is_none());
+
+    cleanup_test_dir(&dir);
+  }
+
   // --- Custom ignore pattern tests ---
 
   fn cfg_with_ignore_patterns(patterns: &[&str]) -> Config {
@@ -3739,6 +8330,24 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_toggle_dirs_first_keeps_selection_and_pins() {
+    let dir = setup_test_dir();
+    let config = Config::load_from_str("[sort]\npinned = [\"ccc.rs\"]\n");
+    let mut app = App::new(dir.clone(), None, &config, None).unwrap();
+    let names = |app: &App| app.tree.entries.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&app), ["ccc.rs", "aaa_dir", "zzz_dir", "bbb.txt"]);
+
+    app.update(Action::GoToBottom).unwrap();
+    app.update(Action::MoveUp).unwrap();
+    assert_eq!(app.selected_entry().unwrap().name, "zzz_dir");
+    app.update(Action::ToggleDirsFirst).unwrap();
+    assert_eq!(names(&app), ["ccc.rs", "aaa_dir", "bbb.txt", "zzz_dir"]);
+    assert_eq!(app.selected_entry().unwrap().name, "zzz_dir");
+    assert_eq!(app.status_message.as_deref(), Some("Sort: interleaved"));
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_toggle_custom_ignore_action() {
     let dir = setup_test_dir();
@@ -3831,6 +8440,57 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_cycle_focus_through_panes_and_preview() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::CycleFocus).unwrap();
+    assert!(app.preview_focused);
+    let cursor = app.cursor;
+    app.update(Action::MoveDown).unwrap();
+    assert_eq!(app.cursor, cursor);
+    app.update(Action::CycleFocus).unwrap();
+    assert!(!app.preview_focused);
+
+    app.update(Action::ToggleDualPane).unwrap();
+    app.update(Action::CycleFocus).unwrap();
+    assert_eq!((app.active_pane, app.preview_focused), (1, false));
+    app.update(Action::CycleFocus).unwrap();
+    assert_eq!((app.active_pane, app.preview_focused), (1, true));
+    app.update(Action::Escape).unwrap();
+    assert!(!app.preview_focused);
+
+    app.focus_order = vec![FocusRegion::Preview, FocusRegion::Left];
+    app.update(Action::CycleFocus).unwrap();
+    assert!(app.preview_focused);
+    app.update(Action::CycleFocus).unwrap();
+    assert_eq!((app.active_pane, app.preview_focused), (0, false));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_window_title_follows_active_pane() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let name = dir.file_name().unwrap().to_string_lossy().to_string();
+    assert_eq!(app.window_title("tfl: {name}"), format!("tfl: {name}"));
+    assert_eq!(app.window_title("{dir}"), contract_home(&dir));
+
+    app.update(Action::ToggleDualPane).unwrap();
+    let pane = app.right_pane.as_mut().unwrap();
+    pane.tree.navigate_to(&dir.join("aaa_dir")).unwrap();
+    pane.rebuild_visible_cache();
+    app.update(Action::SwitchPane).unwrap();
+    assert_eq!(app.window_title("{name}"), "aaa_dir");
+
+    let odd = dir.join("odd\x07\x1b]52;c;aGk=\x07");
+    fs::create_dir(&odd).unwrap();
+    app.update(Action::SwitchPane).unwrap();
+    app.tree.navigate_to(&odd).unwrap();
+    assert_eq!(app.window_title("{name}"), "odd]52;c;aGk=");
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_chmod_toggle_recursive_only_for_dirs() {
     let dir = setup_test_dir();
@@ -3956,7 +8616,7 @@ mod tests {
     app.update(Action::PromptInput('y')).unwrap();
 
     // Wait for async extraction to complete
-    while app.extracting.is_some() {
+    while !app.jobs.is_idle() {
       std::thread::sleep(std::time::Duration::from_millis(10));
       app.update(Action::Tick).unwrap();
     }
@@ -4025,6 +8685,89 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_chmod_special_bits_and_setuid_warning() {
+    let dir = setup_test_dir();
+    let script = dir.join("aaa_dir").join("run.sh");
+    fs::write(&script, "#!/bin/sh").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::EnterDir).unwrap();
+    assert_eq!(app.selected_entry().unwrap().name, "run.sh");
+
+    app.update(Action::ChmodStart).unwrap();
+    assert!(app.chmod_state.warning().is_none());
+    app.update(Action::ChmodToggleBit(9)).unwrap();
+    assert_eq!(app.chmod_state.new_mode & 0o7777, 0o4755);
+    assert!(app.chmod_state.warning().is_some_and(|w| w.contains("setuid")));
+    assert_eq!(ChmodState::octal(app.chmod_state.new_mode), "4755");
+
+    // Without an execute bit there is nothing to run, so no warning
+    for bit in [2, 5, 8] {
+      app.update(Action::ChmodToggleBit(bit)).unwrap();
+    }
+    assert!(app.chmod_state.warning().is_none());
+    app.update(Action::ChmodToggleBit(11)).unwrap();
+    app.update(Action::ChmodApply).unwrap();
+    assert_eq!(fs::metadata(&script).unwrap().permissions().mode() & 0o7777, 0o5644);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_chmod_presets_apply_immediately() {
+    let dir = setup_test_dir();
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o750)).unwrap();
+    let file = dir.join("bbb.txt");
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    let preset = |app: &App, label: &str| app.chmod_presets.iter().position(|p| p.label() == label).unwrap();
+
+    app.update(Action::ChmodStart).unwrap();
+    app.update(Action::ChmodPreset(preset(&app, "600"))).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o600);
+
+    // Matching the parent drops execute bits for files
+    app.update(Action::ChmodStart).unwrap();
+    app.update(Action::ChmodPreset(preset(&app, "parent"))).unwrap();
+    assert_eq!(fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o640);
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_chmod_recursive_skips_symlinks_unless_following() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("aaa_dir").join("inner.txt"), "content").unwrap();
+    let outside = dir.join("zzz_dir").join("outside.txt");
+    fs::write(&outside, "outside").unwrap();
+    fs::set_permissions(&outside, fs::Permissions::from_mode(0o644)).unwrap();
+    std::os::unix::fs::symlink(&outside, dir.join("aaa_dir").join("link.txt")).unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+
+    assert_eq!(app.tree.entries[0].name, "aaa_dir");
+    app.update(Action::ChmodStart).unwrap();
+    assert!(!app.chmod_state.follow_symlinks);
+    app.update(Action::ChmodToggleRecursive).unwrap();
+    app.chmod_state.new_mode = (app.chmod_state.original_mode & !0o777) | 0o700;
+    app.update(Action::ChmodApply).unwrap();
+
+    let mode = |p: &PathBuf| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&dir.join("aaa_dir").join("inner.txt")), 0o700);
+    assert_eq!(mode(&outside), 0o644);
+    assert!(app.status_message.as_ref().is_some_and(|m| m.contains("skipped 1 symlink")));
+
+    app.update(Action::ChmodStart).unwrap();
+    app.update(Action::ChmodToggleRecursive).unwrap();
+    app.update(Action::ChmodToggleFollowSymlinks).unwrap();
+    app.chmod_state.new_mode = (app.chmod_state.original_mode & !0o777) | 0o700;
+    app.update(Action::ChmodApply).unwrap();
+    assert_eq!(mode(&outside), 0o700);
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_new_navigation_clears_forward_history() {
     let dir = setup_test_dir();
@@ -4164,6 +8907,37 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_breadcrumb_menu_goes_to_sibling() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.join("aaa_dir"), None, &cfg(), None).unwrap();
+    app.update(Action::BreadcrumbMenuOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::BreadcrumbMenu);
+    let menu = app.breadcrumb_menu.as_ref().unwrap();
+    assert_eq!(menu.dir, dir);
+    assert_eq!(menu.selected(), Some(&dir.join("aaa_dir")));
+
+    app.update(Action::BreadcrumbMenuDown).unwrap();
+    app.update(Action::BreadcrumbMenuSelect).unwrap();
+    assert_eq!(app.tree.root, dir.join("zzz_dir"));
+    assert_eq!(app.input_mode, InputMode::Normal);
+    app.update(Action::HistoryBack).unwrap();
+    assert_eq!(app.tree.root, dir.join("aaa_dir"));
+
+    // Clicks go to a row, or close the dropdown outside it
+    let last = app.breadcrumb_segments.len() - 2;
+    app.update(Action::BreadcrumbMenuAt(last)).unwrap();
+    app.breadcrumb_menu_area = Some(Rect::new(10, 1, 24, 4));
+    app.update(Action::BreadcrumbMenuClick(0, 8)).unwrap();
+    assert!(app.breadcrumb_menu.is_none());
+    app.update(Action::BreadcrumbMenuAt(last)).unwrap();
+    app.breadcrumb_menu_area = Some(Rect::new(10, 1, 24, 4));
+    app.update(Action::BreadcrumbMenuClick(12, 3)).unwrap();
+    assert_eq!(app.tree.root, dir.join("zzz_dir"));
+
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_breadcrumb_select_navigates_to_parent() {
     let dir = setup_test_dir();
@@ -4276,6 +9050,76 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_urls_picker_opens_url_in_view() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let runner = Arc::new(MockRunner::default());
+    app.runner = runner.clone();
+    let file = dir.join("links.txt");
+    fs::write(&file, "see https://a.org/x.\nagain https://a.org/x or (http://b.net)\nbelow https://c.com\n").unwrap();
+    app.preview.request_preview(&file, None, None);
+    app.preview_rows = 2;
+
+    app.update(Action::UrlsOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::Urls);
+    assert_eq!(app.urls, ["https://a.org/x", "http://b.net"]);
+    app.update(Action::UrlsPick(1)).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(runner.commands(), ["xdg-open http://b.net"]);
+
+    app.preview.scroll_offset = 3;
+    app.update(Action::UrlsOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.status_message.as_deref(), Some("No URLs in the preview"));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_size_column_sums_directories_on_screen() {
+    let dir = setup_test_dir();
+    fs::create_dir_all(dir.join("aaa_dir/nested")).unwrap();
+    fs::write(dir.join("aaa_dir/nested/data"), [0; 2048]).unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.viewport_height = 20;
+    assert!(!app.poll_dir_sizes());
+
+    app.update(Action::ToggleSizeColumn).unwrap();
+    assert!(app.dir_sizes.enabled);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while app.dir_sizes.get(&dir.join("aaa_dir")).is_none() || app.dir_sizes.get(&dir.join("zzz_dir")).is_none() {
+      assert!(Instant::now() < deadline);
+      app.poll_dir_sizes();
+      std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(app.dir_sizes.get(&dir.join("aaa_dir")), Some(&2048));
+    assert_eq!(app.dir_sizes.get(&dir.join("zzz_dir")), Some(&0));
+
+    app.update(Action::ToggleSizeColumn).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Size column off"));
+    assert_eq!(app.dir_sizes.get(&dir.join("aaa_dir")), None);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_doctor_overlay_lists_checks() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    let mut runner = MockRunner::default();
+    runner.outputs.insert("git".into(), "git version 2.45.0\n".into());
+    app.runner = Arc::new(runner);
+
+    app.update(Action::DoctorOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::Doctor);
+    let git = app.doctor.iter().find(|c| c.name == "Git").unwrap();
+    assert_eq!(git.status, doctor::Status::Ok);
+    assert!(git.detail.ends_with("git version 2.45.0"));
+    app.update(Action::DoctorClose).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(app.doctor.is_empty());
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_toggle_blame_resets_scroll() {
     let dir = setup_test_dir();
@@ -4374,14 +9218,60 @@ mod tests {
   }
 
   #[test]
-  fn test_properties_close() {
+  fn test_properties_close() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::ShowProperties).unwrap();
+    assert_eq!(app.input_mode, InputMode::Properties);
+    app.update(Action::PropertiesClose).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(app.file_properties.is_none());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_properties_multi_aggregate_and_toggle_executable() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.marked.insert(dir.join("bbb.txt"));
+    app.marked.insert(dir.join("ccc.rs"));
+    app.update(Action::ShowProperties).unwrap();
+    assert_eq!(app.input_mode, InputMode::Properties);
+    assert_eq!(app.multi_properties.as_ref().map(|m| m.file_count), Some(2));
+
+    app.update(Action::PropertiesToggleExecutable).unwrap();
+    for name in ["bbb.txt", "ccc.rs"] {
+      let mode = std::fs::metadata(dir.join(name)).unwrap().permissions().mode();
+      assert_ne!(mode & 0o100, 0, "{name} should be executable");
+    }
+    app.update(Action::PropertiesToggleExecutable).unwrap();
+    let mode = std::fs::metadata(dir.join("bbb.txt")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o111, 0);
+
+    app.update(Action::PropertiesClose).unwrap();
+    assert!(app.multi_properties.is_none());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_properties_chmod_applies_to_every_marked_entry() {
     let dir = setup_test_dir();
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.marked.insert(dir.join("bbb.txt"));
+    app.marked.insert(dir.join("ccc.rs"));
+
     app.update(Action::ShowProperties).unwrap();
-    assert_eq!(app.input_mode, InputMode::Properties);
-    app.update(Action::PropertiesClose).unwrap();
-    assert_eq!(app.input_mode, InputMode::Normal);
-    assert!(app.file_properties.is_none());
+    app.update(Action::ChmodStart).unwrap();
+    assert_eq!(app.chmod_state.others, [dir.join("ccc.rs")]);
+    app.chmod_state.new_mode = (app.chmod_state.original_mode & !0o777) | 0o600;
+    app.update(Action::ChmodApply).unwrap();
+    for name in ["bbb.txt", "ccc.rs"] {
+      assert_eq!(std::fs::metadata(dir.join(name)).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
     cleanup_test_dir(&dir);
   }
 
@@ -4757,11 +9647,11 @@ mod tests {
 
     let right = app.right_pane.as_ref().unwrap();
     assert!(right.cached_visible.len() < left_count);
-    assert!(!right.search_query.is_empty());
+    assert!(!right.search.is_empty());
 
     // Left pane should be unaffected
     assert_eq!(app.cached_visible.len(), left_count);
-    assert!(app.search_query.is_empty());
+    assert!(app.search.is_empty());
     cleanup_test_dir(&dir);
   }
 
@@ -4832,7 +9722,7 @@ mod tests {
     let dir = setup_test_dir();
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
     // Clear pre-existing favorites for a clean test
-    while app.favorites.len() > 0 {
+    while !app.favorites.is_empty() {
       app.favorites.remove(0);
     }
     app.favorites.add(dir.join("aaa_dir"));
@@ -4858,7 +9748,7 @@ mod tests {
     let dir = setup_test_dir();
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
     // Clear any existing favorites
-    while app.favorites.len() > 0 {
+    while !app.favorites.is_empty() {
       app.favorites.remove(0);
     }
     app.update(Action::FavoritesOpen).unwrap();
@@ -4875,7 +9765,7 @@ mod tests {
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
     app.update(Action::FavoriteAdd).unwrap();
     assert!(app.status_message.as_ref().unwrap().contains("Added"));
-    assert!(app.wrote_config);
+    assert!(app.state_writer.is_dirty(StateFile::Favorites));
     assert!(app.favorites.contains(&dir));
     cleanup_test_dir(&dir);
   }
@@ -4895,7 +9785,7 @@ mod tests {
     let dir = setup_test_dir();
     let target = dir.join("aaa_dir");
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
-    while app.favorites.len() > 0 {
+    while !app.favorites.is_empty() {
       app.favorites.remove(0);
     }
     app.favorites.add(target.clone());
@@ -4926,7 +9816,7 @@ mod tests {
   fn test_favorites_remove_clamps_cursor() {
     let dir = setup_test_dir();
     let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
-    while app.favorites.len() > 0 {
+    while !app.favorites.is_empty() {
       app.favorites.remove(0);
     }
     app.favorites.add(dir.join("aaa_dir"));
@@ -5049,6 +9939,71 @@ mod tests {
     cleanup_test_dir(&dir);
   }
 
+  #[test]
+  fn test_hints_follow_selection() {
+    use crate::ui::hints::hints;
+
+    let dir = setup_test_dir();
+    std::fs::write(dir.join("pack.zip"), "").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.cursor = 0; // aaa_dir
+    assert_eq!(hints(&app)[0], Action::OpenDefault);
+    while app.selected_entry().is_none_or(|e| e.name != "pack.zip") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    assert_eq!(hints(&app)[..2], [Action::ExtractArchive, Action::ExtractAndDelete]);
+    // Labelled as the help overlay describes them
+    assert_eq!(command::Command::describe(&hints(&app)[1]), "extract and delete archive");
+
+    // Marks and a full clipboard bring their own keys
+    app.update(Action::CopyFile).unwrap();
+    app.update(Action::ToggleMark).unwrap();
+    let marked = hints(&app);
+    assert_eq!(marked[0], Action::Paste);
+    assert!(marked.contains(&Action::ClearMarks));
+    app.read_only = true;
+    assert!(!hints(&app).contains(&Action::DeleteFile));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_compress_queues_while_another_runs() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::CompressStart).unwrap();
+    app.update(Action::CompressSelect(0)).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Compressing to bbb.zip..."));
+    // A second compression waits instead of being refused, under its own name
+    app.update(Action::CompressStart).unwrap();
+    app.update(Action::CompressSelect(0)).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Queued compress to bbb_2.zip (#1)"));
+    app.update(Action::CompressStart).unwrap();
+    app.update(Action::CompressSelect(1)).unwrap();
+
+    app.update(Action::JobsOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::Jobs);
+    // A running compression can't be stopped, a queued one can be cancelled
+    app.update(Action::JobsCancel).unwrap();
+    assert_eq!(app.jobs.queued_len(), 2);
+    app.update(Action::JobsDown).unwrap();
+    app.update(Action::JobsDown).unwrap();
+    app.update(Action::JobsCancel).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Cancelled compress of bbb.tar.gz"));
+    assert_eq!(app.jobs_cursor, 1);
+
+    while !app.jobs.is_idle() {
+      std::thread::sleep(std::time::Duration::from_millis(10));
+      app.update(Action::Tick).unwrap();
+    }
+    assert!(dir.join("bbb.zip").exists());
+    assert!(dir.join("bbb_2.zip").exists());
+    assert!(!dir.join("bbb.tar.gz").exists());
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_compress_select_invalid_format() {
     let dir = setup_test_dir();
@@ -5216,4 +10171,510 @@ mod tests {
     assert_eq!(app.marked.len(), filtered);
     cleanup_test_dir(&dir);
   }
+
+  fn cfg_with_template_rule(marker: &str) -> Config {
+    let mut c = cfg();
+    c.template_rules = vec![ContextRule {
+      marker: marker.to_string(),
+      templates: vec![FileTemplate {
+        name: "Widget.rs".to_string(),
+        content: "pub struct {name};\n".to_string(),
+      }],
+    }];
+    c
+  }
+
+  #[test]
+  fn test_templates_start_without_context_shows_status() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg_with_template_rule("tfl-no-such-marker"), None).unwrap();
+    app.update(Action::NewFromTemplate).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(app.status_message.as_ref().unwrap().contains("No templates"));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_templates_select_creates_file_with_content() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("marker.toml"), "").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg_with_template_rule("marker.toml"), None).unwrap();
+    // Select a file at the root so the new file lands in the root dir
+    while app.selected_entry().is_some_and(|e| e.is_dir) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::NewFromTemplate).unwrap();
+    assert_eq!(app.input_mode, InputMode::Templates);
+    assert_eq!(app.template_choices.len(), 1);
+
+    app.update(Action::TemplatesSelect).unwrap();
+    assert_eq!(app.input_mode, InputMode::Prompt);
+    assert_eq!(app.prompt_kind, Some(PromptKind::NewFile));
+    assert_eq!(app.prompt.text, "Widget.rs");
+
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("Widget.rs")).unwrap(), "pub struct Widget;\n");
+    assert!(app.pending_template.is_none());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_templates_cancel_prompt_clears_pending_template() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("marker.toml"), "").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg_with_template_rule("marker.toml"), None).unwrap();
+    while app.selected_entry().is_some_and(|e| e.is_dir) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::NewFromTemplate).unwrap();
+    app.update(Action::TemplatesSelect).unwrap();
+    assert!(app.pending_template.is_some());
+    app.update(Action::PromptCancel).unwrap();
+    assert!(app.pending_template.is_none());
+
+    // A plain new file afterwards stays empty
+    app.update(Action::NewFileStart).unwrap();
+    for c in "plain.txt".chars() {
+      app.update(Action::PromptInput(c)).unwrap();
+    }
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("plain.txt")).unwrap(), "");
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_open_difftool_outside_repo_shows_status() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_some_and(|e| e.is_dir) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::OpenDiffTool).unwrap();
+    assert!(app.should_suspend.is_none());
+    assert!(app.status_message.is_some());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_open_difftool_suspends_with_relative_path() {
+    let dir = setup_test_dir();
+    git2::Repository::init(&dir).unwrap();
+    let mut c = cfg();
+    c.difftool = "delta".to_string();
+    let mut app = App::new(dir.clone(), None, &c, None).unwrap();
+    while app.selected_entry().is_some_and(|e| e.is_dir) {
+      app.update(Action::MoveDown).unwrap();
+    }
+    let name = app.selected_entry().unwrap().name.clone();
+    app.update(Action::OpenDiffTool).unwrap();
+    match app.should_suspend {
+      Some(SuspendAction::DiffTool(ref cmd, ref root, ref rel)) => {
+        assert_eq!(cmd, "delta");
+        assert_eq!(root.canonicalize().unwrap(), dir.canonicalize().unwrap());
+        assert_eq!(rel, &PathBuf::from(name));
+      }
+      ref other => panic!("expected DiffTool suspend, got {other:?}"),
+    }
+    cleanup_test_dir(&dir);
+  }
+
+  /// Commit bbb.txt and modify it, leaving the cursor on it
+  fn app_with_modified_file(dir: &PathBuf) -> App {
+    let repo = git2::Repository::init(dir).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(std::path::Path::new("bbb.txt")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = git2::Signature::now("Test", "test@test.com").unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+    fs::write(dir.join("bbb.txt"), "hello\nworld\n").unwrap();
+
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    while app.selected_entry().is_some_and(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app
+  }
+
+  #[test]
+  fn test_export_patch_requires_diff() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::ExportPatchStart).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.status_message.as_deref(), Some("No diff to export"));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_export_patch_writes_file() {
+    let dir = setup_test_dir();
+    let mut app = app_with_modified_file(&dir);
+    app.update(Action::ShowDiff).unwrap();
+    app.update(Action::ExportPatchStart).unwrap();
+    assert_eq!(app.input_mode, InputMode::PatchExport);
+
+    app.update(Action::ExportPatchSelect(0)).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::PatchPath));
+    assert_eq!(app.prompt.text, "bbb.txt.patch");
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    let patch = fs::read_to_string(dir.join("bbb.txt.patch")).unwrap();
+    assert!(patch.starts_with("diff --git a/bbb.txt b/bbb.txt"));
+    assert!(patch.contains("+world"));
+    assert_eq!(app.selected_entry().unwrap().name, "bbb.txt");
+
+    // Current hunk goes to its own file, without clobbering the first export
+    app.update(Action::ExportPatchStart).unwrap();
+    app.update(Action::ExportPatchSelect(1)).unwrap();
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("bbb.txt.hunk1.patch")).unwrap(), patch);
+
+    // The path can be changed; a directory gets the default name
+    app.update(Action::ExportPatchStart).unwrap();
+    app.update(Action::ExportPatchSelect(0)).unwrap();
+    app.prompt.set("zzz_dir");
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("zzz_dir").join("bbb.txt.patch")).unwrap(), patch);
+    assert_eq!(app.status_message.as_deref(), Some("Saved patch to zzz_dir/bbb.txt.patch"));
+
+    // An existing file is not overwritten
+    app.update(Action::ExportPatchStart).unwrap();
+    app.update(Action::ExportPatchSelect(0)).unwrap();
+    app.prompt.set("ccc.rs");
+    app.update(Action::PromptConfirm).unwrap();
+    assert!(app.status_message.as_deref().unwrap().ends_with("ccc.rs already exists"));
+
+    // Nor can `..` leave a restricted root
+    app.restrict_root = Some(dir.clone());
+    app.update(Action::ExportPatchStart).unwrap();
+    app.update(Action::ExportPatchSelect(0)).unwrap();
+    app.prompt.set("../tfl_escaped.patch");
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.status_message, Some(format!("Restricted to {}", dir.display())));
+    assert!(!dir.parent().unwrap().join("tfl_escaped.patch").exists());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_stage_hunk_after_confirmation() {
+    let dir = setup_test_dir();
+    let mut app = app_with_modified_file(&dir);
+    app.update(Action::ShowDiff).unwrap();
+    app.update(Action::StageHunk).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::ConfirmHunk(HunkAction::Stage, 0)));
+
+    app.update(Action::PromptInput('y')).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.status_message.as_deref(), Some("Staged hunk 1"));
+    // Nothing left unstaged; the staged view shows the change
+    assert!(app.preview.current_diff().is_none());
+    let file = dir.join("bbb.txt");
+    assert!(diff::generate_diff(&dir, &file, DiffMode::IndexHead).is_some());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_revert_hunk_cancelled_keeps_file() {
+    let dir = setup_test_dir();
+    let mut app = app_with_modified_file(&dir);
+    app.update(Action::ShowDiff).unwrap();
+    app.update(Action::RevertHunk).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::ConfirmHunk(HunkAction::Revert, 0)));
+    app.update(Action::PromptInput('n')).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("bbb.txt")).unwrap(), "hello\nworld\n");
+
+    app.update(Action::RevertHunk).unwrap();
+    app.update(Action::PromptInput('y')).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("bbb.txt")).unwrap(), "hello");
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_revert_hunk_unavailable_in_staged_view() {
+    let dir = setup_test_dir();
+    let mut app = app_with_modified_file(&dir);
+    app.update(Action::ShowDiff).unwrap();
+    app.preview.diff_mode = DiffMode::WorktreeHead;
+    app.update(Action::CycleDiffMode).unwrap(); // -> unstaged
+    app.update(Action::CycleDiffMode).unwrap(); // -> staged
+    assert_eq!(app.preview.diff_mode, DiffMode::IndexHead);
+    app.update(Action::RevertHunk).unwrap();
+    assert_eq!(app.prompt_kind, None);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_git_stage_unstage_and_discard_selection() {
+    let dir = setup_test_dir();
+    let mut app = app_with_modified_file(&dir);
+    let status = |app: &App| app.selected_entry().unwrap().git_status;
+
+    app.update(Action::GitStage).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Staged bbb.txt"));
+    assert_eq!(status(&app), GitStatus { staged: Some(GitFileStatus::Modified), unstaged: None });
+    app.update(Action::GitUnstage).unwrap();
+    assert_eq!(status(&app), GitStatus { staged: None, unstaged: Some(GitFileStatus::Modified) });
+
+    // Discarding asks first
+    app.update(Action::GitDiscard).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::ConfirmGitDiscard(1)));
+    app.update(Action::PromptInput('n')).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Discard cancelled"));
+    assert_eq!(fs::read_to_string(dir.join("bbb.txt")).unwrap(), "hello\nworld\n");
+    app.update(Action::GitDiscard).unwrap();
+    app.update(Action::PromptInput('y')).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Discarded changes to bbb.txt"));
+    assert_eq!(fs::read_to_string(dir.join("bbb.txt")).unwrap(), "hello");
+    assert!(status(&app).is_clean());
+    assert_eq!(app.selected_entry().unwrap().name, "bbb.txt");
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_git_stage_in_the_right_pane_updates_its_statuses() {
+    let dir = setup_test_dir();
+    let mut app = app_with_modified_file(&dir);
+    app.update(Action::ToggleDualPane).unwrap();
+    app.update(Action::SwitchPane).unwrap();
+    while app.selected_entry().is_some_and(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::GitStage).unwrap();
+    let entry = app.selected_entry().unwrap();
+    assert_eq!(entry.name, "bbb.txt");
+    assert_eq!(entry.git_status, GitStatus { staged: Some(GitFileStatus::Modified), unstaged: None });
+    let left = app.tree.entries.iter().find(|e| e.name == "bbb.txt").unwrap();
+    assert_eq!(left.git_status, entry.git_status);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_git_stage_outside_a_repository() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::GitStage).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Not in a git repository"));
+    app.update(Action::GitDiscard).unwrap();
+    assert_eq!(app.prompt_kind, None);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_session_restore_round_trip() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("aaa_dir").join("inner.txt"), "x").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.tree.toggle_expand(0).unwrap();
+    app.rebuild_visible_cache();
+    let inner = dir.join("aaa_dir").join("inner.txt");
+    app.reposition_cursor_to(&inner);
+    app.marked.insert(dir.join("bbb.txt"));
+    app.clipboard = Clipboard { paths: vec![dir.join("ccc.rs")], op: Some(ClipboardOp::Cut) };
+    let state = app.session_state();
+
+    let mut fresh = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    fresh.offer_session_restore(state);
+    assert_eq!(fresh.prompt_kind, Some(PromptKind::ConfirmRestoreSession));
+    fresh.update(Action::PromptInput('y')).unwrap();
+    assert_eq!(fresh.input_mode, InputMode::Normal);
+    assert_eq!(fresh.selected_entry().map(|e| e.path.clone()), Some(inner));
+    assert!(fresh.marked.contains(&dir.join("bbb.txt")));
+    assert_eq!(fresh.clipboard.paths, vec![dir.join("ccc.rs")]);
+    assert_eq!(fresh.clipboard.op, Some(ClipboardOp::Cut));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_resume_session_brings_back_view_but_not_marks() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("aaa_dir").join("inner.txt"), "x").unwrap();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::ToggleHidden).unwrap();
+    app.tree.toggle_expand(app.tree.entries.iter().position(|e| e.name == "aaa_dir").unwrap()).unwrap();
+    app.rebuild_visible_cache();
+    let inner = dir.join("aaa_dir").join("inner.txt");
+    app.reposition_cursor_to(&inner);
+    app.update(Action::ToggleDualPane).unwrap();
+    app.right_pane.as_mut().unwrap().tree.navigate_to(&dir.join("zzz_dir")).unwrap();
+    app.marked.insert(dir.join("bbb.txt"));
+    let state = app.session_state();
+    assert!(state.show_hidden);
+    assert_eq!(state.right_root, Some(dir.join("zzz_dir")));
+
+    let mut fresh = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    fresh.resume_session(&state).unwrap();
+    assert_eq!(fresh.input_mode, InputMode::Normal);
+    assert!(fresh.tree.show_hidden);
+    assert!(fresh.dual_pane_mode);
+    assert_eq!(fresh.right_pane.as_ref().unwrap().tree.root, dir.join("zzz_dir"));
+    assert_eq!(fresh.selected_entry().map(|e| e.path.clone()), Some(inner));
+    assert!(fresh.marked.is_empty());
+    assert_eq!(fresh.status_message, None);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_session_restore_declined_or_unneeded() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    // Nothing beyond the current directory to restore: no prompt
+    app.offer_session_restore(app.session_state());
+    assert_eq!(app.input_mode, InputMode::Normal);
+
+    let mut state = app.session_state();
+    state.marked.push(dir.join("bbb.txt"));
+    app.offer_session_restore(state);
+    assert_eq!(app.input_mode, InputMode::Prompt);
+    app.update(Action::PromptInput('n')).unwrap();
+    assert!(app.marked.is_empty());
+    assert!(app.pending_session.is_none());
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_toggle_debug_overlay() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    assert!(!app.show_debug);
+    app.update(Action::ToggleDebugOverlay).unwrap();
+    assert!(app.show_debug);
+    // Non-modal: normal input keeps working
+    assert_eq!(app.input_mode, InputMode::Normal);
+    app.update(Action::ToggleDebugOverlay).unwrap();
+    assert!(!app.show_debug);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_tick_recovers_from_deleted_root() {
+    let dir = setup_test_dir();
+    let sub = dir.join("aaa_dir").join("sub");
+    fs::create_dir_all(&sub).unwrap();
+    fs::write(sub.join("file.txt"), "x").unwrap();
+    let mut app = App::new(sub.clone(), None, &cfg(), None).unwrap();
+    app.marked.insert(sub.join("file.txt"));
+
+    fs::remove_dir_all(&sub).unwrap();
+    app.update(Action::Tick).unwrap();
+    assert_eq!(app.tree.root, dir.join("aaa_dir"));
+    assert!(app.marked.is_empty());
+    assert_eq!(app.cursor, 0);
+    assert!(app.status_message.as_deref().is_some_and(|m| m.contains("no longer exists")));
+    assert_eq!(app.breadcrumb_segments.last().map(|s| s.path.clone()), Some(dir.join("aaa_dir")));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_op_log_records_rename_and_delete() {
+    let dir = setup_test_dir();
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::OpLogOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+
+    let log_path = dir.with_extension("oplog");
+    let _ = fs::remove_file(&log_path);
+    app.op_log = OpLog::new(log_path.clone(), true);
+    while app.selected_entry().is_none_or(|e| e.name != "bbb.txt") {
+      app.update(Action::MoveDown).unwrap();
+    }
+    app.update(Action::RenameStart).unwrap();
+    app.prompt.set("renamed.txt".to_string());
+    app.update(Action::PromptConfirm).unwrap();
+    app.update(Action::DeleteFile).unwrap();
+    app.update(Action::PromptInput('y')).unwrap();
+
+    app.update(Action::OpLogOpen).unwrap();
+    assert_eq!(app.input_mode, InputMode::OpLog);
+    let ops: Vec<&str> = app.op_log_entries.iter().map(|r| r.op.as_str()).collect();
+    assert_eq!(ops.len(), 2);
+    assert!(ops[0] == "delete" || ops[0] == "trash");
+    assert_eq!(ops[1], "rename");
+    assert_eq!(app.op_log_entries[1].from.as_deref(), Some(dir.join("bbb.txt").as_path()));
+    assert_eq!(app.op_log_entries[1].to.as_deref(), Some(dir.join("renamed.txt").as_path()));
+    app.update(Action::Escape).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    let _ = fs::remove_file(&log_path);
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_branch_switcher_checks_out_and_creates_branches() {
+    let dir = setup_test_dir();
+    app_with_modified_file(&dir);
+    let repo = git2::Repository::open(&dir).unwrap();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("feature", &head, false).unwrap();
+    let main = repo.head().unwrap().shorthand().unwrap().to_string();
+
+    let mut app = App::new(dir.clone(), None, &cfg(), None).unwrap();
+    app.update(Action::BranchesStart).unwrap();
+    assert_eq!(app.input_mode, InputMode::Branches);
+    assert_eq!(app.branch_current.as_deref(), Some(main.as_str()));
+    // The cursor starts off the current branch
+    assert_eq!(app.branch_choices[app.branch_cursor].name, "feature");
+    app.update(Action::BranchesSelect).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.status_message.as_deref(), Some("Switched to feature"));
+    assert_eq!(app.tree.git_info.branch.as_deref(), Some("feature"));
+    // The uncommitted change came along
+    assert_eq!(fs::read_to_string(dir.join("bbb.txt")).unwrap(), "hello\nworld\n");
+
+    app.update(Action::BranchesStart).unwrap();
+    app.update(Action::BranchesNew).unwrap();
+    assert_eq!(app.prompt_kind, Some(PromptKind::NewBranch));
+    app.prompt.set("topic".to_string());
+    app.update(Action::PromptConfirm).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Switched to new branch topic"));
+    assert_eq!(app.tree.git_info.branch.as_deref(), Some("topic"));
+
+    app.update(Action::BranchesStart).unwrap();
+    app.branch_cursor = app.branch_choices.iter().position(|b| b.name == "topic").unwrap();
+    app.update(Action::BranchesSelect).unwrap();
+    assert_eq!(app.status_message.as_deref(), Some("Already on topic"));
+    cleanup_test_dir(&dir);
+  }
+
+  #[test]
+  fn test_worktrees_add_for_branch_and_switch() {
+    let dir = setup_test_dir();
+    app_with_modified_file(&dir);
+    let repo = git2::Repository::open(&dir).unwrap();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("feature/x", &head, false).unwrap();
+
+    let mut c = cfg();
+    let worktrees = dir.with_extension("wt");
+    c.worktree_dir = Some(worktrees.clone());
+    let mut app = App::new(dir.clone(), None, &c, None).unwrap();
+    app.update(Action::WorktreesStart).unwrap();
+    assert_eq!(app.input_mode, InputMode::Worktrees);
+    assert!(matches!(app.worktree_choices[0], WorktreeChoice::Existing(ref w) if w.is_main));
+    let branch = app.worktree_choices.iter()
+      .position(|c| matches!(c, WorktreeChoice::Branch(b) if b.name == "feature/x"))
+      .unwrap();
+
+    let added = worktrees.join("feature-x");
+    // Outside --restrict nothing is created, and the switcher closes
+    app.restrict_root = Some(dir.clone());
+    app.worktree_cursor = branch;
+    app.update(Action::WorktreesSelect).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert!(!added.exists());
+    assert!(app.status_message.is_some());
+    app.restrict_root = None;
+
+    app.update(Action::WorktreesStart).unwrap();
+    app.worktree_cursor = branch;
+    app.update(Action::WorktreesSelect).unwrap();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.tree.root.canonicalize().unwrap(), added.canonicalize().unwrap());
+    assert!(added.join("bbb.txt").exists());
+
+    // The branch is now listed as a worktree rather than offered again
+    app.update(Action::WorktreesStart).unwrap();
+    assert!(!app.worktree_choices.iter().any(|c| matches!(c, WorktreeChoice::Branch(b) if b.name == "feature/x")));
+    let _ = fs::remove_dir_all(&worktrees);
+    cleanup_test_dir(&dir);
+  }
 }
@@ -0,0 +1,289 @@
+//! `tfl --batch`: runs file commands read from stdin without the TUI.
+
+use std::io::{BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use globset::Glob;
+
+use crate::app::App;
+use crate::fs::{ops, walk};
+use crate::op_log::OpRecord;
+use crate::preview::archive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+  Cd(PathBuf),
+  /// Mark the entries of the current directory matching any glob; no
+  /// globs clears the selection
+  Select(Vec<String>),
+  CopyTo(PathBuf),
+  /// Extract the selected archives, next to each archive by default
+  Extract(Option<PathBuf>),
+  Mkdir(PathBuf),
+}
+
+impl Command {
+  /// Parse one line; Ok(None) for blank lines and `#` comments.
+  pub fn parse(line: &str) -> Result<Option<Command>, String> {
+    let mut args = split_args(line)?.into_iter();
+    let Some(name) = args.next() else {
+      return Ok(None);
+    };
+    let rest: Vec<String> = args.collect();
+    let one = |rest: &[String]| match rest {
+      [arg] => Ok(PathBuf::from(arg)),
+      _ => Err(format!("{name} takes exactly one argument")),
+    };
+    let command = match name.as_str() {
+      "cd" => Command::Cd(one(&rest)?),
+      "select" => Command::Select(rest),
+      "copy-to" => Command::CopyTo(one(&rest)?),
+      "extract" => match rest.as_slice() {
+        [] => Command::Extract(None),
+        _ => Command::Extract(Some(one(&rest)?)),
+      },
+      "mkdir" => Command::Mkdir(one(&rest)?),
+      _ => return Err(format!("unknown command {name:?}")),
+    };
+    Ok(Some(command))
+  }
+
+  /// Name of the command if it modifies files.
+  fn mutation(&self) -> Option<&'static str> {
+    match self {
+      Command::Cd(_) | Command::Select(_) => None,
+      Command::CopyTo(_) => Some("copy-to"),
+      Command::Extract(_) => Some("extract"),
+      Command::Mkdir(_) => Some("mkdir"),
+    }
+  }
+}
+
+/// Split on whitespace, honouring single and double quotes and `\` escapes.
+/// A `#` at the start of a word starts a comment.
+fn split_args(line: &str) -> Result<Vec<String>, String> {
+  let mut args = Vec::new();
+  let mut current: Option<String> = None;
+  let mut quote = None;
+  let mut chars = line.chars();
+  while let Some(c) = chars.next() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some('"') | None, '\\') => {
+        let escaped = chars.next().ok_or("trailing backslash")?;
+        current.get_or_insert_with(String::new).push(escaped);
+      }
+      (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+      (None, '\'' | '"') => {
+        quote = Some(c);
+        current.get_or_insert_with(String::new);
+      }
+      (None, '#') if current.is_none() => break,
+      (None, c) if c.is_whitespace() => args.extend(current.take()),
+      (None, c) => current.get_or_insert_with(String::new).push(c),
+    }
+  }
+  if quote.is_some() {
+    return Err("unterminated quote".to_string());
+  }
+  args.extend(current);
+  Ok(args)
+}
+
+/// Run every command from `input` against `app`, reporting each result on
+/// `out`. Stops at the first failing command and returns its message,
+/// prefixed with the line number.
+pub fn run(app: &mut App, input: impl BufRead, out: &mut impl Write) -> Result<Result<(), String>> {
+  for (i, line) in input.lines().enumerate() {
+    let line = line?;
+    let result = Command::parse(&line).and_then(|command| match command {
+      Some(command) => execute(app, &command).map(Some),
+      None => Ok(None),
+    });
+    match result {
+      Ok(Some(message)) => writeln!(out, "{message}")?,
+      Ok(None) => {}
+      Err(e) => return Ok(Err(format!("line {}: {e}", i + 1))),
+    }
+  }
+  Ok(Ok(()))
+}
+
+/// `path` relative to the current directory, with symlinks and `..`
+/// resolved as far as it exists. Fails when `--restrict` keeps it out.
+fn resolve(app: &mut App, name: &str, path: &Path) -> Result<PathBuf, String> {
+  let mut resolved = PathBuf::new();
+  for component in app.tree.root.join(path).components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        resolved.pop();
+      }
+      component => {
+        resolved.push(component);
+        if resolved.exists() {
+          resolved = resolved.canonicalize().map_err(|e| format!("{name} {}: {e}", path.display()))?;
+        }
+      }
+    }
+  }
+  if app.restricted(&resolved) {
+    return Err(format!("{name} {}: outside the restricted tree", path.display()));
+  }
+  Ok(resolved)
+}
+
+fn execute(app: &mut App, command: &Command) -> Result<String, String> {
+  if app.read_only
+    && let Some(name) = command.mutation()
+  {
+    return Err(format!("{name} is disabled in read-only mode"));
+  }
+  match command {
+    Command::Cd(path) => {
+      let dir = resolve(app, "cd", path)?;
+      if !dir.is_dir() {
+        return Err(format!("cd {}: not a directory", path.display()));
+      }
+      app.change_dir(&dir).map_err(|e| e.to_string())?;
+      Ok(format!("cd {}", dir.display()))
+    }
+    Command::Select(patterns) => {
+      app.marked.clear();
+      for pattern in patterns {
+        let matcher = Glob::new(pattern).map_err(|e| e.to_string())?.compile_matcher();
+        let matches: Vec<PathBuf> = app
+          .tree
+          .entries
+          .iter()
+          .filter(|e| e.path.parent() == Some(app.tree.root.as_path()) && matcher.is_match(&e.name))
+          .map(|e| e.path.clone())
+          .collect();
+        if matches.is_empty() {
+          return Err(format!("select: nothing matches {pattern:?}"));
+        }
+        app.marked.extend(matches);
+      }
+      Ok(format!("selected {}", app.marked.len()))
+    }
+    Command::CopyTo(path) => {
+      let dest_dir = resolve(app, "copy-to", path)?;
+      if !dest_dir.is_dir() {
+        return Err(format!("copy-to {}: not a directory", path.display()));
+      }
+      let sources = selection(app)?;
+      let mut cycles = 0;
+      for source in &sources {
+        let dest = ops::unique_dest_path(&dest_dir.join(source.file_name().unwrap_or_default()));
+        cycles += ops::copy_path_with(source, &dest, &app.copy_options).map_err(|e| format!("copy {}: {e}", source.display()))?;
+        app.log_op(OpRecord::now("copy").from(source).to(&dest));
+      }
+      refresh(app)?;
+      Ok(format!("copied {} to {}{}", sources.len(), dest_dir.display(), walk::cycle_note(cycles)))
+    }
+    Command::Extract(dest) => {
+      let archives = selection(app)?;
+      for path in &archives {
+        if !archive::is_archive(path) {
+          return Err(format!("extract: {} is not an archive", path.display()));
+        }
+        let dest_dir = match dest {
+          Some(dest) => resolve(app, "extract", dest)?,
+          None => path.parent().unwrap_or(&app.tree.root).to_path_buf(),
+        };
+        archive::extract_archive(path, &dest_dir).map_err(|e| format!("extract {}: {e}", path.display()))?;
+        app.log_op(OpRecord::now("extract").from(path).to(&dest_dir));
+      }
+      refresh(app)?;
+      Ok(format!("extracted {}", archives.len()))
+    }
+    Command::Mkdir(path) => {
+      let dir = resolve(app, "mkdir", path)?;
+      std::fs::create_dir_all(&dir).map_err(|e| format!("mkdir {}: {e}", path.display()))?;
+      app.log_op(OpRecord::now("create dir").to(&dir));
+      refresh(app)?;
+      Ok(format!("mkdir {}", dir.display()))
+    }
+  }
+}
+
+fn selection(app: &App) -> Result<Vec<PathBuf>, String> {
+  let mut paths: Vec<PathBuf> = app.marked.iter().cloned().collect();
+  if paths.is_empty() {
+    return Err("nothing selected".to_string());
+  }
+  paths.sort();
+  Ok(paths)
+}
+
+fn refresh(app: &mut App) -> Result<(), String> {
+  app.tree.reload().map_err(|e| e.to_string())?;
+  app.rebuild_visible_cache();
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Config;
+
+  #[test]
+  fn test_split_args() {
+    assert_eq!(split_args("copy-to \"my dir\" # note").unwrap(), vec!["copy-to", "my dir"]);
+    assert_eq!(split_args("select 'a b' c\\ d *.txt").unwrap(), vec!["select", "a b", "c d", "*.txt"]);
+    assert_eq!(split_args("  # comment").unwrap(), Vec::<String>::new());
+    assert_eq!(split_args("mkdir \"\"").unwrap(), vec!["mkdir", ""]);
+    assert!(split_args("cd \"oops").is_err());
+  }
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(Command::parse("cd src").unwrap(), Some(Command::Cd(PathBuf::from("src"))));
+    assert_eq!(Command::parse("extract").unwrap(), Some(Command::Extract(None)));
+    assert_eq!(Command::parse("select").unwrap(), Some(Command::Select(Vec::new())));
+    assert_eq!(Command::parse("").unwrap(), None);
+    assert!(Command::parse("cd a b").is_err());
+    assert!(Command::parse("rm -rf /").is_err());
+  }
+
+  #[test]
+  fn test_run_script() {
+    let dir = std::env::temp_dir().join(format!("tfl_batch_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/a.txt"), "a").unwrap();
+    std::fs::write(dir.join("src/b.txt"), "b").unwrap();
+    std::fs::write(dir.join("src/c.rs"), "c").unwrap();
+
+    let mut app = App::new(dir.clone(), None, &Config::default(), None).unwrap();
+    let script = "mkdir out\ncd src\nselect *.txt\ncopy-to ../out\n";
+    let mut out = Vec::new();
+    let result = run(&mut app, script.as_bytes(), &mut out).unwrap();
+    assert_eq!(result, Ok(()));
+    assert!(dir.join("out/a.txt").exists());
+    assert!(dir.join("out/b.txt").exists());
+    assert!(!dir.join("out/c.rs").exists());
+    assert!(String::from_utf8(out).unwrap().contains("copied 2 to"));
+
+    let result = run(&mut app, "select *.md\nmkdir never\n".as_bytes(), &mut Vec::new()).unwrap();
+    assert_eq!(result, Err("line 1: select: nothing matches \"*.md\"".to_string()));
+    assert!(!dir.join("src/never").exists());
+
+    // Nothing outside the restricted tree, whether by cd or a target path
+    app.restrict_root = Some(dir.join("src").canonicalize().unwrap());
+    for script in ["cd ..\nmkdir escaped\n", "mkdir ../escaped\n", "mkdir new/../../escaped\n", "select *.txt\ncopy-to ../out\n"] {
+      let result = run(&mut app, script.as_bytes(), &mut Vec::new()).unwrap();
+      assert!(result.unwrap_err().ends_with("outside the restricted tree"), "{script:?}");
+    }
+    assert!(!dir.join("escaped").exists());
+    assert_eq!(run(&mut app, "mkdir inside\n".as_bytes(), &mut Vec::new()).unwrap(), Ok(()));
+    assert!(dir.join("src/inside").is_dir());
+    app.restrict_root = None;
+
+    app.read_only = true;
+    let result = run(&mut app, "mkdir never\n".as_bytes(), &mut Vec::new()).unwrap();
+    assert_eq!(result, Err("line 1: mkdir is disabled in read-only mode".to_string()));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,117 @@
+//! Numbered bookmarks. They live in the state dir next to the favorites
+//! rather than in the config dir: they change as you work, and config dirs
+//! are often kept under version control.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::{paths, persist};
+
+/// Slots a bookmark can be set in, typed as a digit after the set or jump key
+pub const SLOTS: std::ops::RangeInclusive<u8> = 1..=9;
+
+/// Bookmarked paths by slot, for each project
+type Projects = BTreeMap<PathBuf, BTreeMap<u8, PathBuf>>;
+
+#[derive(Debug, Clone)]
+struct Change {
+  project: PathBuf,
+  slot: u8,
+  path: PathBuf,
+}
+
+fn apply(projects: &mut Projects, change: Change) {
+  projects.entry(change.project).or_default().insert(change.slot, change.path);
+}
+
+fn read(path: &Path) -> Projects {
+  std::fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// Numbered quick-jump bookmarks, kept per project (the repository root, or
+/// the tree root outside one) in `bookmarks.json` in the state dir. Unlike
+/// favorites they can point at files. Saving replays this session's changes
+/// onto the file so instances don't drop each other's bookmarks.
+pub struct Bookmarks {
+  path: PathBuf,
+  projects: Projects,
+  /// Changes made since the last save
+  pending: Vec<Change>,
+}
+
+impl Bookmarks {
+  pub fn load() -> Self {
+    Self::load_from(paths::state_dir().join("bookmarks.json"))
+  }
+
+  pub fn load_from(path: PathBuf) -> Self {
+    let projects = read(&path);
+    Self { path, projects, pending: Vec::new() }
+  }
+
+  pub fn save(&mut self) -> Result<()> {
+    if self.pending.is_empty() {
+      return Ok(());
+    }
+    let mut projects = read(&self.path);
+    for change in self.pending.drain(..) {
+      apply(&mut projects, change);
+    }
+    persist::write_atomic(&self.path, serde_json::to_string_pretty(&projects)?)?;
+    self.projects = projects;
+    Ok(())
+  }
+
+  /// Point `slot` of `project` at `path`, replacing what it held.
+  pub fn set(&mut self, project: &Path, slot: u8, path: &Path) {
+    let change = Change { project: project.to_path_buf(), slot, path: path.to_path_buf() };
+    apply(&mut self.projects, change.clone());
+    self.pending.push(change);
+  }
+
+  pub fn get(&self, project: &Path, slot: u8) -> Option<&Path> {
+    self.projects.get(project)?.get(&slot).map(PathBuf::as_path)
+  }
+
+  /// The set slots of `project`, in order.
+  pub fn list(&self, project: &Path) -> impl Iterator<Item = (u8, &Path)> {
+    self.projects.get(project).into_iter().flatten().map(|(slot, path)| (*slot, path.as_path()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_slots_are_per_project_and_saves_merge() {
+    let path = std::env::temp_dir().join(format!("tfl_bookmarks_test_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (web, api) = (Path::new("/src/web"), Path::new("/src/api"));
+
+    let mut a = Bookmarks::load_from(path.clone());
+    let mut b = Bookmarks::load_from(path.clone());
+    a.set(web, 1, Path::new("/src/web/index.html"));
+    a.set(web, 3, Path::new("/src/web/css"));
+    a.set(web, 1, Path::new("/src/web/app.js"));
+    a.set(api, 1, Path::new("/src/api/main.rs"));
+    assert_eq!(a.get(web, 1), Some(Path::new("/src/web/app.js")));
+    assert_eq!(a.get(api, 1), Some(Path::new("/src/api/main.rs")));
+    assert_eq!(a.get(api, 3), None);
+    a.save().unwrap();
+
+    // Another instance's save keeps what this one wrote
+    b.set(web, 2, Path::new("/src/web/README.md"));
+    b.save().unwrap();
+    let slots: Vec<u8> = b.list(web).map(|(slot, _)| slot).collect();
+    assert_eq!(slots, [1, 2, 3]);
+
+    let loaded = Bookmarks::load_from(path.clone());
+    assert_eq!(loaded.get(web, 1), Some(Path::new("/src/web/app.js")));
+    assert_eq!(loaded.get(web, 2), Some(Path::new("/src/web/README.md")));
+    assert_eq!(loaded.list(Path::new("/elsewhere")).count(), 0);
+    let _ = std::fs::remove_file(&path);
+  }
+}
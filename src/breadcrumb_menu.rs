@@ -0,0 +1,102 @@
+//! Dropdown under a breadcrumb segment listing the directories inside it,
+//! for moving sideways to a sibling of the current directory or down into
+//! one without expanding the tree.
+
+use std::path::{Path, PathBuf};
+
+pub struct BreadcrumbMenu {
+  /// Directory whose subdirectories are listed
+  pub dir: PathBuf,
+  pub entries: Vec<PathBuf>,
+  pub cursor: usize,
+  show_hidden: bool,
+}
+
+impl BreadcrumbMenu {
+  /// List the subdirectories of `dir`, highlighting the one `current` is
+  /// in, if any.
+  pub fn open(dir: &Path, current: &Path, show_hidden: bool) -> Self {
+    let entries = subdirectories(dir, show_hidden);
+    let cursor = entries.iter().position(|e| current.starts_with(e)).unwrap_or(0);
+    Self { dir: dir.to_path_buf(), entries, cursor, show_hidden }
+  }
+
+  pub fn selected(&self) -> Option<&PathBuf> {
+    self.entries.get(self.cursor)
+  }
+
+  pub fn move_down(&mut self) {
+    self.cursor = (self.cursor + 1).min(self.entries.len().saturating_sub(1));
+  }
+
+  pub fn move_up(&mut self) {
+    self.cursor = self.cursor.saturating_sub(1);
+  }
+
+  /// List the parent directory instead, highlighting the one left.
+  pub fn parent(&mut self) {
+    if let Some(parent) = self.dir.parent() {
+      *self = Self::open(parent, &self.dir, self.show_hidden);
+    }
+  }
+
+  /// List the highlighted directory instead.
+  pub fn child(&mut self) {
+    if let Some(dir) = self.selected().cloned() {
+      *self = Self::open(&dir, &dir, self.show_hidden);
+    }
+  }
+}
+
+/// The directories in `dir` (symlinks to directories included), sorted by
+/// name ignoring case.
+pub fn subdirectories(dir: &Path, show_hidden: bool) -> Vec<PathBuf> {
+  let Ok(read) = std::fs::read_dir(dir) else {
+    return Vec::new();
+  };
+  let mut dirs: Vec<PathBuf> = read
+    .flatten()
+    .filter(|e| show_hidden || !e.file_name().to_string_lossy().starts_with('.'))
+    .map(|e| e.path())
+    .filter(|p| p.is_dir())
+    .collect();
+  dirs.sort_by_cached_key(|p| p.file_name().unwrap_or_default().to_string_lossy().to_lowercase());
+  dirs
+}
+
+/// First of `rows` visible entries that keeps `cursor` in view.
+pub fn scroll_offset(cursor: usize, rows: usize) -> usize {
+  cursor.saturating_sub(rows.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_menu_lists_subdirectories_and_walks_levels() {
+    let dir = std::env::temp_dir().join(format!("tfl_breadcrumb_menu_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for sub in ["Beta/inner", "alpha", "gamma", ".hidden"] {
+      std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join("file.txt"), "").unwrap();
+
+    let mut menu = BreadcrumbMenu::open(&dir, &dir.join("gamma"), false);
+    let names: Vec<_> = menu.entries.iter().map(|p| p.file_name().unwrap().to_string_lossy().to_string()).collect();
+    assert_eq!(names, ["alpha", "Beta", "gamma"]);
+    assert_eq!(menu.selected(), Some(&dir.join("gamma")));
+    assert_eq!(BreadcrumbMenu::open(&dir, &dir, true).entries.len(), 4);
+
+    menu.move_up();
+    menu.child();
+    assert_eq!(menu.dir, dir.join("Beta"));
+    assert_eq!(menu.entries, [dir.join("Beta/inner")]);
+    menu.parent();
+    assert_eq!(menu.selected(), Some(&dir.join("Beta")));
+
+    assert_eq!(scroll_offset(2, 5), 0);
+    assert_eq!(scroll_offset(7, 5), 3);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
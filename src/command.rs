@@ -0,0 +1,376 @@
+//! Commands: the layer between actions and `App`.
+//!
+//! Every action runs as a [`Command`], which is where read-only mode is
+//! enforced and where an action's confirmation prompt is chosen. File
+//! operations that completed are recorded as commands of their own in
+//! [`History`], so `undo` reverses them and `redo` runs them again.
+
+use std::fmt::Debug;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+
+use crate::action::Action;
+use crate::app::App;
+use crate::config::QuitConfirm;
+use crate::event::PromptKind;
+use crate::fs::ops;
+
+/// How many completed operations `undo` can step back through
+const HISTORY_LIMIT: usize = 100;
+
+pub trait Command: Debug {
+  /// Run the command. Recorded operations also replay through this on redo.
+  fn execute(&mut self, app: &mut App) -> Result<()>;
+
+  /// Reverse an earlier `execute`.
+  fn undo(&mut self, _app: &mut App) -> Result<()> {
+    bail!("{} can't be undone", self.describe())
+  }
+
+  /// Short description for the status bar, e.g. `rename a.txt → b.txt`.
+  fn describe(&self) -> String;
+
+  /// What the command changes on disk, for the read-only mode message, or
+  /// None when it doesn't modify anything.
+  fn mutation(&self) -> Option<&'static str> {
+    None
+  }
+
+  /// The y/N prompt to answer before the command runs, None to run it
+  /// straight away, or why it can't run. `App::run` opens the prompt and
+  /// `y` carries out what it asks.
+  fn confirmation(&self, _app: &App) -> Result<Option<PromptKind>, String> {
+    Ok(None)
+  }
+}
+
+impl Command for Action {
+  fn execute(&mut self, app: &mut App) -> Result<()> {
+    app.dispatch(self.clone())
+  }
+
+  /// What the help overlay calls the action, else its binding name in
+  /// words: `RenameStart` → `rename`, `CopyToStart` → `copy to...`.
+  fn describe(&self) -> String {
+    match (self.description(), self.name()) {
+      (Some(description), _) => {
+        let mut chars = description.chars();
+        chars.next().map(|c| c.to_lowercase().chain(chars).collect()).unwrap_or_default()
+      }
+      (None, Some(name)) => name.replace('_', " "),
+      (None, None) => "input".to_string(),
+    }
+  }
+
+  fn mutation(&self) -> Option<&'static str> {
+    Action::mutation(self)
+  }
+
+  /// Deleting, discarding changes, changing a hunk and extracting an
+  /// archive that is then deleted all ask first; quitting does with
+  /// `quit_confirm = "prompt"`.
+  fn confirmation(&self, app: &App) -> Result<Option<PromptKind>, String> {
+    match self {
+      Action::DeleteFile => Ok(app.delete_confirmation()),
+      Action::GitDiscard => app.git_discard_confirmation(),
+      Action::ExtractAndDelete => app.extract_and_delete_confirmation(),
+      Action::StageHunk => app.hunk_confirmation(false),
+      Action::RevertHunk => app.hunk_confirmation(true),
+      Action::Quit if app.quit_confirm == QuitConfirm::Prompt => Ok(Some(PromptKind::ConfirmQuit)),
+      _ => Ok(None),
+    }
+  }
+}
+
+fn file_name(path: &Path) -> String {
+  path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().to_string())
+}
+
+/// Move `from` to `to`, copying across filesystems with everything kept.
+/// Never overwrites.
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+  if to.symlink_metadata().is_ok() {
+    bail!("{} already exists", file_name(to));
+  }
+  if std::fs::rename(from, to).is_err() {
+    ops::copy_path_with(from, to, &ops::CopyOptions::archive())?;
+    if from.is_dir() {
+      std::fs::remove_dir_all(from)?;
+    } else {
+      std::fs::remove_file(from)?;
+    }
+  }
+  Ok(())
+}
+
+/// A rename from the prompt.
+#[derive(Debug)]
+pub struct Rename {
+  pub from: PathBuf,
+  pub to: PathBuf,
+}
+
+impl Command for Rename {
+  fn execute(&mut self, _app: &mut App) -> Result<()> {
+    move_path(&self.from, &self.to)
+  }
+
+  fn undo(&mut self, _app: &mut App) -> Result<()> {
+    move_path(&self.to, &self.from)
+  }
+
+  fn describe(&self) -> String {
+    format!("rename {} → {}", file_name(&self.from), file_name(&self.to))
+  }
+
+  fn mutation(&self) -> Option<&'static str> {
+    Some("rename")
+  }
+}
+
+/// A new file or directory. Undo only removes empty directories, and moves
+/// a file to the trash when it is enabled; its contents and mode are kept
+/// so redo brings it back as it was.
+#[derive(Debug)]
+pub struct Create {
+  pub path: PathBuf,
+  pub is_dir: bool,
+  /// Outermost directory that didn't exist before, for `a/b/c` names
+  top: PathBuf,
+  contents: Vec<u8>,
+  mode: Option<u32>,
+}
+
+impl Create {
+  pub fn file(path: PathBuf) -> Self {
+    Self { top: path.clone(), path, is_dir: false, contents: Vec::new(), mode: None }
+  }
+
+  /// Call before creating `path`, so missing parents are known.
+  pub fn dir(path: PathBuf) -> Self {
+    let top = path
+      .ancestors()
+      .take_while(|p| p.symlink_metadata().is_err())
+      .last()
+      .unwrap_or(&path)
+      .to_path_buf();
+    Self { path, is_dir: true, top, contents: Vec::new(), mode: None }
+  }
+}
+
+impl Command for Create {
+  fn execute(&mut self, _app: &mut App) -> Result<()> {
+    if self.path.symlink_metadata().is_ok() {
+      bail!("{} already exists", file_name(&self.path));
+    }
+    if self.is_dir {
+      std::fs::create_dir_all(&self.path)?;
+    } else {
+      std::fs::write(&self.path, &self.contents)?;
+      if let Some(mode) = self.mode {
+        std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(mode))?;
+      }
+    }
+    Ok(())
+  }
+
+  fn undo(&mut self, app: &mut App) -> Result<()> {
+    if self.is_dir {
+      // Fails on a directory that has been filled since, which is the point
+      for dir in self.path.ancestors() {
+        std::fs::remove_dir(dir)?;
+        if dir == self.top {
+          break;
+        }
+      }
+    } else {
+      self.contents = std::fs::read(&self.path)?;
+      self.mode = Some(std::fs::metadata(&self.path)?.permissions().mode());
+      App::remove_path(&self.path, app.use_trash).map_err(anyhow::Error::msg)?;
+    }
+    Ok(())
+  }
+
+  fn describe(&self) -> String {
+    let kind = if self.is_dir { "dir" } else { "file" };
+    format!("new {kind} {}", file_name(&self.path))
+  }
+
+  fn mutation(&self) -> Option<&'static str> {
+    Some(if self.is_dir { "new directory" } else { "new file" })
+  }
+}
+
+/// A symlink from the prompt. Undo removes it while it is still a link.
+#[derive(Debug)]
+pub struct Symlink {
+  pub link: PathBuf,
+  /// As written into the link
+  pub target: PathBuf,
+}
+
+impl Command for Symlink {
+  fn execute(&mut self, _app: &mut App) -> Result<()> {
+    if self.link.symlink_metadata().is_ok() {
+      bail!("{} already exists", file_name(&self.link));
+    }
+    std::os::unix::fs::symlink(&self.target, &self.link)?;
+    Ok(())
+  }
+
+  fn undo(&mut self, _app: &mut App) -> Result<()> {
+    if !self.link.symlink_metadata()?.is_symlink() {
+      bail!("{} is no longer a symlink", file_name(&self.link));
+    }
+    std::fs::remove_file(&self.link)?;
+    Ok(())
+  }
+
+  fn describe(&self) -> String {
+    format!("symlink {}", file_name(&self.link))
+  }
+
+  fn mutation(&self) -> Option<&'static str> {
+    Some("symlink")
+  }
+}
+
+/// Pasted entries, as `(source, destination)` pairs. A cut moves them,
+/// undone by moving them back; a copy is undone by removing the copies,
+/// to the trash when it is enabled.
+#[derive(Debug)]
+pub struct Paste {
+  pub pairs: Vec<(PathBuf, PathBuf)>,
+  pub cut: bool,
+  /// How the copies were made, for redo
+  pub options: ops::CopyOptions,
+}
+
+impl Command for Paste {
+  fn execute(&mut self, _app: &mut App) -> Result<()> {
+    for (from, to) in &self.pairs {
+      if self.cut {
+        move_path(from, to)?;
+      } else {
+        if to.symlink_metadata().is_ok() {
+          bail!("{} already exists", file_name(to));
+        }
+        ops::copy_path_with(from, to, &self.options)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn undo(&mut self, app: &mut App) -> Result<()> {
+    for (from, to) in self.pairs.iter().rev() {
+      if self.cut {
+        move_path(to, from)?;
+      } else {
+        App::remove_path(to, app.use_trash).map_err(anyhow::Error::msg)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn describe(&self) -> String {
+    let verb = if self.cut { "move" } else { "copy" };
+    match self.pairs.as_slice() {
+      [(from, _)] => format!("{verb} {}", file_name(from)),
+      pairs => format!("{verb} {} items", pairs.len()),
+    }
+  }
+
+  fn mutation(&self) -> Option<&'static str> {
+    Some("paste")
+  }
+}
+
+/// Completed operations for undo, and undone ones for redo.
+#[derive(Debug, Default)]
+pub struct History {
+  done: Vec<Box<dyn Command>>,
+  undone: Vec<Box<dyn Command>>,
+}
+
+impl History {
+  /// Remember a completed operation. Anything undone before it can no
+  /// longer be redone.
+  pub fn record(&mut self, command: impl Command + 'static) {
+    self.undone.clear();
+    self.push_done(Box::new(command));
+  }
+
+  fn push_done(&mut self, command: Box<dyn Command>) {
+    self.done.push(command);
+    if self.done.len() > HISTORY_LIMIT {
+      self.done.remove(0);
+    }
+  }
+
+  pub fn take_undo(&mut self) -> Option<Box<dyn Command>> {
+    self.done.pop()
+  }
+
+  pub fn take_redo(&mut self) -> Option<Box<dyn Command>> {
+    self.undone.pop()
+  }
+
+  /// Put back a command whose undo succeeded, for redo.
+  pub fn undone(&mut self, command: Box<dyn Command>) {
+    self.undone.push(command);
+  }
+
+  /// Put back a command whose redo succeeded, for undo.
+  pub fn redone(&mut self, command: Box<dyn Command>) {
+    self.push_done(command);
+  }
+
+  pub fn len(&self) -> usize {
+    self.done.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.done.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_action_describe() {
+    assert_eq!(Action::RenameStart.describe(), "rename");
+    assert_eq!(Action::OpenEditor.describe(), "open in $EDITOR");
+    assert_eq!(Action::SwitchPane.describe(), "switch pane");
+    assert_eq!(Action::SearchInput('x').describe(), "input");
+    assert_eq!(Command::mutation(&Action::DeleteFile), Some("delete"));
+    assert_eq!(Command::mutation(&Action::MoveDown), None);
+  }
+
+  #[test]
+  fn test_paste_describe() {
+    let one = Paste { pairs: vec![("/a/x.txt".into(), "/b/x.txt".into())], cut: true, options: ops::CopyOptions::default() };
+    assert_eq!(one.describe(), "move x.txt");
+    let two = Paste { pairs: vec![("/a/x".into(), "/b/x".into()), ("/a/y".into(), "/b/y".into())], cut: false, options: ops::CopyOptions::default() };
+    assert_eq!(two.describe(), "copy 2 items");
+  }
+
+  #[test]
+  fn test_history_limit_and_redo_cleared_by_record() {
+    let mut history = History::default();
+    for i in 0..HISTORY_LIMIT + 5 {
+      history.record(Create::file(PathBuf::from(format!("/tmp/{i}"))));
+    }
+    assert_eq!(history.len(), HISTORY_LIMIT);
+
+    let last = history.take_undo().unwrap();
+    assert_eq!(last.describe(), format!("new file {}", HISTORY_LIMIT + 4));
+    history.undone(last);
+    assert_eq!(history.undone.len(), 1);
+    history.record(Create::dir(PathBuf::from("/tmp/d")));
+    assert!(history.take_redo().is_none());
+  }
+}
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::Deserialize;
 
 use crate::action::Action;
+use crate::fs::ops::CopyOptions;
 use crate::opener::OpenApp;
+use crate::templates::{ContextRule, FileTemplate};
 use crate::theme::Theme;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -44,14 +47,173 @@ impl KeyBinding {
   }
 }
 
+/// What it takes for `quit` to exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuitConfirm {
+  Instant,
+  /// Press quit twice in quick succession
+  Double,
+  /// Answer a y/N prompt
+  Prompt,
+}
+
+impl QuitConfirm {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "instant" => Some(Self::Instant),
+      "double" => Some(Self::Double),
+      "prompt" => Some(Self::Prompt),
+      _ => None,
+    }
+  }
+}
+
+/// Where tfl opens when started without a path and without `start_dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartIn {
+  /// The working directory, or home when it is `/` (as when launched from
+  /// a desktop handler)
+  Cwd,
+  Home,
+  /// The directory tfl was last quit in
+  Last,
+  /// The whole session tfl was last quit in: its directory, expanded
+  /// directories, selection, hidden files and second pane
+  Session,
+}
+
+impl StartIn {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "cwd" => Some(Self::Cwd),
+      "home" => Some(Self::Home),
+      "last" => Some(Self::Last),
+      "session" => Some(Self::Session),
+      _ => None,
+    }
+  }
+}
+
+/// When pasting renames entries to names FAT and NTFS accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafeNames {
+  /// Only onto mounts detected as FAT, exFAT or NTFS
+  Auto,
+  /// Onto every destination, e.g. SMB shares served from Windows
+  Always,
+  Never,
+}
+
+impl SafeNames {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "auto" => Some(Self::Auto),
+      "always" => Some(Self::Always),
+      "never" => Some(Self::Never),
+      _ => None,
+    }
+  }
+}
+
+/// A region `cycle_focus` moves between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusRegion {
+  Left,
+  /// The right tree; skipped outside dual-pane mode
+  Right,
+  Preview,
+}
+
+impl FocusRegion {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "left" => Some(Self::Left),
+      "right" => Some(Self::Right),
+      "preview" => Some(Self::Preview),
+      _ => None,
+    }
+  }
+}
+
+/// Compile `patterns` into a GlobSet, reporting the invalid ones as
+/// `invalid {what} pattern`.
+fn compile_glob_set(patterns: &[String], what: &str, errors: &mut Vec<String>) -> GlobSet {
+  let mut builder = GlobSetBuilder::new();
+  for pattern in patterns {
+    match Glob::new(pattern) {
+      Ok(glob) => {
+        builder.add(glob);
+      }
+      Err(e) => {
+        errors.push(format!("invalid {what} pattern {pattern:?}: {e}"));
+      }
+    }
+  }
+  builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+/// `~` and `~/...` relative to the home directory.
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+  let home = dirs::home_dir();
+  match (path.strip_prefix('~'), home) {
+    (Some(""), Some(home)) => home,
+    (Some(rest), Some(home)) if rest.starts_with('/') => home.join(rest.trim_start_matches('/')),
+    _ => PathBuf::from(path),
+  }
+}
+
+/// Target of a chmod dialog preset key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChmodPresetMode {
+  Octal(u32),
+  /// The parent directory's permissions, without execute bits for files
+  MatchParent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChmodPreset {
+  pub key: KeyBinding,
+  pub mode: ChmodPresetMode,
+}
+
+impl ChmodPreset {
+  pub fn label(&self) -> String {
+    match self.mode {
+      ChmodPresetMode::Octal(mode) => format!("{mode:03o}"),
+      ChmodPresetMode::MatchParent => "parent".to_string(),
+    }
+  }
+}
+
+/// Keys the chmod dialog handles itself; presets can't use them.
+const CHMOD_DIALOG_KEYS: &str = "rwxRWXugtdlq01234567";
+
 pub struct Config {
   pub tree_ratio: u16,
   pub min_tree_ratio: u16,
   pub max_tree_ratio: u16,
   pub ratio_step: u16,
   pub tick_rate_ms: u64,
+  /// Repeats of a held movement key before it moves 5 entries at a time
+  /// (10 after twice as many); 0 disables acceleration
+  pub move_accel_after: usize,
+  /// Longest gap between key events that still counts as holding the key
+  pub move_accel_ms: u64,
   pub claude_yolo: bool,
+  /// AI CLI launched by `c`; see `opener::expand_ai_command`
+  pub ai_command: String,
+  /// Launched by `C`, or by `c` when `claude_yolo` is set
+  pub ai_command_alt: String,
+  /// Run by `tmux_window`, `tmux_pane` and `zellij_tab`; see
+  /// `opener::expand_dir_command`
+  pub tmux_window_command: String,
+  pub tmux_pane_command: String,
+  pub zellij_tab_command: String,
+  /// Terminal `s` opens detached instead of suspending for `$SHELL`, when a
+  /// graphical session is available; empty always suspends
+  pub terminal_command: String,
   pub use_trash: bool,
+  pub difftool: String,
   pub theme_name: String,
   pub syntax_theme: String,
   pub theme: Theme,
@@ -63,7 +225,67 @@ pub struct Config {
   pub use_gitignore: bool,
   pub use_custom_ignore: bool,
   pub ignore_glob_set: GlobSet,
+  /// List directories before files; toggled at runtime with `toggle_dirs_first`
+  pub dirs_first: bool,
+  /// Names kept at the top of their directory, highest priority first
+  pub pinned_patterns: Vec<String>,
+  pub pinned_glob_set: GlobSet,
+  /// How copies treat symlinks and metadata (`[copy]`)
+  pub copy_options: CopyOptions,
   pub has_apps_file: bool,
+  pub template_rules: Vec<ContextRule>,
+  pub chmod_presets: Vec<ChmodPreset>,
+  pub quit_confirm: QuitConfirm,
+  pub esc_quits: bool,
+  pub print_last_dir: bool,
+  /// Disable every action that modifies files
+  pub read_only: bool,
+  /// Share the cut/copy clipboard with other running instances
+  pub share_clipboard: bool,
+  /// Capture the mouse (clicks, hover); off leaves text selection to the
+  /// terminal
+  pub mouse: bool,
+  /// Mouse hover delay before the quick-preview popup; 0 disables it
+  pub hover_preview_ms: u64,
+  /// Search queries and prompt names remembered per kind; 0 disables history
+  pub history_size: usize,
+  /// Extractions and compressions run at once; more wait in the jobs queue
+  pub max_jobs: usize,
+  /// Draw a halfblock thumbnail under image details when the terminal has
+  /// no graphics protocol
+  pub image_thumbnails: bool,
+  /// Preview the selection as the cursor moves; off previews only on `R`
+  pub auto_preview: bool,
+  /// How long reading a file for its preview may take before it is given
+  /// up on; 0 waits however long it takes
+  pub preview_timeout_ms: u64,
+  /// Offer to rename pasted entries the destination filesystem would reject
+  pub safe_names: SafeNames,
+  /// Append every file operation to `operations.log` in the state dir
+  pub op_log: bool,
+  /// Show the child count of collapsed directories in the tree
+  pub dir_counts: bool,
+  /// Line above the status bar with the keys that fit the selection
+  pub hint_bar: bool,
+  /// File type icons in the tree (needs a Nerd Font)
+  pub icons: bool,
+  /// Git status markers before names in the tree and change counts next
+  /// to the branch in the header
+  pub git_markers: bool,
+  /// Start with hidden files shown, as `--all` does
+  pub show_hidden: bool,
+  /// OSC 8 hyperlinks on URLs and paths in the preview
+  pub hyperlinks: bool,
+  /// Regions `cycle_focus` (Tab) visits, in order
+  pub focus_order: Vec<FocusRegion>,
+  /// Terminal window title; `{dir}`, `{name}` and `{project}` are filled
+  /// in, empty leaves the title alone
+  pub window_title: String,
+  /// Where the worktree switcher adds new worktrees
+  pub worktree_dir: Option<PathBuf>,
+  /// Directory to open when no path is given; overrides `start_in`
+  pub start_dir: Option<PathBuf>,
+  pub start_in: StartIn,
 }
 
 #[derive(Deserialize, Default)]
@@ -71,6 +293,34 @@ struct TomlConfig {
   general: Option<GeneralConfig>,
   keys: Option<KeysConfig>,
   ignore: Option<IgnoreConfig>,
+  sort: Option<SortConfig>,
+  copy: Option<CopyConfig>,
+  templates: Option<TemplatesConfig>,
+  chmod: Option<ChmodConfig>,
+}
+
+#[derive(Deserialize, Default)]
+struct ChmodConfig {
+  presets: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize, Default)]
+struct TemplatesConfig {
+  context: Option<Vec<ContextRuleEntry>>,
+}
+
+#[derive(Deserialize)]
+struct ContextRuleEntry {
+  marker: String,
+  #[serde(default)]
+  templates: Vec<TemplateEntry>,
+}
+
+#[derive(Deserialize)]
+struct TemplateEntry {
+  name: String,
+  #[serde(default)]
+  content: String,
 }
 
 #[derive(Deserialize, Default)]
@@ -80,6 +330,19 @@ struct IgnoreConfig {
   use_custom: Option<bool>,
 }
 
+#[derive(Deserialize, Default)]
+struct SortConfig {
+  dirs_first: Option<bool>,
+  pinned: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Default)]
+struct CopyConfig {
+  symlinks: Option<String>,
+  preserve: Option<Vec<String>>,
+  verify: Option<bool>,
+}
+
 #[derive(Deserialize)]
 struct AppEntry {
   name: String,
@@ -95,14 +358,91 @@ struct AppsFile {
   apps: Vec<AppEntry>,
 }
 
+/// Contents of a file apps are read from, unless someone other than the
+/// user could have written it: its commands would be run as the user.
+fn read_private(path: &Path, errors: &mut Vec<String>) -> Option<String> {
+  let meta = std::fs::metadata(path).ok()?;
+  if !crate::fs::properties::is_private(&meta) {
+    errors.push(format!(
+      "ignored {}: it must be owned by you and not writable by group or others",
+      path.display()
+    ));
+    return None;
+  }
+  std::fs::read_to_string(path).ok()
+}
+
+/// The `[[apps]]` entries of `s`, read from `file`.
+fn parse_apps(s: &str, file: &str, errors: &mut Vec<String>) -> Vec<OpenApp> {
+  let apps_file: AppsFile = match toml::from_str(s) {
+    Ok(f) => f,
+    Err(e) => {
+      errors.push(format!("failed to parse {file}: {e}"));
+      return Vec::new();
+    }
+  };
+
+  let mut apps = Vec::new();
+  for entry in apps_file.apps {
+    if entry.command.is_none() && entry.macos_app.is_none() {
+      errors.push(format!("app {:?} needs command or macos_app", entry.name));
+      continue;
+    }
+    apps.push(OpenApp {
+      name: entry.name,
+      command: entry.command.unwrap_or_default(),
+      is_tui: entry.tui.unwrap_or(false),
+      macos_app: entry.macos_app,
+      opens_dir: entry.opens_dir.unwrap_or(false),
+      dir_mode: false,
+      project: false,
+    });
+  }
+  apps
+}
+
 #[derive(Deserialize, Default)]
 struct GeneralConfig {
   tree_ratio: Option<u16>,
   tick_rate_ms: Option<u64>,
+  move_accel_after: Option<usize>,
+  move_accel_ms: Option<u64>,
   claude_yolo: Option<bool>,
+  ai_command: Option<String>,
+  ai_command_alt: Option<String>,
+  tmux_window_command: Option<String>,
+  tmux_pane_command: Option<String>,
+  zellij_tab_command: Option<String>,
+  terminal_command: Option<String>,
   use_trash: Option<bool>,
+  difftool: Option<String>,
   theme: Option<String>,
   syntax_theme: Option<String>,
+  quit_confirm: Option<String>,
+  esc_quits: Option<bool>,
+  print_last_dir: Option<bool>,
+  read_only: Option<bool>,
+  share_clipboard: Option<bool>,
+  mouse: Option<bool>,
+  hover_preview_ms: Option<u64>,
+  history_size: Option<usize>,
+  max_jobs: Option<usize>,
+  image_thumbnails: Option<bool>,
+  preview_timeout_ms: Option<u64>,
+  auto_preview: Option<bool>,
+  safe_names: Option<String>,
+  op_log: Option<bool>,
+  dir_counts: Option<bool>,
+  hint_bar: Option<bool>,
+  icons: Option<bool>,
+  git_markers: Option<bool>,
+  show_hidden: Option<bool>,
+  hyperlinks: Option<bool>,
+  focus_order: Option<Vec<String>>,
+  window_title: Option<String>,
+  worktree_dir: Option<String>,
+  start_dir: Option<String>,
+  start_in: Option<String>,
 }
 
 #[derive(Deserialize, Default)]
@@ -222,8 +562,17 @@ impl Config {
       max_tree_ratio: 60,
       ratio_step: 5,
       tick_rate_ms: 100,
+      move_accel_after: 8,
+      move_accel_ms: 100,
       claude_yolo: false,
+      ai_command: "claude {mentions}".to_string(),
+      ai_command_alt: "claude --dangerously-skip-permissions {mentions}".to_string(),
+      tmux_window_command: "tmux new-window -c {dir} -n {name}".to_string(),
+      tmux_pane_command: "tmux split-window -h -c {dir}".to_string(),
+      zellij_tab_command: "zellij action new-tab --cwd {dir} --name {name}".to_string(),
+      terminal_command: String::new(),
       use_trash: true,
+      difftool: "git difftool --no-prompt --".to_string(),
       theme_name: "dark".to_string(),
       syntax_theme: "base16-ocean.dark".to_string(),
       theme: Theme::dark(),
@@ -235,7 +584,38 @@ impl Config {
       use_gitignore: true,
       use_custom_ignore: true,
       ignore_glob_set: GlobSet::empty(),
+      dirs_first: true,
+      pinned_patterns: Vec::new(),
+      pinned_glob_set: GlobSet::empty(),
+      copy_options: CopyOptions::default(),
       has_apps_file: false,
+      template_rules: Vec::new(),
+      chmod_presets: Vec::new(),
+      quit_confirm: QuitConfirm::Instant,
+      esc_quits: true,
+      print_last_dir: false,
+      read_only: false,
+      share_clipboard: true,
+      mouse: true,
+      hover_preview_ms: 600,
+      history_size: 100,
+      max_jobs: 1,
+      image_thumbnails: true,
+      preview_timeout_ms: crate::preview::DEFAULT_TIMEOUT_MS,
+      auto_preview: true,
+      safe_names: SafeNames::Auto,
+      op_log: false,
+      dir_counts: false,
+      hint_bar: false,
+      icons: true,
+      git_markers: true,
+      show_hidden: false,
+      hyperlinks: true,
+      focus_order: vec![FocusRegion::Left, FocusRegion::Right, FocusRegion::Preview],
+      window_title: "tfl: {dir}".to_string(),
+      worktree_dir: None,
+      start_dir: None,
+      start_in: StartIn::Cwd,
     }
   }
 
@@ -255,12 +635,39 @@ impl Config {
       if let Some(tick) = general.tick_rate_ms {
         self.tick_rate_ms = tick;
       }
+      if let Some(after) = general.move_accel_after {
+        self.move_accel_after = after;
+      }
+      if let Some(ms) = general.move_accel_ms {
+        self.move_accel_ms = ms;
+      }
       if let Some(yolo) = general.claude_yolo {
         self.claude_yolo = yolo;
       }
+      if let Some(cmd) = general.ai_command {
+        self.ai_command = cmd;
+      }
+      if let Some(cmd) = general.ai_command_alt {
+        self.ai_command_alt = cmd;
+      }
+      if let Some(cmd) = general.tmux_window_command {
+        self.tmux_window_command = cmd;
+      }
+      if let Some(cmd) = general.tmux_pane_command {
+        self.tmux_pane_command = cmd;
+      }
+      if let Some(cmd) = general.zellij_tab_command {
+        self.zellij_tab_command = cmd;
+      }
+      if let Some(cmd) = general.terminal_command {
+        self.terminal_command = cmd;
+      }
       if let Some(trash) = general.use_trash {
         self.use_trash = trash;
       }
+      if let Some(tool) = general.difftool {
+        self.difftool = tool;
+      }
       if let Some(ref name) = general.theme {
         match Theme::from_name(name) {
           Some(t) => {
@@ -277,6 +684,102 @@ impl Config {
       if let Some(ref st) = general.syntax_theme {
         self.syntax_theme = st.clone();
       }
+      if let Some(ref name) = general.quit_confirm {
+        match QuitConfirm::from_name(name) {
+          Some(q) => self.quit_confirm = q,
+          None => errors.push(format!("unknown quit_confirm {name:?} (available: instant, double, prompt)")),
+        }
+      }
+      if let Some(esc) = general.esc_quits {
+        self.esc_quits = esc;
+      }
+      if let Some(print) = general.print_last_dir {
+        self.print_last_dir = print;
+      }
+      if let Some(read_only) = general.read_only {
+        self.read_only = read_only;
+      }
+      if let Some(share) = general.share_clipboard {
+        self.share_clipboard = share;
+      }
+      if let Some(mouse) = general.mouse {
+        self.mouse = mouse;
+      }
+      if let Some(ms) = general.hover_preview_ms {
+        self.hover_preview_ms = ms;
+      }
+      if let Some(size) = general.history_size {
+        self.history_size = size;
+      }
+      if let Some(max) = general.max_jobs {
+        self.max_jobs = max.max(1);
+      }
+      if let Some(thumbnails) = general.image_thumbnails {
+        self.image_thumbnails = thumbnails;
+      }
+      if let Some(ms) = general.preview_timeout_ms {
+        self.preview_timeout_ms = ms;
+      }
+      if let Some(auto) = general.auto_preview {
+        self.auto_preview = auto;
+      }
+      if let Some(ref name) = general.safe_names {
+        match SafeNames::from_name(name) {
+          Some(s) => self.safe_names = s,
+          None => errors.push(format!("unknown safe_names {name:?} (available: auto, always, never)")),
+        }
+      }
+      if let Some(op_log) = general.op_log {
+        self.op_log = op_log;
+      }
+      if let Some(counts) = general.dir_counts {
+        self.dir_counts = counts;
+      }
+      if let Some(hint_bar) = general.hint_bar {
+        self.hint_bar = hint_bar;
+      }
+      if let Some(icons) = general.icons {
+        self.icons = icons;
+      }
+      if let Some(markers) = general.git_markers {
+        self.git_markers = markers;
+      }
+      if let Some(show_hidden) = general.show_hidden {
+        self.show_hidden = show_hidden;
+      }
+      if let Some(hyperlinks) = general.hyperlinks {
+        self.hyperlinks = hyperlinks;
+      }
+      if let Some(ref names) = general.focus_order {
+        let mut order = Vec::new();
+        for name in names {
+          match FocusRegion::from_name(name) {
+            Some(region) if !order.contains(&region) => order.push(region),
+            Some(_) => {}
+            None => errors.push(format!("unknown focus_order region {name:?} (available: left, right, preview)")),
+          }
+        }
+        if order.is_empty() {
+          errors.push("focus_order needs at least one region".to_string());
+        } else {
+          self.focus_order = order;
+        }
+      }
+      if let Some(ref title) = general.window_title {
+        self.window_title = title.clone();
+      }
+      if let Some(ref dir) = general.worktree_dir {
+        self.worktree_dir = (!dir.is_empty()).then(|| expand_home(dir));
+      }
+      if let Some(ref dir) = general.start_dir {
+        self.start_dir = (!dir.is_empty()).then(|| expand_home(dir));
+      }
+      if let Some(ref name) = general.start_in {
+        match StartIn::from_name(name) {
+          Some(s) => self.start_in = s,
+          None => errors.push(format!("unknown start_in {name:?} (available: cwd, home, last, session)")),
+        }
+      }
     }
 
     if let Some(keys) = toml_config.keys {
@@ -336,32 +839,139 @@ impl Config {
       }
     }
 
-    // Compile ignore patterns into a GlobSet
-    self.ignore_glob_set = self.compile_glob_set(errors);
-  }
+    if let Some(sort) = toml_config.sort {
+      if let Some(dirs_first) = sort.dirs_first {
+        self.dirs_first = dirs_first;
+      }
+      if let Some(pinned) = sort.pinned {
+        self.pinned_patterns = pinned;
+      }
+    }
 
-  fn compile_glob_set(&self, errors: &mut Vec<String>) -> GlobSet {
-    let mut builder = GlobSetBuilder::new();
-    for pattern in &self.ignore_patterns {
-      match Glob::new(pattern) {
-        Ok(glob) => {
-          builder.add(glob);
+    if let Some(copy) = toml_config.copy {
+      match copy.symlinks.as_deref() {
+        Some("follow") => self.copy_options.preserve_links = false,
+        Some("preserve") => self.copy_options.preserve_links = true,
+        Some(other) => errors.push(format!("unknown copy symlinks {other:?} (available: follow, preserve)")),
+        None => {}
+      }
+      if let Some(ref names) = copy.preserve {
+        let options = &mut self.copy_options;
+        options.preserve_mode = false;
+        options.preserve_timestamps = false;
+        options.preserve_xattrs = false;
+        for name in names {
+          match name.as_str() {
+            "mode" => options.preserve_mode = true,
+            "timestamps" => options.preserve_timestamps = true,
+            "xattrs" => options.preserve_xattrs = true,
+            _ => errors.push(format!("unknown copy preserve {name:?} (available: mode, timestamps, xattrs)")),
+          }
         }
-        Err(e) => {
-          errors.push(format!("invalid ignore pattern {pattern:?}: {e}"));
+      }
+      if let Some(verify) = copy.verify {
+        self.copy_options.verify = verify;
+      }
+    }
+
+    if let Some(templates) = toml_config.templates
+      && let Some(context) = templates.context
+    {
+      self.template_rules = context
+        .into_iter()
+        .map(|rule| ContextRule {
+          marker: rule.marker,
+          templates: rule
+            .templates
+            .into_iter()
+            .map(|t| FileTemplate { name: t.name, content: t.content })
+            .collect(),
+        })
+        .collect();
+    }
+
+    // Presets extend the built-in ones rather than replacing them
+    if let Some(chmod) = toml_config.chmod
+      && let Some(presets) = chmod.presets
+    {
+      for (key_str, mode_str) in &presets {
+        let Some(key) = parse_key_binding(key_str) else {
+          errors.push(format!("invalid key binding: {key_str:?}"));
+          continue;
+        };
+        if key.modifiers.is_empty()
+          && let KeyCode::Char(c) = key.code
+          && CHMOD_DIALOG_KEYS.contains(c)
+        {
+          errors.push(format!("chmod preset key {key_str:?} is used by the chmod dialog"));
+          continue;
         }
+        self.chmod_presets.retain(|p| p.key != key);
+        let mode = match mode_str.as_str() {
+          "none" => continue,
+          "parent" => ChmodPresetMode::MatchParent,
+          octal => match u32::from_str_radix(octal, 8) {
+            Ok(mode) if (3..=4).contains(&octal.len()) && mode <= 0o7777 => ChmodPresetMode::Octal(mode),
+            _ => {
+              errors.push(format!("invalid chmod preset mode {mode_str:?} (use octal like \"644\" or \"parent\")"));
+              continue;
+            }
+          },
+        };
+        self.chmod_presets.push(ChmodPreset { key, mode });
       }
+      self.chmod_presets.sort_by_key(|p| match p.mode {
+        ChmodPresetMode::Octal(mode) => mode,
+        ChmodPresetMode::MatchParent => u32::MAX,
+      });
     }
-    builder.build().unwrap_or_else(|_| GlobSet::empty())
+
+    // Compile ignore and pin patterns into GlobSets
+    self.ignore_glob_set = compile_glob_set(&self.ignore_patterns, "ignore", errors);
+    self.pinned_glob_set = compile_glob_set(&self.pinned_patterns, "pinned", errors);
   }
 
   pub fn default_toml() -> &'static str {
     r#"[general]
 tree_ratio = 30       # initial tree pane width (percentage)
 tick_rate_ms = 100    # event loop tick rate in ms
+move_accel_after = 8  # held j/k moves 5 entries after this many repeats, 10 after twice as many (0 = off)
+move_accel_ms = 100   # longest gap between repeats that still counts as holding the key
 use_trash = true      # move to trash instead of permanent delete
+difftool = "git difftool --no-prompt --"  # external diff command (D), file path is appended
+ai_command = "claude {mentions}"  # AI CLI for c: {files} = one arg per file, {mentions} = "@a @b"
+ai_command_alt = "claude --dangerously-skip-permissions {mentions}"  # for C (c with claude_yolo)
+tmux_window_command = "tmux new-window -c {dir} -n {name}"  # tmux_window: {dir} = directory, {name} = its name
+tmux_pane_command = "tmux split-window -h -c {dir}"         # tmux_pane
+zellij_tab_command = "zellij action new-tab --cwd {dir} --name {name}"  # zellij_tab
+terminal_command = ""     # s opens this detached instead of suspending, e.g. "kitty --directory {dir}" ("" = suspend)
 theme = "dark"                      # "dark", "light", "catppuccin-mocha"
 syntax_theme = "base16-ocean.dark"  # syntect theme for code highlighting
+quit_confirm = "instant"  # "instant", "double" (press q twice) or "prompt" (y/N)
+esc_quits = true          # Esc quits once nothing is left to close
+print_last_dir = false    # print the final directory to stdout on exit
+read_only = false         # disable delete, rename, paste, chmod, new file, extract, ...
+share_clipboard = true    # cut/copy in one tfl window, paste in another
+mouse = true              # capture the mouse; false leaves text selection to the terminal (M toggles)
+hover_preview_ms = 600    # mouse hover delay before the quick-preview popup (0 = off)
+history_size = 100        # search queries and names kept per prompt for Up/Down (0 = off)
+max_jobs = 1              # extractions/compressions run at once, the rest queue (t shows them)
+image_thumbnails = true   # halfblock thumbnail under image details without terminal graphics
+auto_preview = true       # preview the selection as the cursor moves (false = only on R)
+preview_timeout_ms = 2000 # give up reading a file for the preview after this long (0 = wait)
+safe_names = "auto"       # offer FAT/NTFS-safe names when pasting: "auto" (detect the mount), "always", "never"
+op_log = false            # log file operations to operations.log in the state dir (L shows today's)
+dir_counts = false        # show "(12)" child counts next to collapsed directories (lists them in the background)
+hint_bar = false          # line above the status bar with keys for the selection (archive: x extract archive, ...)
+icons = true              # file type icons in the tree (needs a Nerd Font)
+git_markers = true        # git status markers (+ ~ ? !) in the tree and change counts next to the branch
+show_hidden = false       # start with hidden files shown, like --all (. toggles)
+hyperlinks = true         # Ctrl+click URLs, diff file headers and listed entries in the preview (OSC 8)
+focus_order = ["left", "right", "preview"]  # regions Tab cycles through ("right" only in dual-pane mode)
+window_title = "tfl: {dir}"  # terminal title: {dir}, {name} or {project} (repo/path inside it); "" = leave alone
+# worktree_dir = "~/worktrees"  # where W adds worktrees (default: <repo>.worktrees next to the repo)
+# start_dir = "~/projects"  # open here when no path is given
+start_in = "cwd"          # without start_dir: "cwd" (home if cwd is /), "home", "last" or "session"
 
 [keys.normal]
 j = "move_down"
@@ -377,8 +987,8 @@ enter = "open_default"
 o = "open_with"
 "shift+j" = "scroll_preview_down"
 "shift+k" = "scroll_preview_up"
-pagedown = "scroll_preview_down"
-pageup = "scroll_preview_up"
+pagedown = "page_down"
+pageup = "page_up"
 "." = "toggle_hidden"
 "shift+g" = "go_to_bottom"
 g = "g_press"
@@ -389,7 +999,8 @@ c = "open_claude"
 "shift+c" = "open_claude_alt"
 s = "open_shell"
 q = "quit"
-esc = "quit"
+"shift+o" = "quit_print_selection"
+esc = "escape"
 delete = "delete_file"
 "ctrl+x" = "cut_file"
 "ctrl+v" = "paste"
@@ -411,22 +1022,71 @@ x = "extract_archive"
 "-" = "history_back"
 "+" = "history_forward"
 m = "toggle_markdown_mode"
-tab = "switch_pane"
+"shift+b" = "toggle_size_format"
+tab = "cycle_focus"
 f6 = "toggle_dual_pane"
 "shift+p" = "toggle_formatted"
 d = "show_diff"
 n = "next_hunk"
 "shift+n" = "prev_hunk"
+"shift+d" = "open_difftool"
+"|" = "toggle_diff_side_by_side"
+"shift+s" = "cycle_diff_mode"
+p = "export_patch"
+"ctrl+a" = "stage_hunk"
+"ctrl+r" = "revert_hunk"
+"[" = "image_revision_older"
+"]" = "image_revision_newer"
+f12 = "toggle_debug_overlay"
+"shift+r" = "reload_preview"
 i = "show_properties"
 v = "toggle_mark"
 "shift+v" = "mark_all"
 u = "clear_marks"
 "shift+z" = "compress"
+"shift+t" = "new_from_template"
+"shift+w" = "worktrees"
+"shift+l" = "operation_log"
+b = "layouts"
+z = "toggle_dirs_first"
+"shift+m" = "toggle_mouse"
+"ctrl+z" = "suspend"
+t = "jobs"
+"," = "repeat_last"
+"shift+u" = "undo"
+"ctrl+y" = "redo"
+"shift+q" = "macro_record"
+"@" = "macro_play"
+"\"" = "bookmark_set"
+"'" = "bookmark_jump"
+":" = "preview_goto_line"
+"<" = "scroll_preview_left"
+">" = "scroll_preview_right"
 
 [keys.g_prefix]
 g = "go_to_top"
 h = "go_home"
 b = "toggle_blame"
+f = "toggle_follow"
+n = "toggle_line_numbers"
+w = "toggle_wrap"
+t = "tmux_window"
+s = "tmux_pane"
+z = "zellij_tab"
+c = "copy_to"
+m = "move_to"
+v = "paste_with_options"
+r = "restorecon"
+e = "settings"
+p = "breadcrumb_menu"
+x = "open_url"
+d = "doctor"
+u = "toggle_size_column"
+l = "create_symlink"
+a = "git_stage"
+"shift+a" = "git_unstage"
+"shift+r" = "git_discard"
+"shift+b" = "branches"
 
 [keys.search]
 enter = "search_confirm"
@@ -441,6 +1101,43 @@ patterns = [
 ]
 use_gitignore = true   # respect .gitignore files
 use_custom = true      # apply custom patterns (toggle with I)
+
+[sort]
+dirs_first = true      # directories before files; false interleaves them by name (toggle with z)
+pinned = []            # names kept at the top of their directory, e.g. ["README*", "Cargo.toml"]
+
+# Pasting and copy to; gv pastes with these changed for once. Moves across
+# filesystems always keep everything, like mv.
+[copy]
+symlinks = "follow"    # "follow" copies what a link points to (cp -L), "preserve" recreates the link (cp -P)
+preserve = ["mode"]    # kept on copies: "mode", "timestamps", "xattrs"; all three plus symlinks = "preserve" is cp -a
+verify = false         # read each copied file back and compare it (slow; for flaky USB drives and network shares)
+
+# Quick-new templates (T), offered when `marker` exists in the current
+# directory or an ancestor. `{name}` in content expands to the file stem.
+[[templates.context]]
+marker = "Cargo.toml"
+templates = [
+  { name = "mod.rs", content = "" },
+  { name = "tests.rs", content = "use super::*;\n\n#[test]\nfn it_works() {}\n" },
+]
+
+[[templates.context]]
+marker = "package.json"
+templates = [
+  { name = "Component.tsx", content = "export function {name}() {\n  return null;\n}\n" },
+  { name = "Component.test.tsx", content = "import { describe, it } from \"vitest\";\n\ndescribe(\"{name}\", () => {\n  it(\"works\", () => {});\n});\n" },
+]
+
+# Single-key presets in the chmod dialog (Ctrl+p). Entries here are added to
+# the built-in ones; "parent" copies the parent directory's permissions
+# (without execute bits for files) and "none" removes a preset.
+[chmod.presets]
+f = "644"
+e = "755"
+p = "600"
+"shift+p" = "700"
+m = "parent"
 "#
   }
 
@@ -588,9 +1285,7 @@ tui = true
       None => Config::default(),
     };
 
-    let apps_content = config_dir
-      .map(|d| d.join("apps.toml"))
-      .and_then(|p| std::fs::read_to_string(p).ok());
+    let apps_content = config_dir.and_then(|d| read_private(&d.join("apps.toml"), &mut errors));
 
     if let Some(s) = apps_content {
       config.has_apps_file = true;
@@ -601,28 +1296,51 @@ tui = true
   }
 
   pub fn load_apps_str(&mut self, s: &str, errors: &mut Vec<String>) {
-    let apps_file: AppsFile = match toml::from_str(s) {
-      Ok(f) => f,
-      Err(e) => {
-        errors.push(format!("failed to parse apps.toml: {e}"));
-        return;
-      }
+    self.custom_apps.extend(parse_apps(s, "apps.toml", errors));
+  }
+
+  /// Open-with apps of the project `dir` is in: the `[[apps]]` of
+  /// `.tfl.toml` and `apps.toml` in the nearest directory from `dir` up to
+  /// `top` that has either. Commands starting with `./` or `../` are taken
+  /// from that directory. Files others own or can write are skipped.
+  pub fn project_apps(dir: &Path, top: &Path, errors: &mut Vec<String>) -> Vec<OpenApp> {
+    let files = [".tfl.toml", "apps.toml"];
+    let Some(root) = dir
+      .ancestors()
+      .take_while(|d| d.starts_with(top))
+      .find(|d| files.iter().any(|f| d.join(f).is_file()))
+    else {
+      return Vec::new();
     };
-
-    for entry in apps_file.apps {
-      if entry.command.is_none() && entry.macos_app.is_none() {
-        errors.push(format!("app {:?} needs command or macos_app", entry.name));
-        continue;
+    let mut apps = Vec::new();
+    for file in files {
+      if let Some(s) = read_private(&root.join(file), errors) {
+        apps.extend(parse_apps(&s, file, errors));
       }
-      self.custom_apps.push(OpenApp {
-        name: entry.name,
-        command: entry.command.unwrap_or_default(),
-        is_tui: entry.tui.unwrap_or(false),
-        macos_app: entry.macos_app,
-        opens_dir: entry.opens_dir.unwrap_or(false),
-        dir_mode: false,
-      });
     }
+    for app in &mut apps {
+      if app.command.starts_with("./") || app.command.starts_with("../") {
+        app.command = root.join(&app.command).to_string_lossy().into_owned();
+      }
+      app.project = true;
+    }
+    apps
+  }
+
+  /// Directory to open when no path was given on the command line. `last`
+  /// is where tfl was last quit, or the last session's root, if remembered.
+  pub fn resolve_start_dir(&self, cwd: Option<PathBuf>, last: Option<&Path>) -> PathBuf {
+    if let Some(dir) = self.start_dir.as_ref().filter(|d| d.is_dir()) {
+      return dir.clone();
+    }
+    let home = dirs::home_dir();
+    let cwd = cwd.filter(|d| d != Path::new("/"));
+    let dir = match self.start_in {
+      StartIn::Cwd => cwd.or(home),
+      StartIn::Home => home.or(cwd),
+      StartIn::Last | StartIn::Session => last.filter(|d| d.is_dir()).map(Path::to_path_buf).or(cwd).or(home),
+    };
+    dir.unwrap_or_else(|| PathBuf::from("/"))
   }
 
   #[cfg(test)]
@@ -646,6 +1364,7 @@ tui = true
 #[cfg(test)]
 mod tests {
   use super::*;
+  use std::os::unix::fs::PermissionsExt;
   use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
 
   // --- parse_key_binding tests ---
@@ -768,7 +1487,7 @@ mod tests {
 
     let expected = vec![
       (KeyCode::Char('q'), n, Action::Quit),
-      (KeyCode::Esc, n, Action::Quit),
+      (KeyCode::Esc, n, Action::Escape),
       (KeyCode::Char('j'), n, Action::MoveDown),
       (KeyCode::Down, n, Action::MoveDown),
       (KeyCode::Char('k'), n, Action::MoveUp),
@@ -782,8 +1501,8 @@ mod tests {
       (KeyCode::Char('o'), n, Action::OpenWithStart),
       (KeyCode::Char('J'), n, Action::ScrollPreviewDown),
       (KeyCode::Char('K'), n, Action::ScrollPreviewUp),
-      (KeyCode::PageDown, n, Action::ScrollPreviewDown),
-      (KeyCode::PageUp, n, Action::ScrollPreviewUp),
+      (KeyCode::PageDown, n, Action::PageDown),
+      (KeyCode::PageUp, n, Action::PageUp),
       (KeyCode::Char('.'), n, Action::ToggleHidden),
       (KeyCode::Char('g'), n, Action::GPress),
       (KeyCode::Char('G'), n, Action::GoToBottom),
@@ -812,13 +1531,22 @@ mod tests {
       (KeyCode::Char('p'), KeyModifiers::CONTROL, Action::ChmodStart),
       (KeyCode::Char('-'), n, Action::HistoryBack),
       (KeyCode::Char('+'), n, Action::HistoryForward),  // shift+= produces '+'
-      (KeyCode::Tab, n, Action::SwitchPane),
+      (KeyCode::Tab, n, Action::CycleFocus),
       (KeyCode::F(6), n, Action::ToggleDualPane),
       (KeyCode::Char('i'), n, Action::ShowProperties),
       (KeyCode::Char('v'), n, Action::ToggleMark),
       (KeyCode::Char('V'), n, Action::MarkAll),
       (KeyCode::Char('u'), n, Action::ClearMarks),
       (KeyCode::Char('Z'), n, Action::CompressStart),
+      (KeyCode::Char('|'), n, Action::ToggleDiffSideBySide),
+      (KeyCode::Char('S'), n, Action::CycleDiffMode),
+      (KeyCode::Char('p'), n, Action::ExportPatchStart),
+      (KeyCode::Char('a'), KeyModifiers::CONTROL, Action::StageHunk),
+      (KeyCode::Char('r'), KeyModifiers::CONTROL, Action::RevertHunk),
+      (KeyCode::Char('['), n, Action::ImageRevisionOlder),
+      (KeyCode::Char(']'), n, Action::ImageRevisionNewer),
+      (KeyCode::F(12), n, Action::ToggleDebugOverlay),
+      (KeyCode::Char('R'), n, Action::ReloadPreview),
     ];
 
     for (code, mods, action) in expected {
@@ -840,6 +1568,10 @@ mod tests {
     assert_eq!(config.g_prefix_keys.get(&kb_h), Some(&Action::GoHome));
     let kb_b = KeyBinding { code: KeyCode::Char('b'), modifiers: KeyModifiers::NONE };
     assert_eq!(config.g_prefix_keys.get(&kb_b), Some(&Action::ToggleBlame));
+    let kb_t = KeyBinding { code: KeyCode::Char('t'), modifiers: KeyModifiers::NONE };
+    assert_eq!(config.g_prefix_keys.get(&kb_t), Some(&Action::TmuxWindow));
+    let kb_x = KeyBinding { code: KeyCode::Char('x'), modifiers: KeyModifiers::NONE };
+    assert_eq!(config.g_prefix_keys.get(&kb_x), Some(&Action::UrlsOpen));
   }
 
   // --- Config::load_from_str tests ---
@@ -873,10 +1605,14 @@ j = "move_up"
 [general]
 tree_ratio = 50
 tick_rate_ms = 200
+move_accel_after = 0
+move_accel_ms = 60
 "#;
     let config = Config::load_from_str(toml);
     assert_eq!(config.tree_ratio, 50);
     assert_eq!(config.tick_rate_ms, 200);
+    assert_eq!(config.move_accel_after, 0);
+    assert_eq!(config.move_accel_ms, 60);
   }
 
   #[test]
@@ -999,6 +1735,30 @@ j = "quit"
     assert_eq!(j_actions[0].1, &Action::Quit);
   }
 
+  #[test]
+  fn test_chmod_presets_extend_defaults() {
+    let toml = r#"
+[chmod.presets]
+"shift+g" = "640"
+f = "none"
+e = "0750"
+z = "999"
+r = "600"
+"#;
+    let mut errors = Vec::new();
+    let config = Config::load_from_str_collecting_errors(toml, &mut errors);
+    let label = |c: char| {
+      let kb = KeyBinding { code: KeyCode::Char(c), modifiers: KeyModifiers::NONE };
+      config.chmod_presets.iter().find(|p| p.key == kb).map(|p| p.label())
+    };
+    assert_eq!(label('G').as_deref(), Some("640"));
+    assert_eq!(label('e').as_deref(), Some("750"));
+    assert_eq!(label('f'), None);
+    assert_eq!(label('m').as_deref(), Some("parent"));
+    assert_eq!(label('z'), None);
+    assert_eq!(errors.len(), 2, "{errors:?}");
+  }
+
   #[test]
   fn test_user_keys_section_replaces_defaults() {
     // When the user provides [keys.normal], it replaces all default
@@ -1105,7 +1865,8 @@ tree_ratio = 40
     let lookup = config.reverse_lookup();
     let quit_keys = lookup.get(&Action::Quit).expect("Quit should have keys");
     assert!(quit_keys.contains(&"q".to_string()));
-    assert!(quit_keys.contains(&"Esc".to_string()));
+    let escape_keys = lookup.get(&Action::Escape).expect("Escape should have keys");
+    assert!(escape_keys.contains(&"Esc".to_string()));
   }
 
   #[test]
@@ -1153,6 +1914,48 @@ command = "lite-xl"
     assert!(!config.custom_apps[1].is_tui);
   }
 
+  #[test]
+  fn test_project_apps_from_nearest_project_dir() {
+    let top = std::env::temp_dir().join(format!("tfl_project_apps_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&top);
+    let dir = top.join("crates/web/src");
+    std::fs::create_dir_all(&dir).unwrap();
+    let web = top.join("crates/web");
+    std::fs::write(top.join("apps.toml"), "[[apps]]\nname = \"Root\"\ncommand = \"root\"\n").unwrap();
+    std::fs::write(
+      web.join(".tfl.toml"),
+      "[general]\ntheme = \"x\"\n\n[[apps]]\nname = \"Preview\"\ncommand = \"./scripts/preview.sh\"\n",
+    )
+    .unwrap();
+    std::fs::write(web.join("apps.toml"), "[[apps]]\nname = \"Serve\"\ncommand = \"serve\"\ntui = true\n").unwrap();
+    for file in [top.join("apps.toml"), web.join(".tfl.toml"), web.join("apps.toml")] {
+      std::fs::set_permissions(file, std::fs::Permissions::from_mode(0o644)).unwrap();
+    }
+
+    let mut errors = Vec::new();
+    let apps = Config::project_apps(&dir, &top, &mut errors);
+    let names: Vec<&str> = apps.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["Preview", "Serve"]);
+    assert_eq!(apps[0].command, web.join("./scripts/preview.sh").to_string_lossy());
+    assert_eq!(apps[1].command, "serve");
+    assert!(apps.iter().all(|a| a.project));
+    assert!(errors.is_empty());
+
+    assert_eq!(Config::project_apps(&top.join("crates"), &top, &mut errors)[0].name, "Root");
+    // Not above the top of the project
+    assert!(Config::project_apps(&dir, &web.join("src"), &mut errors).is_empty());
+    std::fs::write(top.join("apps.toml"), "[[apps]").unwrap();
+    assert!(Config::project_apps(&top, &top, &mut errors).is_empty());
+    assert!(errors[0].starts_with("failed to parse apps.toml"));
+
+    // Files others can write are not trusted with commands
+    std::fs::set_permissions(web.join("apps.toml"), std::fs::Permissions::from_mode(0o664)).unwrap();
+    let names: Vec<String> = Config::project_apps(&dir, &top, &mut errors).into_iter().map(|a| a.name).collect();
+    assert_eq!(names, ["Preview"]);
+    assert!(errors[1].contains("not writable by group or others"));
+    std::fs::remove_dir_all(&top).unwrap();
+  }
+
   #[test]
   fn test_custom_apps_default_tui_false() {
     let apps_toml = r#"
@@ -1199,6 +2002,32 @@ command = "kak"
     assert!(!config.claude_yolo);
   }
 
+  #[test]
+  fn test_ai_command_default_and_override() {
+    let config = Config::default();
+    assert_eq!(config.ai_command, "claude {mentions}");
+    let config = Config::load_from_str("[general]\nai_command = \"aider {files}\"\nai_command_alt = \"aider --yes-always {files}\"\n");
+    assert_eq!(config.ai_command, "aider {files}");
+    assert_eq!(config.ai_command_alt, "aider --yes-always {files}");
+  }
+
+  #[test]
+  fn test_multiplexer_commands() {
+    let config = Config::default();
+    assert_eq!(config.tmux_window_command, "tmux new-window -c {dir} -n {name}");
+    assert_eq!(config.tmux_pane_command, "tmux split-window -h -c {dir}");
+    assert_eq!(config.zellij_tab_command, "zellij action new-tab --cwd {dir} --name {name}");
+    let config = Config::load_from_str("[general]\ntmux_pane_command = \"tmux split-window -v -c {dir}\"\n");
+    assert_eq!(config.tmux_pane_command, "tmux split-window -v -c {dir}");
+  }
+
+  #[test]
+  fn test_terminal_command() {
+    assert_eq!(Config::default().terminal_command, "");
+    let config = Config::load_from_str("[general]\nterminal_command = \"kitty --directory {dir}\"\n");
+    assert_eq!(config.terminal_command, "kitty --directory {dir}");
+  }
+
   #[test]
   fn test_claude_yolo_parsed_true() {
     let toml = r#"
@@ -1293,6 +2122,31 @@ patterns = ["*.log", "node_modules"]
     assert!(!config.ignore_glob_set.is_match("main.rs"));
   }
 
+  #[test]
+  fn test_sort_section() {
+    let config = Config::load_from_str("[sort]\ndirs_first = false\npinned = [\"README*\", \"Cargo.toml\"]\n");
+    assert!(!config.dirs_first);
+    assert_eq!(config.pinned_glob_set.matches("Cargo.toml"), vec![1]);
+    assert!(config.pinned_glob_set.is_match("README.md"));
+    assert!(Config::default().dirs_first);
+    assert!(Config::default().pinned_patterns.is_empty());
+  }
+
+  #[test]
+  fn test_copy_section() {
+    assert_eq!(Config::default().copy_options, CopyOptions::default());
+    let config = Config::load_from_str("[copy]\nsymlinks = \"preserve\"\npreserve = [\"mode\", \"timestamps\", \"xattrs\"]\n");
+    assert_eq!(config.copy_options, CopyOptions::archive());
+    let config = Config::load_from_str("[copy]\npreserve = []\n");
+    assert!(!config.copy_options.preserve_mode && !config.copy_options.preserve_links);
+    assert!(Config::load_from_str("[copy]\nverify = true\n").copy_options.verify);
+
+    let mut errors = Vec::new();
+    let config = Config::load_from_str_collecting_errors("[copy]\nsymlinks = \"keep\"\npreserve = [\"owner\", \"timestamps\"]\n", &mut errors);
+    assert_eq!(errors.len(), 2);
+    assert!(config.copy_options.preserve_timestamps);
+  }
+
   #[test]
   fn test_ignore_invalid_pattern_logged() {
     let toml = r#"
@@ -1314,10 +2168,10 @@ patterns = ["[invalid", "valid.log"]
   }
 
   #[test]
-  fn test_default_tab_binds_switch_pane() {
+  fn test_default_tab_binds_cycle_focus() {
     let config = Config::default();
     let kb = KeyBinding { code: KeyCode::Tab, modifiers: KeyModifiers::NONE };
-    assert_eq!(config.normal_keys.get(&kb), Some(&Action::SwitchPane));
+    assert_eq!(config.normal_keys.get(&kb), Some(&Action::CycleFocus));
   }
 
   #[test]
@@ -1369,6 +2223,145 @@ patterns = ["[invalid", "valid.log"]
     assert!(!config.has_apps_file);
   }
 
+  #[test]
+  fn test_difftool_default_and_override() {
+    assert_eq!(Config::default().difftool, "git difftool --no-prompt --");
+    let config = Config::load_from_str("[general]\ndifftool = \"git -c core.pager=delta diff --\"\n");
+    assert_eq!(config.difftool, "git -c core.pager=delta diff --");
+  }
+
+  #[test]
+  fn test_quit_options() {
+    let config = Config::default();
+    assert_eq!(config.quit_confirm, QuitConfirm::Instant);
+    assert!(config.esc_quits);
+    assert!(!config.print_last_dir);
+
+    let config = Config::load_from_str("[general]\nquit_confirm = \"double\"\nesc_quits = false\nprint_last_dir = true\n");
+    assert_eq!(config.quit_confirm, QuitConfirm::Double);
+    assert!(!config.esc_quits);
+    assert!(config.print_last_dir);
+    assert!(!config.read_only);
+    assert!(config.share_clipboard);
+    assert!(config.mouse);
+    assert!(!Config::load_from_str("[general]\nmouse = false\n").mouse);
+    assert_eq!(config.hover_preview_ms, 600);
+    assert_eq!(Config::load_from_str("[general]\nhover_preview_ms = 0\n").hover_preview_ms, 0);
+    assert_eq!(config.history_size, 100);
+    assert_eq!(Config::load_from_str("[general]\nhistory_size = 0\n").history_size, 0);
+    assert_eq!(config.max_jobs, 1);
+    assert_eq!(Config::load_from_str("[general]\nmax_jobs = 3\n").max_jobs, 3);
+    assert_eq!(Config::load_from_str("[general]\nmax_jobs = 0\n").max_jobs, 1);
+    assert!(config.image_thumbnails);
+    assert!(!Config::load_from_str("[general]\nimage_thumbnails = false\n").image_thumbnails);
+    assert!(config.auto_preview);
+    assert!(!Config::load_from_str("[general]\nauto_preview = false\n").auto_preview);
+    assert_eq!(config.preview_timeout_ms, 2000);
+    assert_eq!(Config::load_from_str("[general]\npreview_timeout_ms = 0\n").preview_timeout_ms, 0);
+    assert_eq!(config.safe_names, SafeNames::Auto);
+    assert_eq!(Config::load_from_str("[general]\nsafe_names = \"always\"\n").safe_names, SafeNames::Always);
+    assert!(!config.op_log);
+    assert!(Config::load_from_str("[general]\nop_log = true\n").op_log);
+    assert!(!config.dir_counts);
+    assert!(Config::load_from_str("[general]\ndir_counts = true\n").dir_counts);
+    assert!(!config.hint_bar);
+    assert!(Config::load_from_str("[general]\nhint_bar = true\n").hint_bar);
+    assert!(config.icons);
+    assert!(!Config::load_from_str("[general]\nicons = false\n").icons);
+    assert!(config.git_markers);
+    assert!(!Config::load_from_str("[general]\ngit_markers = false\n").git_markers);
+    assert!(!config.show_hidden);
+    assert!(Config::load_from_str("[general]\nshow_hidden = true\n").show_hidden);
+    assert!(config.hyperlinks);
+    assert!(!Config::load_from_str("[general]\nhyperlinks = false\n").hyperlinks);
+    assert_eq!(config.focus_order, vec![FocusRegion::Left, FocusRegion::Right, FocusRegion::Preview]);
+    assert_eq!(
+      Config::load_from_str("[general]\nfocus_order = [\"preview\", \"left\"]\n").focus_order,
+      vec![FocusRegion::Preview, FocusRegion::Left]
+    );
+    assert_eq!(config.window_title, "tfl: {dir}");
+    assert_eq!(Config::load_from_str("[general]\nwindow_title = \"\"\n").window_title, "");
+    assert_eq!(config.worktree_dir, None);
+    assert_eq!(Config::load_from_str("[general]\nworktree_dir = \"/srv/trees\"\n").worktree_dir, Some(PathBuf::from("/srv/trees")));
+    assert!(!Config::load_from_str("[general]\nshare_clipboard = false\n").share_clipboard);
+    assert!(Config::load_from_str("[general]\nread_only = true\n").read_only);
+
+    let mut errors = Vec::new();
+    let config = Config::load_from_str_collecting_errors("[general]\nquit_confirm = \"twice\"\n", &mut errors);
+    assert_eq!(config.quit_confirm, QuitConfirm::Instant);
+    assert_eq!(errors.len(), 1);
+
+    let mut errors = Vec::new();
+    let config = Config::load_from_str_collecting_errors("[general]\nfocus_order = [\"top\"]\n", &mut errors);
+    assert_eq!(config.focus_order.len(), 3);
+    assert_eq!(errors.len(), 2);
+  }
+
+  #[test]
+  fn test_start_dir_options() {
+    let config = Config::default();
+    assert_eq!(config.start_dir, None);
+    assert_eq!(config.start_in, StartIn::Cwd);
+
+    let config = Config::load_from_str("[general]\nstart_dir = \"~/projects\"\nstart_in = \"last\"\n");
+    assert_eq!(config.start_dir, dirs::home_dir().map(|h| h.join("projects")));
+    assert_eq!(config.start_in, StartIn::Last);
+    assert_eq!(expand_home("/srv/~x"), PathBuf::from("/srv/~x"));
+    let config = Config::load_from_str("[general]\nstart_in = \"session\"\n");
+    assert_eq!(config.start_in, StartIn::Session);
+
+    let mut errors = Vec::new();
+    let config = Config::load_from_str_collecting_errors("[general]\nstart_in = \"desktop\"\n", &mut errors);
+    assert_eq!(config.start_in, StartIn::Cwd);
+    assert_eq!(errors.len(), 1);
+  }
+
+  #[test]
+  fn test_resolve_start_dir() {
+    let dir = std::env::temp_dir().join(format!("tfl_start_{}", std::process::id()));
+    let (work, last) = (dir.join("work"), dir.join("last"));
+    std::fs::create_dir_all(&work).unwrap();
+    std::fs::create_dir_all(&last).unwrap();
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+
+    let mut config = Config::default();
+    assert_eq!(config.resolve_start_dir(Some(work.clone()), Some(&last)), work);
+    // `/` is what desktop launchers hand us
+    assert_eq!(config.resolve_start_dir(Some(PathBuf::from("/")), None), home);
+    config.start_in = StartIn::Last;
+    assert_eq!(config.resolve_start_dir(Some(work.clone()), Some(&last)), last);
+    assert_eq!(config.resolve_start_dir(Some(work.clone()), Some(&dir.join("gone"))), work);
+    config.start_in = StartIn::Home;
+    assert_eq!(config.resolve_start_dir(Some(work.clone()), None), home);
+    config.start_dir = Some(work.clone());
+    assert_eq!(config.resolve_start_dir(Some(last.clone()), None), work);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  // === templates tests ===
+
+  #[test]
+  fn test_default_template_rules() {
+    let config = Config::default();
+    let markers: Vec<&str> = config.template_rules.iter().map(|r| r.marker.as_str()).collect();
+    assert_eq!(markers, vec!["Cargo.toml", "package.json"]);
+    assert!(config.template_rules[0].templates.iter().any(|t| t.name == "mod.rs"));
+  }
+
+  #[test]
+  fn test_templates_context_parsed() {
+    let config = Config::load_from_str(r#"
+[[templates.context]]
+marker = "go.mod"
+templates = [{ name = "main.go", content = "package main\n" }, { name = "doc.go" }]
+"#);
+    assert_eq!(config.template_rules.len(), 1);
+    let rule = &config.template_rules[0];
+    assert_eq!(rule.marker, "go.mod");
+    assert_eq!(rule.templates[0], FileTemplate { name: "main.go".to_string(), content: "package main\n".to_string() });
+    assert_eq!(rule.templates[1].content, "");
+  }
+
   // === named_key tests ===
 
   #[test]
@@ -1411,10 +2404,9 @@ patterns = ["[invalid", "valid.log"]
 
   #[test]
   fn test_compile_glob_set_invalid_pattern() {
-    let mut config = Config::empty();
-    config.ignore_patterns = vec!["*.log".to_string(), "[invalid".to_string()];
+    let patterns = vec!["*.log".to_string(), "[invalid".to_string()];
     let mut errors = Vec::new();
-    let glob_set = config.compile_glob_set(&mut errors);
+    let glob_set = compile_glob_set(&patterns, "ignore", &mut errors);
     // Invalid pattern should produce an error
     assert!(!errors.is_empty());
     // The GlobSet should still be usable (with valid patterns)
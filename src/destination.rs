@@ -0,0 +1,149 @@
+//! Destination picker behind copy to / move to: the typed path, with Tab
+//! completing directory names, and recent destinations, favorites and the
+//! other pane's root offered below it.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::expand_home;
+use crate::ui::favorites::contract_home;
+
+pub struct DestinationPicker {
+  /// Move rather than copy
+  pub cut: bool,
+  pub sources: Vec<PathBuf>,
+  pub choices: Vec<PathBuf>,
+  /// Text typed before Up/Down started filling in choices
+  query: String,
+  /// Position among the choices matching `query`
+  cursor: Option<usize>,
+}
+
+impl DestinationPicker {
+  pub fn new(cut: bool, sources: Vec<PathBuf>, choices: Vec<PathBuf>) -> Self {
+    Self { cut, sources, choices, query: String::new(), cursor: None }
+  }
+
+  fn matching(&self, query: &str) -> Vec<&PathBuf> {
+    let query = query.trim().to_lowercase();
+    self.choices.iter().filter(|p| contract_home(p).to_lowercase().contains(&query)).collect()
+  }
+
+  /// The choice Up/Down filled in, while the prompt still holds it.
+  fn browsing(&self, text: &str) -> Option<usize> {
+    let cursor = self.cursor?;
+    let shown = self.matching(&self.query).get(cursor).map(|p| contract_home(p))?;
+    (shown == text).then_some(cursor)
+  }
+
+  /// Choices to list for the prompt's `text`, and the highlighted one.
+  pub fn view(&self, text: &str) -> (Vec<&PathBuf>, Option<usize>) {
+    match self.browsing(text) {
+      Some(cursor) => (self.matching(&self.query), Some(cursor)),
+      None => (self.matching(text), None),
+    }
+  }
+
+  /// Move to the next (`down`) or previous matching choice, returning the
+  /// text to put in the prompt. Editing the prompt starts a new filter.
+  pub fn step(&mut self, text: &str, down: bool) -> Option<String> {
+    if self.browsing(text).is_none() {
+      self.query = text.to_string();
+      self.cursor = None;
+    }
+    let len = self.matching(&self.query).len();
+    if len == 0 {
+      return None;
+    }
+    let cursor = match (self.cursor, down) {
+      (None, true) => 0,
+      (None, false) => len - 1,
+      (Some(i), true) => (i + 1).min(len - 1),
+      (Some(i), false) => i.saturating_sub(1),
+    };
+    self.cursor = Some(cursor);
+    self.matching(&self.query).get(cursor).map(|p| contract_home(p))
+  }
+}
+
+/// The directory `text` names: `~` is home, relative paths start at `cwd`.
+pub fn expand(text: &str, cwd: &Path) -> PathBuf {
+  let path = expand_home(text.trim());
+  if path.is_absolute() { path } else { cwd.join(path) }
+}
+
+/// Complete the last component of `text` to the longest prefix the
+/// matching subdirectories share, with a trailing `/` once it is unique.
+/// Hidden directories are offered when the component starts with a dot.
+pub fn complete(text: &str, cwd: &Path) -> Option<String> {
+  if text == "~" {
+    return Some("~/".to_string());
+  }
+  let (dir, prefix) = match text.rfind('/') {
+    Some(i) => text.split_at(i + 1),
+    None => ("", text),
+  };
+  let base = expand(if dir.is_empty() { "." } else { dir }, cwd);
+  let mut names: Vec<String> = std::fs::read_dir(base)
+    .ok()?
+    .flatten()
+    .filter(|e| e.path().is_dir())
+    .map(|e| e.file_name().to_string_lossy().to_string())
+    .filter(|n| n.starts_with(prefix) && (prefix.starts_with('.') || !n.starts_with('.')))
+    .collect();
+  names.sort();
+  let first = names.first()?;
+  let completed = if names.len() == 1 {
+    format!("{first}/")
+  } else {
+    let shared = names[1..].iter().fold(first.as_str(), |shared, name| {
+      let len = shared
+        .char_indices()
+        .zip(name.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(shared.len().min(name.len()), |((i, _), _)| i);
+      &shared[..len]
+    });
+    shared.to_string()
+  };
+  let result = format!("{dir}{completed}");
+  (result != text).then_some(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_complete_and_browse_choices() {
+    let dir = std::env::temp_dir().join(format!("tfl_destination_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for sub in ["project-a", "project-b", "photos", ".cache"] {
+      std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+    assert_eq!(complete("pro", &dir).as_deref(), Some("project-"));
+    assert_eq!(complete("project-", &dir), None);
+    assert_eq!(complete("ph", &dir).as_deref(), Some("photos/"));
+    assert_eq!(complete(".c", &dir).as_deref(), Some(".cache/"));
+    assert_eq!(complete("no", &dir), None);
+    let abs = format!("{}/project-b", dir.display());
+    assert_eq!(complete(&abs, Path::new("/")), Some(format!("{abs}/")));
+    assert_eq!(expand("photos", &dir), dir.join("photos"));
+
+    let choices = vec![dir.join("project-a"), dir.join("photos"), dir.join("project-b")];
+    let mut picker = DestinationPicker::new(false, Vec::new(), choices);
+    assert_eq!(picker.view("").0.len(), 3);
+    // Up/Down walk the choices matching what was typed
+    let first = picker.step("proj", true).unwrap();
+    assert!(first.ends_with("project-a"));
+    assert_eq!(picker.view(&first).1, Some(0));
+    let second = picker.step(&first, true).unwrap();
+    assert!(second.ends_with("project-b"));
+    assert_eq!(picker.view(&second).0.len(), 2);
+    // Typing over a filled-in choice filters afresh
+    assert_eq!(picker.view("pho").0.len(), 1);
+    assert_eq!(picker.view("pho").1, None);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
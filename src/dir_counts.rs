@@ -0,0 +1,83 @@
+//! Child counts shown next to collapsed directories. Directories are listed
+//! on a background thread and the counts cached by mtime, so revisiting a
+//! directory that hasn't changed costs a single stat.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::path_cache::{PathCache, Work};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirCount {
+  pub mtime: SystemTime,
+  pub all: usize,
+  /// Children whose name doesn't start with a dot
+  pub visible: usize,
+}
+
+impl DirCount {
+  pub fn shown(&self, show_hidden: bool) -> usize {
+    if show_hidden { self.all } else { self.visible }
+  }
+}
+
+/// Count `dir`'s children, reusing `cached` when the mtime still matches.
+pub fn count_dir(dir: &Path, cached: Option<DirCount>) -> Option<DirCount> {
+  let mtime = std::fs::metadata(dir).and_then(|m| m.modified()).ok()?;
+  if let Some(cached) = cached
+    && cached.mtime == mtime
+  {
+    return Some(cached);
+  }
+  let mut count = DirCount { mtime, all: 0, visible: 0 };
+  for entry in std::fs::read_dir(dir).ok()?.flatten() {
+    count.all += 1;
+    if !entry.file_name().to_string_lossy().starts_with('.') {
+      count.visible += 1;
+    }
+  }
+  Some(count)
+}
+
+pub type DirCounts = PathCache<DirCount>;
+
+/// Count each directory's children on the one worker thread.
+pub fn work() -> Work<DirCount> {
+  Arc::new(|batch| {
+    batch
+      .into_iter()
+      .map(|(dir, cached)| {
+        let count = count_dir(&dir, cached);
+        (dir, count)
+      })
+      .collect()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_count_dir_reuses_cache_until_mtime_changes() {
+    let dir = std::env::temp_dir().join(format!("tfl_dir_counts_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("a.txt"), "").unwrap();
+    std::fs::write(dir.join(".hidden"), "").unwrap();
+
+    let count = count_dir(&dir, None).unwrap();
+    assert_eq!((count.all, count.visible), (3, 2));
+    assert_eq!(count.shown(false), 2);
+
+    // A matching mtime trusts the cached numbers
+    let fake = DirCount { all: 99, ..count };
+    assert_eq!(count_dir(&dir, Some(fake)).unwrap().all, 99);
+    let old = DirCount { mtime: SystemTime::UNIX_EPOCH, ..fake };
+    assert_eq!(count_dir(&dir, Some(old)).unwrap().all, 3);
+
+    assert!(count_dir(&dir.join("missing"), None).is_none());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
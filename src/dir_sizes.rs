@@ -0,0 +1,72 @@
+//! Cumulative sizes for the tree's size column. Directories on screen are
+//! walked by a small pool of background threads; a size stays shown until
+//! the next walk after a change replaces it.
+
+use std::sync::Arc;
+
+use crate::fs::progress;
+use crate::path_cache::{PathCache, Work};
+
+/// Threads walking directories at once
+pub const WORKERS: usize = 4;
+
+pub type DirSizes = PathCache<u64>;
+
+/// Walk each directory for the bytes under it.
+pub fn work() -> Work<u64> {
+  Arc::new(|batch| {
+    batch
+      .into_iter()
+      .map(|(dir, _)| {
+        let bytes = progress::total(&[&dir]).bytes;
+        (dir, Some(bytes))
+      })
+      .collect()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::{Duration, Instant};
+
+  #[test]
+  fn test_sizes_add_up_nested_files_in_the_background() {
+    let dir = std::env::temp_dir().join(format!("tfl_dir_sizes_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("a/b")).unwrap();
+    std::fs::write(dir.join("a/one"), [0; 100]).unwrap();
+    std::fs::write(dir.join("a/b/two"), [0; 20]).unwrap();
+    std::fs::write(dir.join("three"), [0; 3]).unwrap();
+    assert_eq!(progress::total(&[&dir]).bytes, 123);
+    assert_eq!(progress::total(&[dir.join("missing")]).bytes, 0);
+
+    let mut sizes = DirSizes::new(false, WORKERS);
+    sizes.request([dir.as_path()], work);
+    assert!(!sizes.poll());
+    sizes.enabled = true;
+    sizes.request([dir.as_path(), dir.join("a").as_path()], work);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while sizes.get(&dir.join("a")).is_none() || sizes.get(&dir).is_none() {
+      assert!(Instant::now() < deadline);
+      sizes.poll();
+      std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(sizes.get(&dir), Some(&123));
+    assert_eq!(sizes.get(&dir.join("a")), Some(&120));
+
+    // Walked again only after a change under it
+    std::fs::write(dir.join("three"), [0; 30]).unwrap();
+    sizes.request([dir.as_path()], work);
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(!sizes.poll());
+    sizes.invalidate(&[dir.join("three")]);
+    sizes.request([dir.as_path(), dir.join("a").as_path()], work);
+    while sizes.get(&dir) != Some(&150) {
+      assert!(Instant::now() < deadline);
+      sizes.poll();
+      std::thread::sleep(Duration::from_millis(5));
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
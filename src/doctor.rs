@@ -0,0 +1,242 @@
+//! `tfl --doctor` and the diagnostics overlay: the optional pieces tfl
+//! looks for on this system, and how to fix the ones it did not find.
+
+use std::path::Path;
+
+use ratatui_image::picker::ProtocolType;
+
+use crate::process::{ProcessRunner, ProcessSpec};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+  Ok,
+  /// Could not be told either way
+  Unknown,
+  Missing,
+}
+
+impl Status {
+  pub fn symbol(self) -> &'static str {
+    match self {
+      Status::Ok => "✓",
+      Status::Unknown => "?",
+      Status::Missing => "✗",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+  pub name: &'static str,
+  pub status: Status,
+  pub detail: String,
+  /// How to fix it, when it is not ok
+  pub hint: Option<String>,
+}
+
+impl Check {
+  fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+    Self { name, status: Status::Ok, detail: detail.into(), hint: None }
+  }
+
+  fn not_ok(name: &'static str, status: Status, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+    Self { name, status, detail: detail.into(), hint: Some(hint.into()) }
+  }
+}
+
+/// What the checks read from outside: environment variables and the
+/// config directory, passed in so tests can stand in for the system.
+pub struct Probe<'a> {
+  pub runner: &'a dyn ProcessRunner,
+  pub var: &'a dyn Fn(&str) -> Option<String>,
+  pub config_home: Option<&'a Path>,
+  /// Graphics protocol the terminal answered with, None when not queried
+  pub image_protocol: Option<ProtocolType>,
+}
+
+pub fn run(probe: &Probe) -> Vec<Check> {
+  let mut checks = vec![image_protocol(probe), clipboard(probe), git(probe), nerd_font(probe), watcher()];
+  if cfg!(target_os = "linux") {
+    checks.push(handler(probe));
+    checks.push(portal(probe));
+  }
+  checks
+}
+
+/// The checks as aligned text for `tfl --doctor`.
+pub fn report(checks: &[Check]) -> String {
+  let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+  let mut out = String::new();
+  for check in checks {
+    out.push_str(&format!("{} {:<width$}  {}\n", check.status.symbol(), check.name, check.detail));
+    if let Some(ref hint) = check.hint {
+      out.push_str(&format!("  {:<width$}  → {hint}\n", ""));
+    }
+  }
+  out
+}
+
+fn image_protocol(probe: &Probe) -> Check {
+  const NAME: &str = "Image protocol";
+  match probe.image_protocol {
+    Some(ProtocolType::Halfblocks) => Check::not_ok(
+      NAME,
+      Status::Missing,
+      "none, images are shown as halfblock thumbnails",
+      "use a terminal with Kitty graphics, Sixel or iTerm2 images (kitty, WezTerm, Ghostty, foot, iTerm2)",
+    ),
+    Some(ProtocolType::Kitty) => Check::ok(NAME, "Kitty graphics"),
+    Some(ProtocolType::Sixel) => Check::ok(NAME, "Sixel"),
+    Some(ProtocolType::Iterm2) => Check::ok(NAME, "iTerm2 inline images"),
+    None => Check::not_ok(NAME, Status::Unknown, "terminal not queried", "run in the terminal tfl is used in"),
+  }
+}
+
+fn clipboard(probe: &Probe) -> Check {
+  const NAME: &str = "Clipboard";
+  let var = |name: &str| (probe.var)(name).filter(|v| !v.is_empty());
+  if var("WSL_DISTRO_NAME").is_some() {
+    Check::ok(NAME, "Windows clipboard through powershell.exe (WSL)")
+  } else if var("SSH_CLIENT").is_some() {
+    Check::ok(NAME, "OSC 52 escape sequence (SSH session), if the terminal allows it")
+  } else if cfg!(target_os = "macos") {
+    Check::ok(NAME, "macOS pasteboard")
+  } else if var("WAYLAND_DISPLAY").is_some() {
+    Check::ok(NAME, "Wayland")
+  } else if var("DISPLAY").is_some() {
+    Check::ok(NAME, "X11")
+  } else {
+    Check::not_ok(
+      NAME,
+      Status::Missing,
+      "no X11 or Wayland display",
+      "yank needs a graphical session, or an SSH connection for OSC 52",
+    )
+  }
+}
+
+fn git(probe: &Probe) -> Check {
+  const NAME: &str = "Git";
+  let library = crate::git::libgit2_version();
+  match probe.runner.output(&ProcessSpec::new("git").arg("--version")) {
+    Ok((true, version)) => Check::ok(NAME, format!("libgit2 {library}, {}", version.trim())),
+    _ => Check::not_ok(
+      NAME,
+      Status::Missing,
+      format!("libgit2 {library}, no git command"),
+      "status, diffs and blame work without it; install git for the external difftool (D)",
+    ),
+  }
+}
+
+fn nerd_font(probe: &Probe) -> Check {
+  const NAME: &str = "Nerd Font";
+  match probe.runner.output(&ProcessSpec::new("fc-list").args([":", "family"])) {
+    Ok((true, families)) => match families.lines().find(|f| f.contains("Nerd Font") || f.ends_with(" NF")) {
+      Some(family) => Check::ok(NAME, format!("{} installed", family.split(',').next().unwrap_or(family))),
+      None => Check::not_ok(
+        NAME,
+        Status::Missing,
+        "none installed",
+        "install one from nerdfonts.com and use it in the terminal, or set icons = false",
+      ),
+    },
+    _ => Check::not_ok(
+      NAME,
+      Status::Unknown,
+      "cannot list fonts (no fc-list)",
+      "if tree icons show as boxes, use a Nerd Font in the terminal or set icons = false",
+    ),
+  }
+}
+
+fn watcher() -> Check {
+  const NAME: &str = "File watcher";
+  if cfg!(target_os = "linux") {
+    match std::fs::read_to_string("/proc/sys/fs/inotify/max_user_watches").ok().and_then(|s| s.trim().parse::<u64>().ok()) {
+      Some(watches) if watches < 8192 => Check::not_ok(
+        NAME,
+        Status::Missing,
+        format!("inotify, only {watches} watches per user"),
+        "raise fs.inotify.max_user_watches so changes in large trees are noticed",
+      ),
+      Some(watches) => Check::ok(NAME, format!("inotify, {watches} watches per user")),
+      None => Check::ok(NAME, "inotify"),
+    }
+  } else if cfg!(target_os = "macos") {
+    Check::ok(NAME, "FSEvents")
+  } else {
+    Check::ok(NAME, "kqueue")
+  }
+}
+
+fn handler(probe: &Probe) -> Check {
+  const NAME: &str = "File manager";
+  match probe.runner.output(&ProcessSpec::new("xdg-mime").args(["query", "default", "inode/directory"])) {
+    Ok((true, current)) if current.trim() == "tfl.desktop" => Check::ok(NAME, "tfl opens folders (inode/directory)"),
+    Ok((true, current)) => {
+      let current = current.trim();
+      let detail = if current.is_empty() { "no default for folders".to_string() } else { format!("folders open in {current}") };
+      Check::not_ok(NAME, Status::Missing, detail, "tfl --install-handler makes tfl the default")
+    }
+    _ => Check::not_ok(NAME, Status::Unknown, "no xdg-mime", "install xdg-utils to set the default file manager"),
+  }
+}
+
+fn portal(probe: &Probe) -> Check {
+  const NAME: &str = "File dialogs";
+  let config = probe.config_home.map(|home| home.join("xdg-desktop-portal-termfilechooser").join("config"));
+  match config.and_then(|path| std::fs::read_to_string(path).ok()) {
+    Some(config) if config.contains("tfl-wrapper.sh") => Check::ok(NAME, "tfl answers file dialogs (termfilechooser)"),
+    _ => Check::not_ok(
+      NAME,
+      Status::Missing,
+      "not set up",
+      "tfl --install-portal, with xdg-desktop-portal-termfilechooser installed",
+    ),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::process::MockRunner;
+
+  #[test]
+  fn test_checks_report_found_and_missing_pieces() {
+    let home = std::env::temp_dir().join(format!("tfl_doctor_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&home);
+    std::fs::create_dir_all(home.join("xdg-desktop-portal-termfilechooser")).unwrap();
+    std::fs::write(home.join("xdg-desktop-portal-termfilechooser/config"), "[filechooser]\ncmd=/x/tfl-wrapper.sh\n")
+      .unwrap();
+    let mut runner = MockRunner::default();
+    runner.outputs.insert("git".into(), "git version 2.45.0\n".into());
+    runner.outputs.insert("fc-list".into(), "DejaVu Sans\nJetBrainsMono Nerd Font,JetBrainsMono NF\n".into());
+    runner.outputs.insert("xdg-mime".into(), "org.gnome.Nautilus.desktop\n".into());
+    let var = |name: &str| (name == "WAYLAND_DISPLAY").then(|| "wayland-0".to_string());
+    let probe = Probe { runner: &runner, var: &var, config_home: Some(&home), image_protocol: Some(ProtocolType::Kitty) };
+
+    let checks = run(&probe);
+    let find = |name: &str| checks.iter().find(|c| c.name == name).unwrap();
+    assert_eq!(find("Image protocol").detail, "Kitty graphics");
+    assert_eq!(find("Clipboard").detail, "Wayland");
+    assert!(find("Git").detail.ends_with("git version 2.45.0"));
+    assert_eq!(find("Nerd Font").detail, "JetBrainsMono Nerd Font installed");
+    assert_eq!(find("File manager").status, Status::Missing);
+    assert_eq!(find("File manager").detail, "folders open in org.gnome.Nautilus.desktop");
+    assert_eq!(find("File dialogs").status, Status::Ok);
+
+    let no_display = |_: &str| None;
+    let bare = MockRunner { failing: vec!["git".into(), "fc-list".into()], ..MockRunner::default() };
+    let probe = Probe { runner: &bare, var: &no_display, config_home: None, image_protocol: None };
+    let checks = run(&probe);
+    assert_eq!(checks[0].status, Status::Unknown);
+    assert_eq!(checks[1].status, Status::Missing);
+    assert_eq!(checks[2].status, Status::Missing);
+    assert_eq!(checks[3].status, Status::Unknown);
+    let text = report(&checks);
+    assert!(text.starts_with("? Image protocol  terminal not queried\n"));
+    assert!(text.contains("\n                  → yank needs a graphical session"));
+    std::fs::remove_dir_all(&home).unwrap();
+  }
+}
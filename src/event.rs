@@ -1,27 +1,80 @@
 use std::collections::HashSet;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use crossterm::event::{self, Event as CrosstermEvent, KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use crossterm::event::{self, Event as CrosstermEvent, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
 use notify::{RecommendedWatcher, Watcher};
 
 use crate::action::Action;
 use crate::config::{Config, normalize_key_event};
+use crate::fs::ops::Collision;
+use crate::line_editor::LineEdit;
+use crate::preview::diff::HunkAction;
 
 const WATCHED_FILES: &[&str] = &["config.toml", "apps.toml", "favorites"];
+/// Quiet period after the last change in a watched directory before the
+/// tree reloads.
+pub const TREE_QUIET: Duration = Duration::from_millis(150);
+/// Longest a reload waits while changes keep coming, as during a build.
+pub const TREE_MAX_WAIT: Duration = Duration::from_secs(1);
 
 pub enum Event {
   Key(KeyEvent),
   Mouse(MouseEvent),
+  /// Text pasted in one piece (bracketed paste)
+  Paste(String),
   Resize(u16, u16),
   Tick,
-  ConfigChanged,
-  TreeChanged,
+  /// One of `WATCHED_FILES` changed at these paths
+  ConfigChanged(Vec<PathBuf>),
+  /// Entries were created, removed or renamed at these paths
+  TreeChanged(Vec<PathBuf>),
+  /// A file in a watched directory was written in place
+  FileChanged(PathBuf),
+  /// SIGTSTP while tfl has the terminal: hand it back and stop
+  Stop,
+  /// SIGCONT after being stopped from outside: take the terminal over again
+  Continue,
+  /// SIGTERM or SIGHUP: save state and exit
+  Terminate,
+}
+
+/// Coalesces `TreeChanged` events into one reload once they go quiet, or
+/// once a second while they don't.
+#[derive(Debug, Default)]
+pub struct TreeDebounce {
+  /// First and latest change since the last reload
+  pending: Option<(Instant, Instant)>,
+  /// Paths changed since the last reload
+  changed: Vec<PathBuf>,
+}
+
+impl TreeDebounce {
+  pub fn note(&mut self, now: Instant, paths: &[PathBuf]) {
+    let first = self.pending.map_or(now, |(first, _)| first);
+    self.pending = Some((first, now));
+    for path in paths {
+      if !self.changed.contains(path) {
+        self.changed.push(path.clone());
+      }
+    }
+  }
+
+  /// The paths changed if the tree should reload now; the changes count as
+  /// handled afterwards.
+  pub fn take_due(&mut self, now: Instant) -> Option<Vec<PathBuf>> {
+    let due = self.pending.is_some_and(|(first, last)| now - last >= TREE_QUIET || now - first >= TREE_MAX_WAIT);
+    if !due {
+      return None;
+    }
+    self.pending = None;
+    Some(std::mem::take(&mut self.changed))
+  }
 }
 
 enum WatchCommand {
@@ -44,7 +97,11 @@ impl TreeWatcher {
           EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(notify::event::ModifyKind::Name(_))
         );
         if dominated {
-          let _ = event_tx.send(Event::TreeChanged);
+          let _ = event_tx.send(Event::TreeChanged(ev.paths));
+        } else if matches!(ev.kind, EventKind::Modify(notify::event::ModifyKind::Data(_) | notify::event::ModifyKind::Any)) {
+          for path in ev.paths {
+            let _ = event_tx.send(Event::FileChanged(path));
+          }
         }
       }
     }) {
@@ -91,41 +148,13 @@ pub struct EventLoop {
 }
 
 impl EventLoop {
-  pub fn new(tick_rate: Duration, config_dir: Option<&Path>) -> Self {
+  /// `watched_dirs` are checked for changes to the files in `WATCHED_FILES`.
+  pub fn new(tick_rate: Duration, watched_dirs: &[PathBuf]) -> Self {
     let (tx, rx) = mpsc::channel();
     let paused = Arc::new(AtomicBool::new(false));
     let thread_paused = paused.clone();
 
-    let watcher = config_dir.and_then(|dir| {
-      if !dir.is_dir() {
-        eprintln!("tfl: config dir does not exist: {}", dir.display());
-        return None;
-      }
-      let watch_tx = tx.clone();
-      let mut watcher = match notify::recommended_watcher(move |res: std::result::Result<notify::Event, notify::Error>| {
-        if let Ok(ev) = res {
-          let dominated = ev.paths.iter().any(|p| {
-            p.file_name()
-              .and_then(|f| f.to_str())
-              .is_some_and(|name| WATCHED_FILES.contains(&name))
-          });
-          if dominated {
-            let _ = watch_tx.send(Event::ConfigChanged);
-          }
-        }
-      }) {
-        Ok(w) => w,
-        Err(e) => {
-          eprintln!("tfl: failed to create file watcher: {e}");
-          return None;
-        }
-      };
-      if let Err(e) = watcher.watch(dir, notify::RecursiveMode::NonRecursive) {
-        eprintln!("tfl: failed to watch {}: {e}", dir.display());
-        return None;
-      }
-      Some(watcher)
-    });
+    let watcher = watch_config_files(tx.clone(), watched_dirs);
 
     let tree_tx = tx.clone();
     thread::spawn(move || loop {
@@ -134,33 +163,26 @@ impl EventLoop {
         continue;
       }
       if event::poll(tick_rate).unwrap_or(false) {
-        match event::read() {
-          Ok(CrosstermEvent::Key(key)) => {
-            if tx.send(Event::Key(key)).is_err() {
-              break;
-            }
-          }
-          Ok(CrosstermEvent::Mouse(mouse)) => {
-            // Only send mouse down events to avoid duplicates
-            if matches!(mouse.kind, MouseEventKind::Down(_))
-              && tx.send(Event::Mouse(mouse)).is_err()
-            {
-              break;
-            }
+        let event = match event::read() {
+          Ok(CrosstermEvent::Key(key)) => Event::Key(key),
+          // Only send mouse down and move events to avoid duplicates
+          Ok(CrosstermEvent::Mouse(mouse)) if matches!(mouse.kind, MouseEventKind::Down(_) | MouseEventKind::Moved) => {
+            Event::Mouse(mouse)
           }
-          Ok(CrosstermEvent::Resize(w, h)) => {
-            if tx.send(Event::Resize(w, h)).is_err() {
-              break;
-            }
-          }
-          _ => {}
+          Ok(CrosstermEvent::Paste(text)) => Event::Paste(text),
+          Ok(CrosstermEvent::Resize(w, h)) => Event::Resize(w, h),
+          _ => continue,
+        };
+        if tx.send(event).is_err() {
+          break;
         }
       } else if tx.send(Event::Tick).is_err() {
         break;
       }
     });
 
-    let tree_watcher = TreeWatcher::new(tree_tx);
+    let tree_watcher = TreeWatcher::new(tree_tx.clone());
+    watch_signals(tree_tx, paused.clone());
 
     Self { rx, paused, _watcher: watcher, tree_watcher }
   }
@@ -173,7 +195,7 @@ impl EventLoop {
     self.paused.store(false, Ordering::Relaxed);
     let mut config_changed = false;
     while let Ok(ev) = self.rx.try_recv() {
-      if matches!(ev, Event::ConfigChanged) {
+      if matches!(ev, Event::ConfigChanged(_)) {
         config_changed = true;
       }
       // Drain stale TreeChanged events accumulated during suspend
@@ -192,6 +214,71 @@ impl EventLoop {
   }
 }
 
+/// Turn job control and termination signals into events. While paused for a
+/// foreground program (editor, shell), SIGTSTP stops tfl along with it and
+/// SIGCONT is left to that program, as if no handler were installed.
+fn watch_signals(tx: mpsc::Sender<Event>, paused: Arc<AtomicBool>) {
+  use signal_hook::consts::{SIGCONT, SIGHUP, SIGTERM, SIGTSTP};
+
+  let mut signals = match signal_hook::iterator::Signals::new([SIGTSTP, SIGCONT, SIGTERM, SIGHUP]) {
+    Ok(signals) => signals,
+    Err(e) => {
+      tracing::warn!("failed to install signal handlers: {e}");
+      return;
+    }
+  };
+  thread::spawn(move || {
+    for signal in signals.forever() {
+      let suspended = paused.load(Ordering::Relaxed);
+      let event = match signal {
+        SIGTSTP if suspended => {
+          let _ = signal_hook::low_level::emulate_default_handler(SIGTSTP);
+          continue;
+        }
+        SIGTSTP => Event::Stop,
+        SIGCONT if suspended => continue,
+        SIGCONT => Event::Continue,
+        _ => Event::Terminate,
+      };
+      if tx.send(event).is_err() {
+        break;
+      }
+    }
+  });
+}
+
+/// Send `ConfigChanged` when one of `WATCHED_FILES` changes in `dirs`.
+fn watch_config_files(tx: mpsc::Sender<Event>, dirs: &[PathBuf]) -> Option<RecommendedWatcher> {
+  let mut watcher = match notify::recommended_watcher(move |res: std::result::Result<notify::Event, notify::Error>| {
+    if let Ok(ev) = res {
+      let changed: Vec<PathBuf> = ev
+        .paths
+        .into_iter()
+        .filter(|p| p.file_name().and_then(|f| f.to_str()).is_some_and(|name| WATCHED_FILES.contains(&name)))
+        .collect();
+      if !changed.is_empty() {
+        let _ = tx.send(Event::ConfigChanged(changed));
+      }
+    }
+  }) {
+    Ok(w) => w,
+    Err(e) => {
+      eprintln!("tfl: failed to create file watcher: {e}");
+      return None;
+    }
+  };
+  for dir in dirs {
+    if !dir.is_dir() {
+      eprintln!("tfl: config dir does not exist: {}", dir.display());
+      continue;
+    }
+    if let Err(e) = watcher.watch(dir, notify::RecursiveMode::NonRecursive) {
+      eprintln!("tfl: failed to watch {}: {e}", dir.display());
+    }
+  }
+  Some(watcher)
+}
+
 /// Whether the app is in search/filter mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
@@ -201,11 +288,77 @@ pub enum InputMode {
   Help,
   Prompt,
   Favorites,
+  /// Saved layouts picker
+  Layouts,
+  /// Directories dropdown under a breadcrumb segment
+  BreadcrumbMenu,
   OpenWith,
+  /// Numbered URLs in view in the preview
+  Urls,
+  /// Detected capabilities (`tfl --doctor`)
+  Doctor,
   Chmod,
   Properties,
   Compress,
+  Templates,
+  Worktrees,
+  /// Local branches to check out
+  Branches,
+  /// Today's entries of the operation log
+  OpLog,
+  /// Running and queued extractions, compressions and copies
+  Jobs,
+  /// Symlink and metadata choices for one paste
+  PasteOptions,
+  /// What to do with a pasted name that is already taken
+  PasteConflict,
+  PatchExport,
+  /// Theme, icons, tree width and sorting, applied as they change
+  Settings,
   Error,
+  /// Waiting for the register key of a macro record/replay
+  MacroRegister,
+  /// Waiting for the slot digit of a bookmark to set
+  BookmarkSet,
+  /// Waiting for the slot digit of a bookmark to jump to
+  BookmarkJump,
+}
+
+impl InputMode {
+  /// What Esc does in this mode. The match is exhaustive so a new mode has
+  /// to decide; `Action::Escape` hands over to the layered normal-mode
+  /// handling in `App`.
+  pub fn escape_action(self) -> Action {
+    match self {
+      InputMode::Normal
+      | InputMode::GPrefix
+      | InputMode::MacroRegister
+      | InputMode::BookmarkSet
+      | InputMode::BookmarkJump => Action::Escape,
+      InputMode::Search => Action::SearchCancel,
+      InputMode::Help => Action::ToggleHelp,
+      InputMode::Prompt => Action::PromptCancel,
+      InputMode::Favorites => Action::FavoritesClose,
+      InputMode::Layouts => Action::LayoutsClose,
+      InputMode::BreadcrumbMenu => Action::BreadcrumbMenuClose,
+      InputMode::OpenWith => Action::OpenWithClose,
+      InputMode::Urls => Action::UrlsClose,
+      InputMode::Doctor => Action::DoctorClose,
+      InputMode::Chmod => Action::ChmodClose,
+      InputMode::Properties => Action::PropertiesClose,
+      InputMode::Compress => Action::CompressClose,
+      InputMode::Templates => Action::TemplatesClose,
+      InputMode::Worktrees => Action::WorktreesClose,
+      InputMode::Branches => Action::BranchesClose,
+      InputMode::OpLog => Action::OpLogClose,
+      InputMode::Jobs => Action::JobsClose,
+      InputMode::PasteOptions => Action::PasteOptionsClose,
+      InputMode::PasteConflict => Action::PasteConflictCancel,
+      InputMode::PatchExport => Action::ExportPatchClose,
+      InputMode::Settings => Action::SettingsClose,
+      InputMode::Error => Action::ErrorClose,
+    }
+  }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -216,12 +369,129 @@ pub enum PromptKind {
   ConfirmDelete,
   ConfirmDeleteMulti(usize),
   ConfirmExtractAndDelete,
+  /// Stage, unstage or revert the given hunk of the shown diff
+  ConfirmHunk(HunkAction, usize),
+  /// Discard the unstaged changes of this many entries
+  ConfirmGitDiscard(usize),
+  /// Restore the session journaled before an unclean exit
+  ConfirmRestoreSession,
+  /// Paste with names the destination filesystem accepts
+  ConfirmSafeNames,
+  ConfirmQuit,
+  /// Name for the current root and expansion, saved as a layout
+  SaveLayout,
+  /// Directory to copy or move the targets into
+  CopyTo,
+  MoveTo,
+  /// Where to create a symlink to the selected entry
+  Symlink,
+  /// Name of a branch to create and switch to
+  NewBranch,
+  /// Line to scroll the text preview to
+  GotoLine,
+  /// Where to save the exported patch
+  PatchPath,
+}
+
+impl PromptKind {
+  /// Whether the prompt edits a name rather than waiting for y/n.
+  pub fn takes_text(self) -> bool {
+    matches!(
+      self,
+      PromptKind::Rename
+        | PromptKind::NewFile
+        | PromptKind::NewDir
+        | PromptKind::SaveLayout
+        | PromptKind::CopyTo
+        | PromptKind::MoveTo
+        | PromptKind::Symlink
+        | PromptKind::NewBranch
+        | PromptKind::GotoLine
+        | PromptKind::PatchPath
+    )
+  }
+}
+
+/// Readline keys shared by every text prompt. Checked before plain
+/// characters so Ctrl+w doesn't type a `w`.
+fn line_edit_key(key: KeyEvent) -> Option<LineEdit> {
+  if key.modifiers.contains(KeyModifiers::CONTROL) {
+    match key.code {
+      KeyCode::Char('w') => Some(LineEdit::DeleteWord),
+      KeyCode::Char('u') => Some(LineEdit::KillToStart),
+      KeyCode::Char('k') => Some(LineEdit::KillToEnd),
+      KeyCode::Char('v') => Some(LineEdit::Paste),
+      _ => None,
+    }
+  } else if key.modifiers.contains(KeyModifiers::ALT) {
+    match key.code {
+      KeyCode::Char('b') => Some(LineEdit::WordLeft),
+      KeyCode::Char('f') => Some(LineEdit::WordRight),
+      _ => None,
+    }
+  } else {
+    None
+  }
+}
+
+/// Speeds up `move_down`/`move_up` while their key is held: after `after`
+/// repeats arriving less than `gap` apart each event moves 5 entries, after
+/// twice as many 10. Terminals report held keys as a stream of presses, so
+/// holding is told apart from tapping by timing alone.
+pub struct MoveAccel {
+  after: usize,
+  gap: Duration,
+  last: Option<(KeyCode, Instant)>,
+  repeats: usize,
+}
+
+impl MoveAccel {
+  pub fn new(config: &Config) -> Self {
+    Self { after: config.move_accel_after, gap: Duration::from_millis(config.move_accel_ms), last: None, repeats: 0 }
+  }
+
+  pub fn apply_config(&mut self, config: &Config) {
+    self.after = config.move_accel_after;
+    self.gap = Duration::from_millis(config.move_accel_ms);
+  }
+
+  /// `action` as mapped from `key`, turned into a bigger move if the key
+  /// has been held long enough.
+  pub fn accelerate(&mut self, action: Action, key: KeyCode, now: Instant) -> Action {
+    let held = self.last.is_some_and(|(code, at)| code == key && now.duration_since(at) <= self.gap);
+    self.repeats = if held { self.repeats + 1 } else { 0 };
+    self.last = Some((key, now));
+    let step = match self.after {
+      0 => 1,
+      after if self.repeats >= 2 * after => 10,
+      after if self.repeats >= after => 5,
+      _ => 1,
+    };
+    match action {
+      Action::MoveDown if step > 1 => Action::MoveBy(step),
+      Action::MoveUp if step > 1 => Action::MoveBy(-step),
+      action => action,
+    }
+  }
 }
 
 pub fn map_key(key: KeyEvent, mode: InputMode, config: &Config) -> Action {
+  // Normal and search mode Esc stay rebindable through the key config
+  if key.code == KeyCode::Esc && !matches!(mode, InputMode::Normal | InputMode::Search) {
+    return mode.escape_action();
+  }
+  if matches!(mode, InputMode::Prompt | InputMode::Search)
+    && let Some(edit) = line_edit_key(key)
+  {
+    return Action::LineEdit(edit);
+  }
   match mode {
     InputMode::Search => match key.code {
       KeyCode::Backspace => Action::SearchBackspace,
+      KeyCode::Up => Action::InputHistoryPrev,
+      KeyCode::Down => Action::InputHistoryNext,
+      KeyCode::PageDown => Action::PageDown,
+      KeyCode::PageUp => Action::PageUp,
       KeyCode::Char(c) => Action::SearchInput(c),
       _ => {
         let kb = normalize_key_event(key);
@@ -240,7 +510,6 @@ pub fn map_key(key: KeyEvent, mode: InputMode, config: &Config) -> Action {
       config.g_prefix_keys.get(&kb).cloned().unwrap_or(Action::None)
     }
     InputMode::Help => match key.code {
-      KeyCode::Esc => Action::ToggleHelp,
       KeyCode::Char('?') => Action::ToggleHelp,
       KeyCode::Char('q') => Action::ToggleHelp,
       KeyCode::Char('j') | KeyCode::Char('J') | KeyCode::Down | KeyCode::PageDown => Action::ScrollPreviewDown,
@@ -248,7 +517,6 @@ pub fn map_key(key: KeyEvent, mode: InputMode, config: &Config) -> Action {
       _ => Action::None,
     },
     InputMode::Prompt => match key.code {
-      KeyCode::Esc => Action::PromptCancel,
       KeyCode::Enter => Action::PromptConfirm,
       KeyCode::Backspace => Action::PromptBackspace,
       KeyCode::Delete => Action::PromptDelete,
@@ -256,6 +524,9 @@ pub fn map_key(key: KeyEvent, mode: InputMode, config: &Config) -> Action {
       KeyCode::Right => Action::PromptRight,
       KeyCode::Home => Action::PromptHome,
       KeyCode::End => Action::PromptEnd,
+      KeyCode::Up => Action::InputHistoryPrev,
+      KeyCode::Down => Action::InputHistoryNext,
+      KeyCode::Tab => Action::PromptTab,
       KeyCode::Char(c) => Action::PromptInput(c),
       _ => Action::None,
     },
@@ -263,20 +534,59 @@ pub fn map_key(key: KeyEvent, mode: InputMode, config: &Config) -> Action {
       KeyCode::Char('j') | KeyCode::Down => Action::FavoritesDown,
       KeyCode::Char('k') | KeyCode::Up => Action::FavoritesUp,
       KeyCode::Enter => Action::FavoritesSelect,
-      KeyCode::Esc | KeyCode::Char('q') => Action::FavoritesClose,
+      KeyCode::Char('q') => Action::FavoritesClose,
       KeyCode::Char('d') | KeyCode::Delete => Action::FavoritesRemove,
       KeyCode::Char('a') => Action::FavoritesAddCurrent,
       _ => Action::None,
     },
+    InputMode::Layouts => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::LayoutsDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::LayoutsUp,
+      KeyCode::Enter => Action::LayoutsSelect,
+      KeyCode::Char('q') | KeyCode::Char('b') => Action::LayoutsClose,
+      KeyCode::Char('d') | KeyCode::Delete => Action::LayoutsRemove,
+      KeyCode::Char('a') => Action::LayoutSaveStart,
+      _ => Action::None,
+    },
+    InputMode::BreadcrumbMenu => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::BreadcrumbMenuDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::BreadcrumbMenuUp,
+      KeyCode::Char('h') | KeyCode::Left => Action::BreadcrumbMenuParent,
+      KeyCode::Char('l') | KeyCode::Right => Action::BreadcrumbMenuChild,
+      KeyCode::Enter => Action::BreadcrumbMenuSelect,
+      KeyCode::Char('q') => Action::BreadcrumbMenuClose,
+      _ => Action::None,
+    },
+    InputMode::Settings => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::SettingsDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::SettingsUp,
+      KeyCode::Char('l') | KeyCode::Right | KeyCode::Char(' ') => Action::SettingsChange(true),
+      KeyCode::Char('h') | KeyCode::Left => Action::SettingsChange(false),
+      KeyCode::Enter => Action::SettingsSave,
+      KeyCode::Char('q') => Action::SettingsClose,
+      _ => Action::None,
+    },
     InputMode::OpenWith => match key.code {
       KeyCode::Char('j') | KeyCode::Down => Action::OpenWithDown,
       KeyCode::Char('k') | KeyCode::Up => Action::OpenWithUp,
       KeyCode::Enter => Action::OpenWithSelect,
-      KeyCode::Esc | KeyCode::Char('q') => Action::OpenWithClose,
+      KeyCode::Char('q') => Action::OpenWithClose,
+      _ => Action::None,
+    },
+    InputMode::Urls => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::UrlsDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::UrlsUp,
+      KeyCode::Enter => Action::UrlsSelect,
+      KeyCode::Char(c @ '1'..='9') => Action::UrlsPick(c as usize - '1' as usize),
+      KeyCode::Char('q') => Action::UrlsClose,
+      _ => Action::None,
+    },
+    InputMode::Doctor => match key.code {
+      KeyCode::Char('q') => Action::DoctorClose,
       _ => Action::None,
     },
     InputMode::Chmod => match key.code {
-      KeyCode::Esc | KeyCode::Char('q') => Action::ChmodClose,
+      KeyCode::Char('q') => Action::ChmodClose,
       KeyCode::Enter => Action::ChmodApply,
       KeyCode::Char('r') => Action::ChmodToggleBit(0), // owner read
       KeyCode::Char('w') => Action::ChmodToggleBit(1), // owner write
@@ -284,28 +594,110 @@ pub fn map_key(key: KeyEvent, mode: InputMode, config: &Config) -> Action {
       KeyCode::Char('R') => Action::ChmodToggleBit(3), // group read
       KeyCode::Char('W') => Action::ChmodToggleBit(4), // group write
       KeyCode::Char('X') => Action::ChmodToggleBit(5), // group execute
+      KeyCode::Char('u') => Action::ChmodToggleBit(9), // setuid
+      KeyCode::Char('g') => Action::ChmodToggleBit(10), // setgid
+      KeyCode::Char('t') => Action::ChmodToggleBit(11), // sticky
       KeyCode::Char(c @ '0'..='7') => Action::ChmodDigit(c),
       KeyCode::Tab => Action::ChmodToggleOctal,
       KeyCode::Backspace => Action::ChmodOctalBackspace,
       KeyCode::Char('d') => Action::ChmodToggleRecursive,
-      _ => Action::None,
+      KeyCode::Char('l') => Action::ChmodToggleFollowSymlinks,
+      _ => {
+        let kb = normalize_key_event(key);
+        config.chmod_presets.iter().position(|p| p.key == kb).map_or(Action::None, Action::ChmodPreset)
+      }
     },
     InputMode::Compress => match key.code {
       KeyCode::Char('1') => Action::CompressSelect(0),
       KeyCode::Char('2') => Action::CompressSelect(1),
       KeyCode::Char('3') => Action::CompressSelect(2),
       KeyCode::Char('4') => Action::CompressSelect(3),
-      KeyCode::Esc | KeyCode::Char('q') => Action::CompressClose,
+      KeyCode::Char('q') => Action::CompressClose,
+      _ => Action::None,
+    },
+    InputMode::PatchExport => match key.code {
+      KeyCode::Char('1') => Action::ExportPatchSelect(0),
+      KeyCode::Char('2') => Action::ExportPatchSelect(1),
+      KeyCode::Char('3') => Action::ExportPatchSelect(2),
+      KeyCode::Char('4') => Action::ExportPatchSelect(3),
+      KeyCode::Char('q') => Action::ExportPatchClose,
+      _ => Action::None,
+    },
+    InputMode::Templates => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::TemplatesDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::TemplatesUp,
+      KeyCode::Enter => Action::TemplatesSelect,
+      KeyCode::Char('q') => Action::TemplatesClose,
+      _ => Action::None,
+    },
+    InputMode::Worktrees => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::WorktreesDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::WorktreesUp,
+      KeyCode::Enter => Action::WorktreesSelect,
+      KeyCode::Char('q') => Action::WorktreesClose,
+      _ => Action::None,
+    },
+    InputMode::Branches => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::BranchesDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::BranchesUp,
+      KeyCode::Enter => Action::BranchesSelect,
+      KeyCode::Char('n') => Action::BranchesNew,
+      KeyCode::Char('q') => Action::BranchesClose,
+      _ => Action::None,
+    },
+    InputMode::OpLog => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::OpLogDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::OpLogUp,
+      KeyCode::Char('q') | KeyCode::Char('L') => Action::OpLogClose,
+      _ => Action::None,
+    },
+    InputMode::PasteOptions => match key.code {
+      KeyCode::Char('l') => Action::PasteOptionsToggle(0),
+      KeyCode::Char('m') => Action::PasteOptionsToggle(1),
+      KeyCode::Char('t') => Action::PasteOptionsToggle(2),
+      KeyCode::Char('x') => Action::PasteOptionsToggle(3),
+      KeyCode::Char('v') => Action::PasteOptionsToggle(4),
+      KeyCode::Enter => Action::PasteOptionsConfirm,
+      KeyCode::Char('q') => Action::PasteOptionsClose,
+      _ => Action::None,
+    },
+    InputMode::PasteConflict => match key.code {
+      KeyCode::Char('o') => Action::PasteConflictChoose(Collision::Overwrite),
+      KeyCode::Char('s') => Action::PasteConflictChoose(Collision::Skip),
+      KeyCode::Char('r') => Action::PasteConflictChoose(Collision::Rename),
+      KeyCode::Char('a') => Action::PasteConflictAll,
+      KeyCode::Char('q') => Action::PasteConflictCancel,
+      _ => Action::None,
+    },
+    InputMode::Jobs => match key.code {
+      KeyCode::Char('j') | KeyCode::Down => Action::JobsDown,
+      KeyCode::Char('k') | KeyCode::Up => Action::JobsUp,
+      KeyCode::Char('d') | KeyCode::Delete => Action::JobsCancel,
+      KeyCode::Char('q') | KeyCode::Char('t') => Action::JobsClose,
       _ => Action::None,
     },
     InputMode::Properties => match key.code {
-      KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('i') => Action::PropertiesClose,
+      KeyCode::Char('q') | KeyCode::Char('i') => Action::PropertiesClose,
+      KeyCode::Char('r') => Action::RenameStart,
+      KeyCode::Char('x') => Action::PropertiesToggleExecutable,
+      KeyCode::Char('p') => Action::ChmodStart,
       _ => Action::None,
     },
     InputMode::Error => match key.code {
-      KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => Action::ErrorClose,
+      KeyCode::Enter | KeyCode::Char('q') => Action::ErrorClose,
+      _ => Action::None,
+    },
+    InputMode::MacroRegister => match key.code {
+      KeyCode::Char(c) => Action::MacroRegister(c),
       _ => Action::None,
     },
+    InputMode::BookmarkSet | InputMode::BookmarkJump => match key.code {
+      KeyCode::Char(c @ '1'..='9') => {
+        let slot = c as u8 - b'0';
+        if mode == InputMode::BookmarkSet { Action::SetMark(slot) } else { Action::JumpMark(slot) }
+      }
+      _ => Action::Escape,
+    },
     InputMode::Normal => {
       let kb = normalize_key_event(key);
       config.normal_keys.get(&kb).cloned().unwrap_or(Action::None)
@@ -321,6 +713,11 @@ pub fn map_breadcrumb_click(col: u16, segments: &[crate::ui::breadcrumb::Breadcr
   crate::ui::breadcrumb::segment_at_column(segments, adjusted_col).map(Action::BreadcrumbSelect)
 }
 
+/// Map a right click on the header to the dropdown of that segment.
+pub fn map_breadcrumb_menu_click(col: u16, segments: &[crate::ui::breadcrumb::BreadcrumbSegment]) -> Option<Action> {
+  crate::ui::breadcrumb::segment_at_column(segments, col.saturating_sub(1)).map(Action::BreadcrumbMenuAt)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -352,7 +749,7 @@ mod tests {
   fn test_normal_mode_quit() {
     let c = cfg();
     assert_eq!(map_key(key(KeyCode::Char('q')), InputMode::Normal, &c), Action::Quit);
-    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Normal, &c), Action::Quit);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Normal, &c), Action::Escape);
   }
 
   #[test]
@@ -378,6 +775,16 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Char('K')), InputMode::Normal, &c), Action::ScrollPreviewUp);
   }
 
+  #[test]
+  fn test_ctrl_z_suspends() {
+    let c = cfg();
+    assert_eq!(
+      map_key(key_with_mod(KeyCode::Char('z'), KeyModifiers::CONTROL), InputMode::Normal, &c),
+      Action::SuspendProcess
+    );
+    assert_eq!(map_key(key(KeyCode::Char('M')), InputMode::Normal, &c), Action::ToggleMouse);
+  }
+
   #[test]
   fn test_ctrl_c_copies_file() {
     let c = cfg();
@@ -400,13 +807,46 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Enter), InputMode::Search, &c), Action::SearchConfirm);
     assert_eq!(map_key(key(KeyCode::Esc), InputMode::Search, &c), Action::SearchCancel);
     assert_eq!(map_key(key(KeyCode::Backspace), InputMode::Search, &c), Action::SearchBackspace);
+    assert_eq!(map_key(key(KeyCode::Up), InputMode::Search, &c), Action::InputHistoryPrev);
+    assert_eq!(map_key(key(KeyCode::Down), InputMode::Search, &c), Action::InputHistoryNext);
   }
 
   #[test]
   fn test_g_prefix_mode() {
     let c = cfg();
     assert_eq!(map_key(key(KeyCode::Char('g')), InputMode::GPrefix, &c), Action::GoToTop);
-    assert_eq!(map_key(key(KeyCode::Char('x')), InputMode::GPrefix, &c), Action::None);
+    assert_eq!(map_key(key(KeyCode::Char('y')), InputMode::GPrefix, &c), Action::None);
+    assert_eq!(map_key(key(KeyCode::Char('x')), InputMode::GPrefix, &c), Action::UrlsOpen);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::GPrefix, &c), Action::Escape);
+    assert_eq!(map_key(key(KeyCode::Char('c')), InputMode::GPrefix, &c), Action::CopyToStart);
+    assert_eq!(map_key(key(KeyCode::Char('m')), InputMode::GPrefix, &c), Action::MoveToStart);
+  }
+
+  #[test]
+  fn test_macro_register_mode() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('a')), InputMode::MacroRegister, &c), Action::MacroRegister('a'));
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::MacroRegister, &c), Action::Escape);
+    assert_eq!(map_key(key(KeyCode::Char('@')), InputMode::Normal, &c), Action::MacroPlay);
+  }
+
+  #[test]
+  fn test_bookmark_slot_modes() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('"')), InputMode::Normal, &c), Action::BookmarkSet);
+    assert_eq!(map_key(key(KeyCode::Char('\'')), InputMode::Normal, &c), Action::BookmarkJump);
+    assert_eq!(map_key(key(KeyCode::Char('3')), InputMode::BookmarkSet, &c), Action::SetMark(3));
+    assert_eq!(map_key(key(KeyCode::Char('9')), InputMode::BookmarkJump, &c), Action::JumpMark(9));
+    assert_eq!(map_key(key(KeyCode::Char('0')), InputMode::BookmarkJump, &c), Action::Escape);
+    assert_eq!(map_key(key(KeyCode::Char('x')), InputMode::BookmarkSet, &c), Action::Escape);
+  }
+
+  #[test]
+  fn test_esc_follows_escape_action_in_modal_modes() {
+    let c = cfg();
+    for mode in [InputMode::Help, InputMode::Prompt, InputMode::Chmod, InputMode::Properties, InputMode::Error] {
+      assert_eq!(map_key(key(KeyCode::Esc), mode, &c), mode.escape_action());
+    }
   }
 
   #[test]
@@ -465,6 +905,12 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Enter), InputMode::Prompt, &c), Action::PromptConfirm);
   }
 
+  #[test]
+  fn test_prompt_mode_tab_toggles_executable() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Tab), InputMode::Prompt, &c), Action::PromptTab);
+  }
+
   #[test]
   fn test_prompt_mode_esc() {
     let c = cfg();
@@ -477,6 +923,35 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Backspace), InputMode::Prompt, &c), Action::PromptBackspace);
   }
 
+  #[test]
+  fn test_prompt_mode_history_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Up), InputMode::Prompt, &c), Action::InputHistoryPrev);
+    assert_eq!(map_key(key(KeyCode::Down), InputMode::Prompt, &c), Action::InputHistoryNext);
+  }
+
+  #[test]
+  fn test_readline_keys_in_text_modes() {
+    let c = cfg();
+    for mode in [InputMode::Prompt, InputMode::Search] {
+      let ctrl = |ch| map_key(key_with_mod(KeyCode::Char(ch), KeyModifiers::CONTROL), mode, &c);
+      assert_eq!(ctrl('w'), Action::LineEdit(LineEdit::DeleteWord));
+      assert_eq!(ctrl('u'), Action::LineEdit(LineEdit::KillToStart));
+      assert_eq!(ctrl('k'), Action::LineEdit(LineEdit::KillToEnd));
+      assert_eq!(ctrl('v'), Action::LineEdit(LineEdit::Paste));
+      let alt = |ch| map_key(key_with_mod(KeyCode::Char(ch), KeyModifiers::ALT), mode, &c);
+      assert_eq!(alt('b'), Action::LineEdit(LineEdit::WordLeft));
+      assert_eq!(alt('f'), Action::LineEdit(LineEdit::WordRight));
+    }
+    // Plain letters still type
+    assert_eq!(map_key(key(KeyCode::Char('w')), InputMode::Prompt, &c), Action::PromptInput('w'));
+    // Normal mode keeps its own Ctrl+v
+    assert_eq!(
+      map_key(key_with_mod(KeyCode::Char('v'), KeyModifiers::CONTROL), InputMode::Normal, &c),
+      Action::Paste
+    );
+  }
+
   #[test]
   fn test_favorites_mode_navigation() {
     let c = cfg();
@@ -511,6 +986,26 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Char('q')), InputMode::OpenWith, &c), Action::OpenWithClose);
   }
 
+  #[test]
+  fn test_urls_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('j')), InputMode::Urls, &c), Action::UrlsDown);
+    assert_eq!(map_key(key(KeyCode::Enter), InputMode::Urls, &c), Action::UrlsSelect);
+    assert_eq!(map_key(key(KeyCode::Char('1')), InputMode::Urls, &c), Action::UrlsPick(0));
+    assert_eq!(map_key(key(KeyCode::Char('9')), InputMode::Urls, &c), Action::UrlsPick(8));
+    assert_eq!(map_key(key(KeyCode::Char('0')), InputMode::Urls, &c), Action::None);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Urls, &c), Action::UrlsClose);
+  }
+
+  #[test]
+  fn test_doctor_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('d')), InputMode::GPrefix, &c), Action::DoctorOpen);
+    assert_eq!(map_key(key(KeyCode::Char('q')), InputMode::Doctor, &c), Action::DoctorClose);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Doctor, &c), Action::DoctorClose);
+    assert_eq!(map_key(key(KeyCode::Char('j')), InputMode::Doctor, &c), Action::None);
+  }
+
   #[test]
   fn test_open_with_mode_other_keys_ignored() {
     let c = cfg();
@@ -552,6 +1047,9 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Char('R')), InputMode::Chmod, &c), Action::ChmodToggleBit(3));
     assert_eq!(map_key(key(KeyCode::Char('W')), InputMode::Chmod, &c), Action::ChmodToggleBit(4));
     assert_eq!(map_key(key(KeyCode::Char('X')), InputMode::Chmod, &c), Action::ChmodToggleBit(5));
+    assert_eq!(map_key(key(KeyCode::Char('u')), InputMode::Chmod, &c), Action::ChmodToggleBit(9));
+    assert_eq!(map_key(key(KeyCode::Char('g')), InputMode::Chmod, &c), Action::ChmodToggleBit(10));
+    assert_eq!(map_key(key(KeyCode::Char('t')), InputMode::Chmod, &c), Action::ChmodToggleBit(11));
   }
 
   #[test]
@@ -560,6 +1058,22 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Char('d')), InputMode::Chmod, &c), Action::ChmodToggleRecursive);
   }
 
+  #[test]
+  fn test_chmod_mode_toggle_follow_symlinks() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('l')), InputMode::Chmod, &c), Action::ChmodToggleFollowSymlinks);
+  }
+
+  #[test]
+  fn test_chmod_mode_preset_keys() {
+    let c = cfg();
+    let Action::ChmodPreset(index) = map_key(key(KeyCode::Char('e')), InputMode::Chmod, &c) else {
+      panic!("e should be a preset");
+    };
+    assert_eq!(c.chmod_presets[index].label(), "755");
+    assert_eq!(map_key(key(KeyCode::Char('z')), InputMode::Chmod, &c), Action::None);
+  }
+
   #[test]
   fn test_chmod_mode_toggle_octal() {
     let c = cfg();
@@ -591,6 +1105,14 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Enter), InputMode::Properties, &c), Action::None);
   }
 
+  #[test]
+  fn test_properties_mode_edit_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('r')), InputMode::Properties, &c), Action::RenameStart);
+    assert_eq!(map_key(key(KeyCode::Char('x')), InputMode::Properties, &c), Action::PropertiesToggleExecutable);
+    assert_eq!(map_key(key(KeyCode::Char('p')), InputMode::Properties, &c), Action::ChmodStart);
+  }
+
   // === Compress mode tests ===
 
   #[test]
@@ -617,6 +1139,130 @@ mod tests {
     assert_eq!(map_key(key(KeyCode::Enter), InputMode::Compress, &c), Action::None);
   }
 
+  // === Patch export mode tests ===
+
+  #[test]
+  fn test_patch_export_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('1')), InputMode::PatchExport, &c), Action::ExportPatchSelect(0));
+    assert_eq!(map_key(key(KeyCode::Char('4')), InputMode::PatchExport, &c), Action::ExportPatchSelect(3));
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::PatchExport, &c), Action::ExportPatchClose);
+    assert_eq!(map_key(key(KeyCode::Char('q')), InputMode::PatchExport, &c), Action::ExportPatchClose);
+    assert_eq!(map_key(key(KeyCode::Char('5')), InputMode::PatchExport, &c), Action::None);
+  }
+
+  // === Templates mode tests ===
+
+  #[test]
+  fn test_templates_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('j')), InputMode::Templates, &c), Action::TemplatesDown);
+    assert_eq!(map_key(key(KeyCode::Down), InputMode::Templates, &c), Action::TemplatesDown);
+    assert_eq!(map_key(key(KeyCode::Char('k')), InputMode::Templates, &c), Action::TemplatesUp);
+    assert_eq!(map_key(key(KeyCode::Up), InputMode::Templates, &c), Action::TemplatesUp);
+    assert_eq!(map_key(key(KeyCode::Enter), InputMode::Templates, &c), Action::TemplatesSelect);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Templates, &c), Action::TemplatesClose);
+    assert_eq!(map_key(key(KeyCode::Char('q')), InputMode::Templates, &c), Action::TemplatesClose);
+    assert_eq!(map_key(key(KeyCode::Char('x')), InputMode::Templates, &c), Action::None);
+  }
+
+  #[test]
+  fn test_worktrees_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('j')), InputMode::Worktrees, &c), Action::WorktreesDown);
+    assert_eq!(map_key(key(KeyCode::Up), InputMode::Worktrees, &c), Action::WorktreesUp);
+    assert_eq!(map_key(key(KeyCode::Enter), InputMode::Worktrees, &c), Action::WorktreesSelect);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Worktrees, &c), Action::WorktreesClose);
+    assert_eq!(map_key(key(KeyCode::Char('W')), InputMode::Normal, &c), Action::WorktreesStart);
+    assert_eq!(map_key(key(KeyCode::Char('n')), InputMode::Branches, &c), Action::BranchesNew);
+    assert_eq!(map_key(key(KeyCode::Enter), InputMode::Branches, &c), Action::BranchesSelect);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Branches, &c), Action::BranchesClose);
+  }
+
+  #[test]
+  fn test_move_accel_speeds_up_held_key() {
+    let mut c = cfg();
+    c.move_accel_after = 3;
+    let mut accel = MoveAccel::new(&c);
+    let start = Instant::now();
+    let at = |ms: u64| start + Duration::from_millis(ms);
+    let steps: Vec<Action> = (0..8).map(|i| accel.accelerate(Action::MoveDown, KeyCode::Char('j'), at(i * 30))).collect();
+    assert_eq!(steps[..3], [Action::MoveDown, Action::MoveDown, Action::MoveDown]);
+    assert_eq!(steps[3], Action::MoveBy(5));
+    assert_eq!(steps[6], Action::MoveBy(10));
+    // A pause or another key starts over
+    assert_eq!(accel.accelerate(Action::MoveUp, KeyCode::Char('k'), at(260)), Action::MoveUp);
+    assert_eq!(accel.accelerate(Action::MoveDown, KeyCode::Char('j'), at(1000)), Action::MoveDown);
+    assert_eq!(map_key(key(KeyCode::PageDown), InputMode::Search, &c), Action::PageDown);
+    assert_eq!(map_key(key(KeyCode::PageUp), InputMode::Normal, &c), Action::PageUp);
+    c.move_accel_after = 0;
+    accel.apply_config(&c);
+    for i in 0..30 {
+      assert_eq!(accel.accelerate(Action::MoveDown, KeyCode::Char('j'), at(1000 + i * 10)), Action::MoveDown);
+    }
+  }
+
+  #[test]
+  fn test_op_log_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('L')), InputMode::Normal, &c), Action::OpLogOpen);
+    assert_eq!(map_key(key(KeyCode::Char('j')), InputMode::OpLog, &c), Action::OpLogDown);
+    assert_eq!(map_key(key(KeyCode::Up), InputMode::OpLog, &c), Action::OpLogUp);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::OpLog, &c), Action::OpLogClose);
+  }
+
+  #[test]
+  fn test_breadcrumb_menu_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('j')), InputMode::BreadcrumbMenu, &c), Action::BreadcrumbMenuDown);
+    assert_eq!(map_key(key(KeyCode::Left), InputMode::BreadcrumbMenu, &c), Action::BreadcrumbMenuParent);
+    assert_eq!(map_key(key(KeyCode::Char('l')), InputMode::BreadcrumbMenu, &c), Action::BreadcrumbMenuChild);
+    assert_eq!(map_key(key(KeyCode::Enter), InputMode::BreadcrumbMenu, &c), Action::BreadcrumbMenuSelect);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::BreadcrumbMenu, &c), Action::BreadcrumbMenuClose);
+  }
+
+  #[test]
+  fn test_settings_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('j')), InputMode::Settings, &c), Action::SettingsDown);
+    assert_eq!(map_key(key(KeyCode::Char(' ')), InputMode::Settings, &c), Action::SettingsChange(true));
+    assert_eq!(map_key(key(KeyCode::Left), InputMode::Settings, &c), Action::SettingsChange(false));
+    assert_eq!(map_key(key(KeyCode::Enter), InputMode::Settings, &c), Action::SettingsSave);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Settings, &c), Action::SettingsClose);
+  }
+
+  #[test]
+  fn test_paste_options_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('l')), InputMode::PasteOptions, &c), Action::PasteOptionsToggle(0));
+    assert_eq!(map_key(key(KeyCode::Char('x')), InputMode::PasteOptions, &c), Action::PasteOptionsToggle(3));
+    assert_eq!(map_key(key(KeyCode::Enter), InputMode::PasteOptions, &c), Action::PasteOptionsConfirm);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::PasteOptions, &c), Action::PasteOptionsClose);
+    assert_eq!(map_key(key(KeyCode::Char('s')), InputMode::PasteConflict, &c), Action::PasteConflictChoose(Collision::Skip));
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::PasteConflict, &c), Action::PasteConflictCancel);
+  }
+
+  #[test]
+  fn test_jobs_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('t')), InputMode::Normal, &c), Action::JobsOpen);
+    assert_eq!(map_key(key(KeyCode::Char('k')), InputMode::Jobs, &c), Action::JobsUp);
+    assert_eq!(map_key(key(KeyCode::Delete), InputMode::Jobs, &c), Action::JobsCancel);
+    assert_eq!(map_key(key(KeyCode::Char('t')), InputMode::Jobs, &c), Action::JobsClose);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Jobs, &c), Action::JobsClose);
+  }
+
+  #[test]
+  fn test_layouts_mode_keys() {
+    let c = cfg();
+    assert_eq!(map_key(key(KeyCode::Char('b')), InputMode::Normal, &c), Action::LayoutsOpen);
+    assert_eq!(map_key(key(KeyCode::Char('j')), InputMode::Layouts, &c), Action::LayoutsDown);
+    assert_eq!(map_key(key(KeyCode::Enter), InputMode::Layouts, &c), Action::LayoutsSelect);
+    assert_eq!(map_key(key(KeyCode::Char('a')), InputMode::Layouts, &c), Action::LayoutSaveStart);
+    assert_eq!(map_key(key(KeyCode::Char('d')), InputMode::Layouts, &c), Action::LayoutsRemove);
+    assert_eq!(map_key(key(KeyCode::Esc), InputMode::Layouts, &c), Action::LayoutsClose);
+  }
+
   // === Error mode tests ===
 
   #[test]
@@ -651,6 +1297,9 @@ mod tests {
     // Click on second segment
     let action = map_breadcrumb_click(8, &segments);
     assert_eq!(action, Some(Action::BreadcrumbSelect(1)));
+
+    // A right click opens that segment's dropdown instead
+    assert_eq!(map_breadcrumb_menu_click(8, &segments), Some(Action::BreadcrumbMenuAt(1)));
   }
 
   #[test]
@@ -664,4 +1313,26 @@ mod tests {
     let action = map_breadcrumb_click(6, &segments);
     assert_eq!(action, None);
   }
+
+  #[test]
+  fn test_tree_changes_reload_once_quiet_or_after_max_wait() {
+    let start = Instant::now();
+    let mut debounce = TreeDebounce::default();
+    assert!(debounce.take_due(start).is_none());
+    debounce.note(start, &[PathBuf::from("/r/a")]);
+    debounce.note(start + Duration::from_millis(100), &[PathBuf::from("/r/b"), PathBuf::from("/r/a")]);
+    assert!(debounce.take_due(start + Duration::from_millis(200)).is_none());
+    assert_eq!(debounce.take_due(start + Duration::from_millis(250)), Some(vec![PathBuf::from("/r/a"), PathBuf::from("/r/b")]));
+    assert!(debounce.take_due(start + Duration::from_millis(400)).is_none());
+
+    // A build writing every 100ms still reloads once a second
+    let mut at = start;
+    let mut reloads = 0;
+    while at < start + Duration::from_millis(2500) {
+      debounce.note(at, &[]);
+      at += Duration::from_millis(100);
+      reloads += usize::from(debounce.take_due(at).is_some());
+    }
+    assert_eq!(reloads, 2);
+  }
 }
@@ -1,10 +1,65 @@
+use std::fs::File;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 
+use crate::{paths, persist};
+
+/// Favorite directories, stored as an append-only log of `+ path` and
+/// `- path` lines so concurrent instances merge rather than overwrite each
+/// other. Plain path lines from older versions count as additions. Access
+/// goes through an advisory lock on a sibling `.lock` file.
 pub struct Favorites {
   path: PathBuf,
   entries: Vec<PathBuf>,
+  /// Changes made since the last save
+  pending: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+  Add(PathBuf),
+  Remove(PathBuf),
+}
+
+impl Change {
+  fn parse(line: &str) -> Option<Change> {
+    if let Some(path) = line.strip_prefix("+ ") {
+      Some(Change::Add(PathBuf::from(path)))
+    } else if let Some(path) = line.strip_prefix("- ") {
+      Some(Change::Remove(PathBuf::from(path)))
+    } else if line.is_empty() {
+      None
+    } else {
+      Some(Change::Add(PathBuf::from(line)))
+    }
+  }
+
+  fn line(&self) -> String {
+    match self {
+      Change::Add(path) => format!("+ {}\n", path.to_string_lossy()),
+      Change::Remove(path) => format!("- {}\n", path.to_string_lossy()),
+    }
+  }
+}
+
+/// Replay a log; returns the entries and how many lines it took.
+fn replay(content: &str) -> (Vec<PathBuf>, usize) {
+  let mut entries: Vec<PathBuf> = Vec::new();
+  let mut lines = 0;
+  for change in content.lines().filter_map(Change::parse) {
+    lines += 1;
+    match change {
+      Change::Add(path) => {
+        if !entries.contains(&path) {
+          entries.push(path);
+        }
+      }
+      Change::Remove(path) => entries.retain(|p| *p != path),
+    }
+  }
+  (entries, lines)
 }
 
 impl Favorites {
@@ -14,41 +69,61 @@ impl Favorites {
 
   pub fn load_from(path: PathBuf) -> Self {
     let entries = if path.exists() {
-      std::fs::read_to_string(&path)
-        .unwrap_or_default()
-        .lines()
-        .filter(|l| !l.is_empty())
-        .map(PathBuf::from)
-        .collect()
+      let lock = Self::lock_file(&path).ok();
+      if let Some(ref lock) = lock {
+        let _ = lock.lock_shared();
+      }
+      replay(&std::fs::read_to_string(&path).unwrap_or_default()).0
     } else {
       Vec::new()
     };
-    Self { path, entries }
+    Self { path, entries, pending: Vec::new() }
   }
 
-  pub fn save(&self) -> Result<()> {
+  fn lock_file(path: &Path) -> Result<File> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    Ok(File::options().create(true).truncate(false).write(true).open(path.with_file_name(name))?)
+  }
+
+  /// Append the pending changes and merge in whatever other instances
+  /// saved meanwhile. The log is compacted once it is mostly history.
+  pub fn save(&mut self) -> Result<()> {
     if let Some(parent) = self.path.parent() {
       std::fs::create_dir_all(parent)?;
     }
-    let content: String = self
-      .entries
-      .iter()
-      .map(|p| p.to_string_lossy().to_string())
-      .collect::<Vec<_>>()
-      .join("\n");
-    std::fs::write(&self.path, content)?;
+    let lock = Self::lock_file(&self.path)?;
+    lock.lock()?;
+    let existing = std::fs::read_to_string(&self.path).unwrap_or_default();
+    let mut appended: String = self.pending.iter().map(Change::line).collect();
+    // Older versions didn't end the file with a newline
+    if !existing.is_empty() && !existing.ends_with('\n') {
+      appended.insert(0, '\n');
+    }
+    let mut file = File::options().create(true).append(true).open(&self.path)?;
+    file.write_all(appended.as_bytes())?;
+    self.pending.clear();
+
+    let (entries, lines) = replay(&std::fs::read_to_string(&self.path)?);
+    self.entries = entries;
+    if lines > 2 * self.entries.len() + 16 {
+      let compacted: String = self.entries.iter().map(|p| Change::Add(p.clone()).line()).collect();
+      persist::write_atomic(&self.path, compacted)?;
+    }
     Ok(())
   }
 
   pub fn add(&mut self, path: PathBuf) {
     if !self.entries.contains(&path) {
-      self.entries.push(path);
+      self.entries.push(path.clone());
+      self.pending.push(Change::Add(path));
     }
   }
 
   pub fn remove(&mut self, index: usize) {
     if index < self.entries.len() {
-      self.entries.remove(index);
+      let path = self.entries.remove(index);
+      self.pending.push(Change::Remove(path));
     }
   }
 
@@ -56,6 +131,11 @@ impl Favorites {
     self.entries.get(index).map(|p| p.as_path())
   }
 
+  /// The file the favorites are stored in.
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
   pub fn list(&self) -> &[PathBuf] {
     &self.entries
   }
@@ -64,15 +144,16 @@ impl Favorites {
     self.entries.len()
   }
 
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
   pub fn contains(&self, path: &Path) -> bool {
     self.entries.iter().any(|p| p == path)
   }
 
   fn favorites_path() -> PathBuf {
-    dirs::config_dir()
-      .unwrap_or_else(|| PathBuf::from("."))
-      .join("tfl")
-      .join("favorites")
+    paths::state_dir().join("favorites")
   }
 }
 
@@ -83,6 +164,11 @@ mod tests {
 
   static COUNTER: AtomicU32 = AtomicU32::new(0);
 
+  fn cleanup(path: &Path) {
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(path.with_file_name(format!("{}.lock", path.file_name().unwrap().to_string_lossy())));
+  }
+
   fn temp_path() -> PathBuf {
     let id = COUNTER.fetch_add(1, Ordering::SeqCst);
     std::env::temp_dir().join(format!("tfl_favorites_test_{id}_{}", std::process::id()))
@@ -158,6 +244,39 @@ mod tests {
     let _ = std::fs::remove_file(&path);
   }
 
+  #[test]
+  fn test_concurrent_saves_merge() {
+    let path = temp_path();
+    let mut a = Favorites::load_from(path.clone());
+    let mut b = Favorites::load_from(path.clone());
+    a.add(PathBuf::from("/a"));
+    a.save().unwrap();
+    b.add(PathBuf::from("/b"));
+    b.save().unwrap();
+    assert_eq!(b.list(), &[PathBuf::from("/a"), PathBuf::from("/b")]);
+
+    a.remove(0);
+    a.save().unwrap();
+    assert_eq!(a.list(), &[PathBuf::from("/b")]);
+    assert_eq!(Favorites::load_from(path.clone()).list(), &[PathBuf::from("/b")]);
+    cleanup(&path);
+  }
+
+  #[test]
+  fn test_legacy_format_and_compaction() {
+    let path = temp_path();
+    std::fs::write(&path, "/old\n/older").unwrap();
+    let mut favs = Favorites::load_from(path.clone());
+    assert_eq!(favs.list(), &[PathBuf::from("/old"), PathBuf::from("/older")]);
+    for _ in 0..20 {
+      favs.add(PathBuf::from("/tmp"));
+      favs.remove(2);
+    }
+    favs.save().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "+ /old\n+ /older\n");
+    cleanup(&path);
+  }
+
   #[test]
   fn test_get_by_index() {
     let path = temp_path();
@@ -1,8 +0,0 @@
-pub mod entry;
-pub mod ops;
-pub mod properties;
-pub mod tree;
-
-pub use entry::{FileEntry, GitFileStatus, GitStatus};
-pub use properties::FileProperties;
-pub use tree::FileTree;
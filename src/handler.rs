@@ -0,0 +1,163 @@
+//! `--install-handler` / `--uninstall-handler`: registering tfl as the
+//! default `inode/directory` handler through xdg-mime.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::paths;
+use crate::process::{ProcessRunner, ProcessSpec};
+
+/// Files the handler install reads and writes.
+pub struct HandlerPaths {
+  /// Binary the desktop entry launches
+  pub tfl_path: PathBuf,
+  /// Where the previous handler is remembered for uninstall
+  pub backup_dir: PathBuf,
+  /// `applications` dir the desktop entry goes into
+  pub apps_dir: PathBuf,
+}
+
+impl HandlerPaths {
+  pub fn system() -> Result<Self> {
+    Ok(Self {
+      tfl_path: std::env::current_exe()?,
+      backup_dir: paths::state_dir().join("handler-backup"),
+      apps_dir: dirs::data_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine data directory"))?
+        .join("applications"),
+    })
+  }
+}
+
+fn desktop_entry(tfl_path: &Path) -> String {
+  format!(
+    "[Desktop Entry]\n\
+     Type=Application\n\
+     Name=tfl\n\
+     GenericName=File Manager\n\
+     Comment=Terminal file explorer with vim-style navigation\n\
+     Exec={} %f\n\
+     Icon=system-file-manager\n\
+     Terminal=true\n\
+     Categories=System;FileManager;ConsoleOnly;\n\
+     MimeType=inode/directory;\n",
+    tfl_path.display()
+  )
+}
+
+fn set_default(runner: &dyn ProcessRunner, desktop_file: &str) -> Result<()> {
+  if !runner.run(&ProcessSpec::new("xdg-mime").args(["default", desktop_file, "inode/directory"]))? {
+    anyhow::bail!("xdg-mime default failed");
+  }
+  Ok(())
+}
+
+pub fn install(runner: &dyn ProcessRunner, paths: &HandlerPaths) -> Result<()> {
+  std::fs::create_dir_all(&paths.backup_dir)?;
+
+  // Query current default handler
+  let (_, output) = runner.output(&ProcessSpec::new("xdg-mime").args(["query", "default", "inode/directory"]))?;
+  let current = output.trim().to_string();
+
+  if !current.is_empty() && current != "tfl.desktop" {
+    let backup_file = paths.backup_dir.join("mime-handler");
+    std::fs::write(&backup_file, &current)?;
+    println!("Backed up current handler: {current}");
+  }
+
+  // Write desktop file with resolved path
+  std::fs::create_dir_all(&paths.apps_dir)?;
+  let desktop_path = paths.apps_dir.join("tfl.desktop");
+  std::fs::write(&desktop_path, desktop_entry(&paths.tfl_path))?;
+  println!("Installed: {}", desktop_path.display());
+
+  set_default(runner, "tfl.desktop")?;
+  println!("Set tfl as default file manager for inode/directory");
+
+  Ok(())
+}
+
+pub fn uninstall(runner: &dyn ProcessRunner, paths: &HandlerPaths) -> Result<()> {
+  let backup_file = paths.backup_dir.join("mime-handler");
+
+  // Restore previous handler
+  if backup_file.exists() {
+    let old_handler = std::fs::read_to_string(&backup_file)?.trim().to_string();
+    if !old_handler.is_empty() {
+      set_default(runner, &old_handler)?;
+      println!("Restored default handler: {old_handler}");
+    }
+    std::fs::remove_file(&backup_file)?;
+  } else {
+    println!("No backup found — nothing to restore");
+  }
+
+  // Remove desktop file
+  let desktop_path = paths.apps_dir.join("tfl.desktop");
+  if desktop_path.exists() {
+    std::fs::remove_file(&desktop_path)?;
+    println!("Removed: {}", desktop_path.display());
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::process::MockRunner;
+
+  fn temp_paths(name: &str) -> HandlerPaths {
+    let dir = std::env::temp_dir().join(format!("tfl_handler_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    HandlerPaths { tfl_path: PathBuf::from("/opt/tfl/bin/tfl"), backup_dir: dir.join("backup"), apps_dir: dir.join("applications") }
+  }
+
+  #[test]
+  fn test_install_then_uninstall_restores_previous_handler() {
+    let paths = temp_paths("roundtrip");
+    let mut runner = MockRunner::default();
+    runner.outputs.insert("xdg-mime".into(), "org.gnome.Nautilus.desktop\n".into());
+
+    install(&runner, &paths).unwrap();
+    assert_eq!(std::fs::read_to_string(paths.backup_dir.join("mime-handler")).unwrap(), "org.gnome.Nautilus.desktop");
+    let entry = std::fs::read_to_string(paths.apps_dir.join("tfl.desktop")).unwrap();
+    assert!(entry.contains("Exec=/opt/tfl/bin/tfl %f"));
+
+    uninstall(&runner, &paths).unwrap();
+    assert!(!paths.backup_dir.join("mime-handler").exists());
+    assert!(!paths.apps_dir.join("tfl.desktop").exists());
+    assert_eq!(
+      runner.commands(),
+      vec![
+        "xdg-mime query default inode/directory",
+        "xdg-mime default tfl.desktop inode/directory",
+        "xdg-mime default org.gnome.Nautilus.desktop inode/directory",
+      ]
+    );
+    let _ = std::fs::remove_dir_all(paths.backup_dir.parent().unwrap());
+  }
+
+  #[test]
+  fn test_reinstall_keeps_original_backup() {
+    let paths = temp_paths("reinstall");
+    let mut runner = MockRunner::default();
+    runner.outputs.insert("xdg-mime".into(), "thunar.desktop\n".into());
+    install(&runner, &paths).unwrap();
+    // tfl is already the default the second time round
+    runner.outputs.insert("xdg-mime".into(), "tfl.desktop\n".into());
+    install(&runner, &paths).unwrap();
+    assert_eq!(std::fs::read_to_string(paths.backup_dir.join("mime-handler")).unwrap(), "thunar.desktop");
+    let _ = std::fs::remove_dir_all(paths.backup_dir.parent().unwrap());
+  }
+
+  #[test]
+  fn test_install_reports_xdg_mime_failure() {
+    let paths = temp_paths("failure");
+    let runner = MockRunner { failing: vec!["xdg-mime".into()], ..MockRunner::default() };
+    let err = install(&runner, &paths).unwrap_err();
+    assert_eq!(err.to_string(), "xdg-mime default failed");
+    let _ = std::fs::remove_dir_all(paths.backup_dir.parent().unwrap());
+  }
+}
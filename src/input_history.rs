@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::event::PromptKind;
+use crate::{paths, persist};
+
+/// Which text input an entry was typed into. Each keeps its own list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+  Search,
+  Rename,
+  NewFile,
+  NewDir,
+  /// Directories picked for copy/move to
+  Destination,
+}
+
+impl HistoryKind {
+  pub fn of_prompt(kind: PromptKind) -> Option<Self> {
+    match kind {
+      PromptKind::Rename => Some(HistoryKind::Rename),
+      PromptKind::NewFile => Some(HistoryKind::NewFile),
+      PromptKind::NewDir => Some(HistoryKind::NewDir),
+      _ => None,
+    }
+  }
+}
+
+type Entries = HashMap<HistoryKind, Vec<String>>;
+
+/// Where Up/Down currently is in one kind's list.
+struct Browse {
+  kind: HistoryKind,
+  /// 0 is the newest entry
+  index: usize,
+  /// What was typed before browsing started, given back past the newest
+  draft: String,
+}
+
+/// Filter queries and prompt names from earlier, recalled with Up/Down and
+/// kept in `history.json` in the state dir. Lists are oldest first and hold
+/// at most `limit` entries; a limit of 0 turns history off.
+pub struct InputHistory {
+  limit: usize,
+  entries: Entries,
+  /// Recorded since the last save, replayed onto the file so instances
+  /// saving one after another don't drop each other's entries
+  added: Vec<(HistoryKind, String)>,
+  browse: Option<Browse>,
+}
+
+/// Append `text` to its list, moving an earlier copy to the end.
+fn push(entries: &mut Entries, kind: HistoryKind, text: &str, limit: usize) {
+  let list = entries.entry(kind).or_default();
+  list.retain(|e| e != text);
+  list.push(text.to_string());
+  let excess = list.len().saturating_sub(limit);
+  list.drain(..excess);
+}
+
+fn read(path: &Path) -> Entries {
+  std::fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+impl InputHistory {
+  pub fn new(limit: usize) -> Self {
+    Self { limit, entries: Entries::new(), added: Vec::new(), browse: None }
+  }
+
+  pub fn load(limit: usize) -> Self {
+    Self::load_from(&Self::default_path(), limit)
+  }
+
+  pub fn load_from(path: &Path, limit: usize) -> Self {
+    let mut history = Self::new(limit);
+    history.entries = read(path);
+    history.set_limit(limit);
+    history
+  }
+
+  pub fn default_path() -> PathBuf {
+    paths::state_dir().join("history.json")
+  }
+
+  pub fn save(&mut self) -> Result<()> {
+    self.save_to(&Self::default_path())
+  }
+
+  /// Merge this session's entries into the file at `path`.
+  pub fn save_to(&mut self, path: &Path) -> Result<()> {
+    if self.added.is_empty() {
+      return Ok(());
+    }
+    let mut entries = read(path);
+    for (kind, text) in self.added.drain(..) {
+      push(&mut entries, kind, &text, self.limit);
+    }
+    persist::write_atomic(path, serde_json::to_string(&entries)?)?;
+    self.entries = entries;
+    Ok(())
+  }
+
+  pub fn set_limit(&mut self, limit: usize) {
+    self.limit = limit;
+    for list in self.entries.values_mut() {
+      let excess = list.len().saturating_sub(limit);
+      list.drain(..excess);
+    }
+  }
+
+  pub fn entries(&self, kind: HistoryKind) -> &[String] {
+    self.entries.get(&kind).map_or(&[], Vec::as_slice)
+  }
+
+  /// Remember a confirmed query or name. Blank text isn't kept.
+  pub fn record(&mut self, kind: HistoryKind, text: &str) {
+    self.browse = None;
+    let text = text.trim();
+    if self.limit == 0 || text.is_empty() {
+      return;
+    }
+    push(&mut self.entries, kind, text, self.limit);
+    self.added.push((kind, text.to_string()));
+  }
+
+  /// Forget the browse position, for when a new prompt opens.
+  pub fn reset(&mut self) {
+    self.browse = None;
+  }
+
+  /// The entry before the one shown (Up). `current` is what's typed now,
+  /// kept to come back to.
+  pub fn older(&mut self, kind: HistoryKind, current: &str) -> Option<String> {
+    let list = self.entries.get(&kind)?;
+    let index = match self.browse {
+      Some(ref b) if b.kind == kind => b.index + 1,
+      _ => 0,
+    };
+    let entry = list.iter().rev().nth(index)?.clone();
+    match self.browse {
+      Some(ref mut b) if b.kind == kind => b.index = index,
+      _ => self.browse = Some(Browse { kind, index, draft: current.to_string() }),
+    }
+    Some(entry)
+  }
+
+  /// The entry after the one shown (Down), or the draft past the newest.
+  pub fn newer(&mut self, kind: HistoryKind) -> Option<String> {
+    let browse = self.browse.take_if(|b| b.kind == kind)?;
+    if browse.index == 0 {
+      return Some(browse.draft);
+    }
+    let index = browse.index - 1;
+    let entry = self.entries(kind).iter().rev().nth(index).cloned();
+    self.browse = Some(Browse { index, ..browse });
+    entry
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+  fn temp_file() -> PathBuf {
+    let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+    let dir = std::env::temp_dir().join(format!("tfl_history_{}_{n}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir.join("history.json")
+  }
+
+  #[test]
+  fn test_record_dedupes_and_caps() {
+    let mut history = InputHistory::new(3);
+    for q in ["a", "b", "a", "c", "d", "  "] {
+      history.record(HistoryKind::Search, q);
+    }
+    assert_eq!(history.entries(HistoryKind::Search), ["a", "c", "d"]);
+    assert!(history.entries(HistoryKind::Rename).is_empty());
+
+    let mut off = InputHistory::new(0);
+    off.record(HistoryKind::Search, "a");
+    assert!(off.entries(HistoryKind::Search).is_empty());
+  }
+
+  #[test]
+  fn test_browse_up_and_back_down_to_draft() {
+    let mut history = InputHistory::new(10);
+    history.record(HistoryKind::Rename, "one.txt");
+    history.record(HistoryKind::Rename, "two.txt");
+    assert_eq!(history.older(HistoryKind::Rename, "draft").as_deref(), Some("two.txt"));
+    assert_eq!(history.older(HistoryKind::Rename, "two.txt").as_deref(), Some("one.txt"));
+    // Stays on the oldest
+    assert_eq!(history.older(HistoryKind::Rename, "one.txt"), None);
+    assert_eq!(history.newer(HistoryKind::Rename).as_deref(), Some("two.txt"));
+    assert_eq!(history.newer(HistoryKind::Rename).as_deref(), Some("draft"));
+    assert_eq!(history.newer(HistoryKind::Rename), None);
+    // Other kinds have their own lists
+    assert_eq!(history.older(HistoryKind::NewDir, ""), None);
+  }
+
+  #[test]
+  fn test_save_merges_with_other_instances() {
+    let path = temp_file();
+    let mut first = InputHistory::load_from(&path, 10);
+    let mut second = InputHistory::load_from(&path, 10);
+    first.record(HistoryKind::Search, "foo");
+    second.record(HistoryKind::Search, "bar");
+    second.record(HistoryKind::NewFile, "notes.md");
+    first.save_to(&path).unwrap();
+    second.save_to(&path).unwrap();
+
+    let reloaded = InputHistory::load_from(&path, 10);
+    assert_eq!(reloaded.entries(HistoryKind::Search), ["foo", "bar"]);
+    assert_eq!(reloaded.entries(HistoryKind::NewFile), ["notes.md"]);
+    assert_eq!(InputHistory::load_from(&path, 1).entries(HistoryKind::Search), ["bar"]);
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+  }
+}
@@ -0,0 +1,593 @@
+//! Background work: extractions, compressions and large copies wait in a
+//! queue and at most `max_jobs` of them run at once, each on its own thread
+//! reporting its progress.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+
+use crate::fs::names;
+use crate::fs::ops::{self, Collision, CopyOptions};
+use crate::fs::progress::{Counts, Progress};
+use crate::preview::archive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobKind {
+  /// Unpack `path` next to itself
+  Extract { path: PathBuf, delete_after: bool },
+  /// Unpack the file or directory `entry` of `archive` next to the archive
+  ExtractEntry { archive: PathBuf, entry: String },
+  /// Pack `targets` into the archive at `dest`
+  Compress { targets: Vec<PathBuf>, dest: PathBuf, format: String },
+  /// Copy, or with `cut` move, `sources` into `target_dir`; `collisions`
+  /// says what to do with sources whose names are taken there
+  Transfer {
+    sources: Vec<PathBuf>,
+    target_dir: PathBuf,
+    cut: bool,
+    options: CopyOptions,
+    collisions: HashMap<PathBuf, Collision>,
+    /// Entries overwritten go to the trash
+    use_trash: bool,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+  pub id: u64,
+  pub kind: JobKind,
+}
+
+impl Job {
+  /// File name of the archive read or written, of the entry extracted, or
+  /// of what is copied.
+  pub fn name(&self) -> String {
+    let path = match &self.kind {
+      JobKind::Extract { path, .. } => path,
+      JobKind::ExtractEntry { entry, .. } => Path::new(entry),
+      JobKind::Compress { dest, .. } => dest,
+      JobKind::Transfer { sources, .. } => match sources.as_slice() {
+        [one] => one,
+        many => return format!("{} items", many.len()),
+      },
+    };
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+  }
+
+  pub fn verb(&self) -> &'static str {
+    match self.kind {
+      JobKind::Extract { .. } | JobKind::ExtractEntry { .. } => "extract",
+      JobKind::Compress { .. } => "compress",
+      JobKind::Transfer { cut: false, .. } => "copy",
+      JobKind::Transfer { cut: true, .. } => "move",
+    }
+  }
+
+  /// Whether it stops when cancelled while running.
+  pub fn cancellable(&self) -> bool {
+    matches!(self.kind, JobKind::Extract { .. } | JobKind::Transfer { .. })
+  }
+
+  fn run(&self, progress: &Progress) -> (Result<(), String>, Transferred) {
+    let result = match &self.kind {
+      JobKind::Extract { path, .. } => {
+        let dest_dir = path.parent().unwrap_or(Path::new("."));
+        archive::extract_archive_tracked(path, dest_dir, progress)
+      }
+      JobKind::ExtractEntry { archive, entry } => {
+        let dest_dir = archive.parent().unwrap_or(Path::new("."));
+        archive::extract_archive_entry(archive, entry, dest_dir).map(|_| ())
+      }
+      JobKind::Compress { targets, dest, format } => archive::compress_to_archive(targets, dest, format),
+      JobKind::Transfer { sources, target_dir, cut, options, collisions, use_trash } => {
+        progress.set_total(crate::fs::progress::measure(sources, progress));
+        let transferred = transfer(sources, target_dir, *cut, options, collisions, *use_trash, progress);
+        let result = match transferred.error {
+          Some((_, ref e)) => Err(e.to_string()),
+          None => Ok(()),
+        };
+        return (result, transferred);
+      }
+    };
+    (result, Transferred::default())
+  }
+}
+
+/// What a copy or move did, up to where it stopped.
+#[derive(Debug, Default)]
+pub struct Transferred {
+  /// Each source and where it went
+  pub pairs: Vec<(PathBuf, PathBuf)>,
+  /// Symlink cycles skipped while following links
+  pub cycles: usize,
+  /// Sources that were gone when their turn came
+  pub missing: Vec<PathBuf>,
+  /// Sources left out because their names were taken
+  pub skipped: usize,
+  /// Destinations that held an entry the source replaced
+  pub replaced: Vec<PathBuf>,
+  /// What stopped it, with the source it was on
+  pub error: Option<(PathBuf, io::Error)>,
+  /// Where the last source went, or already was
+  pub last_dest: Option<PathBuf>,
+}
+
+/// Where `source` goes in `target_dir` when its name is free.
+/// `sanitize` gives it a form FAT and NTFS accept.
+pub fn pasted_path(source: &Path, target_dir: &Path, sanitize: bool) -> PathBuf {
+  let file_name = source.file_name().unwrap_or_default();
+  if sanitize {
+    target_dir.join(names::sanitize_name(&file_name.to_string_lossy()))
+  } else {
+    target_dir.join(file_name)
+  }
+}
+
+/// Whether `source` pasted as `dest` would run into something else there
+/// that it could replace. Pasting onto itself, an ancestor or a descendant
+/// always gets a new name instead.
+fn collides(source: &Path, dest: &Path) -> bool {
+  dest.symlink_metadata().is_ok() && !source.starts_with(dest) && !dest.starts_with(source)
+}
+
+/// The sources whose names are already taken in `target_dir`, in order.
+pub fn collisions(sources: &[PathBuf], target_dir: &Path, sanitize: bool) -> Vec<PathBuf> {
+  sources.iter().filter(|s| collides(s, &pasted_path(s, target_dir, sanitize))).cloned().collect()
+}
+
+/// Remove a file, symlink or whole directory.
+fn remove_entry(path: &Path) -> io::Result<()> {
+  if path.symlink_metadata()?.is_dir() { std::fs::remove_dir_all(path) } else { std::fs::remove_file(path) }
+}
+
+/// Copy or move `sources` into `target_dir`, stopping at the first failure.
+/// Moves rename when they can and otherwise copy with `options` and delete
+/// the original. `options.sanitize` gives each pasted name a form FAT and
+/// NTFS accept. A source whose name is taken is renamed, skipped or
+/// overwrites as `collisions` says, renamed when it isn't listed. A
+/// directory overwriting a directory is merged into it; what it replaces
+/// goes to the trash with `use_trash`. A copy stopped halfway is removed.
+pub fn transfer(
+  sources: &[PathBuf],
+  target_dir: &Path,
+  cut: bool,
+  options: &CopyOptions,
+  collisions: &HashMap<PathBuf, Collision>,
+  use_trash: bool,
+  progress: &Progress,
+) -> Transferred {
+  let mut done = Transferred::default();
+  for source in sources {
+    if !source.exists() {
+      done.missing.push(source.clone());
+      continue;
+    }
+
+    let raw_dest = pasted_path(source, target_dir, options.sanitize);
+
+    // Cut to same location is a no-op
+    if cut && raw_dest == *source {
+      done.last_dest = Some(raw_dest);
+      continue;
+    }
+
+    let collision = if collides(source, &raw_dest) {
+      collisions.get(source).copied().unwrap_or_default()
+    } else {
+      Collision::Rename
+    };
+    let dest = match collision {
+      Collision::Rename => ops::unique_dest_path(&raw_dest),
+      Collision::Skip => {
+        done.skipped += 1;
+        continue;
+      }
+      Collision::Overwrite if is_dir(source) && is_dir(&raw_dest) => {
+        // Each entry of the directory overwrites its namesake in the other
+        let children = match children(source) {
+          Ok(children) => children,
+          Err(e) => {
+            done.error = Some((source.clone(), e));
+            return done;
+          }
+        };
+        let choices = children.iter().map(|child| (child.clone(), Collision::Overwrite)).collect();
+        let merged = transfer(&children, &raw_dest, cut, options, &choices, use_trash, progress);
+        done.pairs.extend(merged.pairs);
+        done.cycles += merged.cycles;
+        done.missing.extend(merged.missing);
+        done.skipped += merged.skipped;
+        done.replaced.extend(merged.replaced);
+        if merged.error.is_some() {
+          done.error = merged.error;
+          return done;
+        }
+        if cut {
+          let _ = std::fs::remove_dir(source);
+        }
+        done.last_dest = Some(raw_dest);
+        continue;
+      }
+      Collision::Overwrite => {
+        match overwrite(source, &raw_dest, cut, options, use_trash, progress) {
+          Ok(cycles) => done.cycles += cycles,
+          Err(e) => {
+            done.error = Some((source.clone(), e));
+            return done;
+          }
+        }
+        done.replaced.push(raw_dest.clone());
+        done.pairs.push((source.clone(), raw_dest.clone()));
+        done.last_dest = Some(raw_dest);
+        continue;
+      }
+    };
+    // Try rename first (same filesystem), fallback to copy+delete
+    if !(cut && std::fs::rename(source, &dest).is_ok()) {
+      match ops::copy_path_tracked(source, &dest, options, progress) {
+        Ok(skipped) => done.cycles += skipped,
+        Err(e) => {
+          if e.kind() == io::ErrorKind::Interrupted {
+            let _ = remove_entry(&dest);
+          }
+          done.error = Some((source.clone(), e));
+          return done;
+        }
+      }
+      if cut {
+        let _ = remove_entry(source);
+      }
+    }
+    done.pairs.push((source.clone(), dest.clone()));
+    done.last_dest = Some(dest);
+  }
+  done
+}
+
+/// Put `source` in place of `dest`. It is copied (or moved) to a temporary
+/// name beside `dest` first, so a failed copy leaves `dest` as it was.
+fn overwrite(
+  source: &Path,
+  dest: &Path,
+  cut: bool,
+  options: &CopyOptions,
+  use_trash: bool,
+  progress: &Progress,
+) -> io::Result<usize> {
+  let name = dest.file_name().unwrap_or_default().to_string_lossy();
+  let part = ops::unique_dest_path(&dest.with_file_name(format!(".{name}.tfl-part")));
+  let moved = cut && std::fs::rename(source, &part).is_ok();
+  let cycles = if moved {
+    0
+  } else {
+    ops::copy_path_tracked(source, &part, options, progress).inspect_err(|_| {
+      let _ = remove_entry(&part);
+    })?
+  };
+  if let Err(e) = replace(&part, dest, use_trash) {
+    if moved {
+      let _ = std::fs::rename(&part, source);
+    } else {
+      let _ = remove_entry(&part);
+    }
+    return Err(e);
+  }
+  if cut && !moved {
+    let _ = remove_entry(source);
+  }
+  Ok(cycles)
+}
+
+/// Rename `part` over `dest`, trashing or removing what was there first
+/// unless a rename can replace it in one step (a file over a file).
+fn replace(part: &Path, dest: &Path, use_trash: bool) -> io::Result<()> {
+  if use_trash {
+    trash::delete(dest).map_err(io::Error::other)?;
+  } else if is_dir(dest) || is_dir(part) {
+    remove_entry(dest)?;
+  }
+  std::fs::rename(part, dest)
+}
+
+/// A directory itself, not a link to one.
+fn is_dir(path: &Path) -> bool {
+  path.symlink_metadata().is_ok_and(|m| m.is_dir())
+}
+
+fn children(dir: &Path) -> io::Result<Vec<PathBuf>> {
+  let mut children = std::fs::read_dir(dir)?.map(|entry| entry.map(|e| e.path())).collect::<io::Result<Vec<_>>>()?;
+  children.sort();
+  Ok(children)
+}
+
+/// A job that stopped running, successfully or not.
+pub struct Finished {
+  pub job: Job,
+  pub result: Result<(), String>,
+  /// What a copy or move got done; empty for other jobs
+  pub transferred: Transferred,
+  /// It stopped because it was cancelled
+  pub cancelled: bool,
+}
+
+struct Running {
+  job: Job,
+  progress: Arc<Progress>,
+  rx: mpsc::Receiver<(Result<(), String>, Transferred)>,
+}
+
+pub struct Jobs {
+  /// How many jobs may run at once (at least one)
+  max: usize,
+  next_id: u64,
+  running: Vec<Running>,
+  queued: VecDeque<Job>,
+  /// Progress of the running jobs when last shown
+  shown: Counts,
+}
+
+impl Jobs {
+  pub fn new(max: usize) -> Self {
+    Self { max: max.max(1), next_id: 0, running: Vec::new(), queued: VecDeque::new(), shown: Counts::default() }
+  }
+
+  /// Change the concurrency limit. Raising it starts queued jobs right away;
+  /// lowering it lets running jobs finish.
+  pub fn set_limit(&mut self, max: usize) {
+    self.max = max.max(1);
+    self.start_queued();
+  }
+
+  /// Start `kind` if a slot is free, otherwise queue it. Returns the queue
+  /// position (1 is next) when it had to wait.
+  pub fn push(&mut self, kind: JobKind) -> Option<usize> {
+    self.next_id += 1;
+    self.queued.push_back(Job { id: self.next_id, kind });
+    self.start_queued();
+    self.queued.iter().position(|j| j.id == self.next_id).map(|i| i + 1)
+  }
+
+  fn start_queued(&mut self) {
+    while self.running.len() < self.max
+      && let Some(job) = self.queued.pop_front()
+    {
+      let (tx, rx) = mpsc::channel();
+      let work = job.clone();
+      let progress = Arc::new(Progress::default());
+      let reporter = Arc::clone(&progress);
+      std::thread::spawn(move || {
+        let _ = tx.send(work.run(&reporter));
+      });
+      self.running.push(Running { job, progress, rx });
+    }
+  }
+
+  /// Collect jobs that finished since the last poll and start queued ones
+  /// in their place.
+  pub fn poll(&mut self) -> Vec<Finished> {
+    let mut finished = Vec::new();
+    let mut i = 0;
+    while i < self.running.len() {
+      let (result, transferred) = match self.running[i].rx.try_recv() {
+        Ok(done) => done,
+        Err(mpsc::TryRecvError::Empty) => {
+          i += 1;
+          continue;
+        }
+        Err(mpsc::TryRecvError::Disconnected) => (Err("thread died".to_string()), Transferred::default()),
+      };
+      let Running { job, progress, .. } = self.running.remove(i);
+      finished.push(Finished { job, result, transferred, cancelled: progress.is_cancelled() });
+    }
+    if !finished.is_empty() {
+      self.start_queued();
+    }
+    finished
+  }
+
+  /// Whether the running jobs got further since the last call.
+  pub fn progress_changed(&mut self) -> bool {
+    let now = self.running.iter().map(|r| r.progress.done()).fold(Counts::default(), |sum, c| Counts {
+      entries: sum.entries + c.entries,
+      bytes: sum.bytes + c.bytes,
+    });
+    std::mem::replace(&mut self.shown, now) != now
+  }
+
+  /// Drop the job at `index` of the queue before it starts.
+  pub fn cancel(&mut self, index: usize) -> Option<Job> {
+    self.queued.remove(index)
+  }
+
+  /// Ask the running job at `index` to stop. Returns it unless it can't
+  /// be stopped.
+  pub fn cancel_running(&self, index: usize) -> Option<&Job> {
+    let running = self.running.get(index).filter(|r| r.job.cancellable())?;
+    running.progress.cancel();
+    Some(&running.job)
+  }
+
+  pub fn running(&self) -> impl Iterator<Item = &Job> {
+    self.running.iter().map(|r| &r.job)
+  }
+
+  /// The running jobs with how far each got.
+  pub fn running_progress(&self) -> impl Iterator<Item = (&Job, &Progress)> {
+    self.running.iter().map(|r| (&r.job, r.progress.as_ref()))
+  }
+
+  pub fn queued(&self) -> impl Iterator<Item = &Job> {
+    self.queued.iter()
+  }
+
+  pub fn queued_len(&self) -> usize {
+    self.queued.len()
+  }
+
+  pub fn is_idle(&self) -> bool {
+    self.running.is_empty() && self.queued.is_empty()
+  }
+
+  /// Whether a running or queued job reads or writes `path`.
+  pub fn involves(&self, path: &Path) -> bool {
+    self.running().chain(self.queued()).any(|job| match &job.kind {
+      JobKind::Extract { path: p, .. } | JobKind::Compress { dest: p, .. } => p == path,
+      JobKind::ExtractEntry { archive, entry } => archive.join(entry) == path,
+      JobKind::Transfer { sources, target_dir, .. } => target_dir == path || sources.iter().any(|s| s == path),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_queue_runs_at_most_max_jobs() {
+    let dir = std::env::temp_dir().join(format!("tfl_jobs_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.txt"), "a").unwrap();
+
+    let mut jobs = Jobs::new(1);
+    let compress = |name: &str| JobKind::Compress {
+      targets: vec![dir.join("a.txt")],
+      dest: dir.join(name),
+      format: "zip".to_string(),
+    };
+    assert_eq!(jobs.push(compress("one.zip")), None);
+    assert_eq!(jobs.push(compress("two.zip")), Some(1));
+    assert_eq!(jobs.push(compress("three.zip")), Some(2));
+    assert!(jobs.involves(&dir.join("three.zip")));
+
+    // Cancelling a queued job moves the rest up
+    assert_eq!(jobs.cancel(1).unwrap().name(), "three.zip");
+    assert!(!jobs.involves(&dir.join("three.zip")));
+
+    let mut done = Vec::new();
+    while !jobs.is_idle() {
+      assert!(jobs.running().count() <= 1);
+      for f in jobs.poll() {
+        assert!(f.result.is_ok());
+        done.push(f.job.name());
+      }
+      std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert_eq!(done, ["one.zip", "two.zip"]);
+    assert!(dir.join("two.zip").exists());
+    assert!(!dir.join("three.zip").exists());
+
+    // Raising the limit starts waiting jobs at once
+    let mut jobs = Jobs::new(1);
+    jobs.push(compress("four.zip"));
+    jobs.push(compress("five.zip"));
+    jobs.set_limit(2);
+    assert_eq!(jobs.running().count(), 2);
+    assert_eq!(jobs.queued_len(), 0);
+    while !jobs.is_idle() {
+      jobs.poll();
+      std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_transfer_copies_in_order_and_stops_when_cancelled() {
+    let dir = std::env::temp_dir().join(format!("tfl_jobs_transfer_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src/tree")).unwrap();
+    std::fs::create_dir_all(dir.join("out")).unwrap();
+    std::fs::write(dir.join("src/a.txt"), "aaa").unwrap();
+    std::fs::write(dir.join("src/tree/b.txt"), "bb").unwrap();
+    let sources = vec![dir.join("src/a.txt"), dir.join("src/gone"), dir.join("src/tree")];
+
+    let progress = Progress::default();
+    let done = transfer(&sources, &dir.join("out"), false, &CopyOptions::default(), &HashMap::new(), false, &progress);
+    assert_eq!(done.pairs.len(), 2);
+    assert_eq!(done.missing, vec![dir.join("src/gone")]);
+    assert_eq!(done.last_dest, Some(dir.join("out/tree")));
+    assert!(done.error.is_none());
+    assert_eq!(progress.done(), Counts { entries: 3, bytes: 5 });
+
+    progress.cancel();
+    let done = transfer(&sources, &dir.join("out"), false, &CopyOptions::default(), &HashMap::new(), false, &progress);
+    assert!(done.pairs.is_empty());
+    let (source, e) = done.error.unwrap();
+    assert_eq!((source, e.kind()), (dir.join("src/a.txt"), io::ErrorKind::Interrupted));
+    assert!(!dir.join("out/a_copy.txt").exists());
+
+    let options = CopyOptions::default();
+    let kind =
+      JobKind::Transfer { sources, target_dir: dir.join("out"), cut: false, options, collisions: HashMap::new(), use_trash: false };
+    let job = Job { id: 1, kind };
+    assert_eq!((job.verb(), job.name().as_str()), ("copy", "3 items"));
+    assert!(job.cancellable());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_transfer_renames_skips_or_overwrites_taken_names() {
+    let dir = std::env::temp_dir().join(format!("tfl_jobs_collisions_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src/d")).unwrap();
+    std::fs::create_dir_all(dir.join("out/d")).unwrap();
+    for name in ["a", "b", "c"] {
+      std::fs::write(dir.join("src").join(name), "new").unwrap();
+      std::fs::write(dir.join("out").join(name), "old").unwrap();
+    }
+    std::fs::write(dir.join("src/d/inner"), "new").unwrap();
+    std::fs::write(dir.join("out/d/stale"), "old").unwrap();
+    let sources: Vec<PathBuf> = ["a", "b", "c", "d"].iter().map(|n| dir.join("src").join(n)).collect();
+    assert_eq!(collisions(&sources, &dir.join("out"), false), sources);
+    // Pasting into the source's own directory is never a collision
+    assert!(collisions(&sources, &dir.join("src"), false).is_empty());
+
+    let choices = HashMap::from([
+      (dir.join("src/b"), Collision::Skip),
+      (dir.join("src/c"), Collision::Overwrite),
+      (dir.join("src/d"), Collision::Overwrite),
+    ]);
+    let done = transfer(&sources, &dir.join("out"), false, &CopyOptions::default(), &choices, false, &Progress::default());
+    assert!(done.error.is_none());
+    assert_eq!((done.pairs.len(), done.skipped), (3, 1));
+    assert_eq!(done.replaced, [dir.join("out/c")]);
+    let read = |name: &str| std::fs::read_to_string(dir.join("out").join(name)).unwrap();
+    assert_eq!((read("a"), read("a_copy")), ("old".to_string(), "new".to_string()));
+    assert_eq!(read("b"), "old");
+    assert!(!dir.join("out/b_copy").exists());
+    assert_eq!(read("c"), "new");
+    // A directory overwriting a directory is merged into it
+    assert_eq!(read("d/inner"), "new");
+    assert_eq!(read("d/stale"), "old");
+    assert!(done.pairs.contains(&(dir.join("src/d/inner"), dir.join("out/d/inner"))));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_failed_overwrite_keeps_the_destination() {
+    let dir = std::env::temp_dir().join(format!("tfl_jobs_overwrite_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::create_dir_all(dir.join("out")).unwrap();
+    std::fs::write(dir.join("src/a"), "new").unwrap();
+    std::fs::write(dir.join("out/a"), "old").unwrap();
+    let sources = vec![dir.join("src/a")];
+    let choices = HashMap::from([(dir.join("src/a"), Collision::Overwrite)]);
+
+    let progress = Progress::default();
+    progress.cancel();
+    let done = transfer(&sources, &dir.join("out"), false, &CopyOptions::default(), &choices, false, &progress);
+    assert!(done.error.is_some());
+    assert_eq!(std::fs::read_to_string(dir.join("out/a")).unwrap(), "old");
+    assert_eq!(std::fs::read_dir(dir.join("out")).unwrap().count(), 1);
+
+    // A move replaces it, leaving no temporary name behind
+    let done = transfer(&sources, &dir.join("out"), true, &CopyOptions::default(), &choices, false, &Progress::default());
+    assert!(done.error.is_none());
+    assert_eq!(std::fs::read_to_string(dir.join("out/a")).unwrap(), "new");
+    assert!(!dir.join("src/a").exists());
+    assert_eq!(std::fs::read_dir(dir.join("out")).unwrap().count(), 1);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,276 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{paths, persist};
+
+/// Lightweight session state journaled while tfl runs so it can be offered
+/// back after an unclean exit, and saved on quit for `start_in = "session"`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+  pub root: PathBuf,
+  #[serde(default)]
+  pub expanded: Vec<PathBuf>,
+  #[serde(default)]
+  pub selected: Option<PathBuf>,
+  #[serde(default)]
+  pub marked: Vec<PathBuf>,
+  #[serde(default)]
+  pub clipboard: Vec<PathBuf>,
+  #[serde(default)]
+  pub clipboard_cut: bool,
+  #[serde(default)]
+  pub show_hidden: bool,
+  /// Root of the right pane while dual-pane mode is on
+  #[serde(default)]
+  pub right_root: Option<PathBuf>,
+}
+
+/// The session tfl was last quit in, kept in `last_session.json` in the
+/// state dir.
+pub fn last_session_path() -> PathBuf {
+  paths::state_dir().join("last_session.json")
+}
+
+pub fn save_last_session(path: &Path, state: &SessionState) -> Result<()> {
+  persist::write_atomic(path, serde_json::to_string_pretty(state)?)?;
+  Ok(())
+}
+
+pub fn load_last_session(path: &Path) -> Option<SessionState> {
+  std::fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// Per-process journal file in the state dir. The file is removed on a clean
+/// exit, so one left behind by a process that is no longer running means that
+/// process crashed or was killed.
+pub struct Journal {
+  dir: PathBuf,
+  pid: u32,
+  last_written: Option<SessionState>,
+  /// Latest state seen, written by the next `flush`
+  noted: Option<SessionState>,
+}
+
+impl Default for Journal {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Journal {
+  pub fn new() -> Self {
+    Self::with_dir(Self::default_dir(), std::process::id())
+  }
+
+  pub fn with_dir(dir: PathBuf, pid: u32) -> Self {
+    Self { dir, pid, last_written: None, noted: None }
+  }
+
+  pub fn default_dir() -> PathBuf {
+    paths::state_dir()
+  }
+
+  pub fn path(&self) -> PathBuf {
+    self.dir.join(format!("session-{}.json", self.pid))
+  }
+
+  /// Write `state` if it differs from what was last journaled.
+  pub fn record(&mut self, state: &SessionState) -> Result<()> {
+    if self.last_written.as_ref() == Some(state) {
+      return Ok(());
+    }
+    persist::write_atomic(&self.path(), serde_json::to_string(state)?)?;
+    self.last_written = Some(state.clone());
+    Ok(())
+  }
+
+  /// Remember `state` for the next `flush`. True when it differs from the
+  /// state noted before.
+  pub fn note(&mut self, state: SessionState) -> bool {
+    if self.noted.as_ref() == Some(&state) {
+      return false;
+    }
+    self.noted = Some(state);
+    true
+  }
+
+  /// Journal the last noted state.
+  pub fn flush(&mut self) -> Result<()> {
+    match self.noted.clone() {
+      Some(state) => self.record(&state),
+      None => Ok(()),
+    }
+  }
+
+  /// Remove this process's journal on a clean exit.
+  pub fn finish(&self) {
+    let _ = std::fs::remove_file(self.path());
+  }
+
+  /// Collect journals left behind by processes that are no longer running.
+  /// The newest one is returned and all of them are removed.
+  pub fn take_orphaned(&self) -> Option<SessionState> {
+    let read_dir = std::fs::read_dir(&self.dir).ok()?;
+    let mut newest: Option<(SystemTime, SessionState)> = None;
+    for entry in read_dir.flatten() {
+      let name = entry.file_name();
+      let Some(pid) = name
+        .to_str()
+        .and_then(|n| n.strip_prefix("session-"))
+        .and_then(|n| n.strip_suffix(".json"))
+        .and_then(|n| n.parse::<u32>().ok())
+      else {
+        continue;
+      };
+      if pid == self.pid || process_alive(pid) {
+        continue;
+      }
+      let path = entry.path();
+      let modified = entry.metadata().and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH);
+      let state = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<SessionState>(&s).ok());
+      let _ = std::fs::remove_file(&path);
+      if let Some(state) = state
+        && newest.as_ref().is_none_or(|(t, _)| modified > *t)
+      {
+        newest = Some((modified, state));
+      }
+    }
+    newest.map(|(_, state)| state)
+  }
+}
+
+#[cfg(target_os = "linux")]
+pub fn process_alive(pid: u32) -> bool {
+  Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn process_alive(pid: u32) -> bool {
+  std::process::Command::new("kill")
+    .args(["-0", &pid.to_string()])
+    .stderr(std::process::Stdio::null())
+    .status()
+    .is_ok_and(|s| s.success())
+}
+
+/// Write a crash report next to the journals and return its path.
+pub fn write_panic_report(dir: &Path, message: &str, journal: &Path) -> Option<PathBuf> {
+  let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+  let mut report = String::new();
+  let _ = writeln!(report, "tfl {} panic report", env!("CARGO_PKG_VERSION"));
+  let _ = writeln!(report, "os: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+  let _ = writeln!(report, "time: {secs}");
+  let _ = writeln!(report, "\n{message}");
+  let _ = writeln!(report, "\nbacktrace:\n{}", std::backtrace::Backtrace::force_capture());
+  if let Ok(state) = std::fs::read_to_string(journal) {
+    let _ = writeln!(report, "last journaled state:\n{state}");
+  }
+  std::fs::create_dir_all(dir).ok()?;
+  let path = dir.join(format!("panic-{secs}.txt"));
+  std::fs::write(&path, report).ok()?;
+  Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+  fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+      "tfl_journal_{}_{}", COUNTER.fetch_add(1, Ordering::SeqCst), std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+  }
+
+  fn sample_state() -> SessionState {
+    SessionState {
+      root: PathBuf::from("/tmp/project"),
+      expanded: vec![PathBuf::from("/tmp/project/src")],
+      selected: Some(PathBuf::from("/tmp/project/src/main.rs")),
+      marked: vec![PathBuf::from("/tmp/project/a.txt")],
+      clipboard: vec![PathBuf::from("/tmp/project/b.txt")],
+      clipboard_cut: true,
+      show_hidden: true,
+      right_root: Some(PathBuf::from("/tmp")),
+    }
+  }
+
+  #[test]
+  fn test_last_session_round_trips() {
+    let dir = temp_dir();
+    let path = dir.join("last_session.json");
+    assert_eq!(load_last_session(&path), None);
+    save_last_session(&path, &sample_state()).unwrap();
+    assert_eq!(load_last_session(&path), Some(sample_state()));
+
+    // Sessions saved before the pane fields existed still load
+    std::fs::write(&path, r#"{"root": "/tmp/project"}"#).unwrap();
+    let state = load_last_session(&path).unwrap();
+    assert_eq!(state.root, PathBuf::from("/tmp/project"));
+    assert!(!state.show_hidden);
+    assert_eq!(state.right_root, None);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_record_and_finish() {
+    let dir = temp_dir();
+    let mut journal = Journal::with_dir(dir.clone(), std::process::id());
+    journal.record(&sample_state()).unwrap();
+    let written: SessionState = serde_json::from_str(&std::fs::read_to_string(journal.path()).unwrap()).unwrap();
+    assert_eq!(written, sample_state());
+
+    journal.finish();
+    assert!(!journal.path().exists());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_take_orphaned_returns_dead_process_journal() {
+    let dir = temp_dir();
+    // u32::MAX is never a live pid
+    let mut dead = Journal::with_dir(dir.clone(), u32::MAX);
+    dead.record(&sample_state()).unwrap();
+
+    let current = Journal::with_dir(dir.clone(), std::process::id());
+    assert_eq!(current.take_orphaned(), Some(sample_state()));
+    assert!(!dead.path().exists());
+    assert_eq!(current.take_orphaned(), None);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_take_orphaned_skips_own_and_live_journals() {
+    let dir = temp_dir();
+    let mut own = Journal::with_dir(dir.clone(), std::process::id());
+    own.record(&sample_state()).unwrap();
+
+    let other = Journal::with_dir(dir.clone(), u32::MAX);
+    assert_eq!(other.take_orphaned(), None);
+    assert!(own.path().exists());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_panic_report_includes_message_and_state() {
+    let dir = temp_dir();
+    let mut journal = Journal::with_dir(dir.clone(), std::process::id());
+    journal.record(&sample_state()).unwrap();
+
+    let report = write_panic_report(&dir, "panicked at src/app.rs:1:1: boom", &journal.path()).unwrap();
+    let content = std::fs::read_to_string(report).unwrap();
+    assert!(content.contains("boom"));
+    assert!(content.contains("/tmp/project/src/main.rs"));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,34 @@
+//! SELinux labels that differ from the policy default, flagged in the tree.
+//! Entries on screen are checked against `matchpathcon` on a background
+//! thread, a batch at a time.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::fs::selinux;
+use crate::path_cache::{PathCache, Work};
+use crate::process::ProcessRunner;
+
+/// Whether each checked entry is mislabeled
+pub type Labels = PathCache<bool>;
+
+impl Labels {
+  pub fn is_mislabeled(&self, path: &Path) -> bool {
+    self.get(path) == Some(&true)
+  }
+}
+
+/// Check each batch with one `matchpathcon` run through `runner`.
+pub fn work(runner: Arc<dyn ProcessRunner>) -> Work<bool> {
+  Arc::new(move |batch| {
+    let paths: Vec<PathBuf> = batch.into_iter().map(|(path, _)| path).collect();
+    let wrong = selinux::mislabeled(runner.as_ref(), &paths);
+    paths
+      .into_iter()
+      .map(|path| {
+        let mislabeled = wrong.contains(&path);
+        (path, Some(mislabeled))
+      })
+      .collect()
+  })
+}
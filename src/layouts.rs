@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{paths, persist};
+
+/// A named root and the directories expanded under it, for coming back to
+/// a curated working set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Layout {
+  pub name: String,
+  pub root: PathBuf,
+  #[serde(default)]
+  pub expanded: Vec<PathBuf>,
+  #[serde(default)]
+  pub selected: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+enum Change {
+  Put(Layout),
+  Remove(String),
+}
+
+fn apply(entries: &mut Vec<Layout>, change: Change) {
+  match change {
+    Change::Put(layout) => match entries.iter_mut().find(|l| l.name == layout.name) {
+      Some(existing) => *existing = layout,
+      None => entries.push(layout),
+    },
+    Change::Remove(name) => entries.retain(|l| l.name != name),
+  }
+}
+
+fn read(path: &Path) -> Vec<Layout> {
+  std::fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// Saved layouts, kept in `layouts.json` in the state dir in the order they
+/// were first saved. Saving replays this session's changes onto the file so
+/// instances don't drop each other's layouts.
+pub struct Layouts {
+  path: PathBuf,
+  entries: Vec<Layout>,
+  /// Changes made since the last save
+  pending: Vec<Change>,
+}
+
+impl Layouts {
+  pub fn load() -> Self {
+    Self::load_from(paths::state_dir().join("layouts.json"))
+  }
+
+  pub fn load_from(path: PathBuf) -> Self {
+    let entries = read(&path);
+    Self { path, entries, pending: Vec::new() }
+  }
+
+  pub fn save(&mut self) -> Result<()> {
+    if self.pending.is_empty() {
+      return Ok(());
+    }
+    let mut entries = read(&self.path);
+    for change in self.pending.drain(..) {
+      apply(&mut entries, change);
+    }
+    persist::write_atomic(&self.path, serde_json::to_string_pretty(&entries)?)?;
+    self.entries = entries;
+    Ok(())
+  }
+
+  /// Add `layout`, replacing one with the same name.
+  pub fn put(&mut self, layout: Layout) {
+    apply(&mut self.entries, Change::Put(layout.clone()));
+    self.pending.push(Change::Put(layout));
+  }
+
+  pub fn remove(&mut self, index: usize) {
+    if index < self.entries.len() {
+      let layout = self.entries.remove(index);
+      self.pending.push(Change::Remove(layout.name));
+    }
+  }
+
+  pub fn get(&self, index: usize) -> Option<&Layout> {
+    self.entries.get(index)
+  }
+
+  pub fn list(&self) -> &[Layout] {
+    &self.entries
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn layout(name: &str, root: &str) -> Layout {
+    Layout { name: name.to_string(), root: PathBuf::from(root), expanded: Vec::new(), selected: None }
+  }
+
+  #[test]
+  fn test_put_replaces_by_name_and_saves_merge() {
+    let path = std::env::temp_dir().join(format!("tfl_layouts_test_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut a = Layouts::load_from(path.clone());
+    let mut b = Layouts::load_from(path.clone());
+    a.put(layout("frontend review", "/src/web"));
+    a.put(layout("backend", "/src/api"));
+    a.put(layout("frontend review", "/src/app"));
+    assert_eq!(a.len(), 2);
+    assert_eq!(a.get(0).unwrap().root, PathBuf::from("/src/app"));
+    a.save().unwrap();
+
+    // Another instance's save keeps what this one wrote
+    b.put(layout("docs", "/src/docs"));
+    b.save().unwrap();
+    let names: Vec<&str> = b.list().iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, ["frontend review", "backend", "docs"]);
+
+    b.remove(1);
+    b.save().unwrap();
+    let loaded = Layouts::load_from(path.clone());
+    let names: Vec<&str> = loaded.list().iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, ["frontend review", "docs"]);
+    let _ = std::fs::remove_file(&path);
+  }
+}
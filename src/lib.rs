@@ -0,0 +1,40 @@
+//! tfl's internals as a library, shared by the binary and the benchmarks.
+//!
+//! The terminal-independent modules live in the `tfl-core` crate and are
+//! re-exported here under their old paths.
+
+pub mod action;
+pub mod app;
+pub mod batch;
+pub mod bookmarks;
+pub mod breadcrumb_menu;
+pub mod command;
+pub mod config;
+pub mod destination;
+pub mod dir_counts;
+pub mod dir_sizes;
+pub mod doctor;
+pub mod event;
+pub mod favorites;
+#[cfg(target_os = "linux")]
+pub mod handler;
+pub mod input_history;
+pub mod jobs;
+pub mod journal;
+pub mod labels;
+pub mod layouts;
+pub mod line_editor;
+pub mod logging;
+pub mod macros;
+pub mod op_log;
+pub mod path_cache;
+pub mod persist;
+pub mod preview;
+pub mod profile;
+pub mod repeat;
+pub mod settings;
+pub mod shared_clipboard;
+pub mod templates;
+pub mod ui;
+
+pub use tfl_core::{fs, git, icons, opener, paths, process, theme};
@@ -0,0 +1,232 @@
+//! Single-line text editing shared by the rename/new-file prompt and the
+//! search filter. The cursor is a char index; moves and deletes step over
+//! combining marks so an accent never gets split from its letter.
+
+use crate::ui::width;
+
+/// Readline-style edits beyond plain typing, bound the same way in every
+/// text prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEdit {
+  /// Ctrl+w: delete the word before the cursor
+  DeleteWord,
+  /// Ctrl+u: delete everything before the cursor
+  KillToStart,
+  /// Ctrl+k: delete everything after the cursor
+  KillToEnd,
+  /// Alt+b
+  WordLeft,
+  /// Alt+f
+  WordRight,
+  /// Ctrl+v: insert the system clipboard
+  Paste,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineEditor {
+  pub text: String,
+  pub cursor: usize,
+}
+
+/// Letters and digits make up words; zero-width marks belong to the
+/// letter before them.
+fn is_word_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || width::char_width(c) == 0
+}
+
+impl LineEditor {
+  /// Replace the text and put the cursor at its end.
+  pub fn set(&mut self, text: impl Into<String>) {
+    self.text = text.into();
+    self.cursor = self.len();
+  }
+
+  pub fn clear(&mut self) {
+    self.text.clear();
+    self.cursor = 0;
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.text.is_empty()
+  }
+
+  fn len(&self) -> usize {
+    self.text.chars().count()
+  }
+
+  fn byte_at(&self, pos: usize) -> usize {
+    self.text.char_indices().nth(pos).map_or(self.text.len(), |(i, _)| i)
+  }
+
+  /// Remove chars `start..end` and leave the cursor at `start`.
+  fn remove(&mut self, start: usize, end: usize) {
+    let range = self.byte_at(start)..self.byte_at(end);
+    self.text.replace_range(range, "");
+    self.cursor = start;
+  }
+
+  pub fn insert(&mut self, c: char) {
+    let at = self.byte_at(self.cursor);
+    self.text.insert(at, c);
+    self.cursor += 1;
+  }
+
+  /// Insert `s` at the cursor. Only the first line is kept, as the text is
+  /// always a single line.
+  pub fn insert_str(&mut self, s: &str) {
+    let line: String = s.lines().next().unwrap_or("").chars().filter(|c| !c.is_control()).collect();
+    let at = self.byte_at(self.cursor);
+    self.text.insert_str(at, &line);
+    self.cursor += line.chars().count();
+  }
+
+  pub fn backspace(&mut self) {
+    if self.cursor > 0 {
+      self.remove(width::prev_cursor(&self.text, self.cursor), self.cursor);
+    }
+  }
+
+  pub fn delete(&mut self) {
+    let end = width::next_cursor(&self.text, self.cursor);
+    let cursor = self.cursor;
+    self.remove(cursor, end);
+  }
+
+  pub fn left(&mut self) {
+    self.cursor = width::prev_cursor(&self.text, self.cursor);
+  }
+
+  pub fn right(&mut self) {
+    self.cursor = width::next_cursor(&self.text, self.cursor);
+  }
+
+  pub fn home(&mut self) {
+    self.cursor = 0;
+  }
+
+  pub fn end(&mut self) {
+    self.cursor = self.len();
+  }
+
+  /// Start of the word before the cursor, skipping separators first.
+  fn word_start(&self) -> usize {
+    let chars: Vec<char> = self.text.chars().take(self.cursor).collect();
+    let mut i = chars.len();
+    while i > 0 && !is_word_char(chars[i - 1]) {
+      i -= 1;
+    }
+    while i > 0 && is_word_char(chars[i - 1]) {
+      i -= 1;
+    }
+    i
+  }
+
+  /// End of the word after the cursor, skipping separators first.
+  fn word_end(&self) -> usize {
+    let mut chars = self.text.chars().skip(self.cursor).peekable();
+    let mut i = self.cursor;
+    while chars.next_if(|&c| !is_word_char(c)).is_some() {
+      i += 1;
+    }
+    while chars.next_if(|&c| is_word_char(c)).is_some() {
+      i += 1;
+    }
+    i
+  }
+
+  /// Apply `edit`. `Paste` inserts `clipboard`, which the caller reads so
+  /// the editor itself stays free of system access.
+  pub fn apply(&mut self, edit: LineEdit, clipboard: Option<&str>) {
+    match edit {
+      LineEdit::DeleteWord => self.remove(self.word_start(), self.cursor),
+      LineEdit::KillToStart => self.remove(0, self.cursor),
+      LineEdit::KillToEnd => self.remove(self.cursor, self.len()),
+      LineEdit::WordLeft => self.cursor = self.word_start(),
+      LineEdit::WordRight => self.cursor = self.word_end(),
+      LineEdit::Paste => {
+        if let Some(text) = clipboard {
+          self.insert_str(text);
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn editor(text: &str, cursor: usize) -> LineEditor {
+    LineEditor { text: text.to_string(), cursor }
+  }
+
+  #[test]
+  fn test_insert_and_delete_at_cursor() {
+    let mut e = editor("ac", 1);
+    e.insert('b');
+    assert_eq!((e.text.as_str(), e.cursor), ("abc", 2));
+    e.backspace();
+    assert_eq!((e.text.as_str(), e.cursor), ("ac", 1));
+    e.delete();
+    assert_eq!((e.text.as_str(), e.cursor), ("a", 1));
+    e.delete();
+    assert_eq!(e.text, "a");
+  }
+
+  #[test]
+  fn test_delete_word_stops_at_separators() {
+    let mut e = editor("my_notes-draft.md", 14);
+    e.apply(LineEdit::DeleteWord, None);
+    assert_eq!((e.text.as_str(), e.cursor), ("my_notes-.md", 9));
+    e.apply(LineEdit::DeleteWord, None);
+    assert_eq!((e.text.as_str(), e.cursor), (".md", 0));
+    e.apply(LineEdit::DeleteWord, None);
+    assert_eq!(e.text, ".md");
+  }
+
+  #[test]
+  fn test_kill_to_start_and_end() {
+    let mut e = editor("hello world", 5);
+    e.apply(LineEdit::KillToEnd, None);
+    assert_eq!((e.text.as_str(), e.cursor), ("hello", 5));
+    e.cursor = 2;
+    e.apply(LineEdit::KillToStart, None);
+    assert_eq!((e.text.as_str(), e.cursor), ("llo", 0));
+  }
+
+  #[test]
+  fn test_word_motion() {
+    let mut e = editor("one two.three", 0);
+    e.apply(LineEdit::WordRight, None);
+    assert_eq!(e.cursor, 3);
+    e.apply(LineEdit::WordRight, None);
+    assert_eq!(e.cursor, 7);
+    e.apply(LineEdit::WordRight, None);
+    assert_eq!(e.cursor, 13);
+    e.apply(LineEdit::WordRight, None);
+    assert_eq!(e.cursor, 13);
+    e.apply(LineEdit::WordLeft, None);
+    assert_eq!(e.cursor, 8);
+    e.apply(LineEdit::WordLeft, None);
+    assert_eq!(e.cursor, 4);
+    e.apply(LineEdit::WordLeft, None);
+    e.apply(LineEdit::WordLeft, None);
+    assert_eq!(e.cursor, 0);
+  }
+
+  #[test]
+  fn test_words_keep_combining_marks() {
+    let mut e = editor("old cafe\u{301}", 9);
+    e.apply(LineEdit::DeleteWord, None);
+    assert_eq!(e.text, "old ");
+  }
+
+  #[test]
+  fn test_paste_inserts_first_line_at_cursor() {
+    let mut e = editor("report.txt", 6);
+    e.apply(LineEdit::Paste, Some("_final\nsecond line"));
+    assert_eq!((e.text.as_str(), e.cursor), ("report_final.txt", 12));
+    e.apply(LineEdit::Paste, None);
+    assert_eq!(e.text, "report_final.txt");
+  }
+}
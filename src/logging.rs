@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
+
+use crate::paths;
+
+/// Rotate `tfl.log` once it grows past this size, at startup or while
+/// running
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Number of rotated files kept next to `tfl.log`
+const KEEP_ROTATED: usize = 3;
+/// Lines kept in memory for the debug overlay
+const RECENT_CAPACITY: usize = 200;
+/// Event loop iterations averaged for the debug overlay
+const LATENCY_SAMPLES: usize = 64;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+pub fn log_dir() -> PathBuf {
+  paths::cache_dir()
+}
+
+/// Install the global subscriber: `tfl.log` in the cache dir plus an
+/// in-memory tail for the debug overlay. The level comes from `TFL_LOG`
+/// (`error`..`trace`, or any `EnvFilter` directive) and defaults to `info`.
+pub fn init() {
+  let filter = EnvFilter::try_from_env("TFL_LOG").unwrap_or_else(|_| EnvFilter::new("info"));
+  let dir = log_dir();
+  let file = std::fs::create_dir_all(&dir).ok().and_then(|()| RotatingFile::open(dir).ok());
+  let file_layer = file.map(|f| fmt::layer().with_ansi(false).with_writer(Mutex::new(f)));
+  let recent_layer = fmt::layer()
+    .with_ansi(false)
+    .without_time()
+    .with_target(false)
+    .with_writer(|| RecentWriter);
+  let _ = tracing_subscriber::registry()
+    .with(filter)
+    .with(file_layer)
+    .with(recent_layer)
+    .try_init();
+}
+
+/// `tfl.log`, rotated once a write would take it past `MAX_LOG_BYTES`.
+struct RotatingFile {
+  dir: PathBuf,
+  file: File,
+  len: u64,
+}
+
+impl RotatingFile {
+  fn open(dir: PathBuf) -> io::Result<Self> {
+    rotate(&dir);
+    let file = OpenOptions::new().create(true).append(true).open(dir.join("tfl.log"))?;
+    let len = file.metadata()?.len();
+    Ok(Self { dir, file, len })
+  }
+}
+
+impl Write for RotatingFile {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    if self.len > 0 && self.len + buf.len() as u64 > MAX_LOG_BYTES {
+      self.file.flush()?;
+      *self = Self::open(self.dir.clone())?;
+    }
+    let written = self.file.write(buf)?;
+    self.len += written as u64;
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.file.flush()
+  }
+}
+
+fn rotate(dir: &Path) {
+  let log = dir.join("tfl.log");
+  if std::fs::metadata(&log).map_or(true, |m| m.len() < MAX_LOG_BYTES) {
+    return;
+  }
+  for n in (1..KEEP_ROTATED).rev() {
+    let _ = std::fs::rename(dir.join(format!("tfl.log.{n}")), dir.join(format!("tfl.log.{}", n + 1)));
+  }
+  let _ = std::fs::rename(&log, dir.join("tfl.log.1"));
+}
+
+/// The most recent `count` log lines, oldest first.
+pub fn recent_lines(count: usize) -> Vec<String> {
+  let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+  recent.iter().skip(recent.len().saturating_sub(count)).cloned().collect()
+}
+
+fn push_recent(text: &str) {
+  let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+  for line in text.lines().filter(|l| !l.trim().is_empty()) {
+    if recent.len() == RECENT_CAPACITY {
+      recent.pop_front();
+    }
+    recent.push_back(line.to_string());
+  }
+}
+
+struct RecentWriter;
+
+impl Write for RecentWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    push_recent(&String::from_utf8_lossy(buf));
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// Rolling timings of event-loop iterations (event received to frame drawn).
+#[derive(Debug, Default)]
+pub struct LoopStats {
+  samples: VecDeque<Duration>,
+  pub max: Duration,
+}
+
+impl LoopStats {
+  pub fn record(&mut self, elapsed: Duration) {
+    if self.samples.len() == LATENCY_SAMPLES {
+      self.samples.pop_front();
+    }
+    self.samples.push_back(elapsed);
+    self.max = self.max.max(elapsed);
+  }
+
+  pub fn last(&self) -> Option<Duration> {
+    self.samples.back().copied()
+  }
+
+  pub fn average(&self) -> Option<Duration> {
+    if self.samples.is_empty() {
+      return None;
+    }
+    Some(self.samples.iter().sum::<Duration>() / self.samples.len() as u32)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_rotate_shifts_files() {
+    let dir = std::env::temp_dir().join(format!("tfl_logging_rotate_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tfl.log"), vec![b'x'; MAX_LOG_BYTES as usize]).unwrap();
+    std::fs::write(dir.join("tfl.log.1"), "older").unwrap();
+
+    rotate(&dir);
+    assert!(!dir.join("tfl.log").exists());
+    assert_eq!(std::fs::metadata(dir.join("tfl.log.1")).unwrap().len(), MAX_LOG_BYTES);
+    assert_eq!(std::fs::read_to_string(dir.join("tfl.log.2")).unwrap(), "older");
+
+    // Small logs are left alone
+    std::fs::write(dir.join("tfl.log"), "small").unwrap();
+    rotate(&dir);
+    assert!(dir.join("tfl.log").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_rotating_file_rotates_while_writing() {
+    let dir = std::env::temp_dir().join(format!("tfl_logging_rotating_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tfl.log"), vec![b'x'; MAX_LOG_BYTES as usize - 4]).unwrap();
+
+    let mut file = RotatingFile::open(dir.clone()).unwrap();
+    file.write_all(b"fits").unwrap();
+    assert!(!dir.join("tfl.log.1").exists());
+    file.write_all(b"next\n").unwrap();
+    assert_eq!(std::fs::metadata(dir.join("tfl.log.1")).unwrap().len(), MAX_LOG_BYTES);
+    assert_eq!(std::fs::read_to_string(dir.join("tfl.log")).unwrap(), "next\n");
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_recent_writer_keeps_lines() {
+    let mut writer = RecentWriter;
+    writer.write_all(b"INFO recent-writer-test one\nINFO recent-writer-test two\n").unwrap();
+    let lines = recent_lines(RECENT_CAPACITY);
+    let ours: Vec<_> = lines.iter().filter(|l| l.contains("recent-writer-test")).collect();
+    assert_eq!(ours, ["INFO recent-writer-test one", "INFO recent-writer-test two"]);
+  }
+
+  #[test]
+  fn test_loop_stats() {
+    let mut stats = LoopStats::default();
+    assert!(stats.average().is_none());
+    stats.record(Duration::from_millis(2));
+    stats.record(Duration::from_millis(4));
+    assert_eq!(stats.last(), Some(Duration::from_millis(4)));
+    assert_eq!(stats.average(), Some(Duration::from_millis(3)));
+    assert_eq!(stats.max, Duration::from_millis(4));
+  }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use crate::action::Action;
+
+/// What the register key typed after `Q` or `@` is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroOp {
+  Record,
+  Play,
+}
+
+/// Vim-style macro registers. Registers live for the session only.
+#[derive(Debug, Default)]
+pub struct Macros {
+  registers: HashMap<char, Vec<Action>>,
+  recording: Option<(char, Vec<Action>)>,
+  /// Register most recently replayed, for `@@`
+  pub last_played: Option<char>,
+  /// Waiting for a register key
+  pub pending: Option<MacroOp>,
+}
+
+impl Macros {
+  pub fn is_register(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit()
+  }
+
+  /// Register being recorded into, if any.
+  pub fn recording(&self) -> Option<char> {
+    self.recording.as_ref().map(|(reg, _)| *reg)
+  }
+
+  pub fn start(&mut self, reg: char) {
+    self.recording = Some((reg, Vec::new()));
+  }
+
+  /// Finish recording; returns the register and how many actions it holds.
+  pub fn stop(&mut self) -> Option<(char, usize)> {
+    let (reg, actions) = self.recording.take()?;
+    let len = actions.len();
+    self.registers.insert(reg, actions);
+    Some((reg, len))
+  }
+
+  /// Append `action` to the recording in progress. Macro control actions
+  /// and timer/resize events are never recorded, so a macro can't replay
+  /// itself.
+  pub fn record(&mut self, action: &Action) {
+    let Some((_, ref mut actions)) = self.recording else {
+      return;
+    };
+    if !matches!(
+      action,
+      Action::MacroRecord
+        | Action::MacroPlay
+        | Action::MacroRegister(_)
+        | Action::Tick
+        | Action::Resize(..)
+        | Action::None
+    ) {
+      actions.push(action.clone());
+    }
+  }
+
+  pub fn get(&self, reg: char) -> Option<&[Action]> {
+    self.registers.get(&reg).map(Vec::as_slice).filter(|a| !a.is_empty())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_record_skips_control_actions() {
+    let mut macros = Macros::default();
+    macros.record(&Action::MoveDown);
+    assert_eq!(macros.recording(), None);
+
+    macros.start('a');
+    assert_eq!(macros.recording(), Some('a'));
+    for action in [Action::MoveDown, Action::Tick, Action::MacroPlay, Action::MacroRegister('a'), Action::RenameStart] {
+      macros.record(&action);
+    }
+    assert_eq!(macros.stop(), Some(('a', 2)));
+    assert_eq!(macros.get('a'), Some(&[Action::MoveDown, Action::RenameStart][..]));
+    assert_eq!(macros.get('b'), None);
+  }
+
+  #[test]
+  fn test_is_register() {
+    assert!(Macros::is_register('a'));
+    assert!(Macros::is_register('7'));
+    assert!(!Macros::is_register('A'));
+    assert!(!Macros::is_register('@'));
+  }
+}
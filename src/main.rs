@@ -1,46 +1,56 @@
-mod action;
-mod app;
-mod config;
-mod event;
-mod favorites;
-mod fs;
-mod git;
-mod icons;
-mod opener;
-mod preview;
-mod theme;
-mod ui;
-
 use std::collections::HashSet;
-use std::io;
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
 use std::panic;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use crossterm::event::EnableMouseCapture;
 use crossterm::event::DisableMouseCapture;
+use crossterm::event::{DisableBracketedPaste, EnableBracketedPaste};
+use crossterm::event::{MouseButton, MouseEventKind};
 use crossterm::execute;
 use crossterm::terminal::{
-  EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+  EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode, enable_raw_mode,
 };
 use ratatui::Terminal;
-use ratatui::backend::CrosstermBackend;
+use ratatui::backend::{Backend, CrosstermBackend};
 use ratatui_image::picker::Picker;
 
-use crate::app::{App, SuspendAction};
+use tfl::app::{App, SuspendAction};
 #[cfg(target_os = "linux")]
-use crate::app::PickerOutput;
-use crate::event::{Event, EventLoop, map_breadcrumb_click, map_key};
+use tfl::app::PickerOutput;
+use tfl::event::{Event, EventLoop, MoveAccel, TreeDebounce, map_breadcrumb_click, map_breadcrumb_menu_click, map_key};
+use tfl::input_history::InputHistory;
+use tfl::journal::{self, Journal};
+use tfl::process::ProcessRunner;
+use tfl::shared_clipboard::SharedClipboard;
+use tfl::theme::Theme;
+use tfl::profile::StartupProfile;
+use tfl::{config, logging, paths, persist, ui};
+
+/// Input-to-frame latency above which a warning is logged
+const SLOW_FRAME: Duration = Duration::from_millis(100);
 
 fn main() -> Result<()> {
+  let mut profile = StartupProfile::new();
   let args: Vec<String> = std::env::args().skip(1).collect();
 
   // Parse flags and positional path in a single pass
   let mut show_help = false;
   let mut show_version = false;
   let mut show_init = false;
+  let mut show_doctor = false;
   let mut show_hidden = false;
+  let mut profile_startup = false;
+  let mut last_dir_file: Option<String> = None;
+  let mut batch = false;
+  let mut print_selection = false;
+  let mut print0 = false;
+  let mut read_only = false;
+  let mut restrict = false;
   #[cfg(target_os = "linux")]
   let mut pick_stdout = false;
   #[cfg(target_os = "linux")]
@@ -64,7 +74,20 @@ fn main() -> Result<()> {
       "--help" | "-h" => show_help = true,
       "--version" | "-V" => show_version = true,
       "--init" => show_init = true,
+      "--doctor" => show_doctor = true,
       "--all" | "-a" => show_hidden = true,
+      "--profile-startup" => profile_startup = true,
+      "--batch" => batch = true,
+      "--print-selection" => print_selection = true,
+      "--read-only" => read_only = true,
+      "--restrict" => restrict = true,
+      "--print0" => {
+        print_selection = true;
+        print0 = true;
+      }
+      a if a.starts_with("--last-dir=") => {
+        last_dir_file = Some(a.strip_prefix("--last-dir=").unwrap().to_string());
+      }
       #[cfg(target_os = "linux")]
       "--pick" => pick_stdout = true,
       #[cfg(target_os = "linux")]
@@ -124,6 +147,14 @@ Options:
     ));
     println!(concat!(
       "  --init                   Write default config files to ~/.config/tfl/\n",
+      "  --doctor                 Report detected terminal and system features\n",
+      "  --profile-startup        Print per-phase startup timings on exit\n",
+      "  --last-dir=PATH          Write the final directory to PATH on exit\n",
+      "  --batch                  Run commands from stdin without the TUI\n",
+      "  --read-only              Disable every action that modifies files\n",
+      "  --restrict               Keep navigation inside the starting directory\n",
+      "  --print-selection        Print the marked paths to stdout on exit\n",
+      "  --print0                 Like --print-selection, NUL-delimited\n",
       "  -h, --help               Print this help message\n",
       "  -V, --version            Print version\n",
       "\n",
@@ -137,13 +168,27 @@ Options:
     return Ok(());
   }
 
+  if show_doctor {
+    let picker = Picker::from_query_stdio().ok();
+    let config_home = dirs::config_dir();
+    let probe = tfl::doctor::Probe {
+      runner: &tfl::process::SystemRunner,
+      var: &|name| std::env::var(name).ok(),
+      config_home: config_home.as_deref(),
+      image_protocol: picker.map(|p| p.protocol_type()),
+    };
+    println!("tfl {}\n", env!("CARGO_PKG_VERSION"));
+    print!("{}", tfl::doctor::report(&tfl::doctor::run(&probe)));
+    return Ok(());
+  }
+
   #[cfg(target_os = "linux")]
   {
     if install_handler {
-      return handler::install();
+      return tfl::handler::install(&tfl::process::SystemRunner, &tfl::handler::HandlerPaths::system()?);
     }
     if uninstall_handler {
-      return handler::uninstall();
+      return tfl::handler::uninstall(&tfl::process::SystemRunner, &tfl::handler::HandlerPaths::system()?);
     }
     if install_portal {
       return portal::install();
@@ -225,102 +270,252 @@ Options:
   #[cfg(not(target_os = "linux"))]
   let picker_mode = None;
 
+  profile.mark("arguments");
+  logging::init();
+  tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting");
+  for moved in paths::migrate() {
+    tracing::info!("moved to {}", moved.display());
+  }
   let (mut config, config_errors) = config::Config::load();
-  let config_dir = dirs::config_dir().map(|d| d.join("tfl"));
+  config.read_only |= read_only;
+  // Favorites live in the state dir, the rest of the watched files here
+  let _ = std::fs::create_dir_all(paths::state_dir());
+  let watched_dirs: Vec<PathBuf> = paths::config_dir().into_iter().chain([paths::state_dir()]).collect();
+  profile.mark("config");
 
   // Detect Kitty protocol support BEFORE entering alternate screen
   let picker = Picker::from_query_stdio().ok();
+  profile.mark("terminal query");
+
+  let journal = Journal::new();
+  let mut shared_clipboard = SharedClipboard::new();
+  let orphaned_session = journal.take_orphaned();
 
-  // Install panic hook that restores terminal
+  // Install panic hook that restores terminal and leaves a crash report
   let original_hook = panic::take_hook();
+  let journal_path = journal.path();
   panic::set_hook(Box::new(move |info| {
     let _ = restore_terminal();
+    WindowTitle::pop();
     original_hook(info);
+    tracing::error!("{info}");
+    if let Some(report) = journal::write_panic_report(&Journal::default_dir(), &info.to_string(), &journal_path) {
+      eprintln!("tfl: crash report written to {}", report.display());
+    }
   }));
 
-  let root = path_arg
-    .map(PathBuf::from)
-    .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+  let last_dir_state = paths::state_dir().join("last_dir");
+  let last_session_state = journal::last_session_path();
+  let last_session = (path_arg.is_none() && config.start_in == config::StartIn::Session)
+    .then(|| journal::load_last_session(&last_session_state))
+    .flatten();
+  let root = path_arg.map(PathBuf::from).unwrap_or_else(|| {
+    let last = match last_session {
+      Some(ref session) => Some(session.root.clone()),
+      None => std::fs::read_to_string(&last_dir_state).ok().map(|s| PathBuf::from(s.trim_end_matches('\n'))),
+    };
+    config.resolve_start_dir(std::env::current_dir().ok(), last.as_deref())
+  });
 
   let root = std::fs::canonicalize(root)?;
 
-  setup_terminal()?;
+  if batch {
+    let mut app = App::new_deferred_git(root.clone(), None, &config, None)?;
+    app.restrict_root = restrict.then_some(root);
+    app.tree.show_hidden |= show_hidden;
+    app.tree.reload()?;
+    app.rebuild_visible_cache();
+    if let Err(e) = tfl::batch::run(&mut app, io::stdin().lock(), &mut io::stdout().lock())? {
+      eprintln!("tfl: {e}");
+      std::process::exit(1);
+    }
+    return Ok(());
+  }
+
+  setup_terminal(config.mouse)?;
   let backend = CrosstermBackend::new(io::stdout());
   let mut terminal = Terminal::new(backend)?;
+  profile.mark("terminal setup");
 
-  let mut app = App::new(root, picker, &config, picker_mode)?;
+  let mut app = App::new_deferred_git(root.clone(), picker, &config, picker_mode)?;
+  app.restrict_root = restrict.then_some(root);
+  app.input_history = InputHistory::load(config.history_size);
+  app.journal = Some(journal);
 
-  if show_hidden {
+  // Only when the session's root is where tfl opened, not a start_dir
+  if let Some(ref session) = last_session
+    && session.root == app.tree.root
+  {
+    app.resume_session(session)?;
+  }
+  if show_hidden && !app.tree.show_hidden {
     app.tree.show_hidden = true;
     app.tree.reload()?;
     app.rebuild_visible_cache();
   }
-
-  // Trigger initial preview
-  if !app.tree.entries.is_empty() {
-    let path = app.tree.entries[0].path.clone();
-    app.preview.request_preview(&path, app.picker.as_ref(), app.tree.git_repo());
-  }
+  profile.mark("file tree");
 
   if !config_errors.is_empty() {
     app.show_error(config_errors);
+  } else if let Some(state) = orphaned_session {
+    app.offer_session_restore(state);
   }
 
-  let events = EventLoop::new(Duration::from_millis(config.tick_rate_ms), config_dir.as_deref());
+  let events = EventLoop::new(Duration::from_millis(config.tick_rate_ms), &watched_dirs);
   events.set_watched_dirs(compute_watched_dirs(&app));
   let mut last_reload = Instant::now() - Duration::from_secs(1);
-  let mut last_tree_change: Option<Instant> = None;
+  let mut move_accel = MoveAccel::new(&config);
+  let mut tree_changes = TreeDebounce::default();
+  let mut first_frame = true;
+  let mut git_pending = app.tree.git_pending();
+  // When the input event currently being handled arrived
+  let mut input_received: Option<Instant> = None;
+  let mut window_title = WindowTitle::default();
+  let mut mouse_captured = config.mouse;
+  // OSC 8 links printed after the last draw
+  let mut shown_links = Vec::new();
 
   loop {
+    window_title.show(&app.window_title(&config.window_title))?;
     if app.needs_redraw {
-      terminal.draw(|frame| ui::draw(frame, &mut app, &config))?;
+      let frame = terminal.draw(|frame| ui::draw(frame, &mut app, &config))?;
+      let links = if config.hyperlinks { app.hyperlinks.as_slice() } else { &[] };
+      let cells = ui::hyperlinks::osc8_cells(links, &shown_links, frame.buffer);
+      if !cells.is_empty() {
+        terminal.backend_mut().draw(cells.iter().map(|(x, y, cell)| (*x, *y, cell)))?;
+        Backend::flush(terminal.backend_mut())?;
+      }
+      shown_links = links.to_vec();
       app.needs_redraw = false;
+      if let Some(received) = input_received.take() {
+        let elapsed = received.elapsed();
+        if elapsed >= SLOW_FRAME {
+          tracing::warn!(?elapsed, "slow frame");
+        }
+        app.loop_stats.record(elapsed);
+      }
+      if first_frame {
+        profile.mark("first frame");
+        first_frame = false;
+        // Preview only once the tree is on screen, so a slow first entry
+        // can't hold up startup
+        app.update_preview();
+        app.needs_redraw = true;
+        profile.mark("initial preview");
+      }
+    }
+    if git_pending && !app.tree.git_pending() {
+      profile.mark_since_start("git status (background, from start)");
+      git_pending = false;
+      tracing::info!("git status loaded");
     }
 
-    match events.next()? {
+    let event = events.next()?;
+    let is_input = match event {
+      Event::Key(_) | Event::Paste(_) | Event::Resize(..) => true,
+      Event::Mouse(mouse) => mouse.kind != MouseEventKind::Moved,
+      _ => false,
+    };
+    if is_input {
+      input_received = Some(Instant::now());
+    }
+    match event {
       Event::Key(key) => {
-        let action = map_key(key, app.input_mode, &config);
+        let action = move_accel.accelerate(map_key(key, app.input_mode, &config), key.code, Instant::now());
         app.update(action)?;
         app.needs_redraw = true;
         events.set_watched_dirs(compute_watched_dirs(&app));
       }
+      Event::Stop => app.should_stop = true,
+      Event::Continue => {
+        setup_terminal(mouse_captured)?;
+        terminal.clear()?;
+        app.needs_redraw = true;
+      }
+      Event::Terminate => {
+        tracing::info!("exiting on signal");
+        app.should_quit = true;
+      }
+      Event::Paste(text) => {
+        app.update(tfl::action::Action::InsertText(text))?;
+        app.needs_redraw = true;
+      }
+      Event::Mouse(mouse) if mouse.kind == MouseEventKind::Moved => {
+        if app.mouse_moved(mouse.column, mouse.row) {
+          app.needs_redraw = true;
+        }
+      }
       Event::Mouse(mouse) => {
-        // Handle mouse clicks in the header row (row 0) for breadcrumb navigation
-        if mouse.row == 0
-          && let Some(action) = map_breadcrumb_click(mouse.column, &app.breadcrumb_segments)
-        {
+        // Clicks in the header row (row 0) navigate the breadcrumbs or open
+        // the branch switcher; a right click opens a segment's dropdown, and
+        // clicks go to it while open
+        let action = match mouse.kind {
+          MouseEventKind::Down(MouseButton::Left) if app.input_mode == tfl::event::InputMode::BreadcrumbMenu => {
+            Some(tfl::action::Action::BreadcrumbMenuClick(mouse.column, mouse.row))
+          }
+          MouseEventKind::Down(MouseButton::Left) if mouse.row == 0 && app.on_header_branch(mouse.column) => {
+            Some(tfl::action::Action::BranchesStart)
+          }
+          MouseEventKind::Down(MouseButton::Left) if mouse.row == 0 => {
+            map_breadcrumb_click(mouse.column, &app.breadcrumb_segments)
+          }
+          MouseEventKind::Down(MouseButton::Right) if mouse.row == 0 => {
+            map_breadcrumb_menu_click(mouse.column, &app.breadcrumb_segments)
+          }
+          _ => None,
+        };
+        if let Some(action) = action {
           app.update(action)?;
           app.needs_redraw = true;
           events.set_watched_dirs(compute_watched_dirs(&app));
         }
       }
       Event::Resize(w, h) => {
-        app.update(crate::action::Action::Resize(w, h))?;
+        app.update(tfl::action::Action::Resize(w, h))?;
         app.needs_redraw = true;
       }
-      Event::ConfigChanged => {
-        if app.wrote_config {
-          app.wrote_config = false;
+      Event::ConfigChanged(paths) => {
+        let now = Instant::now();
+        if paths.iter().all(|p| app.state_writer.recently_wrote_watched(p, now)) {
           last_reload = Instant::now();
         } else if last_reload.elapsed() > Duration::from_millis(500) {
-          reload_config(&mut config, &mut app);
+          reload_config(&mut config, &mut app, read_only);
+          move_accel.apply_config(&config);
           last_reload = Instant::now();
           app.needs_redraw = true;
         }
       }
-      Event::TreeChanged => {
-        if app.tree_reloaded {
-          // Suppress self-triggered events from app's own file ops
-          app.tree_reloaded = false;
-          last_tree_change = Some(Instant::now());
-        } else {
-          last_tree_change = Some(last_tree_change.unwrap_or_else(Instant::now));
+      Event::FileChanged(path) => {
+        if app.preview.note_file_changed(&path) {
+          app.needs_redraw = true;
         }
       }
+      Event::TreeChanged(paths) => tree_changes.note(Instant::now(), &paths),
       Event::Tick => {
-        app.update(crate::action::Action::Tick)?;
+        app.update(tfl::action::Action::Tick)?;
+        if app.poll_hover() {
+          app.needs_redraw = true;
+        }
+        if app.poll_dir_counts() || app.poll_dir_sizes() || app.poll_delete_summary() {
+          app.needs_redraw = true;
+        }
+        if app.poll_labels() {
+          app.needs_redraw = true;
+        }
+        app.flush_state(false);
+        if config.share_clipboard {
+          match shared_clipboard.sync(&mut app.clipboard) {
+            Ok(true) => {
+              let n = app.clipboard.paths.len();
+              app.set_status(format!("Clipboard: {n} item{} from another window", if n == 1 { "" } else { "s" }));
+              app.needs_redraw = true;
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!("clipboard sync failed: {e}"),
+          }
+        }
         // Clear status message after it's been visible for a few ticks
-        if app.input_mode == crate::event::InputMode::Normal {
+        if app.input_mode == tfl::event::InputMode::Normal {
           if app.status_ticks > 0 {
             app.status_ticks -= 1;
             app.needs_redraw = true;
@@ -330,10 +525,10 @@ Options:
           }
         }
         // Debounced tree reload from external changes
-        if let Some(ts) = last_tree_change
-          && ts.elapsed() >= Duration::from_millis(150)
-        {
-          last_tree_change = None;
+        if let Some(changed) = tree_changes.take_due(Instant::now()) {
+          app.dir_counts.invalidate(&changed);
+          app.dir_sizes.invalidate(&changed);
+          app.labels.invalidate(&changed);
           let cursor_path = app.selected_entry().map(|e| e.path.clone());
           app.tree.invalidate_git_statuses();
           app.tree.reload()?;
@@ -343,21 +538,37 @@ Options:
             let _ = pane.tree.reload();
             pane.rebuild_visible_cache();
           }
+          app.check_lost_root()?;
           if let Some(ref path) = cursor_path {
             app.reposition_cursor_to(path);
           }
-          app.preview.invalidate();
-          app.update_preview();
+          app.refresh_preview();
           app.needs_redraw = true;
           events.set_watched_dirs(compute_watched_dirs(&app));
         }
       }
     }
 
+    if let Some(name) = app.requested_theme.take()
+      && let Some(theme) = Theme::from_name(&name)
+    {
+      config.theme_name = name;
+      config.theme = theme;
+      app.preview.set_theme(config.theme.clone());
+    }
+
+    if app.mouse_capture != mouse_captured {
+      set_mouse_capture(app.mouse_capture)?;
+      mouse_captured = app.mouse_capture;
+    }
+
     // Suppress watcher events from app's own tree.reload() calls
     if app.tree_reloaded {
       app.tree_reloaded = false;
-      last_tree_change = Some(Instant::now());
+      tree_changes.note(Instant::now(), &[]);
+      app.dir_counts.invalidate_all();
+      app.dir_sizes.invalidate_all();
+      app.labels.invalidate_all();
       events.set_watched_dirs(compute_watched_dirs(&app));
     }
 
@@ -365,10 +576,12 @@ Options:
     if let Some(suspend) = app.handle_suspend() {
       events.pause();
       restore_terminal()?;
-      terminal = suspend_and_resume(terminal, &suspend)?;
+      window_title.reset()?;
+      terminal = suspend_and_resume(terminal, &suspend, app.runner.as_ref(), mouse_captured)?;
       let config_changed = events.resume();
       if config_changed {
-        reload_config(&mut config, &mut app);
+        reload_config(&mut config, &mut app, read_only);
+        move_accel.apply_config(&config);
         last_reload = Instant::now();
       }
       app.tree.invalidate_git_statuses();
@@ -379,21 +592,55 @@ Options:
         let _ = pane.tree.reload();
         pane.rebuild_visible_cache();
       }
+      app.check_lost_root()?;
       app.preview.invalidate();
       // Re-request preview for currently selected file
       if let Some(entry) = app.selected_entry() {
         let path = entry.path.clone();
+        app.preview.git_status = entry.git_status;
         app.preview.request_preview(&path, app.picker.as_ref(), app.tree.git_repo());
       }
       events.set_watched_dirs(compute_watched_dirs(&app));
     }
 
+    // Ctrl+Z or SIGTSTP: give the terminal back, stop until `fg`, take it over again
+    if std::mem::take(&mut app.should_stop) {
+      events.pause();
+      restore_terminal()?;
+      window_title.reset()?;
+      signal_hook::low_level::raise(signal_hook::consts::SIGSTOP)?;
+      setup_terminal(mouse_captured)?;
+      if events.resume() {
+        reload_config(&mut config, &mut app, read_only);
+        move_accel.apply_config(&config);
+        last_reload = Instant::now();
+      }
+      terminal.clear()?;
+      app.needs_redraw = true;
+    }
+
     if app.should_quit {
       break;
     }
   }
 
-  restore_terminal()?;
+  // After a hangup the terminal is gone; state still gets saved
+  if let Err(e) = restore_terminal().and_then(|()| window_title.reset()) {
+    tracing::warn!("failed to restore the terminal: {e}");
+  }
+  app.flush_state(true);
+  if let Some(journal) = app.journal.take() {
+    journal.finish();
+  }
+  if config.share_clipboard
+    && let Err(e) = shared_clipboard.sync(&mut app.clipboard)
+  {
+    tracing::warn!("clipboard sync failed: {e}");
+  }
+
+  if profile_startup {
+    eprintln!("{}", profile.report());
+  }
 
   // Handle picker output
   let is_picker = app.picker_mode.is_some();
@@ -405,6 +652,34 @@ Options:
     std::process::exit(1);
   }
 
+  if !is_picker {
+    let _ = persist::write_atomic(&last_dir_state, format!("{}\n", app.last_dir().display()));
+    if let Err(e) = journal::save_last_session(&last_session_state, &app.session_state()) {
+      tracing::warn!("saving the session failed: {e}");
+    }
+  }
+  if let Some(file) = last_dir_file
+    && let Err(e) = std::fs::write(&file, format!("{}\n", app.last_dir().display()))
+  {
+    eprintln!("tfl: failed to write {file}: {e}");
+  }
+  if app.print_last_dir && !is_picker {
+    println!("{}", app.last_dir().display());
+  }
+  let exported = app.exported_selection.take().or_else(|| print_selection.then(|| app.all_marks()));
+  if let Some(paths) = exported
+    && !is_picker
+  {
+    // Raw bytes: a name that isn't UTF-8 must reach `xargs -0` unchanged
+    let delimiter = if print0 { b'\0' } else { b'\n' };
+    let mut out = io::stdout().lock();
+    for path in paths {
+      out.write_all(path.as_os_str().as_bytes())?;
+      out.write_all(&[delimiter])?;
+    }
+    out.flush()?;
+  }
+
   Ok(())
 }
 
@@ -416,37 +691,138 @@ fn compute_watched_dirs(app: &App) -> HashSet<PathBuf> {
   dirs
 }
 
-fn setup_terminal() -> Result<()> {
+fn setup_terminal(mouse: bool) -> Result<()> {
   enable_raw_mode()?;
-  execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+  execute!(io::stdout(), EnterAlternateScreen, EnableBracketedPaste)?;
+  set_mouse_capture(mouse)
+}
+
+fn set_mouse_capture(on: bool) -> Result<()> {
+  if on {
+    execute!(io::stdout(), EnableMouseCapture)?;
+  } else {
+    execute!(io::stdout(), DisableMouseCapture)?;
+  }
   Ok(())
 }
 
 fn restore_terminal() -> Result<()> {
   disable_raw_mode()?;
-  execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+  execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste)?;
   Ok(())
 }
 
-fn reload_config(config: &mut config::Config, app: &mut App) {
+/// Whether a title was pushed onto the terminal's title stack, so the panic
+/// hook can put the old one back too.
+static TITLE_PUSHED: AtomicBool = AtomicBool::new(false);
+
+/// The terminal window title while tfl runs. The title from before is
+/// saved on the terminal's title stack and restored by `reset`.
+#[derive(Default)]
+struct WindowTitle {
+  shown: Option<String>,
+}
+
+impl WindowTitle {
+  /// Set the title if it changed; an empty one puts the old title back.
+  fn show(&mut self, title: &str) -> Result<()> {
+    if title.is_empty() {
+      return self.reset();
+    }
+    if self.shown.as_deref() == Some(title) {
+      return Ok(());
+    }
+    if self.shown.is_none() {
+      // XTWINOPS: push the current window title
+      write!(io::stdout(), "\x1b[22;0t")?;
+      TITLE_PUSHED.store(true, Ordering::SeqCst);
+    }
+    execute!(io::stdout(), SetTitle(title))?;
+    self.shown = Some(title.to_string());
+    Ok(())
+  }
+
+  fn reset(&mut self) -> Result<()> {
+    self.shown = None;
+    if TITLE_PUSHED.swap(false, Ordering::SeqCst) {
+      // XTWINOPS: pop the title pushed in `show`
+      write!(io::stdout(), "\x1b[23;0t")?;
+      io::stdout().flush()?;
+    }
+    Ok(())
+  }
+
+  /// Put the old title back without a `WindowTitle`, as the panic hook must.
+  fn pop() {
+    let _ = Self::default().reset();
+  }
+}
+
+/// `read_only_flag` is `--read-only`, which a reload can't turn off.
+fn reload_config(config: &mut config::Config, app: &mut App, read_only_flag: bool) {
   let (new, errors) = config::Config::load();
   config.normal_keys = new.normal_keys;
   config.g_prefix_keys = new.g_prefix_keys;
   config.search_keys = new.search_keys;
   config.custom_apps = new.custom_apps;
   config.claude_yolo = new.claude_yolo;
+  config.ai_command = new.ai_command;
+  config.ai_command_alt = new.ai_command_alt;
+  config.tmux_window_command = new.tmux_window_command;
+  config.tmux_pane_command = new.tmux_pane_command;
+  config.zellij_tab_command = new.zellij_tab_command;
+  config.terminal_command = new.terminal_command;
+  config.move_accel_after = new.move_accel_after;
+  config.move_accel_ms = new.move_accel_ms;
   config.use_trash = new.use_trash;
+  config.difftool = new.difftool;
   config.has_apps_file = new.has_apps_file;
   config.ignore_patterns = new.ignore_patterns;
   config.use_gitignore = new.use_gitignore;
   config.use_custom_ignore = new.use_custom_ignore;
   config.ignore_glob_set = new.ignore_glob_set;
+  config.dirs_first = new.dirs_first;
+  config.pinned_patterns = new.pinned_patterns;
+  config.pinned_glob_set = new.pinned_glob_set;
   config.theme_name = new.theme_name;
   config.syntax_theme = new.syntax_theme;
   config.theme = new.theme;
+  config.template_rules = new.template_rules;
+  config.chmod_presets = new.chmod_presets;
+  config.quit_confirm = new.quit_confirm;
+  config.esc_quits = new.esc_quits;
+  config.print_last_dir = new.print_last_dir;
+  config.read_only = new.read_only || read_only_flag;
+  config.share_clipboard = new.share_clipboard;
+  if new.mouse != config.mouse {
+    // Only a change in the file overrides the runtime toggle
+    app.mouse_capture = new.mouse;
+  }
+  config.mouse = new.mouse;
+  config.hover_preview_ms = new.hover_preview_ms;
+  config.history_size = new.history_size;
+  config.max_jobs = new.max_jobs;
+  config.copy_options = new.copy_options;
+  config.image_thumbnails = new.image_thumbnails;
+  config.preview_timeout_ms = new.preview_timeout_ms;
+  config.auto_preview = new.auto_preview;
+  config.safe_names = new.safe_names;
+  config.op_log = new.op_log;
+  config.dir_counts = new.dir_counts;
+  config.hint_bar = new.hint_bar;
+  config.icons = new.icons;
+  config.git_markers = new.git_markers;
+  config.show_hidden = new.show_hidden;
+  config.hyperlinks = new.hyperlinks;
+  config.focus_order = new.focus_order;
+  config.window_title = new.window_title;
+  config.worktree_dir = new.worktree_dir;
+  config.start_dir = new.start_dir;
+  config.start_in = new.start_in;
   app.apply_config(config);
   app.reload_favorites();
   if errors.is_empty() {
+    tracing::info!("config reloaded");
     app.set_status("Config reloaded".to_string());
   } else {
     app.show_error(errors);
@@ -456,10 +832,12 @@ fn reload_config(config: &mut config::Config, app: &mut App) {
 fn suspend_and_resume(
   terminal: Terminal<CrosstermBackend<io::Stdout>>,
   action: &SuspendAction,
+  runner: &dyn ProcessRunner,
+  mouse: bool,
 ) -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
   drop(terminal);
-  App::execute_suspend(action)?;
-  setup_terminal()?;
+  App::execute_suspend(action, runner)?;
+  setup_terminal(mouse)?;
   // Drain stale keystrokes buffered in the TTY while the subprocess ran
   while crossterm::event::poll(std::time::Duration::ZERO).unwrap_or(false) {
     let _ = crossterm::event::read();
@@ -472,114 +850,17 @@ fn suspend_and_resume(
 /// Returns the backup directory for handler/portal operations.
 #[cfg(target_os = "linux")]
 fn backup_dir() -> Result<PathBuf> {
-  let dir = dirs::config_dir()
-    .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?
-    .join("tfl")
-    .join("handler-backup");
+  let dir = tfl::paths::state_dir().join("handler-backup");
   std::fs::create_dir_all(&dir)?;
   Ok(dir)
 }
 
-#[cfg(target_os = "linux")]
-mod handler {
-  use std::path::PathBuf;
-  use std::process::Command;
-
-  use anyhow::Result;
-
-  fn desktop_entry(tfl_path: &std::path::Path) -> String {
-    format!(
-      "[Desktop Entry]\n\
-       Type=Application\n\
-       Name=tfl\n\
-       GenericName=File Manager\n\
-       Comment=Terminal file explorer with vim-style navigation\n\
-       Exec={} %f\n\
-       Icon=system-file-manager\n\
-       Terminal=true\n\
-       Categories=System;FileManager;ConsoleOnly;\n\
-       MimeType=inode/directory;\n",
-      tfl_path.display()
-    )
-  }
-
-  pub fn install() -> Result<()> {
-    let tfl_path = std::env::current_exe()?;
-    let backup = super::backup_dir()?;
-
-    // Query current default handler
-    let output = Command::new("xdg-mime")
-      .args(["query", "default", "inode/directory"])
-      .output()?;
-    let current = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-    if !current.is_empty() && current != "tfl.desktop" {
-      let backup_file = backup.join("mime-handler");
-      std::fs::write(&backup_file, &current)?;
-      println!("Backed up current handler: {current}");
-    }
-
-    // Write desktop file with resolved path
-    let apps_dir = dirs::data_dir()
-      .ok_or_else(|| anyhow::anyhow!("Could not determine data directory"))?
-      .join("applications");
-    std::fs::create_dir_all(&apps_dir)?;
-    let desktop_path = apps_dir.join("tfl.desktop");
-    std::fs::write(&desktop_path, desktop_entry(&tfl_path))?;
-    println!("Installed: {}", desktop_path.display());
-
-    // Set as default
-    let status = Command::new("xdg-mime")
-      .args(["default", "tfl.desktop", "inode/directory"])
-      .status()?;
-    if !status.success() {
-      anyhow::bail!("xdg-mime default failed");
-    }
-    println!("Set tfl as default file manager for inode/directory");
-
-    Ok(())
-  }
-
-  pub fn uninstall() -> Result<()> {
-    let backup = super::backup_dir()?;
-    let backup_file = backup.join("mime-handler");
-
-    // Restore previous handler
-    if backup_file.exists() {
-      let old_handler = std::fs::read_to_string(&backup_file)?.trim().to_string();
-      if !old_handler.is_empty() {
-        let status = Command::new("xdg-mime")
-          .args(["default", &old_handler, "inode/directory"])
-          .status()?;
-        if !status.success() {
-          anyhow::bail!("xdg-mime default failed");
-        }
-        println!("Restored default handler: {old_handler}");
-      }
-      std::fs::remove_file(&backup_file)?;
-    } else {
-      println!("No backup found — nothing to restore");
-    }
-
-    // Remove desktop file
-    let desktop_path: PathBuf = dirs::data_dir()
-      .ok_or_else(|| anyhow::anyhow!("Could not determine data directory"))?
-      .join("applications")
-      .join("tfl.desktop");
-    if desktop_path.exists() {
-      std::fs::remove_file(&desktop_path)?;
-      println!("Removed: {}", desktop_path.display());
-    }
-
-    Ok(())
-  }
-}
-
 #[cfg(target_os = "linux")]
 mod portal {
   use std::path::PathBuf;
 
   use anyhow::Result;
+  use tfl::process::{ProcessRunner, SystemRunner};
 
   const WRAPPER_TEMPLATE: &str = r#"#!/bin/bash
 # tfl wrapper for xdg-desktop-portal-termfilechooser
@@ -605,20 +886,9 @@ else
 fi
 "#;
 
-  fn detect_terminal() -> Option<String> {
+  fn detect_terminal(runner: &dyn ProcessRunner) -> Option<String> {
     let terminals = ["ghostty", "kitty", "alacritty", "foot", "wezterm"];
-    for term in &terminals {
-      if std::process::Command::new("which")
-        .arg(term)
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()
-        .is_ok_and(|s| s.success())
-      {
-        return Some(term.to_string());
-      }
-    }
-    None
+    terminals.iter().find(|term| runner.exists(term)).map(|term| term.to_string())
   }
 
   fn find_portal_file() -> bool {
@@ -643,7 +913,7 @@ fi
       );
     }
 
-    let term = detect_terminal().ok_or_else(|| {
+    let term = detect_terminal(&SystemRunner).ok_or_else(|| {
       anyhow::anyhow!(
         "No supported terminal emulator found.\n\
          Install one of: ghostty, kitty, alacritty, foot, wezterm"
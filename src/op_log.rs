@@ -0,0 +1,147 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::properties;
+use crate::paths;
+
+/// One file operation as written to the log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpRecord {
+  /// Seconds since the Unix epoch
+  pub time: u64,
+  pub user: String,
+  /// What was done, e.g. `"delete"` or `"move"`
+  pub op: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub from: Option<PathBuf>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub to: Option<PathBuf>,
+  /// Anything else worth knowing, like the new mode of a chmod
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub detail: Option<String>,
+}
+
+impl OpRecord {
+  /// `op` done right now by the current user.
+  pub fn now(op: &str) -> Self {
+    Self {
+      time: now_secs(),
+      user: properties::current_user_name(),
+      op: op.to_string(),
+      from: None,
+      to: None,
+      detail: None,
+    }
+  }
+
+  pub fn from(mut self, path: &Path) -> Self {
+    self.from = Some(path.to_path_buf());
+    self
+  }
+
+  pub fn to(mut self, path: &Path) -> Self {
+    self.to = Some(path.to_path_buf());
+    self
+  }
+
+  pub fn detail(mut self, detail: impl Into<String>) -> Self {
+    self.detail = Some(detail.into());
+    self
+  }
+}
+
+fn now_secs() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Start of the current day (UTC) in epoch seconds.
+pub fn today_start() -> u64 {
+  let now = now_secs();
+  now - now % 86400
+}
+
+/// Append-only audit trail of file operations, one JSON object per line in
+/// `operations.log` in the state dir. Several instances can share the file
+/// since each record is a single append.
+pub struct OpLog {
+  path: PathBuf,
+  pub enabled: bool,
+}
+
+impl OpLog {
+  pub fn new(path: PathBuf, enabled: bool) -> Self {
+    Self { path, enabled }
+  }
+
+  pub fn default_path() -> PathBuf {
+    paths::state_dir().join("operations.log")
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Write `record` when logging is on.
+  pub fn append(&self, record: &OpRecord) -> Result<()> {
+    if !self.enabled {
+      return Ok(());
+    }
+    if let Some(dir) = self.path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+  }
+
+  /// Records at or after `since` (epoch seconds), oldest first. Lines that
+  /// don't parse are skipped.
+  pub fn read_since(&self, since: u64) -> Vec<OpRecord> {
+    let Ok(content) = std::fs::read_to_string(&self.path) else {
+      return Vec::new();
+    };
+    content
+      .lines()
+      .filter_map(|line| serde_json::from_str::<OpRecord>(line).ok())
+      .filter(|record| record.time >= since)
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_append_and_read_since() {
+    let dir = std::env::temp_dir().join(format!("tfl_op_log_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let log = OpLog::new(dir.join("operations.log"), true);
+
+    let mut old = OpRecord::now("delete").from(Path::new("/tmp/old.txt"));
+    old.time = 1000;
+    log.append(&old).unwrap();
+    log.append(&OpRecord::now("move").from(Path::new("/a")).to(Path::new("/b"))).unwrap();
+    log.append(&OpRecord::now("chmod").from(Path::new("/c")).detail("755")).unwrap();
+    std::fs::OpenOptions::new().append(true).open(log.path()).unwrap().write_all(b"garbage\n").unwrap();
+
+    assert_eq!(log.read_since(0).len(), 3);
+    let today = log.read_since(today_start());
+    assert_eq!(today.len(), 2);
+    assert_eq!(today[0].op, "move");
+    assert_eq!(today[0].to.as_deref(), Some(Path::new("/b")));
+    assert_eq!(today[1].detail.as_deref(), Some("755"));
+    assert!(!today[0].user.is_empty());
+
+    let off = OpLog::new(dir.join("off.log"), false);
+    off.append(&OpRecord::now("delete")).unwrap();
+    assert!(!off.path().exists());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,220 @@
+//! Values worked out for paths on background threads and kept until the
+//! path changes: the child counts, directory sizes and SELinux flags shown
+//! in the tree. Each path is sent to the workers once; a change under or
+//! above it sends it again when it is next requested, and a path already
+//! being worked on is not sent twice.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+
+/// Paths with what is cached for each, or with the new value (None when
+/// there is none, e.g. the path is gone).
+pub type Batch<V> = Vec<(PathBuf, Option<V>)>;
+
+/// Works out a batch on a worker thread.
+pub type Work<V> = Arc<dyn Fn(Batch<V>) -> Batch<V> + Send + Sync>;
+
+type Worker<V> = (mpsc::Sender<Batch<V>>, mpsc::Receiver<Batch<V>>);
+
+pub struct PathCache<V> {
+  pub enabled: bool,
+  values: HashMap<PathBuf, V>,
+  /// Sent to the workers and unchanged since, in flight or done
+  requested: HashSet<PathBuf>,
+  in_flight: HashSet<PathBuf>,
+  /// Changed while in flight: sent again once the reply is in
+  stale: HashSet<PathBuf>,
+  /// Threads working at once; with more than one each path is sent alone
+  /// so the threads share the work
+  workers: usize,
+  worker: Option<Worker<V>>,
+}
+
+impl<V: Clone + PartialEq + Send + 'static> PathCache<V> {
+  pub fn new(enabled: bool, workers: usize) -> Self {
+    Self {
+      enabled,
+      values: HashMap::new(),
+      requested: HashSet::new(),
+      in_flight: HashSet::new(),
+      stale: HashSet::new(),
+      workers,
+      worker: None,
+    }
+  }
+
+  pub fn get(&self, path: &Path) -> Option<&V> {
+    if !self.enabled {
+      return None;
+    }
+    self.values.get(path)
+  }
+
+  /// Send the paths not requested since they last changed to the workers.
+  /// `work` is only called to start the workers the first time.
+  pub fn request<'a>(&mut self, paths: impl IntoIterator<Item = &'a Path>, work: impl FnOnce() -> Work<V>) {
+    if !self.enabled {
+      return;
+    }
+    let batch: Batch<V> = paths
+      .into_iter()
+      .filter(|p| !self.requested.contains(*p))
+      .map(|p| (p.to_path_buf(), self.values.get(p).cloned()))
+      .collect();
+    if batch.is_empty() {
+      return;
+    }
+    let workers = self.workers;
+    let (tx, _) = self.worker.get_or_insert_with(|| spawn_workers(workers, work()));
+    let batches = if workers > 1 { batch.into_iter().map(|entry| vec![entry]).collect() } else { vec![batch] };
+    for batch in batches {
+      let paths: Vec<PathBuf> = batch.iter().map(|(p, _)| p.clone()).collect();
+      if tx.send(batch).is_ok() {
+        self.requested.extend(paths.iter().cloned());
+        self.in_flight.extend(paths);
+      }
+    }
+  }
+
+  /// Work out the paths inside or above any of `changed` again as they are
+  /// next requested. Known values stay shown meanwhile.
+  pub fn invalidate(&mut self, changed: &[PathBuf]) {
+    let affected = |p: &PathBuf| changed.iter().any(|c| p.starts_with(c) || c.starts_with(p));
+    let stale: Vec<PathBuf> = self.requested.iter().filter(|p| affected(p)).cloned().collect();
+    for path in stale {
+      if self.in_flight.contains(&path) {
+        self.stale.insert(path);
+      } else {
+        self.requested.remove(&path);
+      }
+    }
+  }
+
+  /// Work out every path again as it is next requested.
+  pub fn invalidate_all(&mut self) {
+    self.requested.retain(|p| self.in_flight.contains(p));
+    self.stale.extend(self.in_flight.iter().cloned());
+  }
+
+  /// Take finished values. Returns true if any changed.
+  pub fn poll(&mut self) -> bool {
+    let Some((_, rx)) = &self.worker else {
+      return false;
+    };
+    let mut changed = false;
+    while let Ok(batch) = rx.try_recv() {
+      for (path, value) in batch {
+        self.in_flight.remove(&path);
+        if self.stale.remove(&path) {
+          self.requested.remove(&path);
+        }
+        match value {
+          Some(value) => changed |= self.values.insert(path, value.clone()) != Some(value),
+          None => changed |= self.values.remove(&path).is_some(),
+        }
+      }
+    }
+    changed
+  }
+}
+
+fn spawn_workers<V: Send + 'static>(workers: usize, work: Work<V>) -> Worker<V> {
+  let (request_tx, request_rx) = mpsc::channel::<Batch<V>>();
+  let (reply_tx, reply_rx) = mpsc::channel();
+  let request_rx = Arc::new(Mutex::new(request_rx));
+  for _ in 0..workers.max(1) {
+    let request_rx = Arc::clone(&request_rx);
+    let reply_tx = reply_tx.clone();
+    let work = Arc::clone(&work);
+    std::thread::spawn(move || {
+      loop {
+        // The lock is only held while waiting for the next batch
+        let next = request_rx.lock().map(|rx| rx.recv());
+        let Ok(Ok(batch)) = next else {
+          break;
+        };
+        if reply_tx.send(work(batch)).is_err() {
+          break;
+        }
+      }
+    });
+  }
+  (request_tx, reply_rx)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::time::{Duration, Instant};
+
+  fn wait(cache: &mut PathCache<usize>, done: impl Fn(&PathCache<usize>) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !done(cache) {
+      assert!(Instant::now() < deadline);
+      cache.poll();
+      std::thread::sleep(Duration::from_millis(2));
+    }
+  }
+
+  #[test]
+  fn test_requests_once_until_a_related_path_changes() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let work = move || -> Work<usize> {
+      let counter = Arc::clone(&counter);
+      Arc::new(move |batch: Batch<usize>| {
+        counter.fetch_add(batch.len(), Ordering::SeqCst);
+        batch.into_iter().map(|(p, _)| (p.clone(), Some(p.components().count()))).collect()
+      })
+    };
+    let (a, b, other) = (Path::new("/r/a"), Path::new("/r/a/b"), Path::new("/r/other"));
+    let mut cache = PathCache::new(true, 2);
+    cache.request([a, b, other], &work);
+    cache.request([a, b], &work);
+    wait(&mut cache, |c| c.in_flight.is_empty());
+    assert_eq!(cache.get(b), Some(&4));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    // A change under `a/b` touches it and its ancestors only
+    cache.invalidate(&[PathBuf::from("/r/a/b/new.txt")]);
+    cache.request([a, b, other], &work);
+    wait(&mut cache, |c| c.in_flight.is_empty());
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+    cache.invalidate_all();
+    cache.request([other], &work);
+    wait(&mut cache, |c| c.in_flight.is_empty());
+    assert_eq!(calls.load(Ordering::SeqCst), 6);
+
+    cache.enabled = false;
+    assert_eq!(cache.get(b), None);
+  }
+
+  #[test]
+  fn test_change_while_in_flight_sends_the_path_again_after_the_reply() {
+    let (tx, rx) = mpsc::channel::<()>();
+    let gate = Arc::new(Mutex::new(rx));
+    let work = move || -> Work<usize> {
+      let gate = Arc::clone(&gate);
+      Arc::new(move |batch: Batch<usize>| {
+        let _ = gate.lock().unwrap().recv();
+        batch.into_iter().map(|(p, _)| (p, Some(1))).collect()
+      })
+    };
+    let dir = Path::new("/r/dir");
+    let mut cache = PathCache::new(true, 1);
+    cache.request([dir], &work);
+    cache.invalidate(&[dir.to_path_buf()]);
+    // Still in flight: not sent twice
+    cache.request([dir], &work);
+    assert_eq!(cache.in_flight.len(), 1);
+    tx.send(()).unwrap();
+    wait(&mut cache, |c| c.in_flight.is_empty());
+    assert!(!cache.requested.contains(dir));
+    cache.request([dir], &work);
+    assert!(cache.in_flight.contains(dir));
+    tx.send(()).unwrap();
+  }
+}
@@ -0,0 +1,181 @@
+//! Writing tfl's own state files: favorites, saved layouts, bookmarks,
+//! input history and the session journal. Every write goes to a temp file
+//! renamed over the target, so a reader or a crash never sees half a file,
+//! and changes are coalesced so a burst of them costs one write.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Quiet period after the last change before a state file is written.
+pub const WRITE_DELAY: Duration = Duration::from_millis(500);
+/// Longest a change waits while more keep coming.
+pub const MAX_WRITE_DELAY: Duration = Duration::from_secs(5);
+/// How long after writing a watched file its change events are our own.
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Write `contents` to a temp file next to `path`, then rename it into place.
+/// A symlink (as dotfile managers leave) has its target replaced instead,
+/// and a file already there keeps its permissions.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+  let path = &std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+  let permissions = std::fs::metadata(path).map(|m| m.permissions()).ok();
+  let dir = path.parent().unwrap_or(Path::new("."));
+  std::fs::create_dir_all(dir)?;
+  let name = path.file_name().unwrap_or_default().to_string_lossy();
+  let tmp = dir.join(format!(".{name}.{}.tmp", std::process::id()));
+  let result = std::fs::File::create(&tmp)
+    .and_then(|mut file| file.write_all(contents.as_ref()))
+    .and_then(|()| permissions.map_or(Ok(()), |p| std::fs::set_permissions(&tmp, p)))
+    .and_then(|()| std::fs::rename(&tmp, path));
+  if result.is_err() {
+    let _ = std::fs::remove_file(&tmp);
+  }
+  result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateFile {
+  Favorites,
+  Layouts,
+  Bookmarks,
+  History,
+  Session,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Dirty {
+  first: Instant,
+  last: Instant,
+}
+
+/// Which state files have unsaved changes, and when each watched one was
+/// last written so the config watcher can ignore our own writes.
+#[derive(Debug, Default)]
+pub struct StateWriter {
+  dirty: HashMap<StateFile, Dirty>,
+  watched_written: HashMap<PathBuf, Instant>,
+}
+
+impl StateWriter {
+  pub fn mark(&mut self, file: StateFile) {
+    self.mark_at(file, Instant::now());
+  }
+
+  pub fn mark_at(&mut self, file: StateFile, now: Instant) {
+    self.dirty.entry(file).and_modify(|d| d.last = now).or_insert(Dirty { first: now, last: now });
+  }
+
+  pub fn is_dirty(&self, file: StateFile) -> bool {
+    self.dirty.contains_key(&file)
+  }
+
+  /// Clear `file`, returning whether it had unsaved changes.
+  pub fn take(&mut self, file: StateFile) -> bool {
+    self.dirty.remove(&file).is_some()
+  }
+
+  /// Files quiet for `WRITE_DELAY`, or dirty for `MAX_WRITE_DELAY`; they
+  /// count as clean afterwards.
+  pub fn take_due(&mut self, now: Instant) -> Vec<StateFile> {
+    let due: Vec<StateFile> = self
+      .dirty
+      .iter()
+      .filter(|(_, d)| now - d.last >= WRITE_DELAY || now - d.first >= MAX_WRITE_DELAY)
+      .map(|(file, _)| *file)
+      .collect();
+    for file in &due {
+      self.dirty.remove(file);
+    }
+    due
+  }
+
+  pub fn take_all(&mut self) -> Vec<StateFile> {
+    self.dirty.drain().map(|(file, _)| file).collect()
+  }
+
+  pub fn wrote_watched(&mut self, path: &Path, now: Instant) {
+    self.watched_written.insert(path.to_path_buf(), now);
+  }
+
+  /// Whether a config watcher event for `path` at `now` is likely our own
+  /// write. Other files changing meanwhile still count.
+  pub fn recently_wrote_watched(&self, path: &Path, now: Instant) -> bool {
+    self.watched_written.get(path).is_some_and(|&at| now - at <= OWN_WRITE_WINDOW)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_write_atomic_replaces_file() {
+    let dir = std::env::temp_dir().join(format!("tfl_persist_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("state.json");
+    write_atomic(&path, "one").unwrap();
+    write_atomic(&path, "two").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_write_atomic_follows_symlinks_and_keeps_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("tfl_persist_link_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("dotfiles")).unwrap();
+    let target = dir.join("dotfiles/config.toml");
+    std::fs::write(&target, "old").unwrap();
+    std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o600)).unwrap();
+    let link = dir.join("config.toml");
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    write_atomic(&link, "new").unwrap();
+    assert!(link.symlink_metadata().unwrap().is_symlink());
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+    assert_eq!(std::fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o600);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_changes_are_coalesced_until_quiet() {
+    let start = Instant::now();
+    let mut writer = StateWriter::default();
+    writer.mark_at(StateFile::Favorites, start);
+    writer.mark_at(StateFile::Favorites, start + Duration::from_millis(300));
+    assert!(writer.take_due(start + Duration::from_millis(600)).is_empty());
+    assert_eq!(writer.take_due(start + Duration::from_millis(800)), vec![StateFile::Favorites]);
+    assert!(!writer.is_dirty(StateFile::Favorites));
+  }
+
+  #[test]
+  fn test_constant_changes_still_written() {
+    let start = Instant::now();
+    let mut writer = StateWriter::default();
+    let mut now = start;
+    while now - start < MAX_WRITE_DELAY {
+      writer.mark_at(StateFile::Session, now);
+      assert!(writer.take_due(now).is_empty());
+      now += Duration::from_millis(100);
+    }
+    writer.mark_at(StateFile::Session, now);
+    assert_eq!(writer.take_due(now), vec![StateFile::Session]);
+  }
+
+  #[test]
+  fn test_recently_wrote_watched() {
+    let start = Instant::now();
+    let mut writer = StateWriter::default();
+    let favorites = Path::new("/state/favorites");
+    assert!(!writer.recently_wrote_watched(favorites, start));
+    writer.wrote_watched(favorites, start);
+    assert!(writer.recently_wrote_watched(favorites, start + Duration::from_millis(200)));
+    assert!(!writer.recently_wrote_watched(Path::new("/config/config.toml"), start + Duration::from_millis(200)));
+    assert!(!writer.recently_wrote_watched(favorites, start + Duration::from_secs(2)));
+  }
+}
@@ -0,0 +1,323 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use ratatui::style::Style;
+
+use super::metadata::get_file_metadata_with_lines;
+use super::text::SyntaxHighlighter;
+use super::{PreviewContent, PreviewType, get_extension};
+use crate::theme::Theme;
+
+/// Bytes read from the end of the file when following starts, or when more
+/// than this was appended at once
+const TAIL_BYTES: u64 = 256 * 1024;
+/// Most lines kept; older ones scroll out the top
+const MAX_LINES: usize = 1000;
+/// How long appended lines stay highlighted
+const FRESH_FOR: Duration = Duration::from_secs(2);
+
+/// A text file followed like `tail -f`: lines appended to it are read on
+/// each tick and shown at the bottom of the preview, highlighted for a moment.
+///
+/// The lines before a large file's tail are counted on a worker thread;
+/// until then the tail is numbered from its own start.
+pub struct Follow {
+  pub path: PathBuf,
+  pub content: PreviewContent,
+  /// Bytes of the file read so far, up to the end of its last complete line
+  offset: u64,
+  /// Text of the kept lines, to number them again once the count arrives
+  raw: VecDeque<String>,
+  /// Lines known to come before the tail, and the count of the rest of
+  /// them running in the background
+  counting: Option<(usize, mpsc::Receiver<usize>)>,
+  /// How many of the last lines are highlighted as new, and since when
+  fresh: usize,
+  fresh_at: Instant,
+}
+
+impl Follow {
+  /// Start following `path` from its last lines.
+  pub fn start(path: &Path, highlighter: &SyntaxHighlighter) -> io::Result<Self> {
+    let mut follow = Self {
+      path: path.to_path_buf(),
+      content: empty_content(path),
+      offset: 0,
+      raw: VecDeque::new(),
+      counting: None,
+      fresh: 0,
+      fresh_at: Instant::now(),
+    };
+    follow.read_tail(highlighter)?;
+    Ok(follow)
+  }
+
+  /// Read what was appended since the last call. Returns true when the
+  /// shown lines changed, including new ones no longer being highlighted.
+  pub fn poll(&mut self, highlighter: &SyntaxHighlighter, theme: &Theme, now: Instant) -> bool {
+    let mut changed = self.fade(now);
+    changed |= self.check_counted(highlighter, theme);
+    let Ok(len) = std::fs::metadata(&self.path).map(|m| m.len()) else {
+      return changed;
+    };
+    if len < self.offset || len - self.offset > TAIL_BYTES {
+      // Truncated, replaced, or too much at once: start over from the end
+      return self.read_tail(highlighter).is_ok() || changed;
+    }
+    if len > self.offset
+      && let Ok((text, offset)) = read_lines_from(&self.path, self.offset)
+      && !text.is_empty()
+    {
+      self.offset = offset;
+      self.append(&text, highlighter, theme, now);
+      changed = true;
+    }
+    changed
+  }
+
+  /// Line number of the first line kept
+  pub fn first_line(&self) -> usize {
+    self.content.line_count + 1 - self.content.lines.len()
+  }
+
+  /// Scroll offset that shows the last lines in a pane `rows` high
+  pub fn bottom(&self, rows: usize) -> usize {
+    self.content.lines.len().saturating_sub(rows.max(1))
+  }
+
+  fn read_tail(&mut self, highlighter: &SyntaxHighlighter) -> io::Result<()> {
+    let file = File::open(&self.path)?;
+    let len = file.metadata()?.len();
+    let mut offset = 0;
+    self.counting = if len > TAIL_BYTES {
+      // Begin at the first whole line of the tail
+      let start = len - TAIL_BYTES;
+      let mut reader = BufReader::new(&file);
+      reader.seek(SeekFrom::Start(start))?;
+      let mut partial = Vec::new();
+      let skipped = reader.read_until(b'\n', &mut partial)?;
+      offset = start + skipped as u64;
+      // After a burst of appends only what was skipped needs counting
+      let (from, before) = match self.counting {
+        None if self.offset <= offset => (self.offset, self.content.line_count),
+        _ => (0, 0),
+      };
+      Some((before, count_newlines_async(&self.path, from, offset)))
+    } else {
+      None
+    };
+    let (text, end) = read_lines_from(&self.path, offset)?;
+    let mut lines = highlighter.highlight_from(&text, &self.content.extension, 1);
+    let line_count = lines.len();
+    lines.drain(..lines.len().saturating_sub(MAX_LINES));
+    self.raw = text.lines().map(str::to_string).collect();
+    self.raw.drain(..self.raw.len().saturating_sub(MAX_LINES));
+
+    self.content.lines = lines;
+    self.content.line_count = line_count;
+    self.content.file_size = len;
+    self.content.metadata = get_file_metadata_with_lines(&self.path, line_count);
+    self.offset = end;
+    self.fresh = 0;
+    Ok(())
+  }
+
+  /// Number the lines from where they are in the file once the lines
+  /// before the tail are counted.
+  fn check_counted(&mut self, highlighter: &SyntaxHighlighter, theme: &Theme) -> bool {
+    let Some((before, ref rx)) = self.counting else {
+      return false;
+    };
+    let counted = match rx.try_recv() {
+      Ok(counted) => counted,
+      Err(mpsc::TryRecvError::Empty) => return false,
+      Err(mpsc::TryRecvError::Disconnected) => {
+        self.counting = None;
+        return false;
+      }
+    };
+    self.counting = None;
+    self.content.line_count += before + counted;
+    self.content.metadata = get_file_metadata_with_lines(&self.path, self.content.line_count);
+    let text: String = self.raw.iter().flat_map(|line| [line.as_str(), "\n"]).collect();
+    let mut lines = highlighter.highlight_from(&text, &self.content.extension, self.first_line());
+    let total = lines.len();
+    let style = Style::default().bg(theme.bg_selected);
+    for line in &mut lines[total - self.fresh.min(total)..] {
+      line.style = style;
+    }
+    self.content.lines = lines;
+    true
+  }
+
+  fn append(&mut self, text: &str, highlighter: &SyntaxHighlighter, theme: &Theme, now: Instant) {
+    let new_lines = highlighter.highlight_from(text, &self.content.extension, self.content.line_count + 1);
+    let added = new_lines.len();
+    let style = Style::default().bg(theme.bg_selected);
+    let lines = &mut self.content.lines;
+    lines.extend(new_lines.into_iter().map(|line| line.style(style)));
+    lines.drain(..lines.len().saturating_sub(MAX_LINES));
+    self.raw.extend(text.lines().map(str::to_string));
+    self.raw.drain(..self.raw.len().saturating_sub(MAX_LINES));
+
+    self.content.line_count += added;
+    self.content.file_size = self.offset;
+    self.content.metadata = get_file_metadata_with_lines(&self.path, self.content.line_count);
+    self.fresh = (self.fresh + added).min(lines.len());
+    self.fresh_at = now;
+  }
+
+  /// Drop the highlight from lines that are no longer new.
+  fn fade(&mut self, now: Instant) -> bool {
+    if self.fresh == 0 || now.duration_since(self.fresh_at) < FRESH_FOR {
+      return false;
+    }
+    let total = self.content.lines.len();
+    for line in &mut self.content.lines[total - self.fresh..] {
+      line.style = Style::default();
+    }
+    self.fresh = 0;
+    true
+  }
+}
+
+fn empty_content(path: &Path) -> PreviewContent {
+  PreviewContent {
+    lines: Vec::new(),
+    preview_type: PreviewType::Text,
+    line_count: 0,
+    file_size: 0,
+    extension: get_extension(path),
+    metadata: None,
+    image_metadata: None,
+    git_commits: Vec::new(),
+    blame_data: None,
+    raw_lines: None,
+    is_structured: false,
+    diff_hunks: Vec::new(),
+    side_by_side: None,
+    file_diff: None,
+  }
+}
+
+/// Complete lines of `path` from byte `offset` on, and the offset after them;
+/// a line still being written is left for the next read.
+fn read_lines_from(path: &Path, offset: u64) -> io::Result<(String, u64)> {
+  let mut file = File::open(path)?;
+  file.seek(SeekFrom::Start(offset))?;
+  let mut bytes = Vec::new();
+  file.read_to_end(&mut bytes)?;
+  let end = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+  bytes.truncate(end);
+  Ok((String::from_utf8_lossy(&bytes).into_owned(), offset + end as u64))
+}
+
+/// Count the newlines in bytes `from..to` of `path` on a worker thread.
+fn count_newlines_async(path: &Path, from: u64, to: u64) -> mpsc::Receiver<usize> {
+  let (tx, rx) = mpsc::channel();
+  let path = path.to_path_buf();
+  std::thread::spawn(move || {
+    if let Ok(count) = count_newlines(&path, from, to) {
+      let _ = tx.send(count);
+    }
+  });
+  rx
+}
+
+fn count_newlines(path: &Path, from: u64, to: u64) -> io::Result<usize> {
+  let mut file = File::open(path)?;
+  file.seek(SeekFrom::Start(from))?;
+  let mut reader = BufReader::new(file.take(to.saturating_sub(from)));
+  let mut count = 0;
+  loop {
+    let buf = reader.fill_buf()?;
+    if buf.is_empty() {
+      return Ok(count);
+    }
+    count += buf.iter().filter(|&&b| b == b'\n').count();
+    let n = buf.len();
+    reader.consume(n);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Write;
+
+  fn text(content: &PreviewContent) -> Vec<String> {
+    content.lines.iter().map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect()).collect()
+  }
+
+  #[test]
+  fn test_follow_reads_appended_lines_and_highlights_them() {
+    let path = std::env::temp_dir().join(format!("tfl_follow_{}.log", std::process::id()));
+    std::fs::write(&path, "one\ntwo\n").unwrap();
+    let highlighter = SyntaxHighlighter::new("base16-ocean.dark");
+    let theme = Theme::dark();
+    let mut follow = Follow::start(&path, &highlighter).unwrap();
+    assert_eq!(text(&follow.content), ["   1 one", "   2 two"]);
+
+    let now = Instant::now();
+    assert!(!follow.poll(&highlighter, &theme, now));
+    // A line still being written waits for its newline
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+    write!(file, "three\nfou").unwrap();
+    assert!(follow.poll(&highlighter, &theme, now));
+    assert_eq!(text(&follow.content).last().unwrap(), "   3 three");
+    writeln!(file, "r").unwrap();
+    assert!(follow.poll(&highlighter, &theme, now));
+    assert_eq!(text(&follow.content), ["   1 one", "   2 two", "   3 three", "   4 four"]);
+    assert_eq!(follow.content.line_count, 4);
+    assert_eq!(follow.bottom(3), 1);
+
+    let lines = &follow.content.lines;
+    assert_eq!(lines[1].style, Style::default());
+    assert_eq!(lines[2].style.bg, Some(theme.bg_selected));
+    assert!(follow.poll(&highlighter, &theme, now + FRESH_FOR));
+    assert!(follow.content.lines.iter().all(|l| l.style == Style::default()));
+
+    // Truncated, as by log rotation: read again from the start
+    std::fs::write(&path, "fresh\n").unwrap();
+    assert!(follow.poll(&highlighter, &theme, now + FRESH_FOR));
+    assert_eq!(text(&follow.content), ["   1 fresh"]);
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn test_follow_starts_at_the_tail_of_large_files() {
+    let path = std::env::temp_dir().join(format!("tfl_follow_large_{}.log", std::process::id()));
+    let content: String = (1..=40_000).map(|i| format!("line {i}\n")).collect();
+    std::fs::write(&path, &content).unwrap();
+    let highlighter = SyntaxHighlighter::new("base16-ocean.dark");
+    let theme = Theme::dark();
+    let mut follow = Follow::start(&path, &highlighter).unwrap();
+    assert_eq!(follow.content.lines.len(), MAX_LINES);
+    // Numbered from the start of the tail until the lines before it are counted
+    assert_eq!(text(&follow.content).last().unwrap(), &format!("{} line 40000", follow.content.line_count));
+    let wait_counted = |follow: &mut Follow| {
+      while follow.counting.is_some() {
+        follow.poll(&highlighter, &theme, Instant::now());
+        std::thread::sleep(Duration::from_millis(5));
+      }
+    };
+    wait_counted(&mut follow);
+    assert_eq!(follow.content.line_count, 40_000);
+    assert_eq!(follow.first_line(), 39_001);
+    assert_eq!(text(&follow.content).last().unwrap(), "40000 line 40000");
+    assert_eq!(text(&follow.content)[0], "39001 line 39001");
+
+    // More than the tail at once: only the skipped lines are counted
+    let more: String = (40_001..=80_000).map(|i| format!("line {i}\n")).collect();
+    std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(more.as_bytes()).unwrap();
+    follow.poll(&highlighter, &theme, Instant::now());
+    wait_counted(&mut follow);
+    assert_eq!(follow.content.line_count, 80_000);
+    assert_eq!(text(&follow.content).last().unwrap(), "80000 line 80000");
+    let _ = std::fs::remove_file(&path);
+  }
+}
@@ -1,15 +1,22 @@
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Cursor};
 use std::path::Path;
 use std::sync::mpsc;
 use std::thread;
 
-use image::DynamicImage;
+use image::{DynamicImage, RgbImage};
 use ratatui_image::picker::Picker;
 use ratatui_image::protocol::StatefulProtocol;
 
+/// Largest side of the halfblock thumbnail, in pixels; it is scaled down
+/// further to fit the pane when drawn
+const THUMBNAIL_MAX: u32 = 160;
+
 pub enum ImageLoadResult {
   Loaded(StatefulProtocol),
+  /// Downscaled pixels for drawing with halfblocks, when the terminal has
+  /// no graphics protocol
+  Thumbnail(RgbImage),
   Error(String),
 }
 
@@ -30,17 +37,78 @@ pub fn load_image_async(
   path: &Path,
   picker: &Picker,
 ) -> mpsc::Receiver<ImageLoadResult> {
-  let (tx, rx) = mpsc::channel();
   let path = path.to_path_buf();
-  let picker = picker.clone();
+  decode_async(picker, move || open(&path))
+}
 
+/// Decode a small thumbnail of an image in a background thread
+pub fn load_thumbnail_async(path: &Path) -> mpsc::Receiver<ImageLoadResult> {
+  let path = path.to_path_buf();
+  thumbnail_async(move || open(&path))
+}
+
+/// Decode a small thumbnail of in-memory image data in a background thread
+pub fn load_thumbnail_bytes_async(bytes: Vec<u8>, path: &Path) -> mpsc::Receiver<ImageLoadResult> {
+  let jxl = is_jxl(path);
+  thumbnail_async(move || decode_bytes(bytes, jxl))
+}
+
+fn thumbnail_async<F>(decode: F) -> mpsc::Receiver<ImageLoadResult>
+where
+  F: FnOnce() -> Result<DynamicImage, String> + Send + 'static,
+{
+  let (tx, rx) = mpsc::channel();
   thread::spawn(move || {
-    let img_result = if is_jxl(&path) {
-      load_jxl(&path)
-    } else {
-      image::open(&path).map_err(|e| format!("Failed to load image: {e}"))
+    let result = match decode() {
+      // Only ever scaled down, so small images keep their pixels
+      Ok(img) if img.width() > THUMBNAIL_MAX || img.height() > THUMBNAIL_MAX => {
+        ImageLoadResult::Thumbnail(img.thumbnail(THUMBNAIL_MAX, THUMBNAIL_MAX).to_rgb8())
+      }
+      Ok(img) => ImageLoadResult::Thumbnail(img.to_rgb8()),
+      Err(e) => ImageLoadResult::Error(e),
     };
-    let result = match img_result {
+    let _ = tx.send(result);
+  });
+  rx
+}
+
+fn open(path: &Path) -> Result<DynamicImage, String> {
+  if is_jxl(path) {
+    load_jxl(path)
+  } else {
+    image::open(path).map_err(|e| format!("Failed to load image: {e}"))
+  }
+}
+
+/// Decode in-memory image data (e.g. a past git revision of `path`) in a background thread
+pub fn load_image_bytes_async(
+  bytes: Vec<u8>,
+  path: &Path,
+  picker: &Picker,
+) -> mpsc::Receiver<ImageLoadResult> {
+  let jxl = is_jxl(path);
+  decode_async(picker, move || decode_bytes(bytes, jxl))
+}
+
+fn decode_bytes(bytes: Vec<u8>, jxl: bool) -> Result<DynamicImage, String> {
+  if jxl {
+    let decoder =
+      jxl_oxide::integration::JxlDecoder::new(Cursor::new(bytes)).map_err(|e| format!("Failed to decode JXL: {e}"))?;
+    DynamicImage::from_decoder(decoder).map_err(|e| format!("Failed to convert JXL to image: {e}"))
+  } else {
+    image::load_from_memory(&bytes).map_err(|e| format!("Failed to load image: {e}"))
+  }
+}
+
+fn decode_async<F>(picker: &Picker, decode: F) -> mpsc::Receiver<ImageLoadResult>
+where
+  F: FnOnce() -> Result<DynamicImage, String> + Send + 'static,
+{
+  let (tx, rx) = mpsc::channel();
+  let picker = picker.clone();
+
+  thread::spawn(move || {
+    let result = match decode() {
       Ok(img) => {
         let protocol = picker.new_resize_protocol(img);
         ImageLoadResult::Loaded(protocol)
@@ -65,4 +133,37 @@ mod tests {
     let result = rx.recv().unwrap();
     assert!(matches!(result, ImageLoadResult::Error(_)));
   }
+
+  #[test]
+  fn test_load_image_bytes() {
+    #[allow(deprecated)]
+    let picker = Picker::from_fontsize((8, 16));
+    let mut png = Vec::new();
+    DynamicImage::new_rgb8(2, 2)
+      .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+      .unwrap();
+    let rx = load_image_bytes_async(png, Path::new("a.png"), &picker);
+    assert!(matches!(rx.recv().unwrap(), ImageLoadResult::Loaded(_)));
+
+    let rx = load_image_bytes_async(b"not an image".to_vec(), Path::new("a.png"), &picker);
+    assert!(matches!(rx.recv().unwrap(), ImageLoadResult::Error(_)));
+  }
+
+  #[test]
+  fn test_load_thumbnail_fits_max_size() {
+    let path = std::env::temp_dir().join(format!("tfl_thumb_{}.png", std::process::id()));
+    DynamicImage::new_rgb8(800, 400).save(&path).unwrap();
+    match load_thumbnail_async(&path).recv().unwrap() {
+      ImageLoadResult::Thumbnail(img) => assert_eq!(img.dimensions(), (THUMBNAIL_MAX, THUMBNAIL_MAX / 2)),
+      _ => panic!("expected a thumbnail"),
+    }
+    let _ = std::fs::remove_file(&path);
+
+    let mut png = Vec::new();
+    DynamicImage::new_rgb8(3, 5).write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+    match load_thumbnail_bytes_async(png, Path::new("a.png")).recv().unwrap() {
+      ImageLoadResult::Thumbnail(img) => assert_eq!(img.dimensions(), (3, 5)),
+      _ => panic!("expected a thumbnail"),
+    }
+  }
 }
@@ -1,112 +1,225 @@
-pub mod archive;
-pub mod blame;
-pub mod diff;
-pub mod directory;
-pub mod hex;
+//! Preview state for the TUI: loading, caching and image rendering on top of
+//! the building blocks in `tfl_core::preview`.
+
+pub mod follow;
 pub mod image;
-pub mod markdown;
-pub mod metadata;
-pub mod structured;
-pub mod text;
+
+pub use tfl_core::preview::{
+  MARKDOWN_EXTENSIONS, PreviewContent, PreviewType, archive, blame, detect_preview_type, diff, directory, get_extension, hex,
+  hover, links, markdown, metadata, notebook, special, structured, text,
+};
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant, SystemTime};
 
-use ratatui::text::Line;
+use ::image::RgbImage;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
 use ratatui_image::picker::Picker;
 use ratatui_image::protocol::StatefulProtocol;
 
-use self::blame::BlameData;
-use self::metadata::{FileMetadata, ImageMetadata, get_file_metadata, get_file_metadata_with_lines, get_image_metadata};
+use self::metadata::{get_file_metadata, get_file_metadata_with_lines, get_image_metadata, render_image_info};
 use self::text::SyntaxHighlighter;
+use crate::fs::{GitStatus, TreeSource};
 use crate::git::{GitCommit, GitRepo};
 use crate::theme::Theme;
 
-const MAX_TEXT_BYTES: u64 = 1024 * 1024; // 1MB
 const MAX_TEXT_LINES: usize = 1000;
 const MAX_HEX_BYTES: usize = 4096;
+/// Text larger than this is highlighted on a worker thread, showing a
+/// placeholder meanwhile
+const ASYNC_TEXT_BYTES: usize = 32 * 1024;
 const CACHE_SIZE: usize = 10;
 const DEBOUNCE_MS: u128 = 80;
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum PreviewType {
-  Text,
-  Markdown,
-  Image,
-  Binary,
-  Directory,
-  Archive,
-  Diff,
-  Empty,
-  TooLarge,
-  Error(String),
-}
-
-pub struct PreviewContent {
-  pub lines: Vec<Line<'static>>,
-  pub preview_type: PreviewType,
-  pub line_count: usize,
-  pub file_size: u64,
-  pub extension: String,
-  pub metadata: Option<FileMetadata>,
-  pub image_metadata: Option<ImageMetadata>,
-  pub git_commits: Vec<GitCommit>,
-  pub blame_data: Option<BlameData>,
-  /// Raw (unformatted) lines for structured data files, if formatting was applied.
-  pub raw_lines: Option<Vec<Line<'static>>>,
-  /// Whether this file is a structured data file (JSON/TOML).
-  pub is_structured: bool,
-  pub diff_hunks: Vec<usize>, // Indices of hunk headers for navigation
+pub const DEFAULT_TIMEOUT_MS: u64 = 2000;
+/// Reads left running after timing out, e.g. on a hung network mount, are
+/// capped at this many; past it previews time out without starting another
+const MAX_READS_IN_FLIGHT: usize = 4;
+/// How many past revisions of an image can be flipped through
+const IMAGE_TIMELINE_LIMIT: usize = 50;
+/// Narrower preview panes fall back to the unified diff layout
+pub const SIDE_BY_SIDE_MIN_WIDTH: u16 = 100;
+
+/// Past git revisions of the previewed image, for flipping through its history
+pub struct ImageTimeline {
+  pub path: PathBuf,
+  /// Commits touching the file, newest first
+  pub commits: Vec<GitCommit>,
+  /// Revision being shown: index into `commits`, `None` for the working copy
+  pub index: Option<usize>,
 }
 
 pub struct PreviewState {
   pub scroll_offset: usize,
+  /// Columns scrolled to the right, when lines aren't wrapped
+  pub h_scroll: usize,
+  /// Whether long lines wrap instead of being cut at the pane edge
+  pub wrap: bool,
   pub current_path: Option<PathBuf>,
   pub content: Option<PreviewContent>,
   pub image_protocol: Option<StatefulProtocol>,
+  /// Halfblock thumbnail of the image, used when there is no picker
+  pub thumbnail: Option<RgbImage>,
+  /// Whether to decode thumbnails when there is no picker
+  pub thumbnails: bool,
   pub image_rx: Option<mpsc::Receiver<self::image::ImageLoadResult>>,
   pub git_commits_rx: Option<mpsc::Receiver<(PathBuf, Vec<GitCommit>)>>,
+  /// Listing of the previewed archive, read in the background
+  pub archive_rx: Option<mpsc::Receiver<(PathBuf, Result<archive::ArchiveListing, String>)>>,
+  /// Preview of a large text or markdown file, highlighted in the background
+  pub text_rx: Option<mpsc::Receiver<(PathBuf, PreviewContent)>>,
+  /// Lines of the previewed file changed since HEAD, diffed in the background
+  pub changes_rx: Option<mpsc::Receiver<(PathBuf, HashMap<usize, diff::LineChange>)>>,
   pub blame_enabled: bool,
   pub markdown_rendered: bool,
   /// Whether to show formatted (pretty-printed) view for structured data.
   pub show_formatted: bool,
-  highlighter: SyntaxHighlighter,
+  /// Whether diffs are shown side by side (old left, new right) when the pane is wide enough
+  pub diff_side_by_side: bool,
+  /// Which versions the diff view compares (unstaged, staged, or all changes)
+  pub diff_mode: diff::DiffMode,
+  pub image_timeline: Option<ImageTimeline>,
+  /// Text file followed like `tail -f`, shown instead of its cached preview
+  pub follow: Option<follow::Follow>,
+  /// Width of the preview content area at the last render
+  pub content_width: u16,
+  /// Preview requests served from / missing the cache, for the debug overlay
+  pub cache_hits: u64,
+  pub cache_misses: u64,
+  highlighter: Arc<SyntaxHighlighter>,
   pub theme: Theme,
   cache: HashMap<PathBuf, PreviewContent>,
   cache_order: Vec<PathBuf>,
   last_request: Option<(PathBuf, Instant)>,
   /// Cache for raw markdown content (when toggling between raw/rendered)
   markdown_raw_cache: HashMap<PathBuf, PreviewContent>,
+  /// Modification time of each cached file when its preview was built
+  cache_mtimes: HashMap<PathBuf, SystemTime>,
+  /// The shown file changed on disk since its preview was built
+  pub stale: bool,
+  /// How long reading a file for its preview may take; zero reads on the
+  /// calling thread without a limit
+  pub timeout: Duration,
+  /// Highlight large text files on a worker thread; off renders them in
+  /// place
+  pub async_text: bool,
+  /// Preview reads still running on worker threads
+  reads_in_flight: Arc<AtomicUsize>,
+  pub size_format: directory::SizeFormat,
+  /// Where files are read from instead of the disk, while browsing inside
+  /// an archive
+  pub source: Option<Arc<dyn TreeSource>>,
+  /// Git status of the file about to be requested; only files with
+  /// uncommitted changes are diffed for the change gutter
+  pub git_status: GitStatus,
 }
 
 impl PreviewState {
   pub fn new(syntax_theme: &str, theme: Theme) -> Self {
     Self {
       scroll_offset: 0,
+      h_scroll: 0,
+      wrap: false,
       current_path: None,
       content: None,
       image_protocol: None,
+      thumbnail: None,
+      thumbnails: true,
       image_rx: None,
       git_commits_rx: None,
+      archive_rx: None,
+      text_rx: None,
+      changes_rx: None,
       blame_enabled: false,
       markdown_rendered: true,
       show_formatted: true,
-      highlighter: SyntaxHighlighter::new(syntax_theme),
+      diff_side_by_side: false,
+      diff_mode: diff::DiffMode::default(),
+      image_timeline: None,
+      follow: None,
+      content_width: 0,
+      cache_hits: 0,
+      cache_misses: 0,
+      highlighter: Arc::new(SyntaxHighlighter::new(syntax_theme)),
       theme,
       cache: HashMap::new(),
       cache_order: Vec::new(),
       last_request: None,
       markdown_raw_cache: HashMap::new(),
+      cache_mtimes: HashMap::new(),
+      stale: false,
+      timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+      async_text: true,
+      reads_in_flight: Arc::new(AtomicUsize::new(0)),
+      size_format: directory::SizeFormat::default(),
+      source: None,
+      git_status: GitStatus::default(),
     }
   }
 
   pub fn set_syntax_theme(&mut self, name: &str) {
-    self.highlighter.set_theme_name(name);
+    self.highlighter_mut().set_theme_name(name);
+    self.invalidate();
+  }
+
+  /// The highlighter, for changing its settings. A new one replaces it
+  /// while a worker is still highlighting with the old one.
+  fn highlighter_mut(&mut self) -> &mut SyntaxHighlighter {
+    if Arc::get_mut(&mut self.highlighter).is_none() {
+      let mut fresh = SyntaxHighlighter::new(self.highlighter.theme_name());
+      fresh.set_line_numbers(self.highlighter.line_numbers());
+      self.highlighter = Arc::new(fresh);
+    }
+    Arc::get_mut(&mut self.highlighter).expect("highlighter was just made unique")
+  }
+
+  pub fn line_numbers(&self) -> bool {
+    self.highlighter.line_numbers()
+  }
+
+  /// Show or hide the line-number gutter of highlighted text. Cached
+  /// previews are dropped to be highlighted again.
+  pub fn toggle_line_numbers(&mut self) {
+    let on = !self.line_numbers();
+    self.highlighter_mut().set_line_numbers(on);
+    if let Some(follow) = self.follow.take() {
+      self.follow = follow::Follow::start(&follow.path, &self.highlighter).ok();
+    }
     self.invalidate();
   }
 
+  /// Scroll so that line `line` (counted from 1) of the previewed file is
+  /// at the top, or say why it can't be shown. Formatted structured data and
+  /// rendered notebooks are switched to the raw view first, whose lines are
+  /// the file's.
+  pub fn goto_line(&mut self, line: usize) -> Result<(), String> {
+    let content = self.get_content().ok_or("Nothing previewed")?;
+    let by_line = match content.preview_type {
+      PreviewType::Text => true,
+      PreviewType::Markdown => !self.markdown_rendered,
+      _ => false,
+    };
+    if !by_line {
+      return Err("Go to line works in text previews".to_string());
+    }
+    if line == 0 || line > content.line_count {
+      return Err(format!("No line {line}: the file has {} lines", content.line_count));
+    }
+    if content.is_structured {
+      self.show_formatted = false;
+    }
+    let first = self.follow.as_ref().filter(|_| self.following()).map_or(1, follow::Follow::first_line);
+    let shown = self.get_display_lines().map_or(0, Vec::len);
+    if line < first || line >= first + shown {
+      return Err(format!("Line {line} is beyond the lines shown in the preview"));
+    }
+    self.scroll_offset = line - first;
+    Ok(())
+  }
+
   pub fn set_theme(&mut self, theme: Theme) {
     self.theme = theme;
     self.invalidate();
@@ -115,6 +228,9 @@ impl PreviewState {
   pub fn toggle_blame(&mut self, git_repo: Option<&GitRepo>) {
     self.blame_enabled = !self.blame_enabled;
     self.scroll_offset = 0;
+    if self.blame_enabled {
+      self.follow = None;
+    }
 
     // Lazily compute blame data on first toggle-on
     if self.blame_enabled
@@ -162,49 +278,110 @@ impl PreviewState {
       return;
     }
 
+    self.drop_unfinished();
+    if self.follow.as_ref().is_some_and(|f| f.path != path) {
+      self.follow = None;
+    }
     self.scroll_offset = 0;
+    self.h_scroll = 0;
     self.image_protocol = None;
+    self.thumbnail = None;
     self.image_rx = None;
     self.git_commits_rx = None;
+    self.image_timeline = None;
+    self.stale = false;
     self.current_path = Some(path.to_path_buf());
 
+    // Drop cached previews of files modified since they were built
+    if self.cache.contains_key(path) && !self.cache_is_fresh(path) {
+      self.cache.remove(path);
+      self.cache_order.retain(|p| p != path);
+    }
+
     // Check cache
     if let Some(cached) = self.cache.get(path) {
+      self.cache_hits += 1;
       // Move to front of cache order
       self.cache_order.retain(|p| p != path);
       self.cache_order.push(path.to_path_buf());
 
       // For images, re-trigger async load since we don't cache the protocol
-      if cached.preview_type == PreviewType::Image
-        && let Some(picker) = picker {
-          self.image_rx = Some(self::image::load_image_async(path, picker));
-        }
+      if cached.preview_type == PreviewType::Image {
+        self.start_image_load(path, picker);
+      }
       return;
     }
 
+    self.cache_misses += 1;
+    let started = Instant::now();
     self.load_preview(path, picker, git_repo);
+    tracing::debug!(path = %path.display(), elapsed = ?started.elapsed(), "preview loaded");
+  }
+
+  /// Decode the image for display: through the picker's graphics protocol,
+  /// or as a halfblock thumbnail when the terminal has none.
+  fn start_image_load(&mut self, path: &Path, picker: Option<&Picker>) {
+    if let Some(picker) = picker {
+      self.image_rx = Some(self::image::load_image_async(path, picker));
+    } else if self.thumbnails {
+      self.image_rx = Some(self::image::load_thumbnail_async(path));
+    }
+  }
+
+  /// Like `start_image_load`, for a past revision of `path` read from git
+  fn start_image_bytes_load(&mut self, bytes: Vec<u8>, path: &Path, picker: Option<&Picker>) {
+    if let Some(picker) = picker {
+      self.image_rx = Some(self::image::load_image_bytes_async(bytes, path, picker));
+    } else if self.thumbnails {
+      self.image_rx = Some(self::image::load_thumbnail_bytes_async(bytes, path));
+    }
   }
 
   fn load_preview(&mut self, path: &Path, picker: Option<&Picker>, git_repo: Option<&GitRepo>) {
-    let preview_type = detect_preview_type(path);
+    let Some(fetched) = fetch_within(path, self.source.clone(), self.timeout, &self.reads_in_flight) else {
+      tracing::warn!(path = %path.display(), timeout = ?self.timeout, "preview timed out");
+      self.insert_cache(path.to_path_buf(), timed_out_content());
+      return;
+    };
 
     // Spawn async git commit loading
     if let Some(repo) = git_repo {
       self.git_commits_rx = Some(load_git_commits_async(repo.root(), path, 3));
     }
     let git_commits = Vec::new();
-    let content = match preview_type {
-      PreviewType::Text => self.load_text(path, &git_commits),
-      PreviewType::Markdown => self.load_markdown(path, &git_commits),
-      PreviewType::Image => {
-        if let Some(picker) = picker {
-          self.image_rx = Some(self::image::load_image_async(path, picker));
+    // Uncommitted changes are marked beside the lines of local text files
+    let repo_root = git_repo
+      .filter(|_| self.source.is_none() && !self.git_status.is_clean())
+      .map(|repo| repo.root().to_path_buf());
+    let content = match fetched {
+      Fetched::Text(preview_type, Ok(text)) if self.async_text && text.len() > ASYNC_TEXT_BYTES => {
+        self.text_rx = Some(render_text_async(path, preview_type.clone(), text, self.text_renderer(), repo_root));
+        Some(loading_content(path, preview_type))
+      }
+      Fetched::Text(PreviewType::Markdown, text) => self.load_markdown(path, text, &git_commits),
+      Fetched::Text(_, text) => {
+        let content = self.load_text(path, text, &git_commits);
+        if let Some(root) = repo_root
+          && content.as_ref().is_some_and(|c| c.preview_type == PreviewType::Text)
+        {
+          self.changes_rx = Some(line_changes_async(root, path));
         }
+        content
+      }
+      Fetched::Bytes(data) => self.load_hex(path, data, &git_commits),
+      Fetched::Directory(summary) => self.load_directory(summary),
+      Fetched::Image(image_metadata) => {
+        self.start_image_load(path, picker);
         let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
         let metadata = get_file_metadata(path);
-        let image_metadata = get_image_metadata(path);
+        // Without a graphics protocol the details are all there is to show
+        let lines = if picker.is_some() {
+          vec![Line::from(" Loading image...")]
+        } else {
+          render_image_info(image_metadata.as_ref(), file_size, &self.theme)
+        };
         Some(PreviewContent {
-          lines: vec![Line::from(" Loading image...")],
+          lines,
           preview_type: PreviewType::Image,
           line_count: 0,
           file_size,
@@ -216,12 +393,15 @@ impl PreviewState {
           raw_lines: None,
           is_structured: false,
           diff_hunks: Vec::new(),
+          side_by_side: None,
+          file_diff: None,
         })
       }
-      PreviewType::Binary => self.load_hex(path, &git_commits),
-      PreviewType::Archive => self.load_archive(path, &git_commits),
-      PreviewType::Directory => self.load_directory(path),
-      PreviewType::TooLarge => {
+      Fetched::Type(PreviewType::Archive) => {
+        self.archive_rx = Some(list_archive_async(path, archive::MAX_LISTED_ENTRIES));
+        self.load_archive(path, &git_commits)
+      }
+      Fetched::Type(PreviewType::TooLarge) => {
         let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
         Some(PreviewContent {
           lines: vec![Line::from(" File too large to preview")],
@@ -236,9 +416,11 @@ impl PreviewState {
           raw_lines: None,
           is_structured: false,
           diff_hunks: Vec::new(),
+          side_by_side: None,
+          file_diff: None,
         })
       }
-      PreviewType::Empty => Some(PreviewContent {
+      Fetched::Type(PreviewType::Empty) => Some(PreviewContent {
         lines: vec![Line::from(" Empty file")],
         preview_type: PreviewType::Empty,
         line_count: 0,
@@ -251,11 +433,34 @@ impl PreviewState {
         raw_lines: None,
         is_structured: false,
         diff_hunks: Vec::new(),
+        side_by_side: None,
+        file_diff: None,
       }),
-      PreviewType::Diff => None, // Diff is handled separately via show_diff
-      PreviewType::Error(ref msg) => Some(PreviewContent {
+      Fetched::Type(PreviewType::Special(kind)) => {
+        let lines = match std::fs::metadata(path) {
+          Ok(meta) => special::render_special(kind, &meta, &self.theme),
+          Err(e) => vec![Line::from(format!(" Error: {e}"))],
+        };
+        Some(PreviewContent {
+          lines,
+          preview_type: PreviewType::Special(kind),
+          line_count: 0,
+          file_size: 0,
+          extension: String::new(),
+          metadata: get_file_metadata(path),
+          image_metadata: None,
+          git_commits,
+          blame_data: None,
+          raw_lines: None,
+          is_structured: false,
+          diff_hunks: Vec::new(),
+          side_by_side: None,
+          file_diff: None,
+        })
+      }
+      Fetched::Type(PreviewType::Error(msg)) => Some(PreviewContent {
         lines: vec![Line::from(format!(" Error: {msg}"))],
-        preview_type: preview_type.clone(),
+        preview_type: PreviewType::Error(msg),
         line_count: 0,
         file_size: 0,
         extension: String::new(),
@@ -266,7 +471,11 @@ impl PreviewState {
         raw_lines: None,
         is_structured: false,
         diff_hunks: Vec::new(),
+        side_by_side: None,
+        file_diff: None,
       }),
+      // Diff is handled separately via show_diff; the rest is always fetched with its content
+      Fetched::Type(_) => None,
     };
 
     if let Some(content) = content {
@@ -274,131 +483,20 @@ impl PreviewState {
     }
   }
 
-  fn load_text(&self, path: &Path, git_commits: &[GitCommit]) -> Option<PreviewContent> {
-    let content = match std::fs::read_to_string(path) {
-      Ok(c) => c,
-      Err(e) => {
-        return Some(PreviewContent {
-          lines: vec![Line::from(format!(" Error reading file: {e}"))],
-          preview_type: PreviewType::Error(e.to_string()),
-          line_count: 0,
-          file_size: 0,
-          extension: String::new(),
-          metadata: None,
-          image_metadata: None,
-          git_commits: Vec::new(),
-          blame_data: None,
-          raw_lines: None,
-          is_structured: false,
-          diff_hunks: Vec::new(),
-        });
-      }
-    };
-
-    let line_count = content.lines().count();
-    let truncated: String = content.lines().take(MAX_TEXT_LINES).collect::<Vec<_>>().join("\n");
-    let ext = get_extension(path);
-    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
-    let metadata = get_file_metadata_with_lines(path, line_count);
-
-    // Check if this is a structured data file (JSON/TOML)
-    let is_structured = structured::is_structured_data(&ext);
-
-    let (lines, raw_lines) = if is_structured {
-      // Try to format the content
-      match structured::format_structured(&content, &ext) {
-        Some(structured::FormatResult::Formatted { content: formatted, extension: fmt_ext }) => {
-          // Truncate formatted content too
-          let fmt_truncated: String = formatted.lines().take(MAX_TEXT_LINES).collect::<Vec<_>>().join("\n");
-          let formatted_lines = self.highlighter.highlight(&fmt_truncated, &fmt_ext);
-          let raw_highlighted = self.highlighter.highlight(&truncated, &ext);
-          (formatted_lines, Some(raw_highlighted))
-        }
-        Some(structured::FormatResult::Error) => {
-          // Formatting failed, show raw content
-          let raw_highlighted = self.highlighter.highlight(&truncated, &ext);
-          (raw_highlighted, None)
-        }
-        None => {
-          // Not a structured format (shouldn't happen given is_structured check)
-          let raw_highlighted = self.highlighter.highlight(&truncated, &ext);
-          (raw_highlighted, None)
-        }
-      }
-    } else {
-      // Regular text file
-      let highlighted = self.highlighter.highlight(&truncated, &ext);
-      (highlighted, None)
-    };
-
-    Some(PreviewContent {
-      lines,
-      preview_type: PreviewType::Text,
-      line_count,
-      file_size,
-      extension: ext,
-      metadata,
-      image_metadata: None,
-      git_commits: git_commits.to_vec(),
-      blame_data: None,
-      raw_lines,
-      is_structured,
-      diff_hunks: Vec::new(),
-    })
+  fn load_text(&self, path: &Path, text: std::io::Result<String>, git_commits: &[GitCommit]) -> Option<PreviewContent> {
+    self.text_renderer().text(path, text, git_commits)
   }
 
-  fn load_markdown(&self, path: &Path, git_commits: &[GitCommit]) -> Option<PreviewContent> {
-    let content = match std::fs::read_to_string(path) {
-      Ok(c) => c,
-      Err(e) => {
-        return Some(PreviewContent {
-          lines: vec![Line::from(format!(" Error reading file: {e}"))],
-          preview_type: PreviewType::Error(e.to_string()),
-          line_count: 0,
-          file_size: 0,
-          extension: String::new(),
-          metadata: None,
-          image_metadata: None,
-          git_commits: Vec::new(),
-          blame_data: None,
-          raw_lines: None,
-          is_structured: false,
-          diff_hunks: Vec::new(),
-        });
-      }
-    };
-
-    let line_count = content.lines().count();
-    let truncated: String = content.lines().take(MAX_TEXT_LINES).collect::<Vec<_>>().join("\n");
-    let ext = get_extension(path);
-    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
-    let metadata = get_file_metadata_with_lines(path, line_count);
-
-    // Render markdown if in rendered mode, otherwise show raw with syntax highlighting
-    let lines = if self.markdown_rendered {
-      markdown::render_markdown(&truncated, &self.highlighter, &self.theme)
-    } else {
-      self.highlighter.highlight(&truncated, &ext)
-    };
+  fn load_markdown(&self, path: &Path, text: std::io::Result<String>, git_commits: &[GitCommit]) -> Option<PreviewContent> {
+    self.text_renderer().markdown(path, text, git_commits)
+  }
 
-    Some(PreviewContent {
-      lines,
-      preview_type: PreviewType::Markdown,
-      line_count,
-      file_size,
-      extension: ext,
-      metadata,
-      image_metadata: None,
-      git_commits: git_commits.to_vec(),
-      blame_data: None,
-      raw_lines: None,
-      is_structured: false,
-      diff_hunks: Vec::new(),
-    })
+  fn text_renderer(&self) -> TextRenderer {
+    TextRenderer { highlighter: self.highlighter.clone(), theme: self.theme.clone(), markdown_rendered: self.markdown_rendered }
   }
 
-  fn load_hex(&self, path: &Path, git_commits: &[GitCommit]) -> Option<PreviewContent> {
-    let data = match std::fs::read(path) {
+  fn load_hex(&self, path: &Path, data: std::io::Result<Vec<u8>>, git_commits: &[GitCommit]) -> Option<PreviewContent> {
+    let data = match data {
       Ok(d) => d,
       Err(e) => {
         return Some(PreviewContent {
@@ -414,6 +512,8 @@ impl PreviewState {
           raw_lines: None,
           is_structured: false,
           diff_hunks: Vec::new(),
+          side_by_side: None,
+          file_diff: None,
         });
       }
     };
@@ -436,12 +536,13 @@ impl PreviewState {
       raw_lines: None,
       is_structured: false,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     })
   }
 
-  fn load_directory(&self, path: &Path) -> Option<PreviewContent> {
-    let summary = directory::summarize_dir(path);
-    let lines = directory::render_dir_summary(&summary, &self.theme);
+  fn load_directory(&self, summary: directory::DirSummary) -> Option<PreviewContent> {
+    let lines = directory::render_dir_summary(&summary, &self.theme, self.size_format);
 
     Some(PreviewContent {
       lines,
@@ -456,6 +557,8 @@ impl PreviewState {
       raw_lines: None,
       is_structured: false,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     })
   }
 
@@ -463,7 +566,9 @@ impl PreviewState {
     let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
     let metadata = get_file_metadata(path);
     let archive_type = archive::archive_type(path).unwrap_or("archive");
-    let lines = archive::render_archive_summary(archive_type, file_size, &self.theme);
+    let mut lines = archive::render_archive_summary(archive_type, file_size, &self.theme);
+    lines.push(Line::from(""));
+    lines.push(Line::from(" Listing contents..."));
 
     Some(PreviewContent {
       lines,
@@ -478,6 +583,8 @@ impl PreviewState {
       raw_lines: None,
       is_structured: false,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     })
   }
 
@@ -485,16 +592,92 @@ impl PreviewState {
     if self.cache.len() >= CACHE_SIZE
       && let Some(oldest) = self.cache_order.first().cloned() {
         self.cache.remove(&oldest);
+        self.cache_mtimes.remove(&oldest);
         self.cache_order.remove(0);
       }
+    match file_mtime(&path) {
+      Some(mtime) => self.cache_mtimes.insert(path.clone(), mtime),
+      None => self.cache_mtimes.remove(&path),
+    };
     self.cache_order.push(path.clone());
     self.cache.insert(path, content);
   }
 
+  fn cache_is_fresh(&self, path: &Path) -> bool {
+    self.cache_mtimes.get(path).copied() == file_mtime(path)
+  }
+
+  /// Handle a change notification for `path`. Marks the preview stale when
+  /// it is the shown file and differs from what the preview was built from.
+  /// Returns true if the preview just became stale.
+  pub fn note_file_changed(&mut self, path: &Path) -> bool {
+    if self.stale || self.current_path.as_deref() != Some(path) || !self.cache.contains_key(path) || self.following() {
+      return false;
+    }
+    self.stale = !self.cache_is_fresh(path);
+    self.stale
+  }
+
+  /// Syntax name of the previewed text file, for the pane title.
+  pub fn language(&self) -> Option<String> {
+    let content = self.get_content()?;
+    match content.preview_type {
+      PreviewType::Text | PreviewType::Markdown => self.highlighter.language(&content.extension),
+      _ => None,
+    }
+  }
+
   pub fn get_content(&self) -> Option<&PreviewContent> {
+    if let Some(follow) = &self.follow
+      && self.current_path.as_ref() == Some(&follow.path)
+    {
+      return Some(&follow.content);
+    }
     self.current_path.as_ref().and_then(|p| self.cache.get(p))
   }
 
+  /// Whether the shown file is being followed
+  pub fn following(&self) -> bool {
+    self.follow.as_ref().is_some_and(|f| self.current_path.as_ref() == Some(&f.path))
+  }
+
+  /// Start or stop following the shown text file, scrolled to its last
+  /// lines in a pane `rows` high. Returns whether it is followed now, or
+  /// None when there is no text file on disk to follow.
+  pub fn toggle_follow(&mut self, rows: usize) -> Option<bool> {
+    if self.following() {
+      self.follow = None;
+      self.scroll_down(0);
+      return Some(false);
+    }
+    let path = self.current_path.clone()?;
+    if self.source.is_some() || !self.get_content().is_some_and(|c| c.preview_type == PreviewType::Text) {
+      return None;
+    }
+    let follow = follow::Follow::start(&path, &self.highlighter).ok()?;
+    self.blame_enabled = false;
+    self.stale = false;
+    self.scroll_offset = follow.bottom(rows);
+    self.follow = Some(follow);
+    Some(true)
+  }
+
+  /// Read lines appended to the followed file, keeping the last ones in
+  /// view. Returns true when the preview changed.
+  pub fn check_follow(&mut self, rows: usize) -> bool {
+    if !self.following() {
+      return false;
+    }
+    let Some(follow) = self.follow.as_mut() else {
+      return false;
+    };
+    let changed = follow.poll(&self.highlighter, &self.theme, Instant::now());
+    if changed {
+      self.scroll_offset = follow.bottom(rows);
+    }
+    changed
+  }
+
   pub fn check_image_loaded(&mut self) -> bool {
     if let Some(ref rx) = self.image_rx
       && let Ok(result) = rx.try_recv() {
@@ -502,6 +685,9 @@ impl PreviewState {
           self::image::ImageLoadResult::Loaded(protocol) => {
             self.image_protocol = Some(protocol);
           }
+          self::image::ImageLoadResult::Thumbnail(img) => {
+            self.thumbnail = Some(img);
+          }
           self::image::ImageLoadResult::Error(msg) => {
             if let Some(ref path) = self.current_path {
               let content = PreviewContent {
@@ -517,6 +703,8 @@ impl PreviewState {
                 raw_lines: None,
                 is_structured: false,
                 diff_hunks: Vec::new(),
+                side_by_side: None,
+                file_diff: None,
               };
               self.insert_cache(path.clone(), content);
             }
@@ -528,6 +716,91 @@ impl PreviewState {
     false
   }
 
+  /// Start loading the commit footer for the current preview, for when the
+  /// repository became available after the preview was requested.
+  pub fn request_git_commits(&mut self, git_repo: &GitRepo) {
+    if let Some(path) = &self.current_path
+      && self.cache.contains_key(path)
+    {
+      self.git_commits_rx = Some(load_git_commits_async(git_repo.root(), path, 3));
+    }
+  }
+
+  /// Put the finished listing of the previewed archive in place of its
+  /// summary.
+  pub fn check_archive_listed(&mut self) -> bool {
+    let Some(ref rx) = self.archive_rx else {
+      return false;
+    };
+    let Ok((path, listing)) = rx.try_recv() else {
+      return false;
+    };
+    self.archive_rx = None;
+    let archive_type = archive::archive_type(&path).unwrap_or("archive");
+    if let Some(content) = self.cache.get_mut(&path) {
+      match listing {
+        Ok(listing) => {
+          content.lines = archive::render_archive_contents(archive_type, content.file_size, &listing, &self.theme);
+          content.line_count = listing.total_entries;
+        }
+        Err(e) => {
+          content.lines.pop();
+          content.lines.push(Line::from(format!(" {e}")));
+        }
+      }
+    }
+    true
+  }
+
+  /// Forget the shown preview while its archive listing, highlighted text
+  /// or change gutter is still being built, so it is built again when
+  /// shown next.
+  fn drop_unfinished(&mut self) {
+    let archive = self.archive_rx.take().is_some();
+    let text = self.text_rx.take().is_some();
+    let changes = self.changes_rx.take().is_some();
+    if (archive || text || changes)
+      && let Some(path) = self.current_path.clone()
+    {
+      self.cache.remove(&path);
+      self.cache_mtimes.remove(&path);
+      self.cache_order.retain(|p| *p != path);
+    }
+  }
+
+  /// Put the finished preview of a large text file in place of its
+  /// placeholder, keeping the commits and blame loaded meanwhile.
+  pub fn check_text_loaded(&mut self) -> bool {
+    let Some(ref rx) = self.text_rx else {
+      return false;
+    };
+    let Ok((path, mut content)) = rx.try_recv() else {
+      return false;
+    };
+    self.text_rx = None;
+    if let Some(shown) = self.cache.get_mut(&path) {
+      content.git_commits = std::mem::take(&mut shown.git_commits);
+      content.blame_data = shown.blame_data.take();
+      *shown = content;
+    }
+    true
+  }
+
+  /// Mark the changed lines of the shown text preview once they are diffed.
+  pub fn check_changes_loaded(&mut self) -> bool {
+    let Some(ref rx) = self.changes_rx else {
+      return false;
+    };
+    let Ok((path, changes)) = rx.try_recv() else {
+      return false;
+    };
+    self.changes_rx = None;
+    if let Some(content) = self.cache.get_mut(&path) {
+      mark_changes(content, &changes, &self.theme);
+    }
+    true
+  }
+
   pub fn check_git_commits_loaded(&mut self) -> bool {
     if let Some(ref rx) = self.git_commits_rx
       && let Ok((path, commits)) = rx.try_recv()
@@ -541,26 +814,89 @@ impl PreviewState {
     false
   }
 
+  /// Wrap long lines, or go back to cutting them off.
+  pub fn toggle_wrap(&mut self) {
+    self.wrap = !self.wrap;
+    self.h_scroll = 0;
+  }
+
+  pub fn scroll_left(&mut self, amount: usize) {
+    self.h_scroll = self.h_scroll.saturating_sub(amount);
+  }
+
+  /// Scroll sideways, no further than the end of the longest line shown.
+  pub fn scroll_right(&mut self, amount: usize) {
+    if self.wrap {
+      return;
+    }
+    let widest = self.get_display_lines().map_or(0, |lines| lines.iter().map(Line::width).max().unwrap_or(0));
+    let max = widest.saturating_sub(usize::from(self.content_width));
+    self.h_scroll = (self.h_scroll + amount).min(max);
+  }
+
   pub fn scroll_up(&mut self, amount: usize) {
     self.scroll_offset = self.scroll_offset.saturating_sub(amount);
   }
 
   pub fn scroll_down(&mut self, amount: usize) {
     if let Some(content) = self.get_content() {
-      let max = content.lines.len().saturating_sub(1);
+      let rows = self.active_side_by_side().map_or(content.lines.len(), |sbs| sbs.rows.len());
+      let max = rows.saturating_sub(1);
       self.scroll_offset = (self.scroll_offset + amount).min(max);
     }
   }
 
+  /// Stop showing the current preview but keep the cache.
+  pub fn clear_shown(&mut self) {
+    self.drop_unfinished();
+    self.follow = None;
+    self.stale = false;
+    self.current_path = None;
+    self.content = None;
+    self.last_request = None;
+    self.image_protocol = None;
+    self.thumbnail = None;
+    self.image_rx = None;
+    self.git_commits_rx = None;
+    self.image_timeline = None;
+  }
+
   pub fn invalidate(&mut self) {
     self.cache.clear();
     self.cache_order.clear();
     self.markdown_raw_cache.clear();
+    self.cache_mtimes.clear();
+    self.stale = false;
     self.current_path = None;
+    // Nothing is shown, so reloading the same file right away isn't debounced
+    self.last_request = None;
     self.content = None;
     self.image_protocol = None;
+    self.thumbnail = None;
     self.image_rx = None;
     self.git_commits_rx = None;
+    self.archive_rx = None;
+    self.changes_rx = None;
+    self.image_timeline = None;
+  }
+
+  /// Switch between human-readable and exact sizes, rebuilding cached
+  /// directory summaries.
+  pub fn toggle_size_format(&mut self) {
+    self.size_format = self.size_format.toggle();
+    let dirs: Vec<PathBuf> =
+      self.cache.iter().filter(|(_, c)| c.preview_type == PreviewType::Directory).map(|(p, _)| p.clone()).collect();
+    for path in &dirs {
+      self.cache.remove(path);
+    }
+    self.cache_order.retain(|p| !dirs.contains(p));
+    if let Some(path) = self.current_path.clone()
+      && dirs.contains(&path)
+    {
+      let scroll = self.scroll_offset;
+      self.load_preview(&path, None, None);
+      self.scroll_offset = scroll;
+    }
   }
 
   /// Toggle between raw and rendered markdown mode
@@ -597,20 +933,83 @@ impl PreviewState {
     true
   }
 
-  /// Show the git diff for the given file path
-  pub fn show_diff(&mut self, path: &Path, git_repo: Option<&GitRepo>) -> bool {
-    let repo_root = git_repo.map(|r| r.root());
-
-    let (lines, diff_hunks) = if let Some(root) = repo_root
-      && let Some(file_diff) = diff::generate_diff(root, path)
-    {
-      (diff::render_diff(&file_diff, &self.theme), file_diff.hunks)
-    } else {
-      (diff::render_no_diff_message(&self.theme), Vec::new())
+  /// Flip the image preview one revision back (`older`) or forward through the file's
+  /// git history, ending at the working copy. Returns a status message.
+  pub fn step_image_revision(&mut self, older: bool, picker: Option<&Picker>, git_repo: Option<&GitRepo>) -> Option<String> {
+    if !self.get_content().is_some_and(|c| c.preview_type == PreviewType::Image) {
+      return None;
+    }
+    let path = self.current_path.clone()?;
+    let Some(repo) = git_repo else {
+      return Some("Image history needs a git repository".to_string());
     };
 
-    let has_diff = !diff_hunks.is_empty();
-    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if self.image_timeline.as_ref().is_none_or(|t| t.path != path) {
+      let commits = repo.get_file_commits(&path, IMAGE_TIMELINE_LIMIT);
+      if commits.is_empty() {
+        return Some("No git history for this image".to_string());
+      }
+      self.image_timeline = Some(ImageTimeline { path: path.clone(), commits, index: None });
+    }
+    let timeline = self.image_timeline.as_mut()?;
+
+    let index = match (older, timeline.index) {
+      (true, None) => Some(0),
+      (true, Some(i)) if i + 1 < timeline.commits.len() => Some(i + 1),
+      (true, Some(_)) => return Some("Oldest revision".to_string()),
+      (false, None) => return Some("Showing working copy".to_string()),
+      (false, Some(0)) => None,
+      (false, Some(i)) => Some(i - 1),
+    };
+    timeline.index = index;
+
+    // The previous frame stays up until the new one is decoded
+    let Some(i) = index else {
+      self.start_image_load(&path, picker);
+      return Some("Working copy".to_string());
+    };
+    let commit = &timeline.commits[i];
+    let status = format!("Revision {}/{}: {} {}", i + 1, timeline.commits.len(), commit.hash, commit.message);
+    match repo.read_file_at(&path, commit.id) {
+      Some(bytes) => {
+        self.start_image_bytes_load(bytes, &path, picker);
+        Some(status)
+      }
+      None => {
+        self.image_protocol = None;
+        self.thumbnail = None;
+        self.image_rx = None;
+        Some(format!("{status} (image not present)"))
+      }
+    }
+  }
+
+  /// The revision shown in the image preview, when flipping through its history
+  pub fn image_revision(&self) -> Option<(usize, usize, &GitCommit)> {
+    let timeline = self.image_timeline.as_ref()?;
+    if self.current_path.as_ref() != Some(&timeline.path) {
+      return None;
+    }
+    let i = timeline.index?;
+    Some((i, timeline.commits.len(), &timeline.commits[i]))
+  }
+
+  /// Show the git diff for the given file path in the current diff mode
+  pub fn show_diff(&mut self, path: &Path, git_repo: Option<&GitRepo>) -> bool {
+    let repo_root = git_repo.map(|r| r.root());
+
+    let (lines, diff_hunks, side_by_side, file_diff) = if let Some(root) = repo_root
+      && let Some(file_diff) = diff::generate_diff(root, path, self.diff_mode)
+    {
+      let side_by_side = diff::SideBySideDiff::new(&file_diff);
+      let lines = diff::render_diff(&file_diff, &self.theme);
+      (lines, file_diff.hunks.clone(), Some(side_by_side), Some(file_diff))
+    } else {
+      (diff::render_no_diff_message(self.diff_mode, &self.theme), Vec::new(), None, None)
+    };
+    let has_diff = side_by_side.is_some();
+
+    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
 
     let content = PreviewContent {
       lines,
@@ -625,10 +1024,13 @@ impl PreviewState {
       raw_lines: None,
       is_structured: false,
       diff_hunks,
+      side_by_side,
+      file_diff,
     };
 
     self.scroll_offset = 0;
     self.image_protocol = None;
+    self.thumbnail = None;
     self.image_rx = None;
 
     // Use a unique cache key for diff mode
@@ -642,10 +1044,80 @@ impl PreviewState {
     has_diff
   }
 
+  /// The side-by-side layout of the current diff, if it is the one being displayed
+  pub fn active_side_by_side(&self) -> Option<&diff::SideBySideDiff> {
+    if !self.diff_side_by_side || self.content_width < SIDE_BY_SIDE_MIN_WIDTH {
+      return None;
+    }
+    self.get_content()?.side_by_side.as_ref()
+  }
+
+  /// Toggle side-by-side diff layout, keeping the same part of the diff in view.
+  /// Returns true if the current preview is a diff.
+  pub fn toggle_diff_side_by_side(&mut self) -> bool {
+    if !self.get_content().is_some_and(|c| c.preview_type == PreviewType::Diff) {
+      return false;
+    }
+    self.diff_side_by_side = !self.diff_side_by_side;
+    if self.content_width >= SIDE_BY_SIDE_MIN_WIDTH {
+      self.convert_diff_scroll(!self.diff_side_by_side);
+    }
+    true
+  }
+
+  /// Record the preview content width; crossing the side-by-side threshold converts
+  /// the scroll offset between unified lines and side-by-side rows.
+  pub fn set_content_width(&mut self, width: u16) {
+    if width != self.content_width {
+      let old_width = self.content_width;
+      self.content_width = width;
+      if self.diff_side_by_side {
+        let was_active = old_width >= SIDE_BY_SIDE_MIN_WIDTH;
+        if was_active != (width >= SIDE_BY_SIDE_MIN_WIDTH) {
+          self.convert_diff_scroll(was_active);
+        }
+      }
+    }
+  }
+
+  /// Convert the scroll offset from side-by-side rows to unified lines (`from_side_by_side`)
+  /// or the other way around
+  fn convert_diff_scroll(&mut self, from_side_by_side: bool) {
+    if let Some(sbs) = self.get_content().and_then(|c| c.side_by_side.as_ref()) {
+      self.scroll_offset = if from_side_by_side {
+        sbs.line_for_row(self.scroll_offset)
+      } else {
+        sbs.row_for_line(self.scroll_offset)
+      };
+    }
+  }
+
+  /// The diff currently shown, if any
+  pub fn current_diff(&self) -> Option<&diff::FileDiff> {
+    self.get_content()?.file_diff.as_ref()
+  }
+
+  /// Index of the hunk at the top of the diff view
+  pub fn current_hunk(&self) -> Option<usize> {
+    let line = match self.active_side_by_side() {
+      Some(sbs) => sbs.line_for_row(self.scroll_offset),
+      None => self.scroll_offset,
+    };
+    self.current_diff()?.hunk_at_line(line)
+  }
+
+  /// Hunk positions in the currently displayed diff layout
+  fn active_hunks(&self) -> Option<&[usize]> {
+    if let Some(sbs) = self.active_side_by_side() {
+      return Some(&sbs.hunks);
+    }
+    self.get_content().map(|c| c.diff_hunks.as_slice())
+  }
+
   /// Navigate to the next diff hunk, returns true if moved
   pub fn next_hunk(&mut self) -> bool {
-    if let Some(content) = self.get_content()
-      && let Some(next) = content.diff_hunks.iter().find(|&&idx| idx > self.scroll_offset).copied()
+    if let Some(hunks) = self.active_hunks()
+      && let Some(next) = hunks.iter().find(|&&idx| idx > self.scroll_offset).copied()
     {
       self.scroll_offset = next;
       return true;
@@ -655,8 +1127,8 @@ impl PreviewState {
 
   /// Navigate to the previous diff hunk, returns true if moved
   pub fn prev_hunk(&mut self) -> bool {
-    if let Some(content) = self.get_content()
-      && let Some(prev) = content.diff_hunks.iter().rev().find(|&&idx| idx < self.scroll_offset).copied()
+    if let Some(hunks) = self.active_hunks()
+      && let Some(prev) = hunks.iter().rev().find(|&&idx| idx < self.scroll_offset).copied()
     {
       self.scroll_offset = prev;
       return true;
@@ -684,201 +1156,351 @@ fn load_git_commits_async(
   rx
 }
 
-fn get_extension(path: &Path) -> String {
-  path
-    .extension()
-    .map(|e| e.to_string_lossy().to_string())
-    .unwrap_or_default()
+/// List up to `limit` entries of the archive at `path` on a worker thread.
+pub fn list_archive_async(path: &Path, limit: usize) -> mpsc::Receiver<(PathBuf, Result<archive::ArchiveListing, String>)> {
+  let (tx, rx) = mpsc::channel();
+  let path = path.to_path_buf();
+  std::thread::spawn(move || {
+    let listing = archive::list_archive(&path, limit);
+    let _ = tx.send((path, listing));
+  });
+  rx
 }
 
-const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "tiff", "tif", "ico", "svg", "jxl"];
-const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "mkdn"];
-
-pub fn detect_preview_type(path: &Path) -> PreviewType {
-  if path.is_dir() {
-    return PreviewType::Directory;
-  }
+fn render_text_async(
+  path: &Path,
+  preview_type: PreviewType,
+  text: String,
+  renderer: TextRenderer,
+  repo_root: Option<PathBuf>,
+) -> mpsc::Receiver<(PathBuf, PreviewContent)> {
+  let (tx, rx) = mpsc::channel();
+  let path = path.to_path_buf();
+  std::thread::spawn(move || {
+    let content = match preview_type {
+      PreviewType::Markdown => renderer.markdown(&path, Ok(text), &[]),
+      _ => renderer.text(&path, Ok(text), &[]).map(|mut content| {
+        if let Some(changes) = repo_root.and_then(|root| diff::line_changes(&root, &path)) {
+          mark_changes(&mut content, &changes, &renderer.theme);
+        }
+        content
+      }),
+    };
+    if let Some(content) = content {
+      let _ = tx.send((path, content));
+    }
+  });
+  rx
+}
 
-  let metadata = match path.metadata() {
-    Ok(m) => m,
-    Err(e) => return PreviewType::Error(e.to_string()),
-  };
+fn line_changes_async(repo_root: PathBuf, path: &Path) -> mpsc::Receiver<(PathBuf, HashMap<usize, diff::LineChange>)> {
+  let (tx, rx) = mpsc::channel();
+  let path = path.to_path_buf();
+  std::thread::spawn(move || {
+    if let Some(changes) = diff::line_changes(&repo_root, &path) {
+      let _ = tx.send((path, changes));
+    }
+  });
+  rx
+}
 
-  if metadata.len() == 0 {
-    return PreviewType::Empty;
+/// Put the change gutter on a text preview. Formatted structured data gets
+/// it on its raw lines, which match the file's.
+fn mark_changes(content: &mut PreviewContent, changes: &HashMap<usize, diff::LineChange>, theme: &Theme) {
+  if content.preview_type == PreviewType::Text {
+    let lines = content.raw_lines.as_mut().unwrap_or(&mut content.lines);
+    diff::change_gutter(lines, changes, theme);
   }
+}
 
-  // Check for archive types early (they can be large)
-  if archive::is_archive(path) {
-    return PreviewType::Archive;
+/// Shown while a large text file is highlighted in the background.
+fn loading_content(path: &Path, preview_type: PreviewType) -> PreviewContent {
+  PreviewContent {
+    lines: vec![Line::from(" Loading...")],
+    preview_type,
+    line_count: 0,
+    file_size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    extension: get_extension(path),
+    metadata: get_file_metadata(path),
+    image_metadata: None,
+    git_commits: Vec::new(),
+    blame_data: None,
+    raw_lines: None,
+    is_structured: false,
+    diff_hunks: Vec::new(),
+    side_by_side: None,
+    file_diff: None,
   }
+}
 
-  if metadata.len() > MAX_TEXT_BYTES {
-    // Check if it's an image (images can be large)
-    let ext = get_extension(path);
-    if IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
-      return PreviewType::Image;
-    }
-    return PreviewType::TooLarge;
-  }
+/// What building a text or markdown preview needs, cheap to clone onto a
+/// worker thread.
+struct TextRenderer {
+  highlighter: Arc<SyntaxHighlighter>,
+  theme: Theme,
+  markdown_rendered: bool,
+}
 
-  // Check extension for images
-  let ext = get_extension(path);
-  if IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
-    return PreviewType::Image;
-  }
+impl TextRenderer {
+  fn text(&self, path: &Path, text: std::io::Result<String>, git_commits: &[GitCommit]) -> Option<PreviewContent> {
+    let content = match text {
+      Ok(c) => c,
+      Err(e) => {
+        return Some(PreviewContent {
+          lines: vec![Line::from(format!(" Error reading file: {e}"))],
+          preview_type: PreviewType::Error(e.to_string()),
+          line_count: 0,
+          file_size: 0,
+          extension: String::new(),
+          metadata: None,
+          image_metadata: None,
+          git_commits: Vec::new(),
+          blame_data: None,
+          raw_lines: None,
+          is_structured: false,
+          diff_hunks: Vec::new(),
+          side_by_side: None,
+          file_diff: None,
+        });
+      }
+    };
 
-  // Check extension for markdown
-  if MARKDOWN_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
-    return PreviewType::Markdown;
-  }
+    let line_count = content.lines().count();
+    let truncated: String = content.lines().take(MAX_TEXT_LINES).collect::<Vec<_>>().join("\n");
+    let ext = get_extension(path);
+    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let metadata = get_file_metadata_with_lines(path, line_count);
 
-  // Try to detect if binary using infer
-  if let Ok(data) = std::fs::read(path) {
-    if let Some(kind) = infer::get(&data) {
-      let mime = kind.mime_type();
-      if mime.starts_with("image/") {
-        return PreviewType::Image;
-      }
-      if !mime.starts_with("text/") {
-        return PreviewType::Binary;
+    // Notebooks and structured data files (JSON, YAML, XML...) can be shown raw too
+    let notebook = notebook::is_notebook(&ext)
+      .then(|| notebook::render_notebook(&content, &self.highlighter, &self.theme))
+      .flatten();
+    let is_structured = notebook.is_some() || structured::is_structured_data(&ext);
+
+    let (lines, raw_lines) = if let Some(mut rendered) = notebook {
+      rendered.truncate(MAX_TEXT_LINES);
+      (rendered, Some(self.highlighter.highlight(&truncated, "json")))
+    } else if is_structured {
+      // Try to format the content
+      match structured::format_structured(&content, &ext) {
+        Some(structured::FormatResult::Formatted { content: formatted, extension: fmt_ext }) => {
+          // Truncate formatted content too
+          let fmt_truncated: String = formatted.lines().take(MAX_TEXT_LINES).collect::<Vec<_>>().join("\n");
+          let formatted_lines = self.highlighter.highlight(&fmt_truncated, &fmt_ext);
+          let raw_highlighted = self.highlighter.highlight(&truncated, &ext);
+          (formatted_lines, Some(raw_highlighted))
+        }
+        Some(structured::FormatResult::Error(message)) => {
+          // Formatting failed, show where above the raw content
+          let raw_highlighted = self.highlighter.highlight(&truncated, &ext);
+          let mut lines = vec![
+            Line::from(Span::styled(format!(" {message}"), Style::default().fg(self.theme.error))),
+            Line::from(""),
+          ];
+          lines.extend(raw_highlighted.iter().cloned());
+          (lines, Some(raw_highlighted))
+        }
+        None => {
+          // Not a structured format (shouldn't happen given is_structured check)
+          let raw_highlighted = self.highlighter.highlight(&truncated, &ext);
+          (raw_highlighted, None)
+        }
       }
-    }
+    } else {
+      // Regular text file
+      let highlighted = self.highlighter.highlight(&truncated, &ext);
+      (highlighted, None)
+    };
 
-    // Check if content looks like text (no null bytes in first chunk)
-    let check_len = data.len().min(8192);
-    if data[..check_len].contains(&0) {
-      return PreviewType::Binary;
-    }
+    Some(PreviewContent {
+      lines,
+      preview_type: PreviewType::Text,
+      line_count,
+      file_size,
+      extension: ext,
+      metadata,
+      image_metadata: None,
+      git_commits: git_commits.to_vec(),
+      blame_data: None,
+      raw_lines,
+      is_structured,
+      diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
+    })
   }
 
-  PreviewType::Text
-}
+  fn markdown(&self, path: &Path, text: std::io::Result<String>, git_commits: &[GitCommit]) -> Option<PreviewContent> {
+    let content = match text {
+      Ok(c) => c,
+      Err(e) => {
+        return Some(PreviewContent {
+          lines: vec![Line::from(format!(" Error reading file: {e}"))],
+          preview_type: PreviewType::Error(e.to_string()),
+          line_count: 0,
+          file_size: 0,
+          extension: String::new(),
+          metadata: None,
+          image_metadata: None,
+          git_commits: Vec::new(),
+          blame_data: None,
+          raw_lines: None,
+          is_structured: false,
+          diff_hunks: Vec::new(),
+          side_by_side: None,
+          file_diff: None,
+        });
+      }
+    };
 
-#[cfg(test)]
-mod tests {
-  use super::*;
-  use std::fs;
+    let line_count = content.lines().count();
+    let truncated: String = content.lines().take(MAX_TEXT_LINES).collect::<Vec<_>>().join("\n");
+    let ext = get_extension(path);
+    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let metadata = get_file_metadata_with_lines(path, line_count);
 
-  #[test]
-  fn test_detect_directory() {
-    let dir = std::env::temp_dir();
-    assert_eq!(detect_preview_type(&dir), PreviewType::Directory);
-  }
+    // Render markdown if in rendered mode, otherwise show raw with syntax highlighting
+    let lines = if self.markdown_rendered {
+      markdown::render_markdown(&truncated, &self.highlighter, &self.theme)
+    } else {
+      self.highlighter.highlight(&truncated, &ext)
+    };
 
-  #[test]
-  fn test_detect_text_file() {
-    let dir = std::env::temp_dir().join("tui_explorer_test_detect");
-    let _ = fs::remove_dir_all(&dir);
-    fs::create_dir_all(&dir).unwrap();
-    let file = dir.join("test.txt");
-    fs::write(&file, "hello world").unwrap();
-    assert_eq!(detect_preview_type(&file), PreviewType::Text);
-    let _ = fs::remove_dir_all(&dir);
+    Some(PreviewContent {
+      lines,
+      preview_type: PreviewType::Markdown,
+      line_count,
+      file_size,
+      extension: ext,
+      metadata,
+      image_metadata: None,
+      git_commits: git_commits.to_vec(),
+      blame_data: None,
+      raw_lines: None,
+      is_structured: false,
+      diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
+    })
   }
+}
 
-  #[test]
-  fn test_detect_empty_file() {
-    let dir = std::env::temp_dir().join("tui_explorer_test_empty");
-    let _ = fs::remove_dir_all(&dir);
-    fs::create_dir_all(&dir).unwrap();
-    let file = dir.join("empty.txt");
-    fs::write(&file, "").unwrap();
-    assert_eq!(detect_preview_type(&file), PreviewType::Empty);
-    let _ = fs::remove_dir_all(&dir);
-  }
+/// What a preview is built from: everything read off the disk, so that
+/// only this part has to run under the timeout.
+enum Fetched {
+  Text(PreviewType, std::io::Result<String>),
+  Bytes(std::io::Result<Vec<u8>>),
+  Directory(directory::DirSummary),
+  Image(Option<metadata::ImageMetadata>),
+  /// Nothing to read beyond what detecting the type did
+  Type(PreviewType),
+}
 
-  #[test]
-  fn test_detect_binary_file() {
-    let dir = std::env::temp_dir().join("tui_explorer_test_binary");
-    let _ = fs::remove_dir_all(&dir);
-    fs::create_dir_all(&dir).unwrap();
-    let file = dir.join("binary.bin");
-    fs::write(&file, [0u8, 1, 2, 3, 0, 0, 0, 0]).unwrap();
-    assert_eq!(detect_preview_type(&file), PreviewType::Binary);
-    let _ = fs::remove_dir_all(&dir);
+fn fetch(path: &Path) -> Fetched {
+  match detect_preview_type(path) {
+    preview_type @ (PreviewType::Text | PreviewType::Markdown) => {
+      Fetched::Text(preview_type, std::fs::read_to_string(path))
+    }
+    PreviewType::Binary => Fetched::Bytes(std::fs::read(path)),
+    PreviewType::Directory => Fetched::Directory(directory::summarize_dir(path)),
+    PreviewType::Image => Fetched::Image(get_image_metadata(path)),
+    preview_type => Fetched::Type(preview_type),
   }
+}
 
-  #[test]
-  fn test_detect_image_by_extension() {
-    let dir = std::env::temp_dir().join("tui_explorer_test_imgext");
-    let _ = fs::remove_dir_all(&dir);
-    fs::create_dir_all(&dir).unwrap();
-    let file = dir.join("photo.png");
-    fs::write(&file, "fake png data").unwrap();
-    assert_eq!(detect_preview_type(&file), PreviewType::Image);
-    let _ = fs::remove_dir_all(&dir);
+/// Like `fetch`, for a path in a tree not on the local filesystem. Paths
+/// the source does not have are read from the disk.
+fn fetch_from(source: &dyn TreeSource, path: &Path) -> Fetched {
+  let Ok(entry) = source.stat(path, 0) else {
+    return fetch(path);
+  };
+  if entry.is_dir {
+    return Fetched::Directory(directory::summarize_source_dir(source, path));
   }
-
-  #[test]
-  fn test_detect_archive_zip() {
-    let dir = std::env::temp_dir().join("tui_explorer_test_archive_zip");
-    let _ = fs::remove_dir_all(&dir);
-    fs::create_dir_all(&dir).unwrap();
-    let file = dir.join("test.zip");
-    // Create a minimal valid ZIP file
-    {
-      let f = fs::File::create(&file).unwrap();
-      let mut zip = zip::ZipWriter::new(f);
-      let options = zip::write::SimpleFileOptions::default();
-      zip.start_file("test.txt", options).unwrap();
-      std::io::Write::write_all(&mut zip, b"test").unwrap();
-      zip.finish().unwrap();
-    }
-    assert_eq!(detect_preview_type(&file), PreviewType::Archive);
-    let _ = fs::remove_dir_all(&dir);
+  if entry.size == 0 {
+    return Fetched::Type(PreviewType::Empty);
   }
-
-  #[test]
-  fn test_detect_archive_tar_gz() {
-    let dir = std::env::temp_dir().join("tui_explorer_test_archive_tgz");
-    let _ = fs::remove_dir_all(&dir);
-    fs::create_dir_all(&dir).unwrap();
-    let file = dir.join("test.tar.gz");
-    // Just create a fake file - detection is by extension
-    fs::write(&file, "fake tar.gz data").unwrap();
-    assert_eq!(detect_preview_type(&file), PreviewType::Archive);
-    let _ = fs::remove_dir_all(&dir);
+  if entry.size > tfl_core::preview::MAX_TEXT_BYTES {
+    return Fetched::Type(PreviewType::TooLarge);
   }
-
-  #[test]
-  fn test_detect_markdown_file() {
-    let dir = std::env::temp_dir().join("tui_explorer_test_markdown");
-    let _ = fs::remove_dir_all(&dir);
-    fs::create_dir_all(&dir).unwrap();
-    let file = dir.join("README.md");
-    fs::write(&file, "# Hello World").unwrap();
-    assert_eq!(detect_preview_type(&file), PreviewType::Markdown);
-    let _ = fs::remove_dir_all(&dir);
+  match source.read(path) {
+    Ok(data) if data.contains(&0) => Fetched::Bytes(Ok(data)),
+    Ok(data) => match String::from_utf8(data) {
+      Ok(text) if MARKDOWN_EXTENSIONS.contains(&get_extension(path).to_lowercase().as_str()) => {
+        Fetched::Text(PreviewType::Markdown, Ok(text))
+      }
+      Ok(text) => Fetched::Text(PreviewType::Text, Ok(text)),
+      Err(e) => Fetched::Bytes(Ok(e.into_bytes())),
+    },
+    Err(e) => Fetched::Bytes(Err(e)),
   }
+}
 
-  #[test]
-  fn test_detect_markdown_extensions() {
-    let dir = std::env::temp_dir().join("tui_explorer_test_md_ext");
-    let _ = fs::remove_dir_all(&dir);
-    fs::create_dir_all(&dir).unwrap();
-
-    for ext in &["md", "markdown", "mdown", "mkd", "mkdn"] {
-      let file = dir.join(format!("test.{ext}"));
-      fs::write(&file, "# Test").unwrap();
-      assert_eq!(detect_preview_type(&file), PreviewType::Markdown, "Failed for extension: {ext}");
+/// Fetch `path` on a worker thread, giving up after `budget`. A read stuck
+/// on slow or hung media is left to finish on its own and its result
+/// dropped; None means it ran out of time.
+fn fetch_within(
+  path: &Path,
+  source: Option<Arc<dyn TreeSource>>,
+  budget: Duration,
+  in_flight: &Arc<AtomicUsize>,
+) -> Option<Fetched> {
+  let fetch = move |path: &Path| match &source {
+    Some(source) => fetch_from(source.as_ref(), path),
+    None => fetch(path),
+  };
+  if budget.is_zero() {
+    return Some(fetch(path));
+  }
+  if in_flight.fetch_add(1, Ordering::SeqCst) >= MAX_READS_IN_FLIGHT {
+    in_flight.fetch_sub(1, Ordering::SeqCst);
+    tracing::warn!(path = %path.display(), "too many preview reads still running");
+    return None;
+  }
+  let (tx, rx) = mpsc::channel();
+  let worker_path = path.to_path_buf();
+  let in_flight = Arc::clone(in_flight);
+  std::thread::spawn(move || {
+    let fetched = fetch(&worker_path);
+    in_flight.fetch_sub(1, Ordering::SeqCst);
+    let _ = tx.send(fetched);
+  });
+  match rx.recv_timeout(budget) {
+    Ok(fetched) => Some(fetched),
+    Err(mpsc::RecvTimeoutError::Timeout) => None,
+    Err(mpsc::RecvTimeoutError::Disconnected) => {
+      Some(Fetched::Type(PreviewType::Error(format!("failed to read {}", path.display()))))
     }
-
-    let _ = fs::remove_dir_all(&dir);
   }
+}
 
-  #[test]
-  fn test_detect_nonexistent() {
-    let result = detect_preview_type(Path::new("/nonexistent/file.txt"));
-    assert!(matches!(result, PreviewType::Error(_)));
+fn timed_out_content() -> PreviewContent {
+  PreviewContent {
+    lines: vec![Line::from(" Preview timed out (reload the preview to retry)")],
+    preview_type: PreviewType::TimedOut,
+    line_count: 0,
+    file_size: 0,
+    extension: String::new(),
+    metadata: None,
+    image_metadata: None,
+    git_commits: Vec::new(),
+    blame_data: None,
+    raw_lines: None,
+    is_structured: false,
+    diff_hunks: Vec::new(),
+    side_by_side: None,
+    file_diff: None,
   }
+}
 
-  #[test]
-  fn test_get_extension() {
-    assert_eq!(get_extension(Path::new("foo.rs")), "rs");
-    assert_eq!(get_extension(Path::new("foo")), "");
-    assert_eq!(get_extension(Path::new("foo.tar.gz")), "gz");
-  }
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+  std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
 
   #[test]
   fn test_preview_state_scroll() {
@@ -909,6 +1531,8 @@ mod tests {
         raw_lines: None,
         is_structured: false,
         diff_hunks: Vec::new(),
+        side_by_side: None,
+        file_diff: None,
       };
       state.insert_cache(path, content);
     }
@@ -955,6 +1579,8 @@ mod tests {
       raw_lines: None,
       is_structured: false,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     };
     state.insert_cache(path.clone(), content);
     state.current_path = Some(path);
@@ -982,6 +1608,8 @@ mod tests {
       raw_lines: Some(vec![Line::from("raw")]),
       is_structured: true,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     };
     state.insert_cache(path.clone(), content);
     state.current_path = Some(path);
@@ -1012,6 +1640,8 @@ mod tests {
       raw_lines: Some(vec![Line::from("raw")]),
       is_structured: true,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     };
     state.insert_cache(path.clone(), content);
     state.current_path = Some(path);
@@ -1045,6 +1675,8 @@ mod tests {
       raw_lines: None,
       is_structured: false,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     };
     state.insert_cache(path.clone(), content);
     state.current_path = Some(path);
@@ -1079,6 +1711,8 @@ mod tests {
       raw_lines: None,
       is_structured: false,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     };
     state.insert_cache(path.clone(), content);
     state.current_path = Some(path);
@@ -1113,6 +1747,41 @@ mod tests {
     let _ = fs::remove_dir_all(&dir);
   }
 
+  #[test]
+  fn test_note_file_changed_marks_stale_and_cache_refreshes() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let dir = std::env::temp_dir().join(format!("tfl_preview_stale_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("notes.txt");
+    let other = dir.join("other.txt");
+    fs::write(&file, "before").unwrap();
+    fs::write(&other, "other").unwrap();
+
+    state.request_preview(&file, None, None);
+    // Unchanged file: notifications are ignored
+    assert!(!state.note_file_changed(&file));
+    assert!(!state.stale);
+
+    fs::write(&file, "after").unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+    fs::File::options().write(true).open(&file).unwrap().set_modified(later).unwrap();
+    assert!(!state.note_file_changed(&other));
+    assert!(state.note_file_changed(&file));
+    assert!(state.stale);
+
+    // Coming back to the file rebuilds its preview instead of serving the cache
+    state.last_request = None;
+    state.request_preview(&other, None, None);
+    assert!(!state.stale);
+    state.last_request = None;
+    state.request_preview(&file, None, None);
+    let text: String = state.get_content().unwrap().lines.iter().map(|l| l.to_string()).collect();
+    assert!(text.contains("after"));
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
   #[test]
   fn test_request_preview_cache_hit() {
     let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
@@ -1130,6 +1799,8 @@ mod tests {
       raw_lines: None,
       is_structured: false,
       diff_hunks: Vec::new(),
+      side_by_side: None,
+      file_diff: None,
     };
     state.insert_cache(path.clone(), content);
     // Clear last_request to avoid debounce
@@ -1151,7 +1822,7 @@ mod tests {
     let file = dir.join("test.rs");
     fs::write(&file, "fn main() {\n  println!(\"hello\");\n}\n").unwrap();
 
-    let result = state.load_text(&file, &[]);
+    let result = state.load_text(&file, fs::read_to_string(&file), &[]);
     assert!(result.is_some());
     let content = result.unwrap();
     assert_eq!(content.preview_type, PreviewType::Text);
@@ -1163,12 +1834,229 @@ mod tests {
   #[test]
   fn test_load_text_nonexistent() {
     let state = PreviewState::new("base16-ocean.dark", Theme::dark());
-    let result = state.load_text(Path::new("/nonexistent/file.txt"), &[]);
+    let result = state.load_text(Path::new("/nonexistent/file.txt"), fs::read_to_string("/nonexistent/file.txt"), &[]);
     assert!(result.is_some());
     let content = result.unwrap();
     assert!(matches!(content.preview_type, PreviewType::Error(_)));
   }
 
+  /// A source slow to answer, like a hung network mount
+  #[derive(Debug)]
+  struct SlowSource;
+
+  impl TreeSource for SlowSource {
+    fn children(&self, _dir: &Path, _depth: usize) -> std::io::Result<Vec<crate::fs::FileEntry>> {
+      Ok(Vec::new())
+    }
+
+    fn stat(&self, _path: &Path, _depth: usize) -> std::io::Result<crate::fs::FileEntry> {
+      std::thread::sleep(Duration::from_millis(200));
+      Err(std::io::ErrorKind::NotFound.into())
+    }
+
+    fn read(&self, _path: &Path) -> std::io::Result<Vec<u8>> {
+      Ok(Vec::new())
+    }
+  }
+
+  #[test]
+  fn test_request_preview_times_out_until_retried() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let dir = std::env::temp_dir().join(format!("tfl_preview_timeout_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("slow.txt");
+    fs::write(&file, "hello\n").unwrap();
+
+    state.source = Some(Arc::new(SlowSource));
+    state.timeout = Duration::from_millis(10);
+    state.request_preview(&file, None, None);
+    let content = state.get_content().unwrap();
+    assert_eq!(content.preview_type, PreviewType::TimedOut);
+    assert_eq!(content.lines[0].to_string(), " Preview timed out (reload the preview to retry)");
+
+    state.source = None;
+    state.timeout = Duration::from_secs(5);
+    state.invalidate();
+    state.last_request = None;
+    state.request_preview(&file, None, None);
+    assert_eq!(state.get_content().unwrap().preview_type, PreviewType::Text);
+
+    // With too many reads stuck, no further one is started
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while state.reads_in_flight.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+      std::thread::sleep(Duration::from_millis(2));
+    }
+    state.reads_in_flight.store(MAX_READS_IN_FLIGHT, Ordering::SeqCst);
+    state.invalidate();
+    state.last_request = None;
+    state.request_preview(&file, None, None);
+    assert_eq!(state.get_content().unwrap().preview_type, PreviewType::TimedOut);
+    assert_eq!(state.reads_in_flight.load(Ordering::SeqCst), MAX_READS_IN_FLIGHT);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_request_preview_of_device_describes_it() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    state.request_preview(Path::new("/dev/zero"), None, None);
+    let content = state.get_content().unwrap();
+    assert_eq!(content.preview_type, PreviewType::Special(special::SpecialFile::CharDevice));
+    assert!(content.lines[0].spans.iter().any(|s| s.content == "Character device"));
+    assert!(content.metadata.is_some());
+  }
+
+  #[test]
+  fn test_archive_preview_lists_contents_in_background() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let dir = std::env::temp_dir().join(format!("tfl_preview_archive_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("pkg")).unwrap();
+    fs::write(dir.join("pkg/readme.txt"), "hello").unwrap();
+    let tarball = dir.join("pkg.tar.gz");
+    archive::compress_to_archive(&[dir.join("pkg")], &tarball, "tar.gz").unwrap();
+    let other = dir.join("other.txt");
+    fs::write(&other, "text").unwrap();
+
+    state.request_preview(&tarball, None, None);
+    assert_eq!(state.get_content().unwrap().preview_type, PreviewType::Archive);
+    assert!(state.archive_rx.is_some());
+    // Moving on before the listing arrives drops the half-built preview
+    state.last_request = None;
+    state.request_preview(&other, None, None);
+    assert!(state.archive_rx.is_none());
+    assert!(!state.cache.contains_key(&tarball));
+
+    state.last_request = None;
+    state.request_preview(&tarball, None, None);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !state.check_archive_listed() {
+      assert!(Instant::now() < deadline);
+      std::thread::sleep(Duration::from_millis(5));
+    }
+    let content = state.get_content().unwrap();
+    assert_eq!(content.line_count, 2);
+    let text: Vec<String> =
+      content.lines.iter().map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect()).collect();
+    assert!(text.iter().any(|l| l.ends_with("pkg/readme.txt") && l.contains("5 B")));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_large_text_is_highlighted_in_background() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let dir = std::env::temp_dir().join(format!("tfl_preview_async_text_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let large = dir.join("large.rs");
+    fs::write(&large, "fn main() { println!(\"hello\"); }\n".repeat(2000)).unwrap();
+    let small = dir.join("small.rs");
+    fs::write(&small, "fn main() {}\n").unwrap();
+
+    state.request_preview(&small, None, None);
+    assert!(state.text_rx.is_none());
+    assert_eq!(state.get_content().unwrap().line_count, 1);
+
+    state.request_preview(&large, None, None);
+    let content = state.get_content().unwrap();
+    assert_eq!(content.preview_type, PreviewType::Text);
+    assert_eq!(content.lines.len(), 1);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !state.check_text_loaded() {
+      assert!(Instant::now() < deadline);
+      std::thread::sleep(Duration::from_millis(5));
+    }
+    let content = state.get_content().unwrap();
+    assert_eq!(content.line_count, 2000);
+    assert_eq!(content.lines.len(), MAX_TEXT_LINES);
+
+    // Without a timeout large files still go to the worker
+    state.invalidate();
+    state.clear_shown();
+    state.timeout = Duration::ZERO;
+    state.request_preview(&large, None, None);
+    assert!(state.text_rx.is_some());
+
+    // Switched off, everything is highlighted in place
+    state.invalidate();
+    state.clear_shown();
+    state.async_text = false;
+    state.request_preview(&large, None, None);
+    assert!(state.text_rx.is_none());
+    assert_eq!(state.get_content().unwrap().line_count, 2000);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_notebook_preview_renders_cells_with_raw_json_toggle() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let dir = std::env::temp_dir().join(format!("tfl_preview_notebook_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("analysis.ipynb");
+    let notebook = r#"{"cells": [{"cell_type": "code", "execution_count": 1, "source": "x = 1", "outputs": []}], "metadata": {}}"#;
+    fs::write(&file, notebook).unwrap();
+
+    state.request_preview(&file, None, None);
+    let content = state.get_content().unwrap();
+    assert!(content.is_structured);
+    let first: String = content.lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(first, " In [1]:");
+    assert!(state.toggle_formatted());
+    let raw: String = state.get_display_lines().unwrap()[0].spans.iter().map(|s| s.content.as_ref()).collect();
+    assert!(raw.contains("\"cells\""));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_invalid_structured_file_shows_parse_error_above_raw_content() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let dir = std::env::temp_dir().join(format!("tfl_preview_bad_yaml_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("config.yaml");
+    fs::write(&file, "a: 1\nb: [1, 2\nc: 3\n").unwrap();
+
+    state.request_preview(&file, None, None);
+    let text = |lines: &[Line]| -> Vec<String> {
+      lines.iter().map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect()).collect()
+    };
+    let formatted = text(state.get_display_lines().unwrap());
+    assert!(formatted[0].starts_with(" Invalid YAML") && formatted[0].contains("line 3"));
+    assert!(formatted[2].ends_with("a: 1"));
+    assert!(state.toggle_formatted());
+    assert!(text(state.get_display_lines().unwrap())[0].ends_with("a: 1"));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_goto_line_switches_structured_data_to_the_raw_view() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let dir = std::env::temp_dir().join(format!("tfl_preview_goto_json_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let text = |state: &PreviewState| -> Vec<String> {
+      state.get_display_lines().unwrap().iter().map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect()).collect()
+    };
+
+    let file = dir.join("data.json");
+    fs::write(&file, "{\"a\": [1, 2],\n\"b\": 3}\n").unwrap();
+    state.request_preview(&file, None, None);
+    assert!(state.show_formatted);
+    state.goto_line(2).unwrap();
+    assert!(!state.show_formatted);
+    assert!(text(&state)[state.scroll_offset].ends_with("\"b\": 3}"));
+
+    // The parse error above the content isn't counted either
+    state.show_formatted = true;
+    let file = dir.join("config.yaml");
+    fs::write(&file, "a: 1\nb: [1, 2\nc: 3\n").unwrap();
+    state.request_preview(&file, None, None);
+    state.goto_line(3).unwrap();
+    assert!(text(&state)[state.scroll_offset].ends_with("c: 3"));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
   #[test]
   fn test_load_markdown_success() {
     let state = PreviewState::new("base16-ocean.dark", Theme::dark());
@@ -1178,7 +2066,7 @@ mod tests {
     let file = dir.join("test.md");
     fs::write(&file, "# Hello\n\nWorld\n").unwrap();
 
-    let result = state.load_markdown(&file, &[]);
+    let result = state.load_markdown(&file, fs::read_to_string(&file), &[]);
     assert!(result.is_some());
     let content = result.unwrap();
     assert_eq!(content.preview_type, PreviewType::Markdown);
@@ -1195,7 +2083,7 @@ mod tests {
     let file = dir.join("test.bin");
     fs::write(&file, [0u8, 1, 2, 3, 0xFF, 0xFE]).unwrap();
 
-    let result = state.load_hex(&file, &[]);
+    let result = state.load_hex(&file, fs::read(&file), &[]);
     assert!(result.is_some());
     let content = result.unwrap();
     assert_eq!(content.preview_type, PreviewType::Binary);
@@ -1220,6 +2108,8 @@ mod tests {
       raw_lines: None,
       is_structured: false,
       diff_hunks: Vec::new(), // no hunks
+      side_by_side: None,
+      file_diff: None,
     };
     state.insert_cache(path.clone(), content);
     state.current_path = Some(path);
@@ -1245,6 +2135,8 @@ mod tests {
       raw_lines: None,
       is_structured: false,
       diff_hunks: vec![5, 15, 25],
+      side_by_side: None,
+      file_diff: None,
     };
     state.insert_cache(path.clone(), content);
     state.current_path = Some(path);
@@ -1266,4 +2158,245 @@ mod tests {
     assert!(!state.prev_hunk());
     assert_eq!(state.scroll_offset, 5);
   }
+
+  fn side_by_side_state() -> PreviewState {
+    let mut file_diff = diff::FileDiff::new();
+    let line = |kind, content: &str| diff::DiffLine { kind, content: content.to_string(), old_line_no: None, new_line_no: None };
+    file_diff.lines.push(line(diff::DiffLineKind::HunkHeader, "@@ -1,2 +1,2 @@"));
+    file_diff.lines.push(line(diff::DiffLineKind::Removed, "a"));
+    file_diff.lines.push(line(diff::DiffLineKind::Removed, "b"));
+    file_diff.lines.push(line(diff::DiffLineKind::Added, "c"));
+    file_diff.lines.push(line(diff::DiffLineKind::Added, "d"));
+    file_diff.lines.push(line(diff::DiffLineKind::HunkHeader, "@@ -9,1 +9,1 @@"));
+    file_diff.lines.push(line(diff::DiffLineKind::Context, "e"));
+    file_diff.hunks = vec![0, 5];
+
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let path = PathBuf::from("/tmp/file.rs.diff");
+    state.insert_cache(path.clone(), PreviewContent {
+      lines: vec![Line::from(""); file_diff.lines.len()],
+      preview_type: PreviewType::Diff,
+      line_count: 0,
+      file_size: 0,
+      extension: String::new(),
+      metadata: None,
+      image_metadata: None,
+      git_commits: Vec::new(),
+      blame_data: None,
+      raw_lines: None,
+      is_structured: false,
+      diff_hunks: file_diff.hunks.clone(),
+      side_by_side: Some(diff::SideBySideDiff::new(&file_diff)),
+      file_diff: Some(file_diff),
+    });
+    state.current_path = Some(path);
+    state
+  }
+
+  #[test]
+  fn test_side_by_side_requires_wide_pane() {
+    let mut state = side_by_side_state();
+    state.set_content_width(SIDE_BY_SIDE_MIN_WIDTH - 1);
+    assert!(state.toggle_diff_side_by_side());
+    assert!(state.diff_side_by_side);
+    assert!(state.active_side_by_side().is_none());
+
+    state.set_content_width(SIDE_BY_SIDE_MIN_WIDTH);
+    assert!(state.active_side_by_side().is_some());
+  }
+
+  #[test]
+  fn test_side_by_side_hunks_and_scroll_conversion() {
+    let mut state = side_by_side_state();
+    state.set_content_width(SIDE_BY_SIDE_MIN_WIDTH);
+
+    // Unified: second hunk header is line 5
+    state.scroll_offset = 0;
+    assert!(state.next_hunk());
+    assert_eq!(state.scroll_offset, 5);
+
+    // Side by side: removed/added pairs collapse into 2 rows, so the hunk is row 3
+    assert!(state.toggle_diff_side_by_side());
+    assert_eq!(state.scroll_offset, 3);
+    assert!(state.prev_hunk());
+    assert_eq!(state.scroll_offset, 0);
+    assert!(state.next_hunk());
+    assert_eq!(state.scroll_offset, 3);
+
+    // Narrowing the pane falls back to unified and converts the offset back
+    state.set_content_width(40);
+    assert_eq!(state.scroll_offset, 5);
+  }
+
+  #[test]
+  fn test_toggle_side_by_side_ignored_outside_diff() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    assert!(!state.toggle_diff_side_by_side());
+    assert!(!state.diff_side_by_side);
+  }
+
+  #[test]
+  fn test_step_image_revision_through_history() {
+    let dir = std::env::temp_dir().join(format!("tfl_image_timeline_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let repo = git2::Repository::init(&dir).unwrap();
+    let sig = git2::Signature::now("Test", "test@test.com").unwrap();
+    let path = dir.join("logo.png");
+    for (i, message) in ["first", "second"].iter().enumerate() {
+      fs::write(&path, format!("v{i}")).unwrap();
+      let mut index = repo.index().unwrap();
+      index.add_path(Path::new("logo.png")).unwrap();
+      index.write().unwrap();
+      let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+      let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+      let parents: Vec<&git2::Commit> = parent.iter().collect();
+      repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap();
+    }
+    let git = GitRepo::open(&dir).unwrap();
+
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    state.request_preview(&path, None, None);
+    assert!(state.image_revision().is_none());
+    assert_eq!(state.step_image_revision(false, None, Some(&git)).as_deref(), Some("Showing working copy"));
+
+    let status = state.step_image_revision(true, None, Some(&git)).unwrap();
+    assert!(status.starts_with("Revision 1/2") && status.ends_with("second"), "{status}");
+    let status = state.step_image_revision(true, None, Some(&git)).unwrap();
+    assert!(status.starts_with("Revision 2/2") && status.ends_with("first"), "{status}");
+    let (index, total, commit) = state.image_revision().unwrap();
+    assert_eq!((index, total, commit.message.as_str()), (1, 2, "first"));
+    assert_eq!(state.step_image_revision(true, None, Some(&git)).as_deref(), Some("Oldest revision"));
+
+    state.step_image_revision(false, None, Some(&git));
+    assert_eq!(state.step_image_revision(false, None, Some(&git)).as_deref(), Some("Working copy"));
+    assert!(state.image_revision().is_none());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_image_revisions_show_as_thumbnails_without_picker() {
+    let dir = std::env::temp_dir().join(format!("tfl_image_timeline_thumb_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let repo = git2::Repository::init(&dir).unwrap();
+    let sig = git2::Signature::now("Test", "test@test.com").unwrap();
+    let path = dir.join("logo.png");
+    ::image::RgbImage::new(8, 4).save(&path).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("logo.png")).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, "small", &tree, &[]).unwrap();
+    ::image::RgbImage::new(16, 6).save(&path).unwrap();
+    let git = GitRepo::open(&dir).unwrap();
+
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    let wait_thumbnail = |state: &mut PreviewState| {
+      while !state.check_image_loaded() {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+      }
+      state.thumbnail.as_ref().map(|t| t.dimensions())
+    };
+    state.request_preview(&path, None, None);
+    assert_eq!(wait_thumbnail(&mut state), Some((16, 6)));
+    state.step_image_revision(true, None, Some(&git));
+    assert_eq!(wait_thumbnail(&mut state), Some((8, 4)));
+    state.step_image_revision(false, None, Some(&git));
+    assert_eq!(wait_thumbnail(&mut state), Some((16, 6)));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_text_preview_marks_uncommitted_changes() {
+    let dir = std::env::temp_dir().join(format!("tfl_change_gutter_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let repo = git2::Repository::init(&dir).unwrap();
+    let sig = git2::Signature::now("Test", "test@test.com").unwrap();
+    let (notes, data) = (dir.join("notes.txt"), dir.join("data.json"));
+    fs::write(&notes, "one\ntwo\nthree\n").unwrap();
+    fs::write(&data, "{\"a\": 1}\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("notes.txt")).unwrap();
+    index.add_path(Path::new("data.json")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[]).unwrap();
+    let git = GitRepo::open(&dir).unwrap();
+
+    let text = |lines: &[Line]| lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+    let wait_changes = |state: &mut PreviewState| {
+      while !state.check_changes_loaded() {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+      }
+    };
+    let modified = GitStatus { staged: None, unstaged: Some(crate::fs::GitFileStatus::Modified) };
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    state.request_preview(&notes, None, Some(&git));
+    let lines = text(&state.get_content().unwrap().lines);
+    assert!(lines[0].starts_with("   1"), "{lines:?}");
+
+    // Clean files aren't diffed at all
+    fs::write(&notes, "one\n2\nthree\nfour\n").unwrap();
+    state.invalidate();
+    state.request_preview(&notes, None, Some(&git));
+    assert!(state.changes_rx.is_none());
+
+    state.invalidate();
+    state.git_status = modified;
+    state.request_preview(&notes, None, Some(&git));
+    wait_changes(&mut state);
+    let lines = text(&state.get_content().unwrap().lines);
+    assert!(lines[0].starts_with("    1"), "{lines:?}");
+    assert!(lines[1].starts_with("~   2"), "{lines:?}");
+    assert!(lines[3].starts_with("+   4"), "{lines:?}");
+
+    // Formatted data doesn't line up with the file, so only its raw lines are marked
+    fs::write(&data, "{\"a\": 2}\n").unwrap();
+    state.request_preview(&data, None, Some(&git));
+    wait_changes(&mut state);
+    let content = state.get_content().unwrap();
+    assert!(text(content.raw_lines.as_ref().unwrap())[0].starts_with("~"));
+    assert!(text(&content.lines).iter().all(|l| !l.starts_with('~')));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_step_image_revision_ignored_for_non_images() {
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    assert!(state.step_image_revision(true, None, None).is_none());
+  }
+
+  #[test]
+  fn test_image_without_picker_shows_details_and_thumbnail() {
+    let dir = std::env::temp_dir().join(format!("tfl_image_fallback_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("photo.png");
+    ::image::RgbaImage::new(40, 20).save(&path).unwrap();
+
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    state.request_preview(&path, None, None);
+    let text: Vec<String> = state.get_content().unwrap().lines.iter().map(|l| l.to_string()).collect();
+    assert!(text.iter().any(|l| l.contains("PNG")), "{text:?}");
+    assert!(text.iter().any(|l| l.contains("40×20 (2:1)")), "{text:?}");
+    assert!(text.iter().any(|l| l.contains("RGBA, 8-bit")), "{text:?}");
+
+    assert!(state.image_rx.is_some());
+    while !state.check_image_loaded() {
+      std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert_eq!(state.thumbnail.as_ref().map(|t| t.dimensions()), Some((40, 20)));
+
+    // Turned off, nothing is decoded
+    let mut state = PreviewState::new("base16-ocean.dark", Theme::dark());
+    state.thumbnails = false;
+    state.request_preview(&path, None, None);
+    assert!(state.image_rx.is_none());
+    let _ = fs::remove_dir_all(&dir);
+  }
 }
@@ -0,0 +1,64 @@
+use std::time::{Duration, Instant};
+
+/// Per-phase startup timings collected for `--profile-startup`.
+pub struct StartupProfile {
+  start: Instant,
+  last: Instant,
+  phases: Vec<(&'static str, Duration)>,
+}
+
+impl Default for StartupProfile {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl StartupProfile {
+  pub fn new() -> Self {
+    let now = Instant::now();
+    Self { start: now, last: now, phases: Vec::new() }
+  }
+
+  /// Record the time spent since the previous mark under `phase`.
+  pub fn mark(&mut self, phase: &'static str) {
+    let now = Instant::now();
+    self.phases.push((phase, now - self.last));
+    self.last = now;
+  }
+
+  /// Record a phase that finished in the background, measured from startup.
+  pub fn mark_since_start(&mut self, phase: &'static str) {
+    self.phases.push((phase, self.start.elapsed()));
+  }
+
+  pub fn report(&self) -> String {
+    let width = self.phases.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut out = String::from("startup profile:\n");
+    for (name, duration) in &self.phases {
+      out.push_str(&format!("  {name:<width$}  {:>8.2} ms\n", ms(*duration)));
+    }
+    out.push_str(&format!("  {:<width$}  {:>8.2} ms", "total", ms(self.last - self.start)));
+    out
+  }
+}
+
+fn ms(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_report_lists_phases_in_order() {
+    let mut profile = StartupProfile::new();
+    profile.mark("config");
+    profile.mark("first frame");
+    let report = profile.report();
+    let config = report.find("config").unwrap();
+    let frame = report.find("first frame").unwrap();
+    assert!(config < frame);
+    assert!(report.lines().last().unwrap().trim_start().starts_with("total"));
+  }
+}
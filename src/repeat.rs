@@ -0,0 +1,174 @@
+/// A completed mutating operation that `repeat_last` can run again on
+/// the current selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repeatable {
+  Rename(RenamePattern),
+  Chmod { mode: u32, recursive: bool, follow_symlinks: bool },
+  /// Repeating creates the next free numbered name in the current directory
+  NewEntry { name: String, is_dir: bool, executable: bool, template: Option<String> },
+  Paste,
+}
+
+impl Repeatable {
+  /// Short description for the status bar, e.g. `rename: suffix "_old"`.
+  pub fn describe(&self) -> String {
+    match self {
+      Repeatable::Rename(pattern) => format!("rename: {}", pattern.describe()),
+      Repeatable::Chmod { mode, recursive, .. } => {
+        format!("chmod {:03o}{}", mode & 0o7777, if *recursive { " -R" } else { "" })
+      }
+      Repeatable::NewEntry { name, is_dir: true, .. } => format!("new dir after {name}"),
+      Repeatable::NewEntry { name, .. } => format!("new file after {name}"),
+      Repeatable::Paste => "paste".to_string(),
+    }
+  }
+}
+
+/// The edit a rename made, inferred from the old and new names so it can
+/// be applied to a different name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenamePattern {
+  /// Replace the first occurrence of `from`
+  Replace { from: String, to: String },
+  Prepend(String),
+  Append(String),
+  /// Insert before the extension
+  AppendStem(String),
+  /// Insert at a character offset from the start
+  Insert { at: usize, text: String },
+  SetExtension { from: String, to: String },
+}
+
+/// Byte index of the extension's dot, ignoring a leading dot (`.bashrc`).
+fn ext_start(name: &str) -> Option<usize> {
+  name.rfind('.').filter(|&i| i > 0)
+}
+
+impl RenamePattern {
+  pub fn from_names(old: &str, new: &str) -> Self {
+    let old_chars: Vec<char> = old.chars().collect();
+    let new_chars: Vec<char> = new.chars().collect();
+    let prefix = old_chars.iter().zip(&new_chars).take_while(|(a, b)| a == b).count();
+    let max_suffix = old_chars.len().min(new_chars.len()) - prefix;
+    let suffix = old_chars
+      .iter()
+      .rev()
+      .zip(new_chars.iter().rev())
+      .take(max_suffix)
+      .take_while(|(a, b)| a == b)
+      .count();
+    let removed: String = old_chars[prefix..old_chars.len() - suffix].iter().collect();
+    let inserted: String = new_chars[prefix..new_chars.len() - suffix].iter().collect();
+
+    if removed.is_empty() && prefix == old_chars.len() {
+      return RenamePattern::Append(inserted);
+    }
+    let old_stem_len = ext_start(old).map_or(old_chars.len(), |i| old[..i].chars().count());
+    if prefix > old_stem_len || (prefix == old_stem_len && !removed.is_empty()) {
+      let ext = |name: &str| ext_start(name).map_or(String::new(), |i| name[i..].to_string());
+      return RenamePattern::SetExtension { from: ext(old), to: ext(new) };
+    }
+    if !removed.is_empty() {
+      RenamePattern::Replace { from: removed, to: inserted }
+    } else if prefix == 0 {
+      RenamePattern::Prepend(inserted)
+    } else if prefix == old_stem_len {
+      RenamePattern::AppendStem(inserted)
+    } else {
+      RenamePattern::Insert { at: prefix, text: inserted }
+    }
+  }
+
+  /// The new name for `name`, or None when the pattern doesn't fit it.
+  pub fn apply(&self, name: &str) -> Option<String> {
+    match self {
+      RenamePattern::Replace { from, to } => name.contains(from.as_str()).then(|| name.replacen(from.as_str(), to, 1)),
+      RenamePattern::Prepend(text) => Some(format!("{text}{name}")),
+      RenamePattern::Append(text) => Some(format!("{name}{text}")),
+      RenamePattern::AppendStem(text) => {
+        let at = ext_start(name).unwrap_or(name.len());
+        Some(format!("{}{text}{}", &name[..at], &name[at..]))
+      }
+      RenamePattern::Insert { at, text } => {
+        let byte = name.char_indices().nth(*at).map(|(i, _)| i)?;
+        Some(format!("{}{text}{}", &name[..byte], &name[byte..]))
+      }
+      RenamePattern::SetExtension { from, to } => {
+        let at = ext_start(name).unwrap_or(name.len());
+        (name[at..] == *from).then(|| format!("{}{to}", &name[..at]))
+      }
+    }
+  }
+
+  fn describe(&self) -> String {
+    match self {
+      RenamePattern::Replace { from, to } => format!("{from:?} → {to:?}"),
+      RenamePattern::Prepend(text) => format!("prefix {text:?}"),
+      RenamePattern::Append(text) => format!("append {text:?}"),
+      RenamePattern::AppendStem(text) => format!("suffix {text:?}"),
+      RenamePattern::Insert { at, text } => format!("insert {text:?} at {at}"),
+      RenamePattern::SetExtension { from, to } => format!("{from} → {to}"),
+    }
+  }
+}
+
+/// `name` with its trailing number bumped (keeping zero padding), or `-2`
+/// appended, repeated until `exists` says the name is free.
+pub fn next_numbered_name(name: &str, is_dir: bool, exists: impl Fn(&str) -> bool) -> String {
+  let split = if is_dir { None } else { ext_start(name) };
+  let (stem, ext) = name.split_at(split.unwrap_or(name.len()));
+  let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+  let (base, mut n, width, sep) = if digits > 0 {
+    let (base, num) = stem.split_at(stem.len() - digits);
+    (base, num.parse::<u64>().unwrap_or(0) + 1, digits, "")
+  } else {
+    (stem, 2, 0, "-")
+  };
+  loop {
+    let candidate = format!("{base}{sep}{n:0width$}{ext}");
+    if !exists(&candidate) {
+      return candidate;
+    }
+    n += 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn roundtrip(old: &str, new: &str, other: &str) -> Option<String> {
+    RenamePattern::from_names(old, new).apply(other)
+  }
+
+  #[test]
+  fn test_rename_pattern_kinds() {
+    assert_eq!(RenamePattern::from_names("foo.txt", "foo_old.txt"), RenamePattern::AppendStem("_old".to_string()));
+    assert_eq!(roundtrip("foo.txt", "foo_old.txt", "bar.rs").as_deref(), Some("bar_old.rs"));
+    assert_eq!(roundtrip("a.txt", "x_a.txt", "b.md").as_deref(), Some("x_b.md"));
+    assert_eq!(roundtrip("IMG_1.jpg", "photo_1.jpg", "IMG_2.jpg").as_deref(), Some("photo_2.jpg"));
+    assert_eq!(roundtrip("IMG_1.jpg", "photo_1.jpg", "DSC_2.jpg"), None);
+    assert_eq!(roundtrip("tree.jpeg", "tree.jpg", "free.jpeg").as_deref(), Some("free.jpg"));
+    assert_eq!(roundtrip("tree.jpeg", "tree.jpg", "free.png"), None);
+    assert_eq!(roundtrip("Makefile", "Makefile.bak", "notes").as_deref(), Some("notes.bak"));
+    assert_eq!(roundtrip("foo.txt", "foo.txt.bak", "bar.txt").as_deref(), Some("bar.txt.bak"));
+    assert_eq!(roundtrip("a.txt", "a", "b.txt").as_deref(), Some("b"));
+  }
+
+  #[test]
+  fn test_next_numbered_name() {
+    let taken = ["note-2.txt", "v09"];
+    let exists = |n: &str| taken.contains(&n);
+    assert_eq!(next_numbered_name("note.txt", false, exists), "note-3.txt");
+    assert_eq!(next_numbered_name("draft1.md", false, exists), "draft2.md");
+    assert_eq!(next_numbered_name("v08", true, exists), "v10");
+    assert_eq!(next_numbered_name("img007.png", false, exists), "img008.png");
+    assert_eq!(next_numbered_name("my.dir", true, exists), "my.dir-2");
+  }
+
+  #[test]
+  fn test_describe() {
+    assert_eq!(Repeatable::Chmod { mode: 0o100755, recursive: true, follow_symlinks: false }.describe(), "chmod 755 -R");
+    assert_eq!(Repeatable::Rename(RenamePattern::AppendStem("_old".to_string())).describe(), "rename: suffix \"_old\"");
+  }
+}
@@ -0,0 +1,218 @@
+//! The settings overlay: a few everyday options, applied as they are
+//! changed and written back into config.toml on confirm.
+
+use std::path::Path;
+
+use crate::config::Config;
+use crate::persist;
+use crate::theme::Theme;
+
+/// The options the overlay edits, in the order it lists them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+  pub theme: String,
+  pub icons: bool,
+  pub tree_ratio: u16,
+  pub dirs_first: bool,
+  pub show_hidden: bool,
+}
+
+pub const FIELD_COUNT: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct SettingsEditor {
+  /// Values when the overlay opened, put back on cancel
+  pub original: Settings,
+  pub draft: Settings,
+  pub cursor: usize,
+}
+
+impl SettingsEditor {
+  pub fn new(current: Settings) -> Self {
+    Self { original: current.clone(), draft: current, cursor: 0 }
+  }
+
+  pub fn move_down(&mut self) {
+    self.cursor = (self.cursor + 1).min(FIELD_COUNT - 1);
+  }
+
+  pub fn move_up(&mut self) {
+    self.cursor = self.cursor.saturating_sub(1);
+  }
+
+  /// Step the selected option to its next (or previous) value. The tree
+  /// width moves by `step` within `min..=max`.
+  pub fn change(&mut self, forward: bool, min: u16, max: u16, step: u16) {
+    let draft = &mut self.draft;
+    match self.cursor {
+      0 => {
+        let themes = Theme::available_themes();
+        let at = themes.iter().position(|t| *t == draft.theme).unwrap_or(0);
+        let next = if forward { (at + 1) % themes.len() } else { (at + themes.len() - 1) % themes.len() };
+        draft.theme = themes[next].to_string();
+      }
+      1 => draft.icons = !draft.icons,
+      2 => {
+        draft.tree_ratio =
+          if forward { (draft.tree_ratio + step).min(max) } else { draft.tree_ratio.saturating_sub(step).max(min) };
+      }
+      3 => draft.dirs_first = !draft.dirs_first,
+      4 => draft.show_hidden = !draft.show_hidden,
+      _ => {}
+    }
+  }
+
+  /// Label and shown value of each option.
+  pub fn rows(&self) -> [(&'static str, String); FIELD_COUNT] {
+    let on = |value: bool| if value { "on" } else { "off" }.to_string();
+    let draft = &self.draft;
+    [
+      ("Theme", draft.theme.clone()),
+      ("Icons", on(draft.icons)),
+      ("Tree width", format!("{}%", draft.tree_ratio)),
+      ("Directories first", on(draft.dirs_first)),
+      ("Show hidden", on(draft.show_hidden)),
+    ]
+  }
+}
+
+/// Write the settings that differ from `original` into the config file at
+/// `path`, starting from the default config when there is none yet.
+pub fn save(path: &Path, original: &Settings, draft: &Settings) -> std::io::Result<()> {
+  let text = match std::fs::read_to_string(path) {
+    Ok(text) => text,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default_toml().to_string(),
+    Err(e) => return Err(e),
+  };
+  persist::write_atomic(path, write_changes(&text, original, draft))
+}
+
+/// `text` with the settings that differ from `original` written in.
+pub fn write_changes(text: &str, original: &Settings, draft: &Settings) -> String {
+  let mut text = text.to_string();
+  if draft.theme != original.theme {
+    text = set_value(&text, "general", "theme", &format!("\"{}\"", draft.theme));
+  }
+  if draft.icons != original.icons {
+    text = set_value(&text, "general", "icons", &draft.icons.to_string());
+  }
+  if draft.tree_ratio != original.tree_ratio {
+    text = set_value(&text, "general", "tree_ratio", &draft.tree_ratio.to_string());
+  }
+  if draft.show_hidden != original.show_hidden {
+    text = set_value(&text, "general", "show_hidden", &draft.show_hidden.to_string());
+  }
+  if draft.dirs_first != original.dirs_first {
+    text = set_value(&text, "sort", "dirs_first", &draft.dirs_first.to_string());
+  }
+  text
+}
+
+/// Set `key` in `[section]` to the TOML `value`. An existing line keeps its
+/// trailing comment; otherwise the key goes right under the section header,
+/// and a missing section is appended.
+pub fn set_value(text: &str, section: &str, key: &str, value: &str) -> String {
+  let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+  let mut in_section = false;
+  let mut header_at = None;
+  for (i, line) in lines.iter().enumerate() {
+    let trimmed = line.trim();
+    if trimmed.starts_with('[') {
+      if in_section {
+        break;
+      }
+      in_section = without_comment(trimmed).trim() == format!("[{section}]");
+      if in_section {
+        header_at = Some(i);
+      }
+    } else if in_section && trimmed.split_once('=').is_some_and(|(k, _)| k.trim() == key) {
+      lines[i] = replace_value(line, key, value);
+      return lines.join("\n") + "\n";
+    }
+  }
+  let entry = format!("{key} = {value}");
+  match header_at {
+    Some(at) => lines.insert(at + 1, entry),
+    None => {
+      if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+        lines.push(String::new());
+      }
+      lines.push(format!("[{section}]"));
+      lines.push(entry);
+    }
+  }
+  lines.join("\n") + "\n"
+}
+
+/// `line` with its value replaced, the comment kept in its column.
+fn replace_value(line: &str, key: &str, value: &str) -> String {
+  let indent = &line[..line.len() - line.trim_start().len()];
+  let mut new = format!("{indent}{key} = {value}");
+  let code = without_comment(line);
+  if code.len() < line.len() {
+    let padding = code.chars().count().saturating_sub(new.chars().count()).max(1);
+    new.push_str(&" ".repeat(padding));
+    new.push_str(&line[code.len()..]);
+  }
+  new
+}
+
+/// `line` up to a `#` that is not inside a string.
+fn without_comment(line: &str) -> &str {
+  let mut quote = None;
+  let mut escaped = false;
+  for (i, c) in line.char_indices() {
+    match (quote, c) {
+      (Some('"'), '\\') if !escaped => {
+        escaped = true;
+        continue;
+      }
+      (Some(q), c) if c == q && !escaped => quote = None,
+      (None, '"' | '\'') => quote = Some(c),
+      (None, '#') => return &line[..i],
+      _ => {}
+    }
+    escaped = false;
+  }
+  line
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_set_value_keeps_comments_and_adds_missing_keys() {
+    let text = "[general]\ntheme = \"dark\"      # \"dark\", \"light\"\ntitle = \"a # b\"\n\n[sort]\npinned = []\n";
+
+    let text = set_value(text, "general", "theme", "\"light\"");
+    assert!(text.contains("theme = \"light\"     # \"dark\", \"light\"\n"));
+
+    // A `#` inside a string is not a comment
+    let replaced = set_value(&text, "general", "title", "\"c\"");
+    assert!(replaced.contains("title = \"c\"\n"));
+
+    let text = set_value(&text, "sort", "dirs_first", "false");
+    assert!(text.contains("[sort]\ndirs_first = false\npinned = []\n"));
+
+    let text = set_value(&text, "preview", "max_lines", "10");
+    assert!(text.ends_with("pinned = []\n\n[preview]\nmax_lines = 10\n"));
+
+    let original = Settings {
+      theme: "light".to_string(),
+      icons: true,
+      tree_ratio: 30,
+      dirs_first: false,
+      show_hidden: false,
+    };
+    let draft = Settings { icons: false, tree_ratio: 40, ..original.clone() };
+    let written = write_changes(&text, &original, &draft);
+    let config = Config::load_from_str(&written);
+    assert!(!config.icons);
+    assert_eq!(config.tree_ratio, 40);
+    assert_eq!(config.theme_name, "light");
+    assert!(!config.dirs_first);
+    // Only the changed options are written
+    assert!(!written.contains("show_hidden"));
+  }
+}
@@ -0,0 +1,191 @@
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{Clipboard, ClipboardOp};
+use crate::journal;
+use crate::{paths, persist};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ClipboardFile {
+  #[serde(default)]
+  paths: Vec<PathBuf>,
+  #[serde(default)]
+  cut: bool,
+  pid: u32,
+  /// Nanoseconds since the epoch when written, to tell writes apart
+  stamp: u128,
+}
+
+/// The file clipboard shared between running tfl instances through
+/// `clipboard.json` in the cache dir. Writers hold an exclusive advisory
+/// lock on `clipboard.lock`, readers a shared one.
+pub struct SharedClipboard {
+  dir: PathBuf,
+  pid: u32,
+  /// Clipboard as last published or adopted
+  last: Clipboard,
+  last_stamp: u128,
+  /// Hash of `clipboard.json` as last read or written. Two writes can land
+  /// within one mtime tick, so the content tells them apart
+  seen: Option<u64>,
+  started: bool,
+}
+
+impl Default for SharedClipboard {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl SharedClipboard {
+  pub fn new() -> Self {
+    Self::with_dir(paths::cache_dir(), std::process::id())
+  }
+
+  pub fn with_dir(dir: PathBuf, pid: u32) -> Self {
+    Self {
+      dir,
+      pid,
+      last: Clipboard { paths: Vec::new(), op: None },
+      last_stamp: 0,
+      seen: None,
+      started: false,
+    }
+  }
+
+  fn path(&self) -> PathBuf {
+    self.dir.join("clipboard.json")
+  }
+
+  fn lock(&self) -> Result<File> {
+    std::fs::create_dir_all(&self.dir)?;
+    Ok(File::options().create(true).truncate(false).write(true).open(self.dir.join("clipboard.lock"))?)
+  }
+
+  /// Publish local changes to `clipboard`, or adopt a newer clipboard
+  /// written by another instance. Returns true when `clipboard` was replaced.
+  ///
+  /// On the first call a clipboard left by an instance that has since
+  /// exited is ignored, so a new session doesn't start with stale paths.
+  pub fn sync(&mut self, clipboard: &mut Clipboard) -> Result<bool> {
+    let first = !self.started;
+    self.started = true;
+    if *clipboard != self.last {
+      self.publish(clipboard)?;
+      return Ok(false);
+    }
+    let lock = self.lock()?;
+    lock.lock_shared()?;
+    let contents = std::fs::read_to_string(self.path());
+    drop(lock);
+    let Ok(contents) = contents else {
+      return Ok(false);
+    };
+    let seen = Some(content_hash(&contents));
+    if seen == self.seen {
+      return Ok(false);
+    }
+    self.seen = seen;
+    let Ok(file) = serde_json::from_str::<ClipboardFile>(&contents) else {
+      return Ok(false);
+    };
+    if file.stamp == self.last_stamp || file.pid == self.pid {
+      return Ok(false);
+    }
+    self.last_stamp = file.stamp;
+    if first && !journal::process_alive(file.pid) {
+      return Ok(false);
+    }
+    let adopted = Clipboard {
+      op: (!file.paths.is_empty()).then_some(if file.cut { ClipboardOp::Cut } else { ClipboardOp::Copy }),
+      paths: file.paths,
+    };
+    if adopted == *clipboard {
+      return Ok(false);
+    }
+    *clipboard = adopted.clone();
+    self.last = adopted;
+    Ok(true)
+  }
+
+  fn publish(&mut self, clipboard: &Clipboard) -> Result<()> {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let file = ClipboardFile {
+      paths: clipboard.paths.clone(),
+      cut: clipboard.op == Some(ClipboardOp::Cut),
+      pid: self.pid,
+      stamp,
+    };
+    let contents = serde_json::to_string(&file)?;
+    let lock = self.lock()?;
+    lock.lock()?;
+    persist::write_atomic(&self.path(), &contents)?;
+    drop(lock);
+    self.last = clipboard.clone();
+    self.last_stamp = stamp;
+    self.seen = Some(content_hash(&contents));
+    Ok(())
+  }
+}
+
+fn content_hash(contents: &str) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  contents.hash(&mut hasher);
+  hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tfl_clipboard_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+  }
+
+  #[test]
+  fn test_cut_in_one_instance_pastes_in_another() {
+    let dir = temp_dir("share");
+    let me = std::process::id();
+    let mut a = SharedClipboard::with_dir(dir.clone(), me);
+    let mut b = SharedClipboard::with_dir(dir.clone(), me + 1);
+    let mut clip_a = Clipboard { paths: Vec::new(), op: None };
+    let mut clip_b = clip_a.clone();
+    assert!(!a.sync(&mut clip_a).unwrap());
+    assert!(!b.sync(&mut clip_b).unwrap());
+
+    clip_a = Clipboard { paths: vec![PathBuf::from("/tmp/x")], op: Some(ClipboardOp::Cut) };
+    assert!(!a.sync(&mut clip_a).unwrap());
+    assert!(b.sync(&mut clip_b).unwrap());
+    assert_eq!(clip_b, clip_a);
+    // Nothing new the second time
+    assert!(!b.sync(&mut clip_b).unwrap());
+
+    // Pasting a cut empties the clipboard everywhere
+    clip_b = Clipboard { paths: Vec::new(), op: None };
+    assert!(!b.sync(&mut clip_b).unwrap());
+    assert!(a.sync(&mut clip_a).unwrap());
+    assert!(clip_a.paths.is_empty());
+    assert_eq!(clip_a.op, None);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_ignores_clipboard_of_exited_instance_at_startup() {
+    let dir = temp_dir("stale");
+    std::fs::create_dir_all(&dir).unwrap();
+    let stale = ClipboardFile { paths: vec![PathBuf::from("/tmp/old")], cut: false, pid: u32::MAX - 1, stamp: 1 };
+    std::fs::write(dir.join("clipboard.json"), serde_json::to_string(&stale).unwrap()).unwrap();
+    let mut shared = SharedClipboard::with_dir(dir.clone(), std::process::id());
+    let mut clip = Clipboard { paths: Vec::new(), op: None };
+    assert!(!shared.sync(&mut clip).unwrap());
+    assert!(clip.paths.is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,114 @@
+use std::path::Path;
+
+/// A quick-new file template: a default file name and its initial content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTemplate {
+  pub name: String,
+  pub content: String,
+}
+
+/// Templates offered when `marker` exists in the current directory or any ancestor
+/// (e.g. `Cargo.toml` for Rust projects, `package.json` for npm projects).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextRule {
+  pub marker: String,
+  pub templates: Vec<FileTemplate>,
+}
+
+/// Returns the templates of every rule whose marker is found in `dir` or one of its ancestors,
+/// in rule order. Templates with a duplicate name are only listed once.
+pub fn templates_for_dir(dir: &Path, rules: &[ContextRule]) -> Vec<FileTemplate> {
+  let mut result: Vec<FileTemplate> = Vec::new();
+  for rule in rules {
+    if !dir.ancestors().any(|d| d.join(&rule.marker).exists()) {
+      continue;
+    }
+    for template in &rule.templates {
+      if !result.iter().any(|t| t.name == template.name) {
+        result.push(template.clone());
+      }
+    }
+  }
+  result
+}
+
+/// Renders template content for a new file, substituting `{name}` with the file name
+/// up to its first dot (`Button.test.tsx` -> `Button`).
+pub fn render_content(content: &str, file_name: &str) -> String {
+  let stem = file_name.split('.').next().unwrap_or(file_name);
+  content.replace("{name}", stem)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+  use std::path::PathBuf;
+
+  fn test_dir(prefix: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tfl_templates_{prefix}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn rule(marker: &str, names: &[&str]) -> ContextRule {
+    ContextRule {
+      marker: marker.to_string(),
+      templates: names
+        .iter()
+        .map(|n| FileTemplate { name: n.to_string(), content: String::new() })
+        .collect(),
+    }
+  }
+
+  #[test]
+  fn test_templates_for_dir_matches_marker_in_dir() {
+    let dir = test_dir("in_dir");
+    fs::write(dir.join("Cargo.toml"), "").unwrap();
+    let rules = vec![rule("Cargo.toml", &["mod.rs"]), rule("package.json", &["index.ts"])];
+    let templates = templates_for_dir(&dir, &rules);
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0].name, "mod.rs");
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_templates_for_dir_matches_marker_in_ancestor() {
+    let dir = test_dir("ancestor");
+    fs::write(dir.join("package.json"), "{}").unwrap();
+    let nested = dir.join("src").join("components");
+    fs::create_dir_all(&nested).unwrap();
+    let rules = vec![rule("package.json", &["Component.tsx"])];
+    let templates = templates_for_dir(&nested, &rules);
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0].name, "Component.tsx");
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_templates_for_dir_no_marker() {
+    let dir = test_dir("no_marker");
+    let rules = vec![rule("tfl-no-such-marker.toml", &["mod.rs"])];
+    assert!(templates_for_dir(&dir, &rules).is_empty());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_templates_for_dir_dedups_names() {
+    let dir = test_dir("dedup");
+    fs::write(dir.join("a.marker"), "").unwrap();
+    fs::write(dir.join("b.marker"), "").unwrap();
+    let rules = vec![rule("a.marker", &["x.txt", "y.txt"]), rule("b.marker", &["y.txt", "z.txt"])];
+    let names: Vec<String> = templates_for_dir(&dir, &rules).into_iter().map(|t| t.name).collect();
+    assert_eq!(names, vec!["x.txt", "y.txt", "z.txt"]);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_render_content_substitutes_stem() {
+    assert_eq!(render_content("export function {name}() {}", "Button.tsx"), "export function Button() {}");
+    assert_eq!(render_content("describe(\"{name}\")", "Button.test.tsx"), "describe(\"Button\")");
+    assert_eq!(render_content("no placeholder", "x.rs"), "no placeholder");
+  }
+}
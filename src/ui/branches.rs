@@ -0,0 +1,75 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::theme::Theme;
+use crate::ui::width;
+
+pub fn render_branches(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let choices = &app.branch_choices;
+  let width = 50.min(area.width.saturating_sub(4));
+  let content_height = (choices.len().max(1) as u16 + 2).min(area.height.saturating_sub(2));
+
+  if width < 20 || content_height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(content_height)) / 2;
+  let popup = Rect::new(x, y, width, content_height);
+
+  Clear.render(popup, buf);
+
+  // Keep the cursor row in view when there are more rows than fit
+  let rows = content_height.saturating_sub(2) as usize;
+  let skip = (app.branch_cursor + 1).saturating_sub(rows);
+  let inner = width.saturating_sub(2) as usize;
+
+  let mut lines: Vec<Line> = choices
+    .iter()
+    .enumerate()
+    .skip(skip)
+    .take(rows)
+    .map(|(i, branch)| {
+      let selected = app.branch_cursor == i;
+      let current = app.branch_current.as_ref() == Some(&branch.name);
+      let prefix = match (selected, current) {
+        (true, _) => " > ",
+        (false, true) => " * ",
+        (false, false) => "   ",
+      };
+      let name_style = if selected {
+        Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+      } else if current {
+        Style::default().fg(theme.success)
+      } else {
+        Style::default().fg(theme.text)
+      };
+      let detail = format!("  {}", branch.date);
+      let name = width::truncate(&branch.name, inner.saturating_sub(3 + width::width(&detail)));
+      Line::from(vec![
+        Span::styled(format!("{prefix}{name}"), name_style),
+        Span::styled(detail, Style::default().fg(theme.text_dim)),
+      ])
+    })
+    .collect();
+  if choices.is_empty() {
+    lines.push(Line::from(Span::styled("   No branches yet (n: new)", Style::default().fg(theme.text_dim))));
+  }
+
+  let title = match app.branch_current {
+    Some(ref current) => format!(" Branches (on {current}) "),
+    None => " Branches (detached) ".to_string(),
+  };
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(title)
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  let paragraph = Paragraph::new(lines).block(block);
+  paragraph.render(popup, buf);
+}
@@ -1,5 +1,7 @@
 use std::path::{Path, PathBuf};
 
+use crate::ui::width;
+
 /// A segment of the breadcrumb path
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BreadcrumbSegment {
@@ -9,7 +11,7 @@ pub struct BreadcrumbSegment {
   pub path: PathBuf,
   /// Start position (column) in the rendered breadcrumb
   pub start_col: u16,
-  /// Width of this segment in characters
+  /// Width of this segment in terminal columns
   pub width: u16,
 }
 
@@ -61,7 +63,7 @@ pub fn parse_breadcrumb_segments(path: &Path) -> Vec<BreadcrumbSegment> {
       (name, accumulated.clone())
     };
 
-    let width = display_name.chars().count() as u16;
+    let width = width::width(&display_name) as u16;
 
     segments.push(BreadcrumbSegment {
       name: display_name,
@@ -107,6 +109,8 @@ pub fn truncate_breadcrumbs(
     // Even the minimum doesn't fit, just show truncated last segment
     let mut truncated = last.clone();
     truncated.start_col = 0;
+    truncated.name = width::truncate(&last.name, max_width as usize);
+    truncated.width = width::width(&truncated.name) as u16;
     return (vec![truncated], true);
   }
 
@@ -199,6 +203,31 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_parse_measures_wide_names_in_columns() {
+    let segments = parse_breadcrumb_segments(Path::new("/データ/写真"));
+    let data = segments.iter().find(|s| s.name == "データ").unwrap();
+    assert_eq!(data.width, 6);
+    let photos = segments.last().unwrap();
+    assert_eq!(photos.width, 4);
+    assert_eq!(photos.start_col, data.start_col + 6 + 3);
+    assert_eq!(segment_at_column(&segments, photos.start_col + 3), Some(segments.len() - 1));
+  }
+
+  #[test]
+  fn test_truncate_cuts_wide_last_segment_to_fit() {
+    let segments = vec![BreadcrumbSegment {
+      name: "とても長いディレクトリ名".to_string(),
+      path: PathBuf::from("/とても長いディレクトリ名"),
+      start_col: 0,
+      width: 24,
+    }];
+    let (result, truncated) = truncate_breadcrumbs(&segments, 10);
+    assert!(truncated);
+    assert_eq!(result[0].name, "とても長…");
+    assert_eq!(result[0].width, 9);
+  }
+
   #[test]
   fn test_truncate_short_path_no_truncation() {
     let segments = vec![
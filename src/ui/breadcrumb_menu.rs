@@ -0,0 +1,74 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::breadcrumb_menu::{BreadcrumbMenu, scroll_offset};
+use crate::theme::Theme;
+use crate::ui::breadcrumb::BreadcrumbSegment;
+use crate::ui::width;
+
+/// Draw the dropdown under the segment of the listed directory, or at the
+/// end of the path once it lists a directory further down. Returns where
+/// it went, for mouse clicks.
+pub fn render_breadcrumb_menu(
+  menu: &BreadcrumbMenu,
+  segments: &[BreadcrumbSegment],
+  area: Rect,
+  buf: &mut Buffer,
+  theme: &Theme,
+) -> Option<Rect> {
+  let names: Vec<String> = menu
+    .entries
+    .iter()
+    .map(|p| format!("{}/", p.file_name().unwrap_or_default().to_string_lossy()))
+    .collect();
+  let longest = names.iter().map(|n| width::width(n)).max().unwrap_or(0) as u16;
+  let width = (longest + 5).clamp(24, 48).min(area.width);
+  let height = (names.len().max(1) as u16 + 2).min(area.height.saturating_sub(1));
+  if width < 10 || height < 3 {
+    return None;
+  }
+
+  // One column for the header's leading space
+  let anchor = match segments.iter().find(|s| s.path == menu.dir) {
+    Some(segment) => segment.start_col + 1,
+    None => segments.last().map(|s| s.start_col + s.width + 1).unwrap_or(0),
+  };
+  let x = area.x + anchor.min(area.width - width);
+  let popup = Rect::new(x, area.y + 1, width, height);
+
+  Clear.render(popup, buf);
+
+  let rows = height.saturating_sub(2) as usize;
+  let offset = scroll_offset(menu.cursor, rows);
+  let lines: Vec<Line> = if names.is_empty() {
+    vec![Line::from(Span::styled(" No subdirectories", Style::default().fg(theme.text_muted)))]
+  } else {
+    names
+      .iter()
+      .enumerate()
+      .skip(offset)
+      .take(rows)
+      .map(|(i, name)| {
+        let name = width::truncate(name, width.saturating_sub(5) as usize);
+        if i == menu.cursor {
+          Line::from(Span::styled(format!(" > {name}"), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)))
+        } else {
+          Line::from(Span::styled(format!("   {name}"), Style::default().fg(theme.text)))
+        }
+      })
+      .collect()
+  };
+
+  let title = format!(" {} ", menu.dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or("/".to_string()));
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(width::truncate(&title, width.saturating_sub(2) as usize))
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+  Some(popup)
+}
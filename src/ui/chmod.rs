@@ -4,12 +4,12 @@ use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
 
-use crate::app::App;
+use crate::app::{App, ChmodState};
 use crate::theme::Theme;
 
 pub fn render_chmod(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
-  let width = 50.min(area.width.saturating_sub(4));
-  let height = 14.min(area.height.saturating_sub(2));
+  let width = 56.min(area.width.saturating_sub(4));
+  let height = 18.min(area.height.saturating_sub(2));
 
   if width < 20 || height < 8 {
     return;
@@ -35,6 +35,10 @@ pub fn render_chmod(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
   let name = chmod_state.path.file_name()
     .map(|n| n.to_string_lossy().to_string())
     .unwrap_or_else(|| chmod_state.path.to_string_lossy().to_string());
+  let name = match chmod_state.others.len() {
+    0 => name,
+    n => format!("{name} and {n} more"),
+  };
   lines.push(Line::from(vec![
     Span::styled(" File: ", dim),
     Span::styled(name, Style::default().fg(theme.text)),
@@ -50,30 +54,38 @@ pub fn render_chmod(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
 
   // Owner row
   let owner_spans = render_permission_row(
-    PermRowConfig { label: "Owner", r_key: 'r', w_key: 'w', x_key: 'x', shift: 6 },
+    PermRowConfig { label: "Owner", r_key: 'r', w_key: 'w', x_key: 'x', shift: 6, glyphs: RWX },
     mode, original_mode, changed, theme,
   );
   lines.push(Line::from(owner_spans));
 
   // Group row
   let group_spans = render_permission_row(
-    PermRowConfig { label: "Group", r_key: 'R', w_key: 'W', x_key: 'X', shift: 3 },
+    PermRowConfig { label: "Group", r_key: 'R', w_key: 'W', x_key: 'X', shift: 3, glyphs: RWX },
     mode, original_mode, changed, theme,
   );
   lines.push(Line::from(group_spans));
 
   // Others row
   let others_spans = render_permission_row(
-    PermRowConfig { label: "Others", r_key: '4', w_key: '2', x_key: '1', shift: 0 },
+    PermRowConfig { label: "Others", r_key: '4', w_key: '2', x_key: '1', shift: 0, glyphs: RWX },
     mode, original_mode, changed, theme,
   );
   lines.push(Line::from(others_spans));
 
+  // setuid / setgid / sticky share the row layout: 4000, 2000, 1000
+  let mut special_spans = render_permission_row(
+    PermRowConfig { label: "Special", r_key: 'u', w_key: 'g', x_key: 't', shift: 9, glyphs: ['s', 's', 't'] },
+    mode, original_mode, changed, theme,
+  );
+  special_spans.push(Span::styled("  suid sgid sticky", dim));
+  lines.push(Line::from(special_spans));
+
   lines.push(Line::from(""));
 
   // Octal display
-  let octal_str = format!("{:03o}", mode & 0o777);
-  let original_octal = format!("{:03o}", original_mode & 0o777);
+  let octal_str = ChmodState::octal(mode);
+  let original_octal = ChmodState::octal(original_mode);
   let octal_style = if octal_str != original_octal { changed } else { Style::default().fg(theme.text) };
 
   if chmod_state.octal_mode {
@@ -102,6 +114,33 @@ pub fn render_chmod(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
     ]));
   }
 
+  // Follow symlinks option (only when links can be involved)
+  if chmod_state.is_dir || chmod_state.is_symlink {
+    let follow_style = if chmod_state.follow_symlinks { changed } else { dim };
+    let follow_text = if chmod_state.follow_symlinks { "[x]" } else { "[ ]" };
+    lines.push(Line::from(vec![
+      Span::styled(" Follow symlinks: ", dim),
+      Span::styled(follow_text, follow_style),
+      Span::styled(" (l)", dim),
+    ]));
+  }
+
+  if let Some(warning) = chmod_state.warning() {
+    lines.push(Line::from(Span::styled(
+      format!(" ⚠ {warning}"),
+      Style::default().fg(theme.warning).add_modifier(Modifier::BOLD),
+    )));
+  }
+
+  if !app.chmod_presets.is_empty() {
+    let mut spans = vec![Span::styled(" Presets:", dim)];
+    for preset in &app.chmod_presets {
+      spans.push(Span::styled(format!(" {}", preset.key.display_key()), highlight));
+      spans.push(Span::styled(format!(":{}", preset.label()), Style::default().fg(theme.text)));
+    }
+    lines.push(Line::from(spans));
+  }
+
   lines.push(Line::from(""));
 
   // Hints
@@ -126,8 +165,11 @@ struct PermRowConfig<'a> {
   w_key: char,
   x_key: char,
   shift: u8,
+  glyphs: [char; 3],
 }
 
+const RWX: [char; 3] = ['r', 'w', 'x'];
+
 #[allow(clippy::too_many_arguments)]
 fn render_permission_row<'a>(
   cfg: PermRowConfig<'a>,
@@ -159,11 +201,11 @@ fn render_permission_row<'a>(
   vec![
     Span::styled(format!(" {:6}", cfg.label), dim),
     Span::styled("  ", dim),
-    Span::styled(if r_on { "r" } else { "-" }, r_style),
+    Span::styled(if r_on { cfg.glyphs[0] } else { '-' }.to_string(), r_style),
     Span::styled(format!("({})", cfg.r_key), dim),
-    Span::styled(if w_on { "w" } else { "-" }, w_style),
+    Span::styled(if w_on { cfg.glyphs[1] } else { '-' }.to_string(), w_style),
     Span::styled(format!("({})", cfg.w_key), dim),
-    Span::styled(if x_on { "x" } else { "-" }, x_style),
+    Span::styled(if x_on { cfg.glyphs[2] } else { '-' }.to_string(), x_style),
     Span::styled(format!("({})", cfg.x_key), dim),
   ]
 }
@@ -171,7 +213,6 @@ fn render_permission_row<'a>(
 #[cfg(test)]
 mod tests {
   use super::*;
-  use crate::app::ChmodState;
   use crate::theme::Theme;
   use ratatui::style::Color;
   use std::path::PathBuf;
@@ -179,10 +220,14 @@ mod tests {
   fn make_chmod_state(mode: u32, is_dir: bool) -> ChmodState {
     ChmodState {
       path: PathBuf::from("/test/file.txt"),
+      others: Vec::new(),
       original_mode: mode,
       new_mode: mode,
       is_dir,
       recursive: false,
+      is_symlink: false,
+      follow_symlinks: false,
+      owner_uid: 1000,
       octal_mode: false,
       octal_input: String::new(),
     }
@@ -192,7 +237,7 @@ mod tests {
   fn test_render_permission_row_all_on() {
     let theme = Theme::dark();
     let changed = Style::default().fg(Color::Indexed(114));
-    let cfg = PermRowConfig { label: "Owner", r_key: 'r', w_key: 'w', x_key: 'x', shift: 6 };
+    let cfg = PermRowConfig { label: "Owner", r_key: 'r', w_key: 'w', x_key: 'x', shift: 6, glyphs: RWX };
     let spans = render_permission_row(cfg, 0o700, 0o700, changed, &theme);
     // Check that rwx are rendered
     assert!(spans.iter().any(|s| s.content.contains("r")));
@@ -204,7 +249,7 @@ mod tests {
   fn test_render_permission_row_all_off() {
     let theme = Theme::dark();
     let changed = Style::default().fg(Color::Indexed(114));
-    let cfg = PermRowConfig { label: "Owner", r_key: 'r', w_key: 'w', x_key: 'x', shift: 6 };
+    let cfg = PermRowConfig { label: "Owner", r_key: 'r', w_key: 'w', x_key: 'x', shift: 6, glyphs: RWX };
     let spans = render_permission_row(cfg, 0o000, 0o000, changed, &theme);
     // Check that --- are rendered (dashes instead of letters)
     let content: String = spans.iter().map(|s| s.content.to_string()).collect();
@@ -219,4 +264,14 @@ mod tests {
     assert!(!state.is_dir);
     assert!(!state.recursive);
   }
+
+  #[test]
+  fn test_render_special_row() {
+    let theme = Theme::dark();
+    let changed = Style::default().fg(Color::Indexed(114));
+    let cfg = PermRowConfig { label: "Special", r_key: 'u', w_key: 'g', x_key: 't', shift: 9, glyphs: ['s', 's', 't'] };
+    let spans = render_permission_row(cfg, 0o5755, 0o0755, changed, &theme);
+    let content: String = spans.iter().map(|s| s.content.to_string()).collect();
+    assert!(content.contains("s(u)-(g)t(t)"));
+  }
 }
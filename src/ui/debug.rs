@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::logging;
+use crate::theme::Theme;
+
+/// Non-modal overlay in the bottom-right corner: loop latency, preview cache
+/// hit rate and the tail of the log.
+pub fn render_debug(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let width = 72.min(area.width.saturating_sub(2));
+  let height = 16.min(area.height.saturating_sub(2));
+  if width < 20 || height < 6 {
+    return;
+  }
+
+  let x = area.x + area.width - width - 1;
+  let y = area.y + area.height - height - 1;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let label_style = Style::default().fg(theme.text_muted);
+  let value_style = Style::default().fg(theme.text).add_modifier(Modifier::BOLD);
+  let log_style = Style::default().fg(theme.text_dim);
+
+  let stats = &app.loop_stats;
+  let (hits, misses) = (app.preview.cache_hits, app.preview.cache_misses);
+  let hit_rate = if hits + misses == 0 {
+    "-".to_string()
+  } else {
+    format!("{:.0}% ({hits}/{})", hits as f64 * 100.0 / (hits + misses) as f64, hits + misses)
+  };
+  let mut lines = vec![
+    Line::from(vec![
+      Span::styled(" loop  last ", label_style),
+      Span::styled(fmt_ms(stats.last()), value_style),
+      Span::styled("  avg ", label_style),
+      Span::styled(fmt_ms(stats.average()), value_style),
+      Span::styled("  max ", label_style),
+      Span::styled(fmt_ms(Some(stats.max)), value_style),
+    ]),
+    Line::from(vec![
+      Span::styled(" preview cache ", label_style),
+      Span::styled(hit_rate, value_style),
+    ]),
+    Line::from(""),
+  ];
+
+  let inner_width = width.saturating_sub(2) as usize;
+  let log_rows = (height as usize).saturating_sub(2 + lines.len());
+  let recent = logging::recent_lines(log_rows);
+  if recent.is_empty() {
+    lines.push(Line::from(Span::styled(" (no log lines; raise TFL_LOG for more)", log_style)));
+  }
+  for line in recent {
+    let clipped: String = format!(" {line}").chars().take(inner_width).collect();
+    lines.push(Line::from(Span::styled(clipped, log_style)));
+  }
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" Debug ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
+
+fn fmt_ms(duration: Option<Duration>) -> String {
+  duration.map_or("-".to_string(), |d| format!("{:.1}ms", d.as_secs_f64() * 1000.0))
+}
@@ -0,0 +1,96 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::DeleteSummary;
+use crate::preview::directory::SizeFormat;
+use crate::theme::Theme;
+use crate::ui::width;
+
+/// Direct children listed before the rest are summed up
+const MAX_CHILDREN: usize = 8;
+
+fn plural(n: usize, one: &str, many: &str) -> String {
+  format!("{n} {}", if n == 1 { one } else { many })
+}
+
+/// What a directory or multi-entry delete would remove, shown above the
+/// y/N prompt.
+pub fn render_delete_summary(pending: &DeleteSummary, size_format: SizeFormat, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let width = 60.min(area.width.saturating_sub(4));
+  let inner = width.saturating_sub(2) as usize;
+  let dim = Style::default().fg(theme.text_dim);
+
+  let mut lines: Vec<Line> = Vec::new();
+  match pending.summary {
+    None => lines.push(Line::from(Span::styled(" Counting…", dim))),
+    Some(ref summary) => {
+      lines.push(Line::from(Span::styled(
+        format!(
+          " {} in {}, {}",
+          plural(summary.files, "file", "files"),
+          plural(summary.dirs, "folder", "folders"),
+          size_format.format(summary.bytes)
+        ),
+        Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+      )));
+      if summary.git_repos > 0 {
+        lines.push(Line::from(Span::styled(
+          format!(" Contains {}", plural(summary.git_repos, "git repository", "git repositories")),
+          Style::default().fg(theme.warning),
+        )));
+      }
+      if !summary.children.is_empty() {
+        lines.push(Line::from(""));
+      }
+      for child in summary.children.iter().take(MAX_CHILDREN) {
+        let size = if child.is_dir {
+          format!("{}  {:>9} ", plural(child.files, "file", "files"), size_format.format(child.bytes))
+        } else {
+          format!("{:>9} ", size_format.format(child.bytes))
+        };
+        let name = format!(" {}{}", child.name, if child.is_dir { "/" } else { "" });
+        let name = width::truncate(&name, inner.saturating_sub(width::width(&size) + 1));
+        let pad = inner.saturating_sub(width::width(&name) + width::width(&size));
+        lines.push(Line::from(vec![
+          Span::styled(name, Style::default().fg(theme.text)),
+          Span::raw(" ".repeat(pad)),
+          Span::styled(size, dim),
+        ]));
+      }
+      if summary.children.len() > MAX_CHILDREN {
+        lines.push(Line::from(Span::styled(
+          format!(" … and {} more", summary.children.len() - MAX_CHILDREN),
+          Style::default().fg(theme.text_muted),
+        )));
+      }
+    }
+  }
+
+  let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+  if width < 30 || height < 3 {
+    return;
+  }
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let title = match pending.targets.as_slice() {
+    [path] => {
+      let name = path.file_name().map_or_else(|| path.to_string_lossy(), |n| n.to_string_lossy());
+      format!(" Delete {}/? ", width::truncate(&name, inner.saturating_sub(12)))
+    }
+    targets => format!(" Delete {} items? ", targets.len()),
+  };
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(title)
+    .border_style(Style::default().fg(theme.error))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
@@ -0,0 +1,59 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::destination::DestinationPicker;
+use crate::theme::Theme;
+use crate::ui::favorites::contract_home;
+use crate::ui::width;
+
+/// Recent destinations, favorites and the other pane's root matching the
+/// copy/move to prompt, above it. Nothing is drawn when none match.
+pub fn render_destination(picker: &DestinationPicker, text: &str, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let (choices, highlighted) = picker.view(text);
+  if choices.is_empty() {
+    return;
+  }
+  let width = 60.min(area.width.saturating_sub(4));
+  let height = (choices.len() as u16 + 2).min(area.height.saturating_sub(4));
+  if width < 10 || height < 3 {
+    return;
+  }
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  // Keep the highlighted choice on screen
+  let rows = height.saturating_sub(2) as usize;
+  let skip = highlighted.map_or(0, |i| (i + 1).saturating_sub(rows));
+  let inner = width.saturating_sub(2) as usize;
+  let lines: Vec<Line> = choices
+    .iter()
+    .enumerate()
+    .skip(skip)
+    .map(|(i, path)| {
+      let display = width::truncate(&contract_home(path), inner.saturating_sub(3));
+      if Some(i) == highlighted {
+        Line::from(Span::styled(
+          format!(" > {display}"),
+          Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        ))
+      } else {
+        Line::from(Span::styled(format!("   {display}"), Style::default().fg(theme.text)))
+      }
+    })
+    .collect();
+
+  let title = if picker.cut { " Move to " } else { " Copy to " };
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(title)
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
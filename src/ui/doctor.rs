@@ -0,0 +1,55 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::doctor::Status;
+use crate::theme::Theme;
+
+/// Draw the diagnostics overlay: one row per check, with the hint for
+/// anything not found under it.
+pub fn render_doctor(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let name_width = app.doctor.iter().map(|c| c.name.len()).max().unwrap_or(0);
+  let rows = app.doctor.len() + app.doctor.iter().filter(|c| c.hint.is_some()).count();
+  let width = 84.min(area.width.saturating_sub(4));
+  let height = (rows as u16 + 2).min(area.height.saturating_sub(2));
+  if width < 30 || height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let mut lines = Vec::new();
+  for check in &app.doctor {
+    let color = match check.status {
+      Status::Ok => theme.success,
+      Status::Unknown => theme.warning,
+      Status::Missing => theme.error,
+    };
+    lines.push(Line::from(vec![
+      Span::styled(format!(" {} ", check.status.symbol()), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+      Span::styled(format!("{:<name_width$}  ", check.name), Style::default().fg(theme.text)),
+      Span::styled(check.detail.clone(), Style::default().fg(theme.text_dim)),
+    ]));
+    if let Some(ref hint) = check.hint {
+      lines.push(Line::from(Span::styled(
+        format!("   {:<name_width$}  → {hint}", ""),
+        Style::default().fg(theme.text_muted),
+      )));
+    }
+  }
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" Diagnostics ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
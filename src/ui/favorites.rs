@@ -7,7 +7,7 @@ use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
 use crate::app::App;
 use crate::theme::Theme;
 
-fn contract_home(path: &std::path::Path) -> String {
+pub(crate) fn contract_home(path: &std::path::Path) -> String {
   if let Some(home) = dirs::home_dir()
     && let Ok(rest) = path.strip_prefix(&home)
   {
@@ -5,8 +5,12 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::app::{App, ClipboardOp};
+use crate::fs::{FileEntry, FileTree};
+use crate::fs::properties::user_name;
 use crate::icons::{file_icon, file_name_color};
+use crate::preview::directory::format_size;
 use crate::theme::Theme;
+use crate::ui::width;
 
 pub fn render_file_tree(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
   render_file_tree_with_active(app, area, buf, true, false, theme);
@@ -15,18 +19,22 @@ pub fn render_file_tree(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
 pub fn render_file_tree_with_active(app: &App, area: Rect, buf: &mut Buffer, is_active: bool, is_right_pane: bool, theme: &Theme) {
   let (entries, cursor, scroll_offset, tree, search_query, marks) = if is_right_pane {
     if let Some(ref pane) = app.right_pane {
-      (pane.visible_entries(), pane.cursor, pane.scroll_offset, &pane.tree, &pane.search_query, &pane.marked)
+      (pane.visible_entries(), pane.cursor, pane.scroll_offset, &pane.tree, &pane.search, &pane.marked)
     } else {
       return;
     }
   } else {
-    (app.visible_entries(), app.cursor, app.tree_scroll_offset, &app.tree, &app.search_query, &app.marked)
+    (app.visible_entries(), app.cursor, app.tree_scroll_offset, &app.tree, &app.search, &app.marked)
   };
 
   let inner_height = area.height.saturating_sub(2) as usize; // borders
+  let inner_width = area.width.saturating_sub(2) as usize;
 
   let mut lines: Vec<Line> = Vec::with_capacity(inner_height);
 
+  // Marker column only in repositories, so other trees keep the room
+  let git_markers = app.git_markers && tree.git_repo().is_some();
+
   let start = scroll_offset.min(entries.len());
   let end = (start + inner_height).min(entries.len());
 
@@ -36,6 +44,7 @@ pub fn render_file_tree_with_active(app: &App, area: Rect, buf: &mut Buffer, is_
 
     let indent = "  ".repeat(entry.depth);
     let icon = file_icon(&entry.name, entry.is_dir, entry.expanded, entry.is_symlink);
+    let glyph = if app.icons { icon.glyph } else { "" };
     let name_color = file_name_color(&entry.name, entry.is_dir, entry.is_symlink);
     let symlink_indicator = if let Some(ref target) = entry.symlink_target {
       format!(" -> {target}")
@@ -100,12 +109,54 @@ pub fn render_file_tree_with_active(app: &App, area: Rect, buf: &mut Buffer, is_
       Style::default()
     };
 
+    let git_marker = match entry.git_status.marker() {
+      Some(marker) if git_markers => format!("{marker} "),
+      _ if git_markers => "  ".to_string(),
+      _ => String::new(),
+    };
+    let git_marker_style = if is_selected && is_active {
+      name_style
+    } else {
+      Style::default().fg(entry.git_status.display_color(theme).unwrap_or(theme.text_dim))
+    };
+
+    let cycle = if entry.is_cycle { " ↻" } else { "" };
+    let badge = owner_badge(entry);
+    let label = if app.labels.is_mislabeled(&entry.path) { " ✗ctx" } else { "" };
+    let count = match app.dir_counts.get(&entry.path) {
+      Some(count) if entry.is_dir && !entry.expanded => format!(" ({})", count.shown(tree.show_hidden)),
+      _ => String::new(),
+    };
+    let size = if app.dir_sizes.enabled { size_cell(app, tree, entry) } else { String::new() };
+    let fixed = mark_indicator.len()
+      + git_marker.len()
+      + indent.len()
+      + width::width(glyph)
+      + width::width(&count)
+      + width::width(cycle)
+      + width::width(&badge)
+      + width::width(label);
+    let (name, symlink_indicator) = fit_row(inner_width, fixed + width::width(&size), &entry.name, &symlink_indicator);
+    // Right-align the size column
+    let gap = if size.is_empty() {
+      String::new()
+    } else {
+      " ".repeat(inner_width.saturating_sub(fixed + width::width(&size) + width::width(&name) + width::width(&symlink_indicator)))
+    };
+
     let line = Line::from(vec![
       Span::styled(mark_indicator.to_string(), mark_style),
+      Span::styled(git_marker, git_marker_style),
       Span::styled(indent, name_style),
-      Span::styled(icon.glyph, icon_style),
-      Span::styled(entry.name.clone(), name_style),
-      Span::styled(symlink_indicator, Style::default().fg(theme.text_dim)),
+      Span::styled(glyph, icon_style),
+      Span::styled(name, name_style),
+      Span::styled(symlink_indicator, Style::default().fg(if entry.is_broken_link { theme.error } else { theme.text_dim })),
+      Span::styled(count, Style::default().fg(theme.text_dim)),
+      Span::styled(cycle, Style::default().fg(theme.warning)),
+      Span::styled(badge, Style::default().fg(theme.warning).add_modifier(Modifier::DIM)),
+      Span::styled(label, Style::default().fg(theme.warning)),
+      Span::raw(gap),
+      Span::styled(size, Style::default().fg(theme.text_dim)),
     ]);
 
     lines.push(line);
@@ -147,3 +198,50 @@ pub fn render_file_tree_with_active(app: &App, area: Rect, buf: &mut Buffer, is_
   // Suppress unused variable warning
   let _ = search_query;
 }
+
+/// Name and symlink target cut to the columns the rest of the row leaves,
+/// so the badges after them stay visible. The name gets priority.
+fn fit_row(row_width: usize, fixed: usize, name: &str, symlink: &str) -> (String, String) {
+  let room = row_width.saturating_sub(fixed);
+  let name = width::truncate(name, room);
+  let symlink = width::truncate(symlink, room.saturating_sub(width::width(&name)));
+  (name, symlink)
+}
+
+/// The size column of a row: the file's size, a directory's total once it
+/// has been summed, nothing for linked directories or those of archive and
+/// remote trees, which are not summed.
+fn size_cell(app: &App, tree: &FileTree, entry: &FileEntry) -> String {
+  let size = if !entry.is_dir {
+    format_size(entry.size)
+  } else if entry.is_symlink || entry.is_cycle || !tree.source().is_local() {
+    String::new()
+  } else {
+    app.dir_sizes.get(&entry.path).map_or("…".to_string(), |&bytes| format_size(bytes))
+  };
+  format!(" {size:>9}")
+}
+
+/// Lock badge naming the owner of entries the current user doesn't own.
+fn owner_badge(entry: &FileEntry) -> String {
+  entry.foreign_uid.map_or(String::new(), |uid| format!(" \u{f023} {}", user_name(uid)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fit_row_keeps_wide_names_within_row() {
+    let (name, link) = fit_row(20, 6, "日本語のファイル名.txt", "");
+    assert_eq!(name, "日本語のファ…");
+    assert!(width::width(&name) <= 14);
+    assert_eq!(link, "");
+
+    // Name fits, the symlink target gets what is left
+    let (name, link) = fit_row(20, 6, "リンク", " -> /very/long/target");
+    assert_eq!(name, "リンク");
+    assert_eq!(width::width(&link), 8);
+    assert!(link.ends_with('…'));
+  }
+}
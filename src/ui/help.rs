@@ -6,7 +6,7 @@ use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
 
-use crate::action::Action;
+use crate::action::{Action, HELP_SECTIONS};
 use crate::app::App;
 use crate::config::Config;
 use crate::theme::Theme;
@@ -33,9 +33,9 @@ fn section_line(title: &str, section_style: Style) -> Line<'static> {
   ))
 }
 
-fn entry_line(lookup: &HashMap<Action, Vec<String>>, action: Action, desc: &str, key_style: Style, desc_style: Style) -> Line<'static> {
+fn entry_line(lookup: &HashMap<Action, Vec<String>>, action: &Action, desc: &str, key_style: Style, desc_style: Style) -> Line<'static> {
   let keys = lookup
-    .get(&action)
+    .get(action)
     .map(|v| v.join(" / "))
     .unwrap_or_else(|| "—".to_string());
   Line::from(vec![
@@ -48,83 +48,14 @@ fn entry_line(lookup: &HashMap<Action, Vec<String>>, action: Action, desc: &str,
 }
 
 fn build_sections(lookup: &HashMap<Action, Vec<String>>, key_style: Style, desc_style: Style, section_style: Style) -> Vec<Section> {
-  let s = |title| section_line(title, section_style);
-  let e = |action, desc| entry_line(lookup, action, desc, key_style, desc_style);
-
-  vec![
-    Section {
-      lines: vec![
-        s("Navigation"),
-        e(Action::MoveDown, "Move down"),
-        e(Action::MoveUp, "Move up"),
-        e(Action::MoveLeft, "Collapse / parent"),
-        e(Action::MoveRight, "Expand / select"),
-        e(Action::ToggleExpand, "Toggle expand"),
-        e(Action::GoToTop, "Go to top"),
-        e(Action::GoToBottom, "Go to bottom"),
-        e(Action::GoHome, "Go to home"),
-        e(Action::FavoritesOpen, "Open favorites"),
-        e(Action::FavoriteAdd, "Add to favorites"),
-      ],
-    },
-    Section {
-      lines: vec![
-        s("Search"),
-        e(Action::SearchStart, "Start search"),
-        e(Action::SearchConfirm, "Confirm"),
-        e(Action::SearchCancel, "Cancel"),
-      ],
-    },
-    Section {
-      lines: vec![
-        s("Preview"),
-        e(Action::ScrollPreviewDown, "Scroll down"),
-        e(Action::ScrollPreviewUp, "Scroll up"),
-        e(Action::ShrinkTree, "Shrink tree pane"),
-        e(Action::GrowTree, "Grow tree pane"),
-      ],
-    },
-    Section {
-      lines: vec![
-        s("Actions"),
-        e(Action::OpenDefault, "Open file / enter dir"),
-        e(Action::OpenWithStart, "Open with..."),
-        e(Action::ShowProperties, "Show properties"),
-        e(Action::OpenEditor, "Open in $EDITOR"),
-        e(Action::OpenClaude, "Open Claude Code"),
-        e(Action::OpenShell, "Open $SHELL"),
-        e(Action::YankPath, "Yank path"),
-        e(Action::ToggleHidden, "Toggle hidden files"),
-      ],
-    },
-    Section {
-      lines: vec![
-        s("Marking"),
-        e(Action::ToggleMark, "Toggle mark"),
-        e(Action::MarkAll, "Mark all"),
-        e(Action::ClearMarks, "Clear marks"),
-      ],
-    },
-    Section {
-      lines: vec![
-        s("File Operations"),
-        e(Action::RenameStart, "Rename"),
-        e(Action::DeleteFile, "Delete"),
-        e(Action::CopyFile, "Copy"),
-        e(Action::CutFile, "Cut"),
-        e(Action::Paste, "Paste"),
-        e(Action::NewFileStart, "New file"),
-        e(Action::NewDirStart, "New directory"),
-        e(Action::CompressStart, "Compress to archive"),
-      ],
-    },
-    Section {
-      lines: vec![
-        s("Quit"),
-        e(Action::Quit, "Quit"),
-      ],
-    },
-  ]
+  HELP_SECTIONS
+    .iter()
+    .map(|(title, entries)| {
+      let mut lines = vec![section_line(title, section_style)];
+      lines.extend(entries.iter().map(|(action, desc)| entry_line(lookup, action, desc, key_style, desc_style)));
+      Section { lines }
+    })
+    .collect()
 }
 
 /// Determine column count from available inner width.
@@ -0,0 +1,82 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Paragraph, Widget};
+
+use crate::action::Action;
+use crate::app::App;
+use crate::command::Command;
+use crate::config::Config;
+use crate::event::InputMode;
+use crate::preview::archive;
+use crate::theme::Theme;
+use crate::ui::width;
+
+/// Actions worth a hint for the current marks, selection and clipboard,
+/// most relevant first. Overlays list their own keys in the status bar.
+/// Each is labelled as the help overlay describes it.
+pub fn hints(app: &App) -> Vec<Action> {
+  if app.input_mode != InputMode::Normal {
+    return Vec::new();
+  }
+  let mut hints = if !app.active_marks().is_empty() {
+    vec![
+      Action::CopyFile,
+      Action::CutFile,
+      Action::CopyToStart,
+      Action::MoveToStart,
+      Action::DeleteFile,
+      Action::CompressStart,
+      Action::ShowProperties,
+      Action::ClearMarks,
+    ]
+  } else if let Some(entry) = app.selected_entry() {
+    let mut hints = if entry.is_dir {
+      vec![Action::OpenDefault, Action::ToggleExpand, Action::CompressStart]
+    } else if archive::is_archive(&entry.path) {
+      vec![Action::ExtractArchive, Action::ExtractAndDelete, Action::OpenWithStart]
+    } else {
+      vec![Action::OpenDefault, Action::OpenWithStart, Action::OpenEditor]
+    };
+    if !entry.is_dir && !entry.git_status.is_clean() {
+      hints.push(Action::ShowDiff);
+    }
+    hints.extend([Action::RenameStart, Action::ToggleMark]);
+    hints
+  } else {
+    Vec::new()
+  };
+  if !app.clipboard.paths.is_empty() {
+    hints.insert(0, Action::Paste);
+  }
+  if app.read_only {
+    hints.retain(|action| action.mutation().is_none());
+  }
+  hints
+}
+
+pub fn render_hint_bar(app: &App, config: &Config, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let lookup = config.reverse_lookup();
+  let key_style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+  let label_style = Style::default().fg(theme.text_dim);
+
+  let mut spans = Vec::new();
+  let mut used = 0;
+  for action in hints(app) {
+    // The shortest binding, as the help overlay would list it first
+    let Some(key) = lookup.get(&action).and_then(|keys| keys.iter().min_by_key(|k| width::width(k))) else {
+      continue;
+    };
+    let label = action.describe();
+    let hint_width = 1 + width::width(key) + 1 + width::width(&label) + 1;
+    if used + hint_width > area.width as usize {
+      break;
+    }
+    used += hint_width;
+    spans.push(Span::styled(format!(" {key}"), key_style));
+    spans.push(Span::styled(format!(" {label} "), label_style));
+  }
+
+  Paragraph::new(Line::from(spans)).style(Style::default().bg(theme.bg_bar)).render(area, buf);
+}
@@ -0,0 +1,94 @@
+use image::RgbImage;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::preview::hover::{Hover, HoverBody, THUMB_COLS};
+use crate::preview::metadata::{format_size, format_time};
+use crate::theme::Theme;
+
+const WIDTH: u16 = 44;
+
+/// Small popup next to the mouse pointer for the hovered entry.
+pub fn render_hover(hover: &Hover, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let Some(ref preview) = hover.preview else {
+    return;
+  };
+  let body_height = match preview.body {
+    HoverBody::Lines(ref lines) => lines.len().max(1) as u16,
+    HoverBody::Thumbnail(ref img) => img.height().div_ceil(2) as u16,
+    HoverBody::Note(_) => 1,
+  };
+  let width = WIDTH.min(area.width);
+  let height = (body_height + 3).min(area.height);
+  if width < 16 || height < 4 {
+    return;
+  }
+  // Below-right of the pointer, flipped when it would run off screen
+  let x = if hover.column + 2 + width <= area.right() {
+    hover.column + 2
+  } else {
+    hover.column.saturating_sub(width + 1).max(area.x)
+  };
+  let y = if hover.row + 1 + height <= area.bottom() {
+    hover.row + 1
+  } else {
+    hover.row.saturating_sub(height).max(area.y)
+  };
+  let popup = Rect::new(x, y, width, height);
+  Clear.render(popup, buf);
+
+  let mut info = if preview.is_dir { "directory".to_string() } else { format_size(preview.size) };
+  if let Some(modified) = preview.modified {
+    info.push_str(&format!("  {}", format_time(modified)));
+  }
+  let mut lines = vec![Line::from(Span::styled(format!(" {info}"), Style::default().fg(theme.text_dim)))];
+  match preview.body {
+    HoverBody::Lines(ref body) => {
+      lines.extend(body.iter().map(|l| Line::from(Span::styled(format!(" {l}"), Style::default().fg(theme.text)))));
+    }
+    HoverBody::Note(ref note) => {
+      lines.push(Line::from(Span::styled(format!(" {note}"), Style::default().fg(theme.text_muted))));
+    }
+    HoverBody::Thumbnail(_) => {}
+  }
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(Span::styled(format!(" {} ", preview.name), Style::default().add_modifier(Modifier::BOLD)))
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+  let inner = block.inner(popup);
+  Paragraph::new(lines).block(block).render(popup, buf);
+
+  if let HoverBody::Thumbnail(ref img) = preview.body {
+    let left = inner.x + 1 + (inner.width.saturating_sub(2).saturating_sub(THUMB_COLS as u16)) / 2;
+    draw_halfblocks(img, left, inner.y + 1, inner, buf);
+  }
+}
+
+/// Draw `img` with its top-left cell at (`left`, `top`), two pixels per
+/// cell stacked with `▀`, clipped to `clip`.
+pub fn draw_halfblocks(img: &RgbImage, left: u16, top: u16, clip: Rect, buf: &mut Buffer) {
+  for cell_row in 0..img.height().div_ceil(2) {
+    let y = top + cell_row as u16;
+    if y >= clip.bottom() {
+      break;
+    }
+    for col in 0..img.width() {
+      let x = left + col as u16;
+      if x >= clip.right() {
+        break;
+      }
+      let rgb = |row: u32| {
+        let [r, g, b] = img.get_pixel(col, row.min(img.height() - 1)).0;
+        Color::Rgb(r, g, b)
+      };
+      if let Some(cell) = buf.cell_mut((x, y)) {
+        cell.set_char('▀').set_fg(rgb(cell_row * 2)).set_bg(rgb(cell_row * 2 + 1));
+      }
+    }
+  }
+}
@@ -0,0 +1,105 @@
+//! OSC 8 hyperlinks over the drawn frame. Escape sequences in buffer cells
+//! would throw off ratatui's width accounting, so linked cells are printed
+//! again after each draw, wrapped in the link, straight to the backend.
+
+use ratatui::buffer::{Buffer, Cell};
+use unicode_width::UnicodeWidthStr;
+
+/// Cells `x..x + width` of row `y` link to `url`. `text` is what they held
+/// when the link was found; an overlay drawn over them since drops it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperlink {
+  pub x: u16,
+  pub y: u16,
+  pub width: u16,
+  pub url: String,
+  pub text: String,
+}
+
+/// What `buf` holds in the columns of a link.
+pub fn cell_text(buf: &Buffer, x: u16, y: u16, width: u16) -> String {
+  (x..x + width).filter_map(|x| buf.cell((x, y))).map(|c| c.symbol()).collect()
+}
+
+/// Cells to print after drawing `buf`: those of `links` wrapped in OSC 8,
+/// and those of `shown` (the last frame's links) that are no longer links,
+/// printed plain so the terminal forgets the old target.
+pub fn osc8_cells(links: &[Hyperlink], shown: &[Hyperlink], buf: &Buffer) -> Vec<(u16, u16, Cell)> {
+  let live: Vec<&Hyperlink> = links.iter().filter(|l| cell_text(buf, l.x, l.y, l.width) == l.text).collect();
+  let mut cells = Vec::new();
+  for link in shown.iter().filter(|old| !live.contains(old)) {
+    for x in link.x..link.x + link.width {
+      if let Some(cell) = buf.cell((x, link.y)) {
+        cells.push((x, link.y, cell.clone()));
+      }
+    }
+  }
+  for link in live {
+    let url = escape_controls(&link.url);
+    let mut x = link.x;
+    while x < link.x + link.width {
+      let Some(cell) = buf.cell((x, link.y)) else {
+        break;
+      };
+      let symbol = cell.symbol();
+      let mut linked = cell.clone();
+      linked.set_symbol(&format!("\x1b]8;id=tfl-{}-{};{url}\x1b\\{symbol}\x1b]8;;\x1b\\", link.x, link.y));
+      cells.push((x, link.y, linked));
+      // The cell after a wide character is covered by it
+      x += symbol.width().max(1) as u16;
+    }
+  }
+  cells
+}
+
+/// Percent-encode control characters, which could otherwise end the OSC 8
+/// sequence early and smuggle other escape sequences to the terminal.
+fn escape_controls(url: &str) -> String {
+  let mut escaped = String::with_capacity(url.len());
+  for c in url.chars() {
+    if c.is_control() {
+      let mut bytes = [0; 4];
+      for byte in c.encode_utf8(&mut bytes).bytes() {
+        escaped.push_str(&format!("%{byte:02X}"));
+      }
+    } else {
+      escaped.push(c);
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ratatui::layout::Rect;
+  use ratatui::style::Style;
+
+  #[test]
+  fn test_osc8_cells_wrap_live_links_and_clear_stale_ones() {
+    let mut buf = Buffer::empty(Rect::new(0, 0, 20, 2));
+    buf.set_string(0, 0, "go to x.org now", Style::default());
+    buf.set_string(0, 1, "gone", Style::default());
+    let link = Hyperlink { x: 6, y: 0, width: 5, url: "https://x.org".to_string(), text: "x.org".to_string() };
+    // Covered by an overlay since it was found
+    let covered = Hyperlink { x: 0, y: 1, width: 4, url: "https://a.b".to_string(), text: "a.b ".to_string() };
+
+    let cells = osc8_cells(&[link.clone(), covered.clone()], &[covered], &buf);
+    assert_eq!(cells.len(), 4 + 5);
+    assert_eq!(cells[0].2.symbol(), "g");
+    let (x, y, cell) = &cells[4];
+    assert_eq!((*x, *y), (6, 0));
+    assert_eq!(cell.symbol(), "\x1b]8;id=tfl-6-0;https://x.org\x1b\\x\x1b]8;;\x1b\\");
+    assert!(osc8_cells(&[], std::slice::from_ref(&link), &buf).iter().all(|(_, _, c)| !c.symbol().contains('\x1b')));
+  }
+
+  #[test]
+  fn test_osc8_cells_encode_control_characters_in_urls() {
+    let mut buf = Buffer::empty(Rect::new(0, 0, 10, 1));
+    buf.set_string(0, 0, "x", Style::default());
+    let url = "https://x.org\x07\x1b]52;c;aGk=\x07".to_string();
+    let link = Hyperlink { x: 0, y: 0, width: 1, url, text: "x".to_string() };
+    let cells = osc8_cells(&[link], &[], &buf);
+    assert_eq!(cells[0].2.symbol(), "\x1b]8;id=tfl-0-0;https://x.org%07%1B]52;c;aGk=%07\x1b\\x\x1b]8;;\x1b\\");
+  }
+}
@@ -0,0 +1,78 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::theme::Theme;
+use crate::ui::width;
+
+pub fn render_jobs(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  // Running jobs with how far they got, then the queue with each job's
+  // position in it
+  let rows: Vec<(String, &'static str, String)> = app
+    .jobs
+    .running_progress()
+    .map(|(job, progress)| {
+      let state = match progress.percent() {
+        _ if progress.is_cancelled() => "stopping".to_string(),
+        Some(percent) => format!("{percent}%"),
+        None => "running".to_string(),
+      };
+      (state, job.verb(), job.name())
+    })
+    .chain(app.jobs.queued().enumerate().map(|(i, job)| (format!("#{}", i + 1), job.verb(), job.name())))
+    .collect();
+
+  let width = 60.min(area.width.saturating_sub(4));
+  let height = (rows.len().max(1) as u16 + 2).min(area.height.saturating_sub(2));
+
+  if width < 20 || height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let visible = height.saturating_sub(2) as usize;
+  let skip = (app.jobs_cursor + 1).saturating_sub(visible);
+  let inner = width.saturating_sub(2) as usize;
+
+  let lines: Vec<Line> = if rows.is_empty() {
+    vec![Line::from(Span::styled(" No extractions, compressions or copies running", Style::default().fg(theme.text_dim)))]
+  } else {
+    rows
+      .iter()
+      .enumerate()
+      .skip(skip)
+      .take(visible)
+      .map(|(i, (position, verb, name))| {
+        let selected = app.jobs_cursor == i;
+        let prefix = if selected { " > " } else { "   " };
+        let name_style = if selected {
+          Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+        } else {
+          Style::default().fg(theme.text)
+        };
+        let head = format!("{prefix}{position:<9}{verb:<9}");
+        let rest = inner.saturating_sub(width::width(&head));
+        Line::from(vec![
+          Span::styled(head, Style::default().fg(theme.text_dim)),
+          Span::styled(width::truncate(name, rest), name_style),
+        ])
+      })
+      .collect()
+  };
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" Jobs ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
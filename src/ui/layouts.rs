@@ -0,0 +1,67 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::theme::Theme;
+use crate::ui::favorites::contract_home;
+use crate::ui::width;
+
+pub fn render_layouts(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let width = 60.min(area.width.saturating_sub(4));
+  let layouts = app.layouts.list();
+  let content_height = if layouts.is_empty() { 3 } else { layouts.len() as u16 + 2 };
+  let height = content_height.min(area.height.saturating_sub(2));
+
+  if width < 10 || height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let inner = width.saturating_sub(2) as usize;
+  let lines: Vec<Line> = if layouts.is_empty() {
+    vec![
+      Line::from(""),
+      Line::from(Span::styled(
+        " No layouts — press a to save the current tree",
+        Style::default().fg(theme.text_muted),
+      )),
+    ]
+  } else {
+    layouts
+      .iter()
+      .enumerate()
+      .map(|(i, layout)| {
+        let selected = i == app.layouts_cursor;
+        let marker = if selected { " > " } else { "   " };
+        let name = width::truncate(&layout.name, inner / 2);
+        let detail = format!("  {} · {} open", contract_home(&layout.root), layout.expanded.len());
+        let detail = width::truncate(&detail, inner.saturating_sub(3 + width::width(&name)));
+        let name_style = if selected {
+          Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+        } else {
+          Style::default().fg(theme.text)
+        };
+        Line::from(vec![
+          Span::styled(format!("{marker}{name}"), name_style),
+          Span::styled(detail, Style::default().fg(theme.text_dim)),
+        ])
+      })
+      .collect()
+  };
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" Layouts ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
@@ -1,14 +1,35 @@
+pub mod branches;
 pub mod breadcrumb;
+pub mod breadcrumb_menu;
 pub mod chmod;
 pub mod compress;
+pub mod debug;
+pub mod delete_summary;
+pub mod doctor;
+pub mod destination;
 pub mod error;
 pub mod favorites;
 pub mod file_tree;
 pub mod help;
+pub mod hints;
+pub mod hover;
+pub mod hyperlinks;
+pub mod jobs;
+pub mod layouts;
+pub mod op_log;
 pub mod open_with;
+pub mod paste_conflict;
+pub mod paste_options;
+pub mod patch_export;
 pub mod preview;
 pub mod properties;
+pub mod safe_names;
+pub mod settings;
 pub mod status_bar;
+pub mod templates;
+pub mod urls;
+pub mod width;
+pub mod worktrees;
 
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
@@ -18,20 +39,23 @@ use ratatui::widgets::{Paragraph, Widget};
 use ratatui::Frame;
 
 use crate::app::App;
+use crate::git::GitRepoInfo;
 use crate::config::Config;
 use crate::theme::Theme;
 
 pub fn draw(frame: &mut Frame, app: &mut App, config: &Config) {
   let area = frame.area();
   let theme = &config.theme;
+  app.hyperlinks.clear();
 
-  // Vertical layout: header, main, status bar
+  // Vertical layout: header, main, hint bar (when enabled), status bar
   let chunks = Layout::default()
     .direction(Direction::Vertical)
     .constraints([
-      Constraint::Length(1), // header
-      Constraint::Min(3),   // main area
-      Constraint::Length(1), // status bar
+      Constraint::Length(1),                          // header
+      Constraint::Min(3),                             // main area
+      Constraint::Length(u16::from(config.hint_bar)), // hint bar
+      Constraint::Length(1),                          // status bar
     ])
     .split(area);
 
@@ -56,11 +80,15 @@ pub fn draw(frame: &mut Frame, app: &mut App, config: &Config) {
     // Update viewport height
     app.viewport_height = main_chunks[0].height.saturating_sub(2) as usize;
 
+    app.tree_area = main_chunks[0];
+    app.right_tree_area = Some(main_chunks[1]);
+
     // Left tree (active indicator based on active_pane)
-    file_tree::render_file_tree_with_active(app, main_chunks[0], frame.buffer_mut(), app.active_pane == 0, false, theme);
+    let trees_focused = !app.preview_focused;
+    file_tree::render_file_tree_with_active(app, main_chunks[0], frame.buffer_mut(), trees_focused && app.active_pane == 0, false, theme);
 
     // Right tree
-    file_tree::render_file_tree_with_active(app, main_chunks[1], frame.buffer_mut(), app.active_pane == 1, true, theme);
+    file_tree::render_file_tree_with_active(app, main_chunks[1], frame.buffer_mut(), trees_focused && app.active_pane == 1, true, theme);
 
     // Preview (smaller)
     preview::render_preview(app, main_chunks[2], frame.buffer_mut(), theme);
@@ -77,46 +105,137 @@ pub fn draw(frame: &mut Frame, app: &mut App, config: &Config) {
     // Update viewport height
     app.viewport_height = main_chunks[0].height.saturating_sub(2) as usize;
 
+    app.tree_area = main_chunks[0];
+    app.right_tree_area = None;
+
     // File tree (left pane)
-    file_tree::render_file_tree(app, main_chunks[0], frame.buffer_mut(), theme);
+    file_tree::render_file_tree_with_active(app, main_chunks[0], frame.buffer_mut(), !app.preview_focused, false, theme);
 
     // Preview (right pane)
     preview::render_preview(app, main_chunks[1], frame.buffer_mut(), theme);
   }
 
+  if config.hint_bar {
+    hints::render_hint_bar(app, config, chunks[2], frame.buffer_mut(), theme);
+  }
+
   // Status bar
-  status_bar::render_status_bar(app, chunks[2], frame.buffer_mut(), theme);
+  status_bar::render_status_bar(app, chunks[3], frame.buffer_mut(), theme);
 
   // Overlays
+  if let Some(ref hover) = app.hover {
+    hover::render_hover(hover, area, frame.buffer_mut(), theme);
+  }
+  if app.show_debug {
+    debug::render_debug(app, area, frame.buffer_mut(), theme);
+  }
   if app.show_help {
     help::render_help(app, config, area, frame.buffer_mut(), theme);
   }
   if app.input_mode == crate::event::InputMode::Favorites {
     favorites::render_favorites(app, area, frame.buffer_mut(), theme);
   }
+  if app.input_mode == crate::event::InputMode::Layouts {
+    layouts::render_layouts(app, area, frame.buffer_mut(), theme);
+  }
+  app.breadcrumb_menu_area = None;
+  if app.input_mode == crate::event::InputMode::BreadcrumbMenu
+    && let Some(ref menu) = app.breadcrumb_menu
+  {
+    app.breadcrumb_menu_area =
+      breadcrumb_menu::render_breadcrumb_menu(menu, &app.breadcrumb_segments, area, frame.buffer_mut(), theme);
+  }
   if app.input_mode == crate::event::InputMode::OpenWith {
     open_with::render_open_with(app, area, frame.buffer_mut(), theme);
   }
+  if app.input_mode == crate::event::InputMode::Urls {
+    urls::render_urls(app, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::Doctor {
+    doctor::render_doctor(app, area, frame.buffer_mut(), theme);
+  }
   if app.input_mode == crate::event::InputMode::Chmod {
     chmod::render_chmod(app, area, frame.buffer_mut(), theme);
   }
   if app.input_mode == crate::event::InputMode::Compress {
     compress::render_compress(app, area, frame.buffer_mut(), theme);
   }
-  if app.input_mode == crate::event::InputMode::Properties
-    && let Some(ref props) = app.file_properties
+  if app.input_mode == crate::event::InputMode::PatchExport {
+    patch_export::render_patch_export(app, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::Templates {
+    templates::render_templates(app, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::Worktrees {
+    worktrees::render_worktrees(app, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::Branches {
+    branches::render_branches(app, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::OpLog {
+    op_log::render_op_log(app, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::PasteOptions
+    && let Some(ref options) = app.paste_options
   {
-    properties::render_properties(props, area, frame.buffer_mut(), theme);
+    paste_options::render_paste_options(options, app.clipboard.paths.len(), area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::PasteConflict
+    && let Some(ref plan) = app.paste_plan
+  {
+    paste_conflict::render_paste_conflict(plan, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::Settings
+    && let Some(ref editor) = app.settings
+  {
+    settings::render_settings(editor, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::Jobs {
+    jobs::render_jobs(app, area, frame.buffer_mut(), theme);
+  }
+  if app.prompt_kind == Some(crate::event::PromptKind::ConfirmSafeNames)
+    && let Some(ref plan) = app.pending_safe_names
+  {
+    safe_names::render_safe_names(plan, area, frame.buffer_mut(), theme);
+  }
+  if matches!(app.prompt_kind, Some(crate::event::PromptKind::ConfirmDelete | crate::event::PromptKind::ConfirmDeleteMulti(_)))
+    && let Some(ref pending) = app.delete_summary
+  {
+    delete_summary::render_delete_summary(pending, app.preview.size_format, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::Prompt
+    && let Some(ref picker) = app.destination
+  {
+    destination::render_destination(picker, &app.prompt.text, area, frame.buffer_mut(), theme);
+  }
+  if app.input_mode == crate::event::InputMode::Properties {
+    if let Some(ref multi) = app.multi_properties {
+      properties::render_multi_properties(multi, area, frame.buffer_mut(), theme);
+    } else if let Some(ref props) = app.file_properties {
+      properties::render_properties(props, area, frame.buffer_mut(), theme);
+    }
   }
   if !app.error_messages.is_empty() {
     error::render_error(&app.error_messages, area, frame.buffer_mut(), theme);
   }
 }
 
+/// ` +2 ~1 ?3 !1` after the branch: staged, modified, untracked and
+/// conflicted files, the same signs as the tree's markers. Empty when the
+/// working tree is clean.
+fn git_counts(info: &GitRepoInfo) -> String {
+  [('+', info.staged_count), ('~', info.modified_count), ('?', info.untracked_count), ('!', info.conflicted_count)]
+    .iter()
+    .filter(|(_, count)| *count > 0)
+    .map(|(sign, count)| format!(" {sign}{count}"))
+    .collect()
+}
+
 fn render_header(app: &mut App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let git_counts = if app.git_markers { git_counts(&app.tree.git_info) } else { String::new() };
   // Calculate available width for breadcrumbs (subtract git branch if present)
   let git_branch_width = app.tree.git_info.branch.as_ref()
-    .map(|b| b.len() as u16 + 4) // "  " + branch
+    .map(|b| width::width(b) as u16 + 4 + git_counts.len() as u16) // "  " + branch + counts
     .unwrap_or(0);
   let breadcrumb_width = area.width.saturating_sub(git_branch_width + 2); // +2 for padding
 
@@ -163,12 +282,16 @@ fn render_header(app: &mut App, area: Rect, buf: &mut Buffer, theme: &Theme) {
     spans.push(Span::styled(&segment.name, style));
   }
 
+  app.header_branch = None;
   if let Some(ref branch) = app.tree.git_info.branch {
     spans.push(Span::styled("  ", Style::default().fg(theme.success)));
+    let start = area.x + spans.iter().map(|s| s.width() as u16).sum::<u16>();
+    app.header_branch = Some((start, width::width(branch) as u16));
     spans.push(Span::styled(
       branch.clone(),
       Style::default().fg(theme.success),
     ));
+    spans.push(Span::styled(git_counts, Style::default().fg(theme.warning)));
   }
 
   let line = Line::from(spans);
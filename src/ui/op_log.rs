@@ -0,0 +1,90 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::op_log::OpRecord;
+use crate::theme::Theme;
+use crate::ui::favorites::contract_home;
+use crate::ui::width;
+
+/// `HH:MM:SS` (UTC) of an epoch timestamp.
+fn time_of_day(secs: u64) -> String {
+  let t = secs % 86400;
+  format!("{:02}:{:02}:{:02}", t / 3600, (t % 3600) / 60, t % 60)
+}
+
+/// `from → to (detail)` with whichever parts the record has.
+fn describe(record: &OpRecord) -> String {
+  let mut text = match (&record.from, &record.to) {
+    (Some(from), Some(to)) => format!("{} → {}", contract_home(from), contract_home(to)),
+    (Some(path), None) | (None, Some(path)) => contract_home(path),
+    (None, None) => String::new(),
+  };
+  if let Some(ref detail) = record.detail {
+    if !text.is_empty() {
+      text.push(' ');
+    }
+    text.push_str(&format!("({detail})"));
+  }
+  text
+}
+
+pub fn render_op_log(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let entries = &app.op_log_entries;
+  let width = 90.min(area.width.saturating_sub(4));
+  let content_height = (entries.len().max(1) as u16 + 2).min(area.height.saturating_sub(2));
+
+  if width < 30 || content_height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(content_height)) / 2;
+  let popup = Rect::new(x, y, width, content_height);
+
+  Clear.render(popup, buf);
+
+  let rows = content_height.saturating_sub(2) as usize;
+  let skip = (app.op_log_cursor + 1).saturating_sub(rows);
+  let inner = width.saturating_sub(2) as usize;
+
+  let lines: Vec<Line> = if entries.is_empty() {
+    vec![Line::from(Span::styled(" No operations today", Style::default().fg(theme.text_dim)))]
+  } else {
+    entries
+      .iter()
+      .enumerate()
+      .skip(skip)
+      .take(rows)
+      .map(|(i, record)| {
+        let selected = app.op_log_cursor == i;
+        let prefix = if selected { " > " } else { "   " };
+        let op_style = if selected {
+          Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+        } else {
+          Style::default().fg(theme.text)
+        };
+        let head = format!("{prefix}{} {} ", time_of_day(record.time), record.user);
+        let op = format!("{} ", record.op);
+        let rest = inner.saturating_sub(width::width(&head) + width::width(&op));
+        Line::from(vec![
+          Span::styled(head, Style::default().fg(theme.text_dim)),
+          Span::styled(op, op_style),
+          Span::styled(width::truncate_start(&describe(record), rest), Style::default().fg(theme.text)),
+        ])
+      })
+      .collect()
+  };
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" Today's operations ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  let paragraph = Paragraph::new(lines).block(block);
+  paragraph.render(popup, buf);
+}
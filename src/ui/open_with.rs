@@ -27,19 +27,21 @@ pub fn render_open_with(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
 
   // "Default Application" entry at index 0
   let selected = app.open_with_cursor == 0;
-  lines.push(app_line("Default Application", None, selected, theme));
+  lines.push(app_line("Default Application", &[], selected, theme));
 
   // Detected apps
   for (i, app_entry) in apps.iter().enumerate() {
     let selected = app.open_with_cursor == i + 1;
-    let suffix = if app_entry.is_tui {
-      Some("tui")
+    let mut tags = Vec::new();
+    if app_entry.project {
+      tags.push("project");
+    }
+    if app_entry.is_tui {
+      tags.push("tui");
     } else if app_entry.dir_mode {
-      Some("dir")
-    } else {
-      None
-    };
-    lines.push(app_line(&app_entry.name, suffix, selected, theme));
+      tags.push("dir");
+    }
+    lines.push(app_line(&app_entry.name, &tags, selected, theme));
   }
 
   let block = Block::default()
@@ -52,7 +54,7 @@ pub fn render_open_with(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
   paragraph.render(popup, buf);
 }
 
-fn app_line(name: &str, suffix: Option<&str>, selected: bool, theme: &Theme) -> Line<'static> {
+fn app_line(name: &str, tags: &[&str], selected: bool, theme: &Theme) -> Line<'static> {
   let prefix = if selected { " > " } else { "   " };
   let style = if selected {
     Style::default()
@@ -64,9 +66,9 @@ fn app_line(name: &str, suffix: Option<&str>, selected: bool, theme: &Theme) ->
 
   let mut spans = vec![Span::styled(format!("{prefix}{name}"), style)];
 
-  if let Some(tag) = suffix {
+  if !tags.is_empty() {
     spans.push(Span::styled(
-      format!(" ({tag})"),
+      format!(" ({})", tags.join(", ")),
       Style::default().fg(theme.text_muted),
     ));
   }
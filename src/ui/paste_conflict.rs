@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::PastePlan;
+use crate::fs::ops;
+use crate::jobs;
+use crate::theme::Theme;
+use crate::ui::favorites::contract_home;
+use crate::ui::width;
+
+/// The first taken name of a paste and what can be done about it.
+pub fn render_paste_conflict(plan: &PastePlan, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let Some(source) = plan.pending.first() else {
+    return;
+  };
+  let width = 56.min(area.width.saturating_sub(4));
+  let height = 11.min(area.height.saturating_sub(2));
+
+  if width < 24 || height < 7 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let item_style = Style::default().fg(theme.text);
+  let key_style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+  let dim_style = Style::default().fg(theme.text_muted);
+  let inner = width.saturating_sub(4) as usize;
+
+  let taken = jobs::pasted_path(source, &plan.target_dir, plan.options.sanitize);
+  let name = taken.file_name().unwrap_or_default().to_string_lossy().to_string();
+  let renamed = ops::unique_dest_path(&taken).file_name().unwrap_or_default().to_string_lossy().to_string();
+  let is_dir = |path: &Path| path.symlink_metadata().is_ok_and(|m| m.is_dir());
+  let overwrite = match (is_dir(source), is_dir(&taken)) {
+    (true, true) => "Merge into it, overwriting what's in both",
+    (false, true) => "Overwrite the whole directory",
+    _ => "Overwrite it",
+  };
+  let mut rows = vec![
+    ("o", overwrite.to_string()),
+    ("s", "Skip this one".to_string()),
+    ("r", width::truncate(&format!("Rename to {renamed}"), inner.saturating_sub(3))),
+  ];
+  if plan.pending.len() > 1 {
+    let check = if plan.apply_to_all { "[x]" } else { "[ ]" };
+    rows.push(("a", format!("{check} Apply to all {} remaining", plan.pending.len())));
+  }
+
+  let mut lines = vec![
+    Line::from(Span::styled(format!("  {}", width::truncate(&name, inner)), item_style.add_modifier(Modifier::BOLD))),
+    Line::from(Span::styled(
+      format!("  {}", width::truncate_start(&format!("is already in {}", contract_home(&plan.target_dir)), inner)),
+      item_style,
+    )),
+    Line::from(""),
+  ];
+  lines.extend(rows.into_iter().map(|(key, text)| {
+    Line::from(vec![Span::styled("  ", item_style), Span::styled(key, key_style), Span::styled(format!(") {text}"), item_style)])
+  }));
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled("  Esc to cancel the paste", dim_style)));
+
+  let total = plan.choices.len() + plan.pending.len();
+  let title = if total > 1 {
+    format!(" Name taken ({} of {total}) ", plan.choices.len() + 1)
+  } else {
+    " Name taken ".to_string()
+  };
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(title)
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
@@ -0,0 +1,60 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::fs::ops::CopyOptions;
+use crate::theme::Theme;
+
+pub fn render_paste_options(options: &CopyOptions, count: usize, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let title = if count == 1 { " Paste (1 item) ".to_string() } else { format!(" Paste ({count} items) ") };
+
+  let width = 40.min(area.width.saturating_sub(4));
+  let height = 10.min(area.height.saturating_sub(2));
+
+  if width < 20 || height < 5 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let item_style = Style::default().fg(theme.text);
+  let key_style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+  let dim_style = Style::default().fg(theme.text_muted);
+
+  let check = |on: bool| if on { "[x]" } else { "[ ]" };
+  let links = if options.preserve_links { "keep as links" } else { "follow" };
+  let rows = [
+    ("l", format!("Symlinks: {links}")),
+    ("m", format!("{} Keep permissions", check(options.preserve_mode))),
+    ("t", format!("{} Keep timestamps", check(options.preserve_timestamps))),
+    ("x", format!("{} Keep extended attributes", check(options.preserve_xattrs))),
+    ("v", format!("{} Verify copies", check(options.verify))),
+  ];
+
+  let mut lines: Vec<Line> = rows
+    .into_iter()
+    .map(|(key, text)| {
+      Line::from(vec![
+        Span::styled("  ", item_style),
+        Span::styled(key, key_style),
+        Span::styled(format!(") {text}"), item_style),
+      ])
+    })
+    .collect();
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled("  Enter to paste, Esc to cancel", dim_style)));
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(title)
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
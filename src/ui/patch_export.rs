@@ -0,0 +1,62 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::theme::Theme;
+
+pub fn render_patch_export(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let hunk_count = app.preview.current_diff().map_or(0, |d| d.hunk_patches.len());
+  let title = match app.preview.current_hunk() {
+    Some(hunk) => format!(" Export patch (hunk {}/{hunk_count}) ", hunk + 1),
+    None => " Export patch ".to_string(),
+  };
+
+  let width = 34.min(area.width.saturating_sub(4));
+  let height = 9.min(area.height.saturating_sub(2));
+
+  if width < 10 || height < 5 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let item_style = Style::default().fg(theme.text);
+  let key_style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+  let dim_style = Style::default().fg(theme.text_muted);
+
+  let options = [
+    ") Save .patch file…",
+    ") Save current hunk…",
+    ") Copy patch",
+    ") Copy current hunk",
+  ];
+  let mut lines: Vec<Line> = options
+    .iter()
+    .enumerate()
+    .map(|(i, label)| {
+      Line::from(vec![
+        Span::styled("  ", item_style),
+        Span::styled((i + 1).to_string(), key_style),
+        Span::styled(*label, item_style),
+      ])
+    })
+    .collect();
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled("  Esc to cancel", dim_style)));
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(title)
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  let paragraph = Paragraph::new(lines).block(block);
+  paragraph.render(popup, buf);
+}
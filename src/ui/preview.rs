@@ -1,30 +1,30 @@
+use image::RgbImage;
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph, StatefulWidget, Widget};
+use ratatui::widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap};
 use ratatui_image::StatefulImage;
 use ratatui_image::protocol::StatefulProtocol;
 
 use crate::app::App;
+use crate::preview::hex::BYTES_PER_LINE;
+use crate::preview::{directory, links};
 use crate::preview::metadata::{format_permissions, format_size, format_time};
 use crate::preview::{PreviewContent, PreviewType};
 use crate::theme::Theme;
+use crate::ui::hyperlinks::{self, Hyperlink};
+use crate::ui::{hover, width};
 
 const METADATA_PANEL_HEIGHT: u16 = 7;
 
 pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer, theme: &Theme) {
   let blame_enabled = app.preview.blame_enabled;
-  let title = if blame_enabled { " Blame " } else { " Preview " };
-
+  let border = if app.preview_focused { theme.accent } else { theme.border };
   let block = Block::default()
     .borders(Borders::ALL)
-    .border_style(Style::default().fg(theme.border))
-    .title(title)
-    .title_style(Style::default().fg(if blame_enabled { theme.warning } else { theme.accent }));
-
+    .border_style(Style::default().fg(border));
   let inner = block.inner(area);
-  block.render(area, buf);
 
   let content = app.preview.get_content();
 
@@ -43,12 +43,52 @@ pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer, theme: &Theme
     (inner, None)
   };
 
+  block.title(preview_title(app, content_area.height as usize, theme)).render(area, buf);
+
+  // Banner when the shown file was written since the preview was built
+  let content_area = if app.preview.stale && content_area.height > 2 {
+    let banner = Line::from(Span::styled(
+      " File changed on disk — press R to reload preview",
+      Style::default().fg(theme.warning).add_modifier(Modifier::ITALIC),
+    ));
+    let chunks = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(content_area);
+    Paragraph::new(banner).render(chunks[0], buf);
+    chunks[1]
+  } else {
+    content_area
+  };
+
   // Check if we have an image to render
   let is_image = app.preview.get_content().is_some_and(|c| c.preview_type == PreviewType::Image);
   if is_image && !blame_enabled {
+    // Revision bar above the image while flipping through its git history
+    let content_area = if let Some((index, total, commit)) = app.preview.image_revision()
+      && content_area.height > 2
+    {
+      let bar = Line::from(vec![
+        Span::styled(format!(" ◀ {}/{total} ▶ ", index + 1), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled(commit.hash.clone(), Style::default().fg(theme.warning).add_modifier(Modifier::BOLD)),
+        Span::raw(" "),
+        Span::styled(commit.date.clone(), Style::default().fg(theme.meta_secondary)),
+        Span::raw(" "),
+        Span::styled(commit.author.clone(), Style::default().fg(theme.info)),
+        Span::raw(" "),
+        Span::styled(commit.message.clone(), Style::default().fg(theme.text)),
+      ]);
+      let chunks = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(content_area);
+      Paragraph::new(bar).render(chunks[0], buf);
+      chunks[1]
+    } else {
+      content_area
+    };
+
     if let Some(ref mut protocol) = app.preview.image_protocol {
       let image: StatefulImage<StatefulProtocol> = StatefulImage::default();
       StatefulWidget::render(image, content_area, buf, protocol);
+    } else if app.picker.is_none()
+      && let Some(content) = app.preview.get_content()
+    {
+      render_image_fallback(&content.lines, app.preview.thumbnail.as_ref(), content_area, buf);
     }
 
     if let Some(meta_area) = metadata_area
@@ -81,8 +121,19 @@ pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer, theme: &Theme
     return;
   }
 
+  app.preview_rows = content_area.height as usize;
+
+  // Side-by-side diff rows are laid out at render time to fit the pane width
+  app.preview.set_content_width(content_area.width);
+  let side_by_side = app.preview.active_side_by_side().map(|sbs| {
+    sbs.render(app.preview.scroll_offset, content_area.height as usize, content_area.width, theme)
+  });
+
   // Text-based preview - use get_display_lines() for formatted/raw toggle
-  let lines: Vec<Line> = if let Some(display_lines) = app.preview.get_display_lines() {
+  let sideways = side_by_side.is_none();
+  let lines: Vec<Line> = if let Some(rows) = side_by_side {
+    rows
+  } else if let Some(display_lines) = app.preview.get_display_lines() {
     let scroll = app.preview.scroll_offset;
     display_lines
       .iter()
@@ -94,8 +145,20 @@ pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer, theme: &Theme
     vec![Line::from("  No file selected")]
   };
 
-  let paragraph = Paragraph::new(lines);
+  // Wrapped rows no longer line up with the lines links are found in
+  let shift = if sideways { app.preview.h_scroll } else { 0 };
+  let found = if sideways && app.preview.wrap { Vec::new() } else { line_links(app, &lines, content_area, shift) };
+  let mut paragraph = Paragraph::new(lines);
+  if sideways && app.preview.wrap {
+    paragraph = paragraph.wrap(Wrap { trim: false });
+  } else if sideways && app.preview.h_scroll > 0 {
+    paragraph = paragraph.scroll((0, app.preview.h_scroll.min(u16::MAX as usize) as u16));
+  }
   paragraph.render(content_area, buf);
+  app.hyperlinks.extend(found.into_iter().map(|mut link| {
+    link.text = hyperlinks::cell_text(buf, link.x, link.y, link.width);
+    link
+  }));
 
   // Render metadata panel
   if let Some(meta_area) = metadata_area
@@ -105,6 +168,139 @@ pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer, theme: &Theme
   }
 }
 
+/// Links in the preview rows `lines` are drawn to: URLs everywhere, file
+/// headers of diffs and the entries of a directory listing. The lines are
+/// drawn scrolled `shift` columns to the right; links cut off are skipped.
+fn line_links(app: &App, lines: &[Line], area: Rect, shift: usize) -> Vec<Hyperlink> {
+  let preview_type = app.preview.get_content().map(|c| &c.preview_type);
+  let repo_root = app.tree.git_repo().map(|repo| repo.root());
+  let mut found = Vec::new();
+  for (row, line) in lines.iter().enumerate().take(area.height as usize) {
+    let mut line_links = links::url_links(line);
+    match preview_type {
+      Some(PreviewType::Diff) if let Some(root) = repo_root => line_links.extend(links::diff_header_links(line, root)),
+      Some(PreviewType::Directory) if let Some(ref dir) = app.preview.current_path => {
+        line_links.extend(directory::entry_link(line, dir));
+      }
+      _ => {}
+    }
+    for link in line_links {
+      let Some(start) = link.start.checked_sub(shift) else {
+        continue;
+      };
+      let Some(room) = (area.width as usize).checked_sub(start).filter(|&room| room > 0) else {
+        continue;
+      };
+      found.push(Hyperlink {
+        x: area.x + start as u16,
+        y: area.y + row as u16,
+        width: link.width.min(room) as u16,
+        url: link.url,
+        text: String::new(),
+      });
+    }
+  }
+  found
+}
+
+/// Border title: file name, language, size and the current view mode.
+fn preview_title(app: &App, rows: usize, theme: &Theme) -> Line<'static> {
+  let Some(content) = app.preview.get_content() else {
+    return Line::from(Span::styled(" Preview ", Style::default().fg(theme.accent)));
+  };
+  let name = app
+    .preview
+    .current_path
+    .as_ref()
+    .and_then(|p| p.file_name())
+    .map(|n| n.to_string_lossy().into_owned())
+    .unwrap_or_else(|| "Preview".to_string());
+
+  let mut info = Vec::new();
+  if let Some(language) = app.preview.language() {
+    info.push(language);
+  }
+  if !matches!(content.preview_type, PreviewType::Directory | PreviewType::Special(_) | PreviewType::TimedOut | PreviewType::Error(_)) {
+    info.push(format_size(content.file_size));
+  }
+  if app.preview.wrap {
+    info.push("wrap".to_string());
+  } else if app.preview.h_scroll > 0 {
+    info.push(format!("col {}", app.preview.h_scroll + 1));
+  }
+
+  let mode = if app.preview.blame_enabled {
+    Some("BLAME".to_string())
+  } else if app.preview.following() {
+    Some("FOLLOW".to_string())
+  } else {
+    match content.preview_type {
+      PreviewType::Diff => {
+        let layout = if app.preview.active_side_by_side().is_some() { ", side by side" } else { "" };
+        Some(format!("DIFF {}{layout}", app.preview.diff_mode.label()))
+      }
+      PreviewType::Markdown => Some(if app.preview.markdown_rendered { "RENDERED" } else { "RAW" }.to_string()),
+      PreviewType::Text if content.raw_lines.is_some() => {
+        Some(if app.preview.show_formatted { "FORMATTED" } else { "RAW" }.to_string())
+      }
+      PreviewType::Text if content.is_structured => Some("RAW".to_string()),
+      PreviewType::Binary => hex_range(app.preview.scroll_offset, rows, content.lines.len(), content.file_size)
+        .map(|(start, end)| format!("HEX {start:08x}–{end:08x}")),
+      PreviewType::Image => Some("IMAGE".to_string()),
+      _ => None,
+    }
+  };
+
+  let mut spans = vec![Span::styled(format!(" {name}"), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))];
+  for part in info {
+    spans.push(Span::styled(format!(" · {part}"), Style::default().fg(theme.text_dim)));
+  }
+  if let Some(mode) = mode {
+    let color = if app.preview.blame_enabled { theme.warning } else { theme.info };
+    spans.push(Span::styled(" · ", Style::default().fg(theme.text_dim)));
+    spans.push(Span::styled(mode, Style::default().fg(color).add_modifier(Modifier::BOLD)));
+  }
+  spans.push(Span::raw(" "));
+  Line::from(spans)
+}
+
+/// First and last byte offsets of a hex dump on screen, or None when nothing is.
+fn hex_range(scroll: usize, rows: usize, total_lines: usize, file_size: u64) -> Option<(u64, u64)> {
+  let first = scroll.min(total_lines);
+  let last = (scroll + rows).min(total_lines);
+  if first >= last {
+    return None;
+  }
+  let start = (first * BYTES_PER_LINE) as u64;
+  let end = ((last * BYTES_PER_LINE) as u64).min(file_size);
+  (start < end).then(|| (start, end - 1))
+}
+
+/// Image details with a halfblock thumbnail below them, for terminals
+/// without a graphics protocol.
+fn render_image_fallback(info: &[Line], thumbnail: Option<&RgbImage>, area: Rect, buf: &mut Buffer) {
+  let info_height = (info.len() as u16).min(area.height);
+  Paragraph::new(info.to_vec()).render(Rect { height: info_height, ..area }, buf);
+
+  let Some(img) = thumbnail else { return };
+  // One blank row between the details and the picture
+  let top = area.y + info_height + 1;
+  let rows = area.bottom().saturating_sub(top);
+  let cols = area.width.saturating_sub(2);
+  if rows < 2 || cols < 4 {
+    return;
+  }
+  let (max_w, max_h) = (u32::from(cols), u32::from(rows) * 2);
+  let fitted;
+  let img = if img.width() > max_w || img.height() > max_h {
+    fitted = image::imageops::thumbnail(img, max_w, max_h);
+    &fitted
+  } else {
+    img
+  };
+  hover::draw_halfblocks(img, area.x + 1, top, area, buf);
+}
+
 fn render_metadata_panel(content: &PreviewContent, area: Rect, buf: &mut Buffer, theme: &Theme) {
   // Draw separator line
   let sep_style = Style::default().fg(theme.border);
@@ -319,13 +515,9 @@ fn render_metadata_panel(content: &PreviewContent, area: Rect, buf: &mut Buffer,
     ));
 
     // Message (truncated to fit)
-    let used_width = commit.hash.len() + 1 + commit.date.len() + 2;
+    let used_width = width::width(&commit.hash) + 1 + width::width(&commit.date) + 2;
     let max_msg_width = area.width as usize - used_width.min(area.width as usize);
-    let msg = if commit.message.len() > max_msg_width {
-      format!("{}…", &commit.message[..max_msg_width.saturating_sub(1)])
-    } else {
-      commit.message.clone()
-    };
+    let msg = width::truncate(&commit.message, max_msg_width);
 
     spans.push(Span::raw(" "));
     spans.push(Span::styled(msg, Style::default().fg(theme.text)));
@@ -351,3 +543,17 @@ fn render_metadata_panel(content: &PreviewContent, area: Rect, buf: &mut Buffer,
     git_paragraph.render(git_area, buf);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_hex_range() {
+    // 100 bytes dump to 7 rows; the last one is partial
+    assert_eq!(hex_range(0, 4, 7, 100), Some((0, 63)));
+    assert_eq!(hex_range(5, 4, 7, 100), Some((80, 99)));
+    assert_eq!(hex_range(7, 4, 7, 100), None);
+    assert_eq!(hex_range(0, 0, 7, 100), None);
+  }
+}
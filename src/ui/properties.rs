@@ -4,8 +4,9 @@ use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
 
-use crate::fs::FileProperties;
+use crate::fs::{FileProperties, MultiProperties};
 use crate::theme::Theme;
+use crate::ui::width as text_width;
 
 pub fn render_properties(props: &FileProperties, area: Rect, buf: &mut Buffer, theme: &Theme) {
   let label_style = Style::default().fg(theme.title_inactive);
@@ -29,11 +30,7 @@ pub fn render_properties(props: &FileProperties, area: Rect, buf: &mut Buffer, t
 
   // Path (may need truncation)
   let max_path_len = (width as usize).saturating_sub(12);
-  let path_display = if props.path.len() > max_path_len {
-    format!("...{}", &props.path[props.path.len().saturating_sub(max_path_len - 3)..])
-  } else {
-    props.path.clone()
-  };
+  let path_display = text_width::truncate_start(&props.path, max_path_len);
   lines.push(property_line("Path", &path_display, path_style, label_style));
 
   // Type
@@ -53,11 +50,7 @@ pub fn render_properties(props: &FileProperties, area: Rect, buf: &mut Buffer, t
   // Symlink target
   if let Some(ref target) = props.symlink_target {
     let max_target_len = (width as usize).saturating_sub(14);
-    let target_display = if target.len() > max_target_len {
-      format!("...{}", &target[target.len().saturating_sub(max_target_len - 3)..])
-    } else {
-      target.clone()
-    };
+    let target_display = text_width::truncate_start(target, max_target_len);
     lines.push(property_line("Target", &target_display, path_style, label_style));
   }
 
@@ -69,6 +62,16 @@ pub fn render_properties(props: &FileProperties, area: Rect, buf: &mut Buffer, t
   let owner_str = format!("{} / {}", props.owner, props.group);
   lines.push(property_line("Owner/Group", &owner_str, value_style, label_style));
 
+  // SELinux label, and the policy's when it differs
+  if let Some(ref context) = props.security_context {
+    lines.push(property_line("Context", context, value_style, label_style));
+    if let Some(ref default) = props.default_context
+      && crate::fs::selinux::differs(context, default)
+    {
+      lines.push(property_line("Default", default, Style::default().fg(theme.warning), label_style));
+    }
+  }
+
   // Timestamps
   if let Some(ref modified) = props.modified {
     lines.push(property_line("Modified", modified, value_style, label_style));
@@ -83,7 +86,7 @@ pub fn render_properties(props: &FileProperties, area: Rect, buf: &mut Buffer, t
   // Footer
   lines.push(Line::from(""));
   lines.push(Line::from(Span::styled(
-    " Press i, q, or Esc to close".to_string(),
+    " r rename  x toggle +x  p chmod  i/q/Esc close".to_string(),
     Style::default().fg(theme.text_muted),
   )));
 
@@ -97,6 +100,61 @@ pub fn render_properties(props: &FileProperties, area: Rect, buf: &mut Buffer, t
   paragraph.render(popup, buf);
 }
 
+pub fn render_multi_properties(props: &MultiProperties, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let label_style = Style::default().fg(theme.title_inactive);
+  let value_style = Style::default().fg(theme.text);
+  let mixed_style = Style::default().fg(theme.text_muted);
+
+  let width = 60.min(area.width.saturating_sub(4));
+  let height = 12.min(area.height.saturating_sub(2));
+
+  if width < 20 || height < 8 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let mut lines: Vec<Line> = Vec::new();
+
+  let items = format!("{} files, {} directories", props.file_count, props.dir_count);
+  lines.push(property_line("Items", &items, value_style, label_style));
+  let size_str = format!("{} ({} bytes, files only)", props.size_human, props.total_size);
+  lines.push(property_line("Size", &size_str, value_style, label_style));
+
+  let perms_str = match props.permissions_octal {
+    Some(ref octal) => format!("{} ({octal})", props.permissions_rwx),
+    None => format!("{} (mixed)", props.permissions_rwx),
+  };
+  let perms_style = if props.permissions_octal.is_some() { value_style } else { mixed_style };
+  lines.push(property_line("Permissions", &perms_str, perms_style, label_style));
+
+  let owner_str = format!(
+    "{} / {}",
+    props.owner.as_deref().unwrap_or("mixed"),
+    props.group.as_deref().unwrap_or("mixed"),
+  );
+  lines.push(property_line("Owner/Group", &owner_str, value_style, label_style));
+
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled(
+    " x toggle +x on all files  i/q/Esc close".to_string(),
+    Style::default().fg(theme.text_muted),
+  )));
+
+  let title = format!(" Properties ({} items) ", props.file_count + props.dir_count);
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(title)
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
+
 fn property_line(label: &str, value: &str, value_style: Style, label_style: Style) -> Line<'static> {
   Line::from(vec![
     Span::styled(
@@ -128,6 +186,8 @@ mod tests {
       mime_type: Some("text/plain".to_string()),
       symlink_target: None,
       is_dir: false,
+      security_context: None,
+      default_context: None,
     }
   }
 
@@ -174,6 +234,21 @@ mod tests {
     // Should complete without crashing and include target line
   }
 
+  #[test]
+  fn test_render_properties_flags_context_mismatch() {
+    let mut props = make_test_props();
+    props.security_context = Some("unconfined_u:object_r:user_home_t:s0".to_string());
+    props.default_context = Some("system_u:object_r:httpd_sys_content_t:s0".to_string());
+
+    let theme = Theme::dark();
+    let area = Rect::new(0, 0, 80, 30);
+    let mut buf = Buffer::empty(area);
+    render_properties(&props, area, &mut buf, &theme);
+    let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+    assert!(text.contains("user_home_t"));
+    assert!(text.contains("httpd_sys_content_t"));
+  }
+
   #[test]
   fn test_render_properties_directory() {
     let mut props = make_test_props();
@@ -187,4 +262,26 @@ mod tests {
     render_properties(&props, area, &mut buf, &theme);
     // Should complete without crashing, size line should be skipped
   }
+
+  #[test]
+  fn test_render_multi_properties_shows_mixed() {
+    let props = MultiProperties {
+      file_count: 2,
+      dir_count: 1,
+      total_size: 2048,
+      size_human: "2.00 KB".to_string(),
+      permissions_rwx: "rw?r-?r-?".to_string(),
+      permissions_octal: None,
+      owner: Some("user".to_string()),
+      group: None,
+    };
+    let theme = Theme::dark();
+    let area = Rect::new(0, 0, 80, 30);
+    let mut buf = Buffer::empty(area);
+    render_multi_properties(&props, area, &mut buf, &theme);
+    let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+    assert!(text.contains("2 files, 1 directories"));
+    assert!(text.contains("rw?r-?r-? (mixed)"));
+    assert!(text.contains("user / mixed"));
+  }
 }
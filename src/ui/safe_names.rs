@@ -0,0 +1,58 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::SafeNamesPlan;
+use crate::theme::Theme;
+use crate::ui::width;
+
+/// The renames a paste would make, shown above the safe-names prompt.
+pub fn render_safe_names(plan: &SafeNamesPlan, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let width = 70.min(area.width.saturating_sub(4));
+  let rows = plan.changes.len() as u16;
+  let height = (rows + 2).min(area.height.saturating_sub(4));
+
+  if width < 20 || height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let inner = width.saturating_sub(2) as usize;
+  let shown = height.saturating_sub(2) as usize;
+  // Leave the last row for a count of the rest when they don't all fit
+  let fit = if plan.changes.len() > shown { shown.saturating_sub(1) } else { shown };
+  let mut lines: Vec<Line> = plan
+    .changes
+    .iter()
+    .take(fit)
+    .map(|change| {
+      let new_name = format!(" → {} ", change.new_name);
+      let old = width::truncate_start(&format!(" {}", change.path.display()), inner.saturating_sub(width::width(&new_name)));
+      Line::from(vec![
+        Span::styled(old, Style::default().fg(theme.text_dim)),
+        Span::styled(new_name, Style::default().fg(theme.success)),
+      ])
+    })
+    .collect();
+  if fit < plan.changes.len() {
+    lines.push(Line::from(Span::styled(
+      format!(" … and {} more", plan.changes.len() - fit),
+      Style::default().fg(theme.text_muted),
+    )));
+  }
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(format!(" Rename for {} ", plan.fs_type))
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
@@ -0,0 +1,51 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::settings::SettingsEditor;
+use crate::theme::Theme;
+
+pub fn render_settings(editor: &SettingsEditor, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let width = 44.min(area.width.saturating_sub(4));
+  let height = 10.min(area.height.saturating_sub(2));
+
+  if width < 20 || height < 5 {
+    return;
+  }
+
+  // Keep to the right so the tree and preview stay visible while editing
+  let x = area.x + area.width.saturating_sub(width + 2);
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let item_style = Style::default().fg(theme.text);
+  let selected_style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+  let dim_style = Style::default().fg(theme.text_muted);
+
+  let mut lines: Vec<Line> = editor
+    .rows()
+    .into_iter()
+    .enumerate()
+    .map(|(i, (label, value))| {
+      let (marker, style) = if i == editor.cursor { ("> ", selected_style) } else { ("  ", item_style) };
+      Line::from(vec![
+        Span::styled(format!("{marker}{label:<19}"), style),
+        Span::styled(format!("< {value} >"), style),
+      ])
+    })
+    .collect();
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled("  Enter to save, Esc to cancel", dim_style)));
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" Settings ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
@@ -6,9 +6,12 @@ use ratatui::widgets::{Paragraph, Widget};
 
 use crate::app::App;
 use crate::event::{InputMode, PromptKind};
-use crate::fs::{GitFileStatus, GitStatus};
-use crate::preview::directory::format_size;
+use crate::fs::properties::format_rwx;
+use crate::fs::{GitFileStatus, GitStatus, locks};
+use crate::macros::MacroOp;
+use crate::preview::diff::HunkAction;
 use crate::theme::Theme;
+use crate::ui::width;
 
 fn git_status_label(status: &GitStatus) -> Option<&'static str> {
   if status.is_clean() {
@@ -52,7 +55,8 @@ fn prompt_input_spans(input: &str, cursor: usize, cursor_color: Color, theme: &T
   let before = &input[..byte_at(cursor)];
   if cursor < char_count {
     let cur_start = byte_at(cursor);
-    let cur_end = byte_at(cursor + 1);
+    // Combining marks after the character are part of the cursor cell
+    let cur_end = byte_at(width::next_cursor(input, cursor));
     let cur_ch = &input[cur_start..cur_end];
     let after = &input[cur_end..];
     vec![
@@ -68,14 +72,127 @@ fn prompt_input_spans(input: &str, cursor: usize, cursor_color: Color, theme: &T
   }
 }
 
+/// Permissions the new entry will get under the umask, plus a key hint.
+fn new_mode_spans(app: &App, hint: &str, theme: &Theme) -> Vec<Span<'static>> {
+  let Some(mode) = app.new_entry_mode() else {
+    return Vec::new();
+  };
+  let type_bits = if app.prompt_kind == Some(PromptKind::NewDir) { 0o040000 } else { 0o100000 };
+  let mut spans = vec![Span::styled(
+    format!("  {} {:03o}", format_rwx(type_bits | mode), mode),
+    Style::default().fg(if app.new_file_executable { theme.marked } else { theme.text_dim }),
+  )];
+  if !hint.is_empty() {
+    spans.push(Span::styled(format!("  {hint}"), Style::default().fg(theme.text_muted)));
+  }
+  spans
+}
+
+/// Warning listing processes that hold the prompt's targets open.
+fn holders_span(app: &App, theme: &Theme) -> Option<Span<'static>> {
+  locks::describe(&app.prompt_holders).map(|text| {
+    Span::styled(format!("  ⚠ {text}"), Style::default().fg(theme.warning))
+  })
+}
+
+/// Name of the current input mode, shown at the left of the status bar.
+/// Width of the progress gauge in the status bar, in cells
+const GAUGE_WIDTH: usize = 10;
+
+/// How far the first running job that reports progress got: a gauge when
+/// its total is known, a count of entries otherwise.
+fn progress_badge(app: &App, theme: &Theme) -> Option<Span<'static>> {
+  let (job, progress) = app.jobs.running_progress().find(|(_, p)| p.percent().is_some() || p.done().entries > 0)?;
+  let what = format!("{} {}", job.verb(), width::truncate(&job.name(), 24));
+  let text = match progress.percent() {
+    Some(percent) => {
+      let filled = usize::from(percent) * GAUGE_WIDTH / 100;
+      format!(" {what} {}{} {percent}% ", "█".repeat(filled), "░".repeat(GAUGE_WIDTH - filled))
+    }
+    None => format!(" {what} {} entries ", progress.done().entries),
+  };
+  Some(Span::styled(text, Style::default().fg(theme.bg_selected).bg(theme.info)))
+}
+
+fn mode_label(mode: InputMode, prompt: Option<PromptKind>) -> &'static str {
+  match mode {
+    InputMode::Normal => "NORMAL",
+    InputMode::Search => "SEARCH",
+    InputMode::GPrefix => "G",
+    InputMode::Help => "HELP",
+    InputMode::Prompt => match prompt {
+      Some(PromptKind::Rename) => "PROMPT: rename",
+      Some(PromptKind::NewFile) => "PROMPT: new file",
+      Some(PromptKind::NewDir) => "PROMPT: new dir",
+      Some(PromptKind::SaveLayout) => "PROMPT: layout",
+      Some(PromptKind::CopyTo) => "PROMPT: copy to",
+      Some(PromptKind::MoveTo) => "PROMPT: move to",
+      Some(PromptKind::Symlink) => "PROMPT: symlink",
+      Some(PromptKind::NewBranch) => "PROMPT: branch",
+      Some(PromptKind::GotoLine) => "PROMPT: line",
+      Some(PromptKind::PatchPath) => "PROMPT: patch",
+      Some(_) | None => "CONFIRM",
+    },
+    InputMode::Favorites => "FAVORITES",
+    InputMode::Layouts => "LAYOUTS",
+    InputMode::BreadcrumbMenu => "PATH",
+    InputMode::OpenWith => "OPEN WITH",
+    InputMode::Urls => "URLS",
+    InputMode::Doctor => "DOCTOR",
+    InputMode::Chmod => "CHMOD",
+    InputMode::Properties => "PROPERTIES",
+    InputMode::Compress => "COMPRESS",
+    InputMode::Templates => "TEMPLATES",
+    InputMode::Worktrees => "WORKTREES",
+    InputMode::Branches => "BRANCHES",
+    InputMode::OpLog => "OP LOG",
+    InputMode::Jobs => "JOBS",
+    InputMode::PasteOptions | InputMode::PasteConflict => "PASTE",
+    InputMode::PatchExport => "PATCH",
+    InputMode::Settings => "SETTINGS",
+    InputMode::Error => "ERROR",
+    InputMode::MacroRegister => "MACRO",
+    InputMode::BookmarkSet | InputMode::BookmarkJump => "BOOKMARK",
+  }
+}
+
+fn mode_color(mode: InputMode, prompt: Option<PromptKind>, theme: &Theme) -> Color {
+  match mode {
+    InputMode::Normal => theme.accent,
+    InputMode::Search => theme.info,
+    InputMode::GPrefix
+    | InputMode::Help
+    | InputMode::MacroRegister
+    | InputMode::BookmarkSet
+    | InputMode::BookmarkJump => theme.title_inactive,
+    InputMode::Prompt => match prompt {
+      Some(PromptKind::Rename) => theme.marked,
+      Some(PromptKind::NewFile | PromptKind::NewDir | PromptKind::Symlink | PromptKind::NewBranch) => theme.success,
+      Some(PromptKind::SaveLayout | PromptKind::CopyTo | PromptKind::GotoLine | PromptKind::PatchPath) => theme.accent,
+      Some(PromptKind::MoveTo) => theme.marked,
+      Some(_) | None => theme.warning,
+    },
+    InputMode::Error => theme.error,
+    _ => theme.git_staged,
+  }
+}
+
+fn mode_badge(app: &App, theme: &Theme) -> Span<'static> {
+  Span::styled(
+    format!(" {} ", mode_label(app.input_mode, app.prompt_kind)),
+    Style::default()
+      .fg(theme.bg_selected)
+      .bg(mode_color(app.input_mode, app.prompt_kind, theme))
+      .add_modifier(Modifier::BOLD),
+  )
+}
+
 pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
   let line = match app.input_mode {
     InputMode::Search => {
-      Line::from(vec![
-        Span::styled(" /", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
-        Span::styled(&app.search_query, Style::default().fg(theme.text)),
-        Span::styled("▌", Style::default().fg(theme.accent)),
-      ])
+      let mut spans = vec![Span::styled(" /", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))];
+      spans.extend(prompt_input_spans(&app.search.text, app.search.cursor, theme.accent, theme));
+      Line::from(spans)
     }
     InputMode::GPrefix => {
       Line::from(vec![
@@ -95,39 +212,99 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
           let mut spans = vec![
             Span::styled(" Rename: ", Style::default().fg(theme.marked).add_modifier(Modifier::BOLD)),
           ];
-          spans.extend(prompt_input_spans(&app.prompt_input, app.prompt_cursor, theme.marked, theme));
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, theme.marked, theme));
+          spans.extend(holders_span(app, theme));
           Line::from(spans)
         }
         Some(PromptKind::NewFile) => {
           let mut spans = vec![
             Span::styled(" New file: ", Style::default().fg(theme.success).add_modifier(Modifier::BOLD)),
           ];
-          spans.extend(prompt_input_spans(&app.prompt_input, app.prompt_cursor, theme.success, theme));
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, theme.success, theme));
+          spans.extend(new_mode_spans(app, "Tab: +x", theme));
           Line::from(spans)
         }
         Some(PromptKind::NewDir) => {
           let mut spans = vec![
             Span::styled(" New dir: ", Style::default().fg(theme.success).add_modifier(Modifier::BOLD)),
           ];
-          spans.extend(prompt_input_spans(&app.prompt_input, app.prompt_cursor, theme.success, theme));
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, theme.success, theme));
+          spans.extend(new_mode_spans(app, "", theme));
+          Line::from(spans)
+        }
+        Some(PromptKind::NewBranch) => {
+          let from = app.branch_current.as_deref().unwrap_or("HEAD");
+          let mut spans = vec![
+            Span::styled(format!(" New branch from {from}: "), Style::default().fg(theme.success).add_modifier(Modifier::BOLD)),
+          ];
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, theme.success, theme));
+          Line::from(spans)
+        }
+        Some(PromptKind::Symlink) => {
+          let name = app.link_target.as_ref().and_then(|t| t.file_name()).unwrap_or_default().to_string_lossy();
+          let mut spans = vec![
+            Span::styled(format!(" Link to {name} at: "), Style::default().fg(theme.success).add_modifier(Modifier::BOLD)),
+          ];
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, theme.success, theme));
+          Line::from(spans)
+        }
+        Some(PromptKind::SaveLayout) => {
+          let mut spans = vec![
+            Span::styled(" Save layout as: ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+          ];
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, theme.accent, theme));
+          Line::from(spans)
+        }
+        Some(PromptKind::PatchPath) => {
+          let what = app.pending_patch.as_ref().map_or("patch", |p| p.what.as_str());
+          let mut spans = vec![
+            Span::styled(format!(" Save {what} to: "), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+          ];
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, theme.accent, theme));
+          Line::from(spans)
+        }
+        Some(PromptKind::GotoLine) => {
+          let total = app.preview.get_content().map_or(0, |c| c.line_count);
+          let mut spans = vec![
+            Span::styled(format!(" Go to line (1-{total}): "), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+          ];
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, theme.accent, theme));
+          Line::from(spans)
+        }
+        Some(kind @ (PromptKind::CopyTo | PromptKind::MoveTo)) => {
+          let color = if kind == PromptKind::MoveTo { theme.marked } else { theme.accent };
+          let verb = if kind == PromptKind::MoveTo { "Move" } else { "Copy" };
+          let what = match app.destination.as_ref().map_or(&[][..], |d| d.sources.as_slice()) {
+            [one] => one.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            many => format!("{} items", many.len()),
+          };
+          let mut spans = vec![
+            Span::styled(format!(" {verb} {what} to: "), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+          ];
+          spans.extend(prompt_input_spans(&app.prompt.text, app.prompt.cursor, color, theme));
+          spans.push(Span::styled("  Tab: complete  ↑↓: pick", Style::default().fg(theme.text_dim)));
           Line::from(spans)
         }
         Some(PromptKind::ConfirmDelete) => {
           let name = app.selected_entry().map(|e| e.name.as_str()).unwrap_or("?");
-          Line::from(vec![
+          let mut spans = vec![
             Span::styled(
               format!(" Delete {name}? (y/N)"),
               Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
             ),
-          ])
+          ];
+          spans.extend(holders_span(app, theme));
+          Line::from(spans)
         }
         Some(PromptKind::ConfirmDeleteMulti(count)) => {
-          Line::from(vec![
+          let mut spans = vec![
             Span::styled(
               format!(" Delete {count} items? (y/N)"),
               Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
             ),
-          ])
+          ];
+          spans.extend(holders_span(app, theme));
+          Line::from(spans)
         }
         Some(PromptKind::ConfirmExtractAndDelete) => {
           let name = app.selected_entry().map(|e| e.name.as_str()).unwrap_or("?");
@@ -138,6 +315,51 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
             ),
           ])
         }
+        Some(PromptKind::ConfirmHunk(action, hunk)) => {
+          let count = app.preview.current_diff().map_or(0, |d| d.hunk_patches.len());
+          let color = if action == HunkAction::Revert { theme.error } else { theme.marked };
+          Line::from(vec![
+            Span::styled(
+              format!(" {} hunk {}/{count}? (y/N)", action.verb(), hunk + 1),
+              Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+          ])
+        }
+        Some(PromptKind::ConfirmGitDiscard(count)) => {
+          let what = match app.operation_targets().as_slice() {
+            [one] => one.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            _ => format!("{count} items"),
+          };
+          Line::from(vec![
+            Span::styled(
+              format!(" Discard unstaged changes to {what}? (y/N)"),
+              Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+            ),
+          ])
+        }
+        Some(PromptKind::ConfirmSafeNames) => {
+          let (count, fs_type) =
+            app.pending_safe_names.as_ref().map_or((0, ""), |p| (p.changes.len(), p.fs_type.as_str()));
+          Line::from(vec![
+            Span::styled(
+              format!(" {count} names not allowed on {fs_type}. Paste with safe names? (y/N)"),
+              Style::default().fg(theme.marked).add_modifier(Modifier::BOLD),
+            ),
+          ])
+        }
+        Some(PromptKind::ConfirmQuit) => Line::from(vec![Span::styled(
+          " Quit tfl? (y/N)".to_string(),
+          Style::default().fg(theme.marked).add_modifier(Modifier::BOLD),
+        )]),
+        Some(PromptKind::ConfirmRestoreSession) => {
+          let dir = app.pending_session.as_ref().map_or(String::new(), |s| s.root.display().to_string());
+          Line::from(vec![
+            Span::styled(
+              format!(" tfl exited uncleanly. Restore previous session in {dir}? (y/N)"),
+              Style::default().fg(theme.marked).add_modifier(Modifier::BOLD),
+            ),
+          ])
+        }
         None => {
           Line::from(vec![
             Span::styled(" ...", Style::default().fg(theme.text_dim)),
@@ -151,12 +373,36 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
         Span::styled("a:add  d:remove  Enter:go  Esc:close", Style::default().fg(theme.text_dim)),
       ])
     }
+    InputMode::Layouts => {
+      Line::from(vec![
+        Span::styled(" Layouts ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("a:save current  d:remove  Enter:restore  Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::BreadcrumbMenu => {
+      Line::from(vec![
+        Span::styled(" Path ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("h/l:up/into  Enter:go  Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
     InputMode::OpenWith => {
       Line::from(vec![
         Span::styled(" Open with ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
         Span::styled("Enter:open  Esc:close", Style::default().fg(theme.text_dim)),
       ])
     }
+    InputMode::Urls => {
+      Line::from(vec![
+        Span::styled(" URLs ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("1-9/Enter:open  Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::Doctor => {
+      Line::from(vec![
+        Span::styled(" Diagnostics ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("q/Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
     InputMode::Chmod => {
       Line::from(vec![
         Span::styled(" Chmod ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
@@ -175,6 +421,80 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
         Span::styled("1-4:format  Esc:cancel", Style::default().fg(theme.text_dim)),
       ])
     }
+    InputMode::PatchExport => {
+      Line::from(vec![
+        Span::styled(" Patch ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("1-4:export  Esc:cancel", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::Templates => {
+      Line::from(vec![
+        Span::styled(" Templates ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("Enter:use  Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::Worktrees => {
+      Line::from(vec![
+        Span::styled(" Worktrees ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("Enter:go / add for branch  Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::Branches => {
+      Line::from(vec![
+        Span::styled(" Branches ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("Enter:checkout  n:new branch  Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::OpLog => {
+      Line::from(vec![
+        Span::styled(" Operation log ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("j/k:scroll  Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::PasteOptions => {
+      Line::from(vec![
+        Span::styled(" Paste ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("l/m/t/x/v:toggle  Enter:paste  Esc:cancel", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::PasteConflict => {
+      Line::from(vec![
+        Span::styled(" Name taken ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("o:overwrite  s:skip  r:rename  a:all  Esc:cancel paste", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::Settings => {
+      Line::from(vec![
+        Span::styled(" Settings ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("h/l:change  Enter:save  Esc:cancel", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::Jobs => {
+      Line::from(vec![
+        Span::styled(" Jobs ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled("d:cancel  Esc:close", Style::default().fg(theme.text_dim)),
+      ])
+    }
+    InputMode::MacroRegister => {
+      let hint = match app.macros.pending {
+        Some(MacroOp::Play) => " replay register (a-z, 0-9, @ for last)",
+        _ => " record into register (a-z, 0-9)",
+      };
+      Line::from(vec![Span::styled(hint, Style::default().fg(theme.text_dim))])
+    }
+    InputMode::BookmarkSet => {
+      Line::from(vec![Span::styled(" bookmark the selection in slot (1-9)", Style::default().fg(theme.text_dim))])
+    }
+    InputMode::BookmarkJump => {
+      let project = app.bookmark_project();
+      let mut spans = vec![Span::styled(" jump to bookmark (1-9)", Style::default().fg(theme.text_dim))];
+      for (slot, path) in app.bookmarks.list(&project) {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        spans.push(Span::styled(format!("  {slot}:"), Style::default().fg(theme.accent)));
+        spans.push(Span::styled(name, Style::default().fg(theme.text_dim)));
+      }
+      Line::from(spans)
+    }
     InputMode::Error => {
       Line::from(vec![
         Span::styled(" Error ", Style::default().fg(theme.error).add_modifier(Modifier::BOLD)),
@@ -194,6 +514,28 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
         ));
       }
 
+      if app.read_only {
+        badges.push(Span::styled(
+          " READ-ONLY ",
+          Style::default()
+            .fg(theme.bg_selected)
+            .bg(theme.text_dim)
+            .add_modifier(Modifier::BOLD),
+        ));
+      }
+
+      if let Some(reg) = app.macros.recording() {
+        badges.push(Span::styled(
+          format!(" REC @{reg} "),
+          Style::default()
+            .fg(theme.bg_selected)
+            .bg(theme.error)
+            .add_modifier(Modifier::BOLD),
+        ));
+      }
+
+      badges.extend(progress_badge(app, theme));
+
       let mark_count = app.active_marks().len();
       if mark_count > 0 {
         badges.push(Span::styled(
@@ -216,7 +558,7 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
             Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
           ));
         spans.push(Span::styled(
-            format!(" | {}", format_size(entry.size)),
+            format!(" | {}", app.preview.size_format.format(entry.size)),
             Style::default().fg(theme.text_dim),
           ));
 
@@ -232,7 +574,7 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
         if let Some(content) = app.preview.get_content() {
           if content.file_size > 0 && content.file_size != entry.size {
             spans.push(Span::styled(
-              format!(" ({})", format_size(content.file_size)),
+              format!(" ({})", app.preview.size_format.format(content.file_size)),
               Style::default().fg(theme.text_dim),
             ));
           }
@@ -287,6 +629,14 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
           ));
         }
 
+        // What repeat_last would do
+        if let Some(ref last) = app.last_repeatable {
+          spans.push(Span::styled(
+            format!("  \u{27f3} {}", last.describe()),
+            Style::default().fg(theme.text_muted),
+          ));
+        }
+
         // Position info on the right
         let pos_info = format!(
           " {}/{} ",
@@ -304,7 +654,42 @@ pub fn render_status_bar(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme)
     }
   };
 
-  let paragraph = Paragraph::new(line)
+  let mut spans = vec![mode_badge(app, theme)];
+  spans.extend(line.spans);
+  let paragraph = Paragraph::new(Line::from(spans))
     .style(Style::default().bg(theme.bg_bar));
   paragraph.render(area, buf);
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_mode_label_names_prompt_kind() {
+    assert_eq!(mode_label(InputMode::Normal, None), "NORMAL");
+    assert_eq!(mode_label(InputMode::Prompt, Some(PromptKind::Rename)), "PROMPT: rename");
+    assert_eq!(mode_label(InputMode::Prompt, Some(PromptKind::ConfirmDelete)), "CONFIRM");
+    assert_eq!(mode_label(InputMode::Chmod, None), "CHMOD");
+  }
+
+  #[test]
+  fn test_mode_color_distinguishes_destructive_prompts() {
+    let theme = Theme::dark();
+    assert_eq!(mode_color(InputMode::Normal, None, &theme), theme.accent);
+    assert_eq!(mode_color(InputMode::Prompt, Some(PromptKind::ConfirmDelete), &theme), theme.warning);
+    assert_eq!(mode_color(InputMode::Prompt, Some(PromptKind::NewFile), &theme), theme.success);
+  }
+
+  #[test]
+  fn test_prompt_cursor_covers_combining_marks() {
+    let theme = Theme::dark();
+    let spans = prompt_input_spans("cafe\u{301}s", 3, theme.marked, &theme);
+    let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(texts, vec!["caf", "e\u{301}", "s"]);
+
+    let spans = prompt_input_spans("日本", 1, theme.marked, &theme);
+    let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(texts, vec!["日", "本", ""]);
+  }
+}
@@ -0,0 +1,48 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::theme::Theme;
+
+pub fn render_templates(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let choices = &app.template_choices;
+  let width = 40.min(area.width.saturating_sub(4));
+  let content_height = (choices.len() as u16 + 2).min(area.height.saturating_sub(2));
+
+  if width < 10 || content_height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(content_height)) / 2;
+  let popup = Rect::new(x, y, width, content_height);
+
+  Clear.render(popup, buf);
+
+  let lines: Vec<Line> = choices
+    .iter()
+    .enumerate()
+    .map(|(i, template)| {
+      let selected = app.template_cursor == i;
+      let prefix = if selected { " > " } else { "   " };
+      let style = if selected {
+        Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+      } else {
+        Style::default().fg(theme.text)
+      };
+      Line::from(Span::styled(format!("{prefix}{}", template.name), style))
+    })
+    .collect();
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" New from template ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  let paragraph = Paragraph::new(lines).block(block);
+  paragraph.render(popup, buf);
+}
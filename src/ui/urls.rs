@@ -0,0 +1,60 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::App;
+use crate::breadcrumb_menu::scroll_offset;
+use crate::theme::Theme;
+use crate::ui::width;
+
+/// Draw the URL picker: the preview's URLs in order, the first nine
+/// numbered for their digit key.
+pub fn render_urls(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let longest = app.urls.iter().map(|u| width::width(u)).max().unwrap_or(0) as u16;
+  let width = (longest + 7).clamp(30, 80).min(area.width.saturating_sub(4));
+  let height = (app.urls.len() as u16 + 2).min(area.height.saturating_sub(2));
+  if width < 10 || height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(height)) / 2;
+  let popup = Rect::new(x, y, width, height);
+
+  Clear.render(popup, buf);
+
+  let rows = height.saturating_sub(2) as usize;
+  let offset = scroll_offset(app.urls_cursor, rows);
+  let lines: Vec<Line> = app
+    .urls
+    .iter()
+    .enumerate()
+    .skip(offset)
+    .take(rows)
+    .map(|(i, url)| {
+      let selected = i == app.urls_cursor;
+      let prefix = if selected { " > " } else { "   " };
+      let number = if i < 9 { format!("{} ", i + 1) } else { "  ".to_string() };
+      let style = if selected {
+        Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+      } else {
+        Style::default().fg(theme.text)
+      };
+      Line::from(vec![
+        Span::styled(prefix, style),
+        Span::styled(number, Style::default().fg(theme.text_muted)),
+        Span::styled(width::truncate(url, width.saturating_sub(7) as usize), style),
+      ])
+    })
+    .collect();
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" URLs ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  Paragraph::new(lines).block(block).render(popup, buf);
+}
@@ -0,0 +1,129 @@
+//! Terminal column widths. CJK and most emoji take two columns and
+//! combining marks none, so anything that lines text up or cuts it to fit
+//! measures with these instead of `len()` or `chars().count()`.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns `s` takes up.
+pub fn width(s: &str) -> usize {
+  UnicodeWidthStr::width(s)
+}
+
+/// Columns `c` takes up; zero for combining marks and control chars.
+pub fn char_width(c: char) -> usize {
+  c.width().unwrap_or(0)
+}
+
+/// `s` cut to at most `max` columns, ending in `…` when anything was cut.
+/// Combining marks stay with the character they follow.
+pub fn truncate(s: &str, max: usize) -> String {
+  if width(s) <= max {
+    return s.to_string();
+  }
+  let budget = max.saturating_sub(1);
+  let mut used = 0;
+  let mut out = String::new();
+  for c in s.chars() {
+    let w = char_width(c);
+    if used + w > budget {
+      break;
+    }
+    used += w;
+    out.push(c);
+  }
+  if max > 0 {
+    out.push('…');
+  }
+  out
+}
+
+/// Like `truncate`, but keeps the end of `s` and starts with `…`, for paths.
+pub fn truncate_start(s: &str, max: usize) -> String {
+  if width(s) <= max {
+    return s.to_string();
+  }
+  let budget = max.saturating_sub(1);
+  let mut used = 0;
+  let mut start = s.len();
+  for (i, c) in s.char_indices().rev() {
+    let w = char_width(c);
+    if used + w > budget {
+      break;
+    }
+    used += w;
+    start = i;
+  }
+  // Don't open on a combining mark cut off from its base character
+  while let Some(c) = s[start..].chars().next().filter(|&c| char_width(c) == 0) {
+    start += c.len_utf8();
+  }
+  if max == 0 { String::new() } else { format!("…{}", &s[start..]) }
+}
+
+/// Char index of the cursor position after `pos`, stepping over combining
+/// marks so the cursor never lands between a letter and its accent.
+pub fn next_cursor(s: &str, pos: usize) -> usize {
+  let mut chars = s.chars().skip(pos);
+  if chars.next().is_none() {
+    return pos;
+  }
+  pos + 1 + chars.take_while(|&c| char_width(c) == 0).count()
+}
+
+/// Char index of the cursor position before `pos`, the counterpart of
+/// `next_cursor`.
+pub fn prev_cursor(s: &str, pos: usize) -> usize {
+  let chars: Vec<char> = s.chars().take(pos).collect();
+  let mut i = chars.len();
+  while i > 0 {
+    i -= 1;
+    if char_width(chars[i]) > 0 {
+      break;
+    }
+  }
+  i
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_width_counts_columns() {
+    assert_eq!(width("abc"), 3);
+    assert_eq!(width("日本語"), 6);
+    assert_eq!(width("e\u{301}"), 1);
+    assert_eq!(width("🦀.rs"), 5);
+  }
+
+  #[test]
+  fn test_truncate_by_columns() {
+    assert_eq!(truncate("short", 10), "short");
+    assert_eq!(truncate("日本語ファイル", 7), "日本語…");
+    // A wide char that would straddle the limit is dropped whole
+    assert_eq!(truncate("ab日本", 4), "ab…");
+    assert_eq!(width(&truncate("日本語ファイル名.txt", 9)), 9);
+    assert_eq!(truncate("abc", 0), "");
+  }
+
+  #[test]
+  fn test_truncate_start_keeps_tail() {
+    assert_eq!(truncate_start("/home/ユーザー/文書", 8), "…ー/文書");
+    assert_eq!(truncate_start("/tmp/x", 10), "/tmp/x");
+    assert_eq!(truncate_start("abe\u{301}", 2), "…e\u{301}");
+    assert!(width(&truncate_start("/a/b/日本語/ファイル", 7)) <= 7);
+  }
+
+  #[test]
+  fn test_cursor_steps_over_combining_marks() {
+    let s = "ae\u{301}b";
+    assert_eq!(next_cursor(s, 0), 1);
+    assert_eq!(next_cursor(s, 1), 3);
+    assert_eq!(next_cursor(s, 3), 4);
+    assert_eq!(next_cursor(s, 4), 4);
+    assert_eq!(prev_cursor(s, 4), 3);
+    assert_eq!(prev_cursor(s, 3), 1);
+    assert_eq!(prev_cursor(s, 1), 0);
+    assert_eq!(prev_cursor(s, 0), 0);
+  }
+}
@@ -0,0 +1,71 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::app::{App, WorktreeChoice};
+use crate::theme::Theme;
+use crate::ui::favorites::contract_home;
+use crate::ui::width;
+
+pub fn render_worktrees(app: &App, area: Rect, buf: &mut Buffer, theme: &Theme) {
+  let choices = &app.worktree_choices;
+  let width = 70.min(area.width.saturating_sub(4));
+  let content_height = (choices.len() as u16 + 2).min(area.height.saturating_sub(2));
+
+  if width < 20 || content_height < 3 {
+    return;
+  }
+
+  let x = area.x + (area.width.saturating_sub(width)) / 2;
+  let y = area.y + (area.height.saturating_sub(content_height)) / 2;
+  let popup = Rect::new(x, y, width, content_height);
+
+  Clear.render(popup, buf);
+
+  // Keep the cursor row in view when there are more rows than fit
+  let rows = content_height.saturating_sub(2) as usize;
+  let skip = (app.worktree_cursor + 1).saturating_sub(rows);
+  let root = &app.tree.root;
+  let inner = width.saturating_sub(2) as usize;
+
+  let lines: Vec<Line> = choices
+    .iter()
+    .enumerate()
+    .skip(skip)
+    .take(rows)
+    .map(|(i, choice)| {
+      let selected = app.worktree_cursor == i;
+      let prefix = if selected { " > " } else { "   " };
+      let name_style = if selected {
+        Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+      } else {
+        Style::default().fg(theme.text)
+      };
+      let (name, detail) = match choice {
+        WorktreeChoice::Existing(worktree) => {
+          let name = worktree.branch.clone().unwrap_or_else(|| "(detached)".to_string());
+          let here = if root.starts_with(&worktree.path) { "  (here)" } else { "" };
+          (name, format!("  {}{here}", contract_home(&worktree.path)))
+        }
+        WorktreeChoice::Branch(branch) => (format!("+ {}", branch.name), format!("  {}", branch.date)),
+      };
+      let name = width::truncate(&name, inner.saturating_sub(3));
+      let detail = width::truncate_start(&detail, inner.saturating_sub(3 + width::width(&name)));
+      Line::from(vec![
+        Span::styled(format!("{prefix}{name}"), name_style),
+        Span::styled(detail, Style::default().fg(theme.text_dim)),
+      ])
+    })
+    .collect();
+
+  let block = Block::default()
+    .borders(Borders::ALL)
+    .title(" Worktrees ")
+    .border_style(Style::default().fg(theme.title_inactive))
+    .style(Style::default().bg(theme.bg_overlay));
+
+  let paragraph = Paragraph::new(lines).block(block);
+  paragraph.render(popup, buf);
+}
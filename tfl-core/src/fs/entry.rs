@@ -1,35 +1,55 @@
+//! A single row of the file tree and its git status.
+
+use std::os::unix::fs::MetadataExt;
 use std::path::PathBuf;
 
-use ratatui::style::Color;
+use ratatui_core::style::Color;
 
+use crate::fs::walk;
 use crate::theme::Theme;
 
+/// A file's change against the index or the last commit
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GitFileStatus {
+  /// Changed content
   Modified,
+  /// New to the index
   Added,
+  /// Removed
   Deleted,
+  /// Moved from another path
   Renamed,
+  /// Not tracked by git
   Untracked,
+  /// Unmerged after a merge or rebase
   Conflicted,
 }
 
+/// Where an entry stands against the index (staged) and the work tree
+/// (unstaged)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct GitStatus {
+  /// Index against HEAD
   pub staged: Option<GitFileStatus>,
+  /// Work tree against the index
   pub unstaged: Option<GitFileStatus>,
 }
 
 impl GitStatus {
+  /// No change on either side.
   pub fn is_clean(&self) -> bool {
     self.staged.is_none() && self.unstaged.is_none()
   }
 
+  /// Unmerged on either side.
+  pub fn is_conflicted(&self) -> bool {
+    self.staged == Some(GitFileStatus::Conflicted) || self.unstaged == Some(GitFileStatus::Conflicted)
+  }
+
+  /// The colour to draw the entry's name in, if it has changes.
   pub fn display_color(&self, theme: &Theme) -> Option<Color> {
     // Conflicted
-    if self.staged == Some(GitFileStatus::Conflicted)
-      || self.unstaged == Some(GitFileStatus::Conflicted)
-    {
+    if self.is_conflicted() {
       return Some(theme.git_conflicted);
     }
     // Untracked
@@ -49,6 +69,23 @@ impl GitStatus {
     None
   }
 
+  /// Marker shown before the name in the tree, picked in the same order
+  /// as `display_color`: `!` conflicted, `?` untracked, `~` changes not
+  /// yet staged, `+` staged only. The status bar counts use the same signs.
+  pub fn marker(&self) -> Option<char> {
+    if self.is_conflicted() {
+      Some('!')
+    } else if self.staged == Some(GitFileStatus::Untracked) || self.unstaged == Some(GitFileStatus::Untracked) {
+      Some('?')
+    } else if self.unstaged.is_some() {
+      Some('~')
+    } else if self.staged.is_some() {
+      Some('+')
+    } else {
+      None
+    }
+  }
+
   /// Merge another status into this one, keeping the highest severity.
   pub fn merge(&mut self, other: &GitStatus) {
     if self.staged.is_none() || severity(other.staged) > severity(self.staged) {
@@ -71,25 +108,44 @@ fn severity(status: Option<GitFileStatus>) -> u8 {
   }
 }
 
+/// One row of the file tree.
 #[derive(Debug, Clone)]
 pub struct FileEntry {
+  /// Full path
   pub path: PathBuf,
+  /// File name shown in the tree
   pub name: String,
+  /// Levels below the tree's root, starting at 0
   pub depth: usize,
+  /// Directory, or a symlink to one
   pub is_dir: bool,
+  /// A symlink, whatever it points at
   pub is_symlink: bool,
+  /// Where the symlink points, as written in it
   pub symlink_target: Option<String>,
+  /// Symlink whose target doesn't exist
+  pub is_broken_link: bool,
+  /// Symlink to one of its own ancestors
+  pub is_cycle: bool,
+  /// Children are loaded and shown
   pub expanded: bool,
+  /// Size in bytes, of the target for a symlink
   pub size: u64,
+  /// Matched by a `.gitignore`
   pub is_git_ignored: bool,
+  /// Status in the repository, if any
   pub git_status: GitStatus,
+  /// Owner uid when the entry belongs to someone other than the current user
+  pub foreign_uid: Option<u32>,
 }
 
 impl FileEntry {
+  /// Read the entry at `path`, `depth` levels below the root.
   #[allow(dead_code)] // Used in tests
   pub fn from_path(path: PathBuf, depth: usize) -> Self {
     let metadata = path.symlink_metadata();
     let is_symlink = metadata.as_ref().is_ok_and(|m| m.is_symlink());
+    let foreign_uid = metadata.as_ref().ok().and_then(foreign_owner);
     let symlink_target = if is_symlink {
       std::fs::read_link(&path)
         .ok()
@@ -98,6 +154,7 @@ impl FileEntry {
       None
     };
     let metadata = path.metadata();
+    let is_broken_link = is_symlink && metadata.is_err();
     let is_dir = metadata.as_ref().is_ok_and(|m| m.is_dir());
     let size = metadata.as_ref().map_or(0, |m| m.len());
     let name = path
@@ -105,6 +162,8 @@ impl FileEntry {
       .map(|n| n.to_string_lossy().to_string())
       .unwrap_or_default();
 
+    let is_cycle = is_symlink && is_dir && walk::symlink_cycle_target(&path).is_some();
+
     Self {
       path,
       name,
@@ -112,18 +171,24 @@ impl FileEntry {
       is_dir,
       is_symlink,
       symlink_target,
+      is_broken_link,
+      is_cycle,
       expanded: false,
       size,
       is_git_ignored: false,
       git_status: GitStatus::default(),
+      foreign_uid,
     }
   }
 
+  /// Read an entry from a directory listing.
   pub fn from_dir_entry(dir_entry: std::fs::DirEntry, depth: usize) -> Self {
     let path = dir_entry.path();
     let name = dir_entry.file_name().to_string_lossy().to_string();
     let file_type = dir_entry.file_type().ok();
     let is_symlink = file_type.as_ref().is_some_and(|ft| ft.is_symlink());
+    // DirEntry metadata doesn't follow symlinks: ownership is the link's own
+    let link_meta = dir_entry.metadata().ok();
     let symlink_target = if is_symlink {
       std::fs::read_link(&path)
         .ok()
@@ -131,8 +196,10 @@ impl FileEntry {
     } else {
       None
     };
+    let mut is_broken_link = false;
     let (is_dir, size) = if is_symlink {
       let meta = path.metadata().ok();
+      is_broken_link = meta.is_none();
       (
         meta.as_ref().is_some_and(|m| m.is_dir()),
         meta.as_ref().map_or(0, |m| m.len()),
@@ -142,10 +209,13 @@ impl FileEntry {
       let size = if is_dir {
         0
       } else {
-        dir_entry.metadata().ok().map_or(0, |m| m.len())
+        link_meta.as_ref().map_or(0, |m| m.len())
       };
       (is_dir, size)
     };
+    let foreign_uid = link_meta.as_ref().and_then(foreign_owner);
+
+    let is_cycle = is_symlink && is_dir && walk::symlink_cycle_target(&path).is_some();
 
     Self {
       path,
@@ -154,18 +224,51 @@ impl FileEntry {
       is_dir,
       is_symlink,
       symlink_target,
+      is_broken_link,
+      is_cycle,
       expanded: false,
       size,
       is_git_ignored: false,
       git_status: GitStatus::default(),
+      foreign_uid,
     }
   }
 
+  /// Entry with only a name, kind and size, for tree sources that have no
+  /// symlinks, owners or git status to report.
+  pub fn new(path: PathBuf, depth: usize, is_dir: bool, size: u64) -> Self {
+    let name = path
+      .file_name()
+      .map(|n| n.to_string_lossy().to_string())
+      .unwrap_or_default();
+    Self {
+      path,
+      name,
+      depth,
+      is_dir,
+      is_symlink: false,
+      symlink_target: None,
+      is_broken_link: false,
+      is_cycle: false,
+      expanded: false,
+      size: if is_dir { 0 } else { size },
+      is_git_ignored: false,
+      git_status: GitStatus::default(),
+      foreign_uid: None,
+    }
+  }
+
+  /// Name starts with a dot.
   pub fn is_hidden(&self) -> bool {
     self.name.starts_with('.')
   }
 }
 
+fn foreign_owner(meta: &std::fs::Metadata) -> Option<u32> {
+  let uid = meta.uid();
+  (uid != users::get_current_uid()).then_some(uid)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -213,10 +316,13 @@ mod tests {
       is_dir: false,
       is_symlink: false,
       symlink_target: None,
+      is_broken_link: false,
+      is_cycle: false,
       expanded: false,
       size: 0,
       is_git_ignored: false,
       git_status: GitStatus::default(),
+      foreign_uid: None,
     };
     assert!(entry.is_hidden());
 
@@ -227,10 +333,13 @@ mod tests {
       is_dir: false,
       is_symlink: false,
       symlink_target: None,
+      is_broken_link: false,
+      is_cycle: false,
       expanded: false,
       size: 0,
       is_git_ignored: false,
       git_status: GitStatus::default(),
+      foreign_uid: None,
     };
     assert!(!entry.is_hidden());
   }
@@ -248,6 +357,15 @@ mod tests {
     let entry = FileEntry::from_path(link.clone(), 0);
     assert!(entry.is_symlink);
     assert_eq!(entry.symlink_target, Some(file.to_string_lossy().to_string()));
+    assert!(!entry.is_broken_link);
+
+    // Still a symlink with a target once that is gone, but a broken one
+    fs::remove_file(&file).unwrap();
+    let listed = fs::read_dir(&dir).unwrap().flatten().find(|e| e.path() == link).unwrap();
+    for entry in [FileEntry::from_path(link.clone(), 0), FileEntry::from_dir_entry(listed, 0)] {
+      assert!(entry.is_symlink && entry.is_broken_link);
+      assert_eq!(entry.symlink_target, Some(file.to_string_lossy().to_string()));
+    }
 
     let _ = fs::remove_dir_all(&dir);
   }
@@ -366,6 +484,17 @@ mod tests {
     assert_eq!(status.display_color(&theme), Some(theme.git_modified));
   }
 
+  #[test]
+  fn test_marker_follows_display_color_precedence() {
+    let status = |staged, unstaged| GitStatus { staged, unstaged };
+    assert_eq!(GitStatus::default().marker(), None);
+    assert_eq!(status(Some(GitFileStatus::Added), None).marker(), Some('+'));
+    assert_eq!(status(Some(GitFileStatus::Added), Some(GitFileStatus::Modified)).marker(), Some('~'));
+    assert_eq!(status(None, Some(GitFileStatus::Deleted)).marker(), Some('~'));
+    assert_eq!(status(None, Some(GitFileStatus::Untracked)).marker(), Some('?'));
+    assert_eq!(status(Some(GitFileStatus::Conflicted), None).marker(), Some('!'));
+  }
+
   #[test]
   fn test_git_status_merge() {
     let mut parent = GitStatus::default();
@@ -384,4 +513,20 @@ mod tests {
     parent.merge(&child2);
     assert_eq!(parent.staged, Some(GitFileStatus::Conflicted));
   }
+
+  #[test]
+  fn test_foreign_owner_flagged() {
+    let dir = std::env::temp_dir().join(format!("tfl_test_owner_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let own = dir.join("mine.txt");
+    fs::write(&own, "").unwrap();
+    assert_eq!(FileEntry::from_path(own, 0).foreign_uid, None);
+    let root = FileEntry::from_path(PathBuf::from("/"), 0);
+    if users::get_current_uid() == 0 {
+      assert_eq!(root.foreign_uid, None);
+    } else {
+      assert_eq!(root.foreign_uid, Some(0));
+    }
+    let _ = fs::remove_dir_all(&dir);
+  }
 }
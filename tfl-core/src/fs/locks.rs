@@ -0,0 +1,149 @@
+//! Finding processes that hold files open before they are deleted or moved.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+/// Names of other processes holding `paths` open, sorted and deduplicated.
+/// A directory counts as held when a process has something beneath it open
+/// or is running inside it. Only called right before a destructive operation,
+/// since it walks every process's open files.
+pub fn holders(paths: &[PathBuf]) -> Vec<String> {
+  let targets: Vec<PathBuf> = paths
+    .iter()
+    .map(|p| p.canonicalize().unwrap_or_else(|_| p.clone()))
+    .collect();
+  if targets.is_empty() {
+    return Vec::new();
+  }
+  let mut names = scan(&targets);
+  names.sort();
+  names.dedup();
+  names
+}
+
+/// `holders` on a worker thread, for prompts that shouldn't wait for it.
+pub fn holders_async(paths: Vec<PathBuf>) -> mpsc::Receiver<Vec<String>> {
+  let (tx, rx) = mpsc::channel();
+  std::thread::spawn(move || {
+    let _ = tx.send(holders(&paths));
+  });
+  rx
+}
+
+/// Short suffix for prompts and errors, e.g. "in use by less, vim".
+pub fn describe(holders: &[String]) -> Option<String> {
+  const MAX_NAMES: usize = 3;
+  if holders.is_empty() {
+    return None;
+  }
+  let mut text = format!("in use by {}", holders.iter().take(MAX_NAMES).cloned().collect::<Vec<_>>().join(", "));
+  if holders.len() > MAX_NAMES {
+    text.push_str(&format!(" +{}", holders.len() - MAX_NAMES));
+  }
+  Some(text)
+}
+
+fn is_held(open: &Path, targets: &[PathBuf]) -> bool {
+  targets.iter().any(|t| open.starts_with(t))
+}
+
+#[cfg(target_os = "linux")]
+fn scan(targets: &[PathBuf]) -> Vec<String> {
+  let own = std::process::id().to_string();
+  let Ok(procs) = std::fs::read_dir("/proc") else {
+    return Vec::new();
+  };
+  let mut names = Vec::new();
+  for proc_entry in procs.flatten() {
+    let pid = proc_entry.file_name();
+    let Some(pid) = pid.to_str().filter(|p| p.bytes().all(|b| b.is_ascii_digit())) else {
+      continue;
+    };
+    if pid == own {
+      continue;
+    }
+    let dir = proc_entry.path();
+    let in_cwd = std::fs::read_link(dir.join("cwd")).is_ok_and(|cwd| is_held(&cwd, targets));
+    // Other users' fds are unreadable without privileges; those are skipped
+    let has_fd = in_cwd
+      || std::fs::read_dir(dir.join("fd")).is_ok_and(|fds| {
+        fds.flatten().any(|fd| std::fs::read_link(fd.path()).is_ok_and(|open| is_held(&open, targets)))
+      });
+    if has_fd
+      && let Ok(comm) = std::fs::read_to_string(dir.join("comm"))
+    {
+      names.push(comm.trim().to_string());
+    }
+  }
+  names
+}
+
+#[cfg(not(target_os = "linux"))]
+fn scan(targets: &[PathBuf]) -> Vec<String> {
+  // `lsof -F cn` prints one `p<pid>`, `c<command>` and `n<name>` line per
+  // field. Only the targets are looked up; `+D` covers what is beneath a
+  // directory, its processes' working directories included.
+  let mut command = std::process::Command::new("lsof");
+  command.args(["-w", "-F", "cn", "-a", "-p"]).arg(format!("^{}", std::process::id()));
+  for target in targets {
+    if target.is_dir() {
+      command.arg("+D");
+    }
+    command.arg(target);
+  }
+  let Ok(output) = command.stderr(std::process::Stdio::null()).output()
+  else {
+    return Vec::new();
+  };
+  let mut names = Vec::new();
+  let mut command = None;
+  for line in String::from_utf8_lossy(&output.stdout).lines() {
+    if let Some(c) = line.strip_prefix('c') {
+      command = Some(c.to_string());
+    } else if let Some(n) = line.strip_prefix('n')
+      && is_held(Path::new(n), targets)
+      && let Some(c) = &command
+    {
+      names.push(c.clone());
+    }
+  }
+  names
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_describe() {
+    assert_eq!(describe(&[]), None);
+    assert_eq!(describe(&["vim".to_string()]).as_deref(), Some("in use by vim"));
+    let many: Vec<String> = ["a", "b", "c", "d", "e"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(describe(&many).as_deref(), Some("in use by a, b, c +2"));
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn test_holders_finds_process_running_in_dir() {
+    let dir = std::env::temp_dir().join(format!("tfl_locks_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    let other = std::env::temp_dir().join(format!("tfl_locks_free_{}", std::process::id()));
+    std::fs::create_dir_all(&other).unwrap();
+
+    let mut child = std::process::Command::new("sleep")
+      .arg("30")
+      .current_dir(dir.join("sub"))
+      .spawn()
+      .unwrap();
+    let held = holders(std::slice::from_ref(&dir));
+    let free = holders(std::slice::from_ref(&other));
+    let _ = child.kill();
+    let _ = child.wait();
+
+    assert!(held.contains(&"sleep".to_string()), "{held:?}");
+    assert!(free.is_empty(), "{free:?}");
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(&other);
+  }
+}
@@ -0,0 +1,17 @@
+//! The file tree and the filesystem operations around it.
+
+pub mod entry;
+pub mod locks;
+pub mod names;
+pub mod ops;
+pub mod progress;
+pub mod properties;
+pub mod selinux;
+pub mod source;
+pub mod tree;
+pub mod walk;
+
+pub use entry::{FileEntry, GitFileStatus, GitStatus};
+pub use properties::{FileProperties, MultiProperties};
+pub use source::{ArchiveSource, LocalSource, MemorySource, TreeSource};
+pub use tree::FileTree;
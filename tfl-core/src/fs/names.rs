@@ -0,0 +1,173 @@
+//! Filename rules of the filesystem being written to. FAT, exFAT and NTFS
+//! reject names Linux allows, like `a:b` or `what?`, so pastes onto those
+//! mounts can rename offending entries first.
+
+use std::path::{Path, PathBuf};
+
+use crate::fs::walk::VisitedDirs;
+
+/// Filesystem types that follow the Windows naming rules. `fuseblk` is
+/// most often ntfs-3g or exfat-fuse.
+const WINDOWS_FILESYSTEMS: &[&str] = &["vfat", "msdos", "fat", "exfat", "ntfs", "ntfs3", "fuseblk"];
+
+/// Device names Windows won't use as a file name, with or without extension
+const RESERVED_NAMES: &[&str] = &[
+  "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2",
+  "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// A name inside a paste that the destination would reject.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameChange {
+  /// Path of the entry relative to the pasted item's parent
+  pub path: PathBuf,
+  /// The name it gets at the destination
+  pub new_name: String,
+}
+
+/// The filesystem type `dir` lives on when it restricts names, e.g.
+/// `"vfat"`. Read from `/proc/self/mounts`, so only detected on Linux.
+pub fn restricted_fs(dir: &Path) -> Option<String> {
+  let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+  let dir = dir.canonicalize().ok()?;
+  mount_fs_type(&mounts, &dir).filter(|fs| WINDOWS_FILESYSTEMS.contains(&fs.as_str()))
+}
+
+/// Type of the filesystem mounted closest above `path`.
+fn mount_fs_type(mounts: &str, path: &Path) -> Option<String> {
+  mounts
+    .lines()
+    .filter_map(|line| {
+      let mut fields = line.split_whitespace();
+      let _device = fields.next()?;
+      let mount_point = PathBuf::from(unescape_mount(fields.next()?));
+      let fs_type = fields.next()?;
+      path.starts_with(&mount_point).then(|| (mount_point, fs_type.to_string()))
+    })
+    .max_by_key(|(mount_point, _)| mount_point.components().count())
+    .map(|(_, fs_type)| fs_type)
+}
+
+/// Undo the octal escapes (`\040` for a space) in mount points.
+fn unescape_mount(field: &str) -> String {
+  let mut out = String::with_capacity(field.len());
+  let mut rest = field;
+  while let Some(i) = rest.find('\\') {
+    out.push_str(&rest[..i]);
+    let code = rest.get(i + 1..i + 4).and_then(|d| u8::from_str_radix(d, 8).ok());
+    match code {
+      Some(byte) => {
+        out.push(byte as char);
+        rest = &rest[i + 4..];
+      }
+      None => {
+        out.push('\\');
+        rest = &rest[i + 1..];
+      }
+    }
+  }
+  out.push_str(rest);
+  out
+}
+
+/// `name` made acceptable to FAT and NTFS: forbidden characters become
+/// `_`, trailing dots and spaces are dropped and reserved device names get
+/// a `_` appended.
+pub fn sanitize_name(name: &str) -> String {
+  let replaced: String = name
+    .chars()
+    .map(|c| if matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') || c.is_control() { '_' } else { c })
+    .collect();
+  let mut safe = replaced.trim_end_matches(['.', ' ']).to_string();
+  if safe.is_empty() {
+    safe.push('_');
+  }
+  let stem = safe.split('.').next().unwrap_or("").trim_end();
+  if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+    safe.insert(stem.len(), '_');
+  }
+  safe
+}
+
+/// Every name in `sources`, and inside them when they are directories,
+/// that `sanitize_name` would change.
+pub fn unsafe_names(sources: &[PathBuf]) -> Vec<NameChange> {
+  let mut changes = Vec::new();
+  let mut visited = VisitedDirs::default();
+  for source in sources {
+    let base = source.parent().unwrap_or(Path::new(""));
+    collect_unsafe(source, base, &mut visited, &mut changes);
+  }
+  changes
+}
+
+fn collect_unsafe(path: &Path, base: &Path, visited: &mut VisitedDirs, changes: &mut Vec<NameChange>) {
+  let Some(name) = path.file_name() else {
+    return;
+  };
+  let name = name.to_string_lossy();
+  let new_name = sanitize_name(&name);
+  if new_name != name {
+    let path = path.strip_prefix(base).unwrap_or(path).to_path_buf();
+    changes.push(NameChange { path, new_name });
+  }
+  if path.is_dir() && visited.enter(path) {
+    let Ok(read_dir) = std::fs::read_dir(path) else {
+      return;
+    };
+    let mut children: Vec<PathBuf> = read_dir.flatten().map(|e| e.path()).collect();
+    children.sort();
+    for child in children {
+      collect_unsafe(&child, base, visited, changes);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  #[test]
+  fn test_sanitize_name() {
+    assert_eq!(sanitize_name("notes.txt"), "notes.txt");
+    assert_eq!(sanitize_name("10:30 meeting?.md"), "10_30 meeting_.md");
+    assert_eq!(sanitize_name("a<b>|c*\"d\""), "a_b__c__d_");
+    assert_eq!(sanitize_name("draft. "), "draft");
+    assert_eq!(sanitize_name("..."), "_");
+    assert_eq!(sanitize_name("con"), "con_");
+    assert_eq!(sanitize_name("Aux.tar.gz"), "Aux_.tar.gz");
+    assert_eq!(sanitize_name("console.log"), "console.log");
+    assert_eq!(sanitize_name("tab\there"), "tab_here");
+  }
+
+  #[test]
+  fn test_mount_fs_type_picks_deepest_mount() {
+    let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+                  /dev/sdb1 /media/usb\\040stick vfat rw 0 0\n\
+                  tmpfs /tmp tmpfs rw 0 0\n";
+    assert_eq!(mount_fs_type(mounts, Path::new("/media/usb stick/photos")).as_deref(), Some("vfat"));
+    assert_eq!(mount_fs_type(mounts, Path::new("/media/usb")).as_deref(), Some("ext4"));
+    assert_eq!(mount_fs_type(mounts, Path::new("/tmp/x")).as_deref(), Some("tmpfs"));
+  }
+
+  #[test]
+  fn test_unsafe_names_walks_directories() {
+    let dir = std::env::temp_dir().join(format!("tfl_names_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("logs:old")).unwrap();
+    fs::write(dir.join("logs:old/ok.txt"), "").unwrap();
+    fs::write(dir.join("logs:old/what?.txt"), "").unwrap();
+    fs::write(dir.join("fine.txt"), "").unwrap();
+
+    let changes = unsafe_names(&[dir.join("logs:old"), dir.join("fine.txt")]);
+    assert_eq!(
+      changes,
+      vec![
+        NameChange { path: PathBuf::from("logs:old"), new_name: "logs_old".to_string() },
+        NameChange { path: PathBuf::from("logs:old/what?.txt"), new_name: "what_.txt".to_string() },
+      ]
+    );
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
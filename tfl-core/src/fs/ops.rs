@@ -0,0 +1,791 @@
+//! Filesystem helpers: copying, collision-free names and default permissions.
+
+use std::io;
+use std::ops::ControlFlow;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::fs::names::sanitize_name;
+use crate::fs::progress::Progress;
+use crate::fs::walk::{VisitedDirs, walk};
+
+/// Returns a unique destination path by appending `_copy`, `_copy2`, etc.
+/// if the path already exists (a dangling symlink counts).
+pub fn unique_dest_path(dest: &Path) -> PathBuf {
+  let taken = |path: &Path| path.symlink_metadata().is_ok();
+  if !taken(dest) {
+    return dest.to_path_buf();
+  }
+
+  let stem = dest
+    .file_stem()
+    .map(|s| s.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let ext = dest.extension().map(|e| e.to_string_lossy().to_string());
+  let parent = dest.parent().unwrap_or(Path::new("."));
+
+  let make_name = |suffix: &str| -> PathBuf {
+    match &ext {
+      Some(e) => parent.join(format!("{stem}{suffix}.{e}")),
+      None => parent.join(format!("{stem}{suffix}")),
+    }
+  };
+
+  let first = make_name("_copy");
+  if !taken(&first) {
+    return first;
+  }
+
+  let mut n = 2u32;
+  loop {
+    let candidate = make_name(&format!("_copy{n}"));
+    if !taken(&candidate) {
+      return candidate;
+    }
+    n += 1;
+  }
+}
+
+/// `to` relative to the directory `from`, climbing out with `..` where
+/// they part, as `ln -sr` writes link targets. Both should be absolute.
+pub fn relative_path(from: &Path, to: &Path) -> PathBuf {
+  let from: Vec<_> = from.components().collect();
+  let to: Vec<_> = to.components().collect();
+  let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+  let mut relative: PathBuf = from[common..].iter().map(|_| "..").collect();
+  relative.extend(&to[common..]);
+  if relative.as_os_str().is_empty() {
+    relative.push(".");
+  }
+  relative
+}
+
+/// What a paste does with a source whose name is already taken where it
+/// goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Collision {
+  /// Keep both, the pasted one under a [`unique_dest_path`] name
+  #[default]
+  Rename,
+  /// Remove what is there first
+  Overwrite,
+  /// Leave the source out
+  Skip,
+}
+
+/// The process umask. Linux exposes it in /proc; elsewhere it can only be
+/// read by setting it, so it is swapped out and straight back.
+pub fn current_umask() -> u32 {
+  let from_proc = std::fs::read_to_string("/proc/self/status").ok().and_then(|status| {
+    status
+      .lines()
+      .find_map(|l| l.strip_prefix("Umask:"))
+      .and_then(|v| u32::from_str_radix(v.trim(), 8).ok())
+  });
+  from_proc.unwrap_or_else(|| {
+    // SAFETY: umask only swaps the process file mode mask and cannot fail
+    let umask = unsafe { libc::umask(0o022) };
+    unsafe { libc::umask(umask) };
+    umask as u32
+  })
+}
+
+/// Permissions a newly created file or directory gets under `umask`.
+pub fn default_mode(is_dir: bool, umask: u32) -> u32 {
+  let base = if is_dir { 0o777 } else { 0o666 };
+  base & !umask
+}
+
+/// Add execute bits wherever `mode` grants read, like `chmod +x` does under
+/// the default umask.
+pub fn with_execute(mode: u32) -> u32 {
+  mode | ((mode & 0o444) >> 2)
+}
+
+/// How a copy treats symlinks and metadata, after `cp`'s `-P`/`-L` and
+/// `--preserve`. The default follows symlinks and keeps only permissions,
+/// like a plain `cp -r`; [`CopyOptions::archive`] is `cp -a`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyOptions {
+  /// Recreate symlinks as links instead of copying what they point to
+  pub preserve_links: bool,
+  /// Keep permission bits as they are; otherwise the umask is applied to
+  /// them, as `cp` does without `-p`
+  pub preserve_mode: bool,
+  /// Keep access and modification times
+  pub preserve_timestamps: bool,
+  /// Keep extended attributes, where the destination accepts them
+  pub preserve_xattrs: bool,
+  /// Give everything inside a copied directory a name FAT and NTFS accept
+  /// (see `names::sanitize_name`). The destination itself is used as given.
+  pub sanitize: bool,
+  /// Read every copied file back and compare it with the original
+  pub verify: bool,
+}
+
+impl Default for CopyOptions {
+  fn default() -> Self {
+    Self {
+      preserve_links: false,
+      preserve_mode: true,
+      preserve_timestamps: false,
+      preserve_xattrs: false,
+      sanitize: false,
+      verify: false,
+    }
+  }
+}
+
+impl CopyOptions {
+  /// Everything kept as it was, like `cp -a`. Moves across filesystems use
+  /// this so the result matches a rename.
+  pub fn archive() -> Self {
+    Self {
+      preserve_links: true,
+      preserve_mode: true,
+      preserve_timestamps: true,
+      preserve_xattrs: true,
+      sanitize: false,
+      verify: false,
+    }
+  }
+}
+
+/// Copy a file or directory to `dest`. For directories, copies recursively.
+/// Returns the number of symlink cycles that were skipped.
+pub fn copy_path(source: &Path, dest: &Path) -> io::Result<usize> {
+  copy_path_with(source, dest, &CopyOptions::default())
+}
+
+/// Like `copy_path`, but everything inside a copied directory gets a name
+/// FAT and NTFS accept (see `names::sanitize_name`). `dest` itself is used
+/// as given.
+pub fn copy_path_sanitized(source: &Path, dest: &Path) -> io::Result<usize> {
+  copy_path_with(source, dest, &CopyOptions { sanitize: true, ..CopyOptions::default() })
+}
+
+/// Copy `source` to `dest` as `options` says. Returns the number of symlink
+/// cycles that were skipped (only possible while following links).
+pub fn copy_path_with(source: &Path, dest: &Path, options: &CopyOptions) -> io::Result<usize> {
+  copy_path_tracked(source, dest, options, &Progress::default())
+}
+
+/// Like `copy_path_with`, counting each copied entry and the bytes of large
+/// files as they are written in `progress`, and failing with `Interrupted`
+/// at the next entry or chunk once it is cancelled.
+pub fn copy_path_tracked(source: &Path, dest: &Path, options: &CopyOptions, progress: &Progress) -> io::Result<usize> {
+  let mut visited = VisitedDirs::default();
+  copy_entry(source, dest, options, &mut visited, progress)?;
+  Ok(visited.cycles)
+}
+
+/// Recursively copy a directory and all its contents, following symlinks but
+/// never re-entering a directory already copied. Returns the number of
+/// symlink cycles that were skipped.
+pub fn copy_dir_recursive(source: &Path, dest: &Path) -> io::Result<usize> {
+  copy_path(source, dest)
+}
+
+fn copy_entry(
+  source: &Path,
+  dest: &Path,
+  options: &CopyOptions,
+  visited: &mut VisitedDirs,
+  progress: &Progress,
+) -> io::Result<()> {
+  progress.check()?;
+  let meta = if options.preserve_links { source.symlink_metadata()? } else { source.metadata()? };
+  if meta.is_symlink() {
+    std::os::unix::fs::symlink(std::fs::read_link(source)?, dest)?;
+  } else if meta.is_dir() {
+    if !visited.enter(source) {
+      return Ok(());
+    }
+    std::fs::create_dir_all(dest)?;
+    for entry in std::fs::read_dir(source)? {
+      let entry = entry?;
+      let name = entry.file_name();
+      let safe = options.sanitize.then(|| sanitize_name(&name.to_string_lossy())).filter(|safe| name != safe.as_str());
+      // Two names can sanitize to the same one; the later copy gets a suffix
+      let dst_path = match safe {
+        Some(safe) => unique_dest_path(&dest.join(safe)),
+        None => dest.join(name),
+      };
+      copy_entry(&entry.path(), &dst_path, options, visited, progress)?;
+    }
+  } else {
+    copy_file_tracked(source, dest, progress)?;
+    if options.verify && !verify_copy(source, dest)? {
+      let name = dest.file_name().unwrap_or_default().to_string_lossy();
+      return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{name} differs from the original after copying")));
+    }
+  }
+  progress.add(1, 0);
+  // After the contents, so copying them doesn't bump a directory's mtime
+  // or trip over a read-only mode
+  copy_metadata(dest, &meta, source, options)
+}
+
+/// Copy one file's contents and permissions, cheapest way first: a reflink
+/// clone sharing the source's extents (btrfs, XFS), then a hole-preserving
+/// copy for sparse files such as VM images, then a plain copy, which uses
+/// copy_file_range on Linux and clonefile on macOS.
+pub fn copy_file(source: &Path, dest: &Path) -> io::Result<()> {
+  copy_file_tracked(source, dest, &Progress::default())
+}
+
+/// Bytes copied between progress updates and cancel checks
+const COPY_CHUNK: u64 = 8 << 20;
+
+/// Like `copy_file`, adding the bytes to `progress` a chunk at a time and
+/// failing with `Interrupted` between chunks once it is cancelled.
+pub fn copy_file_tracked(source: &Path, dest: &Path, progress: &Progress) -> io::Result<()> {
+  #[cfg(target_os = "linux")]
+  {
+    use std::io::{Read, Seek, SeekFrom};
+    use std::os::unix::fs::MetadataExt;
+
+    progress.check()?;
+    let mut src = std::fs::File::open(source)?;
+    let meta = src.metadata()?;
+    let mut dst = std::fs::File::create(dest)?;
+    if clone_file(&src, &dst).is_ok() {
+      progress.add(0, meta.len());
+      return dst.set_permissions(meta.permissions());
+    }
+    // Fewer blocks allocated than the length covers: there are holes
+    let sparse = meta.blocks() * 512 < meta.len();
+    let copied = sparse && match copy_sparse(&src, &dst, meta.len(), progress) {
+      Ok(()) => true,
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => return Err(e),
+      Err(_) => false,
+    };
+    if !copied {
+      // Through the descriptors already open: a read-only source's mode
+      // must not be on `dest` before its data is written
+      src.seek(SeekFrom::Start(0))?;
+      dst.set_len(0)?;
+      dst.seek(SeekFrom::Start(0))?;
+      // `io::copy` still uses copy_file_range for each chunk
+      loop {
+        progress.check()?;
+        let copied = io::copy(&mut (&mut src).take(COPY_CHUNK), &mut dst)?;
+        if copied == 0 {
+          break;
+        }
+        progress.add(0, copied);
+      }
+    }
+    dst.set_permissions(meta.permissions())
+  }
+  #[cfg(not(target_os = "linux"))]
+  {
+    progress.check()?;
+    let copied = std::fs::copy(source, dest)?;
+    progress.add(0, copied);
+    Ok(())
+  }
+}
+
+/// Share `src`'s extents with `dst` (FICLONE). Fails across filesystems
+/// and on filesystems without reflinks.
+#[cfg(target_os = "linux")]
+fn clone_file(src: &std::fs::File, dst: &std::fs::File) -> io::Result<()> {
+  use std::os::fd::AsRawFd;
+
+  // SAFETY: both descriptors are open for the duration of the call
+  let result = unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) };
+  if result == 0 { Ok(()) } else { Err(io::Error::last_os_error()) }
+}
+
+/// Copy only the data regions of `src` (SEEK_DATA/SEEK_HOLE), leaving the
+/// holes between them unallocated in `dst`.
+#[cfg(target_os = "linux")]
+fn copy_sparse(src: &std::fs::File, dst: &std::fs::File, len: u64, progress: &Progress) -> io::Result<()> {
+  use std::os::fd::AsRawFd;
+  use std::os::unix::fs::FileExt;
+
+  let seek = |offset: u64, whence: libc::c_int| -> io::Result<Option<u64>> {
+    // SAFETY: lseek on an open descriptor; the offset is only read back
+    let pos = unsafe { libc::lseek(src.as_raw_fd(), offset as libc::off_t, whence) };
+    match pos {
+      -1 if io::Error::last_os_error().raw_os_error() == Some(libc::ENXIO) => Ok(None),
+      -1 => Err(io::Error::last_os_error()),
+      pos => Ok(Some(pos as u64)),
+    }
+  };
+  dst.set_len(len)?;
+  let mut buf = vec![0u8; 1 << 20];
+  let mut offset = 0;
+  // ENXIO from SEEK_DATA: nothing but a hole from here to the end
+  while offset < len
+    && let Some(start) = seek(offset, libc::SEEK_DATA)?
+  {
+    let end = seek(start, libc::SEEK_HOLE)?.unwrap_or(len).min(len);
+    // Holes count as done, so the copy reaches its measured size
+    progress.add(0, start.min(len).saturating_sub(offset));
+    let mut pos = start;
+    while pos < end {
+      progress.check()?;
+      let want = buf.len().min((end - pos) as usize);
+      let read = src.read_at(&mut buf[..want], pos)?;
+      if read == 0 {
+        break;
+      }
+      dst.write_all_at(&buf[..read], pos)?;
+      pos += read as u64;
+      progress.add(0, read as u64);
+    }
+    offset = end;
+  }
+  progress.add(0, len.saturating_sub(offset));
+  Ok(())
+}
+
+/// Whether `copy` has the same size and bytes as `source`. On Linux the
+/// copy is flushed and dropped from the page cache first, so the check
+/// reads what actually reached the drive rather than memory.
+pub fn verify_copy(source: &Path, copy: &Path) -> io::Result<bool> {
+  let mut original = std::fs::File::open(source)?;
+  let mut copied = std::fs::File::open(copy)?;
+  if original.metadata()?.len() != copied.metadata()?.len() {
+    return Ok(false);
+  }
+  #[cfg(target_os = "linux")]
+  {
+    use std::os::fd::AsRawFd;
+
+    copied.sync_all()?;
+    // SAFETY: advisory call on an open descriptor
+    unsafe { libc::posix_fadvise(copied.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+  }
+  let mut a = vec![0u8; 1 << 20];
+  let mut b = vec![0u8; 1 << 20];
+  loop {
+    let n = read_chunk(&mut original, &mut a)?;
+    let m = read_chunk(&mut copied, &mut b)?;
+    if a[..n] != b[..m] {
+      return Ok(false);
+    }
+    if n == 0 {
+      return Ok(true);
+    }
+  }
+}
+
+/// Fill `buf` from `file`, short only at the end of the file.
+fn read_chunk(file: &mut std::fs::File, buf: &mut [u8]) -> io::Result<usize> {
+  use std::io::Read;
+
+  let mut filled = 0;
+  while filled < buf.len() {
+    match file.read(&mut buf[filled..])? {
+      0 => break,
+      n => filled += n,
+    }
+  }
+  Ok(filled)
+}
+
+fn copy_metadata(dest: &Path, meta: &std::fs::Metadata, source: &Path, options: &CopyOptions) -> io::Result<()> {
+  if meta.is_symlink() {
+    // Links have no mode of their own and rarely carry xattrs
+    if options.preserve_timestamps {
+      let atime = filetime::FileTime::from_last_access_time(meta);
+      let mtime = filetime::FileTime::from_last_modification_time(meta);
+      filetime::set_symlink_file_times(dest, atime, mtime)?;
+    }
+    return Ok(());
+  }
+  if options.preserve_xattrs {
+    for name in xattr::list(source).into_iter().flatten() {
+      if let Ok(Some(value)) = xattr::get(source, &name) {
+        let _ = xattr::set(dest, &name, &value);
+      }
+    }
+  }
+  let mode = meta.permissions().mode() & 0o7777;
+  let mode = if options.preserve_mode { mode } else { mode & 0o777 & !current_umask() };
+  std::fs::set_permissions(dest, std::fs::Permissions::from_mode(mode))?;
+  if options.preserve_timestamps {
+    let atime = filetime::FileTime::from_last_access_time(meta);
+    let mtime = filetime::FileTime::from_last_modification_time(meta);
+    filetime::set_file_times(dest, atime, mtime)?;
+  }
+  Ok(())
+}
+
+/// What a delete would remove, for the confirmation popup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemovalSummary {
+  /// Files and symlinks
+  pub files: usize,
+  /// Directories, not counting a single deleted directory itself
+  pub dirs: usize,
+  /// Bytes held by the files
+  pub bytes: u64,
+  /// Git repositories inside, the directory itself included
+  pub git_repos: usize,
+  /// The children of a single deleted directory, or the deleted entries,
+  /// with everything under them, biggest first
+  pub children: Vec<RemovalChild>,
+}
+
+/// One child of a directory being deleted, or one of several entries, with
+/// its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovalChild {
+  /// File name
+  pub name: String,
+  /// A directory, not followed if a symlink
+  pub is_dir: bool,
+  /// Files under it, or 1 for a file
+  pub files: usize,
+  /// Bytes under it
+  pub bytes: u64,
+}
+
+/// Tally everything deleting `targets` would remove: a single directory
+/// is listed by its children, several entries by themselves. Symlinks count
+/// as single files and are not followed, like the delete itself. None once
+/// `progress` is cancelled.
+pub fn summarize_removal(targets: &[PathBuf], progress: &Progress) -> Option<RemovalSummary> {
+  let is_dir = |path: &Path| path.symlink_metadata().is_ok_and(|m| m.is_dir());
+  let listed: Vec<PathBuf> = match targets {
+    [dir] if is_dir(dir) => std::fs::read_dir(dir).map(|rd| rd.flatten().map(|e| e.path()).collect()).unwrap_or_default(),
+    _ => targets.to_vec(),
+  };
+  let mut summary = RemovalSummary::default();
+  for path in listed {
+    let mut child = RemovalChild {
+      name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+      is_dir: is_dir(&path),
+      files: 0,
+      bytes: 0,
+    };
+    let flow = walk(&path, &mut |path, meta| {
+      if progress.is_cancelled() {
+        return ControlFlow::Break(());
+      }
+      if path.file_name().is_some_and(|name| name == ".git") {
+        summary.git_repos += 1;
+      }
+      if meta.is_dir() {
+        summary.dirs += 1;
+      } else {
+        child.files += 1;
+        child.bytes += meta.len();
+      }
+      ControlFlow::Continue(())
+    });
+    if flow.is_break() {
+      return None;
+    }
+    summary.files += child.files;
+    summary.bytes += child.bytes;
+    summary.children.push(child);
+  }
+  summary.children.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+  Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+  fn test_dir(prefix: &str) -> PathBuf {
+    let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+    let dir = std::env::temp_dir().join(format!("tfl_ops_{prefix}_{id}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_relative_path_climbs_out_where_paths_part() {
+    let rel = |from: &str, to: &str| relative_path(Path::new(from), Path::new(to));
+    assert_eq!(rel("/a/b", "/a/b/c.txt"), PathBuf::from("c.txt"));
+    assert_eq!(rel("/a/b", "/a/d/e"), PathBuf::from("../d/e"));
+    assert_eq!(rel("/a/b/c", "/x"), PathBuf::from("../../../x"));
+    assert_eq!(rel("/a/b", "/a"), PathBuf::from(".."));
+    assert_eq!(rel("/a", "/a"), PathBuf::from("."));
+  }
+
+  #[test]
+  fn test_unique_dest_path_no_conflict() {
+    let dir = test_dir("no_conflict");
+    let dest = dir.join("foo.txt");
+    assert_eq!(unique_dest_path(&dest), dest);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_unique_dest_path_with_extension() {
+    let dir = test_dir("with_ext");
+    let dest = dir.join("foo.txt");
+    fs::write(&dest, "").unwrap();
+    let result = unique_dest_path(&dest);
+    assert_eq!(result, dir.join("foo_copy.txt"));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_unique_dest_path_without_extension() {
+    let dir = test_dir("no_ext");
+    let dest = dir.join("foo");
+    fs::write(&dest, "").unwrap();
+    let result = unique_dest_path(&dest);
+    assert_eq!(result, dir.join("foo_copy"));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_unique_dest_path_incrementing() {
+    let dir = test_dir("incr");
+    let dest = dir.join("foo.txt");
+    fs::write(&dest, "").unwrap();
+    fs::write(dir.join("foo_copy.txt"), "").unwrap();
+    let result = unique_dest_path(&dest);
+    assert_eq!(result, dir.join("foo_copy2.txt"));
+
+    fs::write(dir.join("foo_copy2.txt"), "").unwrap();
+    let result = unique_dest_path(&dest);
+    assert_eq!(result, dir.join("foo_copy3.txt"));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_path_file() {
+    let dir = test_dir("copy_file");
+    let src = dir.join("src.txt");
+    let dst = dir.join("dst.txt");
+    fs::write(&src, "hello").unwrap();
+    copy_path(&src, &dst).unwrap();
+    assert_eq!(fs::read_to_string(&dst).unwrap(), "hello");
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_path_tracked_counts_and_stops_when_cancelled() {
+    let dir = test_dir("copy_tracked");
+    let src = dir.join("src_dir");
+    fs::create_dir_all(src.join("sub")).unwrap();
+    fs::write(src.join("a.txt"), "aaaa").unwrap();
+    fs::write(src.join("sub/b.txt"), "bb").unwrap();
+    let progress = Progress::default();
+    copy_path_tracked(&src, &dir.join("dst_dir"), &CopyOptions::default(), &progress).unwrap();
+    assert_eq!(progress.done(), crate::fs::progress::Counts { entries: 4, bytes: 6 });
+
+    progress.cancel();
+    let err = copy_path_tracked(&src, &dir.join("again"), &CopyOptions::default(), &progress).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+    assert!(!dir.join("again").exists());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_file_tracked_reports_bytes_by_chunk() {
+    let dir = test_dir("copy_file_tracked");
+    let src = dir.join("big.bin");
+    fs::write(&src, vec![7u8; (COPY_CHUNK * 2 + 5) as usize]).unwrap();
+    let progress = Progress::default();
+    copy_file_tracked(&src, &dir.join("copy.bin"), &progress).unwrap();
+    assert_eq!(progress.done().bytes, COPY_CHUNK * 2 + 5);
+    assert_eq!(fs::metadata(dir.join("copy.bin")).unwrap().len(), COPY_CHUNK * 2 + 5);
+
+    progress.cancel();
+    let err = copy_file_tracked(&src, &dir.join("again.bin"), &progress).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_dir_recursive() {
+    let dir = test_dir("copy_dir");
+    let src = dir.join("src_dir");
+    fs::create_dir_all(src.join("sub")).unwrap();
+    fs::write(src.join("a.txt"), "aaa").unwrap();
+    fs::write(src.join("sub").join("b.txt"), "bbb").unwrap();
+
+    let dst = dir.join("dst_dir");
+    copy_path(&src, &dst).unwrap();
+
+    assert!(dst.join("a.txt").exists());
+    assert!(dst.join("sub").join("b.txt").exists());
+    assert_eq!(fs::read_to_string(dst.join("a.txt")).unwrap(), "aaa");
+    assert_eq!(fs::read_to_string(dst.join("sub").join("b.txt")).unwrap(), "bbb");
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_path_sanitized_renames_children() {
+    let dir = test_dir("copy_sanitized");
+    let src = dir.join("src_dir");
+    fs::create_dir_all(src.join("12:00")).unwrap();
+    fs::write(src.join("12:00").join("a?.txt"), "a").unwrap();
+    fs::write(src.join("12:00").join("a*.txt"), "b").unwrap();
+    fs::write(src.join("ok.txt"), "ok").unwrap();
+
+    let dst = dir.join("dst_dir");
+    copy_path_sanitized(&src, &dst).unwrap();
+
+    assert!(dst.join("ok.txt").exists());
+    assert!(dst.join("12_00").join("a_.txt").exists());
+    assert!(dst.join("12_00").join("a__copy.txt").exists());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_dir_skips_symlink_cycle() {
+    let dir = test_dir("copy_cycle");
+    let src = dir.join("src_dir");
+    fs::create_dir_all(src.join("sub")).unwrap();
+    fs::write(src.join("sub").join("b.txt"), "bbb").unwrap();
+    std::os::unix::fs::symlink(&src, src.join("sub").join("loop")).unwrap();
+
+    let dst = dir.join("dst_dir");
+    assert_eq!(copy_path(&src, &dst).unwrap(), 1);
+    assert!(dst.join("sub").join("b.txt").exists());
+    assert!(!dst.join("sub").join("loop").exists());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_options_links_and_metadata() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = test_dir("copy_options");
+    let src = dir.join("src_dir");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("a.txt"), "aaa").unwrap();
+    fs::set_permissions(src.join("a.txt"), fs::Permissions::from_mode(0o640)).unwrap();
+    std::os::unix::fs::symlink("a.txt", src.join("link")).unwrap();
+    let old = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_times(src.join("a.txt"), old, old).unwrap();
+    // Not every filesystem takes user xattrs
+    let has_xattr = xattr::set(src.join("a.txt"), "user.tfl", b"1").is_ok();
+
+    // Plain copy: the link becomes a file, times are new
+    let plain = dir.join("plain");
+    copy_path(&src, &plain).unwrap();
+    assert!(!plain.join("link").symlink_metadata().unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(plain.join("link")).unwrap(), "aaa");
+    assert_eq!(fs::metadata(plain.join("a.txt")).unwrap().permissions().mode() & 0o777, 0o640);
+    let mtime = filetime::FileTime::from_last_modification_time(&fs::metadata(plain.join("a.txt")).unwrap());
+    assert_ne!(mtime, old);
+
+    // Archive copy: like cp -a
+    let archive = dir.join("archive");
+    copy_path_with(&src, &archive, &CopyOptions::archive()).unwrap();
+    assert_eq!(fs::read_link(archive.join("link")).unwrap(), PathBuf::from("a.txt"));
+    let meta = fs::metadata(archive.join("a.txt")).unwrap();
+    assert_eq!(filetime::FileTime::from_last_modification_time(&meta), old);
+    assert_eq!(meta.permissions().mode() & 0o777, 0o640);
+    if has_xattr {
+      assert_eq!(xattr::get(archive.join("a.txt"), "user.tfl").unwrap().as_deref(), Some(&b"1"[..]));
+    }
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_file_keeps_holes() {
+    use std::io::{Seek, SeekFrom, Write};
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = test_dir("copy_sparse");
+    let src = dir.join("disk.img");
+    let mut file = fs::File::create(&src).unwrap();
+    file.set_len(8 << 20).unwrap();
+    file.seek(SeekFrom::Start(4 << 20)).unwrap();
+    file.write_all(b"data in the middle").unwrap();
+    drop(file);
+
+    let dst = dir.join("copy.img");
+    copy_file(&src, &dst).unwrap();
+    let copied = fs::read(&dst).unwrap();
+    assert_eq!(copied.len(), 8 << 20);
+    assert_eq!(&copied[4 << 20..(4 << 20) + 18], b"data in the middle");
+    assert!(copied[..4 << 20].iter().all(|&b| b == 0));
+    // Where the filesystem made the source sparse, the copy is too
+    let (src_meta, dst_meta) = (fs::metadata(&src).unwrap(), fs::metadata(&dst).unwrap());
+    if src_meta.blocks() * 512 < src_meta.len() {
+      assert!(dst_meta.blocks() * 512 < dst_meta.len());
+    }
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_copy_file_keeps_read_only_mode_after_the_data() {
+    let dir = test_dir("copy_read_only");
+    let src = dir.join("locked.txt");
+    fs::write(&src, "contents").unwrap();
+    fs::set_permissions(&src, fs::Permissions::from_mode(0o444)).unwrap();
+    let dst = dir.join("copy.txt");
+    copy_file(&src, &dst).unwrap();
+    assert_eq!(fs::read_to_string(&dst).unwrap(), "contents");
+    assert_eq!(fs::metadata(&dst).unwrap().permissions().mode() & 0o777, 0o444);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_verify_copy() {
+    let dir = test_dir("verify");
+    fs::write(dir.join("a.bin"), vec![7u8; 3 << 20]).unwrap();
+    let options = CopyOptions { verify: true, ..CopyOptions::default() };
+    copy_path_with(&dir.join("a.bin"), &dir.join("b.bin"), &options).unwrap();
+    assert!(verify_copy(&dir.join("a.bin"), &dir.join("b.bin")).unwrap());
+
+    // Same size, one byte off past the first chunk
+    let mut bytes = vec![7u8; 3 << 20];
+    bytes[(2 << 20) + 5] = 8;
+    fs::write(dir.join("c.bin"), &bytes).unwrap();
+    assert!(!verify_copy(&dir.join("a.bin"), &dir.join("c.bin")).unwrap());
+    fs::write(dir.join("d.bin"), b"short").unwrap();
+    assert!(!verify_copy(&dir.join("a.bin"), &dir.join("d.bin")).unwrap());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_default_mode_applies_umask() {
+    assert_eq!(default_mode(false, 0o022), 0o644);
+    assert_eq!(default_mode(true, 0o022), 0o755);
+    assert_eq!(default_mode(false, 0o077), 0o600);
+    assert_eq!(with_execute(0o644), 0o755);
+    assert_eq!(with_execute(0o600), 0o700);
+    assert!(current_umask() <= 0o777);
+  }
+
+  #[test]
+  fn test_summarize_removal() {
+    let dir = test_dir("removal");
+    fs::create_dir_all(dir.join("repo/.git")).unwrap();
+    fs::write(dir.join("repo/.git/HEAD"), "ref").unwrap();
+    fs::write(dir.join("repo/big.bin"), vec![0u8; 1000]).unwrap();
+    fs::write(dir.join("small.txt"), "hi").unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+
+    let progress = Progress::default();
+    let summary = summarize_removal(std::slice::from_ref(&dir), &progress).unwrap();
+    // HEAD, big.bin, small.txt and the unfollowed symlink
+    assert_eq!(summary.files, 4);
+    assert_eq!(summary.dirs, 2);
+    assert_eq!(summary.git_repos, 1);
+    assert_eq!(summary.children[0].name, "repo");
+    assert_eq!((summary.children[0].files, summary.children[0].bytes), (2, 1003));
+    assert_eq!(summary.children.len(), 3);
+
+    // Several entries are listed by themselves
+    let both = summarize_removal(&[dir.join("repo"), dir.join("small.txt")], &Progress::default()).unwrap();
+    assert_eq!((both.files, both.dirs, both.bytes), (3, 2, 1005));
+    let names: Vec<&str> = both.children.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["repo", "small.txt"]);
+
+    progress.cancel();
+    assert_eq!(summarize_removal(std::slice::from_ref(&dir), &progress), None);
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,173 @@
+//! How far a long copy or extraction got, shared between the thread doing
+//! it and the UI watching it, and a flag that asks it to stop.
+
+use std::io::{self, Read};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::fs::walk::walk;
+
+/// Entries and bytes, done or in total.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+  /// Files and directories
+  pub entries: u64,
+  /// Bytes of file content
+  pub bytes: u64,
+}
+
+/// Progress of a long file operation, shared between the worker doing it
+/// and the UI showing it.
+#[derive(Debug, Default)]
+pub struct Progress {
+  entries: AtomicU64,
+  bytes: AtomicU64,
+  total_entries: AtomicU64,
+  /// Zero until the total is known
+  total_bytes: AtomicU64,
+  cancelled: AtomicBool,
+}
+
+impl Progress {
+  /// Count `entries` more entries and `bytes` more bytes as done.
+  pub fn add(&self, entries: u64, bytes: u64) {
+    self.entries.fetch_add(entries, Ordering::Relaxed);
+    self.bytes.fetch_add(bytes, Ordering::Relaxed);
+  }
+
+  /// Set the amount of work expected once counted.
+  pub fn set_total(&self, total: Counts) {
+    self.total_entries.store(total.entries, Ordering::Relaxed);
+    self.total_bytes.store(total.bytes, Ordering::Relaxed);
+  }
+
+  /// The work done so far.
+  pub fn done(&self) -> Counts {
+    Counts { entries: self.entries.load(Ordering::Relaxed), bytes: self.bytes.load(Ordering::Relaxed) }
+  }
+
+  /// The work expected, zero until set.
+  pub fn total(&self) -> Counts {
+    Counts { entries: self.total_entries.load(Ordering::Relaxed), bytes: self.total_bytes.load(Ordering::Relaxed) }
+  }
+
+  /// Share of the bytes done, 0 to 100, or None while the total is unknown.
+  pub fn percent(&self) -> Option<u8> {
+    let total = self.total().bytes;
+    (total > 0).then(|| (self.done().bytes.min(total) * 100 / total) as u8)
+  }
+
+  /// Ask the work to stop at the next entry.
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::Relaxed);
+  }
+
+  /// Whether `cancel` was called.
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::Relaxed)
+  }
+
+  /// An `Interrupted` error once cancelled, for `?` between entries.
+  pub fn check(&self) -> io::Result<()> {
+    if self.is_cancelled() {
+      return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+    }
+    Ok(())
+  }
+}
+
+/// Entries and bytes under `paths`, symlinks not followed, for the total of
+/// a copy. Stops early, with what it counted, once `progress` is cancelled.
+pub fn measure(paths: &[impl AsRef<Path>], progress: &Progress) -> Counts {
+  count(paths, &|_| progress.is_cancelled())
+}
+
+/// Whether `paths` hold more entries or bytes than `limit`. Stops counting
+/// as soon as they do.
+pub fn exceeds(paths: &[impl AsRef<Path>], limit: Counts) -> bool {
+  let over = |c: &Counts| c.entries > limit.entries || c.bytes > limit.bytes;
+  over(&count(paths, &over))
+}
+
+/// Entries and bytes under `paths`, symlinks counted as their own size and
+/// directories as none, like `du --apparent-size`.
+pub fn total(paths: &[impl AsRef<Path>]) -> Counts {
+  count(paths, &|_| false)
+}
+
+/// Entries and bytes under `paths` until `stop` says enough.
+fn count(paths: &[impl AsRef<Path>], stop: &dyn Fn(&Counts) -> bool) -> Counts {
+  let mut counts = Counts::default();
+  for path in paths {
+    let flow = walk(path.as_ref(), &mut |_, meta| {
+      if stop(&counts) {
+        return ControlFlow::Break(());
+      }
+      counts.entries += 1;
+      if !meta.is_dir() {
+        counts.bytes += meta.len();
+      }
+      ControlFlow::Continue(())
+    });
+    if flow.is_break() {
+      break;
+    }
+  }
+  counts
+}
+
+/// Counts the bytes read through it as done, for work whose total is the
+/// size of its input, such as unpacking a compressed tarball.
+pub struct CountingReader<'a, R> {
+  inner: R,
+  progress: &'a Progress,
+}
+
+impl<'a, R: Read> CountingReader<'a, R> {
+  /// Wrap `inner`, counting into `progress`.
+  pub fn new(inner: R, progress: &'a Progress) -> Self {
+    Self { inner, progress }
+  }
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let n = self.inner.read(buf)?;
+    self.progress.add(0, n as u64);
+    Ok(n)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_progress_counts_and_cancels() {
+    let dir = std::env::temp_dir().join(format!("tfl_test_progress_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("a")).unwrap();
+    std::fs::write(dir.join("a/one"), [0; 300]).unwrap();
+    std::fs::write(dir.join("two"), [0; 100]).unwrap();
+
+    let progress = Progress::default();
+    let total = measure(&[dir.join("a"), dir.join("two")], &progress);
+    assert_eq!(total, Counts { entries: 3, bytes: 400 });
+    assert!(exceeds(&[dir.join("a"), dir.join("two")], Counts { entries: 10, bytes: 399 }));
+    assert!(!exceeds(&[dir.join("a"), dir.join("two")], Counts { entries: 3, bytes: 400 }));
+    assert_eq!(progress.percent(), None);
+    progress.set_total(total);
+    progress.add(2, 300);
+    assert_eq!(progress.percent(), Some(75));
+
+    let mut read = Vec::new();
+    CountingReader::new(&[0u8; 50][..], &progress).read_to_end(&mut read).unwrap();
+    assert_eq!(progress.done(), Counts { entries: 2, bytes: 350 });
+
+    assert!(progress.check().is_ok());
+    progress.cancel();
+    assert_eq!(progress.check().unwrap_err().kind(), io::ErrorKind::Interrupted);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
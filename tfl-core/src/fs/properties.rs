@@ -1,28 +1,54 @@
+//! Permissions, ownership, times and sizes for the properties view.
+
+use std::collections::HashMap;
 use std::fs::{self, Metadata};
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::SystemTime;
 
+use crate::preview::special::SpecialFile;
+
 /// File properties for display in the properties panel
 #[derive(Debug, Clone)]
 pub struct FileProperties {
+  /// Full path
   pub path: String,
+  /// Size in bytes, 0 for directories
   pub size: u64,
+  /// Size with a unit, e.g. `4.2 KB`
   pub size_human: String,
+  /// Mode bits in octal, e.g. `0644`
   pub permissions_octal: String,
+  /// Mode bits as `-rw-r--r--`
   pub permissions_rwx: String,
+  /// Owner's user name, or uid when unknown
   pub owner: String,
+  /// Group name, or gid when unknown
   pub group: String,
+  /// Birth time, where the filesystem records one
   pub created: Option<String>,
+  /// Last content change
   pub modified: Option<String>,
+  /// Last read
   pub accessed: Option<String>,
+  /// Kind of entry, e.g. `Directory` or `Rust source`
   pub file_type: String,
+  /// Sniffed from the content of regular files
   pub mime_type: Option<String>,
+  /// Where the symlink points
   pub symlink_target: Option<String>,
+  /// A directory, following symlinks
   pub is_dir: bool,
+  /// SELinux label, when the filesystem carries one
+  pub security_context: Option<String>,
+  /// The policy's label for this path; filled in by the caller since it
+  /// takes a `matchpathcon` run
+  pub default_context: Option<String>,
 }
 
 impl FileProperties {
+  /// Read the properties of `path`, or None if it can't be stat'ed.
   pub fn from_path(path: &Path) -> Option<Self> {
     let symlink_meta = fs::symlink_metadata(path).ok()?;
     let is_symlink = symlink_meta.is_symlink();
@@ -51,7 +77,8 @@ impl FileProperties {
     let accessed = meta.accessed().ok().and_then(format_time);
 
     let file_type = determine_file_type(path, &meta, is_symlink);
-    let mime_type = if !is_dir {
+    // Only regular files: sniffing a FIFO or a device can block
+    let mime_type = if meta.is_file() {
       infer::get_from_path(path).ok().flatten().map(|t| t.mime_type().to_string())
     } else {
       None
@@ -72,6 +99,66 @@ impl FileProperties {
       mime_type,
       symlink_target,
       is_dir,
+      security_context: super::selinux::context(path),
+      default_context: None,
+    })
+  }
+}
+
+/// Aggregate properties of several marked entries
+#[derive(Debug, Clone)]
+pub struct MultiProperties {
+  /// Marked files
+  pub file_count: usize,
+  /// Marked directories
+  pub dir_count: usize,
+  /// Sum of the marked files' sizes; directory contents aren't walked
+  pub total_size: u64,
+  /// `total_size` with a unit
+  pub size_human: String,
+  /// rwx string with `?` where the entries disagree
+  pub permissions_rwx: String,
+  /// Octal mode when every entry has the same permissions
+  pub permissions_octal: Option<String>,
+  /// Owner when shared by every entry
+  pub owner: Option<String>,
+  /// Group when shared by every entry
+  pub group: Option<String>,
+}
+
+impl MultiProperties {
+  /// Combine the properties of `paths`, skipping ones that can't be read. None
+  /// when none can.
+  pub fn from_paths(paths: &[PathBuf]) -> Option<Self> {
+    let metas: Vec<Metadata> = paths.iter().filter_map(|p| fs::metadata(p).ok()).collect();
+    let first = metas.first()?;
+    let dir_count = metas.iter().filter(|m| m.is_dir()).count();
+    let total_size = metas.iter().filter(|m| !m.is_dir()).map(|m| m.len()).sum();
+
+    // Bits set in every entry, and bits set in at least one
+    let all = metas.iter().fold(0o7777, |acc, m| acc & m.permissions().mode());
+    let any = metas.iter().fold(0, |acc, m| acc | (m.permissions().mode() & 0o7777));
+    let mixed = all ^ any;
+    let permissions_rwx: String = format_rwx(0o100000 | all)
+      .chars()
+      .skip(1)
+      .enumerate()
+      .map(|(i, c)| if mixed & (0o400 >> i) != 0 { '?' } else { c })
+      .collect();
+    let permissions_octal = (mixed == 0).then(|| format!("{all:04o}"));
+
+    let owner = metas.iter().all(|m| m.uid() == first.uid()).then(|| resolve_user(first.uid()));
+    let group = metas.iter().all(|m| m.gid() == first.gid()).then(|| resolve_group(first.gid()));
+
+    Some(MultiProperties {
+      file_count: metas.len() - dir_count,
+      dir_count,
+      total_size,
+      size_human: format_size(total_size),
+      permissions_rwx,
+      permissions_octal,
+      owner,
+      group,
     })
   }
 }
@@ -95,7 +182,9 @@ fn format_size(bytes: u64) -> String {
   }
 }
 
-fn format_rwx(mode: u32) -> String {
+/// Mode bits as `drwxr-xr-x`: the entry type, then the permissions with
+/// setuid, setgid and the sticky bit.
+pub fn format_rwx(mode: u32) -> String {
   let mut result = String::with_capacity(10);
 
   // File type indicator
@@ -151,6 +240,24 @@ fn resolve_user(uid: u32) -> String {
     .unwrap_or_else(|| uid.to_string())
 }
 
+/// User name for `uid`, cached since the tree looks it up on every frame.
+pub fn user_name(uid: u32) -> String {
+  static NAMES: Mutex<Option<HashMap<u32, String>>> = Mutex::new(None);
+  let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+  names.get_or_insert_with(HashMap::new).entry(uid).or_insert_with(|| resolve_user(uid)).clone()
+}
+
+/// Name of the user tfl runs as.
+pub fn current_user_name() -> String {
+  user_name(users::get_current_uid())
+}
+
+/// Owned by the user tfl runs as and not writable by group or others, so
+/// nobody else could have put commands in it.
+pub fn is_private(meta: &Metadata) -> bool {
+  meta.uid() == users::get_current_uid() && meta.mode() & 0o022 == 0
+}
+
 fn resolve_group(gid: u32) -> String {
   users::get_group_by_gid(gid)
     .map(|g| g.name().to_string_lossy().to_string())
@@ -221,6 +328,10 @@ fn determine_file_type(path: &Path, meta: &Metadata, is_symlink: bool) -> String
     return "Directory".to_string();
   }
 
+  if let Some(kind) = SpecialFile::from_metadata(meta) {
+    return kind.label().to_string();
+  }
+
   // Try to determine from extension first
   if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
     let ext_lower = ext.to_lowercase();
@@ -348,6 +459,31 @@ mod tests {
     let _ = fs::remove_dir_all(dir);
   }
 
+  #[test]
+  fn test_multi_properties_aggregate() {
+    let dir = setup_test_dir();
+    let a = dir.join("a.sh");
+    let b = dir.join("b.txt");
+    fs::write(&a, "12345").unwrap();
+    fs::write(&b, "123").unwrap();
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::set_permissions(&a, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::set_permissions(&b, fs::Permissions::from_mode(0o644)).unwrap();
+
+    let multi = MultiProperties::from_paths(&[a.clone(), b.clone(), dir.join("sub")]).unwrap();
+    assert_eq!(multi.file_count, 2);
+    assert_eq!(multi.dir_count, 1);
+    assert_eq!(multi.total_size, 8);
+    assert!(multi.owner.is_some());
+
+    let files = MultiProperties::from_paths(&[a.clone(), b]).unwrap();
+    assert_eq!(files.permissions_rwx, "rw?r-?r-?");
+    assert_eq!(files.permissions_octal, None);
+    let same = MultiProperties::from_paths(&[a.clone(), a]).unwrap();
+    assert_eq!(same.permissions_octal.as_deref(), Some("0755"));
+    cleanup_test_dir(&dir);
+  }
+
   #[test]
   fn test_format_size_bytes() {
     assert_eq!(format_size(0), "0 B");
@@ -0,0 +1,124 @@
+//! SELinux security contexts: reading an entry's label, asking the policy
+//! what it should be, and restoring it.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::process::{ProcessRunner, ProcessSpec};
+
+/// Whether the kernel has SELinux enabled (enforcing or permissive).
+pub fn enabled() -> bool {
+  Path::new("/sys/fs/selinux/enforce").exists()
+}
+
+/// The label on `path` itself, e.g. `unconfined_u:object_r:user_home_t:s0`.
+pub fn context(path: &Path) -> Option<String> {
+  let value = xattr::get(path, "security.selinux").ok()??;
+  let text = String::from_utf8_lossy(&value);
+  let text = text.trim_end_matches('\0').trim();
+  (!text.is_empty()).then(|| text.to_string())
+}
+
+/// The type field of a context (`user_home_t` above).
+pub fn context_type(context: &str) -> Option<&str> {
+  context.split(':').nth(2)
+}
+
+/// Whether `actual` needs relabeling to match `expected`. Like restorecon
+/// without `-F`, only the types are compared.
+pub fn differs(actual: &str, expected: &str) -> bool {
+  context_type(actual) != context_type(expected)
+}
+
+/// The policy's default context for each of `paths`, from `matchpathcon`.
+/// Paths the policy has no entry for are left out.
+pub fn default_contexts(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> HashMap<PathBuf, String> {
+  if paths.is_empty() {
+    return HashMap::new();
+  }
+  let spec = ProcessSpec::new("matchpathcon").args(paths);
+  let Ok((_, output)) = runner.output(&spec) else {
+    return HashMap::new();
+  };
+  // One `path<TAB>context` line per path
+  output
+    .lines()
+    .filter_map(|line| {
+      let (path, context) = line.trim_end().rsplit_once(char::is_whitespace)?;
+      let context = context.trim();
+      (context.contains(':') && context != "<<none>>").then(|| (PathBuf::from(path.trim_end()), context.to_string()))
+    })
+    .collect()
+}
+
+/// Those of `paths` whose label differs from the policy default, reading
+/// labels with `context_of`.
+pub fn mislabeled_with(
+  runner: &dyn ProcessRunner,
+  paths: &[PathBuf],
+  context_of: impl Fn(&Path) -> Option<String>,
+) -> HashSet<PathBuf> {
+  default_contexts(runner, paths)
+    .into_iter()
+    .filter(|(path, expected)| context_of(path).is_some_and(|actual| differs(&actual, expected)))
+    .map(|(path, _)| path)
+    .collect()
+}
+
+/// Those of `paths` whose label differs from the policy default.
+pub fn mislabeled(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> HashSet<PathBuf> {
+  mislabeled_with(runner, paths, context)
+}
+
+/// `restorecon` for `paths`, descending into directories when `recursive`.
+/// Each changed label is printed as a `Relabeled ...` line.
+pub fn restorecon(paths: &[PathBuf], recursive: bool) -> ProcessSpec {
+  ProcessSpec::new("restorecon").arg(if recursive { "-Rv" } else { "-v" }).arg("--").args(paths)
+}
+
+/// How many labels a `restorecon -v` run reports changing.
+pub fn relabeled_count(output: &str) -> usize {
+  output.lines().filter(|l| l.trim_start().starts_with("Relabeled")).count()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::process::MockRunner;
+
+  #[test]
+  fn test_mislabeled_compares_types_with_matchpathcon() {
+    let mut runner = MockRunner::with_installed(&["matchpathcon"]);
+    runner.outputs.insert(
+      "matchpathcon".to_string(),
+      "/srv/www/index.html\tsystem_u:object_r:httpd_sys_content_t:s0\n\
+       /srv/www/moved.html\tsystem_u:object_r:httpd_sys_content_t:s0\n\
+       /srv/www/odd\t<<none>>\n"
+        .to_string(),
+    );
+    let paths: Vec<PathBuf> =
+      ["/srv/www/index.html", "/srv/www/moved.html", "/srv/www/odd"].iter().map(PathBuf::from).collect();
+
+    let defaults = default_contexts(&runner, &paths);
+    assert_eq!(defaults.len(), 2);
+    assert_eq!(context_type(&defaults[Path::new("/srv/www/index.html")]), Some("httpd_sys_content_t"));
+
+    let labels = |path: &Path| {
+      Some(match path.file_name()?.to_str()? {
+        // Only the user differs, which restorecon leaves alone
+        "index.html" => "unconfined_u:object_r:httpd_sys_content_t:s0".to_string(),
+        _ => "unconfined_u:object_r:user_home_t:s0".to_string(),
+      })
+    };
+    let wrong = mislabeled_with(&runner, &paths, labels);
+    assert_eq!(wrong, HashSet::from([PathBuf::from("/srv/www/moved.html")]));
+    assert_eq!(runner.commands()[0], "matchpathcon /srv/www/index.html /srv/www/moved.html /srv/www/odd");
+
+    let spec = restorecon(&paths[1..2], false);
+    assert_eq!(spec.to_string(), "restorecon -v -- /srv/www/moved.html");
+    assert_eq!(restorecon(&paths[..1], true).args[0], "-Rv");
+    let output = "Relabeled /srv/www/moved.html from unconfined_u:object_r:user_home_t:s0 to \
+                  unconfined_u:object_r:httpd_sys_content_t:s0\n";
+    assert_eq!(relabeled_count(output), 1);
+  }
+}
@@ -0,0 +1,351 @@
+//! `TreeSource`: where a `FileTree` gets its entries and file contents from.
+//!
+//! The tree itself only sorts, filters, expands and collapses; listing a
+//! directory, looking up a single path and reading a file go through the
+//! source, so archives, remote hosts or a filtered git view can back the same
+//! tree as the local filesystem.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::entry::FileEntry;
+use crate::preview::archive;
+
+/// Where the file tree reads its entries from: the local filesystem, an
+/// archive, or files held in memory.
+pub trait TreeSource: Debug + Send + Sync {
+  /// Entries directly inside `dir`, at `depth` in the tree. Order and
+  /// hidden-file filtering are left to the tree.
+  fn children(&self, dir: &Path, depth: usize) -> io::Result<Vec<FileEntry>>;
+
+  /// The entry for `path` itself.
+  fn stat(&self, path: &Path, depth: usize) -> io::Result<FileEntry>;
+
+  /// Contents of the file at `path`.
+  fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+  /// Whether `path` is a directory that can be listed.
+  fn is_dir(&self, path: &Path) -> bool {
+    self.stat(path, 0).is_ok_and(|e| e.is_dir)
+  }
+
+  /// Whether paths are real files a git repository can be discovered for.
+  fn supports_git(&self) -> bool {
+    false
+  }
+
+  /// Whether paths are on the local filesystem, so they can be watched,
+  /// walked and handed to other programs.
+  fn is_local(&self) -> bool {
+    false
+  }
+}
+
+/// The local filesystem.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalSource;
+
+impl TreeSource for LocalSource {
+  fn children(&self, dir: &Path, depth: usize) -> io::Result<Vec<FileEntry>> {
+    Ok(
+      std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| FileEntry::from_dir_entry(entry, depth))
+        .collect(),
+    )
+  }
+
+  fn stat(&self, path: &Path, depth: usize) -> io::Result<FileEntry> {
+    path.symlink_metadata()?;
+    Ok(FileEntry::from_path(path.to_path_buf(), depth))
+  }
+
+  fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+    std::fs::read(path)
+  }
+
+  fn is_dir(&self, path: &Path) -> bool {
+    path.is_dir()
+  }
+
+  fn supports_git(&self) -> bool {
+    true
+  }
+
+  fn is_local(&self) -> bool {
+    true
+  }
+}
+
+/// A fixed set of files held in memory. Directories are implied by the
+/// paths of the files inside them.
+#[derive(Debug, Default, Clone)]
+pub struct MemorySource {
+  files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemorySource {
+  /// An empty source.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add a file, with its parent directories implied.
+  pub fn with_file(mut self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> Self {
+    self.files.insert(path.into(), contents.into());
+    self
+  }
+
+  fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))
+  }
+}
+
+impl TreeSource for MemorySource {
+  fn children(&self, dir: &Path, depth: usize) -> io::Result<Vec<FileEntry>> {
+    if !self.is_dir(dir) {
+      return Err(Self::not_found(dir));
+    }
+    let mut children: BTreeMap<PathBuf, FileEntry> = BTreeMap::new();
+    for (path, contents) in &self.files {
+      let Ok(rest) = path.strip_prefix(dir) else {
+        continue;
+      };
+      let Some(first) = rest.components().next() else {
+        continue;
+      };
+      let child = dir.join(first);
+      let is_dir = child != *path;
+      children
+        .entry(child.clone())
+        .or_insert_with(|| FileEntry::new(child, depth, is_dir, contents.len() as u64));
+    }
+    Ok(children.into_values().collect())
+  }
+
+  fn stat(&self, path: &Path, depth: usize) -> io::Result<FileEntry> {
+    if let Some(contents) = self.files.get(path) {
+      return Ok(FileEntry::new(path.to_path_buf(), depth, false, contents.len() as u64));
+    }
+    if self.is_dir(path) {
+      return Ok(FileEntry::new(path.to_path_buf(), depth, true, 0));
+    }
+    Err(Self::not_found(path))
+  }
+
+  fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+    self.files.get(path).cloned().ok_or_else(|| Self::not_found(path))
+  }
+
+  fn is_dir(&self, path: &Path) -> bool {
+    self.files.keys().any(|p| p != path && p.starts_with(path))
+  }
+}
+
+/// The inside of a ZIP or tar archive, rooted at the archive's own path:
+/// `pkg.tar.gz/src/main.rs` is `src/main.rs` in `pkg.tar.gz`. Entries are
+/// listed once when opened and indexed by directory; files are read out of
+/// the archive on demand.
+#[derive(Debug, Clone)]
+pub struct ArchiveSource {
+  archive: PathBuf,
+  /// Size and whether it is a directory of the entries directly inside
+  /// each directory, including ones only implied by the paths of their files
+  dirs: HashMap<PathBuf, BTreeMap<PathBuf, (u64, bool)>>,
+}
+
+impl ArchiveSource {
+  /// List `archive` in full. Blocks for as long as that takes.
+  pub fn open(archive: &Path) -> io::Result<Self> {
+    let listing = archive::list_archive(archive, usize::MAX).map_err(io::Error::other)?;
+    Ok(Self::from_listing(archive, listing))
+  }
+
+  /// A source over an archive listed in full elsewhere, e.g. on a worker.
+  pub fn from_listing(archive: &Path, listing: archive::ArchiveListing) -> Self {
+    let mut dirs: HashMap<PathBuf, BTreeMap<PathBuf, (u64, bool)>> = HashMap::new();
+    dirs.insert(archive.to_path_buf(), BTreeMap::new());
+    for entry in listing.entries {
+      let name = archive::clean_entry_name(&entry.path);
+      if name.is_empty() {
+        continue;
+      }
+      let path = archive.join(name);
+      if entry.is_dir {
+        dirs.entry(path.clone()).or_default();
+      }
+      let mut child = path.as_path();
+      while let Some(parent) = child.parent().filter(|p| p.starts_with(archive)) {
+        let siblings = dirs.entry(parent.to_path_buf()).or_default();
+        if child == path {
+          let known = siblings.entry(path.clone()).or_insert((entry.size, entry.is_dir));
+          known.0 = entry.size;
+          known.1 |= entry.is_dir;
+        } else {
+          // Directories without an entry of their own are implied by their files
+          siblings.entry(child.to_path_buf()).or_insert((0, true)).1 = true;
+        }
+        child = parent;
+      }
+    }
+    Self { archive: archive.to_path_buf(), dirs }
+  }
+
+  fn entry(&self, path: &Path) -> Option<(u64, bool)> {
+    self.dirs.get(path.parent()?)?.get(path).copied()
+  }
+
+  /// Path of the archive itself.
+  pub fn archive(&self) -> &Path {
+    &self.archive
+  }
+
+  /// `path` as an entry name in the archive, None for the archive itself.
+  pub fn entry_name(&self, path: &Path) -> Option<String> {
+    let rest = path.strip_prefix(&self.archive).ok()?;
+    (!rest.as_os_str().is_empty()).then(|| rest.to_string_lossy().to_string())
+  }
+
+  fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))
+  }
+}
+
+impl TreeSource for ArchiveSource {
+  fn children(&self, dir: &Path, depth: usize) -> io::Result<Vec<FileEntry>> {
+    let children = self.dirs.get(dir).ok_or_else(|| Self::not_found(dir))?;
+    Ok(children.iter().map(|(path, &(size, is_dir))| FileEntry::new(path.clone(), depth, is_dir, size)).collect())
+  }
+
+  fn stat(&self, path: &Path, depth: usize) -> io::Result<FileEntry> {
+    if let Some((size, is_dir)) = self.entry(path) {
+      return Ok(FileEntry::new(path.to_path_buf(), depth, is_dir, size));
+    }
+    if self.is_dir(path) {
+      return Ok(FileEntry::new(path.to_path_buf(), depth, true, 0));
+    }
+    Err(Self::not_found(path))
+  }
+
+  fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+    match (self.entry(path), self.entry_name(path)) {
+      (Some((_, false)), Some(name)) => archive::read_archive_entry(&self.archive, &name).map_err(io::Error::other),
+      _ => Err(Self::not_found(path)),
+    }
+  }
+
+  fn is_dir(&self, path: &Path) -> bool {
+    self.dirs.contains_key(path)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sample() -> MemorySource {
+    MemorySource::new()
+      .with_file("/virt/README.md", "# hi")
+      .with_file("/virt/src/main.rs", "fn main() {}")
+      .with_file("/virt/src/lib.rs", "")
+  }
+
+  #[test]
+  fn test_memory_children_lists_files_and_implied_dirs() {
+    let source = sample();
+    let children = source.children(Path::new("/virt"), 0).unwrap();
+    let names: Vec<_> = children.iter().map(|e| (e.name.as_str(), e.is_dir)).collect();
+    assert_eq!(names, vec![("README.md", false), ("src", true)]);
+    assert_eq!(children[0].size, 4);
+    assert_eq!(source.children(Path::new("/virt/src"), 1).unwrap().len(), 2);
+  }
+
+  #[test]
+  fn test_memory_stat_and_read() {
+    let source = sample();
+    assert!(source.is_dir(Path::new("/virt/src")));
+    assert!(!source.is_dir(Path::new("/virt/src/main.rs")));
+    assert_eq!(source.stat(Path::new("/virt/src/main.rs"), 0).unwrap().size, 12);
+    assert_eq!(source.read(Path::new("/virt/README.md")).unwrap(), b"# hi");
+    assert_eq!(source.read(Path::new("/virt/missing")).unwrap_err().kind(), io::ErrorKind::NotFound);
+    assert!(source.children(Path::new("/elsewhere"), 0).is_err());
+  }
+
+  #[test]
+  fn test_archive_source_lists_and_reads_entries() {
+    let dir = std::env::temp_dir().join(format!("tfl_test_archive_source_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("pkg/src")).unwrap();
+    std::fs::write(dir.join("pkg/README"), "read me").unwrap();
+    std::fs::write(dir.join("pkg/src/lib.rs"), "pub fn f() {}").unwrap();
+    let zip = dir.join("pkg.zip");
+    archive::compress_to_archive(&[dir.join("pkg")], &zip, "zip").unwrap();
+
+    let source = ArchiveSource::open(&zip).unwrap();
+    let top = source.children(&zip, 0).unwrap();
+    assert_eq!(top.len(), 1);
+    assert!(top[0].is_dir && top[0].name == "pkg");
+    let names: Vec<_> = source.children(&zip.join("pkg"), 1).unwrap().into_iter().map(|e| (e.name, e.is_dir)).collect();
+    assert_eq!(names, vec![("README".to_string(), false), ("src".to_string(), true)]);
+    assert_eq!(source.stat(&zip.join("pkg/src/lib.rs"), 2).unwrap().size, 13);
+    assert_eq!(source.read(&zip.join("pkg/README")).unwrap(), b"read me");
+    assert!(source.read(&zip.join("pkg/src")).is_err());
+    assert_eq!(source.entry_name(&zip.join("pkg/src")).as_deref(), Some("pkg/src"));
+    assert!(source.entry_name(&zip).is_none());
+    assert!(!source.supports_git());
+    assert!(!source.is_local());
+
+    let out = dir.join("out");
+    std::fs::create_dir_all(&out).unwrap();
+    let created = archive::extract_archive_entry(&zip, "pkg/src", &out).unwrap();
+    assert_eq!(created, out.join("src"));
+    assert_eq!(std::fs::read_to_string(out.join("src/lib.rs")).unwrap(), "pub fn f() {}");
+    // Taken names get another
+    let again = archive::extract_archive_entry(&zip, "pkg/README", &out).unwrap();
+    assert_eq!(std::fs::read_to_string(&again).unwrap(), "read me");
+    assert_ne!(archive::extract_archive_entry(&zip, "pkg/README", &out).unwrap(), again);
+    assert!(archive::extract_archive_entry(&zip, "pkg/missing", &out).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_archive_source_implies_directories_from_file_paths() {
+    let entry = |path: &str, size, is_dir| archive::ArchiveEntry { path: path.to_string(), size, is_dir };
+    let listing = archive::ArchiveListing {
+      entries: vec![entry("a/b/c.txt", 3, false), entry("./empty/", 0, true), entry("top.txt", 7, false)],
+      total_entries: 3,
+      total_size: 10,
+    };
+    let root = Path::new("/x/pkg.tar");
+    let source = ArchiveSource::from_listing(root, listing);
+    let top: Vec<_> = source.children(root, 0).unwrap().into_iter().map(|e| (e.name, e.is_dir)).collect();
+    assert_eq!(top, vec![("a".to_string(), true), ("empty".to_string(), true), ("top.txt".to_string(), false)]);
+    assert!(source.is_dir(&root.join("a/b")));
+    assert!(source.children(&root.join("empty"), 1).unwrap().is_empty());
+    assert_eq!(source.stat(&root.join("a/b/c.txt"), 2).unwrap().size, 3);
+    assert!(!source.is_dir(&root.join("top.txt")));
+    assert!(source.children(&root.join("missing"), 1).is_err());
+  }
+
+  #[test]
+  fn test_local_source_matches_read_dir() {
+    let dir = std::env::temp_dir().join("tfl_test_local_source");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("a.txt"), "abc").unwrap();
+
+    let source = LocalSource;
+    let mut names: Vec<_> = source.children(&dir, 0).unwrap().into_iter().map(|e| e.name).collect();
+    names.sort();
+    assert_eq!(names, vec!["a.txt", "sub"]);
+    assert!(source.is_dir(&dir.join("sub")));
+    assert_eq!(source.read(&dir.join("a.txt")).unwrap(), b"abc");
+    assert!(source.stat(&dir.join("missing"), 0).is_err());
+    assert!(source.is_local());
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -1,10 +1,15 @@
+//! `FileTree`: the explorer tree as a flat vec with depths.
+
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
 
 use anyhow::Result;
 use globset::GlobSet;
 
 use super::entry::{FileEntry, GitStatus};
+use super::source::{LocalSource, TreeSource};
 use crate::git::{GitRepo, GitRepoInfo};
 
 fn mark_git_status(statuses: &HashMap<PathBuf, GitStatus>, children: &mut [FileEntry]) {
@@ -18,6 +23,8 @@ fn mark_git_status(statuses: &HashMap<PathBuf, GitStatus>, children: &mut [FileE
   }
 }
 
+type GitStatuses = (HashMap<PathBuf, GitStatus>, GitRepoInfo, HashSet<PathBuf>);
+
 fn propagate_git_status(entries: &mut [FileEntry]) {
   // Single forward pass using a stack of ancestor directory indices.
   // When we encounter a non-clean entry, merge its status into all ancestors.
@@ -46,33 +53,68 @@ fn propagate_git_status(entries: &mut [FileEntry]) {
   }
 }
 
+/// Entries matching a `pinned` pattern first, in pattern order, then
+/// directories before files when `dirs_first`, then case-insensitively by
+/// name.
+fn sort_children(children: &mut [FileEntry], dirs_first: bool, pinned: &GlobSet) {
+  children.sort_by_cached_key(|e| {
+    let pin = pinned.matches(&e.name).into_iter().min().unwrap_or(usize::MAX);
+    (pin, dirs_first && !e.is_dir, e.name.to_lowercase())
+  });
+}
+
 fn mark_git_ignored(ignored_set: &HashSet<PathBuf>, children: &mut [FileEntry]) {
   for child in children.iter_mut() {
     child.is_git_ignored = ignored_set.contains(&child.path);
   }
 }
 
+/// The rows of the tree: the loaded entries under `root` in display order,
+/// with the git status of the repository around it.
 #[derive(Debug)]
 pub struct FileTree {
+  /// Directory the tree shows the contents of
   pub root: PathBuf,
+  /// Loaded entries, children straight after their directory
   pub entries: Vec<FileEntry>,
+  /// Dotfiles are listed
   pub show_hidden: bool,
+  /// Names matching the configured ignore patterns are listed
   pub show_custom_ignored: bool,
+  /// List directories before files rather than interleaved by name
+  pub dirs_first: bool,
+  /// Status of every changed path in the repository
   pub git_statuses: HashMap<PathBuf, GitStatus>,
+  /// Branch and change counts of the repository
   pub git_info: GitRepoInfo,
   git_repo: Option<GitRepo>,
   git_ignored_set: HashSet<PathBuf>,
   git_statuses_dirty: bool,
+  pending_git: Option<Receiver<(Option<GitRepo>, GitStatuses)>>,
+  /// Root that vanished during the last reload, until `take_lost_root`
+  lost_root: Option<PathBuf>,
   ignore_glob_set: GlobSet,
+  /// Names kept at the top of their directory
+  pinned: GlobSet,
+  source: Arc<dyn TreeSource>,
 }
 
 impl FileTree {
+  /// Tree over the local directory `root`.
   pub fn new(root: PathBuf) -> Result<Self> {
     Self::with_ignore_patterns(root, GlobSet::empty())
   }
 
+  /// Tree over the local directory `root`, hiding names that match
+  /// `ignore_glob_set`.
   pub fn with_ignore_patterns(root: PathBuf, ignore_glob_set: GlobSet) -> Result<Self> {
-    let git_repo = GitRepo::open(&root);
+    Self::with_source(root, Arc::new(LocalSource), ignore_glob_set)
+  }
+
+  /// Tree over any `TreeSource`. Git status is only looked up when the
+  /// source's paths are local files.
+  pub fn with_source(root: PathBuf, source: Arc<dyn TreeSource>, ignore_glob_set: GlobSet) -> Result<Self> {
+    let git_repo = if source.supports_git() { GitRepo::open(&root) } else { None };
     let (git_statuses, git_info, git_ignored_set) = git_repo
       .as_ref()
       .map(|r| r.get_file_statuses())
@@ -82,27 +124,146 @@ impl FileTree {
       entries: Vec::new(),
       show_hidden: false,
       show_custom_ignored: false,
+      dirs_first: true,
       git_statuses,
       git_info,
       git_repo,
       git_ignored_set,
       git_statuses_dirty: false,
+      pending_git: None,
+      lost_root: None,
       ignore_glob_set,
+      pinned: GlobSet::empty(),
+      source,
     };
     tree.load_dir(&root, 0)?;
     propagate_git_status(&mut tree.entries);
     Ok(tree)
   }
 
+  /// Like `with_ignore_patterns`, but repository discovery and the status
+  /// scan run on a background thread. Entries show as clean until
+  /// `poll_git` picks up the result.
+  pub fn with_deferred_git(root: PathBuf, ignore_glob_set: GlobSet) -> Result<Self> {
+    let (tx, rx) = mpsc::channel();
+    let git_root = root.clone();
+    std::thread::spawn(move || {
+      let repo = GitRepo::open(&git_root);
+      let statuses = repo.as_ref().map(|r| r.get_file_statuses()).unwrap_or_default();
+      let _ = tx.send((repo, statuses));
+    });
+    let mut tree = Self {
+      root: root.clone(),
+      entries: Vec::new(),
+      show_hidden: false,
+      show_custom_ignored: false,
+      dirs_first: true,
+      git_statuses: HashMap::new(),
+      git_info: GitRepoInfo::default(),
+      git_repo: None,
+      git_ignored_set: HashSet::new(),
+      git_statuses_dirty: false,
+      pending_git: Some(rx),
+      lost_root: None,
+      ignore_glob_set,
+      pinned: GlobSet::empty(),
+      source: Arc::new(LocalSource),
+    };
+    tree.load_dir(&root, 0)?;
+    Ok(tree)
+  }
+
+  /// Whether a background git scan is still running.
+  pub fn git_pending(&self) -> bool {
+    self.pending_git.is_some()
+  }
+
+  /// Rescan git status on a background thread, for changes the file
+  /// watcher can't see such as `git add` in another terminal. `poll_git`
+  /// applies the result. Does nothing outside a repository or while a scan
+  /// is already running.
+  pub fn refresh_git_in_background(&mut self) {
+    if self.pending_git.is_some() || self.git_repo.is_none() || !self.source.supports_git() {
+      return;
+    }
+    let (tx, rx) = mpsc::channel();
+    let git_root = self.root.clone();
+    std::thread::spawn(move || {
+      let repo = GitRepo::open(&git_root);
+      let statuses = repo.as_ref().map(|r| r.get_file_statuses()).unwrap_or_default();
+      let _ = tx.send((repo, statuses));
+    });
+    self.pending_git = Some(rx);
+  }
+
+  /// Apply the result of a deferred git load or background rescan if it
+  /// has arrived. Returns true when any status changed.
+  pub fn poll_git(&mut self) -> bool {
+    let Some(rx) = &self.pending_git else {
+      return false;
+    };
+    let (repo, (statuses, info, ignored_set)) = match rx.try_recv() {
+      Ok(result) => result,
+      Err(TryRecvError::Empty) => return false,
+      Err(TryRecvError::Disconnected) => {
+        self.pending_git = None;
+        return false;
+      }
+    };
+    self.pending_git = None;
+    let changed = statuses != self.git_statuses || info != self.git_info || ignored_set != self.git_ignored_set;
+    self.git_repo = repo;
+    self.git_statuses = statuses;
+    self.git_info = info;
+    self.git_ignored_set = ignored_set;
+    // The root may have moved while the scan was running
+    self.refresh_git_if_needed();
+    // Entries that became clean keep no stale status
+    for entry in &mut self.entries {
+      entry.git_status = GitStatus::default();
+    }
+    mark_git_status(&self.git_statuses, &mut self.entries);
+    mark_git_ignored(&self.git_ignored_set, &mut self.entries);
+    propagate_git_status(&mut self.entries);
+    changed
+  }
+
+  /// Set the names hidden unless `show_custom_ignored`; takes effect on the
+  /// next load or reload.
   pub fn set_ignore_patterns(&mut self, glob_set: GlobSet) {
     self.ignore_glob_set = glob_set;
   }
 
+  /// Names hidden unless `show_custom_ignored`.
+  pub fn ignore_patterns(&self) -> &GlobSet {
+    &self.ignore_glob_set
+  }
+
+  /// Set the sort order; takes effect on the next load or reload.
+  pub fn set_sort(&mut self, dirs_first: bool, pinned: GlobSet) {
+    self.dirs_first = dirs_first;
+    self.pinned = pinned;
+  }
+
+  /// Names kept at the top of their directory.
+  pub fn pinned(&self) -> &GlobSet {
+    &self.pinned
+  }
+
+  /// The repository around the root, if any.
   pub fn git_repo(&self) -> Option<&GitRepo> {
     self.git_repo.as_ref()
   }
 
+  /// Where entries and file contents come from.
+  pub fn source(&self) -> &Arc<dyn TreeSource> {
+    &self.source
+  }
+
   fn refresh_git_if_needed(&mut self) {
+    if self.pending_git.is_some() || !self.source.supports_git() {
+      return;
+    }
     let needs_reopen = match &self.git_repo {
       Some(repo) => !self.root.starts_with(repo.root()),
       None => true,
@@ -124,12 +285,19 @@ impl FileTree {
     }
   }
 
+  /// Rescan git status on the next reload.
   pub fn invalidate_git_statuses(&mut self) {
     self.git_statuses_dirty = true;
   }
 
+  /// The root and every expanded directory, for the file watcher. Empty
+  /// unless the source is the local filesystem.
   pub fn watched_dirs(&self) -> std::collections::HashSet<PathBuf> {
     let mut dirs = std::collections::HashSet::new();
+    // Only directories on the local filesystem can be watched
+    if !self.source.is_local() {
+      return dirs;
+    }
     dirs.insert(self.root.clone());
     for entry in &self.entries {
       if entry.is_dir && entry.expanded {
@@ -139,6 +307,8 @@ impl FileTree {
     dirs
   }
 
+  /// Load the children of `path`: the whole tree when `depth` is 0, else
+  /// right after the entry for `path`.
   pub fn load_dir(&mut self, path: &Path, depth: usize) -> Result<()> {
     let insert_pos = if depth == 0 {
       self.entries.clear();
@@ -152,43 +322,44 @@ impl FileTree {
         .unwrap_or(self.entries.len())
     };
 
-    let mut children = Vec::new();
-    let read_dir = match std::fs::read_dir(path) {
-      Ok(rd) => rd,
-      Err(_) => return Ok(()),
+    let Some(children) = self.list_children(path, depth) else {
+      return Ok(());
     };
 
-    for entry in read_dir.flatten() {
-      let child = FileEntry::from_dir_entry(entry, depth);
-      if !self.show_hidden && child.is_hidden() {
-        continue;
-      }
-      if !self.show_custom_ignored && self.ignore_glob_set.is_match(&child.name) {
-        continue;
-      }
-      children.push(child);
-    }
-
-    // Sort: directories first, then case-insensitive alphabetical
-    children.sort_by(|a, b| {
-      b.is_dir
-        .cmp(&a.is_dir)
-        .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
-    });
-
-    mark_git_ignored(&self.git_ignored_set, &mut children);
-    mark_git_status(&self.git_statuses, &mut children);
-
     // Insert children at the correct position
     self.entries.splice(insert_pos..insert_pos, children);
 
     Ok(())
   }
 
+  /// Visible children of `dir`, sorted and marked with git status. `None`
+  /// when the source can't list it.
+  fn list_children(&self, dir: &Path, depth: usize) -> Option<Vec<FileEntry>> {
+    let mut children: Vec<FileEntry> = self
+      .source
+      .children(dir, depth)
+      .ok()?
+      .into_iter()
+      .filter(|child| self.show_hidden || !child.is_hidden())
+      .filter(|child| self.show_custom_ignored || !self.ignore_glob_set.is_match(&child.name))
+      .collect();
+
+    sort_children(&mut children, self.dirs_first, &self.pinned);
+
+    mark_git_ignored(&self.git_ignored_set, &mut children);
+    mark_git_status(&self.git_statuses, &mut children);
+    Some(children)
+  }
+
+  /// Expand or collapse the directory at `index`. A symlink back to one of
+  /// its ancestors is never expanded: it would only list that again.
   pub fn toggle_expand(&mut self, index: usize) -> Result<()> {
     if index >= self.entries.len() || !self.entries[index].is_dir {
       return Ok(());
     }
+    if self.entries[index].is_cycle && !self.entries[index].expanded {
+      return Ok(());
+    }
 
     if self.entries[index].expanded {
       self.collapse(index);
@@ -203,32 +374,10 @@ impl FileTree {
     let depth = self.entries[index].depth + 1;
     self.entries[index].expanded = true;
 
-    let mut children = Vec::new();
-    let read_dir = match std::fs::read_dir(&path) {
-      Ok(rd) => rd,
-      Err(_) => return Ok(()),
+    let Some(children) = self.list_children(&path, depth) else {
+      return Ok(());
     };
 
-    for entry in read_dir.flatten() {
-      let child = FileEntry::from_dir_entry(entry, depth);
-      if !self.show_hidden && child.is_hidden() {
-        continue;
-      }
-      if !self.show_custom_ignored && self.ignore_glob_set.is_match(&child.name) {
-        continue;
-      }
-      children.push(child);
-    }
-
-    children.sort_by(|a, b| {
-      b.is_dir
-        .cmp(&a.is_dir)
-        .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
-    });
-
-    mark_git_ignored(&self.git_ignored_set, &mut children);
-    mark_git_status(&self.git_statuses, &mut children);
-
     self.entries.splice(index + 1..index + 1, children);
 
     propagate_git_status(&mut self.entries);
@@ -253,17 +402,29 @@ impl FileTree {
     self.entries.drain(index + 1..index + 1 + remove_count);
   }
 
+  /// Show or hide dotfiles.
   pub fn toggle_hidden(&mut self) -> Result<()> {
     self.show_hidden = !self.show_hidden;
     self.reload()
   }
 
+  /// Show or hide names matching the ignore patterns.
   pub fn toggle_custom_ignored(&mut self) -> Result<()> {
     self.show_custom_ignored = !self.show_custom_ignored;
     self.reload()
   }
 
+  /// Switch between directories first and interleaved by name.
+  pub fn toggle_dirs_first(&mut self) -> Result<()> {
+    self.dirs_first = !self.dirs_first;
+    self.reload()
+  }
+
+  /// Read the tree again, keeping the expanded directories expanded.
   pub fn reload(&mut self) -> Result<()> {
+    if let Some(lost) = self.recover_missing_root() {
+      self.lost_root = Some(lost);
+    }
     self.refresh_git_if_needed();
 
     // Remember expanded dirs
@@ -291,6 +452,22 @@ impl FileTree {
     Ok(())
   }
 
+  /// If the root was deleted or unmounted, move it up to the nearest
+  /// ancestor that still exists. Returns the vanished root.
+  pub fn recover_missing_root(&mut self) -> Option<PathBuf> {
+    if self.source.is_dir(&self.root) {
+      return None;
+    }
+    let existing = self.root.ancestors().skip(1).find(|p| self.source.is_dir(p))?.to_path_buf();
+    Some(std::mem::replace(&mut self.root, existing))
+  }
+
+  /// The root that vanished during an earlier reload, if any.
+  pub fn take_lost_root(&mut self) -> Option<PathBuf> {
+    self.lost_root.take()
+  }
+
+  /// Make the directory at `index` the root.
   pub fn enter_dir(&mut self, index: usize) -> Result<()> {
     if index >= self.entries.len() || !self.entries[index].is_dir {
       return Ok(());
@@ -305,6 +482,7 @@ impl FileTree {
     Ok(())
   }
 
+  /// Make `path` the root.
   pub fn navigate_to(&mut self, path: &Path) -> Result<()> {
     self.root = path.to_path_buf();
     self.refresh_git_if_needed();
@@ -329,6 +507,8 @@ impl FileTree {
       .find(|&i| self.entries[i].is_dir && self.entries[i].depth == target_depth - 1)
   }
 
+  /// Make the root's parent the root, keeping the old root expanded. Returns
+  /// the old root, or None at `/`.
   pub fn go_parent(&mut self) -> Result<Option<PathBuf>> {
     if let Some(parent) = self.root.parent().map(|p| p.to_path_buf()) {
       let old_root = self.root.clone();
@@ -423,6 +603,24 @@ mod tests {
     cleanup(&dir);
   }
 
+  #[test]
+  fn test_interleaved_sort_and_pinned_entries() {
+    let dir = setup_test_dir();
+    fs::write(dir.join("aardvark.txt"), "").unwrap();
+    let mut tree = FileTree::new(dir.clone()).unwrap();
+    let names = |tree: &FileTree| tree.entries.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&tree), ["alpha_dir", "beta_dir", "aardvark.txt", "charlie.txt", "delta.rs"]);
+
+    tree.toggle_dirs_first().unwrap();
+    assert_eq!(names(&tree), ["aardvark.txt", "alpha_dir", "beta_dir", "charlie.txt", "delta.rs"]);
+
+    // Pinned names lead in pattern order, whatever else the order is
+    tree.set_sort(true, make_glob_set(&["delta.rs", "beta*"]));
+    tree.reload().unwrap();
+    assert_eq!(names(&tree), ["delta.rs", "beta_dir", "alpha_dir", "aardvark.txt", "charlie.txt"]);
+    cleanup(&dir);
+  }
+
   #[test]
   fn test_hidden_files_excluded_by_default() {
     let dir = setup_test_dir();
@@ -459,6 +657,21 @@ mod tests {
     cleanup(&dir);
   }
 
+  #[test]
+  fn test_symlink_to_an_ancestor_is_not_expanded() {
+    let dir = setup_test_dir();
+    std::os::unix::fs::symlink(&dir, dir.join("alpha_dir").join("up")).unwrap();
+    let mut tree = FileTree::new(dir.clone()).unwrap();
+    tree.toggle_expand(0).unwrap();
+    let link = tree.entries.iter().position(|e| e.name == "up").unwrap();
+    assert!(tree.entries[link].is_cycle);
+    let len_before = tree.entries.len();
+    tree.toggle_expand(link).unwrap();
+    assert!(!tree.entries[link].expanded);
+    assert_eq!(tree.entries.len(), len_before);
+    cleanup(&dir);
+  }
+
   #[test]
   fn test_toggle_expand_on_file_is_noop() {
     let dir = setup_test_dir();
@@ -611,6 +824,27 @@ mod tests {
     let _ = fs::remove_dir_all(&dir);
   }
 
+  #[test]
+  fn test_reload_recovers_from_deleted_root() {
+    let dir = setup_test_dir();
+    let deep = dir.join("alpha_dir").join("gone").join("deeper");
+    fs::create_dir_all(&deep).unwrap();
+    let mut tree = FileTree::new(deep.clone()).unwrap();
+
+    fs::remove_dir_all(dir.join("alpha_dir").join("gone")).unwrap();
+    tree.reload().unwrap();
+    assert_eq!(tree.root, dir.join("alpha_dir"));
+    assert!(tree.entries.iter().any(|e| e.name == "inner.txt"));
+    assert_eq!(tree.take_lost_root(), Some(deep));
+    assert_eq!(tree.take_lost_root(), None);
+
+    // Existing roots are left alone
+    tree.reload().unwrap();
+    assert_eq!(tree.root, dir.join("alpha_dir"));
+    assert_eq!(tree.take_lost_root(), None);
+    cleanup(&dir);
+  }
+
   #[test]
   fn test_reload_preserves_expanded() {
     let dir = setup_test_dir();
@@ -739,6 +973,48 @@ mod tests {
     let _ = fs::remove_dir_all(&dir);
   }
 
+  #[test]
+  fn test_background_refresh_picks_up_staging_and_cleaned_files() {
+    let dir = std::env::temp_dir().join(format!(
+      "tui_tree_gitrefresh_{}_{}", COUNTER.fetch_add(1, Ordering::SeqCst), std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let repo = init_git_repo_with_config(&dir);
+    fs::write(dir.join("file.txt"), "initial").unwrap();
+    git_add_and_commit(&repo, &["file.txt"], "init");
+    fs::write(dir.join("file.txt"), "changed").unwrap();
+    fs::write(dir.join("new.txt"), "new").unwrap();
+
+    let mut tree = FileTree::new(dir.clone()).unwrap();
+    assert_eq!(tree.git_info.untracked_count, 1);
+    assert_eq!(tree.git_info.modified_count, 1);
+
+    // Staged and reverted behind the tree's back, as another terminal would
+    git_stage(&repo, &["new.txt"]);
+    fs::write(dir.join("file.txt"), "initial").unwrap();
+    tree.refresh_git_in_background();
+    assert!(tree.git_pending());
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while !tree.poll_git() {
+      assert!(std::time::Instant::now() < deadline);
+      std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    let status = |name: &str| tree.entries.iter().find(|e| e.name == name).unwrap().git_status;
+    assert_eq!(status("new.txt").staged, Some(GitFileStatus::Added));
+    assert!(status("file.txt").is_clean());
+    assert_eq!((tree.git_info.staged_count, tree.git_info.modified_count, tree.git_info.untracked_count), (1, 0, 0));
+
+    // Nothing changed since, so the next scan reports no change
+    tree.refresh_git_in_background();
+    while tree.git_pending() {
+      assert!(!tree.poll_git());
+      std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
   #[test]
   fn test_reload_refreshes_git_status() {
     let dir = std::env::temp_dir().join(format!(
@@ -769,6 +1045,34 @@ mod tests {
     let _ = fs::remove_dir_all(&dir);
   }
 
+  #[test]
+  fn test_deferred_git_applies_statuses_on_poll() {
+    let dir = std::env::temp_dir().join(format!(
+      "tui_tree_gitdefer_{}_{}", COUNTER.fetch_add(1, Ordering::SeqCst), std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    git2::Repository::init(&dir).unwrap();
+    fs::write(dir.join("new.txt"), "new").unwrap();
+
+    let mut tree = FileTree::with_deferred_git(dir.clone(), GlobSet::empty()).unwrap();
+    assert!(tree.git_pending());
+    assert!(tree.git_repo().is_none());
+
+    let start = std::time::Instant::now();
+    while !tree.poll_git() {
+      assert!(start.elapsed() < std::time::Duration::from_secs(10), "git load timed out");
+      std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert!(!tree.git_pending());
+    assert!(tree.git_repo().is_some());
+    let file = tree.entries.iter().find(|e| e.name == "new.txt").unwrap();
+    assert!(!file.git_status.is_clean());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
   #[test]
   fn test_find_parent_index() {
     let dir = setup_test_dir();
@@ -922,4 +1226,36 @@ mod tests {
 
     cleanup(&dir);
   }
+
+  #[test]
+  fn test_tree_over_memory_source() {
+    let source = crate::fs::MemorySource::new()
+      .with_file("/virt/zeta.txt", "z")
+      .with_file("/virt/.hidden", "")
+      .with_file("/virt/docs/guide.md", "# guide")
+      .with_file("/virt/Alpha.rs", "fn a() {}");
+    let mut tree = FileTree::with_source(PathBuf::from("/virt"), Arc::new(source), GlobSet::empty()).unwrap();
+    let names: Vec<_> = tree.entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["docs", "Alpha.rs", "zeta.txt"]);
+    assert!(tree.git_repo().is_none());
+
+    tree.toggle_expand(0).unwrap();
+    assert_eq!(tree.entries[1].name, "guide.md");
+    assert_eq!(tree.entries[1].depth, 1);
+    assert_eq!(tree.source().read(&tree.entries[1].path).unwrap(), b"# guide");
+
+    tree.toggle_hidden().unwrap();
+    assert!(tree.entries.iter().any(|e| e.name == ".hidden"));
+    // Expansion survives the reload
+    assert!(tree.entries.iter().any(|e| e.name == "guide.md"));
+  }
+
+  #[test]
+  fn test_memory_source_missing_root_moves_up() {
+    let source = crate::fs::MemorySource::new().with_file("/virt/a/b.txt", "");
+    let mut tree = FileTree::with_source(PathBuf::from("/virt/gone"), Arc::new(source), GlobSet::empty()).unwrap();
+    assert!(tree.entries.is_empty());
+    assert_eq!(tree.recover_missing_root(), Some(PathBuf::from("/virt/gone")));
+    assert_eq!(tree.root, PathBuf::from("/virt"));
+  }
 }
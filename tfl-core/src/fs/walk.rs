@@ -0,0 +1,114 @@
+//! Recursive walks: the one behind copy totals, the size column and the
+//! delete summary, and symlink cycle detection for walks that follow links.
+
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::ops::ControlFlow;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Visit `path` and then everything under it, symlinks not followed, until
+/// `visit` breaks. Entries that can't be read are skipped.
+pub fn walk(path: &Path, visit: &mut dyn FnMut(&Path, &Metadata) -> ControlFlow<()>) -> ControlFlow<()> {
+  let Ok(meta) = path.symlink_metadata() else {
+    return ControlFlow::Continue(());
+  };
+  visit(path, &meta)?;
+  if meta.is_dir() {
+    for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+      walk(&entry.path(), visit)?;
+    }
+  }
+  ControlFlow::Continue(())
+}
+
+/// Directories already entered by a recursive walk, keyed by device and
+/// inode so a symlink back up the tree is recognised however it is spelled.
+#[derive(Debug, Default)]
+pub struct VisitedDirs {
+  seen: HashSet<(u64, u64)>,
+  /// Directories skipped because they had already been entered
+  pub cycles: usize,
+}
+
+impl VisitedDirs {
+  /// Record `dir` as entered. Returns false, and counts a cycle, when it
+  /// resolves to a directory this walk has already entered.
+  pub fn enter(&mut self, dir: &Path) -> bool {
+    let Ok(meta) = dir.metadata() else {
+      // Let the caller's read_dir surface the error
+      return true;
+    };
+    if self.seen.insert((meta.dev(), meta.ino())) {
+      true
+    } else {
+      self.cycles += 1;
+      false
+    }
+  }
+}
+
+/// If `path` is a symlink to one of its own ancestors, the resolved target.
+pub fn symlink_cycle_target(path: &Path) -> Option<PathBuf> {
+  if !path.symlink_metadata().is_ok_and(|m| m.is_symlink()) {
+    return None;
+  }
+  let target = path.canonicalize().ok()?;
+  let parent = path.parent()?.canonicalize().ok()?;
+  (target.is_dir() && parent.starts_with(&target)).then_some(target)
+}
+
+/// Status suffix for walks that skipped cycles, e.g. " (skipped 2 symlink cycles)".
+pub fn cycle_note(cycles: usize) -> String {
+  match cycles {
+    0 => String::new(),
+    1 => " (skipped 1 symlink cycle)".to_string(),
+    n => format!(" (skipped {n} symlink cycles)"),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::os::unix::fs::symlink;
+
+  fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tfl_walk_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("a/b")).unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_visited_dirs_detects_symlinked_ancestor() {
+    let dir = test_dir("visited");
+    symlink(&dir, dir.join("a/b/loop")).unwrap();
+
+    let mut visited = VisitedDirs::default();
+    assert!(visited.enter(&dir));
+    assert!(visited.enter(&dir.join("a")));
+    assert!(!visited.enter(&dir.join("a/b/loop")));
+    assert_eq!(visited.cycles, 1);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_symlink_cycle_target() {
+    let dir = test_dir("cycle_target");
+    symlink(dir.join("a"), dir.join("a/b/up")).unwrap();
+    symlink(dir.join("a/b"), dir.join("sideways")).unwrap();
+
+    let canonical = dir.canonicalize().unwrap();
+    assert_eq!(symlink_cycle_target(&dir.join("a/b/up")), Some(canonical.join("a")));
+    assert_eq!(symlink_cycle_target(&dir.join("sideways")), None);
+    assert_eq!(symlink_cycle_target(&dir.join("a")), None);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_cycle_note() {
+    assert_eq!(cycle_note(0), "");
+    assert_eq!(cycle_note(1), " (skipped 1 symlink cycle)");
+    assert_eq!(cycle_note(3), " (skipped 3 symlink cycles)");
+  }
+}
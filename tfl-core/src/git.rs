@@ -1,28 +1,69 @@
+//! Git status, branch info, history and blame via libgit2.
+
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-use git2::{BlameOptions, BranchType, Repository, Status, StatusOptions};
+use git2::build::CheckoutBuilder;
+use git2::{BlameOptions, BranchType, IndexAddOption, ObjectType, Repository, Status, StatusOptions, WorktreeAddOptions};
 
 use crate::fs::entry::{GitFileStatus, GitStatus};
 use crate::preview::blame::{BlameData, BlameLine};
 
-#[derive(Debug, Default)]
+/// Branch and change counts for the status bar.
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct GitRepoInfo {
+  /// Checked-out branch; None when HEAD is detached
   pub branch: Option<String>,
+  /// Commits not yet on the upstream
   pub ahead: usize,
+  /// Upstream commits not yet here
   pub behind: usize,
+  /// Paths with staged changes
   pub staged_count: usize,
+  /// Paths modified in the work tree
   pub modified_count: usize,
+  /// Paths not tracked
   pub untracked_count: usize,
+  /// Paths with merge conflicts
+  pub conflicted_count: usize,
 }
 
+/// A commit that touched a file, for the history view.
 #[derive(Clone)]
 pub struct GitCommit {
+  /// Full id
+  pub id: git2::Oid,
+  /// Abbreviated id
   pub hash: String,
+  /// Relative commit time, e.g. `3d ago`
   pub date: String,
+  /// Author's name
+  pub author: String,
+  /// First line of the message
   pub message: String,
 }
 
+/// A checkout of a repository: its main working tree or a linked worktree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worktree {
+  /// Top of the working tree
+  pub path: PathBuf,
+  /// Branch checked out there; None when HEAD is detached
+  pub branch: Option<String>,
+  /// The repository's own working tree, not a linked worktree
+  pub is_main: bool,
+}
+
+/// A local branch with the relative time of its last commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+  /// Short name, e.g. `main`
+  pub name: String,
+  /// Relative time of the last commit, e.g. `3d ago`
+  pub date: String,
+}
+
+/// A repository found from a path inside it.
 pub struct GitRepo {
   repo: Repository,
   root: PathBuf,
@@ -34,7 +75,14 @@ impl std::fmt::Debug for GitRepo {
   }
 }
 
+/// Version of the libgit2 tfl is built with, as `major.minor.patch`.
+pub fn libgit2_version() -> String {
+  let (major, minor, patch) = git2::Version::get().libgit2_version();
+  format!("{major}.{minor}.{patch}")
+}
+
 impl GitRepo {
+  /// Open the repository `path` is in, searching upwards.
   pub fn open(path: &Path) -> Option<Self> {
     Repository::discover(path).ok().map(|repo| {
       let root = repo
@@ -45,10 +93,12 @@ impl GitRepo {
     })
   }
 
+  /// Top of the working tree, canonicalized.
   pub fn root(&self) -> &Path {
     &self.root
   }
 
+  /// Checked-out branch; None when HEAD is detached.
   pub fn get_branch(&self) -> Option<String> {
     let head = self.repo.head().ok()?;
     if head.is_branch() {
@@ -58,6 +108,7 @@ impl GitRepo {
     }
   }
 
+  /// Commits ahead of and behind the upstream, zeros without one.
   pub fn get_ahead_behind(&self) -> (usize, usize) {
     let Ok(head) = self.repo.head() else { return (0, 0) };
     let Some(local_oid) = head.target() else { return (0, 0) };
@@ -76,6 +127,8 @@ impl GitRepo {
     self.repo.graph_ahead_behind(local_oid, upstream_oid).unwrap_or((0, 0))
   }
 
+  /// Status of every changed path, the counts for the status bar, and the
+  /// ignored paths.
   pub fn get_file_statuses(&self) -> (HashMap<PathBuf, GitStatus>, GitRepoInfo, HashSet<PathBuf>) {
     let mut info = GitRepoInfo {
       branch: self.get_branch(),
@@ -109,7 +162,9 @@ impl GitRepo {
 
       let git_status = convert_status(status);
 
-      if git_status.staged.is_some() && git_status.staged != Some(GitFileStatus::Untracked) {
+      if git_status.is_conflicted() {
+        info.conflicted_count += 1;
+      } else if git_status.staged.is_some() && git_status.staged != Some(GitFileStatus::Untracked) {
         info.staged_count += 1;
       }
       if git_status.unstaged == Some(GitFileStatus::Modified) {
@@ -125,6 +180,7 @@ impl GitRepo {
     (map, info, ignored_set)
   }
 
+  /// Whether `.gitignore` rules match `path`.
   #[allow(dead_code)] // Used in tests
   pub fn is_ignored(&self, path: &Path) -> bool {
     // Make path relative to repo root
@@ -132,6 +188,7 @@ impl GitRepo {
     self.repo.status_should_ignore(rel_path).unwrap_or(false)
   }
 
+  /// The `paths` that `.gitignore` rules match.
   #[allow(dead_code)] // Used in tests
   pub fn is_ignored_batch(&self, paths: &[PathBuf]) -> HashSet<PathBuf> {
     let mut ignored = HashSet::new();
@@ -143,6 +200,7 @@ impl GitRepo {
     ignored
   }
 
+  /// The last `limit` commits that touched `path`, newest first.
   pub fn get_file_commits(&self, path: &Path, limit: usize) -> Vec<GitCommit> {
     let rel_path = match path.strip_prefix(&self.root) {
       Ok(p) => p,
@@ -184,7 +242,9 @@ impl GitRepo {
         .unwrap_or("")
         .to_string();
 
-      commits.push(GitCommit { hash, date, message });
+      let author = commit.author().name().unwrap_or("").to_string();
+
+      commits.push(GitCommit { id: oid, hash, date, author, message });
 
       if commits.len() >= limit {
         break;
@@ -194,6 +254,16 @@ impl GitRepo {
     commits
   }
 
+  /// Contents of `path` as of the given commit, if the file existed there
+  pub fn read_file_at(&self, path: &Path, commit: git2::Oid) -> Option<Vec<u8>> {
+    let rel_path = path.strip_prefix(&self.root).ok()?;
+    let tree = self.repo.find_commit(commit).ok()?.tree().ok()?;
+    let entry = tree.get_path(rel_path).ok()?;
+    let blob = entry.to_object(&self.repo).ok()?.peel_to_blob().ok()?;
+    Some(blob.content().to_vec())
+  }
+
+  /// Who last changed each line of `path`, or None if it isn't tracked.
   pub fn get_file_blame(&self, path: &Path) -> Option<BlameData> {
     let rel_path = path.strip_prefix(&self.root).ok()?;
 
@@ -238,6 +308,194 @@ impl GitRepo {
       Some(BlameData::new(blame_lines))
     }
   }
+
+  /// The repository the worktrees hang off, which is this one unless it
+  /// was opened from a linked worktree.
+  fn main_repo(&self) -> Option<Repository> {
+    if self.repo.is_worktree() { Repository::open(self.repo.commondir()).ok() } else { None }
+  }
+
+  /// The main working tree followed by the linked worktrees that still exist.
+  pub fn worktrees(&self) -> Vec<Worktree> {
+    let main = self.main_repo();
+    let main = main.as_ref().unwrap_or(&self.repo);
+    let mut worktrees = Vec::new();
+    if let Some(path) = main.workdir() {
+      worktrees.push(Worktree { path: canonical(path), branch: head_branch(main), is_main: true });
+    }
+    let Ok(names) = main.worktrees() else { return worktrees };
+    for name in names.iter().flatten() {
+      let Ok(wt) = main.find_worktree(name) else { continue };
+      if wt.validate().is_err() {
+        continue;
+      }
+      let branch = Repository::open_from_worktree(&wt).ok().and_then(|r| head_branch(&r));
+      worktrees.push(Worktree { path: canonical(wt.path()), branch, is_main: false });
+    }
+    worktrees
+  }
+
+  /// Local branches, most recently committed to first.
+  pub fn recent_branches(&self, limit: usize) -> Vec<Branch> {
+    let Ok(branches) = self.repo.branches(Some(BranchType::Local)) else { return Vec::new() };
+    let mut dated: Vec<(i64, String)> = branches
+      .flatten()
+      .filter_map(|(branch, _)| {
+        let name = branch.name().ok()??.to_string();
+        let time = branch.get().peel_to_commit().ok()?.time().seconds();
+        Some((time, name))
+      })
+      .collect();
+    dated.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    dated
+      .into_iter()
+      .take(limit)
+      .map(|(time, name)| Branch { name, date: format_relative_time(time) })
+      .collect()
+  }
+
+  /// Check out the local `branch` into a new linked worktree at `path`.
+  /// Fails if the branch is already checked out somewhere.
+  pub fn add_worktree(&self, branch: &str, path: &Path) -> anyhow::Result<()> {
+    let main = self.main_repo();
+    let main = main.as_ref().unwrap_or(&self.repo);
+    let reference = main.find_branch(branch, BranchType::Local)?.into_reference();
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    // Worktree names live under .git/worktrees and can't contain slashes
+    let name = branch.replace('/', "-");
+    let mut opts = WorktreeAddOptions::new();
+    opts.reference(Some(&reference));
+    main.worktree(&name, path, Some(&opts))?;
+    Ok(())
+  }
+
+  /// Switch the working tree to the local `branch`, like `git switch`.
+  /// Uncommitted changes come along unless the switch would overwrite
+  /// them, in which case it fails and nothing changes.
+  pub fn checkout_branch(&self, branch: &str) -> anyhow::Result<()> {
+    if let Some(other) = self.worktrees().into_iter().find(|w| w.branch.as_deref() == Some(branch) && w.path != self.root) {
+      anyhow::bail!("{branch} is checked out at {}", other.path.display());
+    }
+    let reference = self.repo.find_branch(branch, BranchType::Local)?.into_reference();
+    let tree = reference.peel_to_tree()?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.safe();
+    self.repo.checkout_tree(tree.as_object(), Some(&mut checkout))?;
+    self.repo.set_head(reference.name().unwrap_or_default())?;
+    Ok(())
+  }
+
+  /// Create `branch` at HEAD and switch to it, like `git switch -c`. The
+  /// working tree is left as it is.
+  pub fn create_branch(&self, branch: &str) -> anyhow::Result<()> {
+    let head = self.repo.head()?.peel_to_commit()?;
+    let created = self.repo.branch(branch, &head, false)?;
+    self.repo.set_head(created.get().name().unwrap_or_default())?;
+    Ok(())
+  }
+
+  /// Stage the changes under `paths`, deletions included, like `git add
+  /// --all`. Ignored files stay out.
+  pub fn stage(&self, paths: &[PathBuf]) -> anyhow::Result<()> {
+    let specs = self.pathspecs(paths)?;
+    if specs.is_empty() {
+      return Ok(());
+    }
+    let mut index = self.repo.index()?;
+    let mut literal = |path: &Path, _: &[u8]| if matches_literally(&specs, path) { 0 } else { 1 };
+    index.add_all(&specs, IndexAddOption::DEFAULT | IndexAddOption::DISABLE_PATHSPEC_MATCH, Some(&mut literal))?;
+    // add_all only adds; files gone from the working tree need this. It
+    // has no literal mode, so the callback skips what only a glob matched
+    index.update_all(&specs, Some(&mut literal))?;
+    index.write()?;
+    Ok(())
+  }
+
+  /// Put the index entries under `paths` back to HEAD, like `git restore
+  /// --staged`. The working tree is left as it is.
+  pub fn unstage(&self, paths: &[PathBuf]) -> anyhow::Result<()> {
+    let specs = self.pathspecs(paths)?;
+    if specs.is_empty() {
+      return Ok(());
+    }
+    match self.repo.head().and_then(|head| head.peel(ObjectType::Commit)) {
+      // reset has no literal mode, so the specs are escaped instead
+      Ok(head) => self.repo.reset_default(Some(&head), specs.iter().map(|spec| escape_glob(spec)))?,
+      // Nothing committed yet, so unstaging takes the files out of the index
+      Err(_) => {
+        let mut index = self.repo.index()?;
+        let mut literal = |path: &Path, _: &[u8]| if matches_literally(&specs, path) { 0 } else { 1 };
+        index.remove_all(&specs, Some(&mut literal))?;
+        index.write()?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Throw away the unstaged changes under `paths`, restoring the staged
+  /// content like `git restore`. Untracked files are left alone.
+  pub fn discard(&self, paths: &[PathBuf]) -> anyhow::Result<()> {
+    let specs = self.pathspecs(paths)?;
+    // Checking out without paths would restore the whole working tree
+    if specs.is_empty() {
+      return Ok(());
+    }
+    let mut checkout = CheckoutBuilder::new();
+    // A name like `[draft].md` must not discard every file it matches as a glob
+    checkout.force().disable_pathspec_match(true);
+    for spec in &specs {
+      checkout.path(spec);
+    }
+    self.repo.checkout_index(None, Some(&mut checkout))?;
+    Ok(())
+  }
+
+  /// `paths` relative to the working tree, for pathspecs; a directory
+  /// matches everything under it. Links are kept, not followed.
+  fn pathspecs(&self, paths: &[PathBuf]) -> anyhow::Result<Vec<String>> {
+    paths
+      .iter()
+      .map(|path| {
+        let path = match (path.parent(), path.file_name()) {
+          (Some(parent), Some(name)) => canonical(parent).join(name),
+          _ => canonical(path),
+        };
+        let rel = path
+          .strip_prefix(&self.root)
+          .map_err(|_| anyhow::anyhow!("{} is outside the repository", path.display()))?;
+        Ok(if rel.as_os_str().is_empty() { "*".to_string() } else { rel.to_string_lossy().to_string() })
+      })
+      .collect()
+  }
+}
+
+/// Whether `path`, relative to the working tree, is one of `specs` or under
+/// one, reading them as plain paths rather than globs.
+fn matches_literally(specs: &[String], path: &Path) -> bool {
+  specs.iter().any(|spec| spec == "*" || path.starts_with(spec))
+}
+
+/// `spec` with the glob characters escaped, for matching it literally.
+fn escape_glob(spec: &str) -> String {
+  let mut escaped = String::with_capacity(spec.len());
+  for c in spec.chars() {
+    if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
+
+fn head_branch(repo: &Repository) -> Option<String> {
+  let head = repo.head().ok()?;
+  if head.is_branch() { head.shorthand().map(|s| s.to_string()) } else { None }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+  path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
 fn commit_touches_file(repo: &Repository, commit: &git2::Commit, path: &str) -> bool {
@@ -431,6 +689,179 @@ mod tests {
     let _ = fs::remove_dir_all(&dir);
   }
 
+  fn commit_file(dir: &Path, name: &str, content: &[u8], message: &str) {
+    fs::write(dir.join(name), content).unwrap();
+    let repo = Repository::open(dir).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(name)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = repo.signature().unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap();
+  }
+
+  #[test]
+  fn test_worktrees_and_recent_branches() {
+    let dir = make_test_dir();
+    let repo_dir = dir.join("repo");
+    fs::create_dir_all(&repo_dir).unwrap();
+    init_git_repo(&repo_dir);
+    commit_file(&repo_dir, "a.txt", b"a", "first");
+    {
+      let raw = Repository::open(&repo_dir).unwrap();
+      let head = raw.head().unwrap().peel_to_commit().unwrap();
+      raw.branch("feature/login", &head, false).unwrap();
+    }
+
+    let repo = GitRepo::open(&repo_dir).unwrap();
+    let names: Vec<String> = repo.recent_branches(10).into_iter().map(|b| b.name).collect();
+    assert!(names.contains(&"feature/login".to_string()));
+    let worktrees = repo.worktrees();
+    assert_eq!(worktrees.len(), 1);
+    assert!(worktrees[0].is_main);
+    assert_eq!(worktrees[0].path, repo_dir);
+
+    let target = dir.join("trees").join("feature-login");
+    repo.add_worktree("feature/login", &target).unwrap();
+    assert!(target.join("a.txt").exists());
+    let worktrees = repo.worktrees();
+    assert_eq!(worktrees.len(), 2);
+    assert_eq!(worktrees[1].path, target.canonicalize().unwrap());
+    assert_eq!(worktrees[1].branch.as_deref(), Some("feature/login"));
+
+    // The same list is seen from inside the linked worktree
+    let linked = GitRepo::open(&target).unwrap();
+    assert_eq!(linked.worktrees(), worktrees);
+    // A branch can only be checked out once
+    assert!(repo.add_worktree("feature/login", &dir.join("trees").join("again")).is_err());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_create_and_checkout_branches() {
+    let dir = make_test_dir();
+    init_git_repo(&dir);
+    commit_file(&dir, "a.txt", b"a", "first");
+    let repo = GitRepo::open(&dir).unwrap();
+    let main = repo.get_branch().unwrap();
+
+    repo.create_branch("feature").unwrap();
+    assert_eq!(repo.get_branch().as_deref(), Some("feature"));
+    assert!(repo.create_branch("feature").is_err());
+    commit_file(&dir, "b.txt", b"b", "on feature");
+    commit_file(&dir, "a.txt", b"a2", "change a");
+
+    repo.checkout_branch(&main).unwrap();
+    assert_eq!(repo.get_branch().as_deref(), Some(main.as_str()));
+    assert!(!dir.join("b.txt").exists());
+    assert_eq!(fs::read(dir.join("a.txt")).unwrap(), b"a");
+
+    // A change the switch would overwrite stops it
+    fs::write(dir.join("a.txt"), "local").unwrap();
+    assert!(repo.checkout_branch("feature").is_err());
+    assert_eq!(repo.get_branch().as_deref(), Some(main.as_str()));
+    assert_eq!(fs::read(dir.join("a.txt")).unwrap(), b"local");
+    assert!(repo.checkout_branch("missing").is_err());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_stage_unstage_and_discard() {
+    let dir = make_test_dir();
+    init_git_repo(&dir);
+    commit_file(&dir, "a.txt", b"a", "first");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub/new.txt"), "new").unwrap();
+    fs::write(dir.join("a.txt"), "changed").unwrap();
+    let repo = GitRepo::open(&dir).unwrap();
+    let status = |path: &str| repo.get_file_statuses().0.get(&dir.join(path)).copied().unwrap_or_default();
+
+    // A directory stages everything under it
+    repo.stage(&[dir.join("sub"), dir.join("a.txt")]).unwrap();
+    assert_eq!(status("sub/new.txt").staged, Some(GitFileStatus::Added));
+    assert_eq!(status("a.txt"), GitStatus { staged: Some(GitFileStatus::Modified), unstaged: None });
+
+    repo.unstage(&[dir.join("a.txt")]).unwrap();
+    assert_eq!(status("a.txt"), GitStatus { staged: None, unstaged: Some(GitFileStatus::Modified) });
+    assert_eq!(status("sub/new.txt").staged, Some(GitFileStatus::Added));
+
+    repo.discard(&[dir.join("a.txt")]).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "a");
+    assert!(status("a.txt").is_clean());
+    // Deletions stage too
+    fs::remove_file(dir.join("a.txt")).unwrap();
+    repo.stage(&[dir.join("a.txt")]).unwrap();
+    assert_eq!(status("a.txt").staged, Some(GitFileStatus::Deleted));
+
+    assert!(repo.stage(&[std::env::temp_dir()]).is_err());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_bracketed_names_are_not_globs() {
+    let dir = make_test_dir();
+    init_git_repo(&dir);
+    commit_file(&dir, "[draft].md", b"draft", "first");
+    commit_file(&dir, "d.md", b"d", "second");
+    fs::write(dir.join("[draft].md"), "draft changed").unwrap();
+    fs::write(dir.join("d.md"), "d changed").unwrap();
+    let repo = GitRepo::open(&dir).unwrap();
+    let status = |path: &str| repo.get_file_statuses().0.get(&dir.join(path)).copied().unwrap_or_default();
+    let draft = [dir.join("[draft].md")];
+
+    repo.stage(&draft).unwrap();
+    assert_eq!(status("[draft].md").staged, Some(GitFileStatus::Modified));
+    assert_eq!(status("d.md"), GitStatus { staged: None, unstaged: Some(GitFileStatus::Modified) });
+
+    repo.stage(&[dir.join("d.md")]).unwrap();
+    repo.unstage(&draft).unwrap();
+    assert_eq!(status("[draft].md").staged, None);
+    assert_eq!(status("d.md").staged, Some(GitFileStatus::Modified));
+
+    // Discarding the bracketed file leaves the one its glob would match
+    repo.unstage(&[dir.join("d.md")]).unwrap();
+    repo.discard(&draft).unwrap();
+    assert_eq!(fs::read_to_string(dir.join("[draft].md")).unwrap(), "draft");
+    assert_eq!(fs::read_to_string(dir.join("d.md")).unwrap(), "d changed");
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_unstage_before_the_first_commit() {
+    let dir = make_test_dir();
+    init_git_repo(&dir);
+    fs::write(dir.join("new.txt"), "new").unwrap();
+    let repo = GitRepo::open(&dir).unwrap();
+    repo.stage(&[dir.join("new.txt")]).unwrap();
+    assert_eq!(repo.get_file_statuses().1.staged_count, 1);
+    repo.unstage(&[dir.join("new.txt")]).unwrap();
+    let (statuses, info, _) = repo.get_file_statuses();
+    assert_eq!(info.staged_count, 0);
+    assert_eq!(statuses.get(&dir.join("new.txt")).unwrap().unstaged, Some(GitFileStatus::Untracked));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_read_file_at_past_revisions() {
+    let dir = make_test_dir();
+    init_git_repo(&dir);
+    commit_file(&dir, "logo.bin", b"v1", "first");
+    commit_file(&dir, "logo.bin", b"v2", "second");
+
+    let repo = GitRepo::open(&dir).unwrap();
+    let path = dir.join("logo.bin");
+    let commits = repo.get_file_commits(&path, 10);
+    assert_eq!(commits.len(), 2);
+    assert_eq!(commits[0].message, "second");
+    assert_eq!(commits[0].author, "Test");
+    assert_eq!(repo.read_file_at(&path, commits[0].id).as_deref(), Some(&b"v2"[..]));
+    assert_eq!(repo.read_file_at(&path, commits[1].id).as_deref(), Some(&b"v1"[..]));
+    assert!(repo.read_file_at(&dir.join("missing.bin"), commits[0].id).is_none());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
   #[test]
   fn test_is_ignored() {
     let dir = make_test_dir();
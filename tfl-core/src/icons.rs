@@ -1,10 +1,16 @@
-use ratatui::style::Color;
+//! Nerd Font icons and colors by file name and extension.
 
+use ratatui_core::style::Color;
+
+/// A Nerd Font glyph and the colour to draw it in.
 pub struct FileIcon {
+  /// Glyph followed by a space
   pub glyph: &'static str,
+  /// Glyph colour
   pub color: Color,
 }
 
+/// The icon for a tree entry, by kind, then name, then extension.
 pub fn file_icon(name: &str, is_dir: bool, expanded: bool, is_symlink: bool) -> FileIcon {
   // Priority 1: Symlinks
   if is_symlink {
@@ -112,6 +118,7 @@ pub fn file_icon(name: &str, is_dir: bool, expanded: bool, is_symlink: bool) ->
   }
 }
 
+/// The colour for an entry's name, by kind and extension.
 pub fn file_name_color(name: &str, is_dir: bool, is_symlink: bool) -> Color {
   if is_dir {
     return Color::Indexed(75); // blue
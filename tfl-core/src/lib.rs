@@ -0,0 +1,32 @@
+//! The parts of tfl that don't depend on a terminal: reading and walking the
+//! file tree, git status and history, building previews, and detecting
+//! which apps can open a file.
+//!
+//! Previews come out as `ratatui_core` [`Line`](ratatui_core::text::Line)s,
+//! which any ratatui frontend can draw as-is and others can walk span by span
+//! for their text and [`Style`](ratatui_core::style::Style).
+//!
+//! ```no_run
+//! use std::path::PathBuf;
+//!
+//! use tfl_core::fs::FileTree;
+//! use tfl_core::preview::{PreviewType, detect_preview_type};
+//!
+//! let tree = FileTree::new(PathBuf::from(".")).unwrap();
+//! for entry in &tree.entries {
+//!   if detect_preview_type(&entry.path) == PreviewType::Markdown {
+//!     println!("{}", entry.name);
+//!   }
+//! }
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod fs;
+pub mod git;
+pub mod icons;
+pub mod opener;
+pub mod paths;
+pub mod preview;
+pub mod process;
+pub mod theme;
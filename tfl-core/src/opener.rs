@@ -0,0 +1,383 @@
+//! Open-with: which apps are installed and launching a file in one.
+
+use std::path::Path;
+
+use crate::process::{ProcessRunner, ProcessSpec};
+
+/// An app a file can be opened with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenApp {
+  /// Shown in the open-with menu
+  pub name: String,
+  /// Program run with the path as its argument
+  pub command: String,
+  /// Runs in the terminal, so tfl suspends while it does
+  pub is_tui: bool,
+  /// Name of the `.app` bundle launched on macOS instead of `command`
+  pub macos_app: Option<String>,
+  /// Can open a folder, so files also get an "open containing folder" entry
+  pub opens_dir: bool,
+  /// This entry is that "open containing folder" entry
+  pub dir_mode: bool,
+  /// From the project's `.tfl.toml` or `apps.toml`, not the global list
+  pub project: bool,
+}
+
+/// Apps tfl offers when they are installed and `apps.toml` doesn't replace
+/// them.
+pub fn known_apps() -> Vec<OpenApp> {
+  vec![
+    OpenApp {
+      name: "VS Code".into(),
+      command: "code".into(),
+      is_tui: false,
+      macos_app: Some("Visual Studio Code".into()),
+      opens_dir: true,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Cursor".into(),
+      command: "cursor".into(),
+      is_tui: false,
+      macos_app: Some("Cursor".into()),
+      opens_dir: true,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Zed".into(),
+      command: "zed".into(),
+      is_tui: false,
+      macos_app: Some("Zed".into()),
+      opens_dir: true,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Sublime Text".into(),
+      command: "subl".into(),
+      is_tui: false,
+      macos_app: Some("Sublime Text".into()),
+      opens_dir: true,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "IntelliJ IDEA".into(),
+      command: "idea".into(),
+      is_tui: false,
+      macos_app: Some("IntelliJ IDEA".into()),
+      opens_dir: true,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Neovim".into(),
+      command: "nvim".into(),
+      is_tui: true,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Vim".into(),
+      command: "vim".into(),
+      is_tui: true,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Helix".into(),
+      command: "hx".into(),
+      is_tui: true,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Emacs".into(),
+      command: "emacs".into(),
+      is_tui: true,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Nano".into(),
+      command: "nano".into(),
+      is_tui: true,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    },
+    OpenApp {
+      name: "Micro".into(),
+      command: "micro".into(),
+      is_tui: true,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    },
+  ]
+}
+
+/// Quote `s` as one `sh` word.
+pub fn shell_quote(s: &str) -> String {
+  if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c)) {
+    return s.to_string();
+  }
+  format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Expand an AI CLI command template for `sh -c`. `{files}` becomes one
+/// quoted argument per file and `{mentions}` a single `@file @file` prompt
+/// argument; both expand to nothing when there are no files.
+pub fn expand_ai_command(template: &str, files: &[String]) -> String {
+  let quoted: Vec<String> = files.iter().map(|f| shell_quote(f)).collect();
+  let mentions = if files.is_empty() {
+    String::new()
+  } else {
+    let joined: Vec<String> = files.iter().map(|f| format!("@{f}")).collect();
+    shell_quote(&joined.join(" "))
+  };
+  template
+    .replace("{files}", &quoted.join(" "))
+    .replace("{mentions}", &mentions)
+    .trim_end()
+    .to_string()
+}
+
+/// Expand a command template for opening a directory elsewhere, e.g. in a
+/// new tmux window. `{dir}` becomes the quoted path and `{name}` its quoted
+/// last component.
+pub fn expand_dir_command(template: &str, dir: &Path) -> String {
+  let name = dir.file_name().map_or_else(|| dir.to_string_lossy(), |n| n.to_string_lossy());
+  template
+    .replace("{dir}", &shell_quote(&dir.to_string_lossy()))
+    .replace("{name}", &shell_quote(&name))
+}
+
+/// Whether new GUI windows can be opened from here: a Wayland or X11
+/// display, or a local macOS login. False on a plain TTY or over SSH.
+pub fn graphical_session() -> bool {
+  let var = |name| std::env::var_os(name).is_some_and(|v| !v.is_empty());
+  if cfg!(target_os = "macos") {
+    return !var("SSH_CONNECTION");
+  }
+  var("WAYLAND_DISPLAY") || var("DISPLAY")
+}
+
+/// Whether `app_name.app` is in one of the Applications folders.
+#[cfg(target_os = "macos")]
+pub fn macos_app_exists(app_name: &str) -> bool {
+  let mut dirs = vec![
+    "/Applications".into(),
+    "/System/Applications".into(),
+    "/System/Applications/Utilities".into(),
+  ];
+  if let Some(home) = dirs::home_dir() {
+    dirs.push(home.join("Applications").to_string_lossy().into_owned());
+  }
+  dirs.iter()
+    .any(|dir| Path::new(&format!("{dir}/{app_name}.app")).exists())
+}
+
+/// No `.app` bundles off macOS.
+#[cfg(not(target_os = "macos"))]
+pub fn macos_app_exists(_app_name: &str) -> bool {
+  false
+}
+
+/// Whether the app is installed.
+pub fn app_available(runner: &dyn ProcessRunner, app: &OpenApp) -> bool {
+  if cfg!(target_os = "macos")
+    && let Some(ref mac_app) = app.macos_app
+    && macos_app_exists(mac_app)
+  {
+    return true;
+  }
+  runner.exists(&app.command)
+}
+
+fn dedup_key(app: &OpenApp) -> String {
+  if app.command.is_empty() {
+    app.macos_app.clone().unwrap_or_default()
+  } else {
+    app.command.clone()
+  }
+}
+
+/// The installed apps, `custom` first, then the known ones when `use_known`.
+/// An app listed twice is kept once.
+pub fn detect_apps(runner: &dyn ProcessRunner, custom: &[OpenApp], use_known: bool) -> Vec<OpenApp> {
+  let mut apps = Vec::new();
+  let mut seen = std::collections::HashSet::new();
+
+  // Custom apps first
+  for app in custom {
+    if app_available(runner, app) && seen.insert(dedup_key(app)) {
+      apps.push(app.clone());
+    }
+  }
+
+  // Built-in apps (skipped when apps.toml exists on disk)
+  if use_known {
+    for app in known_apps() {
+      if app_available(runner, &app) && seen.insert(dedup_key(&app)) {
+        apps.push(app);
+      }
+    }
+  }
+
+  apps
+}
+
+/// Open `path` in the desktop's default app.
+pub fn open_default(path: &Path) -> Result<(), String> {
+  open::that_detached(path).map_err(|e| format!("Failed to open: {e}"))
+}
+
+/// Launch `app` on `path` without waiting for it.
+pub fn open_with_app(runner: &dyn ProcessRunner, path: &Path, app: &OpenApp) -> Result<(), String> {
+  let spec = match app.macos_app {
+    Some(ref mac_app) if cfg!(target_os = "macos") => ProcessSpec::new("open").arg("-a").arg(mac_app).arg(path),
+    _ => ProcessSpec::new(&app.command).arg(path),
+  };
+  runner.spawn(&spec).map_err(|e| format!("Failed to open with {}: {e}", app.name))
+}
+
+/// Open `url` in the browser: `open` on macOS, `xdg-open` elsewhere.
+pub fn open_url(runner: &dyn ProcessRunner, url: &str) -> Result<(), String> {
+  let program = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+  runner.spawn(&ProcessSpec::new(program).arg(url)).map_err(|e| format!("Failed to open {url}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::process::{MockRunner, SystemRunner};
+
+  #[test]
+  fn test_expand_ai_command() {
+    let files = vec!["src/main.rs".to_string(), "my notes.md".to_string()];
+    assert_eq!(expand_ai_command("claude {mentions}", &files), "claude '@src/main.rs @my notes.md'");
+    assert_eq!(expand_ai_command("aider {files}", &files), "aider src/main.rs 'my notes.md'");
+    assert_eq!(expand_ai_command("claude {mentions}", &[]), "claude");
+    assert_eq!(shell_quote("it's"), "'it'\\''s'");
+  }
+
+  #[test]
+  fn test_expand_dir_command() {
+    let dir = Path::new("/home/me/my project");
+    assert_eq!(
+      expand_dir_command("tmux new-window -c {dir} -n {name}", dir),
+      "tmux new-window -c '/home/me/my project' -n 'my project'"
+    );
+    assert_eq!(expand_dir_command("zellij action new-tab --cwd {dir}", Path::new("/srv")), "zellij action new-tab --cwd /srv");
+  }
+
+  #[test]
+  fn test_known_apps_not_empty() {
+    assert!(!known_apps().is_empty());
+  }
+
+  #[test]
+  fn test_known_apps_have_names_and_commands() {
+    for app in known_apps() {
+      assert!(!app.name.is_empty());
+      assert!(!app.command.is_empty());
+    }
+  }
+
+  #[test]
+  fn test_detect_apps_dedup_custom() {
+    // If custom app has same command as built-in, only custom appears
+    let custom = vec![OpenApp {
+      name: "My Vim".into(),
+      command: "vim".into(),
+      is_tui: true,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    }];
+    let apps = detect_apps(&MockRunner::with_installed(&["vim", "nvim"]), &custom, true);
+    let vim_count = apps.iter().filter(|a| a.command == "vim").count();
+    assert_eq!(vim_count, 1, "vim should appear once, found {vim_count}");
+    // The custom one comes first and wins
+    assert_eq!(apps[0].name, "My Vim");
+    assert_eq!(apps[1].name, "Neovim");
+  }
+
+  #[test]
+  fn test_detect_apps_custom_first() {
+    let custom = vec![OpenApp {
+      name: "Custom App".into(),
+      command: "custom_nonexistent_binary_12345".into(),
+      is_tui: false,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    }];
+    let apps = detect_apps(&SystemRunner, &custom, true);
+    // Custom nonexistent app should not appear
+    assert!(!apps.iter().any(|a| a.command == "custom_nonexistent_binary_12345"));
+  }
+
+  #[test]
+  fn test_command_exists_which() {
+    // `which` itself should always exist
+    assert!(SystemRunner.exists("which"));
+  }
+
+  #[test]
+  fn test_command_not_exists() {
+    assert!(!SystemRunner.exists("nonexistent_binary_xyz_99999"));
+  }
+
+  #[test]
+  fn test_detect_apps_skips_known_when_flag_false() {
+    let custom = vec![OpenApp {
+      name: "Custom".into(),
+      command: "which".into(), // use `which` since it always exists
+      is_tui: false,
+      macos_app: None,
+      opens_dir: false,
+      dir_mode: false,
+      project: false,
+    }];
+    let apps = detect_apps(&MockRunner::with_installed(&["which", "vim"]), &custom, false);
+    // Only custom app should appear, no built-in known apps
+    assert_eq!(apps.len(), 1);
+    assert_eq!(apps[0].name, "Custom");
+  }
+
+  #[test]
+  fn test_detect_apps_includes_known_when_flag_true() {
+    let apps = detect_apps(&MockRunner::with_installed(&["code", "hx"]), &[], true);
+    let names: Vec<&str> = apps.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, vec!["VS Code", "Helix"]);
+  }
+
+  #[test]
+  fn test_open_with_app_spawns_command() {
+    let app = known_apps().into_iter().find(|a| a.command == "zed").unwrap();
+    let runner = MockRunner::default();
+    open_with_app(&runner, Path::new("/tmp/notes.md"), &app).unwrap();
+    let expected = if cfg!(target_os = "macos") { "open -a Zed /tmp/notes.md" } else { "zed /tmp/notes.md" };
+    assert_eq!(runner.commands(), vec![expected]);
+
+    let runner = MockRunner { failing: vec!["zed".into(), "open".into()], ..MockRunner::default() };
+    let err = open_with_app(&runner, Path::new("/tmp/notes.md"), &app).unwrap_err();
+    assert!(err.starts_with("Failed to open with Zed"));
+  }
+}
@@ -0,0 +1,110 @@
+//! Where tfl keeps its files, following the XDG base directory spec:
+//! settings in the config dir, data worth keeping across runs in the state
+//! dir and disposable data in the cache dir.
+
+use std::path::{Path, PathBuf};
+
+use crate::fs::ops;
+
+/// `$XDG_CONFIG_HOME/tfl`: config.toml and apps.toml
+pub fn config_dir() -> Option<PathBuf> {
+  dirs::config_dir().map(|d| d.join("tfl"))
+}
+
+/// `$XDG_STATE_HOME/tfl`: favorites, last directory, session journals,
+/// panic reports and handler backups. Platforms without a state dir use
+/// the local data dir.
+pub fn state_dir() -> PathBuf {
+  dirs::state_dir()
+    .or_else(dirs::data_local_dir)
+    .unwrap_or_else(std::env::temp_dir)
+    .join("tfl")
+}
+
+/// `$XDG_CACHE_HOME/tfl`: logs and the shared clipboard
+pub fn cache_dir() -> PathBuf {
+  dirs::cache_dir()
+    .unwrap_or_else(std::env::temp_dir)
+    .join("tfl")
+}
+
+/// Move files older versions kept in the config and cache dirs to the state
+/// dir. Returns the moved destinations.
+pub fn migrate() -> Vec<PathBuf> {
+  let mut moved = Vec::new();
+  if let Some(config) = config_dir() {
+    moved.extend(migrate_entries(&config, &state_dir(), |name| {
+      name == "favorites" || name == "handler-backup"
+    }));
+  }
+  moved.extend(migrate_entries(&cache_dir(), &state_dir(), |name| {
+    name == "last_dir"
+      || (name.starts_with("session-") && name.ends_with(".json"))
+      || (name.starts_with("panic-") && name.ends_with(".txt"))
+  }));
+  moved
+}
+
+/// Move the entries of `from` whose names pass `wanted` into `to`, leaving
+/// any that already exist there alone.
+fn migrate_entries(from: &Path, to: &Path, wanted: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+  let Ok(read_dir) = std::fs::read_dir(from) else {
+    return Vec::new();
+  };
+  if from == to {
+    return Vec::new();
+  }
+  let mut moved = Vec::new();
+  for entry in read_dir.flatten() {
+    let name = entry.file_name();
+    if !name.to_str().is_some_and(&wanted) {
+      continue;
+    }
+    let dest = to.join(&name);
+    if dest.exists() || std::fs::create_dir_all(to).is_err() {
+      continue;
+    }
+    let source = entry.path();
+    // Fall back to copying when the dirs are on different filesystems
+    let result = std::fs::rename(&source, &dest).or_else(|_| {
+      ops::copy_path(&source, &dest)?;
+      if source.is_dir() { std::fs::remove_dir_all(&source) } else { std::fs::remove_file(&source) }
+    });
+    match result {
+      Ok(()) => moved.push(dest),
+      Err(e) => tracing::warn!("failed to move {} to {}: {e}", source.display(), dest.display()),
+    }
+  }
+  moved
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_migrate_entries_moves_wanted_files_once() {
+    let dir = std::env::temp_dir().join(format!("tfl_paths_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let (old, new) = (dir.join("config"), dir.join("state"));
+    std::fs::create_dir_all(old.join("handler-backup")).unwrap();
+    std::fs::write(old.join("handler-backup/mime-handler"), "org.gnome.Nautilus.desktop").unwrap();
+    std::fs::write(old.join("favorites"), "+ /tmp\n").unwrap();
+    std::fs::write(old.join("config.toml"), "").unwrap();
+
+    let wanted = |name: &str| name == "favorites" || name == "handler-backup";
+    let mut moved = migrate_entries(&old, &new, wanted);
+    moved.sort();
+    assert_eq!(moved, vec![new.join("favorites"), new.join("handler-backup")]);
+    assert_eq!(std::fs::read_to_string(new.join("favorites")).unwrap(), "+ /tmp\n");
+    assert!(new.join("handler-backup/mime-handler").exists());
+    assert!(!old.join("favorites").exists());
+    assert!(old.join("config.toml").exists());
+
+    // Files already in the new place are never overwritten
+    std::fs::write(old.join("favorites"), "+ /old\n").unwrap();
+    assert!(migrate_entries(&old, &new, wanted).is_empty());
+    assert_eq!(std::fs::read_to_string(new.join("favorites")).unwrap(), "+ /tmp\n");
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -1,12 +1,16 @@
+//! Archive listing, extraction and compression.
+
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
 use bzip2::read::BzDecoder;
 use flate2::read::GzDecoder;
-use ratatui::style::Style;
-use ratatui::text::{Line, Span};
+use ratatui_core::style::Style;
+use ratatui_core::text::{Line, Span};
 use tar::Archive as TarArchive;
 
+use crate::fs::progress::{Counts, CountingReader, Progress};
+use crate::fs::walk::VisitedDirs;
 use crate::theme::Theme;
 use xz2::read::XzDecoder;
 use zip::ZipArchive;
@@ -69,9 +73,186 @@ fn format_size(size: u64) -> String {
   }
 }
 
-/// Render a simple archive summary (without reading contents)
-pub fn render_archive_summary(archive_type: &str, file_size: u64, theme: &Theme) -> Vec<Line<'static>> {
-  let format_name = match archive_type {
+/// Entries kept from an archive listing; the rest are only counted
+pub const MAX_LISTED_ENTRIES: usize = 1000;
+
+/// One file or directory inside an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+  /// Path inside the archive, as stored
+  pub path: String,
+  /// Uncompressed size
+  pub size: u64,
+  /// A directory entry
+  pub is_dir: bool,
+}
+
+/// What an archive holds: the first `limit` entries in archive order, and
+/// the count and uncompressed size of all of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveListing {
+  /// The first `limit` entries
+  pub entries: Vec<ArchiveEntry>,
+  /// Entries in the whole archive
+  pub total_entries: usize,
+  /// Uncompressed size of the whole archive
+  pub total_size: u64,
+}
+
+impl ArchiveListing {
+  fn push(&mut self, entry: ArchiveEntry, limit: usize) {
+    self.total_entries += 1;
+    self.total_size += entry.size;
+    if self.entries.len() < limit {
+      self.entries.push(entry);
+    }
+  }
+}
+
+/// List the entries of the archive at `path`, keeping the first `limit`.
+/// Compressed tarballs are decompressed to the end to count everything.
+pub fn list_archive(path: &Path, limit: usize) -> Result<ArchiveListing, String> {
+  if archive_type(path) == Some("zip") {
+    list_zip(path, limit)
+  } else {
+    list_tar(open_tar(path)?, limit)
+  }
+}
+
+fn list_zip(path: &Path, limit: usize) -> Result<ArchiveListing, String> {
+  let file = std::fs::File::open(path).map_err(|e| format!("Failed to open archive: {e}"))?;
+  let mut archive = ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {e}"))?;
+  let mut listing = ArchiveListing::default();
+  for i in 0..archive.len() {
+    let entry = archive.by_index_raw(i).map_err(|e| format!("Failed to read zip entry: {e}"))?;
+    listing.push(
+      ArchiveEntry { path: entry.name().to_string(), size: entry.size(), is_dir: entry.is_dir() },
+      limit,
+    );
+  }
+  Ok(listing)
+}
+
+fn list_tar(mut archive: TarArchive<Box<dyn Read>>, limit: usize) -> Result<ArchiveListing, String> {
+  let mut listing = ArchiveListing::default();
+  for entry in archive.entries().map_err(|e| format!("Failed to read tar: {e}"))? {
+    let entry = entry.map_err(|e| format!("Failed to read tar entry: {e}"))?;
+    let path = entry.path().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+    let is_dir = entry.header().entry_type().is_dir();
+    listing.push(ArchiveEntry { path, size: entry.size(), is_dir }, limit);
+  }
+  Ok(listing)
+}
+
+/// An entry's path as stored, without a leading `./` or trailing `/`.
+pub fn clean_entry_name(name: &str) -> &str {
+  let name = name.trim_end_matches('/');
+  name.strip_prefix("./").unwrap_or(name)
+}
+
+/// The tar stream of a (possibly compressed) tarball.
+fn open_tar(path: &Path) -> Result<TarArchive<Box<dyn Read>>, String> {
+  let file = std::fs::File::open(path).map_err(|e| format!("Failed to open archive: {e}"))?;
+  let reader: Box<dyn Read> = match archive_type(path) {
+    Some("tar.gz" | "gz") => Box::new(GzDecoder::new(file)),
+    Some("tar.bz2" | "bz2") => Box::new(BzDecoder::new(file)),
+    Some("tar.xz" | "xz") => Box::new(XzDecoder::new(file)),
+    Some("tar") => Box::new(file),
+    _ => return Err("Unsupported archive format".to_string()),
+  };
+  Ok(TarArchive::new(reader))
+}
+
+/// Contents of the file `name` (a cleaned entry path) in the archive.
+pub fn read_archive_entry(path: &Path, name: &str) -> Result<Vec<u8>, String> {
+  let mut data = Vec::new();
+  if archive_type(path) == Some("zip") {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open archive: {e}"))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {e}"))?;
+    for i in 0..archive.len() {
+      let mut entry = archive.by_index(i).map_err(|e| format!("Failed to read zip entry: {e}"))?;
+      if !entry.is_dir() && clean_entry_name(entry.name()) == name {
+        entry.read_to_end(&mut data).map_err(|e| format!("Failed to read {name}: {e}"))?;
+        return Ok(data);
+      }
+    }
+  } else {
+    let mut archive = open_tar(path)?;
+    for entry in archive.entries().map_err(|e| format!("Failed to read tar: {e}"))? {
+      let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {e}"))?;
+      let entry_name = entry.path().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+      if !entry.header().entry_type().is_dir() && clean_entry_name(&entry_name) == name {
+        entry.read_to_end(&mut data).map_err(|e| format!("Failed to read {name}: {e}"))?;
+        return Ok(data);
+      }
+    }
+  }
+  Err(format!("{name} not found in archive"))
+}
+
+/// Extract the entry `name` (a cleaned entry path) into `dest_dir` under
+/// its own file name, with everything below it when it is a directory.
+/// Takes another name when that one exists. Returns the created path.
+pub fn extract_archive_entry(path: &Path, name: &str, dest_dir: &Path) -> Result<PathBuf, String> {
+  let base = Path::new(name).file_name().ok_or_else(|| format!("Invalid entry name: {name}"))?;
+  let top = crate::fs::ops::unique_dest_path(&dest_dir.join(base));
+  let prefix = format!("{name}/");
+  // Where an entry goes under `top`, or None when it is not `name` or
+  // below it. Names that climb out with `..` are skipped.
+  let target = |entry_name: &str| -> Option<PathBuf> {
+    let entry_name = clean_entry_name(entry_name);
+    let rest = if entry_name == name { "" } else { entry_name.strip_prefix(&prefix)? };
+    let rest = Path::new(rest);
+    // Joining an empty path would leave a trailing slash
+    let dest = if rest.as_os_str().is_empty() { top.clone() } else { top.join(rest) };
+    rest.components().all(|c| matches!(c, std::path::Component::Normal(_))).then_some(dest)
+  };
+  let mut found = false;
+  let mut write = |dest: PathBuf, is_dir: bool, reader: &mut dyn Read| -> Result<(), String> {
+    found = true;
+    if is_dir {
+      return std::fs::create_dir_all(&dest).map_err(|e| format!("Failed to create {}: {e}", dest.display()));
+    }
+    if let Some(parent) = dest.parent() {
+      std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let mut out = std::fs::File::create(&dest).map_err(|e| format!("Failed to create {}: {e}", dest.display()))?;
+    std::io::copy(reader, &mut out).map_err(|e| format!("Failed to write {}: {e}", dest.display()))?;
+    Ok(())
+  };
+  if archive_type(path) == Some("zip") {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open archive: {e}"))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {e}"))?;
+    for i in 0..archive.len() {
+      let mut entry = archive.by_index(i).map_err(|e| format!("Failed to read zip entry: {e}"))?;
+      if let Some(dest) = target(entry.name()) {
+        let is_dir = entry.is_dir();
+        write(dest, is_dir, &mut entry)?;
+      }
+    }
+  } else {
+    let mut archive = open_tar(path)?;
+    for entry in archive.entries().map_err(|e| format!("Failed to read tar: {e}"))? {
+      let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {e}"))?;
+      let entry_type = entry.header().entry_type();
+      // Links and devices are left out, like in a plain copy of the entry
+      if !(entry_type.is_file() || entry_type.is_dir()) {
+        continue;
+      }
+      let entry_name = entry.path().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+      if let Some(dest) = target(&entry_name) {
+        write(dest, entry_type.is_dir(), &mut entry)?;
+      }
+    }
+  }
+  if !found {
+    return Err(format!("{name} not found in archive"));
+  }
+  Ok(top)
+}
+
+fn format_name(archive_type: &str) -> &'static str {
+  match archive_type {
     "zip" => "ZIP",
     "tar" => "TAR",
     "tar.gz" => "TAR.GZ",
@@ -81,7 +262,12 @@ pub fn render_archive_summary(archive_type: &str, file_size: u64, theme: &Theme)
     "bz2" => "BZIP2",
     "xz" => "XZ",
     _ => "Archive",
-  };
+  }
+}
+
+/// Render a simple archive summary (without reading contents)
+pub fn render_archive_summary(archive_type: &str, file_size: u64, theme: &Theme) -> Vec<Line<'static>> {
+  let format_name = format_name(archive_type);
 
   vec![
     Line::from(""),
@@ -106,15 +292,71 @@ pub fn render_archive_summary(archive_type: &str, file_size: u64, theme: &Theme)
   ]
 }
 
+/// Render an archive's listing: the summary header, then one row per
+/// entry with its uncompressed size.
+pub fn render_archive_contents(
+  archive_type: &str,
+  file_size: u64,
+  listing: &ArchiveListing,
+  theme: &Theme,
+) -> Vec<Line<'static>> {
+  let entries = if listing.total_entries == 1 { "entry" } else { "entries" };
+  let mut lines = vec![
+    Line::from(""),
+    Line::from(Span::styled(format!("  {} archive", format_name(archive_type)), Style::default().fg(theme.info))),
+    Line::from(""),
+    Line::from(vec![
+      Span::styled("  Size: ", Style::default().fg(theme.text_dim)),
+      Span::styled(format_size(file_size), Style::default().fg(theme.warning)),
+      Span::styled(
+        format!("  ({} {entries}, {} unpacked)", listing.total_entries, format_size(listing.total_size)),
+        Style::default().fg(theme.text_dim),
+      ),
+    ]),
+    Line::from(Span::styled("  Press x to extract", Style::default().fg(theme.text_dim))),
+    Line::from(""),
+  ];
+  for entry in &listing.entries {
+    let (size, name_color) = if entry.is_dir {
+      (String::new(), theme.info)
+    } else {
+      (format_size(entry.size), theme.text)
+    };
+    lines.push(Line::from(vec![
+      Span::styled(format!("  {size:>10}  "), Style::default().fg(theme.text_dim)),
+      Span::styled(entry.path.clone(), Style::default().fg(name_color)),
+    ]));
+  }
+  let hidden = listing.total_entries - listing.entries.len();
+  if hidden > 0 {
+    lines.push(Line::from(Span::styled(format!("  … and {hidden} more"), Style::default().fg(theme.text_muted))));
+  }
+  lines
+}
+
 /// Extract entire ZIP archive
 pub fn extract_zip(path: &Path, dest_dir: &Path) -> Result<(), String> {
+  extract_zip_tracked(path, dest_dir, &Progress::default())
+}
+
+fn extract_zip_tracked(path: &Path, dest_dir: &Path, progress: &Progress) -> Result<(), String> {
   let file = std::fs::File::open(path)
     .map_err(|e| format!("Failed to open archive: {e}"))?;
 
   let mut archive = ZipArchive::new(file)
     .map_err(|e| format!("Invalid ZIP archive: {e}"))?;
 
+  // The central directory has every size up front
+  let mut total = Counts { entries: archive.len() as u64, bytes: 0 };
   for i in 0..archive.len() {
+    total.bytes += archive.by_index_raw(i).map_or(0, |f| f.size());
+  }
+  progress.set_total(total);
+
+  for i in 0..archive.len() {
+    if progress.is_cancelled() {
+      return Err(CANCELLED.to_string());
+    }
     let mut file = archive.by_index(i)
       .map_err(|e| format!("Failed to read entry: {e}"))?;
 
@@ -133,6 +375,7 @@ pub fn extract_zip(path: &Path, dest_dir: &Path) -> Result<(), String> {
       std::io::copy(&mut file, &mut outfile)
         .map_err(|e| format!("Failed to extract file: {e}"))?;
     }
+    progress.add(1, file.size());
   }
 
   Ok(())
@@ -145,6 +388,22 @@ pub fn extract_tar<R: Read>(reader: R, dest_dir: &Path) -> Result<(), String> {
     .map_err(|e| format!("Failed to extract tar: {e}"))
 }
 
+/// Like `extract_tar`, stopping between entries once `progress` is
+/// cancelled. Bytes are counted by the reader, entries here.
+fn extract_tar_tracked<R: Read>(reader: R, dest_dir: &Path, progress: &Progress) -> Result<(), String> {
+  let mut archive = TarArchive::new(reader);
+  std::fs::create_dir_all(dest_dir).map_err(|e| format!("Failed to create directory: {e}"))?;
+  for entry in archive.entries().map_err(|e| format!("Failed to extract tar: {e}"))? {
+    if progress.is_cancelled() {
+      return Err(CANCELLED.to_string());
+    }
+    let mut entry = entry.map_err(|e| format!("Failed to extract tar: {e}"))?;
+    entry.unpack_in(dest_dir).map_err(|e| format!("Failed to extract tar: {e}"))?;
+    progress.add(1, 0);
+  }
+  Ok(())
+}
+
 /// Extract TAR.GZ archive
 pub fn extract_tar_gz(path: &Path, dest_dir: &Path) -> Result<(), String> {
   let file = std::fs::File::open(path)
@@ -180,6 +439,27 @@ pub fn extract_tar_file(path: &Path, dest_dir: &Path) -> Result<(), String> {
   extract_tar(file, dest_dir)
 }
 
+/// What a cancelled extraction fails with
+pub const CANCELLED: &str = "Cancelled";
+
+/// Like `extract_archive`, reporting to `progress` and stopping between
+/// entries once it is cancelled. A tarball's progress is how much of the
+/// (compressed) file was read, as its entries are only known by reading it.
+pub fn extract_archive_tracked(path: &Path, dest_dir: &Path, progress: &Progress) -> Result<(), String> {
+  let decoder: fn(CountingReader<'_, std::fs::File>) -> Box<dyn Read + '_> = match archive_type(path) {
+    Some("zip") => return extract_zip_tracked(path, dest_dir, progress),
+    Some("tar.gz" | "gz") => |r| Box::new(GzDecoder::new(r)),
+    Some("tar.bz2" | "bz2") => |r| Box::new(BzDecoder::new(r)),
+    Some("tar.xz" | "xz") => |r| Box::new(XzDecoder::new(r)),
+    Some("tar") => |r| Box::new(r),
+    _ => return Err("Unsupported archive format".to_string()),
+  };
+  let file = std::fs::File::open(path).map_err(|e| format!("Failed to open archive: {e}"))?;
+  let size = file.metadata().map_or(0, |m| m.len());
+  progress.set_total(Counts { entries: 0, bytes: size });
+  extract_tar_tracked(decoder(CountingReader::new(file, progress)), dest_dir, progress)
+}
+
 /// Extract archive based on detected type
 pub fn extract_archive(path: &Path, dest_dir: &Path) -> Result<(), String> {
   match archive_type(path) {
@@ -215,7 +495,7 @@ fn compress_zip(paths: &[PathBuf], dest: &Path) -> Result<(), String> {
 
   for path in paths {
     if path.is_dir() {
-      zip_add_dir_recursive(&mut zip, path, path, options)
+      zip_add_dir_recursive(&mut zip, path, path, options, &mut VisitedDirs::default())
         .map_err(|e| format!("Failed to add directory: {e}"))?;
     } else {
       let name = path.file_name()
@@ -239,7 +519,11 @@ fn zip_add_dir_recursive(
   base: &Path,
   path: &Path,
   options: zip::write::SimpleFileOptions,
+  visited: &mut VisitedDirs,
 ) -> Result<(), String> {
+  if !visited.enter(path) {
+    return Ok(());
+  }
   let base_parent = base.parent().unwrap_or(base);
   for entry in std::fs::read_dir(path)
     .map_err(|e| format!("Failed to read directory: {e}"))?
@@ -253,7 +537,7 @@ fn zip_add_dir_recursive(
     if entry_path.is_dir() {
       zip.add_directory(&name, options)
         .map_err(|e| format!("Failed to add directory: {e}"))?;
-      zip_add_dir_recursive(zip, base, &entry_path, options)?;
+      zip_add_dir_recursive(zip, base, &entry_path, options, visited)?;
     } else {
       zip.start_file(&name, options)
         .map_err(|e| format!("Failed to add file: {e}"))?;
@@ -291,6 +575,8 @@ fn compress_tar(paths: &[PathBuf], dest: &Path, compression: &str) -> Result<(),
 
 fn compress_tar_inner<W: Write>(paths: &[PathBuf], writer: W) -> Result<(), String> {
   let mut builder = tar::Builder::new(writer);
+  // Store symlinks as links: following them loops forever on a cycle
+  builder.follow_symlinks(false);
 
   for path in paths {
     let name = path.file_name()
@@ -372,6 +658,41 @@ mod tests {
     assert_eq!(archive_type(Path::new("test.txt")), None);
   }
 
+  #[test]
+  fn test_list_archive_caps_entries_but_counts_all() {
+    let dir = test_dir("list");
+    let src = dir.join("pkg");
+    fs::create_dir_all(src.join("sub")).unwrap();
+    fs::write(src.join("a.txt"), "12345").unwrap();
+    fs::write(src.join("sub/b.txt"), "123").unwrap();
+    let tarball = dir.join("pkg.tar.gz");
+    compress_to_archive(std::slice::from_ref(&src), &tarball, "tar.gz").unwrap();
+    let zip_path = dir.join("pkg.zip");
+    compress_to_archive(std::slice::from_ref(&src), &zip_path, "zip").unwrap();
+
+    for archive in [&tarball, &zip_path] {
+      let listing = list_archive(archive, 100).unwrap();
+      assert_eq!(listing.total_size, 8);
+      assert!(listing.entries.iter().any(|e| e.path.ends_with("sub/b.txt") && e.size == 3 && !e.is_dir));
+      assert!(listing.entries.iter().any(|e| e.is_dir && e.path.trim_end_matches('/').ends_with("sub")));
+
+      let capped = list_archive(archive, 1).unwrap();
+      assert_eq!(capped.entries.len(), 1);
+      assert_eq!(capped.total_entries, listing.total_entries);
+      let text: Vec<String> = render_archive_contents("zip", 100, &capped, &Theme::dark())
+        .iter()
+        .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+      assert!(text[3].ends_with(&format!("({} entries, 8 B unpacked)", listing.total_entries)));
+      assert_eq!(text.last().unwrap(), &format!("  … and {} more", listing.total_entries - 1));
+    }
+
+    assert!(list_archive(&src.join("a.txt"), 10).is_err());
+    fs::write(dir.join("broken.zip"), "not a zip").unwrap();
+    assert!(list_archive(&dir.join("broken.zip"), 10).is_err());
+    cleanup_dir(&dir);
+  }
+
   #[test]
   fn test_extract_zip() {
     let dir = test_dir("extract_zip");
@@ -498,6 +819,33 @@ mod tests {
     cleanup_dir(&dir);
   }
 
+  #[test]
+  fn test_extract_archive_tracked_reports_progress_and_cancels() {
+    let dir = test_dir("tracked");
+    let src = dir.join("pkg");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("a.txt"), "12345").unwrap();
+    fs::write(src.join("b.txt"), "123").unwrap();
+    for format in ["zip", "tar.gz"] {
+      let archive = dir.join(format!("pkg.{format}"));
+      compress_to_archive(std::slice::from_ref(&src), &archive, format).unwrap();
+      let out = dir.join(format!("out_{format}"));
+
+      let progress = Progress::default();
+      extract_archive_tracked(&archive, &out, &progress).unwrap();
+      assert_eq!(fs::read_to_string(out.join("pkg/a.txt")).unwrap(), "12345");
+      // The zip has no entry of its own for pkg/
+      assert!(progress.done().entries >= 2, "{format}");
+      assert_eq!(progress.percent(), Some(100), "{format}");
+
+      let cancelled = Progress::default();
+      cancelled.cancel();
+      assert_eq!(extract_archive_tracked(&archive, &dir.join("none"), &cancelled), Err(CANCELLED.to_string()));
+      assert!(!dir.join("none/pkg").exists());
+    }
+    let _ = fs::remove_dir_all(&dir);
+  }
+
   #[test]
   fn test_format_size() {
     assert_eq!(format_size(100), "100 B");
@@ -529,6 +877,23 @@ mod tests {
     cleanup_dir(&dir);
   }
 
+  #[test]
+  fn test_compress_dir_with_symlink_cycle_terminates() {
+    let dir = test_dir("compress_cycle");
+    let src = dir.join("project");
+    fs::create_dir_all(src.join("sub")).unwrap();
+    fs::write(src.join("sub").join("a.txt"), "a").unwrap();
+    std::os::unix::fs::symlink(&src, src.join("sub").join("loop")).unwrap();
+
+    for format in ["zip", "tar.gz"] {
+      let dest = dir.join(format!("archive.{format}"));
+      compress_to_archive(std::slice::from_ref(&src), &dest, format).unwrap();
+      assert!(dest.exists());
+    }
+
+    cleanup_dir(&dir);
+  }
+
   #[test]
   fn test_compress_tar_gz() {
     let dir = test_dir("compress_tar_gz");
@@ -1,8 +1,10 @@
+//! Git blame rendering.
+
 use std::collections::HashMap;
 use std::path::Path;
 
-use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Span};
+use ratatui_core::style::{Color, Modifier, Style};
+use ratatui_core::text::{Line, Span};
 
 use crate::git::GitRepo;
 use crate::theme::Theme;
@@ -10,21 +12,29 @@ use crate::theme::Theme;
 /// Information about a single line's blame
 #[derive(Debug, Clone)]
 pub struct BlameLine {
+  /// 1-based line number
   pub line_num: usize,
+  /// Abbreviated id of the commit that last changed the line
   pub commit_hash: String,
+  /// That commit's author
   pub author: String,
+  /// That commit's relative time, e.g. `3d ago`
   pub date: String,
+  /// Text of the line
   pub content: String,
 }
 
 /// Complete blame data for a file
 #[derive(Debug, Clone)]
 pub struct BlameData {
+  /// One per line of the file
   pub lines: Vec<BlameLine>,
+  /// A colour per commit, so lines from the same commit match
   pub commit_colors: HashMap<String, Color>,
 }
 
 impl BlameData {
+  /// Blame over `lines`, with a colour picked per commit.
   pub fn new(lines: Vec<BlameLine>) -> Self {
     let mut commit_colors = HashMap::new();
     let colors = [
@@ -49,6 +59,7 @@ impl BlameData {
     Self { lines, commit_colors }
   }
 
+  /// Draw up to `max_lines` lines from `scroll_offset`.
   pub fn render(&self, max_lines: usize, scroll_offset: usize, theme: &Theme) -> Vec<Line<'static>> {
     self.lines
       .iter()
@@ -0,0 +1,1504 @@
+//! Git diff preview module
+//!
+//! Provides diff generation for modified files and rendering with color highlighting.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use git2::{ApplyLocation, Delta, Diff, DiffFindOptions, DiffOptions, Repository, Status};
+use ratatui_core::style::{Modifier, Style};
+use ratatui_core::text::{Line, Span};
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::theme::Theme;
+
+/// Represents a line in a diff hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLineKind {
+  /// Unchanged line around a change
+  Context,
+  /// Line only in the new side
+  Added,
+  /// Line only in the old side
+  Removed,
+  /// File header, e.g. `diff --git`
+  Header,
+  /// The `@@` line starting a hunk
+  HunkHeader,
+}
+
+/// A single line in the diff output
+#[derive(Debug, Clone)]
+pub struct DiffLine {
+  /// What the line is
+  pub kind: DiffLineKind,
+  /// Text without the `+`/`-`/` ` prefix
+  pub content: String,
+  /// Line number in the old side, if the line is there
+  pub old_line_no: Option<u32>,
+  /// Line number in the new side, if the line is there
+  pub new_line_no: Option<u32>,
+}
+
+/// A detected rename or copy: the file's previous path (relative to the repo root)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameInfo {
+  /// Previous path
+  pub from: PathBuf,
+  /// Current path
+  pub to: PathBuf,
+  /// Copied rather than moved
+  pub copied: bool,
+}
+
+/// Contains the parsed diff for a file
+#[derive(Debug, Clone)]
+pub struct FileDiff {
+  /// Lines in display order
+  pub lines: Vec<DiffLine>,
+  /// Indices of the hunk headers in `lines`
+  pub hunks: Vec<usize>,
+  /// Set when git detected a rename or copy
+  pub rename: Option<RenameInfo>,
+  /// Raw patch text before the first hunk (`diff --git`, `index`, `---`/`+++` lines)
+  pub patch_header: String,
+  /// Raw patch text of each hunk including its `@@` header, in `hunks` order
+  pub hunk_patches: Vec<String>,
+}
+
+impl FileDiff {
+  /// An empty diff.
+  pub fn new() -> Self {
+    Self {
+      lines: Vec::new(),
+      hunks: Vec::new(),
+      rename: None,
+      patch_header: String::new(),
+      hunk_patches: Vec::new(),
+    }
+  }
+
+  /// Returns true if the diff is empty (no changes)
+  pub fn is_empty(&self) -> bool {
+    self.lines.is_empty()
+  }
+
+  /// The whole diff as a patch that `git apply` accepts
+  pub fn patch(&self) -> String {
+    let mut patch = self.patch_header.clone();
+    for hunk in &self.hunk_patches {
+      patch.push_str(hunk);
+    }
+    patch
+  }
+
+  /// A patch containing only the given hunk
+  pub fn hunk_patch(&self, hunk: usize) -> Option<String> {
+    self.hunk_patches.get(hunk).map(|h| format!("{}{h}", self.patch_header))
+  }
+
+  /// The hunk that contains the given line (the first hunk for lines above it)
+  pub fn hunk_at_line(&self, line: usize) -> Option<usize> {
+    if self.hunks.is_empty() {
+      return None;
+    }
+    Some(self.hunks.iter().rposition(|&idx| idx <= line).unwrap_or(0))
+  }
+}
+
+impl Default for FileDiff {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Which two versions of a file a diff compares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffMode {
+  /// Unstaged changes: working tree vs index
+  #[default]
+  WorktreeIndex,
+  /// Staged changes: index vs HEAD
+  IndexHead,
+  /// All uncommitted changes: working tree vs HEAD
+  WorktreeHead,
+}
+
+impl DiffMode {
+  /// The next mode in the cycle unstaged -> staged -> all
+  pub fn next(self) -> Self {
+    match self {
+      DiffMode::WorktreeIndex => DiffMode::IndexHead,
+      DiffMode::IndexHead => DiffMode::WorktreeHead,
+      DiffMode::WorktreeHead => DiffMode::WorktreeIndex,
+    }
+  }
+
+  /// The stage/unstage operation available for hunks in this mode
+  pub fn stage_action(self) -> Option<HunkAction> {
+    match self {
+      DiffMode::WorktreeIndex => Some(HunkAction::Stage),
+      DiffMode::IndexHead => Some(HunkAction::Unstage),
+      DiffMode::WorktreeHead => None,
+    }
+  }
+
+  /// Sides being compared, for the preview title.
+  pub fn label(self) -> &'static str {
+    match self {
+      DiffMode::WorktreeIndex => "worktree vs index",
+      DiffMode::IndexHead => "index vs HEAD",
+      DiffMode::WorktreeHead => "worktree vs HEAD",
+    }
+  }
+
+  /// Run this diff over the repository, limited by `opts`
+  fn diff<'r>(self, repo: &'r Repository, opts: &mut DiffOptions) -> Option<Diff<'r>> {
+    // An unborn HEAD diffs against the empty tree
+    let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    match self {
+      DiffMode::WorktreeIndex => repo.diff_index_to_workdir(None, Some(opts)),
+      DiffMode::IndexHead => repo.diff_tree_to_index(head.as_ref(), None, Some(opts)),
+      DiffMode::WorktreeHead => repo.diff_tree_to_workdir_with_index(head.as_ref(), Some(opts)),
+    }
+    .ok()
+  }
+}
+
+/// An operation on a single hunk of the shown diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkAction {
+  /// Add the hunk to the index (unstaged view)
+  Stage,
+  /// Take the hunk out of the index (staged view)
+  Unstage,
+  /// Discard the hunk from the working tree (unstaged view)
+  Revert,
+}
+
+impl HunkAction {
+  /// Capitalised name of the action, for prompts and status messages.
+  pub fn verb(self) -> &'static str {
+    match self {
+      HunkAction::Stage => "Stage",
+      HunkAction::Unstage => "Unstage",
+      HunkAction::Revert => "Revert",
+    }
+  }
+}
+
+/// Apply `action` to hunk number `hunk` of the file's diff in `mode`.
+///
+/// Unstaging and reverting apply the hunk in reverse (to the index and the working
+/// tree respectively), so only the selected hunk's lines change.
+pub fn apply_hunk(repo_root: &Path, file_path: &Path, mode: DiffMode, hunk: usize, action: HunkAction) -> Result<()> {
+  let (reverse, location) = match (action, mode) {
+    (HunkAction::Stage, DiffMode::WorktreeIndex) => (false, ApplyLocation::Index),
+    (HunkAction::Unstage, DiffMode::IndexHead) => (true, ApplyLocation::Index),
+    (HunkAction::Revert, DiffMode::WorktreeIndex) => (true, ApplyLocation::WorkDir),
+    _ => bail!("{} is not available in the {} view", action.verb().to_lowercase(), mode.label()),
+  };
+
+  let repo = Repository::open(repo_root)?;
+  let rel_path = file_path.strip_prefix(repo_root)?;
+  let mut opts = DiffOptions::new();
+  opts.pathspec(rel_path);
+  let diff = mode.diff(&repo, &mut opts).context("could not diff file")?;
+  let mut patch = collect_file_diff(&diff, rel_path)
+    .and_then(|d| d.hunk_patch(hunk))
+    .ok_or_else(|| anyhow!("hunk no longer exists"))?;
+  if reverse {
+    patch = reverse_patch(&patch)?;
+  }
+
+  let patch = Diff::from_buffer(patch.as_bytes())?;
+  repo.apply(&patch, location, None)?;
+  Ok(())
+}
+
+/// Invert a single-file patch so applying it undoes the original change
+fn reverse_patch(patch: &str) -> Result<String> {
+  let mut out = String::with_capacity(patch.len());
+  let mut in_hunk = false;
+  // Changed lines of the current run, already inverted; removals are emitted before
+  // additions so "\ No newline at end of file" markers stay in a valid position
+  let mut removed = String::new();
+  let mut added = String::new();
+  let mut last_was_removed = false;
+
+  for line in patch.split_inclusive('\n') {
+    if let Some(rest) = line.strip_prefix("@@ -") {
+      out.push_str(&removed);
+      out.push_str(&added);
+      removed.clear();
+      added.clear();
+      // "@@ -a,b +c,d @@ ..." -> "@@ -c,d +a,b @@ ..."
+      let (ranges, tail) = rest.split_once(" @@").ok_or_else(|| anyhow!("malformed hunk header"))?;
+      let (old, new) = ranges.split_once(" +").ok_or_else(|| anyhow!("malformed hunk header"))?;
+      out.push_str(&format!("@@ -{new} +{old} @@{tail}"));
+      in_hunk = true;
+    } else if in_hunk {
+      match line.as_bytes().first() {
+        Some(b'+') => {
+          removed.push_str(&format!("-{}", &line[1..]));
+          last_was_removed = true;
+        }
+        Some(b'-') => {
+          added.push_str(&format!("+{}", &line[1..]));
+          last_was_removed = false;
+        }
+        Some(b'\\') if last_was_removed => removed.push_str(line),
+        Some(b'\\') => added.push_str(line),
+        _ => {
+          out.push_str(&removed);
+          out.push_str(&added);
+          removed.clear();
+          added.clear();
+          out.push_str(line);
+        }
+      }
+    } else if line.starts_with("new file mode")
+      || line.starts_with("deleted file mode")
+      || line.starts_with("rename from")
+      || line.starts_with("copy from")
+    {
+      bail!("hunks of added, deleted or renamed files cannot be reversed");
+    } else if let Some(rest) = line.strip_prefix("index ") {
+      // "index abc..def 100644" -> "index def..abc 100644"
+      let (ids, mode) = rest.split_once(' ').map_or((rest.trim_end(), "\n".to_string()), |(i, m)| (i, format!(" {m}")));
+      let (from, to) = ids.split_once("..").ok_or_else(|| anyhow!("malformed index line"))?;
+      out.push_str(&format!("index {to}..{from}{mode}"));
+    } else {
+      out.push_str(line);
+    }
+  }
+  out.push_str(&removed);
+  out.push_str(&added);
+  Ok(out)
+}
+
+/// Generate a diff for the given file path in the given mode
+///
+/// New files are checked against deleted files for renames/copies, so a moved
+/// file shows `renamed: old -> new` with only its content changes.
+pub fn generate_diff(repo_root: &Path, file_path: &Path, mode: DiffMode) -> Option<FileDiff> {
+  let repo = Repository::open(repo_root).ok()?;
+
+  // Make path relative to repo root
+  let rel_path = file_path.strip_prefix(repo_root).ok()?;
+  let rel_path_str = rel_path.to_string_lossy();
+
+  let mut diff_opts = DiffOptions::new();
+  diff_opts.pathspec(&*rel_path_str);
+
+  let diff = mode.diff(&repo, &mut diff_opts)?;
+  let file_diff = collect_file_diff(&diff, rel_path)?;
+
+  // A new (untracked or newly staged) file may be the new side of a rename; that needs
+  // the whole diff so the deleted counterpart can be paired with it.
+  if repo
+    .status_file(rel_path)
+    .is_ok_and(|s| s.intersects(Status::WT_NEW | Status::INDEX_NEW))
+  {
+    let mut opts = DiffOptions::new();
+    opts
+      .include_untracked(true)
+      .recurse_untracked_dirs(true)
+      .show_untracked_content(true);
+    let mut diff = mode.diff(&repo, &mut opts)?;
+    find_renames(&mut diff)?;
+    let renamed = collect_file_diff(&diff, rel_path)?;
+    if renamed.rename.is_some() {
+      return Some(renamed);
+    }
+  }
+
+  // Plain untracked files have nothing to diff against
+  if file_diff.is_empty() {
+    None
+  } else {
+    Some(file_diff)
+  }
+}
+
+/// Pair deleted/added files into renames and copies (including untracked files)
+fn find_renames(diff: &mut Diff) -> Option<()> {
+  let mut find_opts = DiffFindOptions::new();
+  find_opts.renames(true).copies(true).for_untracked(true);
+  diff.find_similar(Some(&mut find_opts)).ok()
+}
+
+/// Collect the patch lines of the delta whose new side is `rel_path`
+fn collect_file_diff(diff: &Diff, rel_path: &Path) -> Option<FileDiff> {
+  let mut file_diff = FileDiff::new();
+
+  diff
+    .print(git2::DiffFormat::Patch, |delta, _hunk, line| {
+      if delta.new_file().path() != Some(rel_path) {
+        return true;
+      }
+
+      if file_diff.lines.is_empty()
+        && matches!(delta.status(), Delta::Renamed | Delta::Copied)
+        && let (Some(from), Some(to)) = (delta.old_file().path(), delta.new_file().path())
+      {
+        let copied = delta.status() == Delta::Copied;
+        let verb = if copied { "copied" } else { "renamed" };
+        file_diff.lines.push(DiffLine {
+          kind: DiffLineKind::Header,
+          content: format!("{verb}: {} -> {}", from.display(), to.display()),
+          old_line_no: None,
+          new_line_no: None,
+        });
+        file_diff.rename = Some(RenameInfo { from: from.to_path_buf(), to: to.to_path_buf(), copied });
+      }
+
+      let content = String::from_utf8_lossy(line.content()).to_string();
+
+      // Keep the raw patch text, per hunk, for export and apply
+      let raw = match line.origin() {
+        origin @ ('+' | '-' | ' ') => format!("{origin}{content}"),
+        _ => content.clone(),
+      };
+      match line.origin() {
+        'H' => file_diff.hunk_patches.push(raw),
+        'F' => file_diff.patch_header.push_str(&raw),
+        _ => {
+          if let Some(hunk) = file_diff.hunk_patches.last_mut() {
+            hunk.push_str(&raw);
+          }
+        }
+      }
+
+      let kind = match line.origin() {
+        '+' => DiffLineKind::Added,
+        '-' => DiffLineKind::Removed,
+        ' ' => DiffLineKind::Context,
+        'H' => DiffLineKind::HunkHeader,
+        'F' => DiffLineKind::Header,
+        _ => DiffLineKind::Header,
+      };
+
+      // Track hunk positions
+      if kind == DiffLineKind::HunkHeader {
+        file_diff.hunks.push(file_diff.lines.len());
+      }
+
+      // File headers span several lines (diff --git, rename from/to, ---/+++);
+      // split them so each gets its own row and hunk indices match rendered rows
+      if kind == DiffLineKind::Header {
+        for header_line in content.lines() {
+          file_diff.lines.push(DiffLine {
+            kind: DiffLineKind::Header,
+            content: header_line.to_string(),
+            old_line_no: None,
+            new_line_no: None,
+          });
+        }
+        return true;
+      }
+
+      file_diff.lines.push(DiffLine {
+        kind,
+        content,
+        old_line_no: line.old_lineno(),
+        new_line_no: line.new_lineno(),
+      });
+
+      true
+    })
+    .ok()?;
+
+  Some(file_diff)
+}
+
+/// Render the diff as styled ratatui Lines
+///
+/// Removed lines directly followed by added lines are paired up, and the tokens that
+/// differ within each pair are emphasized (word diff).
+pub fn render_diff(diff: &FileDiff, theme: &Theme) -> Vec<Line<'static>> {
+  let word_diffs = pair_word_diffs(&diff.lines);
+
+  diff
+    .lines
+    .iter()
+    .zip(word_diffs)
+    .map(|(line, segments)| {
+      let (prefix, style) = match line.kind {
+        DiffLineKind::Added => (
+          "+",
+          Style::default().fg(theme.success),
+        ),
+        DiffLineKind::Removed => (
+          "-",
+          Style::default().fg(theme.error),
+        ),
+        DiffLineKind::Context => (" ", Style::default().fg(theme.text)),
+        DiffLineKind::HunkHeader => (
+          "",
+          Style::default().fg(theme.accent),
+        ),
+        DiffLineKind::Header => ("", Style::default().fg(theme.meta_secondary)),
+      };
+
+      // Build line number gutter
+      let gutter = match line.kind {
+        DiffLineKind::Added => {
+          format!("{:>4}  ", line.new_line_no.map(|n| n.to_string()).unwrap_or_default())
+        }
+        DiffLineKind::Removed => {
+          format!("  {:>4} ", line.old_line_no.map(|n| n.to_string()).unwrap_or_default())
+        }
+        DiffLineKind::Context => {
+          let old = line.old_line_no.map(|n| n.to_string()).unwrap_or_default();
+          let new = line.new_line_no.map(|n| n.to_string()).unwrap_or_default();
+          format!("{:>4} {:>4} ", old, new)
+        }
+        DiffLineKind::HunkHeader | DiffLineKind::Header => String::new(),
+      };
+
+      let gutter = Span::styled(gutter, Style::default().fg(theme.text_dim));
+
+      let Some(segments) = segments else {
+        let content = line.content.trim_end_matches('\n');
+        return Line::from(vec![gutter, Span::styled(format!("{prefix}{content}"), style)]);
+      };
+
+      let emphasis = style.add_modifier(Modifier::BOLD | Modifier::REVERSED);
+      let mut spans = vec![gutter, Span::styled(prefix, style)];
+      spans.extend(segments.into_iter().map(|(text, changed)| {
+        Span::styled(text, if changed { emphasis } else { style })
+      }));
+      Line::from(spans)
+    })
+    .collect()
+}
+
+/// Lines longer than this many tokens skip the word diff (LCS is quadratic)
+const MAX_WORD_DIFF_TOKENS: usize = 400;
+
+/// Segments of a line tagged with whether they changed relative to the paired line
+type WordSegments = Vec<(String, bool)>;
+
+/// Compute word-diff segments for every removed/added line pair, `None` for other lines.
+///
+/// Within a run of removed lines followed by a run of added lines, the n-th removed
+/// line is paired with the n-th added line.
+fn pair_word_diffs(lines: &[DiffLine]) -> Vec<Option<WordSegments>> {
+  let mut result: Vec<Option<WordSegments>> = vec![None; lines.len()];
+  let mut i = 0;
+  while i < lines.len() {
+    if lines[i].kind != DiffLineKind::Removed {
+      i += 1;
+      continue;
+    }
+    let removed_start = i;
+    while i < lines.len() && lines[i].kind == DiffLineKind::Removed {
+      i += 1;
+    }
+    let added_start = i;
+    while i < lines.len() && lines[i].kind == DiffLineKind::Added {
+      i += 1;
+    }
+    let pairs = (added_start - removed_start).min(i - added_start);
+    for n in 0..pairs {
+      let (old, new) = (removed_start + n, added_start + n);
+      if let Some((old_segs, new_segs)) = word_diff(
+        lines[old].content.trim_end_matches('\n'),
+        lines[new].content.trim_end_matches('\n'),
+      ) {
+        result[old] = Some(old_segs);
+        result[new] = Some(new_segs);
+      }
+    }
+  }
+  result
+}
+
+/// Split a line into word, whitespace and single punctuation tokens
+fn tokenize(s: &str) -> Vec<&str> {
+  let mut tokens = Vec::new();
+  let mut start = 0;
+  let mut chars = s.char_indices().peekable();
+  while let Some((i, c)) = chars.next() {
+    let class = token_class(c);
+    let mut end = i + c.len_utf8();
+    if class != 2 {
+      while let Some(&(j, next)) = chars.peek() {
+        if token_class(next) != class {
+          break;
+        }
+        end = j + next.len_utf8();
+        chars.next();
+      }
+    }
+    tokens.push(&s[start..end]);
+    start = end;
+  }
+  tokens
+}
+
+/// 0 = word character, 1 = whitespace, 2 = punctuation (never merged)
+fn token_class(c: char) -> u8 {
+  if c.is_alphanumeric() || c == '_' {
+    0
+  } else if c.is_whitespace() {
+    1
+  } else {
+    2
+  }
+}
+
+/// Diff two lines token by token, returning merged segments for each side.
+///
+/// Returns `None` when the lines are too long or share too little to make
+/// emphasizing individual tokens useful.
+fn word_diff(old: &str, new: &str) -> Option<(WordSegments, WordSegments)> {
+  let a = tokenize(old);
+  let b = tokenize(new);
+  if a.len() > MAX_WORD_DIFF_TOKENS || b.len() > MAX_WORD_DIFF_TOKENS {
+    return None;
+  }
+
+  // LCS table over token suffixes
+  let mut lcs = vec![vec![0u16; b.len() + 1]; a.len() + 1];
+  for i in (0..a.len()).rev() {
+    for j in (0..b.len()).rev() {
+      lcs[i][j] = if a[i] == b[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        lcs[i + 1][j].max(lcs[i][j + 1])
+      };
+    }
+  }
+
+  let mut a_changed = vec![true; a.len()];
+  let mut b_changed = vec![true; b.len()];
+  let (mut i, mut j) = (0, 0);
+  while i < a.len() && j < b.len() {
+    if a[i] == b[j] {
+      a_changed[i] = false;
+      b_changed[j] = false;
+      i += 1;
+      j += 1;
+    } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+      i += 1;
+    } else {
+      j += 1;
+    }
+  }
+
+  // Mostly-rewritten lines read better with plain whole-line coloring
+  let common: usize = a
+    .iter()
+    .zip(&a_changed)
+    .filter(|&(t, &changed)| !changed && !t.trim().is_empty())
+    .map(|(t, _)| t.len())
+    .sum();
+  let longest = old.trim().len().max(new.trim().len());
+  if longest == 0 || common * 2 < longest {
+    return None;
+  }
+
+  Some((merge_segments(&a, &a_changed), merge_segments(&b, &b_changed)))
+}
+
+/// Join adjacent tokens with the same changed flag into segments
+fn merge_segments(tokens: &[&str], changed: &[bool]) -> WordSegments {
+  let mut segments: WordSegments = Vec::new();
+  for (token, &is_changed) in tokens.iter().zip(changed) {
+    match segments.last_mut() {
+      Some((text, last)) if *last == is_changed => text.push_str(token),
+      _ => segments.push((token.to_string(), is_changed)),
+    }
+  }
+  segments
+}
+
+/// One row of the side-by-side layout; indices point into the diff's lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SideBySideRow {
+  /// File and hunk headers span both columns
+  Full(usize),
+  /// Old line on the left, new line on the right (context lines appear on both sides)
+  Split(Option<usize>, Option<usize>),
+}
+
+impl SideBySideRow {
+  /// Index of the first diff line shown in this row
+  fn first_line(&self) -> usize {
+    match *self {
+      SideBySideRow::Full(i) => i,
+      SideBySideRow::Split(left, right) => left.or(right).unwrap_or(0),
+    }
+  }
+}
+
+/// A diff laid out in two columns (old left, new right)
+#[derive(Debug, Clone)]
+pub struct SideBySideDiff {
+  lines: Vec<DiffLine>,
+  word_diffs: Vec<Option<WordSegments>>,
+  /// One per line pair
+  pub rows: Vec<SideBySideRow>,
+  /// Indices of the hunk header rows in `rows`
+  pub hunks: Vec<usize>,
+}
+
+impl SideBySideDiff {
+  /// Lay out `diff` side by side, pairing removed lines with the added lines that follow them
+  pub fn new(diff: &FileDiff) -> Self {
+    let lines = &diff.lines;
+    let mut rows = Vec::new();
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+      match lines[i].kind {
+        DiffLineKind::Header | DiffLineKind::HunkHeader => {
+          if lines[i].kind == DiffLineKind::HunkHeader {
+            hunks.push(rows.len());
+          }
+          rows.push(SideBySideRow::Full(i));
+          i += 1;
+        }
+        DiffLineKind::Context => {
+          rows.push(SideBySideRow::Split(Some(i), Some(i)));
+          i += 1;
+        }
+        DiffLineKind::Removed | DiffLineKind::Added => {
+          let removed_start = i;
+          while i < lines.len() && lines[i].kind == DiffLineKind::Removed {
+            i += 1;
+          }
+          let added_start = i;
+          while i < lines.len() && lines[i].kind == DiffLineKind::Added {
+            i += 1;
+          }
+          let removed = added_start - removed_start;
+          let added = i - added_start;
+          for n in 0..removed.max(added) {
+            rows.push(SideBySideRow::Split(
+              (n < removed).then_some(removed_start + n),
+              (n < added).then_some(added_start + n),
+            ));
+          }
+        }
+      }
+    }
+
+    Self {
+      lines: lines.clone(),
+      word_diffs: pair_word_diffs(lines),
+      rows,
+      hunks,
+    }
+  }
+
+  /// Row containing the given unified diff line
+  pub fn row_for_line(&self, line: usize) -> usize {
+    self.rows.iter().rposition(|r| r.first_line() <= line).unwrap_or(0)
+  }
+
+  /// First unified diff line shown in the given row
+  pub fn line_for_row(&self, row: usize) -> usize {
+    self.rows.get(row).map(SideBySideRow::first_line).unwrap_or(0)
+  }
+
+  /// Render `count` rows starting at `start`, each column fitted to half of `width`
+  pub fn render(&self, start: usize, count: usize, width: u16, theme: &Theme) -> Vec<Line<'static>> {
+    let col_width = (width as usize).saturating_sub(1) / 2;
+    self
+      .rows
+      .iter()
+      .skip(start)
+      .take(count)
+      .map(|row| match *row {
+        SideBySideRow::Full(i) => {
+          let line = &self.lines[i];
+          let color = if line.kind == DiffLineKind::HunkHeader { theme.accent } else { theme.meta_secondary };
+          let (text, _) = fit_to_width(line.content.trim_end_matches('\n'), width as usize);
+          Line::from(Span::styled(text, Style::default().fg(color)))
+        }
+        SideBySideRow::Split(left, right) => {
+          let mut spans = self.render_column(left, true, col_width, theme);
+          spans.push(Span::styled("│", Style::default().fg(theme.border)));
+          spans.extend(self.render_column(right, false, col_width, theme));
+          Line::from(spans)
+        }
+      })
+      .collect()
+  }
+
+  /// Render one column cell: line number gutter, +/- prefix and content padded to `width`
+  fn render_column(&self, index: Option<usize>, old_side: bool, width: usize, theme: &Theme) -> Vec<Span<'static>> {
+    let Some(i) = index else {
+      return vec![Span::raw(" ".repeat(width))];
+    };
+    let line = &self.lines[i];
+    let (prefix, style) = match line.kind {
+      DiffLineKind::Removed => ("-", Style::default().fg(theme.error)),
+      DiffLineKind::Added => ("+", Style::default().fg(theme.success)),
+      _ => (" ", Style::default().fg(theme.text)),
+    };
+    let line_no = if old_side { line.old_line_no } else { line.new_line_no };
+    let gutter = format!("{:>4} ", line_no.map(|n| n.to_string()).unwrap_or_default());
+    let (gutter, gutter_used) = fit_to_width(&gutter, width);
+    let (prefix, prefix_used) = fit_to_width(prefix, width - gutter_used);
+    let mut remaining = width - gutter_used - prefix_used;
+
+    let mut spans = vec![
+      Span::styled(gutter, Style::default().fg(theme.text_dim)),
+      Span::styled(prefix, style),
+    ];
+    let content = line.content.trim_end_matches('\n');
+    let emphasis = style.add_modifier(Modifier::BOLD | Modifier::REVERSED);
+    let segments = match &self.word_diffs[i] {
+      Some(segments) => segments.clone(),
+      None => vec![(content.to_string(), false)],
+    };
+    for (text, changed) in segments {
+      if remaining == 0 {
+        break;
+      }
+      let (text, used) = fit_to_width(&text, remaining);
+      remaining -= used;
+      spans.push(Span::styled(text, if changed { emphasis } else { style }));
+    }
+    if remaining > 0 {
+      spans.push(Span::raw(" ".repeat(remaining)));
+    }
+    spans
+  }
+}
+
+/// Truncate `text` to at most `width` display columns (tabs expand to spaces),
+/// returning the text and the columns it occupies
+fn fit_to_width(text: &str, width: usize) -> (String, usize) {
+  let mut out = String::new();
+  let mut used = 0;
+  for c in text.chars() {
+    if c == '\t' {
+      let spaces = (4 - used % 4).min(width - used);
+      out.push_str(&" ".repeat(spaces));
+      used += spaces;
+    } else {
+      let w = c.width().unwrap_or(0);
+      if used + w > width {
+        break;
+      }
+      out.push(c);
+      used += w;
+    }
+    if used >= width {
+      break;
+    }
+  }
+  (out, used)
+}
+
+/// How a line of the working file differs from HEAD, for the preview's change gutter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+  /// Line not in HEAD
+  Added,
+  /// Line changed from HEAD
+  Modified,
+  /// Lines were deleted next to this one
+  Removed,
+}
+
+/// Changed lines of `file_path` against HEAD, by 1-based line number, or None
+/// when it has no uncommitted changes.
+///
+/// Added lines that replace removed ones count as modified; a deletion with
+/// nothing added in its place marks the line after it (or the last line, at
+/// the end of the file).
+pub fn line_changes(repo_root: &Path, file_path: &Path) -> Option<HashMap<usize, LineChange>> {
+  let diff = generate_diff(repo_root, file_path, DiffMode::WorktreeHead)?;
+  let mut changes = HashMap::new();
+  // Removed lines not yet paired with added ones, and whether any were added since
+  let mut removed = 0;
+  let mut added = false;
+  let mut last = 0;
+  for line in diff.lines.iter().map(Some).chain([None]) {
+    let kind = line.map(|l| &l.kind);
+    let new_line = line.and_then(|l| l.new_line_no).map_or(0, |n| n as usize);
+    match kind {
+      Some(DiffLineKind::Removed) => removed += 1,
+      Some(DiffLineKind::Added) => {
+        let change = if removed > 0 {
+          removed -= 1;
+          LineChange::Modified
+        } else {
+          LineChange::Added
+        };
+        changes.insert(new_line, change);
+        added = true;
+        last = new_line;
+      }
+      _ => {
+        if removed > 0 && !added {
+          // Context follows the deletion; otherwise it ran to the end of the file
+          let at = if kind == Some(&DiffLineKind::Context) { new_line } else { last };
+          changes.entry(at.max(1)).or_insert(LineChange::Removed);
+        }
+        removed = 0;
+        added = false;
+        if kind == Some(&DiffLineKind::Context) {
+          last = new_line;
+        }
+      }
+    }
+  }
+  (!changes.is_empty()).then_some(changes)
+}
+
+/// Put a `+`/`~`/`-` marker before each changed line of a preview, where
+/// `lines[i]` is line `i + 1` of the file.
+pub fn change_gutter(lines: &mut [Line<'static>], changes: &HashMap<usize, LineChange>, theme: &Theme) {
+  for (i, line) in lines.iter_mut().enumerate() {
+    let marker = match changes.get(&(i + 1)) {
+      Some(LineChange::Added) => Span::styled("+", Style::default().fg(theme.success)),
+      Some(LineChange::Modified) => Span::styled("~", Style::default().fg(theme.git_modified)),
+      Some(LineChange::Removed) => Span::styled("-", Style::default().fg(theme.error)),
+      None => Span::raw(" "),
+    };
+    line.spans.insert(0, marker);
+  }
+}
+
+/// Render a message when file has no diff in the given mode
+pub fn render_no_diff_message(mode: DiffMode, theme: &Theme) -> Vec<Line<'static>> {
+  let message = match mode {
+    DiffMode::WorktreeIndex => " File has no uncommitted changes",
+    DiffMode::IndexHead => " File has no staged changes",
+    DiffMode::WorktreeHead => " File has no changes since HEAD",
+  };
+  vec![Line::from(Span::styled(
+    message,
+    Style::default().fg(theme.meta_secondary),
+  ))]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::theme::Theme;
+  use std::fs;
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+  fn make_test_dir() -> std::path::PathBuf {
+    let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+    let dir = std::env::temp_dir().join(format!("tfl_diff_test_{id}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn init_git_repo(dir: &Path) -> Repository {
+    let repo = Repository::init(dir).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.email", "test@test.com").unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    repo
+  }
+
+  fn create_initial_commit(repo: &Repository, dir: &Path) {
+    let file = dir.join("test.txt");
+    fs::write(&file, "line 1\nline 2\nline 3\n").unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("test.txt")).unwrap();
+    index.write().unwrap();
+
+    let tree_id = index.write_tree().unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+    let sig = repo.signature().unwrap();
+
+    repo
+      .commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+      .unwrap();
+  }
+
+  #[test]
+  fn test_file_diff_new_is_empty() {
+    let diff = FileDiff::new();
+    assert!(diff.is_empty());
+    assert!(diff.hunks.is_empty());
+  }
+
+  #[test]
+  fn test_file_diff_default_is_empty() {
+    let diff = FileDiff::default();
+    assert!(diff.is_empty());
+  }
+
+  #[test]
+  fn test_diff_line_kind_equality() {
+    assert_eq!(DiffLineKind::Added, DiffLineKind::Added);
+    assert_ne!(DiffLineKind::Added, DiffLineKind::Removed);
+  }
+
+  #[test]
+  fn test_generate_diff_modified_file() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    // Modify the file
+    let file = dir.join("test.txt");
+    fs::write(&file, "line 1\nmodified line 2\nline 3\nnew line 4\n").unwrap();
+
+    let diff = generate_diff(&dir, &file, DiffMode::WorktreeIndex);
+    assert!(diff.is_some());
+
+    let diff = diff.unwrap();
+    assert!(!diff.is_empty());
+
+    // Should have some added and removed lines
+    let has_added = diff.lines.iter().any(|l| l.kind == DiffLineKind::Added);
+    let has_removed = diff.lines.iter().any(|l| l.kind == DiffLineKind::Removed);
+    assert!(has_added, "diff should have added lines");
+    assert!(has_removed, "diff should have removed lines");
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_line_changes_mark_added_modified_and_removed_lines() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+    let file = dir.join("test.txt");
+    assert!(line_changes(&dir, &file).is_none());
+
+    fs::write(&file, "line 1\nchanged 2\nline 3\nline 4\n").unwrap();
+    let changes = line_changes(&dir, &file).unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[&2], LineChange::Modified);
+    assert_eq!(changes[&4], LineChange::Added);
+
+    // A deletion marks the line after it, or the last line at the end
+    fs::write(&file, "line 2\nline 3\n").unwrap();
+    let changes = line_changes(&dir, &file).unwrap();
+    assert_eq!(changes.into_iter().collect::<Vec<_>>(), [(1, LineChange::Removed)]);
+    fs::write(&file, "line 1\nline 2\n").unwrap();
+    let changes = line_changes(&dir, &file).unwrap();
+    assert_eq!(changes.into_iter().collect::<Vec<_>>(), [(2, LineChange::Removed)]);
+
+    let mut lines = vec![Line::from("a"), Line::from("b")];
+    change_gutter(&mut lines, &HashMap::from([(2, LineChange::Removed)]), &Theme::dark());
+    assert_eq!(lines[0].spans[0].content, " ");
+    assert_eq!(lines[1].spans[0].content, "-");
+    assert_eq!(lines[1].spans[0].style.fg, Some(Theme::dark().error));
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_generate_diff_unmodified_file() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    // File unchanged
+    let file = dir.join("test.txt");
+    let diff = generate_diff(&dir, &file, DiffMode::WorktreeIndex);
+    assert!(diff.is_none(), "unmodified file should have no diff");
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_generate_diff_non_git_repo() {
+    let dir = make_test_dir();
+    let file = dir.join("test.txt");
+    fs::write(&file, "content").unwrap();
+
+    let diff = generate_diff(&dir, &file, DiffMode::WorktreeIndex);
+    assert!(diff.is_none());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_render_diff_colors() {
+    let mut diff = FileDiff::new();
+    diff.lines.push(DiffLine {
+      kind: DiffLineKind::Added,
+      content: "added line".to_string(),
+      old_line_no: None,
+      new_line_no: Some(1),
+    });
+    diff.lines.push(DiffLine {
+      kind: DiffLineKind::Removed,
+      content: "removed line".to_string(),
+      old_line_no: Some(1),
+      new_line_no: None,
+    });
+    diff.lines.push(DiffLine {
+      kind: DiffLineKind::Context,
+      content: "context line".to_string(),
+      old_line_no: Some(2),
+      new_line_no: Some(2),
+    });
+
+    let lines = render_diff(&diff, &Theme::dark());
+    assert_eq!(lines.len(), 3);
+
+    // Verify spans exist (gutter + content)
+    assert!(lines[0].spans.len() >= 2);
+    assert!(lines[1].spans.len() >= 2);
+    assert!(lines[2].spans.len() >= 2);
+  }
+
+  #[test]
+  fn test_render_no_diff_message() {
+    let lines = render_no_diff_message(DiffMode::WorktreeIndex, &Theme::dark());
+    assert_eq!(lines.len(), 1);
+    let content: String = lines[0].spans.iter().map(|s| s.content.to_string()).collect();
+    assert!(content.contains("no uncommitted changes"));
+  }
+
+  #[test]
+  fn test_hunk_tracking() {
+    let mut diff = FileDiff::new();
+    // Header
+    diff.lines.push(DiffLine {
+      kind: DiffLineKind::Header,
+      content: "diff --git".to_string(),
+      old_line_no: None,
+      new_line_no: None,
+    });
+    // First hunk at index 1
+    diff.hunks.push(1);
+    diff.lines.push(DiffLine {
+      kind: DiffLineKind::HunkHeader,
+      content: "@@ -1,3 +1,4 @@".to_string(),
+      old_line_no: None,
+      new_line_no: None,
+    });
+    diff.lines.push(DiffLine {
+      kind: DiffLineKind::Context,
+      content: "context".to_string(),
+      old_line_no: Some(1),
+      new_line_no: Some(1),
+    });
+    // Second hunk at index 3
+    diff.hunks.push(3);
+    diff.lines.push(DiffLine {
+      kind: DiffLineKind::HunkHeader,
+      content: "@@ -10,3 +11,4 @@".to_string(),
+      old_line_no: None,
+      new_line_no: None,
+    });
+
+    // Test hunk positions are tracked
+    assert_eq!(diff.hunks.len(), 2);
+    assert_eq!(diff.hunks[0], 1);
+    assert_eq!(diff.hunks[1], 3);
+  }
+
+  #[test]
+  fn test_generate_diff_tracks_hunks() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    // Modify file
+    let file = dir.join("test.txt");
+    fs::write(&file, "modified\nline 2\nline 3\n").unwrap();
+
+    let diff = generate_diff(&dir, &file, DiffMode::WorktreeIndex);
+    assert!(diff.is_some());
+
+    let diff = diff.unwrap();
+    // Should have at least one hunk
+    assert!(!diff.hunks.is_empty(), "diff should track hunk positions");
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_diff_mode_cycles() {
+    assert_eq!(DiffMode::default(), DiffMode::WorktreeIndex);
+    assert_eq!(DiffMode::WorktreeIndex.next(), DiffMode::IndexHead);
+    assert_eq!(DiffMode::IndexHead.next(), DiffMode::WorktreeHead);
+    assert_eq!(DiffMode::WorktreeHead.next(), DiffMode::WorktreeIndex);
+  }
+
+  fn changed_lines(diff: &FileDiff) -> Vec<String> {
+    diff
+      .lines
+      .iter()
+      .filter(|l| matches!(l.kind, DiffLineKind::Added | DiffLineKind::Removed))
+      .map(|l| format!("{}{}", if l.kind == DiffLineKind::Added { '+' } else { '-' }, l.content.trim_end()))
+      .collect()
+  }
+
+  #[test]
+  fn test_generate_diff_modes_staged_and_unstaged() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    // Stage one change, then make another on top of it
+    let file = dir.join("test.txt");
+    fs::write(&file, "line 1\nstaged\nline 3\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("test.txt")).unwrap();
+    index.write().unwrap();
+    fs::write(&file, "line 1\nstaged\nunstaged\n").unwrap();
+
+    let unstaged = generate_diff(&dir, &file, DiffMode::WorktreeIndex).unwrap();
+    assert_eq!(changed_lines(&unstaged), vec!["-line 3", "+unstaged"]);
+
+    let staged = generate_diff(&dir, &file, DiffMode::IndexHead).unwrap();
+    assert_eq!(changed_lines(&staged), vec!["-line 2", "+staged"]);
+
+    let all = generate_diff(&dir, &file, DiffMode::WorktreeHead).unwrap();
+    assert_eq!(changed_lines(&all), vec!["-line 2", "-line 3", "+staged", "+unstaged"]);
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_generate_diff_staged_rename() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    // git mv test.txt moved.txt
+    fs::rename(dir.join("test.txt"), dir.join("moved.txt")).unwrap();
+    let mut index = repo.index().unwrap();
+    index.remove_path(Path::new("test.txt")).unwrap();
+    index.add_path(Path::new("moved.txt")).unwrap();
+    index.write().unwrap();
+
+    let moved = dir.join("moved.txt");
+    let staged = generate_diff(&dir, &moved, DiffMode::IndexHead).unwrap();
+    assert_eq!(staged.lines[0].content, "renamed: test.txt -> moved.txt");
+    assert!(changed_lines(&staged).is_empty());
+    assert!(generate_diff(&dir, &moved, DiffMode::WorktreeIndex).is_none());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_render_no_diff_message_per_mode() {
+    let text = |mode| -> String {
+      render_no_diff_message(mode, &Theme::dark())[0].spans.iter().map(|s| s.content.to_string()).collect()
+    };
+    assert!(text(DiffMode::IndexHead).contains("no staged changes"));
+    assert!(text(DiffMode::WorktreeHead).contains("since HEAD"));
+  }
+
+  #[test]
+  fn test_patch_export_applies_cleanly() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    let file = dir.join("test.txt");
+    fs::write(&file, "line 1\nchanged 2\nline 3\n").unwrap();
+    let diff = generate_diff(&dir, &file, DiffMode::WorktreeIndex).unwrap();
+
+    let patch = diff.patch();
+    assert!(patch.starts_with("diff --git a/test.txt b/test.txt\n"));
+    assert!(patch.contains("\n-line 2\n+changed 2\n"));
+    assert_eq!(diff.hunk_patch(0).as_deref(), Some(patch.as_str()));
+    assert!(diff.hunk_patch(1).is_none());
+
+    // The exported patch applies to the index
+    let parsed = git2::Diff::from_buffer(patch.as_bytes()).unwrap();
+    repo.apply(&parsed, git2::ApplyLocation::Index, None).unwrap();
+    assert!(generate_diff(&dir, &file, DiffMode::WorktreeIndex).is_none());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_hunk_at_line() {
+    let mut diff = FileDiff::new();
+    assert_eq!(diff.hunk_at_line(3), None);
+    diff.hunks = vec![2, 10];
+    assert_eq!(diff.hunk_at_line(0), Some(0));
+    assert_eq!(diff.hunk_at_line(9), Some(0));
+    assert_eq!(diff.hunk_at_line(10), Some(1));
+    assert_eq!(diff.hunk_at_line(50), Some(1));
+  }
+
+  /// Commit a 12-line file, then change its first and last line (two separate hunks)
+  fn two_hunk_repo() -> (std::path::PathBuf, std::path::PathBuf) {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    let file = dir.join("test.txt");
+    let original: String = (1..=12).map(|i| format!("line {i}\n")).collect();
+    fs::write(&file, &original).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("test.txt")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = repo.signature().unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+
+    let modified = original.replace("line 1\n", "first\n").replace("line 12\n", "last\n");
+    fs::write(&file, modified).unwrap();
+    (dir, file)
+  }
+
+  #[test]
+  fn test_apply_hunk_stage_and_unstage() {
+    let (dir, file) = two_hunk_repo();
+    assert_eq!(generate_diff(&dir, &file, DiffMode::WorktreeIndex).unwrap().hunks.len(), 2);
+
+    apply_hunk(&dir, &file, DiffMode::WorktreeIndex, 1, HunkAction::Stage).unwrap();
+    let staged = generate_diff(&dir, &file, DiffMode::IndexHead).unwrap();
+    assert_eq!(changed_lines(&staged), vec!["-line 12", "+last"]);
+    let unstaged = generate_diff(&dir, &file, DiffMode::WorktreeIndex).unwrap();
+    assert_eq!(changed_lines(&unstaged), vec!["-line 1", "+first"]);
+
+    apply_hunk(&dir, &file, DiffMode::IndexHead, 0, HunkAction::Unstage).unwrap();
+    assert!(generate_diff(&dir, &file, DiffMode::IndexHead).is_none());
+    assert_eq!(generate_diff(&dir, &file, DiffMode::WorktreeIndex).unwrap().hunks.len(), 2);
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_apply_hunk_revert_only_touches_hunk() {
+    let (dir, file) = two_hunk_repo();
+
+    apply_hunk(&dir, &file, DiffMode::WorktreeIndex, 0, HunkAction::Revert).unwrap();
+    let content = fs::read_to_string(&file).unwrap();
+    assert!(content.starts_with("line 1\n"));
+    assert!(content.ends_with("last\n"));
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_reverse_patch_swaps_sides() {
+    let patch = "diff --git a/f b/f\nindex 111..222 100644\n--- a/f\n+++ b/f\n@@ -1,2 +1,3 @@ fn x\n-old\n+new\n+more\n same\n";
+    assert_eq!(
+      reverse_patch(patch).unwrap(),
+      "diff --git a/f b/f\nindex 222..111 100644\n--- a/f\n+++ b/f\n@@ -1,3 +1,2 @@ fn x\n-new\n-more\n+old\n same\n"
+    );
+    assert!(reverse_patch("diff --git a/f b/f\nnew file mode 100644\n").is_err());
+  }
+
+  #[test]
+  fn test_apply_hunk_rejects_unsupported_mode() {
+    let (dir, file) = two_hunk_repo();
+    assert!(apply_hunk(&dir, &file, DiffMode::WorktreeHead, 0, HunkAction::Stage).is_err());
+    assert!(apply_hunk(&dir, &file, DiffMode::IndexHead, 0, HunkAction::Revert).is_err());
+    assert!(apply_hunk(&dir, &file, DiffMode::WorktreeIndex, 5, HunkAction::Stage).is_err());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_generate_diff_detects_rename() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    // Move the file and tweak one line
+    fs::remove_file(dir.join("test.txt")).unwrap();
+    let new_file = dir.join("moved.txt");
+    fs::write(&new_file, "line 1\nline 2\nline 3 changed\n").unwrap();
+
+    let diff = generate_diff(&dir, &new_file, DiffMode::WorktreeIndex).expect("rename should produce a diff");
+    let rename = diff.rename.as_ref().expect("rename should be detected");
+    assert_eq!(rename.from, PathBuf::from("test.txt"));
+    assert_eq!(rename.to, PathBuf::from("moved.txt"));
+    assert!(!rename.copied);
+    assert_eq!(diff.lines[0].content, "renamed: test.txt -> moved.txt");
+
+    // Only the changed line is shown, not a full add/delete
+    let removed = diff.lines.iter().filter(|l| l.kind == DiffLineKind::Removed).count();
+    let added = diff.lines.iter().filter(|l| l.kind == DiffLineKind::Added).count();
+    assert_eq!((removed, added), (1, 1));
+
+    // Hunk indices point at hunk headers
+    assert!(!diff.hunks.is_empty());
+    for &idx in &diff.hunks {
+      assert_eq!(diff.lines[idx].kind, DiffLineKind::HunkHeader);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_generate_diff_untracked_without_rename_is_none() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    let new_file = dir.join("brand_new.txt");
+    fs::write(&new_file, "completely different content\n").unwrap();
+    assert!(generate_diff(&dir, &new_file, DiffMode::WorktreeIndex).is_none());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_generate_diff_splits_file_header_lines() {
+    let dir = make_test_dir();
+    let repo = init_git_repo(&dir);
+    create_initial_commit(&repo, &dir);
+
+    let file = dir.join("test.txt");
+    fs::write(&file, "line 1\nchanged\nline 3\n").unwrap();
+
+    let diff = generate_diff(&dir, &file, DiffMode::WorktreeIndex).unwrap();
+    assert!(diff.lines.iter().all(|l| l.kind != DiffLineKind::Header || !l.content.contains('\n')));
+    assert!(diff.rename.is_none());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  fn diff_line(kind: DiffLineKind, content: &str) -> DiffLine {
+    DiffLine { kind, content: content.to_string(), old_line_no: None, new_line_no: None }
+  }
+
+  fn changed_text(line: &Line) -> Vec<String> {
+    line
+      .spans
+      .iter()
+      .filter(|s| s.style.add_modifier.contains(Modifier::REVERSED))
+      .map(|s| s.content.to_string())
+      .collect()
+  }
+
+  #[test]
+  fn test_tokenize_splits_words_spaces_and_punctuation() {
+    assert_eq!(tokenize("let x_1 = foo(a, b);"), vec![
+      "let", " ", "x_1", " ", "=", " ", "foo", "(", "a", ",", " ", "b", ")", ";"
+    ]);
+    assert!(tokenize("").is_empty());
+  }
+
+  #[test]
+  fn test_render_diff_highlights_changed_words() {
+    let mut diff = FileDiff::new();
+    diff.lines.push(diff_line(DiffLineKind::Removed, "let timeout = compute(value, 30);\n"));
+    diff.lines.push(diff_line(DiffLineKind::Added, "let timeout = compute(value, 60);\n"));
+
+    let lines = render_diff(&diff, &Theme::dark());
+    assert_eq!(changed_text(&lines[0]), vec!["30"]);
+    assert_eq!(changed_text(&lines[1]), vec!["60"]);
+
+    // Full text is preserved
+    let text: String = lines[1].spans[1..].iter().map(|s| s.content.to_string()).collect();
+    assert_eq!(text, "+let timeout = compute(value, 60);");
+  }
+
+  #[test]
+  fn test_render_diff_pairs_removed_and_added_runs() {
+    let mut diff = FileDiff::new();
+    diff.lines.push(diff_line(DiffLineKind::Removed, "alpha one"));
+    diff.lines.push(diff_line(DiffLineKind::Removed, "beta two"));
+    diff.lines.push(diff_line(DiffLineKind::Added, "alpha uno"));
+    diff.lines.push(diff_line(DiffLineKind::Added, "beta dos"));
+    diff.lines.push(diff_line(DiffLineKind::Added, "gamma three"));
+
+    let lines = render_diff(&diff, &Theme::dark());
+    assert_eq!(changed_text(&lines[0]), vec!["one"]);
+    assert_eq!(changed_text(&lines[1]), vec!["two"]);
+    assert_eq!(changed_text(&lines[2]), vec!["uno"]);
+    assert_eq!(changed_text(&lines[3]), vec!["dos"]);
+    // Unpaired added line keeps whole-line coloring
+    assert!(changed_text(&lines[4]).is_empty());
+  }
+
+  #[test]
+  fn test_render_diff_skips_word_diff_for_rewritten_lines() {
+    let mut diff = FileDiff::new();
+    diff.lines.push(diff_line(DiffLineKind::Removed, "fn old_name() -> u32 {"));
+    diff.lines.push(diff_line(DiffLineKind::Added, "// completely unrelated comment"));
+
+    let lines = render_diff(&diff, &Theme::dark());
+    assert!(changed_text(&lines[0]).is_empty());
+    assert!(changed_text(&lines[1]).is_empty());
+  }
+
+  fn numbered(kind: DiffLineKind, content: &str, old: Option<u32>, new: Option<u32>) -> DiffLine {
+    DiffLine { kind, content: content.to_string(), old_line_no: old, new_line_no: new }
+  }
+
+  fn sample_diff() -> FileDiff {
+    let mut diff = FileDiff::new();
+    diff.lines.push(diff_line(DiffLineKind::Header, "diff --git a/f b/f"));
+    diff.hunks.push(1);
+    diff.lines.push(diff_line(DiffLineKind::HunkHeader, "@@ -1,3 +1,4 @@"));
+    diff.lines.push(numbered(DiffLineKind::Context, "same\n", Some(1), Some(1)));
+    diff.lines.push(numbered(DiffLineKind::Removed, "old a\n", Some(2), None));
+    diff.lines.push(numbered(DiffLineKind::Added, "new a\n", None, Some(2)));
+    diff.lines.push(numbered(DiffLineKind::Added, "extra\n", None, Some(3)));
+    diff.lines.push(numbered(DiffLineKind::Context, "tail\n", Some(3), Some(4)));
+    diff
+  }
+
+  #[test]
+  fn test_side_by_side_rows_pair_changes() {
+    let sbs = SideBySideDiff::new(&sample_diff());
+    assert_eq!(sbs.rows, vec![
+      SideBySideRow::Full(0),
+      SideBySideRow::Full(1),
+      SideBySideRow::Split(Some(2), Some(2)),
+      SideBySideRow::Split(Some(3), Some(4)),
+      SideBySideRow::Split(None, Some(5)),
+      SideBySideRow::Split(Some(6), Some(6)),
+    ]);
+    assert_eq!(sbs.hunks, vec![1]);
+  }
+
+  #[test]
+  fn test_side_by_side_row_line_mapping() {
+    let sbs = SideBySideDiff::new(&sample_diff());
+    assert_eq!(sbs.row_for_line(4), 3); // added line paired with removed line 3
+    assert_eq!(sbs.row_for_line(6), 5);
+    assert_eq!(sbs.line_for_row(4), 5);
+    assert_eq!(sbs.line_for_row(0), 0);
+  }
+
+  #[test]
+  fn test_side_by_side_render_fits_width() {
+    let sbs = SideBySideDiff::new(&sample_diff());
+    let lines = sbs.render(0, 10, 41, &Theme::dark());
+    assert_eq!(lines.len(), 6);
+    for line in &lines[2..] {
+      assert_eq!(line.width(), 41);
+    }
+    let row: String = lines[3].spans.iter().map(|s| s.content.to_string()).collect();
+    let (left, right) = row.split_once('│').unwrap();
+    assert!(left.contains("-old a") && left.contains('2'));
+    assert!(right.contains("+new a"));
+  }
+
+  #[test]
+  fn test_fit_to_width_truncates_and_expands_tabs() {
+    assert_eq!(fit_to_width("abcdef", 3), ("abc".to_string(), 3));
+    assert_eq!(fit_to_width("\tx", 10), ("    x".to_string(), 5));
+    assert_eq!(fit_to_width("日本", 3), ("日".to_string(), 2));
+  }
+
+  #[test]
+  fn test_diff_line_numbers_in_gutter() {
+    let mut diff = FileDiff::new();
+    diff.lines.push(DiffLine {
+      kind: DiffLineKind::Added,
+      content: "new".to_string(),
+      old_line_no: None,
+      new_line_no: Some(42),
+    });
+
+    let lines = render_diff(&diff, &Theme::dark());
+    let gutter = &lines[0].spans[0].content;
+    assert!(gutter.contains("42"), "gutter should show line number 42");
+  }
+}
@@ -0,0 +1,358 @@
+//! Directory summaries: entry counts and sizes.
+
+use std::path::{Path, PathBuf};
+
+use ratatui_core::style::Style;
+use ratatui_core::text::{Line, Span};
+use unicode_width::UnicodeWidthStr;
+
+use crate::fs::{TreeSource, walk};
+use crate::icons::{file_icon, file_name_color};
+use crate::preview::links::{LineLink, file_url};
+use crate::theme::Theme;
+
+/// Longest name the size column is aligned after; longer names push it out
+const MAX_NAME_WIDTH: usize = 40;
+
+/// How byte counts are shown in the directory summary and the status bar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeFormat {
+  /// Rounded with a unit, e.g. `4.2 KB`
+  #[default]
+  Human,
+  /// Exact byte count with thousands separators
+  Exact,
+}
+
+impl SizeFormat {
+  /// The other format.
+  pub fn toggle(self) -> Self {
+    match self {
+      SizeFormat::Human => SizeFormat::Exact,
+      SizeFormat::Exact => SizeFormat::Human,
+    }
+  }
+
+  /// `bytes` in this format.
+  pub fn format(self, bytes: u64) -> String {
+    match self {
+      SizeFormat::Human => format_size(bytes),
+      SizeFormat::Exact => format_exact(bytes),
+    }
+  }
+}
+
+/// What a directory directly holds, for its preview.
+pub struct DirSummary {
+  /// Files directly inside
+  pub file_count: usize,
+  /// Directories directly inside
+  pub dir_count: usize,
+  /// Bytes of the files directly inside
+  pub total_size: u64,
+  /// Directories first, then by name
+  pub entries: Vec<DirEntry>,
+  /// Set when the directory is a symlink to one of its own ancestors
+  pub cycle_target: Option<PathBuf>,
+}
+
+/// One child in a directory summary.
+pub struct DirEntry {
+  /// File name
+  pub name: String,
+  /// A directory, following symlinks
+  pub is_dir: bool,
+  /// Size in bytes
+  pub size: u64,
+  /// A symlink to one of its own ancestors
+  pub is_cycle: bool,
+}
+
+/// Summarize the local directory `path`; empty when it can't be read.
+pub fn summarize_dir(path: &Path) -> DirSummary {
+  let mut summary = DirSummary {
+    file_count: 0,
+    dir_count: 0,
+    total_size: 0,
+    entries: Vec::new(),
+    cycle_target: walk::symlink_cycle_target(path),
+  };
+
+  let read_dir = match std::fs::read_dir(path) {
+    Ok(rd) => rd,
+    Err(_) => return summary,
+  };
+
+  for entry in read_dir.flatten() {
+    let meta = entry.metadata();
+    let is_dir = meta.as_ref().is_ok_and(|m| m.is_dir());
+    let size = meta.as_ref().map_or(0, |m| m.len());
+    let name = entry.file_name().to_string_lossy().to_string();
+    let is_cycle = walk::symlink_cycle_target(&entry.path()).is_some();
+
+    if is_dir {
+      summary.dir_count += 1;
+    } else {
+      summary.file_count += 1;
+      summary.total_size += size;
+    }
+
+    summary.entries.push(DirEntry { name, is_dir, size, is_cycle });
+  }
+
+  sort_entries(&mut summary.entries);
+  summary
+}
+
+/// Like `summarize_dir`, for a directory of a tree not on the local
+/// filesystem.
+pub fn summarize_source_dir(source: &dyn TreeSource, path: &Path) -> DirSummary {
+  let mut summary =
+    DirSummary { file_count: 0, dir_count: 0, total_size: 0, entries: Vec::new(), cycle_target: None };
+  for entry in source.children(path, 0).unwrap_or_default() {
+    if entry.is_dir {
+      summary.dir_count += 1;
+    } else {
+      summary.file_count += 1;
+      summary.total_size += entry.size;
+    }
+    summary.entries.push(DirEntry { name: entry.name, is_dir: entry.is_dir, size: entry.size, is_cycle: false });
+  }
+  sort_entries(&mut summary.entries);
+  summary
+}
+
+/// Dirs first, then alphabetical
+fn sort_entries(entries: &mut [DirEntry]) {
+  entries.sort_by(|a, b| {
+    b.is_dir
+      .cmp(&a.is_dir)
+      .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+  });
+}
+
+/// Lay out the summary as preview lines, sizes in `size_format`.
+pub fn render_dir_summary<'a>(summary: &DirSummary, theme: &Theme, size_format: SizeFormat) -> Vec<Line<'a>> {
+  let mut lines = Vec::new();
+
+  lines.push(Line::from(vec![
+    Span::styled(
+      format!(
+        " {} files, {} directories, {}",
+        summary.file_count,
+        summary.dir_count,
+        size_format.format(summary.total_size)
+      ),
+      Style::default().fg(theme.text),
+    ),
+  ]));
+  if let Some(target) = &summary.cycle_target {
+    lines.push(Line::from(Span::styled(
+      format!(" ↻ symlink cycle: links back to {}", target.display()),
+      Style::default().fg(theme.warning),
+    )));
+  }
+  lines.push(Line::from(""));
+
+  // Sizes are right-aligned in a column after the longest name
+  let sizes: Vec<Option<String>> =
+    summary.entries.iter().map(|e| (!e.is_dir).then(|| size_format.format(e.size))).collect();
+  let name_width = summary.entries.iter().map(|e| e.name.width()).max().unwrap_or(0).min(MAX_NAME_WIDTH);
+  let size_width = sizes.iter().flatten().map(|s| s.len()).max().unwrap_or(0);
+
+  for (entry, size) in summary.entries.iter().zip(sizes) {
+    let icon = file_icon(&entry.name, entry.is_dir, false, false);
+    let color = file_name_color(&entry.name, entry.is_dir, false);
+    let size_str = match size {
+      Some(size) => {
+        let pad = name_width.saturating_sub(entry.name.width());
+        format!("{}  {size:>size_width$}", " ".repeat(pad))
+      }
+      None => String::new(),
+    };
+
+    lines.push(Line::from(vec![
+      Span::styled(" ", Style::default()),
+      Span::styled(icon.glyph, Style::default().fg(icon.color)),
+      Span::styled(entry.name.clone(), Style::default().fg(color)),
+      Span::styled(size_str, Style::default().fg(theme.text_dim)),
+      Span::styled(if entry.is_cycle { " ↻" } else { "" }, Style::default().fg(theme.warning)),
+    ]));
+  }
+
+  lines
+}
+
+/// The link of an entry line from `render_dir_summary` to the entry in
+/// `dir`. The totals and notes above the entries have none.
+pub fn entry_link(line: &Line, dir: &Path) -> Option<LineLink> {
+  // " ", icon, name, size, cycle marker
+  let [lead, icon, name, _, _] = &line.spans[..] else {
+    return None;
+  };
+  (lead.content == " " && !name.content.is_empty()).then(|| LineLink {
+    start: 1 + icon.content.width(),
+    width: name.content.width(),
+    url: file_url(&dir.join(name.content.as_ref())),
+  })
+}
+
+/// `bytes` rounded with a unit, e.g. `4.2 KB`.
+pub fn format_size(bytes: u64) -> String {
+  const KB: u64 = 1024;
+  const MB: u64 = 1024 * KB;
+  const GB: u64 = 1024 * MB;
+
+  if bytes >= GB {
+    format!("{:.1} GB", bytes as f64 / GB as f64)
+  } else if bytes >= MB {
+    format!("{:.1} MB", bytes as f64 / MB as f64)
+  } else if bytes >= KB {
+    format!("{:.1} KB", bytes as f64 / KB as f64)
+  } else {
+    format!("{bytes} B")
+  }
+}
+
+/// `1234567` -> `1,234,567 B`
+pub fn format_exact(bytes: u64) -> String {
+  let digits = bytes.to_string();
+  let mut out = String::with_capacity(digits.len() + digits.len() / 3 + 2);
+  for (i, c) in digits.chars().enumerate() {
+    if i > 0 && (digits.len() - i).is_multiple_of(3) {
+      out.push(',');
+    }
+    out.push(c);
+  }
+  out.push_str(" B");
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_exact() {
+    assert_eq!(format_exact(0), "0 B");
+    assert_eq!(format_exact(999), "999 B");
+    assert_eq!(format_exact(1000), "1,000 B");
+    assert_eq!(format_exact(1234567), "1,234,567 B");
+    assert_eq!(SizeFormat::Human.format(2560), "2.5 KB");
+    assert_eq!(SizeFormat::Human.toggle().format(2560), "2,560 B");
+  }
+
+  #[test]
+  fn test_summary_sizes_right_aligned() {
+    let summary = DirSummary {
+      file_count: 2,
+      dir_count: 1,
+      total_size: 1_000_005,
+      entries: vec![
+        DirEntry { name: "dir".to_string(), is_dir: true, size: 0, is_cycle: false },
+        DirEntry { name: "a".to_string(), is_dir: false, size: 5, is_cycle: false },
+        DirEntry { name: "longer.txt".to_string(), is_dir: false, size: 1_000_000, is_cycle: false },
+      ],
+      cycle_target: None,
+    };
+    let lines = render_dir_summary(&summary, &Theme::dark(), SizeFormat::Exact);
+    assert!(lines[0].to_string().ends_with("1,000,005 B"));
+    let rows: Vec<String> = lines[2..].iter().map(|l| l.to_string()).collect();
+    assert!(!rows[0].contains(" B"));
+    assert!(rows[1].ends_with(&format!("a{}5 B", " ".repeat(19))));
+    assert!(rows[2].ends_with("longer.txt  1,000,000 B"));
+  }
+
+  #[test]
+  fn test_format_size_bytes() {
+    assert_eq!(format_size(0), "0 B");
+    assert_eq!(format_size(512), "512 B");
+    assert_eq!(format_size(1023), "1023 B");
+  }
+
+  #[test]
+  fn test_format_size_kb() {
+    assert_eq!(format_size(1024), "1.0 KB");
+    assert_eq!(format_size(2560), "2.5 KB");
+  }
+
+  #[test]
+  fn test_format_size_mb() {
+    assert_eq!(format_size(1024 * 1024), "1.0 MB");
+  }
+
+  #[test]
+  fn test_format_size_gb() {
+    assert_eq!(format_size(1024 * 1024 * 1024), "1.0 GB");
+  }
+
+  #[test]
+  fn test_summarize_tempdir() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_dir_summary");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("file1.txt"), "hello").unwrap();
+    std::fs::write(dir.join("file2.txt"), "world!!").unwrap();
+    std::fs::create_dir(dir.join("subdir")).unwrap();
+
+    let summary = summarize_dir(&dir);
+    assert_eq!(summary.file_count, 2);
+    assert_eq!(summary.dir_count, 1);
+    assert_eq!(summary.total_size, 12); // 5 + 7
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_render_dir_summary() {
+    let summary = DirSummary {
+      file_count: 3,
+      dir_count: 1,
+      total_size: 1024,
+      entries: vec![
+        DirEntry { name: "src".to_string(), is_dir: true, size: 0, is_cycle: false },
+        DirEntry { name: "main.rs".to_string(), is_dir: false, size: 512, is_cycle: false },
+      ],
+      cycle_target: None,
+    };
+    let lines = render_dir_summary(&summary, &Theme::dark(), SizeFormat::Human);
+    assert!(!lines.is_empty());
+    // First line should mention counts
+    let first_line_text: String = lines[0].spans.iter().map(|s| s.content.to_string()).collect();
+    assert!(first_line_text.contains("3 files"));
+  }
+
+  #[test]
+  fn test_summarize_flags_symlink_cycle() {
+    let dir = std::env::temp_dir().join(format!("tfl_dir_summary_cycle_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("sub").join("loop")).unwrap();
+
+    let inner = summarize_dir(&dir.join("sub"));
+    assert!(inner.entries.iter().any(|e| e.name == "loop" && e.is_cycle));
+    let looped = summarize_dir(&dir.join("sub").join("loop"));
+    assert_eq!(looped.cycle_target, Some(dir.canonicalize().unwrap()));
+    let text: String = render_dir_summary(&looped, &Theme::dark(), SizeFormat::Human)
+      .iter()
+      .flat_map(|l| l.spans.iter().map(|s| s.content.to_string()))
+      .collect();
+    assert!(text.contains("symlink cycle"));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_entry_link_points_at_listed_entry() {
+    let dir = std::env::temp_dir().join(format!("tfl_dir_summary_links_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("notes file.txt"), "x").unwrap();
+
+    let lines = render_dir_summary(&summarize_dir(&dir), &Theme::dark(), SizeFormat::Human);
+    assert_eq!(entry_link(&lines[0], &dir), None);
+    let link = entry_link(lines.last().unwrap(), &dir).unwrap();
+    assert_eq!(link.width, "notes file.txt".len());
+    assert!(link.url.ends_with("/notes%20file.txt"));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -1,10 +1,14 @@
-use ratatui::style::Style;
-use ratatui::text::{Line, Span};
+//! Hex dump for binary files.
+
+use ratatui_core::style::Style;
+use ratatui_core::text::{Line, Span};
 
 use crate::theme::Theme;
 
-const BYTES_PER_LINE: usize = 16;
+/// Bytes shown per row of the dump
+pub const BYTES_PER_LINE: usize = 16;
 
+/// Offset, hex and ASCII columns for `data`, `BYTES_PER_LINE` to a row.
 pub fn hex_dump(data: &[u8], theme: &Theme) -> Vec<Line<'static>> {
   let mut lines = Vec::new();
 
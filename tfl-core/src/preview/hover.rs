@@ -0,0 +1,202 @@
+//! Quick preview for the entry under the mouse pointer.
+
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+
+use image::RgbImage;
+
+use super::{PreviewType, detect_preview_type};
+
+const MAX_LINES: usize = 8;
+const MAX_LINE_CHARS: usize = 60;
+/// Thumbnail size in cells; each cell shows two pixels stacked with `▀`
+pub const THUMB_COLS: u32 = 32;
+/// Thumbnail height in cells
+pub const THUMB_ROWS: u32 = 8;
+
+/// The popup's content below its header line.
+#[derive(Debug, Clone)]
+pub enum HoverBody {
+  /// First lines of a text file, or the first entries of a directory
+  Lines(Vec<String>),
+  /// Image scaled to fit `THUMB_COLS` by `THUMB_ROWS` cells
+  Thumbnail(RgbImage),
+  /// Short text in place of a preview, e.g. `empty` or an error
+  Note(String),
+}
+
+/// What the hover popup shows for one entry.
+#[derive(Debug, Clone)]
+pub struct HoverPreview {
+  /// File name
+  pub name: String,
+  /// A directory, following symlinks
+  pub is_dir: bool,
+  /// Size in bytes
+  pub size: u64,
+  /// Last content change
+  pub modified: Option<SystemTime>,
+  /// Content below the header
+  pub body: HoverBody,
+}
+
+impl HoverPreview {
+  /// Load the popup for `path`. Reads at most the start of the file.
+  pub fn load(path: &Path) -> Self {
+    let metadata = std::fs::metadata(path).ok();
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
+    let body = match detect_preview_type(path) {
+      PreviewType::Directory => HoverBody::Lines(dir_lines(path)),
+      PreviewType::Text | PreviewType::Markdown => HoverBody::Lines(text_lines(path)),
+      PreviewType::Image => match image::open(path) {
+        Ok(img) => HoverBody::Thumbnail(img.thumbnail(THUMB_COLS, THUMB_ROWS * 2).to_rgb8()),
+        Err(e) => HoverBody::Note(e.to_string()),
+      },
+      PreviewType::Empty => HoverBody::Note("empty".to_string()),
+      PreviewType::Archive => HoverBody::Note("archive".to_string()),
+      PreviewType::Special(kind) => HoverBody::Note(kind.label().to_lowercase()),
+      PreviewType::Error(e) => HoverBody::Note(e),
+      _ => HoverBody::Note("binary".to_string()),
+    };
+    Self {
+      name,
+      is_dir,
+      size: metadata.as_ref().map_or(0, |m| m.len()),
+      modified: metadata.and_then(|m| m.modified().ok()),
+      body,
+    }
+  }
+}
+
+fn clip(line: &str) -> String {
+  line.replace('\t', "  ").chars().take(MAX_LINE_CHARS).collect()
+}
+
+fn text_lines(path: &Path) -> Vec<String> {
+  let Ok(file) = std::fs::File::open(path) else {
+    return Vec::new();
+  };
+  BufReader::new(file)
+    .split(b'\n')
+    .take(MAX_LINES)
+    .map_while(Result::ok)
+    .map(|line| clip(String::from_utf8_lossy(&line).trim_end()))
+    .collect()
+}
+
+fn dir_lines(path: &Path) -> Vec<String> {
+  let Ok(read_dir) = std::fs::read_dir(path) else {
+    return Vec::new();
+  };
+  let mut names: Vec<String> = read_dir
+    .flatten()
+    .map(|e| {
+      let name = e.file_name().to_string_lossy().into_owned();
+      if e.file_type().is_ok_and(|t| t.is_dir()) { format!("{name}/") } else { name }
+    })
+    .collect();
+  names.sort();
+  let more = names.len().saturating_sub(MAX_LINES);
+  names.truncate(MAX_LINES);
+  if more > 0 {
+    names[MAX_LINES - 1] = format!("… {} more", more + 1);
+  }
+  names.into_iter().map(|n| clip(&n)).collect()
+}
+
+/// The entry under the mouse pointer and its popup, loaded in the
+/// background once the pointer has rested on it for the hover delay.
+pub struct Hover {
+  /// Entry under the pointer
+  pub path: PathBuf,
+  /// Pointer column
+  pub column: u16,
+  /// Pointer row
+  pub row: u16,
+  since: Instant,
+  rx: Option<mpsc::Receiver<HoverPreview>>,
+  /// The popup once loaded
+  pub preview: Option<HoverPreview>,
+}
+
+impl Hover {
+  /// Start timing a rest on `path` at the pointer position.
+  pub fn new(path: PathBuf, column: u16, row: u16) -> Self {
+    Self { path, column, row, since: Instant::now(), rx: None, preview: None }
+  }
+
+  /// Start loading once `delay` has passed; true when the popup became ready.
+  pub fn poll(&mut self, delay: Duration) -> bool {
+    if self.preview.is_some() {
+      return false;
+    }
+    match self.rx {
+      None if self.since.elapsed() >= delay => {
+        let (tx, rx) = mpsc::channel();
+        let path = self.path.clone();
+        std::thread::spawn(move || {
+          let _ = tx.send(HoverPreview::load(&path));
+        });
+        self.rx = Some(rx);
+        false
+      }
+      None => false,
+      Some(ref rx) => match rx.try_recv() {
+        Ok(preview) => {
+          self.preview = Some(preview);
+          self.rx = None;
+          true
+        }
+        Err(_) => false,
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_load_text_and_dir() {
+    let dir = std::env::temp_dir().join(format!("tfl_hover_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    let text: String = (1..=20).map(|i| format!("line\t{i}\n")).collect();
+    std::fs::write(dir.join("notes.txt"), text).unwrap();
+
+    let preview = HoverPreview::load(&dir.join("notes.txt"));
+    assert_eq!(preview.name, "notes.txt");
+    assert!(!preview.is_dir);
+    match preview.body {
+      HoverBody::Lines(lines) => {
+        assert_eq!(lines.len(), MAX_LINES);
+        assert_eq!(lines[0], "line  1");
+      }
+      other => panic!("unexpected body {other:?}"),
+    }
+
+    let preview = HoverPreview::load(&dir);
+    assert!(preview.is_dir);
+    match preview.body {
+      HoverBody::Lines(lines) => assert_eq!(lines, vec!["notes.txt", "sub/"]),
+      other => panic!("unexpected body {other:?}"),
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_poll_waits_for_delay() {
+    let mut hover = Hover::new(std::env::temp_dir(), 3, 4);
+    assert!(!hover.poll(Duration::from_secs(60)));
+    assert!(hover.rx.is_none());
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !hover.poll(Duration::ZERO) && Instant::now() < deadline {
+      std::thread::sleep(Duration::from_millis(5));
+    }
+    assert!(hover.preview.as_ref().is_some_and(|p| p.is_dir));
+  }
+}
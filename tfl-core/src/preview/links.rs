@@ -0,0 +1,167 @@
+//! Links in rendered preview lines (URLs, markdown link text, diff file
+//! headers) for terminals that open OSC 8 hyperlinks on Ctrl+click.
+
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use ratatui_core::style::Modifier;
+use ratatui_core::text::Line;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns `start..start + width` of a line, linking to `url`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineLink {
+  /// First column, 0-based
+  pub start: usize,
+  /// Columns the link text spans
+  pub width: usize,
+  /// Where the link points
+  pub url: String,
+}
+
+/// URLs spelled out in `line`, and the text of each rendered markdown link
+/// (underlined, followed by its ` (url)`).
+pub fn url_links(line: &Line) -> Vec<LineLink> {
+  let mut links = Vec::new();
+  let mut column = 0;
+  // Start, width and underline of the span before
+  let mut previous: Option<(usize, usize, bool)> = None;
+  for span in &line.spans {
+    let text = span.content.as_ref();
+    for (offset, url) in find_urls(text) {
+      links.push(LineLink { start: column + text[..offset].width(), width: url.width(), url: url.to_string() });
+    }
+    if let Some((start, width, true)) = previous
+      && let Some(url) = text.strip_prefix(" (").and_then(|t| t.strip_suffix(')'))
+      && find_urls(url).first().is_some_and(|&(offset, found)| offset == 0 && found == url)
+    {
+      links.push(LineLink { start, width, url: url.to_string() });
+    }
+    let width = text.width();
+    previous = Some((column, width, span.style.add_modifier.contains(Modifier::UNDERLINED)));
+    column += width;
+  }
+  links.sort_by_key(|l| l.start);
+  links
+}
+
+/// `http(s)://` URLs in `text` with their byte offsets. Trailing
+/// punctuation and a closing bracket the URL did not open are left out, and
+/// control characters end a URL so none reach the terminal inside a link.
+pub fn find_urls(text: &str) -> Vec<(usize, &str)> {
+  let mut urls = Vec::new();
+  let mut from = 0;
+  while let Some(found) = text[from..].find("http") {
+    let start = from + found;
+    let rest = &text[start..];
+    let Some(scheme) = ["https://", "http://"].into_iter().find(|s| rest.starts_with(s)) else {
+      from = start + 4;
+      continue;
+    };
+    let end = rest.find(|c: char| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | '"' | '\'' | '`')).unwrap_or(rest.len());
+    let mut url = &rest[..end];
+    loop {
+      let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+      let trimmed = match trimmed.chars().last() {
+        Some(')') if trimmed.matches('(').count() < trimmed.matches(')').count() => &trimmed[..trimmed.len() - 1],
+        Some(']') if trimmed.matches('[').count() < trimmed.matches(']').count() => &trimmed[..trimmed.len() - 1],
+        _ => trimmed,
+      };
+      if trimmed.len() == url.len() {
+        break;
+      }
+      url = trimmed;
+    }
+    if url.len() > scheme.len() {
+      urls.push((start, url));
+    }
+    from = start + end.max(scheme.len());
+  }
+  urls
+}
+
+/// The paths in a diff file header line (`diff --git a/x b/x`, `--- a/x`,
+/// `+++ b/x`), linked to the files under `repo_root`.
+pub fn diff_header_links(line: &Line, repo_root: &Path) -> Vec<LineLink> {
+  let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+  let indent = text.len() - text.trim_start().len();
+  let header = text[indent..].trim_end();
+  let paths: Vec<(usize, &str)> = if let Some(rest) = header.strip_prefix("diff --git ") {
+    match rest.split_once(" b/") {
+      Some((old, _)) if old.starts_with("a/") => vec![(11, old), (11 + old.len() + 1, &rest[old.len() + 1..])],
+      _ => Vec::new(),
+    }
+  } else if header.starts_with("--- a/") || header.starts_with("+++ b/") {
+    vec![(4, &header[4..])]
+  } else {
+    Vec::new()
+  };
+  paths
+    .into_iter()
+    .map(|(offset, shown)| LineLink {
+      start: text[..indent + offset].width(),
+      width: shown.width(),
+      url: file_url(&repo_root.join(&shown[2..])),
+    })
+    .collect()
+}
+
+/// A `file://` URL for `path`, percent-encoding all but unreserved bytes.
+pub fn file_url(path: &Path) -> String {
+  let mut url = String::from("file://");
+  for &byte in path.as_os_str().as_bytes() {
+    if byte.is_ascii_alphanumeric() || matches!(byte, b'/' | b'-' | b'_' | b'.' | b'~') {
+      url.push(byte as char);
+    } else {
+      url.push_str(&format!("%{byte:02X}"));
+    }
+  }
+  url
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ratatui_core::style::Style;
+  use ratatui_core::text::Span;
+
+  #[test]
+  fn test_find_urls_trims_punctuation_and_unopened_brackets() {
+    let text = "see https://example.com/a_(b). and (http://x.org/y), not http:// or httpx://z";
+    let urls: Vec<&str> = find_urls(text).into_iter().map(|(_, url)| url).collect();
+    assert_eq!(urls, ["https://example.com/a_(b)", "http://x.org/y"]);
+    assert_eq!(find_urls(text)[0].0, 4);
+    let urls: Vec<&str> = find_urls("https://x.org\x07\x1b]52;c;aGk=\x07").into_iter().map(|(_, url)| url).collect();
+    assert_eq!(urls, ["https://x.org"]);
+  }
+
+  #[test]
+  fn test_url_links_cover_markdown_link_text() {
+    let line = Line::from(vec![
+      Span::raw("Read "),
+      Span::styled("the book", Style::default().add_modifier(Modifier::UNDERLINED)),
+      Span::raw(" (https://doc.rust-lang.org/book)"),
+    ]);
+    let links = url_links(&line);
+    assert_eq!(links.len(), 2);
+    assert_eq!((links[0].start, links[0].width), (5, 8));
+    assert_eq!(links[0].url, "https://doc.rust-lang.org/book");
+    assert_eq!((links[1].start, links[1].width), (15, 30));
+  }
+
+  #[test]
+  fn test_diff_header_links_point_into_the_repo() {
+    let root = Path::new("/repo");
+    let line = Line::from("diff --git a/src/my file.rs b/src/my file.rs");
+    let links = diff_header_links(&line, root);
+    assert_eq!(links.len(), 2);
+    assert_eq!((links[0].start, links[0].width), (11, 16));
+    assert_eq!(links[1].start, 28);
+    assert_eq!(links[0].url, "file:///repo/src/my%20file.rs");
+
+    let links = diff_header_links(&Line::from("+++ b/Cargo.toml"), root);
+    assert_eq!((links[0].start, links[0].url.as_str()), (4, "file:///repo/Cargo.toml"));
+    assert!(diff_header_links(&Line::from("--- /dev/null"), root).is_empty());
+    assert!(diff_header_links(&Line::from("+ let x = 1;"), root).is_empty());
+  }
+}
@@ -1,6 +1,8 @@
+//! Rendered markdown preview.
+
 use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
-use ratatui::style::{Modifier, Style};
-use ratatui::text::{Line, Span};
+use ratatui_core::style::{Modifier, Style};
+use ratatui_core::text::{Line, Span};
 use unicode_width::UnicodeWidthStr;
 
 use super::text::SyntaxHighlighter;
@@ -37,11 +39,9 @@ pub fn render_markdown(content: &str, highlighter: &SyntaxHighlighter, theme: &T
           flush_line(&mut current_spans, &mut lines);
           heading_level = level;
         }
-        Tag::Paragraph => {
-          // Start new paragraph - add blank line if not at start
-          if !lines.is_empty() && !in_blockquote {
-            lines.push(Line::from(""));
-          }
+        // Start new paragraph - add blank line if not at start
+        Tag::Paragraph if !lines.is_empty() && !in_blockquote => {
+          lines.push(Line::from(""));
         }
         Tag::CodeBlock(kind) => {
           flush_line(&mut current_spans, &mut lines);
@@ -133,7 +133,7 @@ pub fn render_markdown(content: &str, highlighter: &SyntaxHighlighter, theme: &T
         }
         TagEnd::CodeBlock => {
           // Render code block with syntax highlighting
-          let highlighted = highlighter.highlight(&code_block_content, &code_block_lang);
+          let highlighted = highlighter.highlight_code(&code_block_content, &code_block_lang);
 
           // Add code block delimiter
           lines.push(Line::from(Span::styled(
@@ -141,14 +141,11 @@ pub fn render_markdown(content: &str, highlighter: &SyntaxHighlighter, theme: &T
             Style::default().fg(theme.border),
           )));
 
-          // Add highlighted lines (strip line numbers from highlighter output)
+          // Add highlighted lines
           for line in highlighted {
             let mut code_spans: Vec<Span<'static>> = Vec::new();
             code_spans.push(Span::styled("  ", Style::default())); // Indent
-            // Skip the line number span (first span) and collect the rest
-            for span in line.spans.into_iter().skip(1) {
-              code_spans.push(span);
-            }
+            code_spans.extend(line.spans);
             lines.push(Line::from(code_spans));
           }
 
@@ -195,10 +192,8 @@ pub fn render_markdown(content: &str, highlighter: &SyntaxHighlighter, theme: &T
           table_rows.push(table_row.clone());
           is_header_row = false;
         }
-        TagEnd::TableRow => {
-          if !is_header_row {
-            table_rows.push(table_row.clone());
-          }
+        TagEnd::TableRow if !is_header_row => {
+          table_rows.push(table_row.clone());
         }
         TagEnd::TableCell => {
           // Cell content is accumulated in current_spans
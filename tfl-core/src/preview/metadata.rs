@@ -1,36 +1,73 @@
+//! File and image metadata for the preview header.
+
 use std::fs::Metadata;
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::path::Path;
 use std::time::SystemTime;
 
+use image::{ColorType, ImageDecoder};
+use ratatui_core::style::Style;
+use ratatui_core::text::{Line, Span};
+
 use crate::git::{GitCommit, GitRepo};
+use crate::theme::Theme;
 
+/// Filesystem details shown in the preview header.
 #[derive(Debug, Clone)]
 pub struct FileMetadata {
+  /// Size in bytes
   pub size: u64,
+  /// Last content change
   pub modified: Option<SystemTime>,
+  /// Birth time, where the filesystem records one
   pub created: Option<SystemTime>,
+  /// Mode bits
   pub permissions: Option<u32>,
+  /// Owner's user name
   pub owner: Option<String>,
+  /// Group name
   pub group: Option<String>,
+  /// Lines in the file, for text previews
   pub line_count: Option<usize>,
 }
 
+/// What the preview header shows for an image.
 #[derive(Debug, Clone)]
 pub struct ImageMetadata {
+  /// Width in pixels
   pub width: u32,
+  /// Height in pixels
   pub height: u32,
+  /// Reduced ratio, e.g. `16:9`
   pub aspect_ratio: String,
+  /// Container format, e.g. "PNG"
+  pub format: Option<String>,
+  /// Channels and bit depth, e.g. "RGBA, 8-bit"
+  pub color: Option<String>,
+  /// Camera details, for photos that carry them
   pub exif: Option<ExifData>,
 }
 
+/// Camera details from a photo's EXIF tags.
 #[derive(Debug, Clone)]
 pub struct ExifData {
+  /// Make and model
   pub camera: Option<String>,
+  /// Lens model
+  pub lens: Option<String>,
+  /// Exposure time, e.g. `1/250s`
   pub exposure: Option<String>,
+  /// F-number, e.g. `f/2.8`
+  pub aperture: Option<String>,
+  /// Focal length, e.g. `35mm`
+  pub focal_length: Option<String>,
+  /// ISO speed
   pub iso: Option<String>,
+  /// When the photo was taken
+  pub taken: Option<String>,
 }
 
+/// Metadata of `path`, following symlinks, or None if it can't be stat'ed.
 pub fn get_file_metadata(path: &Path) -> Option<FileMetadata> {
   let meta = std::fs::metadata(path).ok()?;
   Some(FileMetadata {
@@ -44,6 +81,7 @@ pub fn get_file_metadata(path: &Path) -> Option<FileMetadata> {
   })
 }
 
+/// Like `get_file_metadata`, with the line count of a text preview.
 pub fn get_file_metadata_with_lines(path: &Path, line_count: usize) -> Option<FileMetadata> {
   let mut meta = get_file_metadata(path)?;
   meta.line_count = Some(line_count);
@@ -84,25 +122,44 @@ fn get_group(meta: &Metadata) -> Option<String> {
   Some(gid.to_string())
 }
 
+/// Dimensions, format, colour type and EXIF of an image, or None if it
+/// can't be decoded.
 pub fn get_image_metadata(path: &Path) -> Option<ImageMetadata> {
-  let (width, height) = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("jxl")) {
-    jxl_dimensions(path)?
+  let (format, (width, height), color) = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("jxl")) {
+    let (dimensions, color) = jxl_info(path)?;
+    (Some("JXL".to_string()), dimensions, color)
   } else {
-    image::image_dimensions(path).ok()?
+    let reader = image::ImageReader::open(path).ok()?.with_guessed_format().ok()?;
+    let format = reader.format().and_then(|f| f.extensions_str().first()).map(|e| e.to_uppercase());
+    let decoder = reader.into_decoder().ok()?;
+    (format, decoder.dimensions(), decoder.color_type())
   };
   let aspect_ratio = calculate_aspect_ratio(width, height);
   let exif = get_exif_data(path);
 
-  Some(ImageMetadata { width, height, aspect_ratio, exif })
+  Some(ImageMetadata { width, height, aspect_ratio, format, color: Some(describe_color(color)), exif })
 }
 
-fn jxl_dimensions(path: &Path) -> Option<(u32, u32)> {
-  use image::ImageDecoder;
-
+fn jxl_info(path: &Path) -> Option<((u32, u32), ColorType)> {
   let file = std::fs::File::open(path).ok()?;
   let reader = std::io::BufReader::new(file);
   let decoder = jxl_oxide::integration::JxlDecoder::new(reader).ok()?;
-  Some(decoder.dimensions())
+  Some((decoder.dimensions(), decoder.color_type()))
+}
+
+/// Channels and bit depth, e.g. `RGBA, 8-bit`.
+pub fn describe_color(color: ColorType) -> String {
+  let channels = match color {
+    ColorType::L8 | ColorType::L16 => "Grayscale",
+    ColorType::La8 | ColorType::La16 => "Grayscale + alpha",
+    ColorType::Rgb8 | ColorType::Rgb16 | ColorType::Rgb32F => "RGB",
+    _ => "RGBA",
+  };
+  let depth = match color {
+    ColorType::Rgb32F | ColorType::Rgba32F => "32-bit float".to_string(),
+    _ => format!("{}-bit", color.bits_per_pixel() / u16::from(color.channel_count())),
+  };
+  format!("{channels}, {depth}")
 }
 
 fn get_exif_data(path: &Path) -> Option<ExifData> {
@@ -111,9 +168,19 @@ fn get_exif_data(path: &Path) -> Option<ExifData> {
   let exif_reader = exif::Reader::new();
   let exif = exif_reader.read_from_container(&mut buf_reader).ok()?;
 
-  let camera = exif
-    .get_field(exif::Tag::Model, exif::In::PRIMARY)
-    .map(|f| f.display_value().to_string().trim_matches('"').to_string());
+  let text = |tag: exif::Tag| {
+    exif
+      .get_field(tag, exif::In::PRIMARY)
+      .map(|f| f.display_value().to_string().trim_matches('"').trim().to_string())
+      .filter(|s| !s.is_empty())
+  };
+
+  let camera = match (text(exif::Tag::Make), text(exif::Tag::Model)) {
+    // Models usually repeat the make ("Canon" / "Canon EOS R6")
+    (Some(make), Some(model)) if !model.starts_with(&make) => Some(format!("{make} {model}")),
+    (make, model) => model.or(make),
+  };
+  let lens = text(exif::Tag::LensModel);
 
   let exposure = exif.get_field(exif::Tag::ExposureTime, exif::In::PRIMARY).map(|f| {
     let val = f.display_value().to_string();
@@ -125,14 +192,68 @@ fn get_exif_data(path: &Path) -> Option<ExifData> {
   let iso = exif
     .get_field(exif::Tag::PhotographicSensitivity, exif::In::PRIMARY)
     .map(|f| format!("ISO {}", f.display_value()));
-
-  if camera.is_none() && exposure.is_none() && iso.is_none() {
+  let aperture = text(exif::Tag::FNumber);
+  let focal_length = text(exif::Tag::FocalLength).map(|f| format!("{f} mm"));
+  let taken = text(exif::Tag::DateTimeOriginal);
+
+  let data = ExifData { camera, lens, exposure, aperture, focal_length, iso, taken };
+  if [&data.camera, &data.lens, &data.exposure, &data.aperture, &data.focal_length, &data.iso, &data.taken]
+    .iter()
+    .all(|f| f.is_none())
+  {
     return None;
   }
+  Some(data)
+}
 
-  Some(ExifData { camera, exposure, iso })
+/// Labelled rows describing an image, shown in place of the picture when
+/// the terminal has no graphics protocol.
+pub fn render_image_info<'a>(meta: Option<&ImageMetadata>, file_size: u64, theme: &Theme) -> Vec<Line<'a>> {
+  let Some(meta) = meta else {
+    return vec![Line::from(Span::styled(" Unreadable image", Style::default().fg(theme.warning)))];
+  };
+  let mut rows: Vec<(&str, String)> = Vec::new();
+  if let Some(ref format) = meta.format {
+    rows.push(("Format", format.clone()));
+  }
+  rows.push(("Dimensions", format!("{}×{} ({})", meta.width, meta.height, meta.aspect_ratio)));
+  if let Some(ref color) = meta.color {
+    rows.push(("Color", color.clone()));
+  }
+  rows.push(("Size", format_size(file_size)));
+  if let Some(ref exif) = meta.exif {
+    let settings: Vec<&str> = [&exif.exposure, &exif.aperture, &exif.focal_length, &exif.iso]
+      .into_iter()
+      .filter_map(|f| f.as_deref())
+      .collect();
+    for (label, value) in [("Camera", &exif.camera), ("Lens", &exif.lens), ("Taken", &exif.taken)] {
+      if let Some(value) = value {
+        rows.push((label, value.clone()));
+      }
+    }
+    if !settings.is_empty() {
+      rows.push(("Settings", settings.join("  ")));
+    }
+  }
+
+  let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+  let mut lines: Vec<Line> = rows
+    .into_iter()
+    .map(|(label, value)| {
+      Line::from(vec![
+        Span::styled(format!(" {label:<label_width$}  "), Style::default().fg(theme.text_dim)),
+        Span::styled(value, Style::default().fg(theme.text)),
+      ])
+    })
+    .collect();
+  lines.push(Line::from(Span::styled(
+    " No terminal graphics support; showing image details",
+    Style::default().fg(theme.text_muted),
+  )));
+  lines
 }
 
+/// Mode bits as `rwxr-xr-x`.
 pub fn format_permissions(mode: u32) -> String {
   let mut result = String::with_capacity(9);
 
@@ -154,6 +275,7 @@ pub fn format_permissions(mode: u32) -> String {
   result
 }
 
+/// `bytes` rounded with a unit, e.g. `4.2 KB`.
 pub fn format_size(bytes: u64) -> String {
   const KB: u64 = 1024;
   const MB: u64 = KB * 1024;
@@ -170,6 +292,7 @@ pub fn format_size(bytes: u64) -> String {
   }
 }
 
+/// How long ago `time` was, e.g. `3d ago`.
 pub fn format_time(time: SystemTime) -> String {
   let now = SystemTime::now();
   let Ok(duration) = now.duration_since(time) else {
@@ -207,6 +330,7 @@ pub fn format_time(time: SystemTime) -> String {
   }
 }
 
+/// The common ratio within 2% of `width:height`, else the ratio reduced.
 pub fn calculate_aspect_ratio(width: u32, height: u32) -> String {
   if width == 0 || height == 0 {
     return "N/A".to_string();
@@ -265,6 +389,7 @@ fn format_exposure(val: &str) -> String {
   val.to_string()
 }
 
+/// The last `limit` commits that touched `path`, none outside a repository.
 #[allow(dead_code)]
 pub fn get_git_commits(git_repo: Option<&GitRepo>, path: &Path, limit: usize) -> Vec<GitCommit> {
   git_repo.map(|r| r.get_file_commits(path, limit)).unwrap_or_default()
@@ -341,4 +466,36 @@ mod tests {
     let now = SystemTime::now();
     assert_eq!(format_time(now), "now");
   }
+
+  #[test]
+  fn test_describe_color() {
+    assert_eq!(describe_color(ColorType::Rgba8), "RGBA, 8-bit");
+    assert_eq!(describe_color(ColorType::L16), "Grayscale, 16-bit");
+    assert_eq!(describe_color(ColorType::La8), "Grayscale + alpha, 8-bit");
+    assert_eq!(describe_color(ColorType::Rgb32F), "RGB, 32-bit float");
+  }
+
+  #[test]
+  fn test_image_metadata_and_info() {
+    let dir = std::env::temp_dir().join(format!("tfl_test_image_meta_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("pic.png");
+    image::RgbImage::new(1920, 1080).save(&file).unwrap();
+
+    let meta = get_image_metadata(&file).unwrap();
+    assert_eq!((meta.width, meta.height), (1920, 1080));
+    assert_eq!(meta.format.as_deref(), Some("PNG"));
+    assert_eq!(meta.color.as_deref(), Some("RGB, 8-bit"));
+    assert!(meta.exif.is_none());
+
+    let lines: Vec<String> = render_image_info(Some(&meta), 2048, &Theme::dark()).iter().map(|l| l.to_string()).collect();
+    assert_eq!(lines[0], " Format      PNG");
+    assert_eq!(lines[1], " Dimensions  1920×1080 (16:9)");
+    assert_eq!(lines[2], " Color       RGB, 8-bit");
+    assert_eq!(lines[3], " Size        2.0 KB");
+
+    assert!(get_image_metadata(&dir.join("missing.png")).is_none());
+    let _ = fs::remove_dir_all(&dir);
+  }
 }
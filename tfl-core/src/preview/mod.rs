@@ -0,0 +1,312 @@
+//! Preview building blocks: detecting what kind of preview a file gets and
+//! turning it into styled lines. Loading, caching and image rendering stay in
+//! the frontend.
+
+pub mod archive;
+pub mod blame;
+pub mod diff;
+pub mod directory;
+pub mod hex;
+pub mod hover;
+pub mod links;
+pub mod markdown;
+pub mod metadata;
+pub mod notebook;
+pub mod special;
+pub mod structured;
+pub mod text;
+
+use std::path::Path;
+
+use ratatui_core::text::Line;
+
+use self::blame::BlameData;
+use self::metadata::{FileMetadata, ImageMetadata};
+use self::special::SpecialFile;
+use crate::git::GitCommit;
+
+/// Files above this size are only previewed as images, never read as text
+pub const MAX_TEXT_BYTES: u64 = 1024 * 1024; // 1MB
+
+/// How a file is previewed, decided from its type and extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviewType {
+  /// Highlighted source or plain text
+  Text,
+  /// Rendered Markdown
+  Markdown,
+  /// Decoded and drawn as an image
+  Image,
+  /// Hex dump
+  Binary,
+  /// Summary of the directory's children
+  Directory,
+  /// Listing of the archive's entries
+  Archive,
+  /// Git diff of the file
+  Diff,
+  /// A file with no content
+  Empty,
+  /// Over `MAX_TEXT_BYTES` and not an image
+  TooLarge,
+  /// A pipe, socket or device, described but never read
+  Special(SpecialFile),
+  /// Reading the file took longer than the preview timeout
+  TimedOut,
+  /// Reading the file failed
+  Error(String),
+}
+
+/// A built preview: its lines and what the header and the preview's modes need.
+pub struct PreviewContent {
+  /// Lines as drawn
+  pub lines: Vec<Line<'static>>,
+  /// How the file was previewed
+  pub preview_type: PreviewType,
+  /// Lines in the file, which may be more than
+  /// in `lines`
+  pub line_count: usize,
+  /// Size in bytes
+  pub file_size: u64,
+  /// Extension without the dot
+  pub extension: String,
+  /// Filesystem details for the header
+  pub metadata: Option<FileMetadata>,
+  /// Image details for the header
+  pub image_metadata: Option<ImageMetadata>,
+  /// Recent commits that touched the file
+  pub git_commits: Vec<GitCommit>,
+  /// Blame, when blame mode is on
+  pub blame_data: Option<BlameData>,
+  /// Raw (unformatted) lines for structured data files, if formatting was applied.
+  pub raw_lines: Option<Vec<Line<'static>>>,
+  /// Whether this file is a structured data file (JSON, YAML, XML...).
+  pub is_structured: bool,
+  /// Indices of the hunk headers in `lines`, for navigation
+  pub diff_hunks: Vec<usize>,
+  /// Two-column layout of the diff, used when side-by-side mode is on and the pane is wide enough
+  pub side_by_side: Option<diff::SideBySideDiff>,
+  /// The diff being shown, kept for patch export and hunk staging
+  pub file_diff: Option<diff::FileDiff>,
+}
+
+/// File extension without the dot, empty when there is none
+pub fn get_extension(path: &Path) -> String {
+  path
+    .extension()
+    .map(|e| e.to_string_lossy().to_string())
+    .unwrap_or_default()
+}
+
+/// Extensions previewed as images
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "tiff", "tif", "ico", "svg", "jxl"];
+/// Extensions previewed as Markdown
+pub const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "mkdn"];
+
+/// How `path` would be previewed, from its type and extension without reading
+/// it.
+pub fn detect_preview_type(path: &Path) -> PreviewType {
+  if path.is_dir() {
+    return PreviewType::Directory;
+  }
+
+  let metadata = match path.metadata() {
+    Ok(m) => m,
+    Err(e) => return PreviewType::Error(e.to_string()),
+  };
+
+  // Before anything opens it: reading a FIFO or a device can hang
+  if let Some(kind) = SpecialFile::from_metadata(&metadata) {
+    return PreviewType::Special(kind);
+  }
+
+  if metadata.len() == 0 {
+    return PreviewType::Empty;
+  }
+
+  // Check for archive types early (they can be large)
+  if archive::is_archive(path) {
+    return PreviewType::Archive;
+  }
+
+  if metadata.len() > MAX_TEXT_BYTES {
+    // Check if it's an image (images can be large)
+    let ext = get_extension(path);
+    if IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
+      return PreviewType::Image;
+    }
+    return PreviewType::TooLarge;
+  }
+
+  // Check extension for images
+  let ext = get_extension(path);
+  if IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
+    return PreviewType::Image;
+  }
+
+  // Check extension for markdown
+  if MARKDOWN_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
+    return PreviewType::Markdown;
+  }
+
+  // Try to detect if binary using infer
+  if let Ok(data) = std::fs::read(path) {
+    if let Some(kind) = infer::get(&data) {
+      let mime = kind.mime_type();
+      if mime.starts_with("image/") {
+        return PreviewType::Image;
+      }
+      if !mime.starts_with("text/") {
+        return PreviewType::Binary;
+      }
+    }
+
+    // Check if content looks like text (no null bytes in first chunk)
+    let check_len = data.len().min(8192);
+    if data[..check_len].contains(&0) {
+      return PreviewType::Binary;
+    }
+  }
+
+  PreviewType::Text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  #[test]
+  fn test_detect_directory() {
+    let dir = std::env::temp_dir();
+    assert_eq!(detect_preview_type(&dir), PreviewType::Directory);
+  }
+
+  #[test]
+  fn test_detect_text_file() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_detect");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("test.txt");
+    fs::write(&file, "hello world").unwrap();
+    assert_eq!(detect_preview_type(&file), PreviewType::Text);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_fifo_and_device_without_reading() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_fifo");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // Named like an archive: the archive check opens files by extension
+    let fifo = dir.join("pipe.zip");
+    let c_path = std::ffi::CString::new(fifo.to_string_lossy().as_bytes()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o644) }, 0);
+    assert_eq!(detect_preview_type(&fifo), PreviewType::Special(SpecialFile::Fifo));
+    assert_eq!(detect_preview_type(Path::new("/dev/zero")), PreviewType::Special(SpecialFile::CharDevice));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_empty_file() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_empty");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("empty.txt");
+    fs::write(&file, "").unwrap();
+    assert_eq!(detect_preview_type(&file), PreviewType::Empty);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_binary_file() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_binary");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("binary.bin");
+    fs::write(&file, [0u8, 1, 2, 3, 0, 0, 0, 0]).unwrap();
+    assert_eq!(detect_preview_type(&file), PreviewType::Binary);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_image_by_extension() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_imgext");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("photo.png");
+    fs::write(&file, "fake png data").unwrap();
+    assert_eq!(detect_preview_type(&file), PreviewType::Image);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_archive_zip() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_archive_zip");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("test.zip");
+    // Create a minimal valid ZIP file
+    {
+      let f = fs::File::create(&file).unwrap();
+      let mut zip = zip::ZipWriter::new(f);
+      let options = zip::write::SimpleFileOptions::default();
+      zip.start_file("test.txt", options).unwrap();
+      std::io::Write::write_all(&mut zip, b"test").unwrap();
+      zip.finish().unwrap();
+    }
+    assert_eq!(detect_preview_type(&file), PreviewType::Archive);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_archive_tar_gz() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_archive_tgz");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("test.tar.gz");
+    // Just create a fake file - detection is by extension
+    fs::write(&file, "fake tar.gz data").unwrap();
+    assert_eq!(detect_preview_type(&file), PreviewType::Archive);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_markdown_file() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_markdown");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("README.md");
+    fs::write(&file, "# Hello World").unwrap();
+    assert_eq!(detect_preview_type(&file), PreviewType::Markdown);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_markdown_extensions() {
+    let dir = std::env::temp_dir().join("tui_explorer_test_md_ext");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    for ext in &["md", "markdown", "mdown", "mkd", "mkdn"] {
+      let file = dir.join(format!("test.{ext}"));
+      fs::write(&file, "# Test").unwrap();
+      assert_eq!(detect_preview_type(&file), PreviewType::Markdown, "Failed for extension: {ext}");
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_detect_nonexistent() {
+    let result = detect_preview_type(Path::new("/nonexistent/file.txt"));
+    assert!(matches!(result, PreviewType::Error(_)));
+  }
+
+  #[test]
+  fn test_get_extension() {
+    assert_eq!(get_extension(Path::new("foo.rs")), "rs");
+    assert_eq!(get_extension(Path::new("foo")), "");
+    assert_eq!(get_extension(Path::new("foo.tar.gz")), "gz");
+  }
+}
@@ -0,0 +1,204 @@
+//! Rendered Jupyter notebook (.ipynb) preview: markdown cells rendered,
+//! code cells highlighted in the kernel's language, and text outputs shown.
+//! Images, HTML and other rich outputs are named instead of dumped as base64.
+
+use ratatui_core::style::{Modifier, Style};
+use ratatui_core::text::{Line, Span};
+use serde_json::Value;
+
+use super::markdown::render_markdown;
+use super::text::SyntaxHighlighter;
+use crate::theme::Theme;
+
+/// Lines of one output shown before the rest is cut off
+const MAX_OUTPUT_LINES: usize = 20;
+
+/// Whether `extension` is a Jupyter notebook's.
+pub fn is_notebook(extension: &str) -> bool {
+  extension.eq_ignore_ascii_case("ipynb")
+}
+
+/// Renders the cells of a notebook, or None when `content` is not one.
+pub fn render_notebook(content: &str, highlighter: &SyntaxHighlighter, theme: &Theme) -> Option<Vec<Line<'static>>> {
+  let notebook: Value = serde_json::from_str(content).ok()?;
+  let cells = notebook.get("cells")?.as_array()?;
+  let extension = code_extension(&notebook);
+
+  let mut lines = Vec::new();
+  for cell in cells {
+    if !lines.is_empty() {
+      lines.push(Line::from(""));
+    }
+    let source = joined(cell.get("source"));
+    match cell.get("cell_type").and_then(Value::as_str) {
+      Some("markdown") => lines.extend(render_markdown(&source, highlighter, theme)),
+      Some("code") => {
+        let count = execution_count(cell);
+        lines.push(Line::from(Span::styled(
+          format!(" In [{count}]:"),
+          Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        )));
+        lines.extend(highlighter.highlight(&source, &extension));
+        for output in cell.get("outputs").and_then(Value::as_array).into_iter().flatten() {
+          render_output(output, &count, theme, &mut lines);
+        }
+      }
+      _ => {
+        for line in source.lines() {
+          lines.push(Line::from(Span::styled(format!(" {line}"), Style::default().fg(theme.text_dim))));
+        }
+      }
+    }
+  }
+  Some(lines)
+}
+
+fn render_output(output: &Value, count: &str, theme: &Theme, lines: &mut Vec<Line<'static>>) {
+  match output.get("output_type").and_then(Value::as_str) {
+    Some("stream") => {
+      let color = if output.get("name").and_then(Value::as_str) == Some("stderr") { theme.warning } else { theme.text };
+      push_text(&joined(output.get("text")), Style::default().fg(color), theme, lines);
+    }
+    Some("execute_result") => {
+      lines.push(Line::from(Span::styled(
+        format!(" Out[{count}]:"),
+        Style::default().fg(theme.success).add_modifier(Modifier::BOLD),
+      )));
+      render_data(output, theme, lines);
+    }
+    Some("display_data") => render_data(output, theme, lines),
+    Some("error") => {
+      let name = output.get("ename").and_then(Value::as_str).unwrap_or("Error");
+      let value = output.get("evalue").and_then(Value::as_str).unwrap_or_default();
+      lines.push(Line::from(Span::styled(format!(" {name}: {value}"), Style::default().fg(theme.error))));
+    }
+    _ => {}
+  }
+}
+
+/// The plain text of a rich output, then the kinds that can't be shown.
+fn render_data(output: &Value, theme: &Theme, lines: &mut Vec<Line<'static>>) {
+  let Some(data) = output.get("data").and_then(Value::as_object) else {
+    return;
+  };
+  if let Some(text) = data.get("text/plain") {
+    push_text(&joined(Some(text)), Style::default().fg(theme.text), theme, lines);
+  }
+  let hidden: Vec<&str> = data.keys().map(String::as_str).filter(|mime| *mime != "text/plain").collect();
+  if !hidden.is_empty() {
+    lines.push(Line::from(Span::styled(
+      format!(" [{} not shown]", hidden.join(", ")),
+      Style::default().fg(theme.text_muted),
+    )));
+  }
+}
+
+fn push_text(text: &str, style: Style, theme: &Theme, lines: &mut Vec<Line<'static>>) {
+  let total = text.lines().count();
+  for line in text.lines().take(MAX_OUTPUT_LINES) {
+    lines.push(Line::from(Span::styled(format!(" {line}"), style)));
+  }
+  if total > MAX_OUTPUT_LINES {
+    lines.push(Line::from(Span::styled(
+      format!(" … {} more lines", total - MAX_OUTPUT_LINES),
+      Style::default().fg(theme.text_muted),
+    )));
+  }
+}
+
+/// Sources and outputs are stored as one string or as a list of lines.
+fn joined(value: Option<&Value>) -> String {
+  match value {
+    Some(Value::String(s)) => s.clone(),
+    Some(Value::Array(parts)) => parts.iter().filter_map(Value::as_str).collect(),
+    _ => String::new(),
+  }
+}
+
+/// Blank for cells that never ran, as Jupyter shows them.
+fn execution_count(cell: &Value) -> String {
+  cell.get("execution_count").and_then(Value::as_u64).map(|n| n.to_string()).unwrap_or_else(|| " ".to_string())
+}
+
+/// File extension of the kernel's language, for highlighting code cells.
+fn code_extension(notebook: &Value) -> String {
+  let metadata = notebook.get("metadata");
+  let info = metadata.and_then(|m| m.get("language_info"));
+  if let Some(ext) = info.and_then(|i| i.get("file_extension")).and_then(Value::as_str) {
+    return ext.trim_start_matches('.').to_string();
+  }
+  let language = info
+    .and_then(|i| i.get("name"))
+    .or_else(|| metadata.and_then(|m| m.get("kernelspec")).and_then(|k| k.get("language")))
+    .and_then(Value::as_str)
+    .unwrap_or("python");
+  match language.to_lowercase().as_str() {
+    "python" => "py".to_string(),
+    "julia" => "jl".to_string(),
+    "javascript" => "js".to_string(),
+    "typescript" => "ts".to_string(),
+    other => other.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn text(lines: &[Line]) -> Vec<String> {
+    lines.iter().map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect()).collect()
+  }
+
+  #[test]
+  fn test_notebook_renders_cells_and_skips_binary_outputs() {
+    let notebook = r##"{
+      "cells": [
+        {"cell_type": "markdown", "source": ["# Analysis\n", "Some *notes*"]},
+        {"cell_type": "code", "execution_count": 3, "source": "print('hi')\n1 + 1",
+         "outputs": [
+           {"output_type": "stream", "name": "stdout", "text": ["hi\n"]},
+           {"output_type": "execute_result", "execution_count": 3, "data": {"text/plain": ["2"]}},
+           {"output_type": "display_data", "data": {"image/png": "iVBORw0KGgoAAAANSUhEUgAAAAEAAAAB", "text/plain": ["<Figure>"]}},
+           {"output_type": "error", "ename": "ZeroDivisionError", "evalue": "division by zero", "traceback": []}
+         ]},
+        {"cell_type": "code", "execution_count": null, "source": "", "outputs": []}
+      ],
+      "metadata": {"language_info": {"name": "python", "file_extension": ".py"}},
+      "nbformat": 4
+    }"##;
+    let highlighter = SyntaxHighlighter::new("base16-ocean.dark");
+    let lines = text(&render_notebook(notebook, &highlighter, &Theme::dark()).unwrap());
+    assert!(lines.iter().any(|l| l.contains("Analysis")));
+    assert!(lines.contains(&" In [3]:".to_string()));
+    assert!(lines.iter().any(|l| l.ends_with("print('hi')")));
+    assert!(lines.contains(&" hi".to_string()));
+    assert!(lines.contains(&" Out[3]:".to_string()));
+    assert!(lines.contains(&" 2".to_string()));
+    assert!(lines.contains(&" <Figure>".to_string()));
+    assert!(lines.contains(&" [image/png not shown]".to_string()));
+    assert!(lines.contains(&" ZeroDivisionError: division by zero".to_string()));
+    assert!(lines.contains(&" In [ ]:".to_string()));
+    assert!(!lines.iter().any(|l| l.contains("iVBOR")));
+  }
+
+  #[test]
+  fn test_long_outputs_are_cut_and_other_json_is_not_a_notebook() {
+    let output: Vec<String> = (0..30).map(|i| format!("line {i}\n")).collect();
+    let notebook = serde_json::json!({
+      "cells": [{"cell_type": "code", "execution_count": 1, "source": "", "outputs": [
+        {"output_type": "stream", "name": "stdout", "text": output}
+      ]}],
+      "metadata": {"kernelspec": {"language": "julia"}}
+    });
+    assert_eq!(code_extension(&notebook), "jl");
+    let highlighter = SyntaxHighlighter::new("base16-ocean.dark");
+    let lines = text(&render_notebook(&notebook.to_string(), &highlighter, &Theme::dark()).unwrap());
+    assert!(lines.contains(&" line 19".to_string()));
+    assert!(!lines.contains(&" line 20".to_string()));
+    assert_eq!(lines.last().unwrap(), " … 10 more lines");
+
+    assert!(render_notebook(r#"{"name": "package"}"#, &highlighter, &Theme::dark()).is_none());
+    assert!(render_notebook("not json", &highlighter, &Theme::dark()).is_none());
+    assert!(is_notebook("IPYNB"));
+  }
+}
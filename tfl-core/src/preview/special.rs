@@ -0,0 +1,104 @@
+//! Named pipes, sockets and device files. Opening a pipe waits for a writer
+//! and a device can stream forever, so these are described from their
+//! metadata and never read.
+
+use std::fs::Metadata;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+use ratatui_core::style::Style;
+use ratatui_core::text::{Line, Span};
+
+use crate::theme::Theme;
+
+/// A filesystem entry that isn't a file, directory or symlink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialFile {
+  /// Named pipe
+  Fifo,
+  /// Unix domain socket
+  Socket,
+  /// Character device, e.g. a terminal
+  CharDevice,
+  /// Block device, e.g. a disk
+  BlockDevice,
+}
+
+impl SpecialFile {
+  /// The kind of `meta`, or None for regular files, directories and
+  /// symlinks.
+  pub fn from_metadata(meta: &Metadata) -> Option<Self> {
+    let file_type = meta.file_type();
+    if file_type.is_fifo() {
+      Some(Self::Fifo)
+    } else if file_type.is_socket() {
+      Some(Self::Socket)
+    } else if file_type.is_char_device() {
+      Some(Self::CharDevice)
+    } else if file_type.is_block_device() {
+      Some(Self::BlockDevice)
+    } else {
+      None
+    }
+  }
+
+  /// The kind spelled out, e.g. `Named pipe (FIFO)`.
+  pub fn label(self) -> &'static str {
+    match self {
+      Self::Fifo => "Named pipe (FIFO)",
+      Self::Socket => "Unix domain socket",
+      Self::CharDevice => "Character device",
+      Self::BlockDevice => "Block device",
+    }
+  }
+
+  /// Why there is no content to show.
+  fn note(self) -> &'static str {
+    match self {
+      Self::Fifo => "Not read: opening a pipe blocks until something writes to it",
+      Self::Socket => "Not read: sockets are connected to, not read",
+      Self::CharDevice | Self::BlockDevice => "Not read: a device can block or stream without end",
+    }
+  }
+}
+
+/// Preview lines for a special file: its kind, device numbers for devices,
+/// and why it is not shown.
+pub fn render_special(kind: SpecialFile, meta: &Metadata, theme: &Theme) -> Vec<Line<'static>> {
+  let mut rows = vec![("Type", kind.label().to_string())];
+  if matches!(kind, SpecialFile::CharDevice | SpecialFile::BlockDevice) {
+    let rdev = meta.rdev();
+    // dev_t is laid out differently per platform
+    let (major, minor) = (libc::major(rdev as libc::dev_t), libc::minor(rdev as libc::dev_t));
+    rows.push(("Device", format!("{major}:{minor}")));
+  }
+  let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+  let mut lines: Vec<Line> = rows
+    .into_iter()
+    .map(|(label, value)| {
+      Line::from(vec![
+        Span::styled(format!(" {label:<label_width$}  "), Style::default().fg(theme.text_dim)),
+        Span::styled(value, Style::default().fg(theme.text)),
+      ])
+    })
+    .collect();
+  lines.push(Line::from(Span::styled(format!(" {}", kind.note()), Style::default().fg(theme.text_muted))));
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_special_files_from_metadata() {
+    let null = std::fs::metadata("/dev/null").unwrap();
+    assert_eq!(SpecialFile::from_metadata(&null), Some(SpecialFile::CharDevice));
+    let text: Vec<String> = render_special(SpecialFile::CharDevice, &null, &Theme::dark())
+      .iter()
+      .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+      .collect();
+    assert_eq!(text[0], " Type    Character device");
+    assert_eq!(text[1], " Device  1:3");
+    assert!(SpecialFile::from_metadata(&std::fs::metadata("/").unwrap()).is_none());
+  }
+}
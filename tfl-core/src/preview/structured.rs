@@ -0,0 +1,341 @@
+//! Structured data (JSON, JSON Lines, TOML, YAML and XML) pretty-printing
+//! and validation, shown with syntax highlighting.
+
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+use yaml_rust2::YamlLoader;
+
+/// Result of attempting to format structured data.
+pub enum FormatResult {
+  /// Successfully formatted content with the given extension for highlighting.
+  Formatted {
+    /// The text to show
+    content: String,
+    /// Extension to highlight it as
+    extension: String,
+  },
+  /// Failed to parse the content; the message names the offending line.
+  Error(String),
+}
+
+/// Detects if a file extension indicates structured data.
+pub fn is_structured_data(extension: &str) -> bool {
+  matches!(extension.to_lowercase().as_str(), "json" | "jsonl" | "ndjson" | "toml" | "yaml" | "yml" | "xml")
+}
+
+fn formatted(content: String, extension: &str) -> FormatResult {
+  FormatResult::Formatted { content, extension: extension.to_string() }
+}
+
+/// 1-based line of byte `offset` in `content`.
+fn line_at(content: &str, offset: usize) -> usize {
+  content.as_bytes()[..offset.min(content.len())].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+/// Pretty-prints JSON content with 2-space indentation.
+pub fn format_json(content: &str) -> FormatResult {
+  match serde_json::from_str::<serde_json::Value>(content) {
+    Ok(value) => match serde_json::to_string_pretty(&value) {
+      Ok(pretty) => formatted(pretty, "json"),
+      Err(e) => FormatResult::Error(format!("Invalid JSON: {e}")),
+    },
+    Err(e) => FormatResult::Error(format!("Invalid JSON: {e}")),
+  }
+}
+
+/// Pretty-prints each record of JSON Lines content, separated by blank lines.
+pub fn format_json_lines(content: &str) -> FormatResult {
+  let mut records = Vec::new();
+  for (i, line) in content.lines().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    match serde_json::from_str::<serde_json::Value>(line).map(|v| serde_json::to_string_pretty(&v)) {
+      Ok(Ok(pretty)) => records.push(pretty),
+      Ok(Err(e)) | Err(e) => return FormatResult::Error(format!("Invalid JSON Lines record at line {}: {e}", i + 1)),
+    }
+  }
+  formatted(records.join("\n\n"), "json")
+}
+
+/// Pretty-prints TOML content.
+pub fn format_toml(content: &str) -> FormatResult {
+  match content.parse::<toml::Table>() {
+    Ok(table) => match toml::to_string_pretty(&table) {
+      Ok(pretty) => formatted(pretty, "toml"),
+      Err(e) => FormatResult::Error(format!("Invalid TOML: {e}")),
+    },
+    Err(e) => {
+      let message = e.message().trim_end().to_string();
+      match e.span() {
+        Some(span) => FormatResult::Error(format!("Invalid TOML: {message} at line {}", line_at(content, span.start))),
+        None => FormatResult::Error(format!("Invalid TOML: {message}")),
+      }
+    }
+  }
+}
+
+/// Validates YAML content and shows it as written. Re-emitting it would drop
+/// comments, expand anchors and rewrite block scalars.
+pub fn format_yaml(content: &str) -> FormatResult {
+  match YamlLoader::load_from_str(content) {
+    Ok(_) => formatted(content.to_string(), "yaml"),
+    Err(e) => FormatResult::Error(format!("Invalid YAML: {e}")),
+  }
+}
+
+/// Re-indents XML content with 2 spaces per level, checking that every
+/// element is closed by a matching end tag.
+pub fn format_xml(content: &str) -> FormatResult {
+  let mut reader = Reader::from_str(content);
+  reader.config_mut().trim_text(true);
+  let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+  let mut open: Vec<String> = Vec::new();
+  loop {
+    let event = match reader.read_event() {
+      Ok(Event::Eof) => break,
+      Ok(event) => event,
+      Err(e) => {
+        let line = line_at(content, reader.error_position() as usize);
+        return FormatResult::Error(format!("Invalid XML: {e} at line {line}"));
+      }
+    };
+    match &event {
+      Event::Start(start) => open.push(String::from_utf8_lossy(start.name().as_ref()).into_owned()),
+      Event::End(_) => {
+        open.pop();
+      }
+      _ => {}
+    }
+    if writer.write_event(event).is_err() {
+      return FormatResult::Error("Invalid XML".to_string());
+    }
+  }
+  if let Some(name) = open.last() {
+    return FormatResult::Error(format!("Invalid XML: <{name}> is not closed at line {}", line_at(content, content.len())));
+  }
+  match String::from_utf8(writer.into_inner()) {
+    Ok(pretty) => formatted(pretty, "xml"),
+    Err(_) => FormatResult::Error("Invalid XML: not UTF-8".to_string()),
+  }
+}
+
+/// Formats content based on file extension.
+/// Returns None if the extension is not a structured data format.
+pub fn format_structured(content: &str, extension: &str) -> Option<FormatResult> {
+  match extension.to_lowercase().as_str() {
+    "json" => Some(format_json(content)),
+    "jsonl" | "ndjson" => Some(format_json_lines(content)),
+    "toml" => Some(format_toml(content)),
+    "yaml" | "yml" => Some(format_yaml(content)),
+    "xml" => Some(format_xml(content)),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_structured_json() {
+    assert!(is_structured_data("json"));
+    assert!(is_structured_data("JSON"));
+    assert!(is_structured_data("Json"));
+  }
+
+  #[test]
+  fn test_is_structured_toml() {
+    assert!(is_structured_data("toml"));
+    assert!(is_structured_data("TOML"));
+    assert!(is_structured_data("Toml"));
+  }
+
+  #[test]
+  fn test_is_structured_other() {
+    assert!(!is_structured_data("txt"));
+    assert!(!is_structured_data("rs"));
+    assert!(!is_structured_data("md"));
+    assert!(!is_structured_data(""));
+  }
+
+  #[test]
+  fn test_format_json_valid() {
+    let input = r#"{"name":"test","count":42}"#;
+    let result = format_json(input);
+    match result {
+      FormatResult::Formatted { content, extension } => {
+        assert_eq!(extension, "json");
+        // Should be pretty-printed with newlines
+        assert!(content.contains('\n'));
+        // Should preserve values
+        assert!(content.contains("\"name\""));
+        assert!(content.contains("\"test\""));
+        assert!(content.contains("42"));
+      }
+      FormatResult::Error(e) => panic!("Expected Formatted, got Error: {e}"),
+    }
+  }
+
+  #[test]
+  fn test_format_json_nested() {
+    let input = r#"{"outer":{"inner":"value"},"array":[1,2,3]}"#;
+    let result = format_json(input);
+    match result {
+      FormatResult::Formatted { content, .. } => {
+        // Should have proper indentation
+        assert!(content.contains("  "));
+        assert!(content.contains("\"outer\""));
+        assert!(content.contains("\"inner\""));
+      }
+      FormatResult::Error(e) => panic!("Expected Formatted, got Error: {e}"),
+    }
+  }
+
+  #[test]
+  fn test_format_json_invalid() {
+    let input = r#"{"name": incomplete"#;
+    let result = format_json(input);
+    assert!(matches!(result, FormatResult::Error(_)));
+  }
+
+  #[test]
+  fn test_format_json_empty_object() {
+    let input = "{}";
+    let result = format_json(input);
+    assert!(matches!(result, FormatResult::Formatted { .. }));
+  }
+
+  #[test]
+  fn test_format_json_array() {
+    let input = r#"[1, 2, 3]"#;
+    let result = format_json(input);
+    match result {
+      FormatResult::Formatted { content, .. } => {
+        assert!(content.contains("1"));
+        assert!(content.contains("2"));
+        assert!(content.contains("3"));
+      }
+      FormatResult::Error(e) => panic!("Expected Formatted, got Error: {e}"),
+    }
+  }
+
+  #[test]
+  fn test_format_toml_valid() {
+    let input = r#"name = "test"
+count = 42"#;
+    let result = format_toml(input);
+    match result {
+      FormatResult::Formatted { content, extension } => {
+        assert_eq!(extension, "toml");
+        assert!(content.contains("name"));
+        assert!(content.contains("test"));
+        assert!(content.contains("42"));
+      }
+      FormatResult::Error(e) => panic!("Expected Formatted, got Error: {e}"),
+    }
+  }
+
+  #[test]
+  fn test_format_toml_with_sections() {
+    let input = r#"[package]
+name = "my-app"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0""#;
+    let result = format_toml(input);
+    match result {
+      FormatResult::Formatted { content, .. } => {
+        assert!(content.contains("[package]"));
+        assert!(content.contains("[dependencies]"));
+      }
+      FormatResult::Error(e) => panic!("Expected Formatted, got Error: {e}"),
+    }
+  }
+
+  #[test]
+  fn test_format_toml_invalid() {
+    let input = r#"[package
+name = incomplete"#;
+    let result = format_toml(input);
+    assert!(matches!(result, FormatResult::Error(_)));
+  }
+
+  #[test]
+  fn test_format_structured_json() {
+    let result = format_structured(r#"{"a":1}"#, "json");
+    assert!(result.is_some());
+    assert!(matches!(result.unwrap(), FormatResult::Formatted { .. }));
+  }
+
+  #[test]
+  fn test_format_structured_toml() {
+    let result = format_structured("key = \"value\"", "toml");
+    assert!(result.is_some());
+    assert!(matches!(result.unwrap(), FormatResult::Formatted { .. }));
+  }
+
+  #[test]
+  fn test_format_structured_unknown() {
+    let result = format_structured("some content", "txt");
+    assert!(result.is_none());
+  }
+
+  fn error(result: FormatResult) -> String {
+    match result {
+      FormatResult::Formatted { content, .. } => panic!("Expected Error, got {content}"),
+      FormatResult::Error(e) => e,
+    }
+  }
+
+  #[test]
+  fn test_format_yaml() {
+    let yaml = "# settings\nbase: &base {a: 1}\nname:   tfl\nmore: *base\nnotes: |\n  kept \"as is\"\n---\nsecond: 2\n";
+    match format_yaml(yaml) {
+      FormatResult::Formatted { content, extension } => {
+        assert_eq!(extension, "yaml");
+        assert_eq!(content, yaml);
+      }
+      FormatResult::Error(e) => panic!("Expected Formatted, got Error: {e}"),
+    }
+    let message = error(format_yaml("a: 1\nb: [1, 2\nc: 3\n"));
+    assert!(message.starts_with("Invalid YAML"), "{message}");
+    assert!(message.contains("line 3"), "{message}");
+  }
+
+  #[test]
+  fn test_format_xml() {
+    match format_xml("<root><item id=\"1\">one</item><empty/></root>") {
+      FormatResult::Formatted { content, extension } => {
+        assert_eq!(extension, "xml");
+        assert_eq!(content, "<root>\n  <item id=\"1\">one</item>\n  <empty/>\n</root>");
+      }
+      FormatResult::Error(e) => panic!("Expected Formatted, got Error: {e}"),
+    }
+    let message = error(format_xml("<root>\n  <a>\n  </b>\n</root>"));
+    assert!(message.contains("line 3"), "{message}");
+    let message = error(format_xml("<root>\n  <a></a>\n"));
+    assert_eq!(message, "Invalid XML: <root> is not closed at line 3");
+  }
+
+  #[test]
+  fn test_format_json_lines() {
+    match format_structured("{\"a\":1}\n\n[1,2]\n", "jsonl").unwrap() {
+      FormatResult::Formatted { content, extension } => {
+        assert_eq!(extension, "json");
+        assert_eq!(content, "{\n  \"a\": 1\n}\n\n[\n  1,\n  2\n]");
+      }
+      FormatResult::Error(e) => panic!("Expected Formatted, got Error: {e}"),
+    }
+    let message = error(format_json_lines("{\"a\":1}\n{\"b\":\n{}"));
+    assert!(message.starts_with("Invalid JSON Lines record at line 2:"), "{message}");
+    assert!(is_structured_data("NDJSON") && is_structured_data("yml"));
+  }
+
+  #[test]
+  fn test_parse_errors_name_the_line() {
+    assert!(error(format_json("{\n  \"a\": 1,\n  oops\n}")).contains("line 3"));
+    assert!(error(format_toml("a = 1\nb = 2\nc = \n")).contains("line 3"));
+  }
+}
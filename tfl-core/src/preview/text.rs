@@ -1,7 +1,10 @@
+//! Syntax highlighting for text previews.
+
 use std::io::Cursor;
+use std::sync::OnceLock;
 
-use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Span};
+use ratatui_core::style::{Color, Modifier, Style};
+use ratatui_core::text::{Line, Span};
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{FontStyle, ThemeSet};
 use syntect::parsing::{SyntaxDefinition, SyntaxSet};
@@ -9,14 +12,35 @@ use syntect::util::LinesWithEndings;
 
 const CATPPUCCIN_MOCHA_THEME: &[u8] = include_bytes!("themes/catppuccin-mocha.tmTheme");
 
-pub struct SyntaxHighlighter {
+struct Syntaxes {
   syntax_set: SyntaxSet,
   theme_set: ThemeSet,
+}
+
+/// Syntax definitions and themes are parsed on the first `highlight` call
+/// rather than at construction, keeping them off the startup path.
+pub struct SyntaxHighlighter {
+  syntaxes: OnceLock<Syntaxes>,
   theme_name: String,
+  /// Whether highlighted files get a line-number gutter
+  line_numbers: bool,
 }
 
 impl SyntaxHighlighter {
+  /// A highlighter using `syntax_theme`, with line numbers on.
   pub fn new(syntax_theme: &str) -> Self {
+    Self {
+      syntaxes: OnceLock::new(),
+      theme_name: syntax_theme.to_string(),
+      line_numbers: true,
+    }
+  }
+
+  fn syntaxes(&self) -> &Syntaxes {
+    self.syntaxes.get_or_init(Self::load)
+  }
+
+  fn load() -> Syntaxes {
     let mut builder = two_face::syntax::extra_newlines().into_builder();
     let toml_syntax = SyntaxDefinition::load_from_str(
       include_str!("syntaxes/TOML.sublime-syntax"),
@@ -33,37 +57,74 @@ impl SyntaxHighlighter {
       theme_set.themes.insert("Catppuccin Mocha".to_string(), theme);
     }
 
-    Self {
+    Syntaxes {
       syntax_set: builder.build(),
       theme_set,
-      theme_name: syntax_theme.to_string(),
     }
   }
 
+  /// Highlight with the syntect theme `name` from now on.
   pub fn set_theme_name(&mut self, name: &str) {
     self.theme_name = name.to_string();
   }
 
+  /// Name of the syntect theme in use.
+  pub fn theme_name(&self) -> &str {
+    &self.theme_name
+  }
+
+  /// Show or hide the line-number gutter.
+  pub fn set_line_numbers(&mut self, on: bool) {
+    self.line_numbers = on;
+  }
+
+  /// Whether highlighted files get a line-number gutter.
+  pub fn line_numbers(&self) -> bool {
+    self.line_numbers
+  }
+
+  /// Name of the syntax used for files with `extension`, unless plain text.
+  pub fn language(&self, extension: &str) -> Option<String> {
+    let syntax = self.syntaxes().syntax_set.find_syntax_by_extension(extension)?;
+    (syntax.name != "Plain Text").then(|| syntax.name.clone())
+  }
+
+  /// Highlight `content` as the language of `extension`, numbering from 1.
   pub fn highlight<'a>(&self, content: &str, extension: &str) -> Vec<Line<'a>> {
+    self.highlight_from(content, extension, 1)
+  }
+
+  /// Like `highlight`, numbering the lines from `first_line`.
+  pub fn highlight_from<'a>(&self, content: &str, extension: &str, first_line: usize) -> Vec<Line<'a>> {
+    self.highlight_lines(content, extension, self.line_numbers.then_some(first_line))
+  }
+
+  /// Highlights without a line-number gutter, for code set inside other
+  /// content such as a markdown code block.
+  pub fn highlight_code<'a>(&self, content: &str, extension: &str) -> Vec<Line<'a>> {
+    self.highlight_lines(content, extension, None)
+  }
+
+  fn highlight_lines<'a>(&self, content: &str, extension: &str, first_line: Option<usize>) -> Vec<Line<'a>> {
+    let Syntaxes { syntax_set, theme_set } = self.syntaxes();
     let syntax = parse_vim_modeline(content)
-      .and_then(|ft| self.syntax_set.find_syntax_by_token(&ft))
-      .or_else(|| self.syntax_set.find_syntax_by_extension(extension))
-      .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+      .and_then(|ft| syntax_set.find_syntax_by_token(&ft))
+      .or_else(|| syntax_set.find_syntax_by_extension(extension))
+      .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
 
-    let theme = self.theme_set.themes.get(&self.theme_name)
-      .or_else(|| self.theme_set.themes.get("base16-ocean.dark"))
+    let theme = theme_set.themes.get(&self.theme_name)
+      .or_else(|| theme_set.themes.get("base16-ocean.dark"))
       .expect("fallback theme must exist");
     let mut highlighter = HighlightLines::new(syntax, theme);
     let mut lines = Vec::new();
 
     for (line_num, line) in LinesWithEndings::from(content).enumerate() {
-      let line_number = format!("{:>4} ", line_num + 1);
-      let mut spans = vec![Span::styled(
-        line_number,
-        Style::default().fg(Color::DarkGray),
-      )];
+      let mut spans = Vec::new();
+      if let Some(first_line) = first_line {
+        spans.push(Span::styled(format!("{:>4} ", line_num + first_line), Style::default().fg(Color::DarkGray)));
+      }
 
-      match highlighter.highlight_line(line, &self.syntax_set) {
+      match highlighter.highlight_line(line, syntax_set) {
         Ok(ranges) => {
           for (style, text) in ranges {
             let fg = Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b);
@@ -133,7 +194,24 @@ mod tests {
   #[test]
   fn test_highlighter_creates() {
     let h = SyntaxHighlighter::new("base16-ocean.dark");
-    assert!(!h.syntax_set.syntaxes().is_empty());
+    assert!(!h.syntaxes().syntax_set.syntaxes().is_empty());
+  }
+
+  #[test]
+  fn test_language_by_extension() {
+    let h = SyntaxHighlighter::new("base16-ocean.dark");
+    assert_eq!(h.language("rs").as_deref(), Some("Rust"));
+    assert_eq!(h.language("toml").as_deref(), Some("TOML"));
+    assert_eq!(h.language("txt"), None);
+    assert_eq!(h.language("no-such-ext"), None);
+  }
+
+  #[test]
+  fn test_highlighter_loads_lazily() {
+    let h = SyntaxHighlighter::new("base16-ocean.dark");
+    assert!(h.syntaxes.get().is_none());
+    h.highlight("fn main() {}", "rs");
+    assert!(h.syntaxes.get().is_some());
   }
 
   #[test]
@@ -175,6 +253,20 @@ mod tests {
     assert!(lines[0].spans[0].content.trim().starts_with('1'));
     // Line 15 should have "  15 " prefix
     assert!(lines[14].spans[0].content.trim().starts_with("15"));
+    // Numbering can start further down, for a file's tail
+    let lines = h.highlight_from("a\nb\n", "txt", 120);
+    assert_eq!(lines[1].spans[0].content, " 121 ");
+  }
+
+  #[test]
+  fn test_line_number_gutter_can_be_turned_off() {
+    let mut h = SyntaxHighlighter::new("base16-ocean.dark");
+    h.set_line_numbers(false);
+    let lines = h.highlight("first\nsecond\n", "txt");
+    assert_eq!(lines[1].to_string(), "second");
+    h.set_line_numbers(true);
+    assert_eq!(h.highlight("first\n", "txt")[0].to_string(), "   1 first");
+    assert_eq!(h.highlight_code("first\n", "txt")[0].to_string(), "first");
   }
 
   #[test]
@@ -240,7 +332,7 @@ mod tests {
   #[test]
   fn test_catppuccin_mocha_theme_loaded() {
     let h = SyntaxHighlighter::new("Catppuccin Mocha");
-    assert!(h.theme_set.themes.contains_key("Catppuccin Mocha"));
+    assert!(h.syntaxes().theme_set.themes.contains_key("Catppuccin Mocha"));
     // Verify it can highlight with the theme
     let lines = h.highlight("fn main() {}\n", "rs");
     assert_eq!(lines.len(), 1);
@@ -0,0 +1,206 @@
+//! External program launches behind a trait, so the code deciding what to
+//! run (open-with, suspends, the file manager handler) can be tested
+//! without running anything.
+
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// One program invocation: what to run, with which arguments, where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessSpec {
+  /// Program name, looked up in `PATH`
+  pub program: String,
+  /// Kept as they are, so paths that aren't UTF-8 reach the program intact
+  pub args: Vec<OsString>,
+  /// Working directory, else the caller's
+  pub cwd: Option<PathBuf>,
+}
+
+impl ProcessSpec {
+  /// Run `program` with no arguments.
+  pub fn new(program: impl Into<String>) -> Self {
+    Self { program: program.into(), args: Vec::new(), cwd: None }
+  }
+
+  /// Add one argument.
+  pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+    self.args.push(arg.as_ref().to_os_string());
+    self
+  }
+
+  /// Add several arguments.
+  pub fn args<I, S>(mut self, args: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+  {
+    self.args.extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+    self
+  }
+
+  /// Run in `dir`.
+  pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.cwd = Some(dir.into());
+    self
+  }
+
+  fn command(&self) -> Command {
+    let mut command = Command::new(&self.program);
+    command.args(&self.args);
+    if let Some(ref cwd) = self.cwd {
+      command.current_dir(cwd);
+    }
+    command
+  }
+}
+
+impl std::fmt::Display for ProcessSpec {
+  /// `program arg arg`, for logs and test assertions
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.program)?;
+    for arg in &self.args {
+      write!(f, " {}", arg.to_string_lossy())?;
+    }
+    Ok(())
+  }
+}
+
+/// Runs external programs, or records them in tests.
+pub trait ProcessRunner: Send + Sync {
+  /// Run in the foreground with the terminal attached and wait for it.
+  /// Returns whether it exited successfully.
+  fn run(&self, spec: &ProcessSpec) -> io::Result<bool>;
+
+  /// Run and capture stdout. Returns the exit success and the output.
+  fn output(&self, spec: &ProcessSpec) -> io::Result<(bool, String)>;
+
+  /// Start in the background without waiting.
+  fn spawn(&self, spec: &ProcessSpec) -> io::Result<()>;
+
+  /// Whether `program` can be found on `PATH`.
+  fn exists(&self, program: &str) -> bool;
+}
+
+/// Runs programs for real.
+pub struct SystemRunner;
+
+impl ProcessRunner for SystemRunner {
+  fn run(&self, spec: &ProcessSpec) -> io::Result<bool> {
+    Ok(spec.command().status()?.success())
+  }
+
+  fn output(&self, spec: &ProcessSpec) -> io::Result<(bool, String)> {
+    let output = spec.command().output()?;
+    Ok((output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned()))
+  }
+
+  fn spawn(&self, spec: &ProcessSpec) -> io::Result<()> {
+    spec.command().spawn().map(|_| ())
+  }
+
+  fn exists(&self, program: &str) -> bool {
+    Command::new("which")
+      .arg(program)
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .status()
+      .is_ok_and(|s| s.success())
+  }
+}
+
+/// Records launches instead of running them. Available to other crates
+/// through the `test-util` feature.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Default)]
+pub struct MockRunner {
+  /// Programs `exists` reports as installed
+  pub installed: Vec<String>,
+  /// Stdout returned by `output`, keyed by program
+  pub outputs: std::collections::HashMap<String, String>,
+  /// Programs whose runs exit unsuccessfully
+  pub failing: Vec<String>,
+  /// Every launch so far
+  pub calls: std::sync::Mutex<Vec<ProcessSpec>>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockRunner {
+  /// A runner reporting `programs` as installed.
+  pub fn with_installed(programs: &[&str]) -> Self {
+    Self { installed: programs.iter().map(|p| p.to_string()).collect(), ..Self::default() }
+  }
+
+  /// Every launch so far, as `program arg arg`
+  pub fn commands(&self) -> Vec<String> {
+    self.calls.lock().unwrap().iter().map(ToString::to_string).collect()
+  }
+
+  fn record(&self, spec: &ProcessSpec) -> bool {
+    self.calls.lock().unwrap().push(spec.clone());
+    !self.failing.contains(&spec.program)
+  }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl ProcessRunner for MockRunner {
+  fn run(&self, spec: &ProcessSpec) -> io::Result<bool> {
+    Ok(self.record(spec))
+  }
+
+  fn output(&self, spec: &ProcessSpec) -> io::Result<(bool, String)> {
+    let success = self.record(spec);
+    Ok((success, self.outputs.get(&spec.program).cloned().unwrap_or_default()))
+  }
+
+  fn spawn(&self, spec: &ProcessSpec) -> io::Result<()> {
+    if self.record(spec) {
+      Ok(())
+    } else {
+      Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", spec.program)))
+    }
+  }
+
+  fn exists(&self, program: &str) -> bool {
+    self.installed.iter().any(|p| p == program)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_system_runner() {
+    let runner = SystemRunner;
+    assert!(runner.exists("sh"));
+    assert!(!runner.exists("nonexistent_binary_xyz_99999"));
+    let (ok, out) = runner.output(&ProcessSpec::new("sh").args(["-c", "pwd"]).current_dir("/")).unwrap();
+    assert!(ok);
+    assert_eq!(out.trim(), "/");
+    assert!(!runner.run(&ProcessSpec::new("sh").args(["-c", "exit 3"])).unwrap());
+  }
+
+  #[test]
+  fn test_args_that_are_not_utf8_pass_through() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let name = OsStr::from_bytes(b"caf\xe9.txt");
+    let output = ProcessSpec::new("printf").arg("%s").arg(name).command().output().unwrap();
+    assert_eq!(output.stdout, b"caf\xe9.txt");
+  }
+
+  #[test]
+  fn test_mock_runner_records() {
+    let mut runner = MockRunner::with_installed(&["code"]);
+    runner.outputs.insert("xdg-mime".into(), "nautilus.desktop\n".into());
+    runner.failing.push("false".into());
+    assert!(runner.exists("code"));
+    assert!(!runner.exists("vim"));
+    assert_eq!(runner.output(&ProcessSpec::new("xdg-mime").arg("query")).unwrap().1, "nautilus.desktop\n");
+    assert!(!runner.run(&ProcessSpec::new("false")).unwrap());
+    assert!(runner.spawn(&ProcessSpec::new("false")).is_err());
+    assert_eq!(runner.commands(), vec!["xdg-mime query", "false", "false"]);
+  }
+}
@@ -1,30 +1,54 @@
-use ratatui::style::Color;
+//! Color themes shared by the tree, previews and overlays.
 
+use ratatui_core::style::Color;
+
+/// Colours for every part of the UI.
 #[derive(Debug, Clone)]
 pub struct Theme {
+  /// Keys, focused borders and highlights
   pub accent: Color,
+  /// Body text
   pub text: Color,
+  /// Secondary text
   pub text_dim: Color,
+  /// Hints and least important text
   pub text_muted: Color,
+  /// Unfocused borders and separators
   pub border: Color,
+  /// Section titles and unfocused pane titles
   pub title_inactive: Color,
+  /// Selected row
   pub bg_selected: Color,
+  /// Popups and overlays
   pub bg_overlay: Color,
+  /// Status and hint bars
   pub bg_bar: Color,
+  /// Inline code in Markdown
   pub bg_inline_code: Color,
+  /// Done messages
   pub success: Color,
+  /// Warnings and pending state
   pub warning: Color,
+  /// Errors
   pub error: Color,
+  /// Marked entries
   pub marked: Color,
+  /// Informational text such as commit authors
   pub info: Color,
+  /// Staged changes
   pub git_staged: Color,
+  /// Modified files
   pub git_modified: Color,
+  /// Untracked files
   pub git_untracked: Color,
+  /// Merge conflicts
   pub git_conflicted: Color,
+  /// Dates and other metadata
   pub meta_secondary: Color,
 }
 
 impl Theme {
+  /// The default theme for dark terminals.
   pub fn dark() -> Self {
     Self {
       accent: Color::Indexed(75),
@@ -50,6 +74,7 @@ impl Theme {
     }
   }
 
+  /// A theme for light terminals.
   pub fn light() -> Self {
     Self {
       accent: Color::Indexed(27),
@@ -75,6 +100,7 @@ impl Theme {
     }
   }
 
+  /// Catppuccin Mocha.
   pub fn catppuccin_mocha() -> Self {
     Self {
       accent: Color::Rgb(137, 180, 250),       // Blue
@@ -100,6 +126,7 @@ impl Theme {
     }
   }
 
+  /// A theme by its config name, e.g. `dark`.
   pub fn from_name(name: &str) -> Option<Self> {
     match name {
       "dark" => Some(Self::dark()),
@@ -109,6 +136,7 @@ impl Theme {
     }
   }
 
+  /// Config names of the built-in themes.
   pub fn available_themes() -> &'static [&'static str] {
     &["dark", "light", "catppuccin-mocha"]
   }